@@ -0,0 +1,261 @@
+//! Read-only GraphQL schema over workflows, executions, and node
+//! executions, served at `POST /api/v1/graphql` while the `graphql`
+//! feature flag is on.
+//!
+//! Objects nest the way dashboards read them — a workflow's recent
+//! executions, and each execution's (e.g. failed) nodes — so one query
+//! replaces a chain of REST calls:
+//!
+//! ```graphql
+//! { workflows(active: true) { name executions(limit: 5) { status nodeExecutions(status: "failed") { nodeId error } } } }
+//! ```
+//!
+//! Every query is confined to the request's project, like the REST
+//! listings.
+
+use std::sync::OnceLock;
+
+use async_graphql::{ComplexObject, Context, EmptyMutation, EmptySubscription, Json, Object, SimpleObject};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+
+use db::models::{
+    ExecutionFilter, ExecutionSummaryRow, NodeExecutionRow, ProjectRow, WorkflowExecutionRow, WorkflowFilter,
+    WorkflowRow,
+};
+use db::repository::{executions as exec_repo, workflows as wf_repo};
+use db::{DbError, DbPool};
+
+pub type Schema = async_graphql::Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// Most rows a single list field returns.
+const MAX_LIMIT: i32 = 100;
+
+/// Deepest nesting a query may use.
+const MAX_DEPTH: usize = 8;
+
+/// The schema, built on first use.  Each request brings its own pool and
+/// project as data.
+pub fn schema() -> &'static Schema {
+    static SCHEMA: OnceLock<Schema> = OnceLock::new();
+    SCHEMA.get_or_init(|| Schema::build(Query, EmptyMutation, EmptySubscription).limit_depth(MAX_DEPTH).finish())
+}
+
+/// Run `request` against `project`'s rows.
+pub async fn execute(pool: DbPool, project: ProjectRow, request: async_graphql::Request) -> async_graphql::Response {
+    schema().execute(request.data(pool).data(project)).await
+}
+
+fn clamp(limit: i32) -> i64 {
+    limit.clamp(0, MAX_LIMIT).into()
+}
+
+/// Log `e`; clients only learn that the field failed.
+fn internal(e: DbError) -> async_graphql::Error {
+    tracing::error!("GraphQL query failed: {e}");
+    async_graphql::Error::new("internal error")
+}
+
+fn pool<'a>(ctx: &Context<'a>) -> &'a DbPool {
+    ctx.data_unchecked::<DbPool>()
+}
+
+fn project(ctx: &Context<'_>) -> Uuid {
+    ctx.data_unchecked::<ProjectRow>().id
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// Workflows of the project, newest first; with `search`, best match
+    /// first.
+    #[allow(clippy::too_many_arguments)]
+    async fn workflows(
+        &self,
+        ctx: &Context<'_>,
+        name: Option<String>,
+        active: Option<bool>,
+        tag: Option<String>,
+        search: Option<String>,
+        #[graphql(default = 20)] limit: i32,
+        #[graphql(default = 0)] offset: i32,
+    ) -> async_graphql::Result<Vec<Workflow>> {
+        let filter =
+            WorkflowFilter { name, active, tag, search, project_id: Some(project(ctx)), ..Default::default() };
+        let rows = wf_repo::list_workflows_page(pool(ctx), &filter, clamp(limit), offset.max(0).into())
+            .await
+            .map_err(internal)?;
+        Ok(rows.into_iter().map(Workflow::from).collect())
+    }
+
+    async fn workflow(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<Option<Workflow>> {
+        match wf_repo::get_workflow(pool(ctx), id).await {
+            Ok(row) if row.project_id == project(ctx) => Ok(Some(row.into())),
+            Ok(_) | Err(DbError::NotFound) => Ok(None),
+            Err(e) => Err(internal(e)),
+        }
+    }
+
+    /// Executions of the project, newest first.
+    async fn executions(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<String>,
+        started_after: Option<DateTime<Utc>>,
+        #[graphql(default = 20)] limit: i32,
+        #[graphql(default = 0)] offset: i32,
+    ) -> async_graphql::Result<Vec<Execution>> {
+        let filter = ExecutionFilter { status, started_after, project_id: Some(project(ctx)), ..Default::default() };
+        let rows = exec_repo::list_executions(pool(ctx), &filter, clamp(limit), offset.max(0).into())
+            .await
+            .map_err(internal)?;
+        Ok(rows.into_iter().map(Execution::from).collect())
+    }
+
+    async fn execution(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<Option<Execution>> {
+        let row = match exec_repo::get_execution(pool(ctx), id).await {
+            Ok(row) => row,
+            Err(DbError::NotFound) => return Ok(None),
+            Err(e) => return Err(internal(e)),
+        };
+        match exec_repo::execution_project(pool(ctx), id).await {
+            Ok(project_id) if project_id == project(ctx) => Ok(Some(row.into())),
+            Ok(_) | Err(DbError::NotFound) => Ok(None),
+            Err(e) => Err(internal(e)),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct Workflow {
+    id: Uuid,
+    name: String,
+    active: bool,
+    tags: Vec<String>,
+    version: i32,
+    created_at: DateTime<Utc>,
+    /// The full definition: nodes, edges, trigger, ….
+    definition: Json<Value>,
+}
+
+impl From<WorkflowRow> for Workflow {
+    fn from(row: WorkflowRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            active: row.active,
+            tags: row.tags,
+            version: row.version,
+            created_at: row.created_at,
+            definition: Json(row.definition),
+        }
+    }
+}
+
+#[ComplexObject]
+impl Workflow {
+    /// The workflow's executions, newest first.
+    async fn executions(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<String>,
+        #[graphql(default = 10)] limit: i32,
+    ) -> async_graphql::Result<Vec<Execution>> {
+        let filter = ExecutionFilter { workflow_id: Some(self.id), status, ..Default::default() };
+        let rows = exec_repo::list_executions(pool(ctx), &filter, clamp(limit), 0).await.map_err(internal)?;
+        Ok(rows.into_iter().map(Execution::from).collect())
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct Execution {
+    id: Uuid,
+    workflow_id: Uuid,
+    status: String,
+    business_key: Option<String>,
+    started_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+impl From<ExecutionSummaryRow> for Execution {
+    fn from(row: ExecutionSummaryRow) -> Self {
+        Self {
+            id: row.id,
+            workflow_id: row.workflow_id,
+            status: row.status,
+            business_key: row.business_key,
+            started_at: row.started_at,
+            finished_at: row.finished_at,
+        }
+    }
+}
+
+impl From<WorkflowExecutionRow> for Execution {
+    fn from(row: WorkflowExecutionRow) -> Self {
+        Self {
+            id: row.id,
+            workflow_id: row.workflow_id,
+            status: row.status,
+            business_key: row.business_key,
+            started_at: row.started_at,
+            finished_at: row.finished_at,
+        }
+    }
+}
+
+#[ComplexObject]
+impl Execution {
+    async fn workflow(&self, ctx: &Context<'_>) -> async_graphql::Result<Workflow> {
+        Ok(wf_repo::get_workflow(pool(ctx), self.workflow_id).await.map_err(internal)?.into())
+    }
+
+    /// The execution's nodes in the order they ran, optionally only those
+    /// with `status` (e.g. `failed`).
+    async fn node_executions(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<String>,
+    ) -> async_graphql::Result<Vec<NodeExecution>> {
+        let rows = exec_repo::list_node_executions(pool(ctx), self.id).await.map_err(internal)?;
+        Ok(rows
+            .into_iter()
+            .filter(|row| status.as_ref().is_none_or(|status| &row.status == status))
+            .map(NodeExecution::from)
+            .collect())
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct NodeExecution {
+    id: Uuid,
+    node_id: String,
+    status: String,
+    input: Json<Value>,
+    output: Option<Json<Value>>,
+    /// Why the node failed, for `failed` nodes.
+    error: Option<String>,
+    /// Branch the node picked, for branching nodes.
+    branch: Option<String>,
+    started_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+impl From<NodeExecutionRow> for NodeExecution {
+    fn from(row: NodeExecutionRow) -> Self {
+        Self {
+            id: row.id,
+            node_id: row.node_id,
+            status: row.status,
+            input: Json(row.input),
+            output: row.output.map(Json),
+            error: row.error,
+            branch: row.branch,
+            started_at: row.started_at,
+            finished_at: row.finished_at,
+        }
+    }
+}
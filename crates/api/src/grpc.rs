@@ -0,0 +1,307 @@
+//! gRPC management API (`proto/management.proto`): create, read, and run
+//! workflows, and stream execution events, for clients that prefer
+//! protobuf contracts to REST.
+//!
+//! The service shares the API's port — requests to [`PATH`] are routed to
+//! it — and its rules: callers authenticate like REST callers (see
+//! [`auth`](crate::auth)), need the role the matching REST endpoint needs
+//! (see [`rbac`](crate::rbac)), and act in the project named by
+//! `x-project` metadata (see [`project`](crate::project)).  Execution
+//! events come from the same relay as `/api/v1/ws` (see
+//! [`live`](crate::live)).
+
+// Every call returns `tonic::Status`, which is large; the generated code
+// and the service trait leave no choice.
+#![allow(clippy::result_large_err)]
+
+use std::fmt::Display;
+use std::pin::Pin;
+
+use futures_util::Stream;
+use serde_json::Value;
+use tokio::sync::broadcast::error::RecvError;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use db::changes::Change;
+use db::models::{ProjectRow, WorkflowExecutionRow, WorkflowFilter, WorkflowRow};
+use db::repository::{executions as exec_repo, jobs as job_repo, workflows as wf_repo};
+use engine::{quotas, triggers, Workflow as Definition};
+
+use crate::auth::AuthError;
+use crate::handlers::workflows::{check_definition, normalize_tags};
+use crate::live::Subscription;
+use crate::project::{self, PROJECT_HEADER};
+use crate::rbac::{self, Role};
+use crate::AppState;
+
+pub mod proto {
+    tonic::include_proto!("rusty_automation.v1");
+}
+
+use proto::management_server::{Management, ManagementServer};
+use proto::{
+    CreateWorkflowRequest, ExecuteWorkflowRequest, ExecuteWorkflowResponse, Execution, ExecutionEvent,
+    GetExecutionRequest, GetWorkflowRequest, ListWorkflowsRequest, ListWorkflowsResponse,
+    StreamExecutionEventsRequest, Workflow,
+};
+
+/// Paths the service answers on.
+pub const PATH: &str = "/rusty_automation.v1.Management/*method";
+
+/// Most workflows one `ListWorkflows` call returns.
+const MAX_PAGE: u32 = 100;
+
+/// The service, bound to `state`.
+pub fn service(state: AppState) -> ManagementServer<ManagementService> {
+    ManagementServer::new(ManagementService { state })
+}
+
+pub struct ManagementService {
+    state: AppState,
+}
+
+/// Who is calling, and in which project.
+struct Caller {
+    subject: Option<String>,
+    project: ProjectRow,
+}
+
+impl ManagementService {
+    /// Authenticate `request`, check that the caller has `required`, and
+    /// resolve the project it acts in.
+    async fn caller<T>(&self, request: &Request<T>, required: Role) -> Result<Caller, Status> {
+        let headers = request.metadata().clone().into_headers();
+        let identity = match self.state.auth.authenticate(&headers).await {
+            Ok(identity) => identity,
+            Err(e @ AuthError::Jwks(_)) => {
+                tracing::error!("Authentication unavailable: {e}");
+                return Err(Status::unavailable(e.to_string()));
+            }
+            Err(e) => return Err(Status::unauthenticated(e.to_string())),
+        };
+        let role = match &identity {
+            None => Some(Role::Admin),
+            Some(identity) => rbac::role_of(&self.state, identity).await.map_err(internal)?,
+        };
+        match role {
+            Some(role) if role >= required => {}
+            Some(_) => return Err(Status::permission_denied(format!("this needs the {required} role"))),
+            None => return Err(Status::permission_denied("no role is assigned to you")),
+        }
+
+        let confined = identity.as_ref().and_then(|identity| identity.project.as_deref());
+        let requested = headers.get(PROJECT_HEADER).and_then(|v| v.to_str().ok()).map(str::trim);
+        let project = match project::resolve(&self.state, confined, requested, false).await {
+            Ok(project) => project,
+            Err((status, message)) => return Err(status_from_http(status, message)),
+        };
+        Ok(Caller { subject: identity.map(|identity| identity.subject), project })
+    }
+
+    /// Workflow `id`, if it is in `project`.
+    async fn workflow(&self, project: &ProjectRow, id: &str) -> Result<WorkflowRow, Status> {
+        let id = parse_id("workflow_id", id)?;
+        match wf_repo::get_workflow(&self.state.pool, id).await {
+            Ok(row) if row.project_id == project.id => Ok(row),
+            Ok(_) | Err(db::DbError::NotFound) => Err(Status::not_found(format!("no workflow {id}"))),
+            Err(e) => Err(internal(e)),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Management for ManagementService {
+    async fn create_workflow(&self, request: Request<CreateWorkflowRequest>) -> Result<Response<Workflow>, Status> {
+        let caller = self.caller(&request, Role::Editor).await?;
+        let payload = request.into_inner();
+        let definition: Value = serde_json::from_str(&payload.definition_json)
+            .map_err(|e| Status::invalid_argument(format!("definition_json is not JSON: {e}")))?;
+        if let Err(invalid) = check_definition(&definition) {
+            let at = invalid.field.map(|field| format!(" at {field}")).unwrap_or_default();
+            return Err(Status::invalid_argument(format!("definition_json is not valid{at}: {}", invalid.error)));
+        }
+        let tags = normalize_tags(&payload.tags);
+        let active = payload.active.unwrap_or(true);
+        let row = wf_repo::create_workflow(
+            &self.state.pool,
+            &payload.name,
+            definition,
+            active,
+            &tags,
+            caller.subject.as_deref(),
+            caller.project.id,
+        )
+        .await
+        .map_err(|e| match e {
+            db::DbError::WebhookPathTaken => Status::already_exists(e.to_string()),
+            e => internal(e),
+        })?;
+        Ok(Response::new(row.into()))
+    }
+
+    async fn get_workflow(&self, request: Request<GetWorkflowRequest>) -> Result<Response<Workflow>, Status> {
+        let caller = self.caller(&request, Role::Viewer).await?;
+        let row = self.workflow(&caller.project, &request.get_ref().id).await?;
+        Ok(Response::new(row.into()))
+    }
+
+    async fn list_workflows(
+        &self,
+        request: Request<ListWorkflowsRequest>,
+    ) -> Result<Response<ListWorkflowsResponse>, Status> {
+        let caller = self.caller(&request, Role::Viewer).await?;
+        let page = request.into_inner();
+        let limit = if page.limit == 0 { 50 } else { page.limit.min(MAX_PAGE) };
+        let filter = WorkflowFilter { project_id: Some(caller.project.id), ..Default::default() };
+        let rows = wf_repo::list_workflows_page(&self.state.pool, &filter, limit.into(), page.offset.into())
+            .await
+            .map_err(internal)?;
+        Ok(Response::new(ListWorkflowsResponse { workflows: rows.into_iter().map(Workflow::from).collect() }))
+    }
+
+    async fn execute_workflow(
+        &self,
+        request: Request<ExecuteWorkflowRequest>,
+    ) -> Result<Response<ExecuteWorkflowResponse>, Status> {
+        let caller = self.caller(&request, Role::Editor).await?;
+        let payload = request.into_inner();
+        let row = self.workflow(&caller.project, &payload.workflow_id).await?;
+        let input: Value = match payload.input_json.trim() {
+            "" => Value::Object(Default::default()),
+            json => serde_json::from_str(json)
+                .map_err(|e| Status::invalid_argument(format!("input_json is not JSON: {e}")))?,
+        };
+
+        let definition = serde_json::from_value::<Definition>(row.definition).ok();
+        if let Some(definition) = &definition {
+            let violations = triggers::check_input(&definition.trigger, &input).map_err(internal)?;
+            if !violations.is_empty() {
+                let reasons: Vec<String> = violations.iter().map(|v| format!("{}: {}", v.path, v.message)).collect();
+                let message = format!("input does not match the trigger's input schema: {}", reasons.join("; "));
+                return Err(Status::invalid_argument(message));
+            }
+        }
+        match quotas::check(&self.state.pool, row.id, definition.as_ref().and_then(|wf| wf.quota.as_ref())).await {
+            Ok(()) => {}
+            Err(e @ engine::EngineError::QuotaExceeded(_)) => return Err(Status::resource_exhausted(e.to_string())),
+            Err(e) => return Err(internal(e)),
+        }
+        let mut meta = definition.map(|wf| wf.execution_meta(&input)).unwrap_or_default();
+        match payload.max_attempts {
+            Some(0) => return Err(Status::invalid_argument("max_attempts must be at least 1")),
+            Some(n) => meta.max_attempts = Some(i32::try_from(n).unwrap_or(i32::MAX)),
+            None => {}
+        }
+
+        let execution = exec_repo::create_execution(&self.state.pool, row.id, &meta).await.map_err(internal)?;
+        let job = job_repo::enqueue_job(&self.state.pool, execution.id, row.id, input).await.map_err(internal)?;
+        self.state.queue.push(&job).await.map_err(internal)?;
+        Ok(Response::new(ExecuteWorkflowResponse {
+            execution_id: execution.id.to_string(),
+            job_id: job.id.to_string(),
+        }))
+    }
+
+    async fn get_execution(&self, request: Request<GetExecutionRequest>) -> Result<Response<Execution>, Status> {
+        let caller = self.caller(&request, Role::Viewer).await?;
+        let id = parse_id("id", &request.get_ref().id)?;
+        let not_found = || Status::not_found(format!("no execution {id}"));
+        match exec_repo::execution_project(&self.state.pool, id).await {
+            Ok(project_id) if project_id == caller.project.id => {}
+            Ok(_) | Err(db::DbError::NotFound) => return Err(not_found()),
+            Err(e) => return Err(internal(e)),
+        }
+        match exec_repo::get_execution(&self.state.pool, id).await {
+            Ok(row) => Ok(Response::new(row.into())),
+            Err(db::DbError::NotFound) => Err(not_found()),
+            Err(e) => Err(internal(e)),
+        }
+    }
+
+    type StreamExecutionEventsStream = Pin<Box<dyn Stream<Item = Result<ExecutionEvent, Status>> + Send>>;
+
+    /// Events come until the client hangs up; a client that falls too far
+    /// behind gets `DATA_LOSS` and has to reconnect.
+    async fn stream_execution_events(
+        &self,
+        request: Request<StreamExecutionEventsRequest>,
+    ) -> Result<Response<Self::StreamExecutionEventsStream>, Status> {
+        let caller = self.caller(&request, Role::Viewer).await?;
+        let filter = request.into_inner();
+        let optional_id = |field, value: Option<String>| value.map(|value| parse_id(field, &value)).transpose();
+        let subscription = Subscription::Executions {
+            workflow_id: optional_id("workflow_id", filter.workflow_id)?,
+            execution_id: optional_id("execution_id", filter.execution_id)?,
+            statuses: filter.statuses,
+        };
+        let project_id = caller.project.id;
+
+        let receiver = self.state.live.subscribe();
+        let events = futures_util::stream::unfold(Some((receiver, subscription)), move |state| async move {
+            let (mut receiver, subscription) = state?;
+            loop {
+                match receiver.recv().await {
+                    Ok(change) if change.project_id() == project_id && subscription.matches(&change) => {
+                        let Change::Execution { id, workflow_id, status, .. } = change else { continue };
+                        let event =
+                            ExecutionEvent { execution_id: id.to_string(), workflow_id: workflow_id.to_string(), status };
+                        return Some((Ok(event), Some((receiver, subscription))));
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(missed)) => {
+                        let message = format!("fell behind and missed {missed} events; subscribe again");
+                        return Some((Err(Status::data_loss(message)), None));
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(events)))
+    }
+}
+
+impl From<WorkflowRow> for Workflow {
+    fn from(row: WorkflowRow) -> Self {
+        Self {
+            id: row.id.to_string(),
+            name: row.name,
+            definition_json: row.definition.to_string(),
+            active: row.active,
+            tags: row.tags,
+            version: row.version,
+            created_at: row.created_at.to_rfc3339(),
+        }
+    }
+}
+
+impl From<WorkflowExecutionRow> for Execution {
+    fn from(row: WorkflowExecutionRow) -> Self {
+        Self {
+            id: row.id.to_string(),
+            workflow_id: row.workflow_id.to_string(),
+            status: row.status,
+            started_at: row.started_at.to_rfc3339(),
+            finished_at: row.finished_at.map(|at| at.to_rfc3339()),
+            business_key: row.business_key,
+        }
+    }
+}
+
+fn parse_id(field: &str, value: &str) -> Result<Uuid, Status> {
+    value.parse().map_err(|_| Status::invalid_argument(format!("{field} is not a UUID")))
+}
+
+/// Log `e`; callers only learn that the call failed.
+fn internal(e: impl Display) -> Status {
+    tracing::error!("gRPC call failed: {e}");
+    Status::internal("internal error")
+}
+
+fn status_from_http(status: axum::http::StatusCode, message: String) -> Status {
+    match status {
+        axum::http::StatusCode::FORBIDDEN => Status::permission_denied(message),
+        axum::http::StatusCode::NOT_FOUND => Status::not_found(message),
+        _ => Status::internal("internal error"),
+    }
+}
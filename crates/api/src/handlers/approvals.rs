@@ -0,0 +1,136 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Html,
+    Extension, Form, Json,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use crate::AppState;
+use crate::auth::{self, Identity};
+use crate::limits::Payload;
+use crate::rbac::Role;
+use engine::approval::{self, Decision};
+use engine::EngineError;
+
+#[derive(serde::Deserialize)]
+pub struct DecideDto {
+    pub token: String,
+    /// `approve` or `reject`.
+    pub decision: String,
+    /// Who decided; only used while authentication is off.
+    #[serde(default)]
+    pub actor: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct TokenQuery {
+    pub token: String,
+}
+
+fn error_status(err: EngineError) -> StatusCode {
+    match err {
+        EngineError::Database(db::DbError::NotFound) | EngineError::ApprovalNotFound { .. } => {
+            StatusCode::NOT_FOUND
+        }
+        EngineError::ApprovalTokenInvalid { .. } => StatusCode::FORBIDDEN,
+        EngineError::ApprovalClosed { .. } => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn parse_decision(decision: &str) -> Result<bool, StatusCode> {
+    match decision {
+        "approve" => Ok(true),
+        "reject" => Ok(false),
+        _ => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+/// `GET /executions/:id/approvals/:node_id` — the pending (or decided, not
+/// yet resumed) approval.  Only callers who may decide it — editors and
+/// admins — also get the approve/reject links, which carry its token.
+pub async fn get(
+    Path((id, node_id)): Path<(Uuid, String)>,
+    State(state): State<AppState>,
+    role: Option<Extension<Role>>,
+) -> Result<Json<Value>, StatusCode> {
+    let record = approval::get(&state.pool, id, &node_id).await.map_err(error_status)?;
+    let mut body = json!({ "execution_id": id, "node_id": node_id, "approval": record });
+    if role.is_some_and(|Extension(role)| role >= Role::Editor) {
+        let link = |decision: &str| {
+            format!("/api/v1/executions/{id}/approvals/{node_id}/{decision}?token={}", record.token)
+        };
+        body["approve_url"] = json!(link("approve"));
+        body["reject_url"] = json!(link("reject"));
+    }
+    Ok(Json(body))
+}
+
+/// `POST /executions/:id/approvals/:node_id` — approve or reject.  The
+/// decision is recorded as the caller's.
+pub async fn decide(
+    Path((id, node_id)): Path<(Uuid, String)>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+    Payload(payload): Payload<DecideDto>,
+) -> Result<Json<Value>, StatusCode> {
+    let decision = Decision {
+        approve: parse_decision(&payload.decision)?,
+        actor: auth::actor(identity, payload.actor.as_deref()),
+        comment: payload.comment,
+    };
+    let record = approval::decide(&state.pool, state.queue.as_ref(), id, &node_id, &payload.token, decision)
+        .await
+        .map_err(error_status)?;
+    Ok(Json(json!({ "execution_id": id, "node_id": node_id, "approval": record })))
+}
+
+/// `GET /executions/:id/approvals/:node_id/:decision?token=...` — a link
+/// that can be sent by email or chat.  It opens a page whose button posts
+/// the decision; opening the link decides nothing, so mail scanners that
+/// fetch links in advance cannot approve or reject.
+pub async fn confirm_link(
+    Path((id, node_id, decision)): Path<(Uuid, String, String)>,
+    Query(query): Query<TokenQuery>,
+    State(state): State<AppState>,
+) -> Result<Html<String>, StatusCode> {
+    let verb = if parse_decision(&decision)? { "Approve" } else { "Reject" };
+    let record = approval::get(&state.pool, id, &node_id).await.map_err(error_status)?;
+    let message = record.message.map(|m| format!("<p>{}</p>", escape(&m))).unwrap_or_default();
+    Ok(Html(format!(
+        "<!doctype html>\n<html><head><meta charset=\"utf-8\"><title>{verb}</title></head><body>\n\
+         {message}<p>{verb} step <code>{node}</code> of execution <code>{id}</code>?</p>\n\
+         <form method=\"post\"><input type=\"hidden\" name=\"token\" value=\"{token}\">\
+         <button type=\"submit\">{verb}</button></form>\n</body></html>\n",
+        node = escape(&node_id),
+        token = escape(&query.token),
+    )))
+}
+
+/// `POST /executions/:id/approvals/:node_id/:decision` with the form the
+/// [`confirm_link`] page posts — the decision, recorded without an actor.
+pub async fn decide_link(
+    Path((id, node_id, decision)): Path<(Uuid, String, String)>,
+    State(state): State<AppState>,
+    Form(form): Form<TokenQuery>,
+) -> Result<Html<String>, StatusCode> {
+    let approve = parse_decision(&decision)?;
+    let decision = Decision { approve, actor: None, comment: None };
+    approval::decide(&state.pool, state.queue.as_ref(), id, &node_id, &form.token, decision)
+        .await
+        .map_err(error_status)?;
+    Ok(Html(format!(
+        "<!doctype html>\n<html><head><meta charset=\"utf-8\"><title>Done</title></head><body>\n\
+         <p>Step <code>{}</code> is {}.</p>\n</body></html>\n",
+        escape(&node_id),
+        if approve { "approved" } else { "rejected" },
+    )))
+}
+
+/// `text` with the characters HTML gives meaning to escaped.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
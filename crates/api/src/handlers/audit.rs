@@ -0,0 +1,24 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use crate::AppState;
+use db::repository::audit as audit_repo;
+
+#[derive(serde::Deserialize)]
+pub struct AuditLogQuery {
+    pub limit: Option<i64>,
+}
+
+/// `GET /admin/audit-log?limit=100` — most recent audited actions.
+pub async fn list(
+    Query(query): Query<AuditLogQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<db::models::AuditLogRow>>, StatusCode> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    match audit_repo::list_recent(&state.pool, limit).await {
+        Ok(rows) => Ok(Json(rows)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
@@ -0,0 +1,79 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use uuid::Uuid;
+use crate::AppState;
+use db::repository::{binary_data as binary_repo, executions as exec_repo};
+
+/// `GET /executions/:id/binary` — the files the execution's nodes stored,
+/// oldest first.
+pub async fn list(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Response {
+    match exec_repo::execution_project(&state.pool, id).await {
+        Ok(_) => {}
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND.into_response(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+    let rows = match binary_repo::list_binary_data(&state.pool, id).await {
+        Ok(rows) => rows,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let binary: Vec<_> = rows
+        .into_iter()
+        .map(|row| {
+            json!({
+                "id": row.id,
+                "mime_type": row.mime_type,
+                "file_name": row.file_name,
+                "size": row.size,
+                "created_at": row.created_at,
+            })
+        })
+        .collect();
+    Json(json!({ "binary": binary })).into_response()
+}
+
+/// `GET /executions/:id/binary/:binary_id` — download a file, with its
+/// MIME type and file name.
+pub async fn download(
+    Path((id, binary_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+) -> Response {
+    let Some(binary) = &state.binary else {
+        let message = "no binary data store is configured (serve --binary-data-url)";
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": message }))).into_response();
+    };
+    let (row, bytes) = match binary.load(id, binary_id).await {
+        Ok(loaded) => loaded,
+        Err(engine::EngineError::Database(db::DbError::NotFound)) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            tracing::error!("cannot load binary data {}: {}", binary_id, e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let mut response = bytes.into_response();
+    let headers = response.headers_mut();
+    if let Ok(mime_type) = HeaderValue::from_str(&row.mime_type) {
+        headers.insert(header::CONTENT_TYPE, mime_type);
+    }
+    // Quotes and control characters cannot appear in the header's file name.
+    let file_name = row
+        .file_name
+        .as_deref()
+        .map(|name| name.chars().filter(|c| !c.is_control() && *c != '"' && *c != '\\').collect::<String>());
+    let disposition = match file_name {
+        Some(name) if !name.is_empty() => format!("attachment; filename=\"{name}\""),
+        _ => "attachment".to_owned(),
+    };
+    if let Ok(disposition) = HeaderValue::from_str(&disposition) {
+        headers.insert(header::CONTENT_DISPOSITION, disposition);
+    }
+    response
+}
@@ -0,0 +1,102 @@
+//! Webhook capture — catch the next request to a workflow's webhook
+//! instead of executing it, and keep its input as the workflow's pinned
+//! sample, so authors can build against real payload shapes.
+//!
+//! While armed, the next request to the webhook is answered `200` with the
+//! captured input and starts no execution, even when the workflow is
+//! inactive; the capture then disarms itself.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use crate::AppState;
+use crate::limits::Payload;
+use db::models::WebhookCaptureRow;
+use db::repository::{captures as capture_repo, workflows as wf_repo};
+use engine::{Trigger, Workflow};
+
+/// How long a capture stays armed unless the request says otherwise.
+const DEFAULT_TIMEOUT_SECS: u64 = 120;
+/// The longest a capture can stay armed.
+const MAX_TIMEOUT_SECS: u64 = 600;
+
+#[derive(Default, serde::Deserialize)]
+pub struct ArmCaptureDto {
+    /// Seconds to wait for a request (default 120, at most 600).
+    pub timeout_secs: Option<u64>,
+}
+
+/// `POST /workflows/:id/webhook-capture` — arm the capture of the next
+/// webhook request.  `400` when the workflow has no webhook trigger.
+pub async fn arm(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    payload: Option<Payload<ArmCaptureDto>>,
+) -> Result<Json<Value>, StatusCode> {
+    let workflow = match wf_repo::get_workflow(&state.pool, id).await {
+        Ok(wf) => wf,
+        Err(db::DbError::NotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    match serde_json::from_value::<Workflow>(workflow.definition) {
+        Ok(Workflow { trigger: Trigger::Webhook { .. }, .. }) => {}
+        _ => return Err(StatusCode::BAD_REQUEST),
+    }
+
+    let Payload(payload) = payload.unwrap_or_default();
+    let timeout = payload.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).clamp(1, MAX_TIMEOUT_SECS);
+    let until = Utc::now() + Duration::seconds(timeout as i64);
+    match capture_repo::arm_capture(&state.pool, id, until).await {
+        Ok(capture) => Ok(Json(capture_view(Some(capture)))),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// `GET /workflows/:id/webhook-capture` — whether a capture is armed, and
+/// the pinned sample (`null` until a request is captured).
+pub async fn get(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, StatusCode> {
+    match wf_repo::get_workflow(&state.pool, id).await {
+        Ok(_) => {}
+        Err(db::DbError::NotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+    match capture_repo::get_capture(&state.pool, id).await {
+        Ok(capture) => Ok(Json(capture_view(capture))),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// `DELETE /workflows/:id/webhook-capture` — disarm the capture and drop
+/// the pinned sample.
+pub async fn delete(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> StatusCode {
+    match capture_repo::delete_capture(&state.pool, id).await {
+        Ok(true) => StatusCode::NO_CONTENT,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// The response body describing a capture.
+fn capture_view(capture: Option<WebhookCaptureRow>) -> Value {
+    let now = Utc::now();
+    match capture {
+        Some(c) => json!({
+            "armed": c.armed_until.is_some_and(|until| until > now),
+            "armed_until": c.armed_until,
+            "sample": c.payload,
+            "captured_at": c.captured_at,
+        }),
+        None => json!({ "armed": false, "armed_until": null, "sample": null, "captured_at": null }),
+    }
+}
@@ -0,0 +1,177 @@
+//! Shared credentials.
+//!
+//! A project's credentials can be used by any of its workflows: nodes name
+//! one by id in their `credential_id` config field.  Like secrets, their
+//! fields are write-only — encrypted with the deployment's secrets key
+//! before they are stored and never returned — and changes are audited
+//! without them.  Without a secrets key they cannot be set (503).
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::auth::Identity;
+use crate::handlers::secrets::no_secrets_key;
+use crate::limits::Payload;
+use crate::AppState;
+use db::models::{CredentialRow, ProjectRow};
+use db::repository::{audit as audit_repo, credentials as credential_repo};
+use nodes::credentials::Credential;
+
+/// A credential to create, or what to replace one with:
+/// `{ "name": "slack", "type": "api_token", "data": { "token": "…" } }`.
+#[derive(Deserialize)]
+pub struct CredentialPayload {
+    pub name: String,
+    #[serde(flatten)]
+    pub credential: Credential,
+}
+
+/// A credential as the API shows it: everything but its fields.
+#[derive(Serialize)]
+pub struct CredentialDto {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<CredentialRow> for CredentialDto {
+    fn from(row: CredentialRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            credential_type: row.credential_type,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// `GET /credentials` — the project's credentials, without their fields.
+pub async fn list(
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+) -> Result<Json<Vec<CredentialDto>>, StatusCode> {
+    match credential_repo::list_credentials(&state.pool, project.id).await {
+        Ok(rows) => Ok(Json(rows.into_iter().map(CredentialDto::from).collect())),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// `GET /credentials/:id`
+pub async fn get(Path(id): Path<Uuid>, State(state): State<AppState>) -> Result<Json<CredentialDto>, StatusCode> {
+    match credential_repo::get_credential(&state.pool, id).await {
+        Ok(row) => Ok(Json(row.into())),
+        Err(db::DbError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// `POST /credentials` — add a credential to the project; 409 when it
+/// already has one with that name.
+pub async fn create(
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+    identity: Option<Extension<Identity>>,
+    Payload(payload): Payload<CredentialPayload>,
+) -> Response {
+    let Some(secrets_key) = &state.secrets else { return no_secrets_key() };
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return invalid_name();
+    }
+    let id = Uuid::new_v4();
+    let sealed = secrets_key.encrypt_credential(id, &payload.credential);
+    let kind = payload.credential.kind();
+    let row = match credential_repo::create_credential(&state.pool, id, project.id, name, kind, &sealed).await {
+        Ok(Some(row)) => row,
+        Ok(None) => return name_taken(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    if let Err(status) = audit(&state, identity, "credential.create", &row).await {
+        return status.into_response();
+    }
+    (StatusCode::CREATED, Json(CredentialDto::from(row))).into_response()
+}
+
+/// `PUT /credentials/:id` — replace a credential's name, type, and fields.
+pub async fn update(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+    Payload(payload): Payload<CredentialPayload>,
+) -> Response {
+    let Some(secrets_key) = &state.secrets else { return no_secrets_key() };
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return invalid_name();
+    }
+    let sealed = secrets_key.encrypt_credential(id, &payload.credential);
+    let kind = payload.credential.kind();
+    let row = match credential_repo::update_credential(&state.pool, id, name, kind, &sealed).await {
+        Ok(row) => row,
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND.into_response(),
+        Err(db::DbError::CredentialNameTaken) => return name_taken(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    if let Err(status) = audit(&state, identity, "credential.update", &row).await {
+        return status.into_response();
+    }
+    Json(CredentialDto::from(row)).into_response()
+}
+
+/// `DELETE /credentials/:id` — nodes still naming it fail from then on.
+pub async fn delete(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+) -> StatusCode {
+    let row = match credential_repo::get_credential(&state.pool, id).await {
+        Ok(row) => row,
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    match credential_repo::delete_credential(&state.pool, id).await {
+        Ok(()) => {}
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+    }
+    match audit(&state, identity, "credential.delete", &row).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(status) => status,
+    }
+}
+
+fn invalid_name() -> Response {
+    let message = "credentials need a name";
+    (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": message, "field": "name" }))).into_response()
+}
+
+fn name_taken() -> Response {
+    let message = db::DbError::CredentialNameTaken.to_string();
+    (StatusCode::CONFLICT, Json(json!({ "error": message }))).into_response()
+}
+
+async fn audit(
+    state: &AppState,
+    identity: Option<Extension<Identity>>,
+    action: &str,
+    row: &CredentialRow,
+) -> Result<(), StatusCode> {
+    let actor = identity.map_or_else(|| "anonymous".to_owned(), |Extension(identity)| identity.subject);
+    let details = json!({ "name": row.name, "type": row.credential_type });
+    match audit_repo::record(&state.pool, &actor, action, "credential", Some(row.id), details).await {
+        Ok(_) => Ok(()),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
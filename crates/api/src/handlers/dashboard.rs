@@ -0,0 +1,49 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use chrono::Utc;
+use serde_json::{json, Value};
+use crate::AppState;
+use db::models::ProjectRow;
+use db::repository::{executions as exec_repo, jobs as job_repo, quotas as quota_repo};
+
+/// Workflows listed under `busiest_workflows`.
+const BUSIEST: i64 = 5;
+
+/// `GET /stats` — the project at a glance for an operations dashboard:
+/// workflow counts, today's (UTC) executions and failure rate, the queue
+/// backlog, the workflows that ran most today, and the project's quota with
+/// what it has used of it.
+pub async fn stats(
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+) -> Result<Json<Value>, StatusCode> {
+    let now = Utc::now();
+    let today = now.date_naive().and_time(chrono::NaiveTime::MIN).and_utc();
+    let (summary, busiest, queues, quota, usage) = tokio::try_join!(
+        exec_repo::dashboard_stats(&state.read_pool, project.id, today),
+        exec_repo::busiest_workflows(&state.read_pool, project.id, today, BUSIEST),
+        job_repo::queue_stats(&state.read_pool, Some(project.id)),
+        quota_repo::project_quota(&state.read_pool, &project.name),
+        quota_repo::project_usage(&state.read_pool, project.id, now),
+    )
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let finished = summary.succeeded + summary.failed;
+    Ok(Json(json!({
+        "as_of": now,
+        "workflows": { "total": summary.workflows, "active": summary.active_workflows },
+        "executions_today": {
+            "total": summary.executions,
+            "succeeded": summary.succeeded,
+            "failed": summary.failed,
+            "in_flight": summary.in_flight,
+        },
+        "failure_rate": (finished > 0).then(|| summary.failed as f64 / finished as f64),
+        "queue_backlog": {
+            "pending": queues.iter().map(|q| q.pending).sum::<i64>(),
+            "due": queues.iter().map(|q| q.due).sum::<i64>(),
+            "oldest_due_secs": queues.iter().filter_map(|q| q.oldest_due_secs).reduce(f64::max),
+        },
+        "busiest_workflows": busiest,
+        "quota": { "limits": quota, "usage": usage },
+    })))
+}
@@ -1,35 +1,481 @@
+use std::collections::BTreeMap;
+
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    Json,
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
-use serde_json::Value;
+use serde_json::{json, Value};
 use uuid::Uuid;
-use super::AppState;
-use db::repository::{executions as exec_repo, jobs as job_repo};
+use crate::AppState;
+use crate::limits::Payload;
+use db::models::{ExecutionFilter, ProjectRow};
+use db::repository::{executions as exec_repo, jobs as job_repo, workflows as wf_repo};
+use engine::{determinism, inheritance, timeline, triggers};
+use engine::lineage::{self, NodeRecord};
+use engine::Workflow;
 
 #[derive(serde::Deserialize)]
 pub struct ExecuteWorkflowDto {
     pub input: Value,
+    /// Execution this run was chained from; the new run inherits its
+    /// priority, queue, and labels.
+    #[serde(default)]
+    pub parent_execution_id: Option<Uuid>,
+    /// Attempts the run's jobs get, overriding the workflow's
+    /// `max_attempts`.
+    #[serde(default)]
+    pub max_attempts: Option<u32>,
+    /// Values of the workflow's parameters for this run, overriding their
+    /// defaults.
+    #[serde(default)]
+    pub params: serde_json::Map<String, Value>,
 }
 
+/// `POST /workflows/:id/execute` — queue a run with the given input.
+///
+/// Input that does not match the manual trigger's `input_schema` is a 422
+/// listing the violations.  `max_attempts` overrides the workflow's attempt
+/// limit for this run, and `params` its parameters' defaults; undeclared
+/// or mistyped parameters are a 422 too.  Once the workflow's or its
+/// project's quota is used up, runs are refused with a 429.
 pub async fn execute(
     Path(id): Path<Uuid>,
     State(state): State<AppState>,
-    Json(payload): Json<ExecuteWorkflowDto>,
-) -> Result<(StatusCode, Json<db::models::JobRow>), StatusCode> {
-    // 1. Create a `pending` execution record
-    let exec = match exec_repo::create_execution(&state.pool, id).await {
+    Payload(payload): Payload<ExecuteWorkflowDto>,
+) -> Response {
+    // 1. Create a `pending` execution record, tagged with its business key/labels
+    let wf_row = match wf_repo::get_workflow(&state.pool, id).await {
+        Ok(w) => w,
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND.into_response(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let project_id = wf_row.project_id;
+    let workflow = serde_json::from_value::<Workflow>(wf_row.definition).ok();
+
+    // The input must match the manual trigger's schema, if it has one.
+    if let Some(workflow) = &workflow {
+        match triggers::check_input(&workflow.trigger, &payload.input) {
+            Ok(violations) if violations.is_empty() => {}
+            Ok(violations) => {
+                let body = json!({ "error": "input does not match the trigger's input schema", "violations": violations });
+                return (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response();
+            }
+            Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        }
+        if let Err(e) = engine::params::resolve(&workflow.params, &payload.params) {
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": e }))).into_response();
+        }
+    }
+    match engine::quotas::check(&state.pool, id, workflow.as_ref().and_then(|wf| wf.quota.as_ref())).await {
+        Ok(()) => {}
+        Err(e @ engine::EngineError::QuotaExceeded(_)) => return over_quota(e),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+    let mut meta = workflow.map(|wf| wf.execution_meta(&payload.input)).unwrap_or_default();
+    meta.params = payload.params;
+
+    // A chained run inherits priority, queue, and labels from its parent.
+    if let Some(parent_id) = payload.parent_execution_id {
+        // ...from the same project only.
+        match exec_repo::execution_project(&state.pool, parent_id).await {
+            Ok(parent_project) if parent_project == project_id => {}
+            Ok(_) | Err(db::DbError::NotFound) => return StatusCode::UNPROCESSABLE_ENTITY.into_response(),
+            Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        }
+        let default_policy = engine::InheritancePolicy::default();
+        meta = match inheritance::inherit(&state.pool, parent_id, meta, &default_policy).await {
+            Ok(m) => m,
+            Err(engine::EngineError::Database(db::DbError::NotFound)) => {
+                return StatusCode::UNPROCESSABLE_ENTITY.into_response()
+            }
+            Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        };
+    }
+
+    match payload.max_attempts {
+        Some(0) => {
+            let body = json!({ "error": "max_attempts must be at least 1" });
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response();
+        }
+        Some(n) => meta.max_attempts = Some(i32::try_from(n).unwrap_or(i32::MAX)),
+        None => {}
+    }
+
+    let exec = match exec_repo::create_execution(&state.pool, id, &meta).await {
         Ok(e) => e,
-        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     };
 
     // 2. Queue the job for background worker
     // The payload represents initial input.
     let job = match job_repo::enqueue_job(&state.pool, exec.id, id, payload.input).await {
         Ok(j) => j,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    if state.queue.push(&job).await.is_err() {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    (StatusCode::ACCEPTED, Json(job)).into_response()
+}
+
+/// 429 for a run refused by a quota.
+fn over_quota(e: engine::EngineError) -> Response {
+    (StatusCode::TOO_MANY_REQUESTS, Json(json!({ "error": e.to_string() }))).into_response()
+}
+
+#[derive(serde::Deserialize, Default)]
+pub struct RetryExecutionDto {
+    /// Resume at the node that failed instead of starting over.
+    #[serde(default)]
+    pub from_failed_node: bool,
+}
+
+/// `POST /executions/:id/retry` — queue a new run of a failed execution,
+/// linked to it through `retry_of`.
+///
+/// The body is optional; `{"from_failed_node": true}` skips the nodes that
+/// already succeeded.  Executions that have not failed (or cannot resume
+/// at their failed node) are a 409, and a used-up quota is a 429.
+pub async fn retry(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    payload: Option<Payload<RetryExecutionDto>>,
+) -> Response {
+    let Payload(payload) = payload.unwrap_or_default();
+    match engine::retry::retry(&state.pool, state.queue.as_ref(), id, payload.from_failed_node).await {
+        Ok(job) => (StatusCode::ACCEPTED, Json(job)).into_response(),
+        Err(engine::EngineError::Database(db::DbError::NotFound)) => StatusCode::NOT_FOUND.into_response(),
+        Err(e @ engine::EngineError::NotRetryable { .. }) => {
+            (StatusCode::CONFLICT, Json(json!({ "error": e.to_string() }))).into_response()
+        }
+        Err(e @ engine::EngineError::QuotaExceeded(_)) => over_quota(e),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+#[derive(serde::Deserialize, Default)]
+pub struct ReplayExecutionDto {
+    /// Keep the recorded outputs of the nodes sorted before this one and
+    /// start there instead of at the first node.
+    pub from_node: Option<String>,
+    /// Only say what the replay would do.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// `POST /executions/:id/replay` — run a finished execution again under
+/// the workflow's current definition, from the start or from `from_node`,
+/// as a new execution linked to it through `retry_of`.
+///
+/// Answers `{"plan": …, "job": …}` with 202, or with `{"dry_run": true}`
+/// just `{"plan": …}` with 200.  Executions still going, or a `from_node`
+/// that is unknown or after a failed node, are a 409; a used-up quota is a
+/// 429.
+pub async fn replay(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    payload: Option<Payload<ReplayExecutionDto>>,
+) -> Response {
+    let Payload(payload) = payload.unwrap_or_default();
+    let from_node = payload.from_node.as_deref();
+    let replayed = if payload.dry_run {
+        engine::retry::plan_replay(&state.pool, id, from_node).await.map(|(plan, _)| (plan, None))
+    } else {
+        engine::retry::replay(&state.pool, state.queue.as_ref(), id, from_node).await.map(|(plan, job)| (plan, Some(job)))
+    };
+    match replayed {
+        Ok((plan, None)) => (StatusCode::OK, Json(json!({ "plan": plan }))).into_response(),
+        Ok((plan, Some(job))) => (StatusCode::ACCEPTED, Json(json!({ "plan": plan, "job": job }))).into_response(),
+        Err(engine::EngineError::Database(db::DbError::NotFound)) => StatusCode::NOT_FOUND.into_response(),
+        Err(e @ engine::EngineError::NotRetryable { .. }) => {
+            (StatusCode::CONFLICT, Json(json!({ "error": e.to_string() }))).into_response()
+        }
+        Err(e @ engine::EngineError::InvalidWorkflow { .. }) => {
+            (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": e.to_string() }))).into_response()
+        }
+        Err(e @ engine::EngineError::QuotaExceeded(_)) => over_quota(e),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct ListExecutionsQuery {
+    pub business_key: Option<String>,
+    pub status: Option<String>,
+    pub started_after: Option<chrono::DateTime<chrono::Utc>>,
+    pub started_before: Option<chrono::DateTime<chrono::Utc>>,
+    /// The workflow trigger's `type`, e.g. `webhook`.
+    pub trigger: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl ListExecutionsQuery {
+    fn filter(&self, project_id: Uuid, workflow_id: Option<Uuid>) -> ExecutionFilter {
+        ExecutionFilter {
+            project_id: Some(project_id),
+            workflow_id,
+            status: self.status.clone(),
+            started_after: self.started_after,
+            started_before: self.started_before,
+            trigger_type: self.trigger.clone(),
+        }
+    }
+}
+
+/// `GET /executions` — a page of runs across workflows, newest first,
+/// filtered by `status`, `started_after`/`started_before`, and `trigger`.
+///
+/// With `?business_key=...` it instead returns every run that dealt with
+/// one business entity, plus a rollup by status and workflow.
+pub async fn list(
+    Query(query): Query<ListExecutionsQuery>,
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+) -> Result<Json<Value>, StatusCode> {
+    match &query.business_key {
+        Some(k) if !k.is_empty() => by_business_key(&state, project.id, k).await,
+        Some(_) => Err(StatusCode::BAD_REQUEST),
+        None => page(&state, &query, project.id, None).await,
+    }
+}
+
+/// `GET /workflows/:id/executions` — a page of one workflow's runs, with
+/// the same filters as `GET /executions`.
+pub async fn list_for_workflow(
+    Path(id): Path<Uuid>,
+    Query(query): Query<ListExecutionsQuery>,
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+) -> Result<Json<Value>, StatusCode> {
+    match wf_repo::get_workflow(&state.pool, id).await {
+        Ok(_) => {}
+        Err(db::DbError::NotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+    page(&state, &query, project.id, Some(id)).await
+}
+
+async fn page(
+    state: &AppState,
+    query: &ListExecutionsQuery,
+    project_id: Uuid,
+    workflow_id: Option<Uuid>,
+) -> Result<Json<Value>, StatusCode> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    // One extra row tells us whether there is a next page.
+    let filter = query.filter(project_id, workflow_id);
+    let mut executions = match exec_repo::list_executions(&state.read_pool, &filter, limit + 1, offset).await {
+        Ok(rows) => rows,
         Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
     };
+    let has_more = executions.len() as i64 > limit;
+    executions.truncate(limit as usize);
+
+    Ok(Json(json!({
+        "executions": executions,
+        "limit": limit,
+        "offset": offset,
+        "next_offset": has_more.then_some(offset + limit),
+    })))
+}
+
+async fn by_business_key(state: &AppState, project_id: Uuid, business_key: &str) -> Result<Json<Value>, StatusCode> {
+    let executions = match exec_repo::list_executions_by_business_key(&state.read_pool, Some(project_id), business_key).await {
+        Ok(rows) => rows,
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let mut by_status: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut by_workflow: BTreeMap<Uuid, (&str, usize)> = BTreeMap::new();
+    for e in &executions {
+        *by_status.entry(e.status.as_str()).or_default() += 1;
+        by_workflow.entry(e.workflow_id).or_insert((e.workflow_name.as_str(), 0)).1 += 1;
+    }
+    let workflows: Vec<Value> = by_workflow
+        .iter()
+        .map(|(id, (name, runs))| json!({ "workflow_id": id, "workflow_name": name, "runs": runs }))
+        .collect();
+
+    Ok(Json(json!({
+        "business_key": business_key,
+        "total": executions.len(),
+        "by_status": by_status,
+        "workflows": workflows,
+        "executions": executions,
+    })))
+}
+
+/// `GET /executions/:id` — an execution and its node results in the order
+/// they ran, each with its duration and, for failed nodes, the error.
+pub async fn get(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, StatusCode> {
+    let exec = match exec_repo::get_execution(&state.read_pool, id).await {
+        Ok(e) => e,
+        Err(db::DbError::NotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let node_execs = match exec_repo::list_node_executions(&state.read_pool, id).await {
+        Ok(rows) => rows,
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let nodes: Vec<Value> = node_execs
+        .into_iter()
+        .map(|n| {
+            let duration_ms = n.finished_at.map(|f| (f - n.started_at).num_milliseconds());
+            json!({
+                "node_id": n.node_id,
+                "status": n.status,
+                "started_at": n.started_at,
+                "finished_at": n.finished_at,
+                "duration_ms": duration_ms,
+                "input": n.input,
+                "output": n.output,
+                "error": n.error,
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({ "execution": exec, "nodes": nodes })))
+}
+
+/// `DELETE /executions/:id` — delete a finished execution with its node
+/// records and jobs.  Running or held executions are a 409.
+pub async fn delete(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Response {
+    match exec_repo::delete_execution(&state.pool, id).await {
+        Ok(true) => return StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => {}
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+    match exec_repo::get_execution(&state.pool, id).await {
+        Ok(_) => {
+            let body = json!({ "error": "only finished executions without a legal hold can be deleted" });
+            (StatusCode::CONFLICT, Json(body)).into_response()
+        }
+        Err(db::DbError::NotFound) => StatusCode::NOT_FOUND.into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct PruneExecutionsQuery {
+    pub workflow_id: Option<Uuid>,
+    /// Delete executions that finished before this time.
+    pub before: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Executions deleted per statement when pruning.
+const PRUNE_BATCH: i64 = 1000;
+
+/// `DELETE /executions?before=...&workflow_id=...` — delete every finished
+/// execution matching the filters (at least one is required), skipping
+/// those under legal hold.  Deletes run in batches.
+pub async fn prune(
+    Query(query): Query<PruneExecutionsQuery>,
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+) -> Result<Json<Value>, StatusCode> {
+    if query.workflow_id.is_none() && query.before.is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let mut deleted = 0;
+    loop {
+        let batch =
+            exec_repo::delete_finished_executions(&state.pool, Some(project.id), query.workflow_id, query.before, PRUNE_BATCH)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        deleted += batch;
+        if batch < PRUNE_BATCH as u64 {
+            break;
+        }
+    }
+    Ok(Json(json!({ "deleted": deleted })))
+}
+
+#[derive(serde::Deserialize)]
+pub struct LineageQuery {
+    pub path: String,
+}
+
+/// `GET /executions/:id/lineage?path=customer.email` — which nodes of the
+/// execution received, read, wrote, or copied the given JSON path.
+pub async fn lineage(
+    Path(id): Path<Uuid>,
+    Query(query): Query<LineageQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<lineage::LineageReport>, StatusCode> {
+    if query.path.trim_matches('.').is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let exec = match exec_repo::get_execution(&state.pool, id).await {
+        Ok(e) => e,
+        Err(db::DbError::NotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let wf_row = match wf_repo::get_workflow(&state.pool, exec.workflow_id).await {
+        Ok(w) => w,
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let workflow: Workflow = match serde_json::from_value(wf_row.definition) {
+        Ok(w) => w,
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let node_execs = match exec_repo::list_node_executions(&state.pool, id).await {
+        Ok(rows) => rows,
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let records: Vec<NodeRecord<'_>> = node_execs
+        .iter()
+        .map(|n| NodeRecord { node_id: &n.node_id, input: &n.input, output: n.output.as_ref() })
+        .collect();
+    Ok(Json(lineage::trace(&workflow, &records, &query.path)))
+}
+
+/// `GET /executions/:id/timeline` — the execution as a waterfall: time
+/// queued, then each node run with its attempts, offset from when the
+/// execution was queued.
+pub async fn timeline(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<Json<timeline::Timeline>, StatusCode> {
+    match exec_repo::execution_timings(&state.read_pool, id).await {
+        Ok((execution, nodes)) => Ok(Json(timeline::build(&execution, &nodes))),
+        Err(db::DbError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// `GET /executions/:id/compare/:other` — where two runs diverged, judged by
+/// the input/output hashes of their nodes.
+pub async fn compare(
+    Path((id, other)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+) -> Result<Json<determinism::Comparison>, StatusCode> {
+    let mut runs = Vec::with_capacity(2);
+    for exec_id in [id, other] {
+        if let Err(e) = exec_repo::get_execution(&state.pool, exec_id).await {
+            return Err(match e {
+                db::DbError::NotFound => StatusCode::NOT_FOUND,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            });
+        }
+        match exec_repo::list_node_executions(&state.pool, exec_id).await {
+            Ok(rows) => runs.push(rows),
+            Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
 
-    Ok((StatusCode::ACCEPTED, Json(job)))
+    Ok(Json(determinism::compare(&runs[0], &runs[1])))
 }
@@ -0,0 +1,137 @@
+//! Admin endpoints for feature flag overrides.
+//!
+//! Changes are audited under the caller's identity and take effect on this
+//! server immediately (other servers pick them up on their next refresh).
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use crate::auth::{self, Identity};
+use crate::AppState;
+use crate::limits::Payload;
+use db::repository::{audit as audit_repo, feature_flags as flag_repo};
+
+/// Where an override applies.
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagScopeKind {
+    Global,
+    Organization,
+    Workflow,
+}
+
+impl FlagScopeKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Global => "global",
+            Self::Organization => "organization",
+            Self::Workflow => "workflow",
+        }
+    }
+
+    /// Normalised scope ID, or `None` if it does not fit the scope.
+    fn scope_id(self, id: Option<&str>) -> Option<String> {
+        let id = id.map(str::trim).unwrap_or_default();
+        match self {
+            Self::Global => id.is_empty().then(String::new),
+            Self::Organization => (!id.is_empty()).then(|| id.to_owned()),
+            Self::Workflow => id.parse::<Uuid>().ok().map(|u| u.to_string()),
+        }
+    }
+}
+
+/// `GET /admin/feature-flags` — startup defaults and stored overrides.
+pub async fn list(State(state): State<AppState>) -> Result<Json<Value>, StatusCode> {
+    let overrides = match flag_repo::list_flags(&state.pool).await {
+        Ok(rows) => rows,
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    Ok(Json(json!({ "defaults": state.flags.defaults(), "overrides": overrides })))
+}
+
+#[derive(serde::Deserialize)]
+pub struct SetFlagDto {
+    pub scope: FlagScopeKind,
+    pub scope_id: Option<String>,
+    pub enabled: bool,
+    /// Who changed the flag, while authentication is off; otherwise the
+    /// caller is recorded in the audit log.
+    #[serde(default)]
+    pub actor: Option<String>,
+}
+
+/// `PUT /admin/feature-flags/:flag` — set an override.
+pub async fn set(
+    Path(flag): Path<String>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+    Payload(payload): Payload<SetFlagDto>,
+) -> Result<Json<db::models::FeatureFlagRow>, StatusCode> {
+    let scope_id = payload.scope.scope_id(payload.scope_id.as_deref());
+    let (Some(scope_id), Some(actor)) = (scope_id, auth::actor(identity, payload.actor.as_deref())) else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let row = match flag_repo::upsert_flag(&state.pool, &flag, payload.scope.as_str(), &scope_id, payload.enabled).await {
+        Ok(r) => r,
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let details = json!({ "flag": flag, "scope": row.scope, "scope_id": row.scope_id, "enabled": row.enabled });
+    if audit_repo::record(&state.pool, &actor, "feature_flag.set", "feature_flag", None, details)
+        .await
+        .is_err()
+    {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    if let Err(e) = state.flags.refresh().await {
+        tracing::warn!("feature flags: refresh after update failed: {}", e);
+    }
+
+    Ok(Json(row))
+}
+
+#[derive(serde::Deserialize)]
+pub struct RemoveFlagQuery {
+    pub scope: FlagScopeKind,
+    pub scope_id: Option<String>,
+    /// As for [`SetFlagDto::actor`].
+    #[serde(default)]
+    pub actor: Option<String>,
+}
+
+/// `DELETE /admin/feature-flags/:flag?scope=...&scope_id=...` — remove an
+/// override, falling back to the next less specific setting.  Without
+/// authentication, `actor=...` names who removed it.
+pub async fn remove(
+    Path(flag): Path<String>,
+    Query(query): Query<RemoveFlagQuery>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+) -> Result<StatusCode, StatusCode> {
+    let scope_id = query.scope.scope_id(query.scope_id.as_deref());
+    let (Some(scope_id), Some(actor)) = (scope_id, auth::actor(identity, query.actor.as_deref())) else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    match flag_repo::delete_flag(&state.pool, &flag, query.scope.as_str(), &scope_id).await {
+        Ok(_) => {}
+        Err(db::DbError::NotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+    let details = json!({ "flag": flag, "scope": query.scope.as_str(), "scope_id": scope_id });
+    if audit_repo::record(&state.pool, &actor, "feature_flag.remove", "feature_flag", None, details)
+        .await
+        .is_err()
+    {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    if let Err(e) = state.flags.refresh().await {
+        tracing::warn!("feature flags: refresh after update failed: {}", e);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
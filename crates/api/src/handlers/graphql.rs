@@ -0,0 +1,19 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use crate::{graphql, AppState};
+use crate::limits::Payload;
+use db::models::ProjectRow;
+use engine::flags::{self, FlagScope};
+
+/// `POST /graphql` — run a GraphQL query (see [`crate::graphql`]) within
+/// the request's project.  404 unless the global `graphql` feature flag
+/// is on.
+pub async fn query(
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+    Payload(request): Payload<async_graphql::Request>,
+) -> Result<Json<async_graphql::Response>, StatusCode> {
+    if !state.flags.is_enabled(flags::GRAPHQL, &FlagScope::global()) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(Json(graphql::execute(state.read_pool.clone(), project, request).await))
+}
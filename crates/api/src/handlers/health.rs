@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use crate::AppState;
+use db::pool::PoolHealth;
+use engine::readiness::ReadinessReport;
+
+/// Longest `/readyz` waits for a database connection before reporting the
+/// pool unhealthy.
+const POOL_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Body of `/readyz`: startup progress and the database pools.
+#[derive(Serialize)]
+pub struct Readyz {
+    #[serde(flatten)]
+    pub report: ReadinessReport,
+    pub database: DatabaseHealth,
+}
+
+/// The pools' health; `read` is the primary pool again without a replica.
+#[derive(Serialize)]
+pub struct DatabaseHealth {
+    pub primary: PoolHealth,
+    pub read: PoolHealth,
+}
+
+/// `GET /healthz` — the process is up.
+pub async fn healthz() -> StatusCode {
+    StatusCode::OK
+}
+
+/// `GET /readyz` — 200 once every startup task has finished and both pools
+/// hand out connections, 503 (with the same report) until then.
+pub async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<Readyz>) {
+    let mut report = state.readiness.report();
+    let database = DatabaseHealth {
+        primary: db::pool::check(&state.pool, POOL_CHECK_TIMEOUT).await,
+        read: db::pool::check(&state.read_pool, POOL_CHECK_TIMEOUT).await,
+    };
+    report.ready &= database.primary.is_healthy() && database.read.is_healthy();
+    let status = if report.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(Readyz { report, database }))
+}
@@ -0,0 +1,106 @@
+//! Admin endpoints for placing and lifting legal holds.
+//!
+//! `:target` is `workflows` or `executions`.  Held records are skipped by
+//! retention pruning and refuse deletion; every change is audited, under
+//! the caller's identity.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use crate::auth::{self, Identity};
+use crate::AppState;
+use crate::limits::Payload;
+use db::models::HoldTarget;
+use db::repository::legal_holds as hold_repo;
+
+/// The `:target` path segment.
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HoldTargetPath {
+    Workflows,
+    Executions,
+}
+
+impl From<HoldTargetPath> for HoldTarget {
+    fn from(path: HoldTargetPath) -> Self {
+        match path {
+            HoldTargetPath::Workflows => HoldTarget::Workflow,
+            HoldTargetPath::Executions => HoldTarget::Execution,
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct SetHoldDto {
+    pub held: bool,
+    /// Who placed or lifted the hold, while authentication is off;
+    /// otherwise the caller is recorded.
+    #[serde(default)]
+    pub actor: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// `PUT /admin/legal-holds/:target/:id` — place or lift a hold.
+///
+/// Responds with the current state and the audit entry, which is `null`
+/// when the record was already in the requested state.
+pub async fn set(
+    Path((target, id)): Path<(HoldTargetPath, Uuid)>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+    Payload(payload): Payload<SetHoldDto>,
+) -> Result<Json<Value>, StatusCode> {
+    let Some(actor) = auth::actor(identity, payload.actor.as_deref()) else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let event = match hold_repo::set_legal_hold(
+        &state.pool,
+        target.into(),
+        id,
+        payload.held,
+        &actor,
+        payload.reason.as_deref(),
+    )
+    .await
+    {
+        Ok(e) => e,
+        Err(db::DbError::NotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    if let Some(e) = &event {
+        tracing::info!(
+            "legal hold {} on {} {} by {}",
+            if e.held { "placed" } else { "lifted" },
+            e.target_type,
+            id,
+            e.actor
+        );
+    }
+
+    Ok(Json(json!({ "held": payload.held, "event": event })))
+}
+
+/// `GET /admin/legal-holds/:target/:id` — current hold state and history.
+pub async fn history(
+    Path((target, id)): Path<(HoldTargetPath, Uuid)>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, StatusCode> {
+    let held = match hold_repo::is_held(&state.pool, target.into(), id).await {
+        Ok(h) => h,
+        Err(db::DbError::NotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let events = match hold_repo::list_legal_hold_events(&state.pool, target.into(), id).await {
+        Ok(rows) => rows,
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    Ok(Json(json!({ "held": held, "events": events })))
+}
@@ -0,0 +1,91 @@
+//! `GET /ws` — live updates over a WebSocket.
+//!
+//! Clients send JSON messages to subscribe and unsubscribe:
+//!
+//! ```json
+//! { "action": "subscribe", "id": "runs", "topic": "executions", "workflow_id": "…", "statuses": ["failed"] }
+//! { "action": "subscribe", "id": "list", "topic": "workflows" }
+//! { "action": "unsubscribe", "id": "runs" }
+//! ```
+//!
+//! and receive, for every change in their project a subscription matches,
+//! `{"type": "change", "subscription": "runs", "change": {…}}`.  Requests
+//! are acknowledged with `{"type": "subscribed" | "unsubscribed", "id": …}`
+//! or answered with `{"type": "error", "error": …}`; a client that fell
+//! behind gets `{"type": "lagged", "missed": n}` and should reload.
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+    Extension,
+};
+use db::changes::Change;
+use db::models::ProjectRow;
+use serde_json::{json, Value};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use uuid::Uuid;
+
+use crate::auth::WS_PROTOCOL;
+use crate::live::{ClientMessage, Subscription};
+use crate::AppState;
+
+/// `GET /ws`
+pub async fn connect(
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let changes = state.live.subscribe();
+    ws.protocols([WS_PROTOCOL]).on_upgrade(move |socket| serve(socket, changes, project.id))
+}
+
+/// Answer the client's messages and push it the changes in `project_id`
+/// its subscriptions match, until either side closes.
+async fn serve(mut socket: WebSocket, mut changes: Receiver<Change>, project_id: Uuid) {
+    let mut subscriptions: HashMap<String, Subscription> = HashMap::new();
+    loop {
+        let outgoing = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => vec![answer(&mut subscriptions, &text)],
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                // Pings are answered by axum.
+                Some(Ok(_)) => continue,
+            },
+            change = changes.recv() => match change {
+                Ok(change) if change.project_id() == project_id => subscriptions
+                    .iter()
+                    .filter(|(_, subscription)| subscription.matches(&change))
+                    .map(|(id, _)| json!({ "type": "change", "subscription": id, "change": change }))
+                    .collect(),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => vec![json!({ "type": "lagged", "missed": missed })],
+                Err(RecvError::Closed) => return,
+            },
+        };
+        for message in outgoing {
+            if socket.send(Message::Text(message.to_string())).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Apply a client's message to its `subscriptions`; returns the reply.
+fn answer(subscriptions: &mut HashMap<String, Subscription>, text: &str) -> Value {
+    match serde_json::from_str::<ClientMessage>(text) {
+        Ok(ClientMessage::Subscribe { id, subscription }) => {
+            subscriptions.insert(id.clone(), subscription);
+            json!({ "type": "subscribed", "id": id })
+        }
+        Ok(ClientMessage::Unsubscribe { id }) => match subscriptions.remove(&id) {
+            Some(_) => json!({ "type": "unsubscribed", "id": id }),
+            None => json!({ "type": "error", "error": format!("no subscription '{id}'") }),
+        },
+        Err(e) => json!({ "type": "error", "error": e.to_string() }),
+    }
+}
@@ -0,0 +1,72 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use uuid::Uuid;
+use crate::AppState;
+use db::models::LogFilter;
+use db::repository::{executions as exec_repo, logs as log_repo};
+use nodes::traits::LogLevel;
+
+#[derive(serde::Deserialize)]
+pub struct ListLogsQuery {
+    /// Least severe level to include, e.g. `warn` for warnings and errors.
+    pub level: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// `GET /executions/:id/logs?level=...&limit=100&offset=0` — the lines the
+/// execution's nodes logged, in the order they were logged.
+pub async fn list(
+    Path(id): Path<Uuid>,
+    Query(query): Query<ListLogsQuery>,
+    State(state): State<AppState>,
+) -> Response {
+    page(&state, id, None, &query).await
+}
+
+/// `GET /executions/:id/nodes/:node_id/logs` — like
+/// `GET /executions/:id/logs`, for one node.
+pub async fn list_for_node(
+    Path((id, node_id)): Path<(Uuid, String)>,
+    Query(query): Query<ListLogsQuery>,
+    State(state): State<AppState>,
+) -> Response {
+    page(&state, id, Some(node_id), &query).await
+}
+
+async fn page(state: &AppState, id: Uuid, node_id: Option<String>, query: &ListLogsQuery) -> Response {
+    let levels = match query.level.as_deref().map(str::parse::<LogLevel>).transpose() {
+        Ok(least) => least.map(|least| {
+            LogLevel::ALL.into_iter().filter(|level| *level >= least).map(|level| level.as_str().to_owned()).collect()
+        }),
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": message }))).into_response(),
+    };
+    match exec_repo::execution_project(&state.pool, id).await {
+        Ok(_) => {}
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND.into_response(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let filter = LogFilter { node_id, levels };
+    // One extra row tells us whether there is a next page.
+    let mut rows = match log_repo::list_logs(&state.read_pool, id, &filter, limit + 1, offset).await {
+        Ok(rows) => rows,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let count = rows.len();
+    rows.truncate(limit as usize);
+    Json(json!({
+        "logs": rows,
+        "limit": limit,
+        "offset": offset,
+        "next_offset": (count as i64 > limit).then_some(offset + limit),
+    }))
+    .into_response()
+}
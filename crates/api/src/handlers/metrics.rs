@@ -0,0 +1,20 @@
+use std::time::Duration;
+
+use axum::{extract::State, response::Response};
+use crate::AppState;
+use db::repository::jobs as job_repo;
+
+/// Longest the pool gauges wait for a connection.
+const POOL_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// `GET /metrics` — every metric in the Prometheus text format, with the
+/// queue gauges refreshed (across all projects) and the pools checked.
+pub async fn render(State(state): State<AppState>) -> Response {
+    crate::metrics::record_pool("primary", &db::pool::check(&state.pool, POOL_CHECK_TIMEOUT).await);
+    crate::metrics::record_pool("read", &db::pool::check(&state.read_pool, POOL_CHECK_TIMEOUT).await);
+    match job_repo::queue_stats(&state.pool, None).await {
+        Ok(queues) => crate::metrics::record_queues(&queues),
+        Err(e) => tracing::warn!("Cannot read queue stats for metrics: {e}"),
+    }
+    crate::metrics::render()
+}
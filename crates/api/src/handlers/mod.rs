@@ -1,3 +1,23 @@
 pub mod workflows;
 pub mod executions;
 pub mod webhooks;
+pub mod captures;
+pub mod legal_holds;
+pub mod privacy;
+pub mod support;
+pub mod audit;
+pub mod feature_flags;
+pub mod health;
+pub mod approvals;
+pub mod queues;
+pub mod users;
+pub mod projects;
+pub mod live;
+pub mod metrics;
+pub mod secrets;
+pub mod credentials;
+pub mod nodes;
+pub mod graphql;
+pub mod logs;
+pub mod dashboard;
+pub mod binary;
@@ -0,0 +1,10 @@
+use axum::{extract::State, Json};
+use crate::AppState;
+use nodes::registry::{catalog as build_catalog, CatalogEntry};
+
+/// `GET /node-types` — every registered node type with its descriptor
+/// (display name, category, config JSON Schema, credentials, input and
+/// output hints), sorted by type, for node palettes and config forms.
+pub async fn catalog(State(state): State<AppState>) -> Json<Vec<CatalogEntry>> {
+    Json(build_catalog(state.automation.registry()))
+}
@@ -0,0 +1,44 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use serde_json::Value;
+use crate::auth::{self, Identity};
+use crate::AppState;
+use crate::limits::Payload;
+use engine::privacy::{self, ErasureCriteria, ErasureReport};
+
+/// Body of `POST /privacy/erasure`: either `business_key`, or `path` and
+/// `value`.
+#[derive(serde::Deserialize)]
+pub struct ErasureDto {
+    pub business_key: Option<String>,
+    pub path: Option<String>,
+    pub value: Option<Value>,
+    /// Who requested the erasure, while authentication is off; otherwise
+    /// the caller is recorded in the erasure log.
+    #[serde(default)]
+    pub actor: Option<String>,
+    /// Report what would be erased without changing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// `POST /privacy/erasure` — redact a data subject's values from stored
+/// execution data and return the erasure report.
+pub async fn erasure(
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+    Payload(payload): Payload<ErasureDto>,
+) -> Result<Json<ErasureReport>, StatusCode> {
+    let criteria = match (payload.business_key, payload.path, payload.value) {
+        (Some(key), None, None) => ErasureCriteria::business_key(key),
+        (None, Some(path), Some(value)) => ErasureCriteria::value(&path, value),
+        _ => None,
+    };
+    let (Some(criteria), Some(actor)) = (criteria, auth::actor(identity, payload.actor.as_deref())) else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    match privacy::erase(&state.pool, &criteria, &actor, payload.dry_run).await {
+        Ok(report) => Ok(Json(report)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
@@ -0,0 +1,82 @@
+//! Admin endpoints for projects.
+//!
+//! Project names are what requests give in `X-Project` and API keys are
+//! confined to; creations and quota changes are audited.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde_json::json;
+use crate::auth::Identity;
+use crate::AppState;
+use crate::limits::Payload;
+use db::repository::{audit as audit_repo, projects as project_repo, quotas as quota_repo};
+use engine::Quota;
+
+#[derive(serde::Deserialize)]
+pub struct CreateProjectDto {
+    pub name: String,
+}
+
+/// `GET /projects` — every project.
+pub async fn list(State(state): State<AppState>) -> Result<Json<Vec<db::models::ProjectRow>>, StatusCode> {
+    match project_repo::list_projects(&state.pool).await {
+        Ok(projects) => Ok(Json(projects)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// `POST /projects` — create a project.  Names are lowercase letters,
+/// digits, `-`, and `_`; a taken name is a 409.
+pub async fn create(
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+    Payload(payload): Payload<CreateProjectDto>,
+) -> Response {
+    let name = payload.name.trim();
+    let valid = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !valid {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+    let project = match project_repo::create_project(&state.pool, name).await {
+        Ok(Some(project)) => project,
+        Ok(None) => {
+            let body = json!({ "error": format!("project '{name}' already exists") });
+            return (StatusCode::CONFLICT, Json(body)).into_response();
+        }
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let actor = identity.map_or_else(|| "anonymous".to_owned(), |Extension(identity)| identity.subject);
+    let details = json!({ "name": project.name });
+    if audit_repo::record(&state.pool, &actor, "project.create", "project", Some(project.id), details).await.is_err() {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    (StatusCode::CREATED, Json(project)).into_response()
+}
+
+/// `PUT /projects/:name/quota` — replace the project's quota (see
+/// [`engine::quotas`]); `{}` removes every limit.  Usage against it is
+/// shown by `GET /stats`.
+pub async fn set_quota(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+    Payload(quota): Payload<Quota>,
+) -> Response {
+    let stored = quota.is_set().then(|| serde_json::to_value(&quota).expect("quotas serialize"));
+    let project_id = match quota_repo::set_project_quota(&state.pool, &name, stored).await {
+        Ok(id) => id,
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND.into_response(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let actor = identity.map_or_else(|| "anonymous".to_owned(), |Extension(identity)| identity.subject);
+    let details = json!({ "name": name, "quota": quota });
+    if audit_repo::record(&state.pool, &actor, "project.quota", "project", Some(project_id), details).await.is_err() {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    Json(json!({ "name": name, "quota": quota })).into_response()
+}
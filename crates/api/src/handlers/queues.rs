@@ -0,0 +1,28 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use serde_json::{json, Value};
+use crate::AppState;
+use db::models::ProjectRow;
+use db::repository::jobs as job_repo;
+
+/// `GET /queues` — backlog and throughput of every queue, plus totals, for
+/// autoscalers and dashboards; only the project's jobs count.
+pub async fn stats(
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+) -> Result<Json<Value>, StatusCode> {
+    let queues = job_repo::queue_stats(&state.read_pool, Some(project.id))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let sum = |count: fn(&db::models::QueueStatsRow) -> i64| queues.iter().map(count).sum::<i64>();
+    let total = json!({
+        "pending": sum(|q| q.pending),
+        "due": sum(|q| q.due),
+        "processing": sum(|q| q.processing),
+        "dead_lettered": sum(|q| q.dead_lettered),
+        "oldest_due_secs": queues.iter().filter_map(|q| q.oldest_due_secs).reduce(f64::max),
+        "completed_last_minute": sum(|q| q.completed_last_minute),
+        "completed_last_hour": sum(|q| q.completed_last_hour),
+    });
+    Ok(Json(json!({ "queues": queues, "total": total })))
+}
@@ -0,0 +1,348 @@
+//! Workflow and project secrets.
+//!
+//! A workflow's secrets are its own; a project's are read by every
+//! workflow in it, a workflow's own secret of the same key winning.
+//!
+//! Values are write-only: they are encrypted with the deployment's secrets
+//! key before they are stored and never returned, so listings only name
+//! the keys and say when each was set.  Changes are audited without their
+//! values.  Without a secrets key these endpoints answer 503.
+//!
+//! A key may also hold a value per environment (`"environment": "prod"` when
+//! creating it, `?environment=prod` to change or delete that value), which
+//! workers running in that environment read instead of the default.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::auth::Identity;
+use crate::AppState;
+use crate::limits::Payload;
+use db::models::{ProjectRow, SecretRow};
+use engine::secrets::SecretsKey;
+use db::repository::{audit as audit_repo, secrets as secret_repo, workflows as wf_repo};
+
+#[derive(Deserialize)]
+pub struct CreateSecretDto {
+    pub key: String,
+    pub value: String,
+    /// Environment the value is for; the key's default without one.
+    pub environment: Option<String>,
+}
+
+/// Which of a key's values `PUT` and `DELETE` act on.
+#[derive(Deserialize, Default)]
+pub struct EnvironmentQuery {
+    /// The environment's; the default without one.
+    pub environment: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateSecretDto {
+    pub value: String,
+}
+
+/// A secret as the API shows it: everything but the value.
+#[derive(Serialize)]
+pub struct SecretDto {
+    pub key: String,
+    /// `None` for the key's default.
+    pub environment: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<SecretRow> for SecretDto {
+    fn from(row: SecretRow) -> Self {
+        let environment = Some(row.environment).filter(|environment| !environment.is_empty());
+        Self { key: row.key, environment, created_at: row.created_at, updated_at: row.updated_at }
+    }
+}
+
+/// `GET /workflows/:id/secrets` — the workflow's secrets in every
+/// environment, without values.
+pub async fn list(Path(id): Path<Uuid>, State(state): State<AppState>) -> Result<Json<Vec<SecretDto>>, StatusCode> {
+    workflow_exists(&state, id).await?;
+    match secret_repo::list_secrets(&state.pool, id).await {
+        Ok(rows) => Ok(Json(rows.into_iter().map(SecretDto::from).collect())),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// `POST /workflows/:id/secrets` — add a secret; 409 when the workflow
+/// already has one with that key in that environment.
+pub async fn create(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+    Payload(payload): Payload<CreateSecretDto>,
+) -> Response {
+    let Some(secrets_key) = &state.secrets else { return no_secrets_key() };
+    if let Some(refused) = refuse(&payload) {
+        return refused;
+    }
+    let environment = payload.environment.as_deref().unwrap_or_default();
+    if let Err(status) = workflow_exists(&state, id).await {
+        return status.into_response();
+    }
+    let sealed = secrets_key.encrypt(id, &payload.key, &payload.value);
+    let row = match secret_repo::create_secret(&state.pool, id, &payload.key, environment, &sealed).await {
+        Ok(Some(row)) => row,
+        Ok(None) => {
+            let message = match environment {
+                "" => format!("the workflow already has secret '{}'", payload.key),
+                environment => format!("the workflow already has secret '{}' in '{environment}'", payload.key),
+            };
+            return (StatusCode::CONFLICT, Json(json!({ "error": message }))).into_response();
+        }
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    if let Err(status) = audit(&state, identity, "secret.create", ("workflow", id), &row.key, environment).await {
+        return status.into_response();
+    }
+    (StatusCode::CREATED, Json(SecretDto::from(row))).into_response()
+}
+
+/// `PUT /workflows/:id/secrets/:key?environment=` — replace a secret's
+/// value.
+pub async fn update(
+    Path((id, key)): Path<(Uuid, String)>,
+    Query(query): Query<EnvironmentQuery>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+    Payload(payload): Payload<UpdateSecretDto>,
+) -> Response {
+    let Some(secrets_key) = &state.secrets else { return no_secrets_key() };
+    let environment = query.environment.as_deref().unwrap_or_default();
+    let sealed = secrets_key.encrypt(id, &key, &payload.value);
+    let row = match secret_repo::update_secret(&state.pool, id, &key, environment, &sealed).await {
+        Ok(row) => row,
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND.into_response(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    if let Err(status) = audit(&state, identity, "secret.update", ("workflow", id), &key, environment).await {
+        return status.into_response();
+    }
+    Json(SecretDto::from(row)).into_response()
+}
+
+/// `DELETE /workflows/:id/secrets/:key?environment=`
+pub async fn delete(
+    Path((id, key)): Path<(Uuid, String)>,
+    Query(query): Query<EnvironmentQuery>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+) -> StatusCode {
+    let environment = query.environment.as_deref().unwrap_or_default();
+    match secret_repo::delete_secret(&state.pool, id, &key, environment).await {
+        Ok(()) => {}
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+    }
+    match audit(&state, identity, "secret.delete", ("workflow", id), &key, environment).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(status) => status,
+    }
+}
+
+/// `GET /secrets` — the project's secrets in every environment, without
+/// values.
+pub async fn list_project(
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+) -> Result<Json<Vec<SecretDto>>, StatusCode> {
+    match secret_repo::list_project_secrets(&state.pool, project.id).await {
+        Ok(rows) => Ok(Json(rows.into_iter().map(SecretDto::from).collect())),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// `POST /secrets` — add a secret to the project; 409 when it already has
+/// one with that key in that environment.
+pub async fn create_project(
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+    identity: Option<Extension<Identity>>,
+    Payload(payload): Payload<CreateSecretDto>,
+) -> Response {
+    let Some(secrets_key) = &state.secrets else { return no_secrets_key() };
+    if let Some(refused) = refuse(&payload) {
+        return refused;
+    }
+    let environment = payload.environment.as_deref().unwrap_or_default();
+    let sealed = secrets_key.encrypt(project.id, &payload.key, &payload.value);
+    let row = match secret_repo::create_project_secret(&state.pool, project.id, &payload.key, environment, &sealed).await
+    {
+        Ok(Some(row)) => row,
+        Ok(None) => {
+            let message = match environment {
+                "" => format!("the project already has secret '{}'", payload.key),
+                environment => format!("the project already has secret '{}' in '{environment}'", payload.key),
+            };
+            return (StatusCode::CONFLICT, Json(json!({ "error": message }))).into_response();
+        }
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    if let Err(status) = audit(&state, identity, "secret.create", ("project", project.id), &row.key, environment).await {
+        return status.into_response();
+    }
+    (StatusCode::CREATED, Json(SecretDto::from(row))).into_response()
+}
+
+/// `PUT /secrets/:key?environment=` — replace a project secret's value.
+pub async fn update_project(
+    Path(key): Path<String>,
+    Query(query): Query<EnvironmentQuery>,
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+    identity: Option<Extension<Identity>>,
+    Payload(payload): Payload<UpdateSecretDto>,
+) -> Response {
+    let Some(secrets_key) = &state.secrets else { return no_secrets_key() };
+    let environment = query.environment.as_deref().unwrap_or_default();
+    let sealed = secrets_key.encrypt(project.id, &key, &payload.value);
+    let row = match secret_repo::update_project_secret(&state.pool, project.id, &key, environment, &sealed).await {
+        Ok(row) => row,
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND.into_response(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    if let Err(status) = audit(&state, identity, "secret.update", ("project", project.id), &key, environment).await {
+        return status.into_response();
+    }
+    Json(SecretDto::from(row)).into_response()
+}
+
+/// `DELETE /secrets/:key?environment=`
+pub async fn delete_project(
+    Path(key): Path<String>,
+    Query(query): Query<EnvironmentQuery>,
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+    identity: Option<Extension<Identity>>,
+) -> StatusCode {
+    let environment = query.environment.as_deref().unwrap_or_default();
+    match secret_repo::delete_project_secret(&state.pool, project.id, &key, environment).await {
+        Ok(()) => {}
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+    }
+    match audit(&state, identity, "secret.delete", ("project", project.id), &key, environment).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(status) => status,
+    }
+}
+
+/// A decrypted secret on its way to another workflow.
+pub(crate) struct SecretCopy {
+    pub key: String,
+    pub environment: String,
+    pub value: String,
+}
+
+/// Workflow `id`'s secrets in every environment, decrypted, for copying
+/// them to another workflow with [`store_copies`].
+pub(crate) async fn read_for_copy(
+    state: &AppState,
+    secrets_key: &SecretsKey,
+    id: Uuid,
+) -> Result<Vec<SecretCopy>, StatusCode> {
+    let rows = secret_repo::list_secrets(&state.pool, id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    rows.into_iter()
+        .map(|row| match secrets_key.decrypt(id, &row.key, &row.encrypted_value) {
+            Ok(value) => Ok(SecretCopy { key: row.key, environment: row.environment, value }),
+            Err(e) => {
+                tracing::error!("Secret '{}' of workflow {id} cannot be decrypted: {e}", row.key);
+                Err(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        })
+        .collect()
+}
+
+/// Add `secrets` (read with [`read_for_copy`]) to workflow `id`, encrypted
+/// for it, auditing each with the workflow it came `from`.
+pub(crate) async fn store_copies(
+    state: &AppState,
+    identity: Option<Extension<Identity>>,
+    from: Uuid,
+    id: Uuid,
+    secrets: &[SecretCopy],
+) -> Result<(), StatusCode> {
+    let Some(secrets_key) = &state.secrets else { return Ok(()) };
+    let actor = identity.map_or_else(|| "anonymous".to_owned(), |Extension(identity)| identity.subject);
+    for secret in secrets {
+        let sealed = secrets_key.encrypt(id, &secret.key, &secret.value);
+        match secret_repo::create_secret(&state.pool, id, &secret.key, &secret.environment, &sealed).await {
+            Ok(_) => {}
+            Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+        let mut details = json!({ "key": secret.key, "copied_from": from });
+        if !secret.environment.is_empty() {
+            details["environment"] = json!(secret.environment);
+        }
+        if audit_repo::record(&state.pool, &actor, "secret.copy", "workflow", Some(id), details).await.is_err() {
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn no_secrets_key() -> Response {
+    let message = "no secrets key is configured (serve --secrets-key)";
+    (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": message }))).into_response()
+}
+
+fn valid_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= 128 && key.chars().all(|c| c.is_ascii_alphanumeric() || "_-.".contains(c))
+}
+
+/// Empty (the default) or a name like a key's, at most 64 characters.
+fn valid_environment(environment: &str) -> bool {
+    environment.is_empty() || (environment.len() <= 64 && valid_key(environment))
+}
+
+/// 422 for a secret with an invalid key or environment.
+fn refuse(payload: &CreateSecretDto) -> Option<Response> {
+    let message = if !valid_key(&payload.key) {
+        "secret keys are letters, digits, '_', '-', and '.', at most 128 of them"
+    } else if !valid_environment(payload.environment.as_deref().unwrap_or_default()) {
+        "environments are letters, digits, '_', '-', and '.', at most 64 of them"
+    } else {
+        return None;
+    };
+    Some((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": message }))).into_response())
+}
+
+async fn workflow_exists(state: &AppState, id: Uuid) -> Result<(), StatusCode> {
+    match wf_repo::workflow_project(&state.pool, id).await {
+        Ok(_) => Ok(()),
+        Err(db::DbError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+async fn audit(
+    state: &AppState,
+    identity: Option<Extension<Identity>>,
+    action: &str,
+    (target, target_id): (&str, Uuid),
+    key: &str,
+    environment: &str,
+) -> Result<(), StatusCode> {
+    let actor = identity.map_or_else(|| "anonymous".to_owned(), |Extension(identity)| identity.subject);
+    let mut details = json!({ "key": key });
+    if !environment.is_empty() {
+        details["environment"] = json!(environment);
+    }
+    match audit_repo::record(&state.pool, &actor, action, target, Some(target_id), details).await {
+        Ok(_) => Ok(()),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
@@ -0,0 +1,121 @@
+//! Support access mode — read-only views of any workflow or execution for
+//! the hosting team, with secrets redacted.
+//!
+//! The endpoints only exist while the global `support_access` feature flag
+//! is on (`serve --feature support_access`, or a stored override);
+//! otherwise they answer 404.  Every request must give the reason
+//! (`X-Support-Reason`, e.g. a ticket number) and is written to the audit
+//! log under the caller's identity before any data is returned — if the
+//! audit entry cannot be written, nothing is shown.  An `X-Support-Actor`
+//! header is kept with the reason; only while authentication is off does
+//! it name the actor, and is then required.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use crate::auth::{self, Identity};
+use crate::AppState;
+use db::repository::{audit as audit_repo, executions as exec_repo, workflows as wf_repo};
+use engine::flags::{self, FlagScope};
+use engine::privacy::redact_secrets;
+
+/// Who is looking and why: the caller, and the reason (with any staff
+/// member named) from the request headers.
+struct SupportAccess {
+    actor: String,
+    reason: String,
+    named: Option<String>,
+}
+
+fn support_access(
+    state: &AppState,
+    identity: Option<Extension<Identity>>,
+    headers: &HeaderMap,
+) -> Result<SupportAccess, StatusCode> {
+    if !state.flags.is_enabled(flags::SUPPORT_ACCESS, &FlagScope::global()) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_owned)
+    };
+    let named = header("x-support-actor");
+    match (auth::actor(identity, named.as_deref()), header("x-support-reason")) {
+        (Some(actor), Some(reason)) => Ok(SupportAccess { actor, reason, named }),
+        _ => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+async fn audit(
+    state: &AppState,
+    access: &SupportAccess,
+    action: &str,
+    target_type: &str,
+    target_id: Uuid,
+) -> Result<(), StatusCode> {
+    let details = match &access.named {
+        Some(named) => json!({ "reason": access.reason, "support_actor": named }),
+        None => json!({ "reason": access.reason }),
+    };
+    match audit_repo::record(&state.pool, &access.actor, action, target_type, Some(target_id), details).await {
+        Ok(_) => Ok(()),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// `GET /support/workflows/:id` — a workflow with its definition redacted.
+pub async fn workflow(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+) -> Result<Json<db::models::WorkflowRow>, StatusCode> {
+    let access = support_access(&state, identity, &headers)?;
+    audit(&state, &access, "support.view_workflow", "workflow", id).await?;
+
+    let mut wf = match wf_repo::get_workflow(&state.pool, id).await {
+        Ok(w) => w,
+        Err(db::DbError::NotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    redact_secrets(&mut wf.definition);
+    Ok(Json(wf))
+}
+
+/// `GET /support/executions/:id` — an execution and its node records, with
+/// secrets redacted from every input and output.
+pub async fn execution(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    let access = support_access(&state, identity, &headers)?;
+    audit(&state, &access, "support.view_execution", "execution", id).await?;
+
+    let exec = match exec_repo::get_execution(&state.pool, id).await {
+        Ok(e) => e,
+        Err(db::DbError::NotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let mut node_execs = match exec_repo::list_node_executions(&state.pool, id).await {
+        Ok(rows) => rows,
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    for node in &mut node_execs {
+        redact_secrets(&mut node.input);
+        if let Some(output) = node.output.as_mut() {
+            redact_secrets(output);
+        }
+    }
+
+    Ok(Json(json!({ "execution": exec, "node_executions": node_execs })))
+}
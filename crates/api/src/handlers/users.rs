@@ -0,0 +1,95 @@
+//! Users, their roles, and the caller's own identity.
+//!
+//! Managing users is for admins; every change is audited with the admin's
+//! subject as the actor.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde_json::{json, Value};
+use crate::auth::Identity;
+use crate::rbac::Role;
+use crate::AppState;
+use crate::limits::Payload;
+use db::repository::{audit as audit_repo, users as user_repo};
+
+#[derive(serde::Deserialize)]
+pub struct SetUserDto {
+    pub role: Role,
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+/// `GET /me` — who the caller is authenticated as, and their role.
+pub async fn me(identity: Option<Extension<Identity>>, role: Option<Extension<Role>>) -> Json<Value> {
+    let identity = identity.map(|Extension(identity)| identity);
+    Json(json!({ "identity": identity, "role": role.map(|Extension(role)| role) }))
+}
+
+/// `GET /users` — every user and their role.
+pub async fn list(State(state): State<AppState>) -> Result<Json<Vec<db::models::UserRow>>, StatusCode> {
+    match user_repo::list_users(&state.pool).await {
+        Ok(users) => Ok(Json(users)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// `PUT /users/:subject` — add a user or change their role.
+pub async fn set(
+    Path(subject): Path<String>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+    Payload(payload): Payload<SetUserDto>,
+) -> Result<Json<db::models::UserRow>, StatusCode> {
+    let subject = subject.trim();
+    if subject.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let user = match user_repo::upsert_user(
+        &state.pool,
+        subject,
+        payload.role.as_str(),
+        payload.name.as_deref(),
+        payload.email.as_deref(),
+    )
+    .await
+    {
+        Ok(user) => user,
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    audit(&state, identity, "user.set_role", json!({ "subject": subject, "role": user.role })).await?;
+    Ok(Json(user))
+}
+
+/// `DELETE /users/:subject` — remove a user; they fall back to the
+/// default role.
+pub async fn delete(
+    Path(subject): Path<String>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+) -> StatusCode {
+    match user_repo::delete_user(&state.pool, &subject).await {
+        Ok(()) => {}
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+    }
+    match audit(&state, identity, "user.remove", json!({ "subject": subject })).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(status) => status,
+    }
+}
+
+async fn audit(
+    state: &AppState,
+    identity: Option<Extension<Identity>>,
+    action: &str,
+    details: Value,
+) -> Result<(), StatusCode> {
+    let actor = identity.map_or_else(|| "anonymous".to_owned(), |Extension(identity)| identity.subject);
+    match audit_repo::record(&state.pool, &actor, action, "user", None, details).await {
+        Ok(_) => Ok(()),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
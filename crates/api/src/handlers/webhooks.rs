@@ -1,51 +1,210 @@
 use axum::{
+    body::Bytes,
     extract::{Path, State},
-    http::StatusCode,
+    http::{header, HeaderMap, Method, StatusCode, Uri},
     Json,
 };
-use serde_json::Value;
-use super::AppState;
-use db::repository::{executions as exec_repo, jobs as job_repo, workflows as wf_repo};
-use engine::Workflow;
+use serde_json::{json, Map, Value};
+use crate::AppState;
+use crate::limits::RawBody;
+use db::repository::{captures as capture_repo, workflows as wf_repo};
+use engine::triggers::{self, Admission, Outcome};
+use engine::{SyncResponse, Workflow};
 
+/// `ANY /webhook/:path` — start the workflow whose webhook trigger has
+/// `path`.
+///
+/// The execution's input describes the whole request:
+///
+/// ```json
+/// {
+///   "method": "POST",
+///   "path": "/webhook/orders",
+///   "query": { "source": "shop" },
+///   "headers": { "content-type": "application/json", "x-signature": "..." },
+///   "body": { "order_id": 42 }
+/// }
+/// ```
+///
+/// JSON bodies are parsed, form-encoded bodies become an object, and any
+/// other body is passed as text; an empty body is `null`.  A body without a
+/// content type is parsed as JSON when it is valid JSON.  Header names are
+/// lowercase.  A query parameter or header that occurs more than once
+/// becomes an array of its values.
+///
+/// `params.NAME` query parameters set the workflow's parameter `NAME` for
+/// the run; unknown parameters and values of the wrong type are a `422`.
+///
+/// With a `sync` trigger option the request waits for the execution (run by
+/// a worker) and is answered with its final output; see [`SyncResponse`].
+///
+/// While a capture is armed (see [`super::captures`]), the request is
+/// stored as the workflow's sample instead and answered `200`.
 pub async fn handle_webhook(
     Path(path): Path<String>,
     State(state): State<AppState>,
-    Json(payload): Json<Value>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    RawBody(body): RawBody,
 ) -> Result<(StatusCode, Json<Value>), StatusCode> {
-    // 1. Find workflow by webhook path
-    let workflows = match wf_repo::list_workflows(&state.pool).await {
-        Ok(wfs) => wfs,
+    let payload = request_input(&method, &uri, &headers, &body)?;
+
+    // 1. Find workflow by webhook path; an active one wins over inactive ones.
+    let row = match wf_repo::find_workflow_by_webhook_path(&state.pool, &path).await {
+        Ok(Some(row)) => row,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
         Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
     };
+    let (workflow_id, active) = (row.id, row.active);
+    let workflow: Workflow = match serde_json::from_value(row.definition) {
+        Ok(workflow) => workflow,
+        Err(_) => return Err(StatusCode::NOT_FOUND),
+    };
 
-    let matched_wf = workflows.into_iter().find(|w| {
-        let wf: Result<Workflow, _> = serde_json::from_value(w.definition.clone());
-        if let Ok(workflow) = wf {
-            if let engine::Trigger::Webhook { path: trigger_path } = &workflow.trigger {
-                if trigger_path == &path {
-                    return true;
-                }
-            }
+    // An armed capture takes the request instead of an execution, even for
+    // an inactive workflow.
+    match capture_repo::capture_payload(&state.pool, workflow_id, payload.clone()).await {
+        Ok(Some(capture)) => {
+            return Ok((
+                StatusCode::OK,
+                Json(json!({ "message": "payload captured", "workflow_id": workflow_id, "sample": capture.payload })),
+            ))
         }
-        false
-    });
+        Ok(None) => {}
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    // Inactive workflows do not answer their webhooks.
+    if !active {
+        return Err(StatusCode::NOT_FOUND);
+    }
 
-    let wf_row = match matched_wf {
-        Some(w) => w,
-        None => return Err(StatusCode::NOT_FOUND),
+    // `params.NAME` query parameters override the workflow's parameters.
+    let params = match webhook_params(&workflow, &uri)? {
+        Ok(params) => params,
+        Err(e) => return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": e })))),
     };
 
-    // 2. Trigger execution
-    let exec = match exec_repo::create_execution(&state.pool, wf_row.id).await {
-        Ok(e) => e,
-        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    // 2. Trigger execution, subject to the trigger's throttle/debounce options
+    let admission =
+        match triggers::admit_with_params(&state.pool, state.queue.as_ref(), workflow_id, &workflow, payload, params)
+            .await
+        {
+            Ok(a) => a,
+            Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+        };
+
+    match admission {
+        Admission::Enqueued(job) => match workflow.trigger.sync_response() {
+            Some(sync) => respond_with_result(&state, job.execution_id, &job.payload, sync).await,
+            None => Ok((StatusCode::ACCEPTED, Json(serde_json::json!({"message": "webhook accepted"})))),
+        },
+        Admission::Debounced(job) => Ok((
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({"message": "webhook debounced", "run_at": job.run_at})),
+        )),
+        Admission::Throttled => Err(StatusCode::TOO_MANY_REQUESTS),
+        Admission::OverQuota(reason) => {
+            Ok((StatusCode::TOO_MANY_REQUESTS, Json(json!({ "error": format!("quota exceeded: {reason}") }))))
+        }
+    }
+}
+
+/// The parameter overrides in `uri`'s query, checked against the workflow's
+/// declared parameters.
+fn webhook_params(workflow: &Workflow, uri: &Uri) -> Result<Result<Map<String, Value>, String>, StatusCode> {
+    let query: Vec<(String, String)> = match uri.query() {
+        Some(query) => serde_urlencoded::from_str(query).map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => Vec::new(),
     };
+    let pairs = query.iter().map(|(key, value)| (key.as_str(), value.as_str()));
+    Ok(engine::params::from_pairs(&workflow.params, pairs)
+        .and_then(|overrides| engine::params::resolve(&workflow.params, &overrides).map(|_| overrides)))
+}
 
-    let _job = match job_repo::enqueue_job(&state.pool, exec.id, wf_row.id, payload.clone()).await {
-        Ok(j) => j,
-        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+/// Wait for the execution of a synchronous webhook and answer with its
+/// final output: `200` on success, `500` on failure, and `202` when it is
+/// still running at the timeout.
+async fn respond_with_result(
+    state: &AppState,
+    execution_id: uuid::Uuid,
+    input: &Value,
+    sync: &SyncResponse,
+) -> Result<(StatusCode, Json<Value>), StatusCode> {
+    let outcome = triggers::wait_for(&state.pool, execution_id, input, sync.timeout())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(match outcome {
+        Outcome::Succeeded(output) => (StatusCode::OK, Json(output)),
+        Outcome::Failed { node_id } => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "message": "execution failed", "execution_id": execution_id, "node_id": node_id })),
+        ),
+        Outcome::Unfinished => (
+            StatusCode::ACCEPTED,
+            Json(json!({ "message": "webhook accepted", "execution_id": execution_id })),
+        ),
+    })
+}
+
+/// The execution input for a webhook request; `400` for a malformed JSON
+/// or form body, `415` for a body that is not UTF-8 text.
+fn request_input(method: &Method, uri: &Uri, headers: &HeaderMap, body: &Bytes) -> Result<Value, StatusCode> {
+    let query: Vec<(String, String)> = match uri.query() {
+        Some(query) => serde_urlencoded::from_str(query).map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => Vec::new(),
     };
+    let header_pairs = headers
+        .iter()
+        .filter_map(|(name, value)| Some((name.as_str().to_owned(), value.to_str().ok()?.to_owned())));
+
+    Ok(json!({
+        "method": method.as_str(),
+        "path": uri.path(),
+        "query": collect_pairs(query),
+        "headers": collect_pairs(header_pairs),
+        "body": parse_body(headers, body)?,
+    }))
+}
+
+/// Name/value pairs as an object; repeated names collect into an array.
+fn collect_pairs(pairs: impl IntoIterator<Item = (String, String)>) -> Value {
+    let mut object = Map::new();
+    for (name, value) in pairs {
+        match object.get_mut(&name) {
+            None => {
+                object.insert(name, Value::String(value));
+            }
+            Some(Value::Array(values)) => values.push(Value::String(value)),
+            Some(first) => *first = json!([first.take(), value]),
+        }
+    }
+    Value::Object(object)
+}
+
+fn parse_body(headers: &HeaderMap, body: &Bytes) -> Result<Value, StatusCode> {
+    if body.is_empty() {
+        return Ok(Value::Null);
+    }
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|v| v.trim().to_ascii_lowercase())
+        .unwrap_or_default();
 
-    Ok((StatusCode::ACCEPTED, Json(serde_json::json!({"message": "webhook accepted"}))))
+    if content_type == "application/json" || content_type.ends_with("+json") {
+        serde_json::from_slice(body).map_err(|_| StatusCode::BAD_REQUEST)
+    } else if content_type == "application/x-www-form-urlencoded" {
+        let fields: Vec<(String, String)> =
+            serde_urlencoded::from_bytes(body).map_err(|_| StatusCode::BAD_REQUEST)?;
+        Ok(collect_pairs(fields))
+    } else if let (true, Ok(value)) = (content_type.is_empty(), serde_json::from_slice(body)) {
+        // No declared type: JSON if it parses, text otherwise.
+        Ok(value)
+    } else {
+        let text = std::str::from_utf8(body).map_err(|_| StatusCode::UNSUPPORTED_MEDIA_TYPE)?;
+        Ok(Value::String(text.to_owned()))
+    }
 }
@@ -1,53 +1,558 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    Json,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
-use serde_json::Value;
+use serde_json::{json, Value};
 use uuid::Uuid;
-use super::AppState;
-use db::repository::workflows as wf_repo;
-use engine::Workflow;
+use crate::auth::Identity;
+use crate::handlers::secrets;
+use crate::limits::{self, Payload};
+use crate::AppState;
+use db::models::{ProjectRow, WorkflowFilter};
+use db::repository::{executions as exec_repo, quotas as quota_repo, workflows as wf_repo};
+use chrono::Utc;
+use engine::scheduler::CronSchedule;
+use engine::bundle::{self, WorkflowBundle};
+use engine::{determinism, EngineError, Workflow};
 
 #[derive(serde::Deserialize)]
 pub struct CreateWorkflowDto {
     pub name: String,
     pub definition: Value,
+    /// Whether triggers start the workflow (default `true`).
+    pub active: Option<bool>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Who is creating it, recorded with version 1 (authenticated callers
+    /// are recorded as themselves).
+    pub author: Option<String>,
 }
 
-pub async fn list(State(state): State<AppState>) -> Result<Json<Vec<db::models::WorkflowRow>>, StatusCode> {
-    match wf_repo::list_workflows(&state.pool).await {
-        Ok(workflows) => Ok(Json(workflows)),
+#[derive(serde::Deserialize)]
+pub struct UpdateWorkflowDto {
+    /// New name; the current one is kept when omitted.
+    pub name: Option<String>,
+    pub definition: Value,
+    pub author: Option<String>,
+    /// The version being replaced, unless `If-Match` names it.
+    pub version: Option<i32>,
+}
+
+#[derive(serde::Deserialize, Default)]
+pub struct RollbackDto {
+    pub author: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ImportWorkflowDto {
+    #[serde(flatten)]
+    pub bundle: WorkflowBundle,
+    pub author: Option<String>,
+}
+
+#[derive(serde::Deserialize, Default)]
+pub struct CloneWorkflowDto {
+    /// Name of the copy (default: the original's, with " (copy)").
+    pub name: Option<String>,
+    /// Whether triggers start the copy (default `false`).
+    pub active: Option<bool>,
+    /// Copy the workflow's secrets too (default `false`).
+    #[serde(default)]
+    pub copy_secrets: bool,
+    pub author: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct SetTagsDto {
+    pub tags: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct SetActiveDto {
+    pub active: bool,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ListWorkflowsQuery {
+    /// Case-insensitive substring of the name.
+    pub name: Option<String>,
+    /// The trigger's `type`, e.g. `webhook`.
+    pub trigger: Option<String>,
+    pub active: Option<bool>,
+    pub tag: Option<String>,
+    /// Full-text search over names and definitions.
+    pub q: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// Return full rows with definitions instead of summaries.
+    #[serde(default)]
+    pub full: bool,
+}
+
+/// `GET /workflows?limit=50&offset=0` — a page of workflows, newest first,
+/// optionally filtered by `name` (substring), `trigger` type, `active`, and
+/// `tag`.  `q` searches names and definitions — node types, URLs,
+/// descriptions — in web search syntax and lists the best matches first.
+///
+/// Entries are summaries (id, name, trigger type, active, created_at)
+/// unless `full=true` asks for the definitions too.
+pub async fn list(
+    Query(query): Query<ListWorkflowsQuery>,
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+) -> Result<Json<Value>, StatusCode> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let filter = WorkflowFilter {
+        name: query.name.filter(|n| !n.is_empty()),
+        trigger_type: query.trigger,
+        active: query.active,
+        tag: query.tag.map(|t| normalize_tag(&t)).filter(|t| !t.is_empty()),
+        project_id: Some(project.id),
+        search: query.q.map(|q| q.trim().to_owned()).filter(|q| !q.is_empty()),
+    };
+
+    // One extra row tells us whether there is a next page.
+    let (workflows, count) = if query.full {
+        let mut rows = wf_repo::list_workflows_page(&state.read_pool, &filter, limit + 1, offset)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let count = rows.len();
+        rows.truncate(limit as usize);
+        (json!(rows), count)
+    } else {
+        let mut rows = wf_repo::list_workflow_summaries(&state.read_pool, &filter, limit + 1, offset)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let count = rows.len();
+        rows.truncate(limit as usize);
+        (json!(rows), count)
+    };
+
+    Ok(Json(json!({
+        "workflows": workflows,
+        "limit": limit,
+        "offset": offset,
+        "next_offset": (count as i64 > limit).then_some(offset + limit),
+    })))
+}
+
+/// `GET /workflows/:id` — the workflow, with its version as the `ETag`
+/// that updates send back in `If-Match`.
+pub async fn get(Path(id): Path<Uuid>, State(state): State<AppState>) -> Response {
+    match wf_repo::get_workflow(&state.read_pool, id).await {
+        Ok(wf) => versioned(wf),
+        Err(db::DbError::NotFound) => StatusCode::NOT_FOUND.into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+    identity: Option<Extension<Identity>>,
+    Payload(payload): Payload<CreateWorkflowDto>,
+) -> Response {
+    if let Err(invalid) = check_definition(&payload.definition) {
+        return invalid.into_response();
+    }
+
+    let active = payload.active.unwrap_or(true);
+    let tags = normalize_tags(&payload.tags);
+    let author = author(identity, payload.author);
+    let created =
+        wf_repo::create_workflow(&state.pool, &payload.name, payload.definition, active, &tags, author.as_deref(), project.id)
+            .await;
+    match created {
+        Ok(wf) => (StatusCode::CREATED, Json(wf)).into_response(),
+        Err(db::DbError::WebhookPathTaken) => path_taken(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+/// 409 for a webhook path that another active workflow answers.
+fn path_taken() -> Response {
+    let body = json!({ "error": db::DbError::WebhookPathTaken.to_string() });
+    (StatusCode::CONFLICT, Json(body)).into_response()
+}
+
+/// `PUT /workflows/:id` — store a new definition as the workflow's next
+/// version.  An invalid definition is a 422, like on creation, and a
+/// webhook path another active workflow answers is a 409.
+///
+/// The version being replaced must be named, in `If-Match` (the `ETag` of
+/// `GET /workflows/:id`) or the body's `version`; without it the update is
+/// a 428, and when the workflow has moved past it a 409 with the current
+/// version, so two editors never silently overwrite each other.
+pub async fn update(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+    Payload(payload): Payload<UpdateWorkflowDto>,
+) -> Response {
+    let expected = match if_match(&headers) {
+        Ok(Some(version)) => version,
+        Ok(None) => match payload.version {
+            Some(version) => Some(version),
+            None => return version_required(),
+        },
+        Err(invalid) => return invalid.into_response(),
+    };
+    if let Err(invalid) = check_definition(&payload.definition) {
+        return invalid.into_response();
+    }
+    let name = match payload.name {
+        Some(name) => name,
+        None => match wf_repo::get_workflow(&state.pool, id).await {
+            Ok(wf) => wf.name,
+            Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND.into_response(),
+            Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        },
+    };
+    let author = author(identity, payload.author);
+    store_version(&state, id, &name, payload.definition, author.as_deref(), expected).await
+}
+
+/// `GET /workflows/:id/versions` — every stored definition, newest first.
+pub async fn versions(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<db::models::WorkflowVersionRow>>, StatusCode> {
+    match wf_repo::list_workflow_versions(&state.read_pool, id).await {
+        Ok(versions) if versions.is_empty() => Err(StatusCode::NOT_FOUND),
+        Ok(versions) => Ok(Json(versions)),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
-pub async fn get(
+/// `POST /workflows/:id/rollback/:version` — store the definition (and
+/// name) of an earlier version as the next version; with `If-Match`, only
+/// if the workflow is still at that version (409 otherwise).
+pub async fn rollback(
+    Path((id, version)): Path<(Uuid, i32)>,
+    State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+    payload: Option<Payload<RollbackDto>>,
+) -> Response {
+    let expected = match if_match(&headers) {
+        Ok(expected) => expected.flatten(),
+        Err(invalid) => return invalid.into_response(),
+    };
+    let Payload(payload) = payload.unwrap_or_default();
+    let old = match wf_repo::get_workflow_version(&state.pool, id, version).await {
+        Ok(v) => v,
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND.into_response(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let author = author(identity, payload.author);
+    store_version(&state, id, &old.name, old.definition, author.as_deref(), expected).await
+}
+
+/// The author to record: the authenticated caller when there is one,
+/// otherwise whoever the request names.
+fn author(identity: Option<Extension<Identity>>, named: Option<String>) -> Option<String> {
+    identity.map(|Extension(identity)| identity.subject).or(named)
+}
+
+async fn store_version(
+    state: &AppState,
+    id: Uuid,
+    name: &str,
+    definition: Value,
+    author: Option<&str>,
+    expected_version: Option<i32>,
+) -> Response {
+    match wf_repo::update_workflow(&state.pool, id, name, definition, author, expected_version).await {
+        Ok(wf) => versioned(wf),
+        Err(db::DbError::NotFound) => StatusCode::NOT_FOUND.into_response(),
+        Err(db::DbError::WebhookPathTaken) => path_taken(),
+        Err(conflict @ db::DbError::VersionConflict(current)) => {
+            let body = json!({ "error": conflict.to_string(), "version": current });
+            (StatusCode::CONFLICT, Json(body)).into_response()
+        }
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+/// `wf` as JSON, with its version as the `ETag`.
+fn versioned(wf: db::models::WorkflowRow) -> Response {
+    let etag = HeaderValue::from_str(&format!("\"{}\"", wf.version)).expect("a quoted number is a valid header");
+    ([(header::ETAG, etag)], Json(wf)).into_response()
+}
+
+/// The version `If-Match` names: `Ok(None)` without the header, and
+/// `Ok(Some(None))` for `*` (any version).  Weak tags (`W/"3"`) count.
+fn if_match(headers: &HeaderMap) -> Result<Option<Option<i32>>, (StatusCode, Json<Value>)> {
+    let Some(value) = headers.get(header::IF_MATCH) else { return Ok(None) };
+    let value = value.to_str().unwrap_or_default().trim();
+    if value == "*" {
+        return Ok(Some(None));
+    }
+    let tag = value.strip_prefix("W/").unwrap_or(value).trim_matches('"');
+    match tag.parse() {
+        Ok(version) => Ok(Some(Some(version))),
+        Err(_) => {
+            let body = json!({ "error": "If-Match must name a workflow version, e.g. \"3\"" });
+            Err((StatusCode::BAD_REQUEST, Json(body)))
+        }
+    }
+}
+
+/// 428 for an update that does not say which version it replaces.
+fn version_required() -> Response {
+    let body = json!({ "error": "name the version being replaced in If-Match or the body's version" });
+    (StatusCode::PRECONDITION_REQUIRED, Json(body)).into_response()
+}
+
+/// `GET /workflows/:id/export` — the workflow as a portable bundle, naming
+/// the secrets it reads without their values.
+pub async fn export(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<Json<WorkflowBundle>, StatusCode> {
+    let wf = match wf_repo::get_workflow(&state.pool, id).await {
+        Ok(wf) => wf,
+        Err(db::DbError::NotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    match bundle::export(&wf, state.automation.registry()) {
+        Ok(bundle) => Ok(Json(bundle)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// `POST /workflows/import` — create a workflow from an exported bundle, or
+/// store it as the next version of the workflow with the same name.
+///
+/// The reply names the secrets the workflow reads, which have to be set up
+/// in this environment.  An invalid bundle is a 422; several workflows
+/// with the bundle's name are a 409.
+pub async fn import(
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+    identity: Option<Extension<Identity>>,
+    Payload(payload): Payload<ImportWorkflowDto>,
+) -> Response {
+    let ImportWorkflowDto { bundle, author: named } = payload;
+    let author = author(identity, named);
+    let secret_keys = match bundle::check(&bundle) {
+        Ok(workflow) => bundle::secret_keys(&workflow, state.automation.registry()),
+        Err(e) => {
+            let body = json!({ "error": e.to_string() });
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response();
+        }
+    };
+    let tags = normalize_tags(&bundle.tags);
+
+    match bundle::import(&state.pool, project.id, bundle, &tags, author.as_deref()).await {
+        Ok((wf, created)) => {
+            let status = if created { StatusCode::CREATED } else { StatusCode::OK };
+            (status, Json(json!({ "workflow": wf, "secret_keys": secret_keys }))).into_response()
+        }
+        Err(EngineError::Database(db::DbError::WebhookPathTaken)) => path_taken(),
+        Err(conflict @ (EngineError::AmbiguousWorkflowName(_) | EngineError::Database(db::DbError::VersionConflict(_)))) => {
+            (StatusCode::CONFLICT, Json(json!({ "error": conflict.to_string() }))).into_response()
+        }
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+/// `POST /workflows/:id/clone` — a new workflow with the definition and
+/// tags of this one, inactive unless asked otherwise, to iterate on a
+/// working automation without touching it.
+///
+/// With `copy_secrets` the copy gets the same secrets (503 without a
+/// secrets key); the reply names the ones copied.
+pub async fn duplicate(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+    identity: Option<Extension<Identity>>,
+    payload: Option<Payload<CloneWorkflowDto>>,
+) -> Response {
+    let Payload(payload) = payload.unwrap_or_default();
+    let original = match wf_repo::get_workflow(&state.pool, id).await {
+        Ok(wf) => wf,
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND.into_response(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let secrets = match (payload.copy_secrets, &state.secrets) {
+        (false, _) => Vec::new(),
+        (true, None) => return secrets::no_secrets_key(),
+        (true, Some(secrets_key)) => match secrets::read_for_copy(&state, secrets_key, id).await {
+            Ok(secrets) => secrets,
+            Err(status) => return status.into_response(),
+        },
+    };
+
+    let name = payload.name.unwrap_or_else(|| format!("{} (copy)", original.name));
+    let active = payload.active.unwrap_or(false);
+    let author = author(identity.clone(), payload.author);
+    let copy = match wf_repo::create_workflow(
+        &state.pool,
+        &name,
+        original.definition,
+        active,
+        &original.tags,
+        author.as_deref(),
+        project.id,
+    )
+    .await
+    {
+        Ok(wf) => wf,
+        Err(db::DbError::WebhookPathTaken) => return path_taken(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    if let Err(status) = secrets::store_copies(&state, identity, id, copy.id, &secrets).await {
+        // Leave no copy with only some of the secrets behind.
+        let _ = wf_repo::delete_workflow(&state.pool, copy.id).await;
+        return status.into_response();
+    }
+    let mut copied: Vec<&str> = secrets.iter().map(|secret| secret.key.as_str()).collect();
+    copied.dedup();
+    (StatusCode::CREATED, Json(json!({ "workflow": copy, "copied_secrets": copied }))).into_response()
+}
+
+/// Why a workflow definition was refused.
+pub(crate) struct InvalidDefinition {
+    /// The field that does not fit, e.g. `definition.nodes[0].node_type`;
+    /// `None` when the graph as a whole is at fault.
+    pub field: Option<String>,
+    pub error: String,
+}
+
+impl IntoResponse for InvalidDefinition {
+    fn into_response(self) -> Response {
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": self.error, "field": self.field }))).into_response()
+    }
+}
+
+/// Check that `definition` is a valid Workflow struct with a valid DAG.
+pub(crate) fn check_definition(definition: &Value) -> Result<(), InvalidDefinition> {
+    let workflow: Workflow = serde_path_to_error::deserialize(definition).map_err(|e| InvalidDefinition {
+        field: Some(match limits::field(e.path()) {
+            Some(field) => format!("definition.{field}"),
+            None => "definition".to_owned(),
+        }),
+        error: e.into_inner().to_string(),
+    })?;
+    engine::validate_dag(&workflow).map_err(|e| InvalidDefinition { field: None, error: e.to_string() })?;
+    Ok(())
+}
+
+/// `PUT /workflows/:id/active` — activate or deactivate a workflow.
+///
+/// Triggers skip inactive workflows: their webhooks answer 404 and their
+/// schedules, polls, and listeners stop.  `POST /workflows/:id/execute`
+/// still runs them, for testing.  Only one active workflow may answer a
+/// webhook path; activating a second is a 409.
+pub async fn set_active(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Payload(payload): Payload<SetActiveDto>,
+) -> Result<Json<db::models::WorkflowRow>, StatusCode> {
+    update_active(&state, id, payload.active).await
+}
+
+/// `POST /workflows/:id/activate` — same as setting `active` to `true`.
+pub async fn activate(
     Path(id): Path<Uuid>,
     State(state): State<AppState>,
 ) -> Result<Json<db::models::WorkflowRow>, StatusCode> {
-    match wf_repo::get_workflow(&state.pool, id).await {
+    update_active(&state, id, true).await
+}
+
+/// `POST /workflows/:id/deactivate` — pause a workflow's triggers without
+/// deleting it.
+pub async fn deactivate(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<Json<db::models::WorkflowRow>, StatusCode> {
+    update_active(&state, id, false).await
+}
+
+async fn update_active(state: &AppState, id: Uuid, active: bool) -> Result<Json<db::models::WorkflowRow>, StatusCode> {
+    match wf_repo::set_workflow_active(&state.pool, id, active).await {
         Ok(wf) => Ok(Json(wf)),
         Err(db::DbError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(db::DbError::WebhookPathTaken) => Err(StatusCode::CONFLICT),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
-pub async fn create(
+/// `PUT /workflows/:id/tags` — replace a workflow's tags.
+pub async fn set_tags(
+    Path(id): Path<Uuid>,
     State(state): State<AppState>,
-    Json(payload): Json<CreateWorkflowDto>,
-) -> Result<(StatusCode, Json<db::models::WorkflowRow>), StatusCode> {
-    // Basic validation to ensure definition is a valid Workflow struct
-    if let Err(_) = serde_json::from_value::<Workflow>(payload.definition.clone()) {
-        return Err(StatusCode::BAD_REQUEST);
+    Payload(payload): Payload<SetTagsDto>,
+) -> Result<Json<db::models::WorkflowRow>, StatusCode> {
+    match wf_repo::set_workflow_tags(&state.pool, id, &normalize_tags(&payload.tags)).await {
+        Ok(wf) => Ok(Json(wf)),
+        Err(db::DbError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
+}
+
+/// `GET /workflows/schema` — the JSON Schema of workflow definitions, for
+/// editors and linters validating workflow files.
+pub async fn schema() -> Json<Value> {
+    Json(Workflow::json_schema())
+}
 
-    match wf_repo::create_workflow(&state.pool, &payload.name, payload.definition).await {
-        Ok(wf) => Ok((StatusCode::CREATED, Json(wf))),
+/// `GET /workflows/tags` — every tag in use, with its workflow count.
+pub async fn tags(
+    State(state): State<AppState>,
+    Extension(project): Extension<ProjectRow>,
+) -> Result<Json<Vec<db::models::WorkflowTagRow>>, StatusCode> {
+    match wf_repo::list_workflow_tags(&state.read_pool, Some(project.id)).await {
+        Ok(tags) => Ok(Json(tags)),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
+/// Tags are trimmed and lowercase, so `Billing ` and `billing` are one tag.
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// `tags` normalized, without blanks or duplicates, sorted.
+pub(crate) fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = tags.iter().map(|t| normalize_tag(t)).filter(|t| !t.is_empty()).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// `POST /workflows/validate` — check a definition without storing it.
+///
+/// Reports the execution order and, for cron triggers, the next fire
+/// times; an invalid definition is a 422 with the reason.
+pub async fn validate(Payload(definition): Payload<Value>) -> (StatusCode, Json<Value>) {
+    let invalid = |error: String| (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "valid": false, "error": error })));
+    let workflow: Workflow = match serde_json::from_value(definition) {
+        Ok(w) => w,
+        Err(e) => return invalid(e.to_string()),
+    };
+    let order = match engine::validate_dag(&workflow) {
+        Ok(order) => order,
+        Err(e) => return invalid(e.to_string()),
+    };
+
+    let mut body = json!({ "valid": true, "order": order });
+    if let Some(Ok(schedule)) = CronSchedule::of(&workflow.trigger) {
+        body["timezone"] = json!(schedule.timezone());
+        body["next_runs"] = json!(schedule.upcoming(Utc::now(), NEXT_RUNS));
+    }
+    (StatusCode::OK, Json(body))
+}
+
+/// Fire times reported by [`validate`].
+const NEXT_RUNS: usize = 5;
+
 pub async fn delete(
     Path(id): Path<Uuid>,
     State(state): State<AppState>,
@@ -55,6 +560,92 @@ pub async fn delete(
     match wf_repo::delete_workflow(&state.pool, id).await {
         Ok(_) => Ok(StatusCode::NO_CONTENT),
         Err(db::DbError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(db::DbError::LegalHold) => Err(StatusCode::CONFLICT),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
+
+#[derive(serde::Deserialize)]
+pub struct StatsQuery {
+    /// Length of the window, in days up to now (default 30, at most 365).
+    pub days: Option<i64>,
+}
+
+/// `GET /workflows/:id/stats?days=30` — how the workflow's executions
+/// started in the window went: success rate (of those that finished),
+/// average and percentile durations, executions per UTC day, the node
+/// that failed most often, per-node run counts and durations from the
+/// daily rollup, the node that took the most time in total first, and the
+/// workflow's quota with what it has used of it.
+pub async fn stats(
+    Path(id): Path<Uuid>,
+    Query(query): Query<StatsQuery>,
+    State(state): State<AppState>,
+) -> Response {
+    let days = query.days.unwrap_or(30);
+    if !(1..=365).contains(&days) {
+        let body = json!({ "error": "days must be between 1 and 365" });
+        return (StatusCode::BAD_REQUEST, Json(body)).into_response();
+    }
+    let quota = match wf_repo::get_workflow(&state.pool, id).await {
+        Ok(row) => serde_json::from_value::<Workflow>(row.definition).ok().and_then(|wf| wf.quota),
+        Err(db::DbError::NotFound) => return StatusCode::NOT_FOUND.into_response(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+
+    let to = Utc::now();
+    let from = to - chrono::Duration::days(days);
+    let (summary, per_day, most_failing, nodes, usage) = match tokio::try_join!(
+        exec_repo::workflow_stats(&state.read_pool, id, from),
+        exec_repo::daily_executions(&state.read_pool, id, from),
+        exec_repo::most_failing_node(&state.read_pool, id, from),
+        exec_repo::node_stats(&state.read_pool, id, from.date_naive()),
+        quota_repo::workflow_usage(&state.read_pool, id, to),
+    ) {
+        Ok(stats) => stats,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let finished = summary.succeeded + summary.failed;
+    Json(json!({
+        "workflow_id": id,
+        "window": { "days": days, "from": from, "to": to },
+        "executions": { "total": summary.total, "succeeded": summary.succeeded, "failed": summary.failed },
+        "success_rate": (finished > 0).then(|| summary.succeeded as f64 / finished as f64),
+        "duration_ms": {
+            "avg": summary.avg_duration_ms,
+            "p50": summary.p50_duration_ms,
+            "p90": summary.p90_duration_ms,
+            "p99": summary.p99_duration_ms,
+        },
+        "per_day": per_day,
+        "most_failing_node": most_failing,
+        "bottleneck": nodes.first().map(|n| &n.node_id),
+        "nodes": nodes,
+        "quota": { "limits": quota, "usage": usage },
+    }))
+    .into_response()
+}
+
+/// `GET /workflows/:id/determinism` — constructs in the workflow that can
+/// make runs with the same input produce different results.
+pub async fn determinism(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, StatusCode> {
+    let wf_row = match wf_repo::get_workflow(&state.pool, id).await {
+        Ok(w) => w,
+        Err(db::DbError::NotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let workflow: Workflow = match serde_json::from_value(wf_row.definition) {
+        Ok(w) => w,
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let findings = determinism::analyze(&workflow);
+    Ok(Json(json!({
+        "workflow_id": id,
+        "deterministic": findings.is_empty(),
+        "findings": findings,
+    })))
+}
@@ -1,50 +1,346 @@
 //! `api` crate — HTTP REST API layer
 //!
 //! Exposes:
-//!   GET    /api/v1/workflows
+//!   GET    /api/v1/workflows?q=...&name=...&trigger=...&active=...&tag=...&limit=...&offset=...&full=true
 //!   POST   /api/v1/workflows
+//!   POST   /api/v1/workflows/validate
+//!   GET    /api/v1/workflows/tags
+//!   GET    /api/v1/workflows/schema
+//!   POST   /api/v1/workflows/import
 //!   GET    /api/v1/workflows/:id
+//!   PUT    /api/v1/workflows/:id
 //!   DELETE /api/v1/workflows/:id
+//!   PUT    /api/v1/workflows/:id/active
+//!   PUT    /api/v1/workflows/:id/tags
+//!   GET    /api/v1/workflows/:id/versions
+//!   GET    /api/v1/workflows/:id/export
+//!   POST   /api/v1/workflows/:id/rollback/:version
+//!   POST   /api/v1/workflows/:id/clone
+//!   POST   /api/v1/workflows/:id/activate
+//!   POST   /api/v1/workflows/:id/deactivate
 //!   POST   /api/v1/workflows/:id/execute
-//!   POST   /webhook/:path
+//!   GET    /api/v1/workflows/:id/executions?status=...&limit=...&offset=...
+//!   GET    /api/v1/workflows/:id/determinism
+//!   GET    /api/v1/workflows/:id/stats?days=...
+//!   GET    /api/v1/workflows/:id/secrets
+//!   POST   /api/v1/workflows/:id/secrets
+//!   PUT    /api/v1/workflows/:id/secrets/:key
+//!   DELETE /api/v1/workflows/:id/secrets/:key
+//!   POST   /api/v1/workflows/:id/webhook-capture
+//!   GET    /api/v1/workflows/:id/webhook-capture
+//!   DELETE /api/v1/workflows/:id/webhook-capture
+//!   GET    /api/v1/executions?status=...&trigger=...&started_after=...
+//!   GET    /api/v1/executions?business_key=...
+//!   DELETE /api/v1/executions?before=...&workflow_id=...
+//!   GET    /api/v1/executions/:id
+//!   DELETE /api/v1/executions/:id
+//!   POST   /api/v1/executions/:id/retry
+//!   POST   /api/v1/executions/:id/replay
+//!   GET    /api/v1/executions/:id/lineage?path=...
+//!   GET    /api/v1/executions/:id/timeline
+//!   GET    /api/v1/executions/:id/compare/:other
+//!   GET    /api/v1/executions/:id/logs?level=...&limit=...&offset=...
+//!   GET    /api/v1/executions/:id/nodes/:node_id/logs?level=...&limit=...&offset=...
+//!   GET    /api/v1/executions/:id/binary
+//!   GET    /api/v1/executions/:id/binary/:binary_id
+//!   GET    /api/v1/executions/:id/approvals/:node_id
+//!   POST   /api/v1/executions/:id/approvals/:node_id
+//!   GET    /api/v1/executions/:id/approvals/:node_id/:decision?token=...  (a page that posts the decision)
+//!   POST   /api/v1/executions/:id/approvals/:node_id/:decision
+//!   GET    /api/v1/secrets
+//!   POST   /api/v1/secrets
+//!   PUT    /api/v1/secrets/:key
+//!   DELETE /api/v1/secrets/:key
+//!   GET    /api/v1/credentials
+//!   POST   /api/v1/credentials
+//!   GET    /api/v1/credentials/:id
+//!   PUT    /api/v1/credentials/:id
+//!   DELETE /api/v1/credentials/:id
+//!   GET    /api/v1/queues
+//!   GET    /api/v1/stats
+//!   GET    /api/v1/node-types
+//!   GET    /api/v1/admin/legal-holds/:target/:id
+//!   PUT    /api/v1/admin/legal-holds/:target/:id
+//!   POST   /api/v1/privacy/erasure
+//!   GET    /api/v1/support/workflows/:id        (`support_access` flag)
+//!   GET    /api/v1/support/executions/:id       (`support_access` flag)
+//!   GET    /api/v1/admin/audit-log
+//!   GET    /api/v1/admin/feature-flags
+//!   PUT    /api/v1/admin/feature-flags/:flag
+//!   DELETE /api/v1/admin/feature-flags/:flag
+//!   GET    /api/v1/users
+//!   PUT    /api/v1/users/:subject
+//!   DELETE /api/v1/users/:subject
+//!   GET    /api/v1/me
+//!   GET    /api/v1/projects
+//!   POST   /api/v1/projects
+//!   PUT    /api/v1/projects/:name/quota
+//!   POST   /api/v1/graphql                      (`graphql` flag; see [`graphql`])
+//!   GET    /api/v1/ws                           (WebSocket; see [`handlers::live`])
+//!   ANY    /webhook/:path
+//!   GET    /healthz
+//!   GET    /readyz                              (503 until startup tasks finish and the database answers)
+//!   GET    /metrics                             (Prometheus; see [`metrics`])
+//!   POST   /rusty_automation.v1.Management/*    (gRPC over HTTP/2; see [`grpc`])
+//!   GET    /*                                   (the web UI, with the `ui` feature; see `ui`)
+//!
+//! When API keys or an OIDC issuer are configured, `/api/v1` routes need
+//! `Authorization: Bearer <api key or JWT>` (see [`auth`]) and a role that
+//! covers the endpoint (see [`rbac`]); approval links carry their own
+//! token and stay open, as do webhooks, health checks, and metrics.  `/api/v1`
+//! requests act within the project named by `X-Project` (see [`project`]).
+//! `/api/v1/ws` pushes workflow and execution changes as they happen (see
+//! [`live`]).  Request bodies have a size limit, and bodies that are too large or
+//! do not fit the endpoint are refused with the reason (see [`limits`]).
+//! Listings, details, statistics, and GraphQL queries read from a read
+//! replica when one is configured; everything else uses the primary.
 
+pub mod auth;
+pub mod graphql;
+pub mod grpc;
 pub mod handlers;
+pub mod limits;
+pub mod live;
+pub mod metrics;
+pub mod project;
+pub mod rbac;
+#[cfg(feature = "ui")]
+pub mod ui;
 
 use axum::{
-    routing::{get, post, delete},
+    extract::DefaultBodyLimit,
+    http::HeaderValue,
+    middleware,
+    routing::{any, get, post, put},
     Router,
 };
 use db::DbPool;
-use tower_http::cors::{Any, CorsLayer};
+use engine::binary::BinaryData;
+use auth::Auth;
+use limits::BodyLimits;
+use live::LiveUpdates;
+use engine::secrets::SecretsKey;
+use engine::{Automation, FeatureFlags, Readiness};
+use queue::SharedQueue;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::trace::TraceLayer;
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: DbPool,
+    /// Where listings, details, and statistics are read: a read replica's
+    /// pool, or `pool` itself when there is none.  Reads there may lag
+    /// writes slightly.
+    pub read_pool: DbPool,
+    /// Where new jobs are handed to workers.
+    pub queue: SharedQueue,
+    pub flags: FeatureFlags,
+    pub readiness: Readiness,
+    /// The engine as a library: node types, for reading what workflow
+    /// definitions reference.
+    pub automation: Automation,
+    /// Who may call `/api/v1`; open by default.
+    pub auth: Auth,
+    /// Changes relayed to `/ws` clients.
+    pub live: LiveUpdates,
+    /// Encrypts the secrets set through the API; without it they cannot be
+    /// set.
+    pub secrets: Option<SecretsKey>,
+    /// Largest request bodies `/api/v1` routes and webhooks accept.
+    pub limits: BodyLimits,
+    /// Where the files of executions are kept; without it they cannot be
+    /// downloaded.
+    pub binary: Option<BinaryData>,
+    /// Origins browsers may call the API from; any origin when empty.
+    pub cors_origins: Vec<HeaderValue>,
 }
 
-pub async fn serve(bind: &str, pool: DbPool) -> Result<(), std::io::Error> {
-    let state = AppState { pool };
+#[allow(clippy::too_many_arguments)]
+pub async fn serve(
+    bind: &str,
+    pool: DbPool,
+    read_pool: Option<DbPool>,
+    queue: SharedQueue,
+    flags: FeatureFlags,
+    readiness: Readiness,
+    automation: Automation,
+    auth: Auth,
+    secrets: Option<SecretsKey>,
+    limits: BodyLimits,
+    binary: Option<BinaryData>,
+    cors_origins: &[String],
+) -> Result<(), std::io::Error> {
+    let cors_origins = cors_origins
+        .iter()
+        .map(|origin| {
+            HeaderValue::from_str(origin).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid CORS origin '{origin}'"))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if !auth.is_enabled() {
+        tracing::warn!("No API keys or OIDC issuer configured; the API is open to anyone who can reach it");
+    }
+    metrics::handle();
+    let live = LiveUpdates::new();
+    tokio::spawn(live.clone().run(pool.clone()));
+    let read_pool = read_pool.unwrap_or_else(|| pool.clone());
+    let state = AppState {
+        pool,
+        read_pool,
+        queue,
+        flags,
+        readiness,
+        automation,
+        auth,
+        live,
+        secrets,
+        limits,
+        binary,
+        cors_origins,
+    };
+    let app = router(state);
+
+    let listener = tokio::net::TcpListener::bind(bind).await?;
+    tracing::info!("Server listening on {}", listener.local_addr()?);
+
+    axum::serve(listener, app).await
+}
 
+/// Every route, with CORS and request tracing, bound to `state`.
+pub fn router(state: AppState) -> Router {
+    let origins = if state.cors_origins.is_empty() {
+        AllowOrigin::from(Any)
+    } else {
+        AllowOrigin::list(state.cors_origins.clone())
+    };
     let cors = CorsLayer::new()
-        .allow_origin(Any)
+        .allow_origin(origins)
         .allow_methods(Any)
         .allow_headers(Any);
 
     let api_router = Router::new()
         .route("/workflows", get(handlers::workflows::list).post(handlers::workflows::create))
-        .route("/workflows/:id", get(handlers::workflows::get).delete(handlers::workflows::delete))
-        .route("/workflows/:id/execute", post(handlers::executions::execute));
+        .route("/workflows/validate", post(handlers::workflows::validate))
+        .route("/workflows/tags", get(handlers::workflows::tags))
+        .route("/workflows/schema", get(handlers::workflows::schema))
+        .route("/workflows/import", post(handlers::workflows::import))
+        .route(
+            "/workflows/:id",
+            get(handlers::workflows::get).put(handlers::workflows::update).delete(handlers::workflows::delete),
+        )
+        .route("/workflows/:id/active", put(handlers::workflows::set_active))
+        .route("/workflows/:id/tags", put(handlers::workflows::set_tags))
+        .route("/workflows/:id/versions", get(handlers::workflows::versions))
+        .route("/workflows/:id/export", get(handlers::workflows::export))
+        .route("/workflows/:id/rollback/:version", post(handlers::workflows::rollback))
+        .route("/workflows/:id/clone", post(handlers::workflows::duplicate))
+        .route("/workflows/:id/activate", post(handlers::workflows::activate))
+        .route("/workflows/:id/deactivate", post(handlers::workflows::deactivate))
+        .route("/workflows/:id/execute", post(handlers::executions::execute))
+        .route("/workflows/:id/executions", get(handlers::executions::list_for_workflow))
+        .route("/workflows/:id/determinism", get(handlers::workflows::determinism))
+        .route("/workflows/:id/stats", get(handlers::workflows::stats))
+        .route("/workflows/:id/secrets", get(handlers::secrets::list).post(handlers::secrets::create))
+        .route(
+            "/workflows/:id/secrets/:key",
+            put(handlers::secrets::update).delete(handlers::secrets::delete),
+        )
+        .route(
+            "/workflows/:id/webhook-capture",
+            post(handlers::captures::arm).get(handlers::captures::get).delete(handlers::captures::delete),
+        )
+        .route("/executions", get(handlers::executions::list).delete(handlers::executions::prune))
+        .route("/executions/:id", get(handlers::executions::get).delete(handlers::executions::delete))
+        .route("/executions/:id/retry", post(handlers::executions::retry))
+        .route("/executions/:id/replay", post(handlers::executions::replay))
+        .route("/executions/:id/lineage", get(handlers::executions::lineage))
+        .route("/executions/:id/timeline", get(handlers::executions::timeline))
+        .route("/executions/:id/compare/:other", get(handlers::executions::compare))
+        .route("/executions/:id/logs", get(handlers::logs::list))
+        .route("/executions/:id/nodes/:node_id/logs", get(handlers::logs::list_for_node))
+        .route("/executions/:id/binary", get(handlers::binary::list))
+        .route("/executions/:id/binary/:binary_id", get(handlers::binary::download))
+        .route(
+            "/executions/:id/approvals/:node_id",
+            get(handlers::approvals::get).post(handlers::approvals::decide),
+        )
+        .route("/secrets", get(handlers::secrets::list_project).post(handlers::secrets::create_project))
+        .route(
+            "/secrets/:key",
+            put(handlers::secrets::update_project).delete(handlers::secrets::delete_project),
+        )
+        .route("/credentials", get(handlers::credentials::list).post(handlers::credentials::create))
+        .route(
+            "/credentials/:id",
+            get(handlers::credentials::get).put(handlers::credentials::update).delete(handlers::credentials::delete),
+        )
+        .route("/queues", get(handlers::queues::stats))
+        .route("/stats", get(handlers::dashboard::stats))
+        .route("/node-types", get(handlers::nodes::catalog))
+        .route(
+            "/admin/legal-holds/:target/:id",
+            get(handlers::legal_holds::history).put(handlers::legal_holds::set),
+        )
+        .route("/privacy/erasure", post(handlers::privacy::erasure))
+        .route("/support/workflows/:id", get(handlers::support::workflow))
+        .route("/support/executions/:id", get(handlers::support::execution))
+        .route("/admin/audit-log", get(handlers::audit::list))
+        .route("/admin/feature-flags", get(handlers::feature_flags::list))
+        .route(
+            "/admin/feature-flags/:flag",
+            put(handlers::feature_flags::set).delete(handlers::feature_flags::remove),
+        )
+        .route("/users", get(handlers::users::list))
+        .route("/users/:subject", put(handlers::users::set).delete(handlers::users::delete))
+        .route("/me", get(handlers::users::me))
+        .route("/projects", get(handlers::projects::list).post(handlers::projects::create))
+        .route("/projects/:name/quota", put(handlers::projects::set_quota))
+        .route("/graphql", post(handlers::graphql::query))
+        .route("/ws", get(handlers::live::connect))
+        .route_layer(middleware::from_fn_with_state(state.clone(), project::scope))
+        .route_layer(middleware::from_fn_with_state(state.clone(), rbac::authorize))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::require_identity))
+        .route(
+            "/executions/:id/approvals/:node_id/:decision",
+            get(handlers::approvals::confirm_link).post(handlers::approvals::decide_link),
+        )
+        .layer(middleware::from_fn_with_state(state.limits.api, limits::enforce))
+        .layer(DefaultBodyLimit::max(state.limits.api));
+    let webhooks = Router::new()
+        .route("/webhook/:path", any(handlers::webhooks::handle_webhook))
+        .layer(middleware::from_fn_with_state(state.limits.webhook, limits::enforce))
+        .layer(DefaultBodyLimit::max(state.limits.webhook));
 
-    let app = Router::new()
+    let router = Router::new()
         .nest("/api/v1", api_router)
-        .route("/webhook/:path", post(handlers::webhooks::handle_webhook))
-        .layer(cors)
-        .layer(TraceLayer::new_for_http())
-        .with_state(state);
+        .merge(webhooks)
+        .route("/healthz", get(handlers::health::healthz))
+        .route("/readyz", get(handlers::health::readyz))
+        .route("/metrics", get(handlers::metrics::render))
+        .route_service(grpc::PATH, grpc::service(state.clone()));
+    #[cfg(feature = "ui")]
+    let router = router.fallback(ui::serve);
 
-    let listener = tokio::net::TcpListener::bind(bind).await?;
-    tracing::info!("Server listening on {}", listener.local_addr()?);
+    router
+        .route_layer(middleware::from_fn(metrics::track))
+        .layer(cors)
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+        .with_state(state)
+}
 
-    axum::serve(listener, app).await
+/// The span of one request, carrying its `x-request-id` (the caller's, or
+/// a new UUID) so every line logged while serving it can be correlated.
+/// The id is echoed in the response.
+fn request_span(request: &axum::http::Request<axum::body::Body>) -> tracing::Span {
+    let request_id = request.headers().get("x-request-id").and_then(|id| id.to_str().ok()).unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id = %request_id,
+    )
 }
@@ -0,0 +1,161 @@
+//! Request body limits, and request bodies that say what is wrong with
+//! them.
+//!
+//! `/api/v1` routes and webhooks each accept bodies up to their own limit
+//! (see [`BodyLimits`]); a larger one is refused with `413` and the limit.
+//! JSON bodies of `/api/v1` routes are read with [`Payload`], which answers
+//! `415` without a JSON content type, `400` for malformed JSON (with where
+//! it broke), and `422` naming the field that does not fit, e.g.
+//!
+//! ```json
+//! { "error": "invalid type: string \"yes\", expected a boolean", "field": "active" }
+//! ```
+
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{rejection::BytesRejection, FromRequest, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+/// Limit of `/api/v1` request bodies unless configured: 2 MiB, axum's own.
+pub const DEFAULT_API_BODY_BYTES: usize = 2 * 1024 * 1024;
+/// Limit of webhook request bodies unless configured: 1 MiB.
+pub const DEFAULT_WEBHOOK_BODY_BYTES: usize = 1024 * 1024;
+
+/// Largest request bodies accepted, in bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimits {
+    pub api: usize,
+    pub webhook: usize,
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        Self { api: DEFAULT_API_BODY_BYTES, webhook: DEFAULT_WEBHOOK_BODY_BYTES }
+    }
+}
+
+/// The limit in force for a request, set by [`enforce`] for the body
+/// extractors' errors.
+#[derive(Debug, Clone, Copy)]
+struct BodyLimit(usize);
+
+/// Middleware refusing requests that declare a body over `limit` before it
+/// is read.  Pair it with `DefaultBodyLimit::max(limit)`, which stops
+/// bodies without a `Content-Length` while they are read.
+pub async fn enforce(State(limit): State<usize>, mut request: Request, next: Next) -> Response {
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if declared.is_some_and(|length| length > limit as u64) {
+        return too_large(Some(limit)).into_response();
+    }
+    request.extensions_mut().insert(BodyLimit(limit));
+    next.run(request).await
+}
+
+fn too_large(limit: Option<usize>) -> (StatusCode, Json<Value>) {
+    let error = match limit {
+        Some(limit) => format!("the request body is larger than the limit of {limit} bytes"),
+        None => "the request body is too large".to_owned(),
+    };
+    (StatusCode::PAYLOAD_TOO_LARGE, Json(json!({ "error": error, "limit_bytes": limit })))
+}
+
+/// Read the whole body of `request`, within its limit.
+async fn read<S: Send + Sync>(request: Request, state: &S) -> Result<Bytes, (StatusCode, Json<Value>)> {
+    let limit = request.extensions().get::<BodyLimit>().map(|limit| limit.0);
+    Bytes::from_request(request, state).await.map_err(|rejection: BytesRejection| {
+        match rejection.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => too_large(limit),
+            status => (status, Json(json!({ "error": rejection.body_text() }))),
+        }
+    })
+}
+
+/// A raw request body, refused with `413` and the limit when too large.
+pub struct RawBody(pub Bytes);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequest<S> for RawBody {
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        read(request, state).await.map(RawBody).map_err(IntoResponse::into_response)
+    }
+}
+
+/// A JSON request body, like [`axum::Json`] with the errors described
+/// above.
+#[derive(Default)]
+pub struct Payload<T>(pub T);
+
+#[async_trait]
+impl<T: DeserializeOwned, S: Send + Sync> FromRequest<S> for Payload<T> {
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_json(request.headers()) {
+            let body = json!({ "error": "expected a body with Content-Type: application/json" });
+            return Err((StatusCode::UNSUPPORTED_MEDIA_TYPE, Json(body)).into_response());
+        }
+        let bytes = read(request, state).await.map_err(IntoResponse::into_response)?;
+        let deserializer = &mut serde_json::Deserializer::from_slice(&bytes);
+        serde_path_to_error::deserialize(deserializer).map(Payload).map_err(|e| {
+            let field = field(e.path());
+            let e = e.into_inner();
+            if e.is_syntax() || e.is_eof() {
+                let error = format!("the body is not valid JSON: {e}");
+                let body = json!({ "error": error, "line": e.line(), "column": e.column() });
+                (StatusCode::BAD_REQUEST, Json(body)).into_response()
+            } else {
+                let body = json!({ "error": e.to_string(), "field": field });
+                (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response()
+            }
+        })
+    }
+}
+
+/// `path` as a dotted field name, e.g. `nodes[0].config`; `None` for the
+/// document itself.
+pub(crate) fn field(path: &serde_path_to_error::Path) -> Option<String> {
+    let path = path.to_string();
+    (path != ".").then_some(path)
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    essence == "application/json" || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(content_type: &str) -> HeaderMap {
+        HeaderMap::from_iter([(header::CONTENT_TYPE, content_type.parse().unwrap())])
+    }
+
+    #[test]
+    fn json_content_types_are_recognized() {
+        assert!(is_json(&headers("application/json")));
+        assert!(is_json(&headers("Application/JSON; charset=utf-8")));
+        assert!(is_json(&headers("application/merge-patch+json")));
+        assert!(!is_json(&headers("text/plain")));
+        assert!(!is_json(&HeaderMap::new()));
+    }
+}
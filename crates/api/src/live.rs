@@ -0,0 +1,167 @@
+//! Live updates: changes to workflows and executions, pushed over
+//! WebSockets.
+//!
+//! One listener per server receives the changes the repository publishes
+//! on [`CHANNEL`] and relays them to every connected client, which picks
+//! out the ones its [`Subscription`]s ask for (see
+//! [`handlers::live`](crate::handlers::live)).  Changes published while the
+//! listener is reconnecting are lost; clients that fall behind are told so
+//! instead of receiving them late.
+
+use std::time::Duration;
+
+use db::changes::{Change, CHANNEL};
+use db::listener::Listener;
+use db::{DbError, DbPool};
+use serde::Deserialize;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Changes a slow client may fall behind by before it misses some.
+const BUFFER: usize = 1024;
+
+/// Longest pause between reconnection attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Relays published changes to connected clients.
+#[derive(Clone)]
+pub struct LiveUpdates {
+    sender: broadcast::Sender<Change>,
+}
+
+impl Default for LiveUpdates {
+    fn default() -> Self {
+        Self { sender: broadcast::channel(BUFFER).0 }
+    }
+}
+
+impl LiveUpdates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every change from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Change> {
+        self.sender.subscribe()
+    }
+
+    /// Relay the changes published on `pool`'s database, reconnecting when
+    /// the listener fails; runs forever.
+    pub async fn run(self, pool: DbPool) {
+        let mut backoff = Duration::from_secs(1);
+        loop {
+            match self.relay(&pool).await {
+                Ok(()) => backoff = Duration::from_secs(1),
+                Err(e) => tracing::warn!("live updates: {}; reconnecting in {:?}", e, backoff),
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    async fn relay(&self, pool: &DbPool) -> Result<(), DbError> {
+        let mut listener = Listener::connect_with(pool).await?;
+        listener.listen(CHANNEL).await?;
+        loop {
+            let notification = listener.recv().await?;
+            match serde_json::from_str::<Change>(&notification.payload) {
+                // No receivers is fine: nobody is connected.
+                Ok(change) => drop(self.sender.send(change)),
+                Err(e) => tracing::warn!("live updates: ignoring a malformed change: {}", e),
+            }
+        }
+    }
+}
+
+/// A message from a client.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Start sending the changes `subscription` matches, tagged `id`
+    /// (replacing an earlier subscription with that id).
+    Subscribe {
+        id: String,
+        #[serde(flatten)]
+        subscription: Subscription,
+    },
+    Unsubscribe { id: String },
+}
+
+/// What a client subscribes to.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "topic", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Subscription {
+    /// Workflows created, changed, or deleted — only `workflow_id`, if
+    /// given.
+    Workflows { workflow_id: Option<Uuid> },
+    /// Executions changing status — only those of `workflow_id`, of
+    /// `execution_id`, or with one of `statuses`, if given.
+    Executions {
+        workflow_id: Option<Uuid>,
+        execution_id: Option<Uuid>,
+        #[serde(default)]
+        statuses: Vec<String>,
+    },
+}
+
+impl Subscription {
+    pub fn matches(&self, change: &Change) -> bool {
+        match (self, change) {
+            (Self::Workflows { workflow_id }, Change::Workflow { id, .. }) => workflow_id.is_none_or(|w| w == *id),
+            (
+                Self::Executions { workflow_id, execution_id, statuses },
+                Change::Execution { id, workflow_id: of, status, .. },
+            ) => {
+                workflow_id.is_none_or(|w| w == *of)
+                    && execution_id.is_none_or(|e| e == *id)
+                    && (statuses.is_empty() || statuses.contains(status))
+            }
+            _ => false,
+        }
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::changes::WorkflowAction;
+    use serde_json::json;
+
+    fn execution(workflow_id: Uuid, status: &str) -> Change {
+        Change::Execution { id: Uuid::new_v4(), workflow_id, project_id: Uuid::nil(), status: status.into() }
+    }
+
+    #[test]
+    fn subscriptions_match_their_topic_and_filters() {
+        let (ours, theirs) = (Uuid::new_v4(), Uuid::new_v4());
+        let message: ClientMessage = serde_json::from_value(json!({
+            "action": "subscribe", "id": "runs",
+            "topic": "executions", "workflow_id": ours, "statuses": ["failed", "completed"]
+        }))
+        .unwrap();
+        let ClientMessage::Subscribe { id, subscription } = message else { panic!("not a subscription") };
+        assert_eq!(id, "runs");
+        assert!(subscription.matches(&execution(ours, "failed")));
+        assert!(!subscription.matches(&execution(ours, "running")));
+        assert!(!subscription.matches(&execution(theirs, "failed")));
+
+        let workflows: Subscription = serde_json::from_value(json!({ "topic": "workflows" })).unwrap();
+        let deleted = Change::Workflow {
+            action: WorkflowAction::Deleted,
+            id: theirs,
+            project_id: Uuid::nil(),
+            name: "nightly".into(),
+            active: None,
+            version: None,
+        };
+        assert!(workflows.matches(&deleted));
+        assert!(!workflows.matches(&execution(ours, "failed")));
+        assert!(!subscription.matches(&deleted));
+
+        let misspelt = json!({ "action": "subscribe", "id": "x", "topic": "executions", "status": ["failed"] });
+        assert!(serde_json::from_value::<ClientMessage>(misspelt).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//! Prometheus metrics.
+//!
+//! [`handle`] installs the process-wide recorder that everything recorded
+//! through the `metrics` facade lands in — the HTTP metrics [`track`]
+//! records here and the engine's (see [`engine::metrics`]) — and renders
+//! it for `GET /metrics`.  Workers have no API, so [`serve`] exposes their
+//! metrics on a port of their own.
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use ::metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram, Unit};
+use axum::{
+    extract::{MatchedPath, Request},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+use db::models::QueueStatsRow;
+use db::pool::PoolHealth;
+use db::DbPool;
+
+/// Requests answered, by `method`, `route`, and `status`.
+pub const HTTP_REQUESTS: &str = "rusty_automation_http_requests_total";
+/// Time taken to answer requests, by `method` and `route`.
+pub const HTTP_REQUEST_DURATION: &str = "rusty_automation_http_request_duration_seconds";
+/// Jobs in each queue, by `queue` and `state` (`pending`, `due`,
+/// `processing`, `dead_lettered`).
+pub const QUEUE_JOBS: &str = "rusty_automation_queue_jobs";
+/// How long the longest-waiting due job of each `queue` has been due.
+pub const QUEUE_OLDEST_DUE: &str = "rusty_automation_queue_oldest_due_seconds";
+
+/// Connections of each database `pool` (`primary`, `read`), by `state`
+/// (`idle`, `in_use`).
+pub const DB_POOL_CONNECTIONS: &str = "rusty_automation_db_pool_connections";
+/// Most connections each database `pool` opens.
+pub const DB_POOL_MAX_CONNECTIONS: &str = "rusty_automation_db_pool_max_connections";
+/// Time taken to get a connection from each database `pool`, as sampled
+/// when metrics are scraped.
+pub const DB_POOL_ACQUIRE_DURATION: &str = "rusty_automation_db_pool_acquire_duration_seconds";
+/// Queries that failed waiting for a free database connection.
+pub const DB_POOL_ACQUIRE_TIMEOUTS: &str = "rusty_automation_db_pool_acquire_timeouts_total";
+
+/// Histogram buckets of durations, in seconds.
+const DURATION_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+/// The installed recorder, installing it on first use.
+pub fn handle() -> &'static PrometheusHandle {
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+    HANDLE.get_or_init(|| {
+        let builder = PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Suffix("duration_seconds".into()), DURATION_BUCKETS)
+            .expect("duration buckets are not empty");
+        let recorder = builder.build_recorder();
+        let handle = recorder.handle();
+        if ::metrics::set_global_recorder(recorder).is_err() {
+            tracing::warn!("Another metrics recorder is installed; /metrics will be empty");
+        }
+        describe();
+        handle
+    })
+}
+
+fn describe() {
+    describe_counter!(HTTP_REQUESTS, "HTTP requests answered");
+    describe_histogram!(HTTP_REQUEST_DURATION, Unit::Seconds, "Time taken to answer HTTP requests");
+    describe_gauge!(QUEUE_JOBS, "Jobs in each queue");
+    describe_gauge!(QUEUE_OLDEST_DUE, Unit::Seconds, "How long the longest-waiting due job has been due");
+    describe_gauge!(DB_POOL_CONNECTIONS, "Database connections of each pool");
+    describe_gauge!(DB_POOL_MAX_CONNECTIONS, "Most database connections each pool opens");
+    describe_histogram!(DB_POOL_ACQUIRE_DURATION, Unit::Seconds, "Time taken to get a database connection");
+    describe_counter!(DB_POOL_ACQUIRE_TIMEOUTS, "Queries that failed waiting for a free database connection");
+    engine::metrics::describe();
+}
+
+/// Middleware: count and time requests by their route (as routed, e.g.
+/// `/api/v1/workflows/:id`, so ids do not each get their own series).
+pub async fn track(request: Request, next: Next) -> Response {
+    let route = request.extensions().get::<MatchedPath>().map(|p| p.as_str().to_owned()).unwrap_or_default();
+    let method = request.method().to_string();
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let status = response.status().as_u16().to_string();
+    histogram!(HTTP_REQUEST_DURATION, "method" => method.clone(), "route" => route.clone())
+        .record(started.elapsed().as_secs_f64());
+    counter!(HTTP_REQUESTS, "method" => method, "route" => route, "status" => status).increment(1);
+    response
+}
+
+/// Set the queue gauges from `stats`.
+pub fn record_queues(stats: &[QueueStatsRow]) {
+    for row in stats {
+        for (state, jobs) in
+            [("pending", row.pending), ("due", row.due), ("processing", row.processing), ("dead_lettered", row.dead_lettered)]
+        {
+            gauge!(QUEUE_JOBS, "queue" => row.queue.clone(), "state" => state).set(jobs as f64);
+        }
+        gauge!(QUEUE_OLDEST_DUE, "queue" => row.queue.clone()).set(row.oldest_due_secs.unwrap_or(0.0));
+    }
+}
+
+/// Set the gauges of database pool `pool` from `health`.
+pub fn record_pool(pool: &'static str, health: &PoolHealth) {
+    let stats = health.stats;
+    gauge!(DB_POOL_CONNECTIONS, "pool" => pool, "state" => "idle").set(f64::from(stats.idle));
+    gauge!(DB_POOL_CONNECTIONS, "pool" => pool, "state" => "in_use").set(f64::from(stats.in_use));
+    gauge!(DB_POOL_MAX_CONNECTIONS, "pool" => pool).set(f64::from(stats.max_connections));
+    if let Some(secs) = health.acquire_secs {
+        histogram!(DB_POOL_ACQUIRE_DURATION, "pool" => pool).record(secs);
+    }
+    counter!(DB_POOL_ACQUIRE_TIMEOUTS).absolute(db::pool::acquire_timeouts());
+}
+
+/// The Prometheus text exposition of every metric.
+pub fn render() -> Response {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], handle().render()).into_response()
+}
+
+/// Serve `GET /metrics` alone on `bind`, for processes without the API,
+/// with the gauges of their database `pool`.
+pub async fn serve(bind: &str, pool: DbPool) -> Result<(), std::io::Error> {
+    handle();
+    let metrics = move || async move {
+        record_pool("primary", &db::pool::check(&pool, Duration::from_secs(2)).await);
+        render()
+    };
+    let app = Router::new().route("/metrics", get(metrics));
+    let listener = tokio::net::TcpListener::bind(bind).await?;
+    tracing::info!("Metrics listening on {}", listener.local_addr()?);
+    axum::serve(listener, app).await
+}
@@ -0,0 +1,113 @@
+//! Projects: which one a request acts in, and keeping it there.
+//!
+//! Requests name their project in the `X-Project` header (`default` when
+//! absent); API keys confined to a project always act in it and cannot
+//! reach the deployment-wide endpoints.  Listings only return the
+//! project's rows, and workflows, executions, and credentials addressed by
+//! id answer 404 when they belong to another project.
+
+use axum::{
+    extract::{MatchedPath, RawPathParams, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json, RequestExt,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use db::models::ProjectRow;
+use db::repository::{
+    credentials as credential_repo, executions as exec_repo, projects as project_repo, workflows as wf_repo,
+};
+
+use crate::auth::Identity;
+use crate::rbac;
+use crate::AppState;
+
+/// Header naming the project a request acts in.
+pub const PROJECT_HEADER: &str = "x-project";
+
+/// Project of requests that do not name one.
+pub const DEFAULT_PROJECT: &str = "default";
+
+/// Middleware, after authorization: resolve the request's project, check
+/// that the workflow, execution, or credential it addresses is in it, and
+/// attach it as an `Extension<ProjectRow>`.
+pub async fn scope(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let route = request.extensions().get::<MatchedPath>().map(|p| p.as_str().to_owned()).unwrap_or_default();
+    let confined = request.extensions().get::<Identity>().and_then(|identity| identity.project.clone());
+    let requested = request.headers().get(PROJECT_HEADER).and_then(|v| v.to_str().ok()).map(str::trim);
+
+    let project = match resolve(&state, confined.as_deref(), requested, rbac::is_deployment_wide(&route)).await {
+        Ok(project) => project,
+        Err((StatusCode::INTERNAL_SERVER_ERROR, _)) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        Err((status, message)) => return error(status, message),
+    };
+
+    if !rbac::is_deployment_wide(&route) {
+        let params = match request.extract_parts::<RawPathParams>().await {
+            Ok(params) => params,
+            Err(rejection) => return rejection.into_response(),
+        };
+        let route = route.strip_prefix("/api/v1").unwrap_or(&route);
+        for (param, value) in &params {
+            match addressed_project(&state, route, param, value).await {
+                Ok(Some(owner)) if owner != project.id => return StatusCode::NOT_FOUND.into_response(),
+                Ok(_) => {}
+                Err(status) => return status.into_response(),
+            }
+        }
+    }
+
+    request.extensions_mut().insert(project);
+    next.run(request).await
+}
+
+/// The project a caller confined to `confined` (if anyone) acts in when it
+/// asks for `requested`, or why it cannot.
+pub(crate) async fn resolve(
+    state: &AppState,
+    confined: Option<&str>,
+    requested: Option<&str>,
+    deployment_wide: bool,
+) -> Result<ProjectRow, (StatusCode, String)> {
+    let name = match (confined, requested) {
+        (Some(_), _) if deployment_wide => {
+            return Err((StatusCode::FORBIDDEN, "API keys confined to a project cannot use this endpoint".into()));
+        }
+        (Some(confined), Some(requested)) if requested != confined => {
+            return Err((StatusCode::FORBIDDEN, format!("this API key is confined to project '{confined}'")));
+        }
+        (Some(confined), _) => confined.to_owned(),
+        (None, Some(requested)) if !requested.is_empty() => requested.to_owned(),
+        (None, _) => DEFAULT_PROJECT.to_owned(),
+    };
+    match project_repo::get_project_by_name(&state.pool, &name).await {
+        Ok(project) => Ok(project),
+        Err(db::DbError::NotFound) => Err((StatusCode::NOT_FOUND, format!("unknown project '{name}'"))),
+        Err(_) => Err((StatusCode::INTERNAL_SERVER_ERROR, String::new())),
+    }
+}
+
+/// The project of the workflow, execution, or credential path parameter
+/// `param` of `route` names; `None` when it names none of them or nothing
+/// exists with that id (the handler then answers as usual).
+async fn addressed_project(state: &AppState, route: &str, param: &str, value: &str) -> Result<Option<Uuid>, StatusCode> {
+    let Ok(id) = value.parse::<Uuid>() else { return Ok(None) };
+    let owner = match (route.split('/').nth(1), param) {
+        (Some("workflows"), "id") => wf_repo::workflow_project(&state.pool, id).await,
+        (Some("executions"), "id" | "other") => exec_repo::execution_project(&state.pool, id).await,
+        (Some("credentials"), "id") => credential_repo::credential_project(&state.pool, id).await,
+        _ => return Ok(None),
+    };
+    match owner {
+        Ok(owner) => Ok(Some(owner)),
+        Err(db::DbError::NotFound) => Ok(None),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+fn error(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
@@ -0,0 +1,157 @@
+//! Role-based access control.
+//!
+//! Every user holds one [`Role`] and every endpoint needs one: reads need
+//! `viewer`, changes and executions need `editor`, and changing secrets or
+//! credentials and the deployment-wide endpoints — users, projects, admin,
+//! privacy, support — need `admin`.  A caller's role comes from the
+//! `users` table by their subject; subjects given to `serve --admin` are
+//! always admins, so someone can add the first users, and subjects without
+//! a row get the configured default role, if any.  With authentication off
+//! everyone is an admin.
+
+use std::fmt;
+use std::str::FromStr;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use db::repository::users as user_repo;
+
+use crate::auth::Identity;
+use crate::AppState;
+
+/// What a user may do; each role includes the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Viewer,
+    Editor,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Viewer => "viewer",
+            Self::Editor => "editor",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(Self::Viewer),
+            "editor" => Ok(Self::Editor),
+            "admin" => Ok(Self::Admin),
+            other => Err(format!("unknown role '{other}'")),
+        }
+    }
+}
+
+/// Whether `route` (as routed, e.g. `/api/v1/users/:subject`) spans every
+/// project rather than acting within one.
+pub fn is_deployment_wide(route: &str) -> bool {
+    let route = route.strip_prefix("/api/v1").unwrap_or(route);
+    ["/admin/", "/users", "/projects", "/privacy/", "/support/"].iter().any(|prefix| route.starts_with(prefix))
+}
+
+/// The role a `method` request to `route` (as routed, e.g.
+/// `/api/v1/workflows/:id`) needs.
+pub fn required_role(method: &Method, route: &str) -> Role {
+    // GraphQL has no mutations, so its POSTs only read.
+    let read = method == Method::GET || method == Method::HEAD || route.ends_with("/graphql");
+    if is_deployment_wide(route) || (!read && (route.contains("/secrets") || route.contains("/credentials"))) {
+        Role::Admin
+    } else if read {
+        Role::Viewer
+    } else {
+        Role::Editor
+    }
+}
+
+/// Middleware, after authentication: answer 403 unless the caller's role
+/// covers the endpoint, and attach the role for handlers to read.
+pub async fn authorize(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let role = match request.extensions().get::<Identity>() {
+        None => Some(Role::Admin),
+        Some(identity) => match role_of(&state, identity).await {
+            Ok(role) => role,
+            Err(e) => {
+                tracing::error!("Could not look up the role of {}: {e}", identity.subject);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        },
+    };
+    let route = request.extensions().get::<MatchedPath>().map(|p| p.as_str().to_owned()).unwrap_or_default();
+    let required = required_role(request.method(), &route);
+    match role {
+        Some(role) if role >= required => {
+            request.extensions_mut().insert(role);
+            next.run(request).await
+        }
+        Some(_) => forbidden(format!("this needs the {required} role")),
+        None => forbidden("no role is assigned to you".to_owned()),
+    }
+}
+
+/// The role of an authenticated caller, if they have one.
+pub(crate) async fn role_of(state: &AppState, identity: &Identity) -> Result<Option<Role>, db::DbError> {
+    if state.auth.is_admin(&identity.subject) {
+        return Ok(Some(Role::Admin));
+    }
+    match user_repo::get_user(&state.pool, &identity.subject).await {
+        Ok(user) => Ok(user.role.parse().ok()),
+        Err(db::DbError::NotFound) => Ok(state.auth.default_role()),
+        Err(e) => Err(e),
+    }
+}
+
+fn forbidden(message: String) -> Response {
+    (StatusCode::FORBIDDEN, Json(json!({ "error": message }))).into_response()
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_need_viewers_changes_editors_and_admin_routes_admins() {
+        assert_eq!(required_role(&Method::GET, "/api/v1/workflows/:id"), Role::Viewer);
+        assert_eq!(required_role(&Method::GET, "/api/v1/executions"), Role::Viewer);
+        assert_eq!(required_role(&Method::POST, "/api/v1/workflows/:id/execute"), Role::Editor);
+        assert_eq!(required_role(&Method::PUT, "/api/v1/workflows/:id"), Role::Editor);
+        assert_eq!(required_role(&Method::GET, "/api/v1/users"), Role::Admin);
+        assert_eq!(required_role(&Method::GET, "/api/v1/admin/audit-log"), Role::Admin);
+        assert_eq!(required_role(&Method::POST, "/api/v1/privacy/erasure"), Role::Admin);
+        assert_eq!(required_role(&Method::GET, "/api/v1/projects"), Role::Admin);
+        assert_eq!(required_role(&Method::GET, "/api/v1/workflows/:id/secrets"), Role::Viewer);
+        assert_eq!(required_role(&Method::PUT, "/api/v1/workflows/:id/secrets/:key"), Role::Admin);
+        assert_eq!(required_role(&Method::GET, "/api/v1/secrets"), Role::Viewer);
+        assert_eq!(required_role(&Method::DELETE, "/api/v1/secrets/:key"), Role::Admin);
+        assert_eq!(required_role(&Method::GET, "/api/v1/credentials"), Role::Viewer);
+        assert_eq!(required_role(&Method::POST, "/api/v1/credentials"), Role::Admin);
+        assert_eq!(required_role(&Method::POST, "/api/v1/graphql"), Role::Viewer);
+        assert!(Role::Admin > Role::Editor && Role::Editor > Role::Viewer);
+        assert_eq!("editor".parse::<Role>(), Ok(Role::Editor));
+    }
+}
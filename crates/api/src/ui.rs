@@ -0,0 +1,68 @@
+//! The web UI, embedded in the binary (`ui` cargo feature).
+//!
+//! The frontend's build output (`frontend/dist`, from `npm run build`) is
+//! compiled in and served at `/` for any path no route claims.  Paths that
+//! are not files fall back to `index.html`, so the single-page app's own
+//! routes survive a reload; `/api/...` paths still answer 404.
+
+use axum::{
+    http::{header, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use rust_embed::RustEmbed;
+
+#[derive(RustEmbed)]
+#[folder = "../../frontend/dist"]
+struct Assets;
+
+/// Page the single-page app boots from.
+const INDEX: &str = "index.html";
+
+/// Fallback handler: the asset at the request's path, or the app's index.
+pub async fn serve(uri: Uri) -> Response {
+    let path = uri.path().trim_start_matches('/');
+    if path == "api" || path.starts_with("api/") {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    match Assets::get(path).filter(|_| !path.is_empty()) {
+        Some(asset) => asset_response(path, asset),
+        None => match Assets::get(INDEX) {
+            Some(index) => asset_response(INDEX, index),
+            None => StatusCode::NOT_FOUND.into_response(),
+        },
+    }
+}
+
+fn asset_response(path: &str, asset: rust_embed::EmbeddedFile) -> Response {
+    let content_type = mime_guess::from_path(path).first_or_octet_stream();
+    (
+        [(header::CONTENT_TYPE, content_type.as_ref()), (header::CACHE_CONTROL, cache_control(path))],
+        asset.data,
+    )
+        .into_response()
+}
+
+/// Vite names the files under `assets/` by their content hash, so they
+/// never change; everything else (the index above all) is revalidated.
+fn cache_control(path: &str) -> &'static str {
+    if path.starts_with("assets/") {
+        "public, max-age=31536000, immutable"
+    } else {
+        "no-cache"
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_hashed_assets_are_cached_for_good() {
+        assert_eq!(cache_control("assets/index-4f2a9c.js"), "public, max-age=31536000, immutable");
+        assert_eq!(cache_control("index.html"), "no-cache");
+        assert_eq!(cache_control("vite.svg"), "no-cache");
+    }
+}
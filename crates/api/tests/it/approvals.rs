@@ -0,0 +1,97 @@
+//! Approval tokens reach only those who may decide, links confirm before
+//! they decide, and decisions are recorded as the caller's.
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+
+use api::auth::{ApiKey, Auth};
+use api::rbac::Role;
+
+use crate::harness::TestApp;
+
+fn request(method: Method, uri: &str, key: &str, body: Option<Value>) -> Request<Body> {
+    let request = Request::builder().method(method).uri(uri).header(header::AUTHORIZATION, format!("Bearer {key}"));
+    match body {
+        Some(json) => request.header(header::CONTENT_TYPE, "application/json").body(Body::from(json.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap()
+}
+
+/// Start an execution of `workflow_id` and run it up to its approval;
+/// returns the approval's URI.
+async fn pending_approval(app: &TestApp, workflow_id: &str) -> String {
+    let execute = format!("/api/v1/workflows/{workflow_id}/execute");
+    let (status, job) = app.send(request(Method::POST, &execute, "r00t", Some(json!({ "input": {} })))).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let result = app.run_next_job().await.expect("a job").expect("execution defers");
+    assert!(result.deferred_until.is_some());
+    format!("/api/v1/executions/{}/approvals/sign_off", job["execution_id"].as_str().unwrap())
+}
+
+#[tokio::test]
+async fn approvals_are_decided_by_editors_and_confirmed_links() {
+    let keys = ["root=r00t", "auditor=aud1t"].map(|entry| ApiKey::parse(entry).unwrap());
+    let auth = Auth::new(keys.to_vec(), None).with_admins(["root".to_owned()]).with_default_role(Some(Role::Viewer));
+    let app = TestApp::start_with_auth(auth).await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "release",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "sign_off", "node_type": "approval", "config": { "message": "Ship <b>it</b>?" } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let create = json!({ "name": "release", "definition": definition });
+    let (_, workflow) = app.send(request(Method::POST, "/api/v1/workflows", "r00t", Some(create))).await;
+    let workflow_id = workflow["id"].as_str().unwrap();
+
+    // Viewers see the approval but neither its token nor the links.
+    let approval = pending_approval(&app, workflow_id).await;
+    let (status, viewed) = app.send(request(Method::GET, &approval, "aud1t", None)).await;
+    assert_eq!((status, &viewed["approval"]["status"]), (StatusCode::OK, &json!("pending")));
+    assert_eq!((viewed.get("approve_url"), viewed["approval"].get("token")), (None, None));
+    let (_, viewed) = app.send(request(Method::GET, &approval, "r00t", None)).await;
+    assert_eq!(viewed["approval"].get("token"), None);
+    let link = viewed["approve_url"].as_str().expect("links for admins");
+    let (path, token) = link.split_once("?token=").unwrap();
+
+    // Opening the link only asks for confirmation.
+    let (status, page) = app.get_text(link).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("<form method=\"post\">") && page.contains(&format!("value=\"{token}\"")));
+    assert!(page.contains("Ship &lt;b&gt;it&lt;/b&gt;?"));
+    let (_, viewed) = app.send(request(Method::GET, &approval, "r00t", None)).await;
+    assert_eq!(viewed["approval"]["status"], "pending");
+
+    let confirm = |token: &str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri(path)
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(format!("token={token}")))
+            .unwrap()
+    };
+    let (status, _) = app.send_text(confirm("wrong")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, page) = app.send_text(confirm(token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("is approved"));
+    let result = app.run_next_job().await.expect("a job").expect("execution resumes");
+    assert_eq!((&result.output["decision"], &result.output["actor"]), (&json!("approved"), &Value::Null));
+
+    // Decisions through the API are the caller's, whoever the body names.
+    let approval = pending_approval(&app, workflow_id).await;
+    let (_, viewed) = app.send(request(Method::GET, &approval, "r00t", None)).await;
+    let token = viewed["reject_url"].as_str().unwrap().split_once("?token=").unwrap().1.to_owned();
+    let decide = json!({ "token": token, "decision": "reject", "actor": "someone else" });
+    let (status, _) = app.send(request(Method::POST, &approval, "aud1t", Some(decide.clone()))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, decided) = app.send(request(Method::POST, &approval, "r00t", Some(decide))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((&decided["approval"]["status"], &decided["approval"]["actor"]), (&json!("rejected"), &json!("root")));
+    assert_eq!(decided["approval"].get("token"), None);
+    let result = app.run_next_job().await.expect("a job").expect("execution resumes");
+    assert_eq!(result.output["actor"], "root");
+}
@@ -0,0 +1,81 @@
+//! With API keys configured, `/api/v1` needs a bearer credential and a
+//! role covering the endpoint, and the caller is recorded as the author of
+//! what they change.
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+
+use api::auth::{ApiKey, Auth};
+
+use crate::harness::TestApp;
+
+fn request(method: Method, uri: &str, key: Option<&str>, body: Option<Value>) -> Request<Body> {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(key) = key {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {key}"));
+    }
+    match body {
+        Some(json) => request.header(header::CONTENT_TYPE, "application/json").body(Body::from(json.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap()
+}
+
+#[tokio::test]
+async fn api_keys_guard_the_api_and_roles_gate_endpoints() {
+    let keys = ["root=r00t", "deploy-bot=s3cret"].map(|entry| ApiKey::parse(entry).unwrap());
+    let auth = Auth::new(keys.to_vec(), None).with_admins(["root".to_owned()]);
+    let app = TestApp::start_with_auth(auth).await;
+
+    let (status, body) = app.send(request(Method::GET, "/api/v1/workflows", None, None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"], "missing bearer credentials");
+    let (status, _) = app.send(request(Method::GET, "/api/v1/workflows", Some("wrong"), None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = app.send(request(Method::GET, "/healthz", None, None)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // No stored role and no default: refused until an admin adds the user.
+    let (status, _) = app.send(request(Method::GET, "/api/v1/workflows", Some("s3cret"), None)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let viewer = json!({ "role": "viewer" });
+    let (status, _) = app.send(request(Method::PUT, "/api/v1/users/deploy-bot", Some("s3cret"), Some(viewer.clone()))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, user) = app.send(request(Method::PUT, "/api/v1/users/deploy-bot", Some("r00t"), Some(viewer))).await;
+    assert_eq!((status, &user["role"]), (StatusCode::OK, &json!("viewer")));
+    let (status, _) = app.send(request(Method::GET, "/api/v1/workflows", Some("s3cret"), None)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "guarded",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let create = json!({ "name": "guarded", "definition": definition, "author": "someone else" });
+    let (status, body) = app.send(request(Method::POST, "/api/v1/workflows", Some("s3cret"), Some(create.clone()))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"], "this needs the editor role");
+
+    let editor = json!({ "role": "editor" });
+    app.send(request(Method::PUT, "/api/v1/users/deploy-bot", Some("r00t"), Some(editor))).await;
+    let (status, me) = app.send(request(Method::GET, "/api/v1/me", Some("s3cret"), None)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((&me["identity"]["subject"], &me["role"]), (&json!("deploy-bot"), &json!("editor")));
+    let (status, _) = app.send(request(Method::GET, "/api/v1/users", Some("s3cret"), None)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, workflow) = app.send(request(Method::POST, "/api/v1/workflows", Some("s3cret"), Some(create))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let uri = format!("/api/v1/workflows/{}/versions", workflow["id"].as_str().unwrap());
+    let (_, versions) = app.send(request(Method::GET, &uri, Some("s3cret"), None)).await;
+    assert_eq!(versions[0]["author"], "deploy-bot");
+
+    let (status, _) = app.send(request(Method::DELETE, "/api/v1/users/deploy-bot", Some("r00t"), None)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = app.send(request(Method::GET, "/api/v1/workflows", Some("s3cret"), None)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,153 @@
+//! Files pass between nodes of a project by reference, can be downloaded,
+//! and are deleted with their execution.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use db::repository::binary_data as binary_repo;
+use engine::executor::ExecutorConfig;
+use engine::retention::Pruner;
+use engine::worker::Worker;
+use engine::{RetentionPolicy, WorkflowExecutor};
+use nodes::binary::BinaryRef;
+use nodes::traits::ExecutionContext;
+use nodes::{ExecutableNode, NodeError};
+
+use crate::harness::TestApp;
+
+/// Stores a text file.
+struct WriteFile;
+
+#[async_trait]
+impl ExecutableNode for WriteFile {
+    async fn execute(&self, _input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let file = ctx.store_binary(b"hello, file".to_vec(), "text/plain", Some("hello.txt")).await?;
+        Ok(json!({ "file": file.to_value() }))
+    }
+}
+
+/// Reads the file its input refers to.
+struct ReadFile;
+
+#[async_trait]
+impl ExecutableNode for ReadFile {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let reference = BinaryRef::from_value(&input["file"]).ok_or_else(|| NodeError::Fatal("no file".into()))?;
+        let bytes = ctx.require_binary()?.get(ctx.execution_id, &reference).await?;
+        Ok(json!({ "text": String::from_utf8(bytes).unwrap() }))
+    }
+}
+
+#[tokio::test]
+async fn files_are_passed_by_reference_and_deleted_with_their_execution() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "files",
+        "trigger": { "type": "manual" },
+        "nodes": [
+            { "id": "write", "node_type": "write_file", "config": {} },
+            { "id": "read", "node_type": "read_file", "config": {} }
+        ],
+        "edges": [{ "from": "write", "to": "read" }],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "binary"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "files", "definition": definition })).await;
+    let (_, job) = app
+        .post(&format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap()), json!({ "input": {} }))
+        .await;
+    let execution_id = job["execution_id"].as_str().unwrap().to_owned();
+
+    let mut registry = nodes::default_registry();
+    registry.insert("write_file".into(), Arc::new(WriteFile));
+    registry.insert("read_file".into(), Arc::new(ReadFile));
+    let executor = WorkflowExecutor::new(app.pool.clone(), registry, ExecutorConfig::default())
+        .with_binary(Arc::new(app.binary.clone()));
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["binary".into()]);
+    while worker.run_next().await.unwrap().is_some() {}
+
+    let (_, execution) = app.get(&format!("/api/v1/executions/{execution_id}")).await;
+    assert_eq!(execution["execution"]["status"], "succeeded", "{execution}");
+    let file = &execution["nodes"][0]["output"]["file"];
+    assert_eq!(file["mime_type"], "text/plain");
+    assert_eq!(file["size"], 11);
+    assert_eq!(execution["nodes"][1]["output"]["text"], "hello, file");
+
+    let (_, listed) = app.get(&format!("/api/v1/executions/{execution_id}/binary")).await;
+    assert_eq!(listed["binary"].as_array().unwrap().len(), 1, "{listed}");
+    assert_eq!(listed["binary"][0]["id"], file["$binary"]);
+
+    let binary_id = file["$binary"].as_str().unwrap();
+    let (status, headers, body) = app.get_bytes(&format!("/api/v1/executions/{execution_id}/binary/{binary_id}")).await;
+    assert!(status.is_success(), "{status}");
+    assert_eq!(headers["content-type"], "text/plain");
+    assert_eq!(headers["content-disposition"], "attachment; filename=\"hello.txt\"");
+    assert_eq!(body, b"hello, file");
+
+    let other = uuid::Uuid::new_v4();
+    let (status, _, _) = app.get_bytes(&format!("/api/v1/executions/{other}/binary/{binary_id}")).await;
+    assert_eq!(status, 404);
+
+    let blob = app.binary_dir.join("executions").join(&execution_id).join(binary_id);
+    assert!(blob.exists());
+    let (status, _) = app.request(axum::http::Method::DELETE, &format!("/api/v1/executions/{execution_id}"), None).await;
+    assert!(status.is_success(), "{status}");
+    let (status, _, _) = app.get_bytes(&format!("/api/v1/executions/{execution_id}/binary/{binary_id}")).await;
+    assert_eq!(status, 404);
+
+    let pruner = Pruner::new(app.pool.clone(), RetentionPolicy::default()).with_binary_data(app.binary.clone());
+    pruner.prune_once().await.unwrap();
+    assert!(!blob.exists());
+    assert!(binary_repo::list_orphaned_binary_data(&app.pool, 100).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn files_are_read_only_within_their_project() {
+    let app = TestApp::start().await;
+    let definition = |name: &str, node_type: &str| {
+        json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": name,
+            "trigger": { "type": "manual" },
+            "nodes": [{ "id": "file", "node_type": node_type, "config": {} }],
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z",
+            "queue": "binary-projects"
+        })
+    };
+    let mut registry = nodes::default_registry();
+    registry.insert("write_file".into(), Arc::new(WriteFile));
+    registry.insert("read_file".into(), Arc::new(ReadFile));
+    let executor = WorkflowExecutor::new(app.pool.clone(), registry, ExecutorConfig::default())
+        .with_binary(Arc::new(app.binary.clone()));
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["binary-projects".into()]);
+
+    let (_, writer) = app.post("/api/v1/workflows", json!({ "name": "writer", "definition": definition("writer", "write_file") })).await;
+    app.post(&format!("/api/v1/workflows/{}/execute", writer["id"].as_str().unwrap()), json!({ "input": {} })).await;
+    let written = worker.run_next().await.unwrap().expect("a job").expect("the file is written");
+    let file = written.output["file"].clone();
+
+    // Readers in the writer's project and in another one, given the reference.
+    let mut runs = Vec::new();
+    for name in ["neighbour", "outsider"] {
+        let (_, reader) = app.post("/api/v1/workflows", json!({ "name": name, "definition": definition(name, "read_file") })).await;
+        let id = reader["id"].as_str().unwrap().to_owned();
+        app.post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": { "file": file } })).await;
+        runs.push(id);
+    }
+    let elsewhere = db::repository::projects::create_project(&app.pool, "binary elsewhere").await.unwrap().unwrap();
+    sqlx::query("UPDATE workflows SET project_id = $1 WHERE id = $2::uuid")
+        .bind(elsewhere.id)
+        .bind(&runs[1])
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let read = worker.run_next().await.unwrap().expect("a job").expect("the neighbour reads the file");
+    assert_eq!(read.output["text"], "hello, file");
+    let error = worker.run_next().await.unwrap().expect("a job").unwrap_err();
+    assert!(error.to_string().contains("does not exist"), "{error}");
+}
@@ -0,0 +1,58 @@
+//! `POST /api/v1/workflows/:id/clone` copies a workflow, and its secrets
+//! when asked.
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use uuid::Uuid;
+
+use engine::secrets::SecretsKey;
+
+use crate::harness::{TestApp, SECRETS_KEY};
+
+#[tokio::test]
+async fn clones_are_inactive_copies_with_secrets_on_request() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "signer",
+        "trigger": { "type": "manual" },
+        "nodes": [{
+            "id": "sign",
+            "node_type": "crypto",
+            "config": { "operation": "hmac", "value": "hello", "secret": "SIGNING_KEY" }
+        }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let create = json!({ "name": "signer", "definition": definition, "tags": ["billing"] });
+    let (_, original) = app.post("/api/v1/workflows", create).await;
+    let id = original["id"].as_str().unwrap();
+    let secret = json!({ "key": "SIGNING_KEY", "value": "k3y" });
+    let (status, _) = app.post(&format!("/api/v1/workflows/{id}/secrets"), secret).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, plain) = app.request(Method::POST, &format!("/api/v1/workflows/{id}/clone"), None).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let copy = &plain["workflow"];
+    assert_ne!(copy["id"], original["id"]);
+    assert_eq!((&copy["name"], &copy["active"], &copy["version"]), (&json!("signer (copy)"), &json!(false), &json!(1)));
+    assert_eq!((&copy["definition"], &copy["tags"]), (&original["definition"], &json!(["billing"])));
+    assert_eq!(plain["copied_secrets"], json!([]));
+    let (_, secrets) = app.get(&format!("/api/v1/workflows/{}/secrets", copy["id"].as_str().unwrap())).await;
+    assert_eq!(secrets, json!([]));
+
+    let request = json!({ "name": "signer v2", "active": true, "copy_secrets": true });
+    let (status, with_secrets) = app.post(&format!("/api/v1/workflows/{id}/clone"), request).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(with_secrets["copied_secrets"], json!(["SIGNING_KEY"]));
+    let copy = &with_secrets["workflow"];
+    assert_eq!((&copy["name"], &copy["active"]), (&json!("signer v2"), &json!(true)));
+    // Sealed for the copy, not the original.
+    let copy_id: Uuid = copy["id"].as_str().unwrap().parse().unwrap();
+    let stored = db::repository::secrets::list_secrets(&app.pool, copy_id).await.unwrap();
+    let key = SecretsKey::parse(SECRETS_KEY).unwrap();
+    assert_eq!(key.decrypt(copy_id, "SIGNING_KEY", &stored[0].encrypted_value).unwrap(), "k3y");
+
+    let (status, _) = app.post(&format!("/api/v1/workflows/{}/clone", Uuid::new_v4()), json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,98 @@
+//! Large execution payloads compressed at rest, and read back as they were;
+//! payloads that merely look compressed read back as they are.
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn large_payloads_are_stored_compressed_and_read_decompressed() {
+    let app = TestApp::start().await;
+    db::payloads::compress_above(Some(256));
+
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "verbose import",
+        "trigger": { "type": "webhook", "path": "it-compressed" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (status, _) = app.post("/api/v1/workflows", json!({ "name": "verbose import", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let lines: Vec<_> = (0..100).map(|n| json!({ "line": n, "sku": "WIDGET-STANDARD", "status": "backordered" })).collect();
+    let body = json!({ "customer": { "email": "verbose@example.com" }, "lines": lines });
+    let (status, _) = app.post("/webhook/it-compressed", body.clone()).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let large = app.run_next_job().await.expect("a queued job").expect("execution succeeds");
+    let (status, _) = app.post("/webhook/it-compressed", json!({ "small": true })).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let small = app.run_next_job().await.expect("a queued job").expect("execution succeeds");
+
+    let stored = |execution_id| {
+        sqlx::query_scalar::<_, String>(
+            "SELECT input::text FROM node_executions WHERE execution_id = $1
+             UNION ALL SELECT payload::text FROM job_queue WHERE execution_id = $1",
+        )
+        .bind(execution_id)
+        .fetch_all(&app.pool)
+    };
+    let rows = stored(large.execution_id).await.unwrap();
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|row| row.contains("$zstd") && !row.contains("verbose@example.com")), "{rows:?}");
+    let rows = stored(small.execution_id).await.unwrap();
+    assert!(rows.iter().all(|row| !row.contains("$zstd")), "small payloads are stored as they are: {rows:?}");
+
+    let detail = format!("/api/v1/executions/{}", large.execution_id);
+    let (status, execution) = app.get(&detail).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(execution["nodes"][0]["input"]["body"], body);
+
+    // Erasure finds the subject inside compressed payloads, and keeps them
+    // compressed.
+    let erasure = json!({ "path": "body.customer.email", "value": "verbose@example.com", "actor": "it" });
+    let (status, report) = app.post("/api/v1/privacy/erasure", erasure).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report["executions"].as_array().unwrap().len(), 1, "{report}");
+    let (_, execution) = app.get(&detail).await;
+    assert_eq!(execution["nodes"][0]["input"]["body"]["customer"]["email"], "[REDACTED]");
+    let rows = stored(large.execution_id).await.unwrap();
+    assert!(rows.iter().all(|row| row.contains("$zstd")), "{rows:?}");
+
+    // Stop compressing for the tests that follow; what was compressed
+    // still reads.
+    db::payloads::compress_above(None);
+}
+
+#[tokio::test]
+async fn payloads_shaped_like_compressed_ones_read_back_as_written() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "zstd lookalike",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (status, workflow) =
+        app.post("/api/v1/workflows", json!({ "name": "zstd lookalike", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let execute = format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap());
+    let lookalike = json!({ "$zstd": "KLUv/SAEIQAAe30=" });
+
+    for threshold in [None, Some(1)] {
+        db::payloads::compress_above(threshold);
+        let (status, _) = app.post(&execute, json!({ "input": lookalike })).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let result = app.run_next_job().await.expect("a queued job").expect("execution succeeds");
+        let (status, execution) = app.get(&format!("/api/v1/executions/{}", result.execution_id)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(execution["nodes"][0]["input"], lookalike, "compress_above {threshold:?}");
+        assert_eq!(execution["nodes"][0]["output"], lookalike, "compress_above {threshold:?}");
+    }
+
+    db::payloads::compress_above(None);
+}
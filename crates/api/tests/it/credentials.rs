@@ -0,0 +1,110 @@
+//! Shared credentials are write-only through the API, stored encrypted,
+//! and reach the nodes of every workflow in their project — but no other.
+
+use axum::body::Body;
+use axum::http::{HeaderMap, Method, Request, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+
+use engine::executor::ExecutorConfig;
+use engine::secrets::SecretsKey;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::{TestApp, SECRETS_KEY};
+
+/// A Jira stand-in whose new comments are the `Authorization` header they
+/// were added with.
+async fn jira() -> String {
+    let comment = |headers: HeaderMap| async move {
+        let authorization = headers.get("authorization").and_then(|v| v.to_str().ok()).unwrap_or_default();
+        Json(json!({ "id": authorization }))
+    };
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = Router::new().route("/rest/api/2/issue/OPS-1/comment", post(comment));
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    format!("http://{addr}")
+}
+
+fn in_project(method: Method, uri: &str, project: &str, body: Value) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("x-project", project)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn credentials_are_write_only_encrypted_and_shared_within_their_project() {
+    let app = TestApp::start().await;
+    let credentials = "/api/v1/credentials";
+
+    let token = json!({ "name": "jira-bot", "type": "api_token", "data": { "token": "t0ken" } });
+    let (status, created) = app.post(credentials, token.clone()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["type"], "api_token");
+    assert!(created.get("data").is_none() && !created.to_string().contains("t0ken"));
+    let id = created["id"].as_str().unwrap().to_owned();
+    let (status, _) = app.post(credentials, token).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, body) = app.post(credentials, json!({ "name": "x", "type": "kerberos", "data": {} })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{body}");
+    let stored = db::repository::credentials::get_credential(&app.pool, id.parse().unwrap()).await.unwrap();
+    assert!(!stored.encrypted_data.contains("t0ken"));
+
+    let basic = json!({ "name": "jira-bot", "type": "http_basic", "data": { "username": "bot", "password": "pw" } });
+    let (status, updated) = app.request(Method::PUT, &format!("{credentials}/{id}"), Some(basic)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["type"], "http_basic");
+    let (_, listed) = app.get(credentials).await;
+    assert!(listed.as_array().unwrap().iter().any(|row| row["id"] == id.as_str()));
+
+    // Two workflows use the credential; one in another project cannot.
+    let project = format!("creds-{}", uuid::Uuid::new_v4().simple());
+    let (status, _) = app.post("/api/v1/projects", json!({ "name": project })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = app.send(in_project(Method::GET, &format!("{credentials}/{id}"), &project, json!({}))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let base_url = jira().await;
+    let definition = |name: &str| {
+        json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": name,
+            "trigger": { "type": "manual" },
+            "nodes": [{
+                "id": "comment",
+                "node_type": "jira",
+                "config": { "base_url": base_url, "credential_id": id, "operation": "add_comment", "issue": "OPS-1", "body": "hi" }
+            }],
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z",
+            "queue": "credentials"
+        })
+    };
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default())
+        .with_secrets_key(SecretsKey::parse(SECRETS_KEY).unwrap());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["credentials".into()]);
+    for name in ["first", "second"] {
+        let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": name, "definition": definition(name) })).await;
+        app.post(&format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap()), json!({ "input": {} })).await;
+        let result = worker.run_next().await.unwrap().expect("a job").expect("the execution succeeds");
+        assert_eq!(result.output["comment_id"], "Basic Ym90OnB3");
+    }
+
+    let create = json!({ "name": "elsewhere", "definition": definition("elsewhere") });
+    let (_, workflow) = app.send(in_project(Method::POST, "/api/v1/workflows", &project, create)).await;
+    let execute = format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap());
+    app.send(in_project(Method::POST, &execute, &project, json!({ "input": {} }))).await;
+    let error = worker.run_next().await.unwrap().expect("a job").expect_err("the credential is out of reach");
+    assert!(error.to_string().contains("missing credential"), "{error}");
+
+    let (status, _) = app.request(Method::DELETE, &format!("{credentials}/{id}"), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = app.request(Method::DELETE, &format!("{credentials}/{id}"), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,84 @@
+//! `GET /api/v1/stats` sums up a project for an operations dashboard.
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+
+use engine::executor::ExecutorConfig;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::TestApp;
+
+/// A request in the `dashboard` project, so other tests' workflows stay
+/// out of the totals.
+fn request(method: Method, uri: &str, body: Option<Value>) -> Request<Body> {
+    let request = Request::builder().method(method).uri(uri).header("x-project", "dashboard");
+    match body {
+        Some(json) => request.header(header::CONTENT_TYPE, "application/json").body(Body::from(json.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap()
+}
+
+#[tokio::test]
+async fn stats_sum_up_the_project() {
+    let app = TestApp::start().await;
+    let (status, _) = app.post("/api/v1/projects", json!({ "name": "dashboard" })).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, empty) = app.send(request(Method::GET, "/api/v1/stats", None)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(empty["workflows"], json!({ "total": 0, "active": 0 }));
+    assert_eq!((&empty["failure_rate"], &empty["busiest_workflows"]), (&json!(null), &json!([])));
+
+    let mut ids = Vec::new();
+    for (name, active) in [("busy", true), ("quiet", true), ("off", false)] {
+        let definition = json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": name,
+            "trigger": { "type": "manual" },
+            "nodes": [{
+                "id": "check",
+                "node_type": "validate_json",
+                "config": { "schema": { "type": "object", "required": ["order"] } }
+            }],
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z",
+            "queue": "dashboard"
+        });
+        let create = json!({ "name": name, "definition": definition, "active": active });
+        let (status, workflow) = app.send(request(Method::POST, "/api/v1/workflows", Some(create))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        ids.push(workflow["id"].as_str().unwrap().to_owned());
+    }
+    let runs = [
+        (&ids[0], json!({ "order": 1 })),
+        (&ids[0], json!({})),
+        (&ids[0], json!({ "order": 2 })),
+        (&ids[1], json!({ "order": 3 })),
+    ];
+    for (id, input) in runs {
+        let uri = format!("/api/v1/workflows/{id}/execute");
+        let (status, _) = app.send(request(Method::POST, &uri, Some(json!({ "input": input })))).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+
+    let (_, queued) = app.send(request(Method::GET, "/api/v1/stats", None)).await;
+    assert_eq!(queued["queue_backlog"]["pending"], 4);
+    assert_eq!(queued["executions_today"]["in_flight"], 4);
+
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["dashboard".into()]);
+    while worker.run_next().await.unwrap().is_some() {}
+
+    let (_, stats) = app.send(request(Method::GET, "/api/v1/stats", None)).await;
+    assert_eq!(stats["workflows"], json!({ "total": 3, "active": 2 }));
+    assert_eq!(stats["executions_today"], json!({ "total": 4, "succeeded": 3, "failed": 1, "in_flight": 0 }));
+    assert_eq!(stats["failure_rate"], 0.25);
+    assert_eq!(stats["queue_backlog"]["pending"], 0);
+    let busiest = stats["busiest_workflows"].as_array().unwrap();
+    assert_eq!(busiest.len(), 2);
+    assert_eq!(busiest[0], json!({ "workflow_id": ids[0], "name": "busy", "executions": 3, "failed": 1 }));
+    assert_eq!((&busiest[1]["name"], &busiest[1]["executions"]), (&json!("quiet"), &json!(1)));
+}
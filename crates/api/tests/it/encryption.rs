@@ -0,0 +1,114 @@
+//! Execution payloads encrypted at rest, and read back in the clear; payloads
+//! that merely look encrypted read back as they are.
+
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use engine::secrets::SecretsKey;
+
+use crate::harness::{TestApp, SECRETS_KEY};
+
+#[tokio::test]
+async fn payloads_are_stored_encrypted_and_read_decrypted() {
+    let app = TestApp::start().await;
+    db::payloads::install(Arc::new(SecretsKey::parse(SECRETS_KEY).unwrap()), true);
+
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "sealed signup",
+        "trigger": { "type": "webhook", "path": "it-sealed" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (status, _) = app.post("/api/v1/workflows", json!({ "name": "sealed signup", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = app.post("/webhook/it-sealed", json!({ "customer": { "email": "sealed@example.com" } })).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let result = app.run_next_job().await.expect("a queued job").expect("execution succeeds");
+
+    let stored: Vec<String> = sqlx::query_scalar(
+        "SELECT input::text FROM node_executions WHERE execution_id = $1
+         UNION ALL SELECT payload::text FROM job_queue WHERE execution_id = $1",
+    )
+    .bind(result.execution_id)
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(stored.len(), 2);
+    assert!(stored.iter().all(|row| row.contains("$sealed") && !row.contains("sealed@example.com")), "{stored:?}");
+
+    let detail = format!("/api/v1/executions/{}", result.execution_id);
+    let (status, execution) = app.get(&detail).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(execution["nodes"][0]["input"]["body"]["customer"]["email"], "sealed@example.com");
+
+    // Erasure finds the subject inside encrypted payloads, and keeps them
+    // encrypted.
+    let erasure = json!({ "path": "body.customer.email", "value": "sealed@example.com", "actor": "it" });
+    let (status, report) = app.post("/api/v1/privacy/erasure", erasure).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report["executions"].as_array().unwrap().len(), 1, "{report}");
+    assert_eq!(report["executions"][0]["execution_id"], json!(result.execution_id));
+    let (_, execution) = app.get(&detail).await;
+    assert_eq!(execution["nodes"][0]["input"]["body"]["customer"]["email"], "[REDACTED]");
+    let input: String = sqlx::query_scalar("SELECT input::text FROM node_executions WHERE execution_id = $1")
+        .bind(result.execution_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert!(input.contains("$sealed"), "{input}");
+
+    // Stop sealing for the tests that follow; what was sealed still opens.
+    db::payloads::install(Arc::new(SecretsKey::parse(SECRETS_KEY).unwrap()), false);
+    assert!(!db::payloads::sealing());
+}
+
+#[tokio::test]
+async fn payloads_shaped_like_sealed_ones_read_back_as_written() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "lookalike",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (status, workflow) = app.post("/api/v1/workflows", json!({ "name": "lookalike", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let execute = format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap());
+    let lookalike = json!({ "$sealed": "not sealed at all" });
+
+    // Neither sealing nor a key, then sealing: both read back literally.
+    let mut executions = Vec::new();
+    for seal in [false, true] {
+        if seal {
+            db::payloads::install(Arc::new(SecretsKey::parse(SECRETS_KEY).unwrap()), true);
+        }
+        let (status, _) = app.post(&execute, json!({ "input": lookalike })).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let result = app.run_next_job().await.expect("a queued job").expect("execution succeeds");
+        let (status, execution) = app.get(&format!("/api/v1/executions/{}", result.execution_id)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(execution["nodes"][0]["input"], lookalike);
+        executions.push(result.execution_id);
+    }
+
+    // A sealed payload only opens for the execution it was sealed for.
+    sqlx::query(
+        "UPDATE node_executions SET input = (SELECT input FROM node_executions WHERE execution_id = $2)
+         WHERE execution_id = $1",
+    )
+    .bind(executions[0])
+    .bind(executions[1])
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    let (status, _) = app.get(&format!("/api/v1/executions/{}", executions[0])).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+    db::payloads::install(Arc::new(SecretsKey::parse(SECRETS_KEY).unwrap()), false);
+}
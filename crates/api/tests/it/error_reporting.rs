@@ -0,0 +1,88 @@
+//! Fatal node errors are reported to Sentry and to an error webhook,
+//! tagged with their workflow, execution, and node.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::routing::post;
+use axum::Router;
+use serde_json::{json, Value};
+
+use engine::error_reporting::ErrorReporter;
+use engine::executor::ExecutorConfig;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::TestApp;
+
+type Received = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+
+/// A Sentry and webhook stand-in keeping the requests it is sent.
+async fn receiver() -> (String, Received) {
+    let received = Received::default();
+    let keep = |State(received): State<Received>, headers: HeaderMap, body: String| async move {
+        received.lock().unwrap().push((headers, body));
+    };
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = Router::new()
+        .route("/api/:project/envelope/", post(keep))
+        .route("/hooks/errors", post(keep))
+        .with_state(received.clone());
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    (format!("127.0.0.1:{}", addr.port()), received)
+}
+
+#[tokio::test]
+async fn fatal_node_errors_are_reported_with_their_tags() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "reported",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": { "required": ["order"] } } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "reported"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "reported", "definition": definition })).await;
+    let (_, job) = app
+        .post(&format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap()), json!({ "input": {} }))
+        .await;
+
+    let (host, received) = receiver().await;
+    let reporter = ErrorReporter::new("it-worker")
+        .with_sentry(&format!("http://public@{host}/7"))
+        .unwrap()
+        .with_webhook(&format!("http://{host}/hooks/errors"))
+        .with_environment("staging");
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default())
+        .with_error_reporter(reporter);
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["reported".into()]);
+    assert!(worker.run_next().await.unwrap().expect("a job").is_err());
+
+    for _ in 0..100 {
+        if received.lock().unwrap().len() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 2, "one Sentry event and one webhook post");
+
+    let (headers, envelope) = &received[0];
+    assert!(headers["x-sentry-auth"].to_str().unwrap().contains("sentry_key=public"));
+    let event: Value = serde_json::from_str(envelope.lines().nth(2).unwrap()).unwrap();
+    assert_eq!(event["tags"]["kind"], "node_fatal");
+    assert_eq!(event["tags"]["workflow_id"], workflow["id"]);
+    assert_eq!(event["tags"]["execution_id"], job["execution_id"]);
+    assert_eq!(event["tags"]["node_id"], "check");
+
+    let report: Value = serde_json::from_str(&received[1].1).unwrap();
+    assert_eq!((&report["kind"], &report["node_id"]), (&json!("node_fatal"), &json!("check")));
+    assert_eq!(report["execution_id"], job["execution_id"]);
+    assert_eq!((&report["service"], &report["environment"]), (&json!("it-worker"), &json!("staging")));
+    assert!(report["message"].as_str().unwrap().contains("order"));
+}
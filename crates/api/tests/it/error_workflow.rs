@@ -0,0 +1,120 @@
+//! A failing execution starts its workflow's error workflow, and so can a
+//! node running far slower than its baseline.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use engine::executor::ExecutorConfig;
+use engine::slow_nodes::SlowNodeConfig;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+use nodes::traits::ExecutionContext;
+use nodes::{ExecutableNode, NodeError};
+
+use crate::harness::TestApp;
+
+fn definition(name: &str, nodes: Value, extra: Value) -> Value {
+    let mut definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": name,
+        "trigger": { "type": "manual" },
+        "nodes": nodes,
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    definition.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+    definition
+}
+
+#[tokio::test]
+async fn failed_execution_starts_the_error_workflow() {
+    let app = TestApp::start().await;
+
+    let on_error = definition("on error", json!([{ "id": "caught", "node_type": "error_trigger", "config": null }]), json!({}));
+    let (status, on_error) = app.post("/api/v1/workflows", json!({ "name": "on error", "definition": on_error })).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let strict = definition(
+        "strict",
+        json!([{
+            "id": "check",
+            "node_type": "validate_json",
+            "config": { "schema": { "type": "object", "required": ["order"] } }
+        }]),
+        json!({ "error_workflow_id": on_error["id"] }),
+    );
+    let (_, strict) = app.post("/api/v1/workflows", json!({ "name": "strict", "definition": strict })).await;
+    let (status, job) = app
+        .post(&format!("/api/v1/workflows/{}/execute", strict["id"].as_str().unwrap()), json!({ "input": {} }))
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    assert!(app.run_next_job().await.expect("the strict run").is_err());
+    let caught = app.run_next_job().await.expect("the error run").expect("error workflow succeeds");
+
+    assert_eq!(caught.output["workflow_id"], strict["id"]);
+    assert_eq!(caught.output["workflow_name"], "strict");
+    assert_eq!(caught.output["execution_id"], job["execution_id"]);
+    assert_eq!(caught.output["node_id"], "check");
+    assert!(caught.output["message"].as_str().unwrap().contains("order"));
+
+    let (status, failed) = app.get(&format!("/api/v1/executions/{}", job["execution_id"].as_str().unwrap())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(failed["execution"]["status"], "failed");
+    assert_eq!((&failed["nodes"][0]["node_id"], &failed["nodes"][0]["status"]), (&json!("check"), &json!("failed")));
+    assert!(failed["nodes"][0]["error"].as_str().unwrap().contains("order"));
+    assert!(failed["nodes"][0]["duration_ms"].as_i64().unwrap() >= 0);
+
+    let (_, detail) = app.get(&format!("/api/v1/support/executions/{}", caught.execution_id)).await;
+    assert_eq!(detail["execution"]["parent_execution_id"], job["execution_id"]);
+}
+
+/// Sleeps for `input.ms` milliseconds.
+struct Sleep;
+
+#[async_trait]
+impl ExecutableNode for Sleep {
+    async fn execute(&self, input: Value, _ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        tokio::time::sleep(Duration::from_millis(input["ms"].as_u64().unwrap_or(0))).await;
+        Ok(input)
+    }
+}
+
+#[tokio::test]
+async fn slow_node_starts_the_error_workflow_when_asked() {
+    let app = TestApp::start().await;
+    let on_slow = definition("on slow", json!([{ "id": "caught", "node_type": "error_trigger", "config": null }]), json!({}));
+    let (_, on_slow) = app.post("/api/v1/workflows", json!({ "name": "on slow", "definition": on_slow })).await;
+    let sleepy = definition(
+        "sleepy",
+        json!([{ "id": "call", "node_type": "sleep", "config": {} }]),
+        json!({ "error_workflow_id": on_slow["id"], "slow_node_alerts": true, "queue": "slow-nodes" }),
+    );
+    let (_, sleepy) = app.post("/api/v1/workflows", json!({ "name": "sleepy", "definition": sleepy })).await;
+    let execute = format!("/api/v1/workflows/{}/execute", sleepy["id"].as_str().unwrap());
+
+    let mut registry = nodes::default_registry();
+    registry.insert("sleep".into(), Arc::new(Sleep));
+    let config = SlowNodeConfig { factor: 3.0, min_samples: 3, refresh: Duration::ZERO, ..Default::default() };
+    let executor = WorkflowExecutor::new(app.pool.clone(), registry, ExecutorConfig::default())
+        .with_slow_node_detection(config);
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["slow-nodes".into()]);
+
+    for _ in 0..4 {
+        app.post(&execute, json!({ "input": { "ms": 20 } })).await;
+        worker.run_next().await.unwrap().expect("a job").expect("the run succeeds");
+    }
+    assert!(worker.run_next().await.unwrap().is_none(), "runs within the baseline raise nothing");
+
+    let (_, job) = app.post(&execute, json!({ "input": { "ms": 300 } })).await;
+    let slow = worker.run_next().await.unwrap().expect("a job").expect("a slow run still succeeds");
+    assert_eq!(slow.execution_id.to_string(), job["execution_id"].as_str().unwrap());
+    let caught = worker.run_next().await.unwrap().expect("the error run").expect("error workflow succeeds");
+    assert_eq!(caught.output["kind"], "slow");
+    assert_eq!((&caught.output["execution_id"], &caught.output["node_id"]), (&job["execution_id"], &json!("call")));
+    assert!(caught.output["message"].as_str().unwrap().contains("p95"), "{}", caught.output);
+}
@@ -0,0 +1,378 @@
+//! Execution listings filter and page across workflows.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+
+use engine::executor::ExecutorConfig;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+use nodes::traits::ExecutionContext;
+use nodes::{ExecutableNode, NodeError};
+
+use crate::harness::TestApp;
+
+/// Create a workflow on the `listing` queue and return its id.
+async fn create(app: &TestApp, name: &str, trigger: Value) -> String {
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": name,
+        "trigger": trigger,
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "listing"
+    });
+    let (status, workflow) = app.post("/api/v1/workflows", json!({ "name": name, "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    workflow["id"].as_str().unwrap().to_owned()
+}
+
+fn names(page: &Value) -> Vec<&str> {
+    page["executions"].as_array().unwrap().iter().map(|e| e["workflow_name"].as_str().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn executions_are_listed_with_filters_and_pages() {
+    let app = TestApp::start().await;
+    let nightly = create(&app, "nightly export", json!({ "type": "manual" })).await;
+    let hook = create(&app, "listing hook", json!({ "type": "webhook", "path": "listing-hook" })).await;
+    for _ in 0..3 {
+        app.post(&format!("/api/v1/workflows/{nightly}/execute"), json!({ "input": {} })).await;
+    }
+    app.post(&format!("/api/v1/workflows/{hook}/execute"), json!({ "input": {} })).await;
+
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["listing".to_owned()]);
+    worker.run_next().await.unwrap().expect("a job").expect("execution succeeds");
+
+    let runs = format!("/api/v1/workflows/{nightly}/executions");
+    let (status, first) = app.get(&format!("{runs}?limit=2")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((names(&first).len(), first["next_offset"].as_i64()), (2, Some(2)));
+    let (_, second) = app.get(&format!("{runs}?limit=2&offset=2")).await;
+    assert_eq!((names(&second), &second["next_offset"]), (vec!["nightly export"], &Value::Null));
+
+    let (_, succeeded) = app.get(&format!("{runs}?status=succeeded")).await;
+    assert_eq!(names(&succeeded).len(), 1);
+    let (_, later) = app.get(&format!("{runs}?started_after=2999-01-01T00:00:00Z")).await;
+    assert!(names(&later).is_empty());
+
+    let (_, webhooks) = app.get("/api/v1/executions?trigger=webhook&limit=500").await;
+    let webhooks = names(&webhooks);
+    assert!(webhooks.contains(&"listing hook") && !webhooks.contains(&"nightly export"));
+
+    let (status, _) = app.get("/api/v1/workflows/00000000-0000-0000-0000-000000000001/executions").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    while worker.run_next().await.unwrap().is_some() {}
+}
+
+#[tokio::test]
+async fn business_keys_find_runs_across_a_projects_workflows() {
+    let app = TestApp::start().await;
+    let in_project = |method: Method, uri: &str, project: &str, body: Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-project", project)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let project = json!({ "name": "orders-elsewhere" });
+    let (status, _) = app.send(in_project(Method::POST, "/api/v1/projects", "default", project)).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // Each project gets workflows tagging their runs with the order they
+    // dealt with; only the first two share a project.
+    let mut execute = Vec::new();
+    for (name, project) in [("take order", "default"), ("ship order", "default"), ("audit order", "orders-elsewhere")] {
+        let definition = json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": name,
+            "trigger": { "type": "manual" },
+            "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z",
+            "queue": "listing",
+            "business_key": "order-{{ input.order_id }}"
+        });
+        let create = json!({ "name": name, "definition": definition });
+        let (status, workflow) = app.send(in_project(Method::POST, "/api/v1/workflows", project, create)).await;
+        assert_eq!(status, StatusCode::CREATED, "{workflow}");
+        execute.push((format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap()), project));
+    }
+    let runs = [
+        (0, json!({ "order_id": 7 })),
+        (0, json!({ "order_id": 8 })),
+        (1, json!({ "order_id": 7 })),
+        (1, json!({})),
+        (2, json!({ "order_id": 7 })),
+    ];
+    for (workflow, input) in runs {
+        let (uri, project) = &execute[workflow];
+        let (status, _) = app.send(in_project(Method::POST, uri, project, json!({ "input": input }))).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+
+    let (status, order) = app.get("/api/v1/executions?business_key=order-7").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((&order["business_key"], &order["total"]), (&json!("order-7"), &json!(2)));
+    assert_eq!(order["by_status"], json!({ "pending": 2 }));
+    let mut workflows: Vec<_> = order["workflows"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|w| (w["workflow_name"].as_str().unwrap(), w["runs"].as_i64().unwrap()))
+        .collect();
+    workflows.sort_unstable();
+    assert_eq!(workflows, [("ship order", 1), ("take order", 1)]);
+    assert!(order["executions"].as_array().unwrap().iter().all(|e| e["business_key"] == "order-7"), "{order}");
+
+    // The other project sees only its own run; a key nothing rendered to
+    // finds nothing, and an empty one is refused.
+    let elsewhere = Request::builder()
+        .uri("/api/v1/executions?business_key=order-7")
+        .header("x-project", "orders-elsewhere")
+        .body(Body::empty())
+        .unwrap();
+    let (_, elsewhere) = app.send(elsewhere).await;
+    assert_eq!(names(&elsewhere), ["audit order"]);
+    let (_, missing) = app.get("/api/v1/executions?business_key=order-").await;
+    assert_eq!(missing["total"], 0);
+    assert_eq!(app.get("/api/v1/executions?business_key=").await.0, StatusCode::BAD_REQUEST);
+
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["listing".to_owned()]);
+    while worker.run_next().await.unwrap().is_some() {}
+}
+
+#[tokio::test]
+async fn failed_executions_are_retried_from_the_start_or_the_failed_node() {
+    let app = TestApp::start().await;
+    let node = |id: &str, schema: Value| json!({ "id": id, "node_type": "validate_json", "config": { "schema": schema } });
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "retried",
+        "trigger": { "type": "manual" },
+        "nodes": [node("first", json!({})), node("check", json!({ "required": ["order"] }))],
+        "edges": [{ "from": "first", "to": "check" }],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "retries"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "retried", "definition": definition })).await;
+    let workflow_id = workflow["id"].as_str().unwrap();
+    let (_, job) = app.post(&format!("/api/v1/workflows/{workflow_id}/execute"), json!({ "input": { "id": 7 } })).await;
+    let retry = format!("/api/v1/executions/{}/retry", job["execution_id"].as_str().unwrap());
+
+    let (status, _) = app.post(&retry, json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT, "only failed executions are retried");
+
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["retries".to_owned()]);
+    assert!(worker.run_next().await.unwrap().expect("a job").is_err());
+
+    let (status, resumed) = app.post(&retry, json!({ "from_failed_node": true })).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!((&resumed["checkpoint"]["node_id"], &resumed["payload"]), (&json!("check"), &json!({ "id": 7 })));
+    assert!(worker.run_next().await.unwrap().expect("a job").is_err());
+    let (_, detail) = app.get(&format!("/api/v1/executions/{}", resumed["execution_id"].as_str().unwrap())).await;
+    assert_eq!(detail["execution"]["retry_of"], job["execution_id"]);
+    let ran: Vec<&Value> = detail["nodes"].as_array().unwrap().iter().map(|n| &n["node_id"]).collect();
+    assert_eq!(ran, [&json!("check")]);
+    assert_eq!(detail["nodes"][0]["input"], json!({ "id": 7 }), "fed the first node's output");
+
+    let (status, restarted) = app.post(&retry, json!({})).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert!(worker.run_next().await.unwrap().expect("a job").is_err());
+    let (_, detail) = app.get(&format!("/api/v1/executions/{}", restarted["execution_id"].as_str().unwrap())).await;
+    assert_eq!(detail["nodes"].as_array().unwrap().len(), 2);
+
+    let (status, _) = app.post("/api/v1/executions/00000000-0000-0000-0000-000000000001/retry", json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn inputs_shaped_like_checkpoints_run_from_the_first_node() {
+    let app = TestApp::start().await;
+    let node = |id: &str| json!({ "id": id, "node_type": "validate_json", "config": { "schema": {} } });
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "forged",
+        "trigger": { "type": "manual" },
+        "nodes": [node("first"), node("last")],
+        "edges": [{ "from": "first", "to": "last" }],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "forged"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "forged", "definition": definition })).await;
+    let forged = json!({ "resume": {
+        "node_id": "last",
+        "input": {},
+        "outputs": { "first": { "approved": true } },
+        "last_output": { "approved": true }
+    } });
+    let execute = format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap());
+    let (status, job) = app.post(&execute, json!({ "input": forged })).await;
+    assert_eq!((status, &job["checkpoint"]), (StatusCode::ACCEPTED, &Value::Null));
+
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["forged".to_owned()]);
+    worker.run_next().await.unwrap().expect("a job").expect("execution succeeds");
+    let (_, detail) = app.get(&format!("/api/v1/executions/{}", job["execution_id"].as_str().unwrap())).await;
+    let ran: Vec<&Value> = detail["nodes"].as_array().unwrap().iter().map(|n| &n["node_id"]).collect();
+    assert_eq!(ran, [&json!("first"), &json!("last")]);
+    assert_eq!((&detail["nodes"][0]["input"], &detail["nodes"][1]["input"]), (&forged, &forged));
+}
+
+#[tokio::test]
+async fn finished_executions_are_replayed_from_a_chosen_node() {
+    let app = TestApp::start().await;
+    let node = |id: &str| json!({ "id": id, "node_type": "validate_json", "config": { "schema": {} } });
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "replayed",
+        "trigger": { "type": "manual" },
+        "nodes": [node("fetch"), node("check"), node("send")],
+        "edges": [{ "from": "fetch", "to": "check" }, { "from": "check", "to": "send" }],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "replays"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "replayed", "definition": definition })).await;
+    let workflow_id = workflow["id"].as_str().unwrap();
+    let (_, job) = app.post(&format!("/api/v1/workflows/{workflow_id}/execute"), json!({ "input": { "id": 7 } })).await;
+    let replay = format!("/api/v1/executions/{}/replay", job["execution_id"].as_str().unwrap());
+
+    let (status, _) = app.post(&replay, json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT, "only finished executions are replayed");
+
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["replays".to_owned()]);
+    assert!(worker.run_next().await.unwrap().expect("a job").is_ok());
+
+    let (status, planned) = app.post(&replay, json!({ "from_node": "check", "dry_run": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(planned["plan"]["reused"], json!(["fetch"]));
+    assert_eq!(planned["plan"]["runs"], json!(["check", "send"]));
+    assert_eq!(planned["plan"]["input"], json!({ "id": 7 }));
+    assert!(planned.get("job").is_none());
+    assert!(worker.run_next().await.unwrap().is_none(), "a dry run queues nothing");
+
+    let (status, replayed) = app.post(&replay, json!({ "from_node": "check" })).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert!(worker.run_next().await.unwrap().expect("a job").is_ok());
+    let (_, detail) = app.get(&format!("/api/v1/executions/{}", replayed["job"]["execution_id"].as_str().unwrap())).await;
+    assert_eq!(detail["execution"]["retry_of"], job["execution_id"]);
+    let ran: Vec<&Value> = detail["nodes"].as_array().unwrap().iter().map(|n| &n["node_id"]).collect();
+    assert_eq!(ran, [&json!("check"), &json!("send")]);
+
+    let (status, body) = app.post(&replay, json!({ "from_node": "gone" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body["error"].as_str().unwrap().contains("'gone'"));
+}
+
+#[tokio::test]
+async fn finished_executions_are_deleted_one_by_one_or_in_bulk() {
+    let app = TestApp::start().await;
+    let workflow = create(&app, "pruned", json!({ "type": "manual" })).await;
+    let mut runs = Vec::new();
+    for _ in 0..3 {
+        let (_, job) = app.post(&format!("/api/v1/workflows/{workflow}/execute"), json!({ "input": {} })).await;
+        runs.push(format!("/api/v1/executions/{}", job["execution_id"].as_str().unwrap()));
+    }
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["listing".to_owned()]);
+    worker.run_next().await.unwrap().expect("a job").expect("execution succeeds");
+    worker.run_next().await.unwrap().expect("a job").expect("execution succeeds");
+
+    let (status, _) = app.request(Method::DELETE, &runs[2], None).await;
+    assert_eq!(status, StatusCode::CONFLICT, "still pending");
+    let (status, _) = app.request(Method::DELETE, &runs[0], None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(app.get(&runs[0]).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(app.request(Method::DELETE, &runs[0], None).await.0, StatusCode::NOT_FOUND);
+
+    let (status, _) = app.request(Method::DELETE, "/api/v1/executions", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "a filter is required");
+    let prune = format!("/api/v1/executions?workflow_id={workflow}");
+    let (_, none) = app.request(Method::DELETE, &format!("{prune}&before=2000-01-01T00:00:00Z"), None).await;
+    assert_eq!(none["deleted"], 0);
+    let (status, pruned) = app.request(Method::DELETE, &prune, None).await;
+    assert_eq!((status, &pruned["deleted"]), (StatusCode::OK, &json!(1)));
+    assert_eq!(app.get(&runs[1]).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(app.get(&runs[2]).await.0, StatusCode::OK);
+
+    while worker.run_next().await.unwrap().is_some() {}
+}
+
+/// Fails its first call with a retryable error.
+#[derive(Default)]
+struct FlakyOnce(AtomicBool);
+
+#[async_trait]
+impl ExecutableNode for FlakyOnce {
+    async fn execute(&self, input: Value, _ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        if self.0.swap(true, Ordering::SeqCst) {
+            Ok(input)
+        } else {
+            Err(NodeError::Retryable("upstream timed out".into()))
+        }
+    }
+}
+
+#[tokio::test]
+async fn execution_timelines_show_queueing_and_retried_attempts() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "timeline",
+        "trigger": { "type": "manual" },
+        "nodes": [
+            { "id": "check", "node_type": "validate_json", "config": { "schema": {} } },
+            { "id": "call", "node_type": "flaky_once", "config": {} }
+        ],
+        "edges": [{ "from": "check", "to": "call" }],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "timeline"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "timeline", "definition": definition })).await;
+    let (_, job) = app
+        .post(&format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap()), json!({ "input": {} }))
+        .await;
+    let timeline = format!("/api/v1/executions/{}/timeline", job["execution_id"].as_str().unwrap());
+
+    let (status, queued) = app.get(&timeline).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((&queued["status"], &queued["queue_ms"], &queued["nodes"]), (&json!("pending"), &Value::Null, &json!([])));
+
+    let mut registry = nodes::default_registry();
+    registry.insert("flaky_once".into(), Arc::new(FlakyOnce::default()));
+    let config = ExecutorConfig { max_retries: 2, retry_base_delay: Duration::from_millis(50) };
+    let executor = WorkflowExecutor::new(app.pool.clone(), registry, config);
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["timeline".to_owned()]);
+    worker.run_next().await.unwrap().expect("a job").expect("execution succeeds");
+
+    let (_, body) = app.get(&timeline).await;
+    assert_eq!(body["status"], "succeeded", "{body}");
+    assert!(body["queue_ms"].as_i64().unwrap() >= 0 && body["run_ms"].as_i64().is_some());
+    let nodes = body["nodes"].as_array().unwrap();
+    let ids: Vec<&Value> = nodes.iter().map(|n| &n["node_id"]).collect();
+    assert_eq!(ids, [&json!("check"), &json!("call")]);
+    assert_eq!(nodes[0]["attempts"].as_array().unwrap().len(), 1);
+    let attempts = nodes[1]["attempts"].as_array().unwrap();
+    assert_eq!(attempts.len(), 2, "{body}");
+    assert!(attempts[0]["error"].as_str().unwrap().contains("timed out"));
+    assert_eq!(attempts[1]["error"], Value::Null);
+    assert!(nodes[1]["retry_ms"].as_i64().unwrap() >= 50, "the backoff counts as retrying");
+    assert_eq!(body["retry_ms"], nodes[1]["retry_ms"]);
+    assert_eq!(body["slowest_node"], "call");
+
+    let (status, _) = app.get("/api/v1/executions/00000000-0000-0000-0000-000000000001/timeline").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,56 @@
+//! Feature flag overrides are audited under the caller's identity.
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+
+use api::auth::{ApiKey, Auth};
+
+use crate::harness::TestApp;
+
+/// A request of the `ops` admin.
+fn as_ops(method: Method, uri: &str, body: Option<Value>) -> Request<Body> {
+    let request = Request::builder().method(method).uri(uri).header(header::AUTHORIZATION, "Bearer 0ps");
+    match body {
+        Some(json) => request.header(header::CONTENT_TYPE, "application/json").body(Body::from(json.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap()
+}
+
+#[tokio::test]
+async fn flag_changes_are_audited_under_the_callers_identity() {
+    let keys = [ApiKey::parse("ops=0ps").unwrap()];
+    let app = TestApp::start_with_auth(Auth::new(keys.to_vec(), None).with_admins(["ops".to_owned()])).await;
+    let flag = "/api/v1/admin/feature-flags/it_audited_flag";
+
+    let set = json!({ "scope": "global", "enabled": true, "actor": "someone-else" });
+    let (status, row) = app.send(as_ops(Method::PUT, flag, Some(set))).await;
+    assert_eq!(status, StatusCode::OK, "{row}");
+    let (status, _) = app.send(as_ops(Method::DELETE, &format!("{flag}?scope=global&actor=someone-else"), None)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, log) = app.send(as_ops(Method::GET, "/api/v1/admin/audit-log?limit=10", None)).await;
+    assert_eq!(status, StatusCode::OK);
+    let entries: Vec<_> = log
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|entry| entry["details"]["flag"] == "it_audited_flag")
+        .map(|entry| (entry["action"].as_str().unwrap(), entry["actor"].as_str().unwrap()))
+        .collect();
+    assert_eq!(entries.len(), 2, "{log}");
+    assert!(entries.contains(&("feature_flag.set", "ops")) && entries.contains(&("feature_flag.remove", "ops")), "{log}");
+}
+
+#[tokio::test]
+async fn without_authentication_the_request_names_the_actor() {
+    let app = TestApp::start().await;
+    let flag = "/api/v1/admin/feature-flags/it_anonymous_flag";
+    let (status, _) = app.request(Method::PUT, flag, Some(json!({ "scope": "global", "enabled": true }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let set = json!({ "scope": "global", "enabled": true, "actor": "ops" });
+    assert_eq!(app.request(Method::PUT, flag, Some(set)).await.0, StatusCode::OK);
+    assert_eq!(app.request(Method::DELETE, &format!("{flag}?scope=global"), None).await.0, StatusCode::BAD_REQUEST);
+    assert_eq!(app.request(Method::DELETE, &format!("{flag}?scope=global&actor=ops"), None).await.0, StatusCode::NO_CONTENT);
+}
@@ -0,0 +1,83 @@
+//! `POST /api/v1/graphql` answers nested queries — a workflow, its recent
+//! executions, and their failed nodes — within the request's project.
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+
+use engine::executor::ExecutorConfig;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::TestApp;
+
+fn query(query: &str, project: Option<&str>) -> Request<Body> {
+    let mut request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/graphql")
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(project) = project {
+        request = request.header("x-project", project);
+    }
+    request.body(Body::from(json!({ "query": query }).to_string())).unwrap()
+}
+
+#[tokio::test]
+async fn nested_queries_reach_failed_nodes() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "graphql",
+        "trigger": { "type": "manual" },
+        "nodes": [{
+            "id": "check",
+            "node_type": "validate_json",
+            "config": { "schema": { "type": "object", "required": ["order"] } }
+        }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "graphql"
+    });
+    let (status, workflow) = app.post("/api/v1/workflows", json!({ "name": "graphql", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let workflow_id = workflow["id"].as_str().unwrap();
+    for input in [json!({ "order": 1 }), json!({})] {
+        let (status, _) = app.post(&format!("/api/v1/workflows/{workflow_id}/execute"), json!({ "input": input })).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["graphql".into()]);
+    while worker.run_next().await.unwrap().is_some() {}
+
+    let nested = format!(
+        r#"{{ workflow(id: "{workflow_id}") {{
+            name version
+            executions(limit: 5) {{ status nodeExecutions(status: "failed") {{ nodeId status error }} }}
+        }} }}"#
+    );
+    let (status, body) = app.send(query(&nested, None)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.get("errors"), None);
+    let found = &body["data"]["workflow"];
+    assert_eq!((&found["name"], &found["version"]), (&json!("graphql"), &json!(1)));
+    let mut runs: Vec<&Value> = found["executions"].as_array().unwrap().iter().collect();
+    runs.sort_by_key(|run| run["status"].as_str().unwrap().to_owned());
+    assert_eq!(runs[0]["status"], "failed");
+    let failed = runs[0]["nodeExecutions"].as_array().unwrap();
+    assert_eq!((failed.len(), &failed[0]["nodeId"]), (1, &json!("check")));
+    assert!(failed[0]["error"].as_str().unwrap().contains("order"));
+    assert_eq!((&runs[1]["status"], &runs[1]["nodeExecutions"]), (&json!("succeeded"), &json!([])));
+
+    let (_, body) = app.send(query(r#"{ executions(status: "failed") { workflow { name } } }"#, None)).await;
+    let names: Vec<&Value> = body["data"]["executions"].as_array().unwrap().iter().map(|e| &e["workflow"]["name"]).collect();
+    assert!(names.contains(&&json!("graphql")), "{body}");
+
+    // Other projects do not see the workflow.
+    let (status, _) = app.post("/api/v1/projects", json!({ "name": "graphql-elsewhere" })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (_, body) = app.send(query(&nested, Some("graphql-elsewhere"))).await;
+    assert_eq!(body["data"]["workflow"], Value::Null);
+
+    let (_, body) = app.send(query("{ nope }", None)).await;
+    assert!(body["errors"][0]["message"].as_str().unwrap().contains("nope"));
+}
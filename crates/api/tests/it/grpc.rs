@@ -0,0 +1,101 @@
+//! The gRPC management service creates and runs workflows and streams
+//! execution events, on the API's port and under its authentication.
+
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use serde_json::json;
+use tonic::metadata::MetadataValue;
+use tonic::transport::Channel;
+use tonic::{Code, Request};
+
+use api::auth::{ApiKey, Auth};
+use api::grpc::proto::management_client::ManagementClient;
+use api::grpc::proto::{
+    CreateWorkflowRequest, ExecuteWorkflowRequest, GetExecutionRequest, GetWorkflowRequest, ListWorkflowsRequest,
+    StreamExecutionEventsRequest,
+};
+use api::rbac::Role;
+use engine::executor::ExecutorConfig;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::TestApp;
+
+fn authorized<T>(message: T, key: &str) -> Request<T> {
+    let mut request = Request::new(message);
+    let bearer: MetadataValue<_> = format!("Bearer {key}").parse().unwrap();
+    request.metadata_mut().insert("authorization", bearer);
+    request
+}
+
+#[tokio::test]
+async fn workflows_are_created_run_and_watched_over_grpc() {
+    let keys = ["automation=gRpc", "auditor=l00k"].map(|entry| ApiKey::parse(entry).unwrap());
+    let auth = Auth::new(keys.to_vec(), None).with_admins(["automation".to_owned()]).with_default_role(Some(Role::Viewer));
+    let app = TestApp::start_with_auth(auth).await;
+    let addr = app.serve().await;
+    let channel = Channel::from_shared(format!("http://{addr}")).unwrap().connect().await.expect("connect");
+    let mut client = ManagementClient::new(channel);
+
+    let status = client.list_workflows(ListWorkflowsRequest::default()).await.unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "grpc",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "grpc"
+    });
+    let create = CreateWorkflowRequest {
+        name: "grpc".into(),
+        definition_json: definition.to_string(),
+        active: None,
+        tags: vec!["Ops".into()],
+    };
+    let status = client.create_workflow(authorized(create.clone(), "l00k")).await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+    let invalid = CreateWorkflowRequest { definition_json: "{}".into(), ..create.clone() };
+    let status = client.create_workflow(authorized(invalid, "gRpc")).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let workflow = client.create_workflow(authorized(create, "gRpc")).await.unwrap().into_inner();
+    assert_eq!((workflow.name.as_str(), workflow.version, workflow.active), ("grpc", 1, true));
+    assert_eq!(workflow.tags, ["ops"]);
+    let read = client.get_workflow(authorized(GetWorkflowRequest { id: workflow.id.clone() }, "l00k")).await.unwrap();
+    assert_eq!(read.into_inner(), workflow);
+    let listed = client.list_workflows(authorized(ListWorkflowsRequest::default(), "l00k")).await.unwrap();
+    assert!(listed.into_inner().workflows.contains(&workflow));
+
+    let watch = StreamExecutionEventsRequest { workflow_id: Some(workflow.id.clone()), ..Default::default() };
+    let mut events = client.stream_execution_events(authorized(watch, "l00k")).await.unwrap().into_inner();
+
+    let execute =
+        ExecuteWorkflowRequest { workflow_id: workflow.id.clone(), input_json: String::new(), max_attempts: None };
+    let queued = client.execute_workflow(authorized(execute, "gRpc")).await.unwrap().into_inner();
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["grpc".into()]);
+    while worker.run_next().await.unwrap().is_some() {}
+
+    let mut statuses = Vec::new();
+    while statuses.last().map(String::as_str) != Some("succeeded") {
+        let event = tokio::time::timeout(Duration::from_secs(10), events.next()).await.expect("event in time");
+        let event = event.expect("stream open").unwrap();
+        assert_eq!((&event.execution_id, &event.workflow_id), (&queued.execution_id, &workflow.id));
+        statuses.push(event.status);
+    }
+    assert_eq!(statuses, ["pending", "running", "succeeded"]);
+
+    let execution = client
+        .get_execution(authorized(GetExecutionRequest { id: queued.execution_id.clone() }, "l00k"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!((execution.status.as_str(), execution.finished_at.is_some()), ("succeeded", true));
+
+    let unknown = GetExecutionRequest { id: uuid::Uuid::new_v4().to_string() };
+    assert_eq!(client.get_execution(authorized(unknown, "l00k")).await.unwrap_err().code(), Code::NotFound);
+}
@@ -0,0 +1,219 @@
+//! Test app: migrated Postgres, the API router, and a one-job worker step.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{HeaderMap, Method, Request, StatusCode};
+use axum::Router;
+use serde_json::Value;
+use uuid::Uuid;
+use testcontainers_modules::postgres::Postgres;
+use testcontainers_modules::testcontainers::runners::AsyncRunner;
+use testcontainers_modules::testcontainers::ContainerAsync;
+use tower::ServiceExt;
+
+use api::auth::Auth;
+use api::limits::BodyLimits;
+use api::live::LiveUpdates;
+use api::AppState;
+use db::changes::{self, Change};
+use db::DbPool;
+use engine::binary::BinaryData;
+use engine::executor::{ExecutionResult, ExecutorConfig};
+use engine::worker::Worker;
+use engine::secrets::SecretsKey;
+use engine::{Automation, EngineError, FeatureFlags, Readiness, WorkflowExecutor};
+use queue::PgJobQueue;
+
+/// Redact whatever differs between runs.
+#[macro_export]
+macro_rules! assert_redacted_snapshot {
+    ($name:expr, $value:expr) => {
+        insta::assert_json_snapshot!($name, $value, {
+            ".id" => "[id]",
+            ".definition.id" => "[id]",
+            ".execution.id" => "[id]",
+            ".execution.workflow_id" => "[id]",
+            ".node_executions[].id" => "[id]",
+            ".node_executions[].execution_id" => "[id]",
+            ".**.created_at" => "[timestamp]",
+            ".**.updated_at" => "[timestamp]",
+            ".**.started_at" => "[timestamp]",
+            ".**.finished_at" => "[timestamp]",
+            ".**.run_at" => "[timestamp]",
+            ".**[\"x-request-id\"]" => "[request id]",
+        })
+    };
+}
+
+/// Key the app encrypts secrets with.
+pub const SECRETS_KEY: &str = "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=";
+
+pub struct TestApp {
+    /// Where the test database is, for tests that need pools of their own.
+    pub database_url: String,
+    pub pool: DbPool,
+    /// The app's read pool: the same database, in read-only sessions.
+    pub read_pool: DbPool,
+    /// The app's binary data store, kept in `binary_dir`.
+    pub binary: BinaryData,
+    pub binary_dir: PathBuf,
+    router: Router,
+    _postgres: Option<ContainerAsync<Postgres>>,
+}
+
+impl TestApp {
+    /// Start Postgres, migrate it, and build the app.  Support access is
+    /// on, so execution details can be read through the API.
+    pub async fn start() -> Self {
+        Self::start_with_auth(Auth::default()).await
+    }
+
+    /// [`TestApp::start`], with `/api/v1` requests authenticated by `auth`.
+    pub async fn start_with_auth(auth: Auth) -> Self {
+        let (url, postgres) = match std::env::var("IT_DATABASE_URL") {
+            Ok(url) => (url, None),
+            Err(_) => {
+                let container = Postgres::default().start().await.expect("start postgres (is Docker running?)");
+                let host = container.get_host().await.expect("container host");
+                let port = container.get_host_port_ipv4(5432).await.expect("container port");
+                (format!("postgres://postgres:postgres@{host}:{port}/postgres"), Some(container))
+            }
+        };
+        let pool = db::pool::create_pool(&url, 5).await.expect("connect to postgres");
+        db::pool::run_migrations(&pool).await.expect("migrations");
+
+        api::metrics::handle();
+        let live = LiveUpdates::new();
+        tokio::spawn(live.clone().run(pool.clone()));
+        wait_for_relay(&pool, &live).await;
+
+        let binary_dir = std::env::temp_dir().join(format!("rusty-it-binary-{}", Uuid::new_v4()));
+        let binary = BinaryData::from_url(pool.clone(), &format!("file://{}", binary_dir.display())).expect("binary data store");
+
+        let defaults =
+            [(engine::flags::SUPPORT_ACCESS.to_owned(), true), (engine::flags::GRAPHQL.to_owned(), true)].into();
+        // Reads meant for a replica go to read-only sessions, so any write
+        // routed there fails the test that makes it.
+        let read_pool = db::pool::create_read_pool(&url, 5).await.expect("connect to postgres");
+        let state = AppState {
+            pool: pool.clone(),
+            read_pool: read_pool.clone(),
+            queue: Arc::new(PgJobQueue::new(pool.clone())),
+            flags: FeatureFlags::from_defaults(defaults),
+            readiness: Readiness::new(),
+            automation: Automation::new(nodes::default_registry()).with_store(pool.clone()),
+            auth,
+            live,
+            secrets: Some(SecretsKey::parse(SECRETS_KEY).unwrap()),
+            limits: BodyLimits::default(),
+            binary: Some(binary.clone()),
+            cors_origins: Vec::new(),
+        };
+        Self { database_url: url, pool, read_pool, binary, binary_dir, router: api::router(state), _postgres: postgres }
+    }
+
+    /// Serve the app on a local port, for clients that need a real
+    /// connection (WebSockets); returns its address.
+    pub async fn serve(&self) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = self.router.clone();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        addr
+    }
+
+    /// Send a request and return the status and JSON body (`null` when
+    /// empty).
+    pub async fn request(&self, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-support-actor", "it")
+            .header("x-support-reason", "integration test");
+        let body = match body {
+            Some(json) => {
+                request = request.header("content-type", "application/json");
+                Body::from(json.to_string())
+            }
+            None => Body::empty(),
+        };
+        self.send(request.body(body).unwrap()).await
+    }
+
+    /// Send a request as built, e.g. with credentials.
+    pub async fn send(&self, request: Request<Body>) -> (StatusCode, Value) {
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json = if bytes.is_empty() { Value::Null } else { serde_json::from_slice(&bytes).unwrap() };
+        (status, json)
+    }
+
+    /// `GET uri` and return the status and body as text.
+    pub async fn get_text(&self, uri: &str) -> (StatusCode, String) {
+        self.send_text(Request::builder().uri(uri).body(Body::empty()).unwrap()).await
+    }
+
+    /// Send a request as built and return the status and body as text.
+    pub async fn send_text(&self, request: Request<Body>) -> (StatusCode, String) {
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    /// `GET uri` and return the status, headers, and raw body.
+    pub async fn get_bytes(&self, uri: &str) -> (StatusCode, HeaderMap, Vec<u8>) {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        (parts.status, parts.headers, bytes.to_vec())
+    }
+
+    /// Send `body` as-is with the given content type.
+    pub async fn send_raw(&self, method: Method, uri: &str, content_type: &str, body: &str) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", content_type)
+            .body(Body::from(body.to_owned()))
+            .unwrap();
+        self.send(request).await
+    }
+
+    pub async fn get(&self, uri: &str) -> (StatusCode, Value) {
+        self.request(Method::GET, uri, None).await
+    }
+
+    pub async fn post(&self, uri: &str, body: Value) -> (StatusCode, Value) {
+        self.request(Method::POST, uri, Some(body)).await
+    }
+
+    /// Do what a worker does with the next due job: run (or resume) its
+    /// execution with the built-in nodes and settle the job.  `None` when
+    /// no job is due.
+    pub async fn run_next_job(&self) -> Option<Result<ExecutionResult, EngineError>> {
+        let executor =
+            WorkflowExecutor::new(self.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+        Worker::new(self.pool.clone(), executor).run_next().await.expect("run job")
+    }
+}
+
+/// Wait until `live` relays published changes, so tests see every change
+/// they cause.
+async fn wait_for_relay(pool: &DbPool, live: &LiveUpdates) {
+    let mut changes = live.subscribe();
+    let probe = Change::Execution { id: Uuid::nil(), workflow_id: Uuid::nil(), project_id: Uuid::nil(), status: "probe".into() };
+    for _ in 0..100 {
+        changes::publish(pool, &probe).await;
+        if tokio::time::timeout(Duration::from_millis(100), changes.recv()).await.is_ok() {
+            return;
+        }
+    }
+    panic!("live updates are not relayed");
+}
@@ -0,0 +1,97 @@
+//! `/readyz` and `/metrics` report the database pools, so an exhausted or
+//! unreachable pool shows up before requests start failing.  Every
+//! response carries the request id its log lines were tagged with.
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+
+use db::pool::PoolSettings;
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn readiness_and_metrics_report_the_pools() {
+    let app = TestApp::start().await;
+
+    let (status, body) = app.get("/readyz").await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["ready"], true);
+    assert_eq!(body["database"]["primary"]["max_connections"], 5);
+    assert_eq!(body["database"]["primary"]["exhausted"], false);
+    assert!(body["database"]["read"]["acquire_secs"].is_f64(), "{body}");
+
+    let (status, text) = app.get_text("/metrics").await;
+    assert_eq!(status, StatusCode::OK);
+    let has = |needle: &str| text.lines().any(|line| line.starts_with(needle));
+    assert!(has(r#"rusty_automation_db_pool_max_connections{pool="primary"} 5"#), "{text}");
+    assert!(has(r#"rusty_automation_db_pool_connections{pool="read",state="in_use"}"#), "{text}");
+    assert!(has("rusty_automation_db_pool_acquire_duration_seconds_bucket{"), "{text}");
+}
+
+#[tokio::test]
+async fn an_exhausted_pool_times_out_and_is_counted() {
+    let app = TestApp::start().await;
+    let settings = PoolSettings::new(1).with_acquire_timeout(Duration::from_millis(200));
+    let pool = db::pool::create_pool_with(&app.database_url, &settings).await.unwrap();
+    let held = pool.acquire().await.unwrap();
+    let timeouts = db::pool::acquire_timeouts();
+
+    let health = db::pool::check(&pool, Duration::from_millis(200)).await;
+    assert!(health.exhausted && !health.is_healthy(), "{health:?}");
+    assert_eq!(health.stats.in_use, 1);
+
+    let error = db::repository::jobs::queue_stats(&pool, None).await.unwrap_err();
+    assert!(error.to_string().contains("timed out"), "{error}");
+    assert!(db::pool::acquire_timeouts() >= timeouts + 2);
+
+    drop(held);
+    assert!(db::pool::check(&pool, Duration::from_millis(200)).await.is_healthy());
+}
+
+#[tokio::test]
+async fn a_migrated_database_has_no_pending_migrations() {
+    let app = TestApp::start().await;
+    let status = db::pool::migration_status(&app.pool).await.unwrap();
+    assert!(status.applied > 0);
+    assert!(status.pending.is_empty() && status.failed.is_empty() && status.modified.is_empty(), "{status:?}");
+    assert!(status.unknown.is_empty());
+}
+
+#[tokio::test]
+async fn only_postgres_urls_are_accepted() {
+    let app = TestApp::start().await;
+    for url in ["mysql://etl:hunter2@db/automation", "mariadb://db/automation", "automation.db"] {
+        let Err(error) = db::pool::create_pool(url, 1).await else {
+            panic!("{url} is refused");
+        };
+        assert!(matches!(error, db::DbError::UnsupportedDatabase(_)), "{url}: {error}");
+        assert!(!error.to_string().contains("hunter2"), "{error}");
+    }
+    let error = db::pool::check_url("mysql://etl:hunter2@db/automation").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "unsupported database 'mysql': only PostgreSQL (postgres:// or postgresql:// URLs) is supported"
+    );
+
+    let url = app.database_url.replacen("postgres://", "PostgreSQL://", 1);
+    db::pool::check_url(&url).unwrap();
+    assert!(db::pool::create_pool(&url, 1).await.is_ok());
+}
+
+#[tokio::test]
+async fn responses_carry_the_request_id() {
+    let app = TestApp::start().await;
+    let (_, headers, _) = app.get_bytes("/healthz").await;
+    let generated = headers["x-request-id"].to_str().unwrap();
+    assert!(uuid::Uuid::parse_str(generated).is_ok(), "{generated}");
+
+    let addr = app.serve().await;
+    let response = reqwest::Client::new()
+        .get(format!("http://{addr}/healthz"))
+        .header("x-request-id", "lb-7f3a")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["x-request-id"], "lb-7f3a");
+}
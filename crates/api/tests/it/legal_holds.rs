@@ -0,0 +1,113 @@
+//! Legal holds: placed and lifted by admins under their own identity, and
+//! held executions survive deletion, pruning, and retention.
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+
+use api::auth::{ApiKey, Auth};
+use engine::executor::ExecutorConfig;
+use engine::retention::Pruner;
+use engine::worker::Worker;
+use engine::{RetentionPolicy, WorkflowExecutor};
+
+use crate::harness::TestApp;
+
+/// A request of the `root` admin.
+fn as_root(method: Method, uri: &str, body: Option<Value>) -> Request<Body> {
+    let request = Request::builder().method(method).uri(uri).header(header::AUTHORIZATION, "Bearer r00t");
+    match body {
+        Some(json) => request.header(header::CONTENT_TYPE, "application/json").body(Body::from(json.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap()
+}
+
+#[tokio::test]
+async fn holds_are_recorded_under_the_callers_identity() {
+    let keys = [ApiKey::parse("root=r00t").unwrap()];
+    let app = TestApp::start_with_auth(Auth::new(keys.to_vec(), None).with_admins(["root".to_owned()])).await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "held by root",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let create = json!({ "name": "held by root", "definition": definition });
+    let (status, workflow) = app.send(as_root(Method::POST, "/api/v1/workflows", Some(create))).await;
+    assert_eq!(status, StatusCode::CREATED, "{workflow}");
+    let hold = format!("/api/v1/admin/legal-holds/workflows/{}", workflow["id"].as_str().unwrap());
+
+    // The body cannot name someone else.
+    let placed = json!({ "held": true, "actor": "someone-else", "reason": "litigation 42" });
+    let (status, body) = app.send(as_root(Method::PUT, &hold, Some(placed))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!((&body["held"], &body["event"]["actor"]), (&json!(true), &json!("root")));
+    let (status, body) = app.send(as_root(Method::PUT, &hold, Some(json!({ "held": false })))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["event"]["actor"], "root");
+
+    let (status, history) = app.send(as_root(Method::GET, &hold, None)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(history["held"], false);
+    let events: Vec<_> = history["events"].as_array().unwrap().iter().map(|e| (e["held"].clone(), e["actor"].clone())).collect();
+    assert_eq!(events.len(), 2, "{history}");
+    assert!(events.iter().all(|(_, actor)| actor == "root"), "{history}");
+}
+
+#[tokio::test]
+async fn held_executions_survive_deletion_pruning_and_retention() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "held",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "held",
+        "retention": { "max_executions": 1 }
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "held", "definition": definition })).await;
+    let id = workflow["id"].as_str().unwrap().to_owned();
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["held".into()]);
+    let run = || async {
+        let (_, job) = app.post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": {} })).await;
+        while worker.run_next().await.unwrap().is_some() {}
+        job["execution_id"].as_str().unwrap().to_owned()
+    };
+    let app = &app;
+    let exists = |execution: String| async move { app.get(&format!("/api/v1/executions/{execution}")).await.0.is_success() };
+
+    let held = run().await;
+    let hold = format!("/api/v1/admin/legal-holds/executions/{held}");
+    let (status, _) = app.request(Method::PUT, &hold, Some(json!({ "held": true }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "without authentication the body names the actor");
+    let (status, body) = app.request(Method::PUT, &hold, Some(json!({ "held": true, "actor": "legal" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["event"]["actor"], "legal");
+
+    let (status, body) = app.request(Method::DELETE, &format!("/api/v1/executions/{held}"), None).await;
+    assert_eq!(status, StatusCode::CONFLICT, "{body}");
+
+    let pruned = run().await;
+    let (status, body) = app.request(Method::DELETE, &format!("/api/v1/executions?workflow_id={id}"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["deleted"], 1);
+    assert!(exists(held.clone()).await && !exists(pruned).await);
+
+    // Retention keeps the newest execution, and the held one besides.
+    let older = run().await;
+    let newest = run().await;
+    Pruner::new(app.pool.clone(), RetentionPolicy::default()).prune_once().await.unwrap();
+    assert!(exists(held.clone()).await && exists(newest).await);
+    assert!(!exists(older).await);
+
+    let (status, _) = app.request(Method::PUT, &hold, Some(json!({ "held": false, "actor": "legal" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.request(Method::DELETE, &format!("/api/v1/executions/{held}"), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
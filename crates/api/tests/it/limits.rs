@@ -0,0 +1,65 @@
+//! Bodies that are too large, malformed, or do not fit are refused with
+//! the reason.
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::json;
+
+use api::limits::{DEFAULT_API_BODY_BYTES, DEFAULT_WEBHOOK_BODY_BYTES};
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn oversized_bodies_are_refused_with_the_limit() {
+    let app = TestApp::start().await;
+    let name = "x".repeat(DEFAULT_API_BODY_BYTES);
+    let (status, refused) = app.post("/api/v1/workflows", json!({ "name": name, "definition": {} })).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(refused["limit_bytes"], DEFAULT_API_BODY_BYTES);
+
+    // A declared length is refused before the body is read.
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/webhook/anything")
+        .header(header::CONTENT_LENGTH, DEFAULT_WEBHOOK_BODY_BYTES + 1)
+        .body(Body::from(vec![b'x'; DEFAULT_WEBHOOK_BODY_BYTES + 1]))
+        .unwrap();
+    let (status, refused) = app.send(request).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(refused["limit_bytes"], DEFAULT_WEBHOOK_BODY_BYTES);
+}
+
+#[tokio::test]
+async fn bodies_that_do_not_fit_name_the_field() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "limits",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+
+    let (status, refused) =
+        app.post("/api/v1/workflows", json!({ "name": "limits", "definition": definition, "active": "yes" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(refused["field"], "active");
+    assert!(refused["error"].as_str().unwrap().contains("expected a boolean"), "{refused}");
+
+    let (status, refused) = app.post("/api/v1/workflows", json!({ "definition": definition })).await;
+    assert_eq!((status, &refused["field"]), (StatusCode::UNPROCESSABLE_ENTITY, &json!(null)));
+    assert!(refused["error"].as_str().unwrap().contains("missing field `name`"), "{refused}");
+
+    let mut broken = definition.clone();
+    broken["nodes"][0]["id"] = json!(7);
+    let (status, refused) = app.post("/api/v1/workflows", json!({ "name": "limits", "definition": broken })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(refused["field"], "definition.nodes[0].id");
+
+    let (status, refused) = app.send_raw(Method::POST, "/api/v1/workflows", "application/json", "{\"name\": ").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(refused["line"], 1);
+    let (status, _) = app.send_raw(Method::POST, "/api/v1/workflows", "text/plain", "{}").await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
@@ -0,0 +1,119 @@
+//! `/api/v1/ws` pushes the workflow and execution changes a client
+//! subscribed to, authenticated through the `bearer` subprotocol.
+
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use api::auth::{ApiKey, Auth};
+use engine::executor::ExecutorConfig;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::TestApp;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn send(socket: &mut Socket, message: Value) {
+    socket.send(Message::Text(message.to_string())).await.unwrap();
+}
+
+/// The next message `keep` accepts, skipping the others.
+async fn next(socket: &mut Socket, keep: impl Fn(&Value) -> bool) -> Value {
+    let wait = async {
+        loop {
+            let Message::Text(text) = socket.next().await.expect("socket open").unwrap() else { continue };
+            let message: Value = serde_json::from_str(&text).unwrap();
+            if keep(&message) {
+                return message;
+            }
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(10), wait).await.expect("message in time")
+}
+
+#[tokio::test]
+async fn subscribers_receive_workflow_and_execution_changes() {
+    let auth = Auth::new(vec![ApiKey::parse("editor-ui=l1ve").unwrap()], None).with_admins(["editor-ui".to_owned()]);
+    let app = TestApp::start_with_auth(auth).await;
+    let addr = app.serve().await;
+
+    let anonymous = format!("ws://{addr}/api/v1/ws").into_client_request().unwrap();
+    assert!(tokio_tungstenite::connect_async(anonymous).await.is_err());
+
+    let mut request = format!("ws://{addr}/api/v1/ws").into_client_request().unwrap();
+    request.headers_mut().insert("sec-websocket-protocol", "bearer, l1ve".parse().unwrap());
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await.expect("connect");
+
+    send(&mut socket, json!({ "action": "subscribe", "id": "list", "topic": "workflows" })).await;
+    assert_eq!(next(&mut socket, |_| true).await, json!({ "type": "subscribed", "id": "list" }));
+    send(&mut socket, json!({ "action": "subscribe", "id": "bad", "topic": "nodes" })).await;
+    assert_eq!(next(&mut socket, |_| true).await["type"], "error");
+
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "live",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "live"
+    });
+    let create = json!({ "name": "live", "definition": definition });
+    let (status, workflow) = app.send(authorized(Method::POST, "/api/v1/workflows", Some(create))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let workflow_id = workflow["id"].as_str().unwrap().to_owned();
+    let created = next(&mut socket, |m| m["type"] == "change").await;
+    assert_eq!(created["subscription"], "list");
+    assert_eq!(created["change"]["type"], "workflow");
+    assert_eq!(created["change"]["action"], "created");
+    assert_eq!(created["change"]["id"], workflow_id.as_str());
+
+    send(&mut socket, json!({ "action": "unsubscribe", "id": "list" })).await;
+    assert_eq!(next(&mut socket, |_| true).await, json!({ "type": "unsubscribed", "id": "list" }));
+    let runs = json!({
+        "action": "subscribe", "id": "runs", "topic": "executions",
+        "workflow_id": workflow_id, "statuses": ["running", "succeeded"]
+    });
+    send(&mut socket, runs).await;
+    assert_eq!(next(&mut socket, |_| true).await, json!({ "type": "subscribed", "id": "runs" }));
+
+    let uri = format!("/api/v1/workflows/{workflow_id}/execute");
+    let (status, _) = app.send(authorized(Method::POST, &uri, Some(json!({ "input": {} })))).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["live".into()]);
+    while worker.run_next().await.unwrap().is_some() {}
+
+    // `pending` is filtered out, and workflow changes are no longer
+    // subscribed to.
+    let uri = format!("/api/v1/workflows/{workflow_id}/active");
+    let (status, _) = app.send(authorized(Method::PUT, &uri, Some(json!({ "active": false })))).await;
+    assert_eq!(status, StatusCode::OK);
+    let statuses: Vec<Value> = [next(&mut socket, |_| true).await, next(&mut socket, |_| true).await]
+        .into_iter()
+        .map(|m| {
+            assert_eq!((m["type"].as_str(), m["subscription"].as_str()), (Some("change"), Some("runs")));
+            m["change"]["status"].clone()
+        })
+        .collect();
+    assert_eq!(statuses, [json!("running"), json!("succeeded")]);
+    send(&mut socket, json!({ "action": "unsubscribe", "id": "runs" })).await;
+    assert_eq!(next(&mut socket, |_| true).await, json!({ "type": "unsubscribed", "id": "runs" }));
+}
+
+fn authorized(method: Method, uri: &str, body: Option<Value>) -> Request<Body> {
+    let request = Request::builder().method(method).uri(uri).header(header::AUTHORIZATION, "Bearer l1ve");
+    match body {
+        Some(json) => request.header(header::CONTENT_TYPE, "application/json").body(Body::from(json.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap()
+}
@@ -0,0 +1,40 @@
+//! Advisory locks let one instance at a time run a singleton service, and
+//! pass to another when the holder releases the lock or dies.
+
+use std::time::Duration;
+
+use db::lock::AdvisoryLock;
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn advisory_locks_have_one_holder_and_fail_over() {
+    let app = TestApp::start().await;
+    let name = format!("singleton-{}", uuid::Uuid::new_v4().simple());
+
+    let mut first = AdvisoryLock::new(app.pool.clone(), &name);
+    let mut second = AdvisoryLock::new(app.pool.clone(), &name);
+    assert!(first.hold().await.unwrap());
+    assert!(!second.hold().await.unwrap());
+    assert!(first.renew().await.unwrap());
+    assert!(!second.is_held());
+
+    first.release().await.unwrap();
+    assert!(!first.is_held());
+    assert!(second.hold().await.unwrap());
+
+    // A holder that dies without releasing: its connection closes and
+    // Postgres drops the lock.
+    drop(second);
+    let mut third = AdvisoryLock::new(app.pool.clone(), &name);
+    let mut held = false;
+    for _ in 0..50 {
+        held = third.hold().await.unwrap();
+        if held {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(held, "the lock passes on when its holder dies");
+    assert!(!first.hold().await.unwrap());
+}
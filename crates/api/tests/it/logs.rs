@@ -0,0 +1,68 @@
+//! Lines nodes log are stored with the execution and read back per
+//! execution or per node, filtered by level and paged.
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use engine::executor::ExecutorConfig;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn node_logs_are_listed_by_execution_and_node() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "logs",
+        "trigger": { "type": "manual" },
+        "nodes": [{
+            "id": "check",
+            "node_type": "validate_json",
+            "config": {
+                "schema": { "type": "object", "required": ["email", "order"] },
+                "on_invalid": "branch"
+            }
+        }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "logs"
+    });
+    let (status, workflow) = app.post("/api/v1/workflows", json!({ "name": "logs", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let uri = format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap());
+    let (status, job) = app.post(&uri, json!({ "input": {} })).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["logs".into()]);
+    while worker.run_next().await.unwrap().is_some() {}
+    let execution = job["execution_id"].as_str().unwrap();
+
+    let (status, body) = app.get(&format!("/api/v1/executions/{execution}/logs")).await;
+    assert_eq!(status, StatusCode::OK);
+    let logs = body["logs"].as_array().unwrap();
+    assert_eq!(logs.len(), 2, "{body}");
+    assert!(logs.iter().all(|line| line["node_id"] == "check" && line["level"] == "warn"));
+    assert!(logs[0]["message"].as_str().unwrap().contains("email"));
+    assert!(logs[1]["message"].as_str().unwrap().contains("order"));
+    assert_eq!(logs[0]["fields"]["path"], "");
+    assert_eq!(body["next_offset"], json!(null));
+
+    let (_, page) = app.get(&format!("/api/v1/executions/{execution}/logs?limit=1")).await;
+    assert_eq!((page["logs"][0]["id"].clone(), page["next_offset"].clone()), (logs[0]["id"].clone(), json!(1)));
+    let (_, page) = app.get(&format!("/api/v1/executions/{execution}/logs?limit=1&offset=1")).await;
+    assert_eq!(page["logs"][0]["id"], logs[1]["id"]);
+
+    let (_, errors) = app.get(&format!("/api/v1/executions/{execution}/logs?level=error")).await;
+    assert_eq!(errors["logs"], json!([]));
+    let (_, warnings) = app.get(&format!("/api/v1/executions/{execution}/nodes/check/logs?level=warn")).await;
+    assert_eq!(warnings["logs"], body["logs"]);
+    let (_, other) = app.get(&format!("/api/v1/executions/{execution}/nodes/other/logs")).await;
+    assert_eq!(other["logs"], json!([]));
+
+    let (status, _) = app.get(&format!("/api/v1/executions/{execution}/logs?level=loud")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app.get(&format!("/api/v1/executions/{}/logs", uuid::Uuid::new_v4())).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,58 @@
+//! End-to-end tests: the HTTP API, a worker step, and a real Postgres.
+//!
+//! Each test starts Postgres in a container (via `testcontainers`), applies
+//! the migrations, and drives the app through its router.  Responses are
+//! checked against insta snapshots in `snapshots/`, with ids and
+//! timestamps redacted.
+//!
+//! ```text
+//! cargo test -p api --features integration --test it
+//! ```
+//!
+//! Docker must be running.  To use an existing server instead, point
+//! `IT_DATABASE_URL` at an empty database; the tests then share it, so run
+//! them with `--test-threads=1`.
+
+mod approvals;
+mod auth;
+mod binary;
+mod clone;
+mod compression;
+mod credentials;
+mod dashboard;
+mod encryption;
+mod error_reporting;
+mod error_workflow;
+mod executions;
+mod feature_flags;
+mod graphql;
+mod grpc;
+mod harness;
+mod health;
+mod legal_holds;
+mod limits;
+mod live;
+mod locks;
+mod logs;
+mod manual;
+mod metrics;
+mod node_types;
+mod otel;
+mod params;
+mod partitions;
+mod pg_notify;
+mod polling;
+mod privacy;
+mod projects;
+mod queues;
+mod quotas;
+mod replica;
+mod retention;
+mod scheduler;
+mod secrets;
+mod stats;
+mod support;
+mod triggers;
+mod webhook_flow;
+mod worker;
+mod workflows;
@@ -0,0 +1,42 @@
+//! Manual runs are checked against the trigger's input schema.
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn manual_input_must_match_the_input_schema() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "invite user",
+        "trigger": {
+            "type": "manual",
+            "input_schema": {
+                "type": "object",
+                "required": ["email"],
+                "properties": { "email": { "type": "string", "title": "Email address" } }
+            }
+        },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (status, workflow) = app.post("/api/v1/workflows", json!({ "name": "invite user", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let execute = format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap());
+
+    let (status, body) = app.post(&execute, json!({ "input": { "email": 7 } })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["violations"][0]["path"], json!("/email"));
+
+    let (status, _) = app.post(&execute, json!({ "input": { "email": "ada@example.com" } })).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    app.run_next_job().await.expect("a queued job").expect("execution succeeds");
+
+    let mut broken = definition.clone();
+    broken["trigger"]["input_schema"] = json!({ "type": "thing" });
+    let (status, _) = app.post("/api/v1/workflows", json!({ "name": "broken", "definition": broken })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
@@ -0,0 +1,47 @@
+//! `GET /metrics` reports requests, executions, node durations, and queue
+//! depth in the Prometheus text format.
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use engine::executor::ExecutorConfig;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn metrics_cover_requests_executions_nodes_and_queues() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "measured",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "metrics"
+    });
+    let (status, workflow) = app.post("/api/v1/workflows", json!({ "name": "measured", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = workflow["id"].as_str().unwrap();
+    let (status, _) = app.get(&format!("/api/v1/workflows/{id}")).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": {} })).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["metrics".into()]);
+    while worker.run_next().await.unwrap().is_some() {}
+
+    let (status, text) = app.get_text("/metrics").await;
+    assert_eq!(status, StatusCode::OK);
+    let has = |needle: &str| text.lines().any(|line| line.starts_with(needle));
+    assert!(has(r#"rusty_automation_http_requests_total{method="GET",route="/api/v1/workflows/:id",status="200"}"#), "{text}");
+    assert!(has("rusty_automation_http_request_duration_seconds_bucket{"), "{text}");
+    assert!(has("rusty_automation_executions_started_total"), "{text}");
+    assert!(has("rusty_automation_executions_succeeded_total"), "{text}");
+    assert!(has("rusty_automation_worker_jobs_total{outcome=\"succeeded\"}"), "{text}");
+    assert!(text.contains(r#"node_type="validate_json""#), "{text}");
+    assert!(has(r#"rusty_automation_queue_jobs{queue="metrics",state="pending"} 0"#), "{text}");
+    assert!(has("# TYPE rusty_automation_node_duration_seconds histogram"), "{text}");
+}
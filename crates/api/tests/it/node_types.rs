@@ -0,0 +1,24 @@
+//! `GET /api/v1/node-types` lists every registered node type with what a
+//! frontend needs to offer and configure it.
+
+use axum::http::StatusCode;
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn node_types_describe_the_registry() {
+    let app = TestApp::start().await;
+    let (status, catalog) = app.get("/api/v1/node-types").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let entries = catalog.as_array().unwrap();
+    assert_eq!(entries.len(), nodes::default_registry().len());
+    let types: Vec<&str> = entries.iter().map(|entry| entry["node_type"].as_str().unwrap()).collect();
+    assert!(types.windows(2).all(|pair| pair[0] < pair[1]), "sorted by type: {types:?}");
+
+    let jira = entries.iter().find(|entry| entry["node_type"] == "jira").unwrap();
+    assert_eq!(jira["display_name"], "Jira");
+    assert_eq!(jira["category"], "integration");
+    assert_eq!(jira["credentials"][0]["config_field"], "api_token_secret");
+    assert!(jira["config_schema"]["properties"].is_object() || jira["config_schema"]["oneOf"].is_array());
+}
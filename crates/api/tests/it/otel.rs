@@ -0,0 +1,94 @@
+//! Executions are traced over OTLP, continuing the trace of the webhook
+//! request that started them.
+
+use std::sync::{Arc, Mutex};
+
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+
+use engine::executor::ExecutorConfig;
+use engine::otel::Tracer;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::TestApp;
+
+/// An OTLP collector stand-in keeping the spans it is sent.
+async fn collector() -> (String, Arc<Mutex<Vec<Value>>>) {
+    let spans = Arc::new(Mutex::new(Vec::new()));
+    let receive = |State(spans): State<Arc<Mutex<Vec<Value>>>>, Json(body): Json<Value>| async move {
+        for resource in body["resourceSpans"].as_array().unwrap() {
+            assert_eq!(resource["resource"]["attributes"][0]["value"]["stringValue"], "it-worker");
+            for scope in resource["scopeSpans"].as_array().unwrap() {
+                spans.lock().unwrap().extend(scope["spans"].as_array().unwrap().iter().cloned());
+            }
+        }
+        Json(json!({}))
+    };
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = Router::new().route("/v1/traces", post(receive)).with_state(spans.clone());
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    (format!("http://{addr}"), spans)
+}
+
+fn attribute<'a>(span: &'a Value, key: &str) -> &'a Value {
+    let attributes = span["attributes"].as_array().unwrap();
+    &attributes.iter().find(|attribute| attribute["key"] == key).expect(key)["value"]
+}
+
+#[tokio::test]
+async fn executions_continue_the_webhook_callers_trace() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "traced",
+        "trigger": { "type": "webhook", "path": "it-traced" },
+        "nodes": [
+            { "id": "check", "node_type": "validate_json", "config": { "field": "body", "schema": { "type": "object" } } },
+            { "id": "again", "node_type": "validate_json", "config": { "field": "body", "schema": { "type": "object" } } }
+        ],
+        "edges": [{ "from": "check", "to": "again" }],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "traced"
+    });
+    let (status, _) = app.post("/api/v1/workflows", json!({ "name": "traced", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/webhook/it-traced")
+        .header("content-type", "application/json")
+        .header("traceparent", "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+        .body(Body::from(json!({ "order": 42 }).to_string()))
+        .unwrap();
+    let (status, _) = app.send(request).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    let (endpoint, spans) = collector().await;
+    let tracer = Tracer::otlp(&endpoint, "it-worker");
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default())
+        .with_tracer(tracer.clone());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["traced".into()]);
+    let result = worker.run_next().await.unwrap().expect("a job").expect("the execution succeeds");
+    tracer.flush().await;
+
+    let spans = spans.lock().unwrap().clone();
+    assert_eq!(spans.len(), 3, "{spans:#?}");
+    assert!(spans.iter().all(|span| span["traceId"] == "4bf92f3577b34da6a3ce929d0e0e4736"));
+    let execution = spans.iter().find(|span| span["name"] == "execution").unwrap();
+    assert_eq!(execution["parentSpanId"], "00f067aa0ba902b7");
+    assert_eq!(attribute(execution, "execution.id")["stringValue"], result.execution_id.to_string());
+    assert_eq!(attribute(execution, "execution.status")["stringValue"], "succeeded");
+
+    let check = spans.iter().find(|span| span["name"] == "node check").unwrap();
+    assert_eq!(check["parentSpanId"], execution["spanId"]);
+    assert_eq!(attribute(check, "node.type")["stringValue"], "validate_json");
+    assert_eq!(attribute(check, "node.status")["stringValue"], "succeeded");
+    assert_eq!(attribute(check, "node.retry_count")["intValue"], "0");
+    assert_eq!(check["status"]["code"], 1);
+}
@@ -0,0 +1,87 @@
+//! Workflow parameters: defaults, execute overrides, and webhook overrides.
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::harness::TestApp;
+
+/// The top `params.limit` orders of a webhook request's body.
+fn top_orders() -> Value {
+    json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "top orders",
+        "trigger": { "type": "webhook", "path": "it-params" },
+        "params": { "limit": { "type": "integer", "default": 2 } },
+        "nodes": [{
+            "id": "top",
+            "node_type": "sort_limit",
+            "config": {
+                "field": "body.orders",
+                "sort": [{ "field": "amount", "order": "desc" }],
+                "limit": "{{ params.limit }}"
+            }
+        }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    })
+}
+
+#[tokio::test]
+async fn runs_use_parameter_defaults_unless_overridden() {
+    let app = TestApp::start().await;
+    let (status, workflow) = app.post("/api/v1/workflows", json!({ "name": "top orders", "definition": top_orders() })).await;
+    assert_eq!(status, StatusCode::CREATED, "{workflow}");
+    let execute = format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap());
+    let orders = json!({ "orders": [{ "amount": 5 }, { "amount": 30 }, { "amount": 12 }] });
+    let count = |result: Value| result["count"].as_u64().unwrap();
+
+    let (status, _) = app.post(&execute, json!({ "input": { "body": orders } })).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let result = app.run_next_job().await.expect("a queued job").expect("execution succeeds");
+    assert_eq!(count(result.output), 2);
+
+    let (status, _) = app.post(&execute, json!({ "input": { "body": orders }, "params": { "limit": 1 } })).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let result = app.run_next_job().await.expect("a queued job").expect("execution succeeds");
+    assert_eq!(result.output["items"], json!([{ "amount": 30 }]));
+
+    let (status, body) = app.post(&execute, json!({ "input": { "body": orders }, "params": { "limit": "1" } })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "parameter 'limit' must be an integer, not \"1\"");
+    let (status, _) = app.post(&execute, json!({ "input": { "body": orders }, "params": { "colour": "red" } })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, _) = app.post("/webhook/it-params?params.limit=3", orders.clone()).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let result = app.run_next_job().await.expect("a queued job").expect("execution succeeds");
+    assert_eq!(count(result.output), 3);
+    let (status, _) = app.post("/webhook/it-params?params.limit=all", orders).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(app.run_next_job().await.is_none());
+}
+
+#[tokio::test]
+async fn runs_whose_overrides_no_longer_fit_fail() {
+    let app = TestApp::start().await;
+    let mut definition = top_orders();
+    definition["trigger"]["path"] = json!("it-params-changed");
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "changing orders", "definition": definition })).await;
+    let id = workflow["id"].as_str().unwrap();
+    let (status, job) = app
+        .post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": { "body": { "orders": [] } }, "params": { "limit": 1 } }))
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    // The parameter changes type while the run waits in the queue.
+    definition["params"]["limit"] = json!({ "type": "string", "default": "2" });
+    let (status, _) = app
+        .request(Method::PUT, &format!("/api/v1/workflows/{id}"), Some(json!({ "definition": definition, "version": 1 })))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let error = app.run_next_job().await.expect("a queued job").unwrap_err();
+    assert!(error.to_string().contains("limit"), "{error}");
+    let (_, execution) = app.get(&format!("/api/v1/executions/{}", job["execution_id"].as_str().unwrap())).await;
+    assert_eq!(execution["execution"]["status"], "failed");
+    assert_eq!(execution["nodes"], json!([]));
+}
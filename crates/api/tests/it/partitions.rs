@@ -0,0 +1,55 @@
+//! Node executions are stored in monthly partitions, which the maintainer
+//! creates ahead of time and drops once empty.
+
+use chrono::{Datelike, NaiveDate, Utc};
+use serde_json::json;
+
+use db::repository::partitions as partition_repo;
+use engine::executor::ExecutorConfig;
+use engine::partitions::PartitionMaintainer;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn maintainer_creates_upcoming_and_drops_empty_partitions() {
+    let app = TestApp::start().await;
+    let old = NaiveDate::from_ymd_opt(2001, 1, 15).unwrap();
+    assert!(partition_repo::create_node_execution_partition(&app.pool, old).await.unwrap());
+    assert!(!partition_repo::create_node_execution_partition(&app.pool, old).await.unwrap());
+
+    let maintainer = PartitionMaintainer::new(app.pool.clone()).with_months_ahead(3);
+    let report = maintainer.maintain_once().await.unwrap();
+    assert!(report.dropped >= 1, "{report:?}");
+    assert_eq!(maintainer.maintain_once().await.unwrap().created, 0);
+
+    let partitions = partition_repo::list_node_execution_partitions(&app.pool).await.unwrap();
+    assert!(!partitions.contains(&"node_executions_p200101".to_owned()), "{partitions:?}");
+    assert!(partitions.contains(&"node_executions_default".to_owned()), "{partitions:?}");
+    let today = Utc::now().date_naive();
+    let this_month = format!("node_executions_p{:04}{:02}", today.year(), today.month());
+    assert!(partitions.contains(&this_month), "{partitions:?}");
+    assert!(partitions.len() >= 5, "{partitions:?}");
+
+    // Node executions still land in and are read from the partitions.
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "partitions",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "partitions"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "partitions", "definition": definition })).await;
+    let id = workflow["id"].as_str().unwrap();
+    let (_, job) = app.post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": {} })).await;
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["partitions".into()]);
+    while worker.run_next().await.unwrap().is_some() {}
+
+    let (_, execution) = app.get(&format!("/api/v1/executions/{}", job["execution_id"].as_str().unwrap())).await;
+    assert_eq!(execution["nodes"].as_array().unwrap().len(), 1, "{execution}");
+    assert_eq!(execution["nodes"][0]["node_id"], "check");
+}
@@ -0,0 +1,47 @@
+//! Notification-triggered workflows start an execution per `NOTIFY` on
+//! their channel.
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use db::listener::notify;
+use db::repository::jobs as job_repo;
+use engine::pg_notify::NotifyTriggers;
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn notify_starts_an_execution_with_the_payload() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "new customers",
+        "trigger": { "type": "pg_notify", "channel": "customer_created" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "queue": "notifications",
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (status, _) = app.post("/api/v1/workflows", json!({ "name": "new customers", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let mut triggers = NotifyTriggers::new(app.pool.clone());
+    assert_eq!(triggers.sync().await.unwrap(), 1);
+
+    // The listener subscribes in the background; notifications sent before
+    // that are lost, so keep notifying until one gets through.
+    let queues = ["notifications".to_owned()];
+    let mut job = None;
+    for _ in 0..50 {
+        notify(&app.pool, "customer_created", r#"{"email": "ada@example.com"}"#).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        job = job_repo::fetch_next_job(&app.pool, &queues).await.unwrap();
+        if job.is_some() {
+            break;
+        }
+    }
+    let job = job.expect("a notification starts an execution");
+    assert_eq!(job.payload, json!({ "channel": "customer_created", "payload": { "email": "ada@example.com" } }));
+}
@@ -0,0 +1,63 @@
+//! Poll-triggered workflows start one execution per new item, remembering
+//! the cursor in their static data.
+
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use db::repository::{jobs as job_repo, state as state_repo};
+use engine::executor::NodeRegistry;
+use engine::polling::{Poller, POLL_STATE_KEY};
+use nodes::mock::MockNode;
+
+use crate::harness::TestApp;
+
+/// A poller whose `feed` node answers with `orders`.
+fn poller(app: &TestApp, orders: Value) -> Poller {
+    let mut registry = NodeRegistry::new();
+    registry.insert("feed".into(), Arc::new(MockNode::returning("feed", json!({ "orders": orders }))));
+    Poller::new(app.pool.clone(), registry)
+}
+
+#[tokio::test]
+async fn only_items_after_the_stored_cursor_start_executions() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "new orders",
+        "trigger": {
+            "type": "poll",
+            "source": { "kind": "node", "node_type": "feed" },
+            "interval_secs": 0,
+            "items": "orders"
+        },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "queue": "polling",
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (status, workflow) = app.post("/api/v1/workflows", json!({ "name": "new orders", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let workflow_id: Uuid = workflow["id"].as_str().unwrap().parse().unwrap();
+
+    // The first poll only records where the feed stands.
+    let seen = json!([{ "id": 1 }, { "id": 2 }]);
+    assert_eq!(poller(&app, seen).poll_due().await.unwrap(), 0);
+    let state = state_repo::get_state(&app.pool, workflow_id, POLL_STATE_KEY).await.unwrap().unwrap();
+    assert_eq!(state.value.unwrap()["cursor"], json!(2));
+
+    let grown = json!([{ "id": 4, "sku": "b" }, { "id": 2 }, { "id": 3, "sku": "a" }, { "id": 1 }]);
+    assert_eq!(poller(&app, grown.clone()).poll_due().await.unwrap(), 2);
+    assert_eq!(poller(&app, grown).poll_due().await.unwrap(), 0, "items are admitted once");
+
+    let queues = ["polling".to_owned()];
+    let mut payloads = Vec::new();
+    while let Some(job) = job_repo::fetch_next_job(&app.pool, &queues).await.unwrap() {
+        job_repo::complete_job(&app.pool, job.id).await.unwrap();
+        payloads.push(job.payload);
+    }
+    payloads.sort_by_key(|p| p["id"].as_i64());
+    assert_eq!(payloads, [json!({ "id": 3, "sku": "a" }), json!({ "id": 4, "sku": "b" })]);
+}
@@ -0,0 +1,49 @@
+//! Erasure requests are logged under the caller's identity.
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::json;
+
+use api::auth::{ApiKey, Auth};
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn erasures_are_logged_under_the_callers_identity() {
+    let keys = [ApiKey::parse("dpo=s3cret").unwrap()];
+    let app = TestApp::start_with_auth(Auth::new(keys.to_vec(), None).with_admins(["dpo".to_owned()])).await;
+    let erase = json!({ "business_key": "customer-1234", "actor": "someone-else" });
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/privacy/erasure")
+        .header(header::AUTHORIZATION, "Bearer s3cret")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(erase.to_string()))
+        .unwrap();
+    let (status, report) = app.send(request).await;
+    assert_eq!(status, StatusCode::OK, "{report}");
+
+    let actor: String = sqlx::query_scalar("SELECT actor FROM privacy_erasures WHERE id = $1::uuid")
+        .bind(report["erasure_id"].as_str().unwrap())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(actor, "dpo");
+}
+
+#[tokio::test]
+async fn without_authentication_the_request_names_the_actor() {
+    let app = TestApp::start().await;
+    let (status, _) = app.post("/api/v1/privacy/erasure", json!({ "business_key": "customer-1234" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, report) =
+        app.post("/api/v1/privacy/erasure", json!({ "business_key": "customer-1234", "actor": "dpo" })).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let actor: String = sqlx::query_scalar("SELECT actor FROM privacy_erasures WHERE id = $1::uuid")
+        .bind(report["erasure_id"].as_str().unwrap())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(actor, "dpo");
+}
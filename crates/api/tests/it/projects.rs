@@ -0,0 +1,92 @@
+//! Workflows and executions belong to projects: requests act in the one
+//! `X-Project` names, and confined API keys cannot leave theirs.
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+
+use api::auth::{ApiKey, Auth};
+use api::rbac::Role;
+use engine::executor::ExecutorConfig;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::TestApp;
+
+fn request(method: Method, uri: &str, key: &str, project: Option<&str>, body: Option<Value>) -> Request<Body> {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {key}"));
+    if let Some(project) = project {
+        request = request.header("x-project", project);
+    }
+    match body {
+        Some(json) => request.header(header::CONTENT_TYPE, "application/json").body(Body::from(json.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap()
+}
+
+fn ids(listing: &Value, key: &str) -> Vec<String> {
+    listing[key].as_array().unwrap().iter().map(|row| row["id"].as_str().unwrap().to_owned()).collect()
+}
+
+#[tokio::test]
+async fn projects_keep_workflows_and_executions_apart() {
+    let keys = ["ops=0ps", "payments-bot@payments=pay"].map(|entry| ApiKey::parse(entry).unwrap());
+    let auth = Auth::new(keys.to_vec(), None).with_admins(["ops".to_owned()]).with_default_role(Some(Role::Editor));
+    let app = TestApp::start_with_auth(auth).await;
+
+    let (status, _) = app.send(request(Method::POST, "/api/v1/projects", "0ps", None, Some(json!({ "name": "payments" })))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = app.send(request(Method::POST, "/api/v1/projects", "0ps", None, Some(json!({ "name": "payments" })))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = app.send(request(Method::GET, "/api/v1/workflows", "0ps", Some("nope"), None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // The confined key works in its project without naming it.
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "settle",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "projects"
+    });
+    let create = json!({ "name": "settle", "definition": definition });
+    let (status, workflow) = app.send(request(Method::POST, "/api/v1/workflows", "pay", None, Some(create))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let workflow_id = workflow["id"].as_str().unwrap().to_owned();
+    let (status, job) = app
+        .send(request(Method::POST, &format!("/api/v1/workflows/{workflow_id}/execute"), "pay", None, Some(json!({ "input": {} }))))
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let execution_id = job["execution_id"].as_str().unwrap().to_owned();
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["projects".into()]);
+    while worker.run_next().await.unwrap().is_some() {}
+
+    let (_, listing) = app.send(request(Method::GET, "/api/v1/workflows", "pay", None, None)).await;
+    assert_eq!(ids(&listing, "workflows"), [workflow_id.as_str()]);
+    let (_, listing) = app.send(request(Method::GET, "/api/v1/executions", "pay", None, None)).await;
+    assert_eq!(ids(&listing, "executions"), [execution_id.as_str()]);
+
+    // Others see it only in that project.
+    let (_, listing) = app.send(request(Method::GET, "/api/v1/workflows", "0ps", None, None)).await;
+    assert!(!ids(&listing, "workflows").contains(&workflow_id));
+    let (status, _) = app.send(request(Method::GET, &format!("/api/v1/workflows/{workflow_id}"), "0ps", None, None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app.send(request(Method::GET, &format!("/api/v1/executions/{execution_id}"), "0ps", None, None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let uri = format!("/api/v1/workflows/{workflow_id}");
+    let (status, _) = app.send(request(Method::GET, &uri, "0ps", Some("payments"), None)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // The confined key cannot leave its project or use deployment-wide routes.
+    let (status, _) = app.send(request(Method::GET, "/api/v1/workflows", "pay", Some("default"), None)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = app.send(request(Method::GET, "/api/v1/me", "pay", None, None)).await;
+    assert_eq!(status, StatusCode::OK);
+}
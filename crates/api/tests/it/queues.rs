@@ -0,0 +1,57 @@
+//! Queue statistics follow jobs from enqueueing to completion.
+
+use serde_json::{json, Value};
+
+use engine::executor::ExecutorConfig;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::TestApp;
+
+/// The stats of `queue`.
+async fn stats_of(app: &TestApp, queue: &str) -> Value {
+    let (_, stats) = app.get("/api/v1/queues").await;
+    stats["queues"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|q| q["queue"] == queue)
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+#[tokio::test]
+async fn queue_stats_report_backlog_and_throughput() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "report",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "reports"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "report", "definition": definition })).await;
+    let execute = format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap());
+    app.post(&execute, json!({ "input": {} })).await;
+    app.post(&execute, json!({ "input": {} })).await;
+
+    let stats = stats_of(&app, "reports").await;
+    assert_eq!((stats["pending"].as_i64(), stats["due"].as_i64()), (Some(2), Some(2)));
+    assert!(stats["oldest_due_secs"].as_f64().unwrap() >= 0.0);
+    assert_eq!(stats["completed_last_minute"], 0);
+
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["reports".to_owned()]);
+    worker.run_next().await.unwrap().expect("a job").expect("execution succeeds");
+
+    let stats = stats_of(&app, "reports").await;
+    assert_eq!((stats["pending"].as_i64(), stats["processing"].as_i64()), (Some(1), Some(0)));
+    assert_eq!((stats["completed_last_minute"].as_i64(), stats["completed_last_hour"].as_i64()), (Some(1), Some(1)));
+
+    worker.run_next().await.unwrap().expect("a job").expect("execution succeeds");
+    let (_, all) = app.get("/api/v1/queues").await;
+    assert!(all["total"]["completed_last_hour"].as_i64().unwrap() >= 2);
+    assert_eq!(stats_of(&app, "reports").await["pending"], 0);
+}
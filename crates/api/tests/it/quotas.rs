@@ -0,0 +1,105 @@
+//! Workflow and project quotas refuse new executions once used up, and the
+//! stats endpoints show how much of them is used.
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+
+use crate::harness::TestApp;
+
+fn in_project(method: Method, uri: &str, project: &str, body: Value) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("x-project", project)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// Run the jobs the test queued, so later tests do not pick them up.
+async fn drain(app: &TestApp) {
+    while let Some(run) = app.run_next_job().await {
+        run.expect("execution succeeds");
+    }
+}
+
+#[tokio::test]
+async fn workflow_quotas_refuse_runs_and_show_usage() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "chatty",
+        "trigger": { "type": "webhook", "path": "it-quota" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "quotas",
+        "quota": { "max_executions_per_hour": 2 }
+    });
+    let (status, workflow) = app.post("/api/v1/workflows", json!({ "name": "chatty", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = workflow["id"].as_str().unwrap();
+
+    for _ in 0..2 {
+        assert_eq!(app.post("/webhook/it-quota", json!({})).await.0, StatusCode::ACCEPTED);
+    }
+    let (status, refused) = app.post("/webhook/it-quota", json!({})).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(refused["error"], format!("quota exceeded: workflow {id}: 2 executions in the last hour"));
+    let (status, _) = app.post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": {} })).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    let (status, stats) = app.get(&format!("/api/v1/workflows/{id}/stats")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stats["quota"]["limits"], json!({ "max_executions_per_hour": 2 }));
+    assert_eq!(
+        stats["quota"]["usage"],
+        json!({ "executions_last_hour": 2, "executions_today": 2, "node_runtime_ms_today": 0 }),
+    );
+    drain(&app).await;
+}
+
+#[tokio::test]
+async fn project_quotas_cover_every_workflow_of_the_project() {
+    let app = TestApp::start().await;
+    assert_eq!(app.post("/api/v1/projects", json!({ "name": "metered" })).await.0, StatusCode::CREATED);
+    let (status, _) = app.request(Method::PUT, "/api/v1/projects/nope/quota", Some(json!({}))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let quota = json!({ "max_executions_per_day": 1 });
+    let (status, set) = app.request(Method::PUT, "/api/v1/projects/metered/quota", Some(quota.clone())).await;
+    assert_eq!((status, &set["quota"]), (StatusCode::OK, &quota));
+
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "metered",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "quotas"
+    });
+    let create = json!({ "name": "metered", "definition": definition });
+    let (status, workflow) = app.send(in_project(Method::POST, "/api/v1/workflows", "metered", create)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let execute = format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap());
+
+    let (status, _) = app.send(in_project(Method::POST, &execute, "metered", json!({ "input": {} }))).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let (status, refused) = app.send(in_project(Method::POST, &execute, "metered", json!({ "input": {} }))).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(refused["error"], "quota exceeded: project: 1 executions today");
+
+    let stats = Request::builder().uri("/api/v1/stats").header("x-project", "metered").body(Body::empty()).unwrap();
+    let (status, stats) = app.send(stats).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stats["quota"]["limits"], quota);
+    assert_eq!(stats["quota"]["usage"]["executions_today"], 1);
+
+    // `{}` lifts the limits.
+    let (status, _) = app.request(Method::PUT, "/api/v1/projects/metered/quota", Some(json!({}))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.send(in_project(Method::POST, &execute, "metered", json!({ "input": {} }))).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    drain(&app).await;
+}
@@ -0,0 +1,31 @@
+//! Listings and details are read from the read pool, which refuses
+//! writes; changes still go to the primary.
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn reads_use_the_read_only_pool_and_writes_the_primary() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "replicated",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (status, created) = app.post("/api/v1/workflows", json!({ "name": "replicated", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = created["id"].as_str().unwrap();
+
+    let (status, workflow) = app.get(&format!("/api/v1/workflows/{id}")).await;
+    assert_eq!((status, &workflow["name"]), (StatusCode::OK, &json!("replicated")));
+    let (status, _) = app.get(&format!("/api/v1/workflows/{id}/stats")).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let write = db::repository::workflows::set_workflow_active(&app.read_pool, id.parse().unwrap(), false).await;
+    assert!(write.unwrap_err().to_string().contains("read-only"));
+}
@@ -0,0 +1,51 @@
+//! The pruner keeps at most a workflow's `max_executions` finished
+//! executions, the newest.
+
+use serde_json::json;
+
+use engine::executor::ExecutorConfig;
+use engine::retention::Pruner;
+use engine::worker::Worker;
+use engine::{RetentionPolicy, WorkflowExecutor};
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn pruner_keeps_the_newest_finished_executions() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "retention",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "retention",
+        "retention": { "max_executions": 2 }
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "retention", "definition": definition })).await;
+    let id = workflow["id"].as_str().unwrap();
+
+    let mut finished = Vec::new();
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["retention".into()]);
+    for _ in 0..4 {
+        let (_, job) = app.post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": {} })).await;
+        while worker.run_next().await.unwrap().is_some() {}
+        finished.push(job["execution_id"].clone());
+    }
+    // Not finished, so neither pruned nor counted.
+    let (_, pending) = app.post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": {} })).await;
+
+    let pruner = Pruner::new(app.pool.clone(), RetentionPolicy::default()).with_batch_size(1);
+    assert_eq!(pruner.prune_once().await.unwrap(), 2);
+    assert_eq!(pruner.prune_once().await.unwrap(), 0);
+
+    for (execution, kept) in [(&finished[0], false), (&finished[1], false), (&finished[2], true), (&finished[3], true)] {
+        let (status, _) = app.get(&format!("/api/v1/executions/{}", execution.as_str().unwrap())).await;
+        assert_eq!(status.is_success(), kept, "{execution}");
+    }
+    let (status, _) = app.get(&format!("/api/v1/executions/{}", pending["execution_id"].as_str().unwrap())).await;
+    assert!(status.is_success());
+    while worker.run_next().await.unwrap().is_some() {}
+}
@@ -0,0 +1,76 @@
+//! Cron-triggered workflows get a persisted schedule that the scheduler
+//! advances as it enqueues runs.
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use db::repository::schedules;
+use engine::scheduler::Scheduler;
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn cron_workflows_are_scheduled_and_enqueued_once_per_fire() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "every minute",
+        "trigger": { "type": "cron", "expression": "* * * * *" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (status, workflow) = app.post("/api/v1/workflows", json!({ "name": "every minute", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let scheduler = Scheduler::new(app.pool.clone());
+    assert_eq!(scheduler.sync().await.unwrap(), 1);
+    let [schedule] = schedules::list_schedules(&app.pool).await.unwrap().try_into().unwrap();
+    assert_eq!(schedule.workflow_id.to_string(), workflow["id"].as_str().unwrap());
+
+    // An unchanged schedule keeps its persisted next run.
+    scheduler.sync().await.unwrap();
+    let [again] = schedules::list_schedules(&app.pool).await.unwrap().try_into().unwrap();
+    assert_eq!(again.next_run_at, schedule.next_run_at);
+
+    let due = schedule.next_run_at;
+    assert_eq!(scheduler.enqueue_due(due).await.unwrap(), 1);
+    assert_eq!(scheduler.enqueue_due(due).await.unwrap(), 0, "a fire is enqueued once");
+    let [advanced] = schedules::list_schedules(&app.pool).await.unwrap().try_into().unwrap();
+    assert_eq!(advanced.last_run_at, Some(due));
+    assert_eq!((advanced.next_run_at - due).num_seconds(), 60);
+
+    let (status, _) = app
+        .request(axum::http::Method::DELETE, &format!("/api/v1/workflows/{}", workflow["id"].as_str().unwrap()), None)
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(scheduler.sync().await.unwrap(), 0);
+    assert!(schedules::list_schedules(&app.pool).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn validation_reports_the_next_fire_times() {
+    let app = TestApp::start().await;
+    let mut definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "weekday mornings",
+        "trigger": { "type": "cron", "expression": "0 30 8 * * 1-5", "timezone": "Europe/Berlin" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+
+    let (status, report) = app.post("/api/v1/workflows/validate", definition.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((report["valid"].as_bool(), report["timezone"].as_str()), (Some(true), Some("Europe/Berlin")));
+    assert_eq!(report["order"], json!(["check"]));
+    assert_eq!(report["next_runs"].as_array().unwrap().len(), 5);
+
+    definition["trigger"]["timezone"] = json!("Atlantis/Capital");
+    let (status, report) = app.post("/api/v1/workflows/validate", definition.clone()).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(report["error"].as_str().unwrap().contains("unknown timezone"));
+    let (status, refused) = app.post("/api/v1/workflows", json!({ "name": "bad", "definition": definition })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(refused["error"].as_str().unwrap().contains("unknown timezone"));
+}
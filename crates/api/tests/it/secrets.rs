@@ -0,0 +1,384 @@
+//! Workflow secrets are write-only through the API, stored encrypted, and
+//! decrypted into the context of the workflow's executions.
+
+use std::sync::Arc;
+
+use axum::extract::Path;
+use axum::http::{HeaderMap, Method, StatusCode};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::json;
+
+use engine::executor::ExecutorConfig;
+use engine::secret_providers::VaultSecrets;
+use engine::secrets::SecretsKey;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::{TestApp, SECRETS_KEY};
+
+#[tokio::test]
+async fn secrets_are_write_only_encrypted_and_reach_nodes() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "signer",
+        "trigger": { "type": "manual" },
+        "nodes": [{
+            "id": "sign",
+            "node_type": "crypto",
+            "config": { "operation": "hmac", "value": "hello", "secret": "SIGNING_KEY" }
+        }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "secrets"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "signer", "definition": definition })).await;
+    let id = workflow["id"].as_str().unwrap();
+    let secrets = format!("/api/v1/workflows/{id}/secrets");
+
+    let (status, created) = app.post(&secrets, json!({ "key": "SIGNING_KEY", "value": "wrong" })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["key"], "SIGNING_KEY");
+    assert!(created.get("value").is_none() && created.get("encrypted_value").is_none());
+    let (status, _) = app.post(&secrets, json!({ "key": "SIGNING_KEY", "value": "again" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = app.post(&secrets, json!({ "key": "not a key", "value": "x" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let missing = format!("/api/v1/workflows/{}/secrets", uuid::Uuid::new_v4());
+    let (status, _) = app.post(&missing, json!({ "key": "K", "value": "x" })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) =
+        app.request(Method::PUT, &format!("{secrets}/SIGNING_KEY"), Some(json!({ "value": "k3y" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.request(Method::PUT, &format!("{secrets}/NOPE"), Some(json!({ "value": "x" }))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, listed) = app.get(&secrets).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert!(!listed.to_string().contains("k3y"));
+    let stored = db::repository::secrets::list_secrets(&app.pool, id.parse().unwrap()).await.unwrap();
+    assert!(!stored[0].encrypted_value.contains("k3y"));
+
+    // HMAC-SHA256 of "hello" under "k3y".
+    app.post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": {} })).await;
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default())
+        .with_secrets_key(SecretsKey::parse(SECRETS_KEY).unwrap());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["secrets".into()]);
+    let result = worker.run_next().await.unwrap().expect("a job").expect("the execution succeeds");
+    assert_eq!(result.output, json!({ "signature": "876e76604d6817debfcac7bda97f616a5641178941207e9d557beac9e44bc25a" }));
+
+    let (status, _) = app.request(Method::DELETE, &format!("{secrets}/SIGNING_KEY"), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = app.request(Method::DELETE, &format!("{secrets}/SIGNING_KEY"), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, listed) = app.get(&secrets).await;
+    assert_eq!(listed, json!([]));
+}
+
+#[tokio::test]
+async fn secret_placeholders_fill_configs_and_values_stay_out_of_recorded_inputs() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "placeholders",
+        "trigger": { "type": "manual" },
+        "nodes": [{
+            "id": "sign",
+            "node_type": "crypto",
+            "config": { "operation": "hmac", "value": "{{ secrets.MESSAGE }}", "secret": "SIGNING_KEY" }
+        }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "placeholders"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "placeholders", "definition": definition })).await;
+    let id = workflow["id"].as_str().unwrap();
+    let secrets = format!("/api/v1/workflows/{id}/secrets");
+    app.post(&secrets, json!({ "key": "SIGNING_KEY", "value": "k3y" })).await;
+
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default())
+        .with_secrets_key(SecretsKey::parse(SECRETS_KEY).unwrap());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["placeholders".into()]);
+
+    let (_, job) = app.post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": {} })).await;
+    let error = worker.run_next().await.unwrap().expect("a job").unwrap_err();
+    assert!(error.to_string().contains("missing secret 'MESSAGE' for node 'sign'"), "{error}");
+    let (_, detail) = app.get(&format!("/api/v1/executions/{}", job["execution_id"].as_str().unwrap())).await;
+    assert_eq!(detail["execution"]["status"], "failed");
+
+    app.post(&secrets, json!({ "key": "MESSAGE", "value": "hello" })).await;
+    let (_, job) = app.post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": { "note": "hello there" } })).await;
+    let result = worker.run_next().await.unwrap().expect("a job").expect("the execution succeeds");
+    // HMAC-SHA256 of "hello" under "k3y".
+    assert_eq!(result.output, json!({ "signature": "876e76604d6817debfcac7bda97f616a5641178941207e9d557beac9e44bc25a" }));
+    let (_, detail) = app.get(&format!("/api/v1/executions/{}", job["execution_id"].as_str().unwrap())).await;
+    assert_eq!(detail["nodes"][0]["input"], json!({ "note": "[redacted] there" }));
+}
+
+#[tokio::test]
+async fn keys_hold_a_value_per_environment() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "per environment",
+        "trigger": { "type": "manual" },
+        "nodes": [{
+            "id": "sign",
+            "node_type": "crypto",
+            "config": { "operation": "hmac", "value": "hello", "secret": "SIGNING_KEY" }
+        }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "environments"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "per environment", "definition": definition })).await;
+    let id = workflow["id"].as_str().unwrap();
+    let secrets = format!("/api/v1/workflows/{id}/secrets");
+
+    app.post(&secrets, json!({ "key": "SIGNING_KEY", "value": "dev-key" })).await;
+    let (status, created) = app.post(&secrets, json!({ "key": "SIGNING_KEY", "value": "wrong", "environment": "prod" })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["environment"], "prod");
+    let (status, _) = app.post(&secrets, json!({ "key": "SIGNING_KEY", "value": "x", "environment": "prod" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = app.post(&secrets, json!({ "key": "SIGNING_KEY", "value": "x", "environment": "no way" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, _) =
+        app.request(Method::PUT, &format!("{secrets}/SIGNING_KEY?environment=prod"), Some(json!({ "value": "k3y" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, listed) = app.get(&secrets).await;
+    let environments: Vec<_> = listed.as_array().unwrap().iter().map(|secret| secret["environment"].clone()).collect();
+    assert_eq!(environments, vec![json!(null), json!("prod")]);
+
+    let worker = |environment: Option<&str>| {
+        let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default())
+            .with_secrets_key(SecretsKey::parse(SECRETS_KEY).unwrap());
+        let executor = match environment {
+            Some(environment) => executor.with_environment(environment),
+            None => executor,
+        };
+        Worker::new(app.pool.clone(), executor).with_queues(vec!["environments".into()])
+    };
+    // HMAC-SHA256 of "hello" under "k3y".
+    let prod = json!({ "signature": "876e76604d6817debfcac7bda97f616a5641178941207e9d557beac9e44bc25a" });
+    let execute = format!("/api/v1/workflows/{id}/execute");
+
+    app.post(&execute, json!({ "input": {} })).await;
+    let result = worker(Some("prod")).run_next().await.unwrap().expect("a job").expect("the execution succeeds");
+    assert_eq!(result.output, prod);
+    // Environments without a value of their own use the default.
+    app.post(&execute, json!({ "input": {} })).await;
+    let staging = worker(Some("staging")).run_next().await.unwrap().expect("a job").expect("the execution succeeds");
+    app.post(&execute, json!({ "input": {} })).await;
+    let default = worker(None).run_next().await.unwrap().expect("a job").expect("the execution succeeds");
+    assert_eq!(staging.output, default.output);
+    assert_ne!(default.output, prod);
+
+    let (status, _) = app.request(Method::DELETE, &format!("{secrets}/SIGNING_KEY?environment=prod"), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    app.post(&execute, json!({ "input": {} })).await;
+    let result = worker(Some("prod")).run_next().await.unwrap().expect("a job").expect("the execution succeeds");
+    assert_eq!(result.output, default.output);
+}
+
+#[tokio::test]
+async fn project_secrets_are_shared_and_workflow_secrets_win() {
+    let app = TestApp::start().await;
+    let definition = |name: &str| {
+        json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": name,
+            "trigger": { "type": "manual" },
+            "nodes": [{
+                "id": "sign",
+                "node_type": "crypto",
+                "config": { "operation": "hmac", "value": "hello", "secret": "SHARED_SIGNING_KEY" }
+            }],
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z",
+            "queue": "project-secrets"
+        })
+    };
+    let (_, shared) = app.post("/api/v1/workflows", json!({ "name": "shared", "definition": definition("shared") })).await;
+    let (_, own) = app.post("/api/v1/workflows", json!({ "name": "own", "definition": definition("own") })).await;
+    let (shared, own) = (shared["id"].as_str().unwrap(), own["id"].as_str().unwrap());
+
+    let (status, created) = app.post("/api/v1/secrets", json!({ "key": "SHARED_SIGNING_KEY", "value": "k3y" })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["key"], "SHARED_SIGNING_KEY");
+    let (status, _) = app.post("/api/v1/secrets", json!({ "key": "SHARED_SIGNING_KEY", "value": "again" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (_, listed) = app.get("/api/v1/secrets").await;
+    assert!(listed.as_array().unwrap().iter().any(|secret| secret["key"] == "SHARED_SIGNING_KEY"));
+    assert!(!listed.to_string().contains("k3y"));
+    // Not among either workflow's own.
+    let (_, listed) = app.get(&format!("/api/v1/workflows/{shared}/secrets")).await;
+    assert_eq!(listed, json!([]));
+    app.post(&format!("/api/v1/workflows/{own}/secrets"), json!({ "key": "SHARED_SIGNING_KEY", "value": "mine" })).await;
+
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default())
+        .with_secrets_key(SecretsKey::parse(SECRETS_KEY).unwrap());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["project-secrets".into()]);
+    // HMAC-SHA256 of "hello" under "k3y".
+    let project = json!({ "signature": "876e76604d6817debfcac7bda97f616a5641178941207e9d557beac9e44bc25a" });
+
+    app.post(&format!("/api/v1/workflows/{shared}/execute"), json!({ "input": {} })).await;
+    let result = worker.run_next().await.unwrap().expect("a job").expect("the execution succeeds");
+    assert_eq!(result.output, project);
+    app.post(&format!("/api/v1/workflows/{own}/execute"), json!({ "input": {} })).await;
+    let result = worker.run_next().await.unwrap().expect("a job").expect("the execution succeeds");
+    assert_ne!(result.output, project);
+
+    let (status, _) =
+        app.request(Method::PUT, "/api/v1/secrets/SHARED_SIGNING_KEY", Some(json!({ "value": "changed" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.request(Method::DELETE, "/api/v1/secrets/SHARED_SIGNING_KEY", None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = app.request(Method::DELETE, "/api/v1/secrets/SHARED_SIGNING_KEY", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+/// A Vault stand-in with a KV v2 engine at `kv` holding `SIGNING_KEY`
+/// for `workflow_id`, read with token `root`.
+async fn vault(workflow_id: String) -> String {
+    let read = move |Path(path): Path<String>, headers: HeaderMap| {
+        let workflow_id = workflow_id.clone();
+        async move {
+            if headers.get("x-vault-token").is_none_or(|token| token != "root") {
+                return (StatusCode::FORBIDDEN, Json(json!({ "errors": ["permission denied"] })));
+            }
+            if path != format!("rusty/{workflow_id}") {
+                return (StatusCode::NOT_FOUND, Json(json!({ "errors": [] })));
+            }
+            (StatusCode::OK, Json(json!({ "data": { "data": { "SIGNING_KEY": "k3y" }, "metadata": { "version": 1 } } })))
+        }
+    };
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = Router::new().route("/v1/kv/data/*path", get(read));
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    format!("http://{addr}")
+}
+
+#[tokio::test]
+async fn secrets_are_fetched_from_an_external_provider() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "vaulted",
+        "trigger": { "type": "manual" },
+        "nodes": [{
+            "id": "sign",
+            "node_type": "crypto",
+            "config": { "operation": "hmac", "value": "hello", "secret": "SIGNING_KEY" }
+        }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "vaulted"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "vaulted", "definition": definition })).await;
+    let id = workflow["id"].as_str().unwrap();
+    // The provider replaces the stored secrets.
+    app.post(&format!("/api/v1/workflows/{id}/secrets"), json!({ "key": "SIGNING_KEY", "value": "stored" })).await;
+    let addr = vault(id.to_owned()).await;
+
+    let worker = |token: &str| {
+        let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default())
+            .with_secrets_key(SecretsKey::parse(SECRETS_KEY).unwrap())
+            .with_secret_provider(Arc::new(VaultSecrets::new(&addr, token, "kv", "rusty")));
+        Worker::new(app.pool.clone(), executor).with_queues(vec!["vaulted".into()])
+    };
+
+    app.post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": {} })).await;
+    let result = worker("root").run_next().await.unwrap().expect("a job").expect("the execution succeeds");
+    // HMAC-SHA256 of "hello" under "k3y".
+    assert_eq!(result.output, json!({ "signature": "876e76604d6817debfcac7bda97f616a5641178941207e9d557beac9e44bc25a" }));
+
+    // A provider that cannot be read fails the execution before it runs.
+    let (_, job) = app.post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": {} })).await;
+    let error = worker("wrong").run_next().await.unwrap().expect("a job").unwrap_err();
+    assert!(error.to_string().contains("Vault answered 403"), "{error}");
+    let (_, execution) = app.get(&format!("/api/v1/executions/{}", job["execution_id"].as_str().unwrap())).await;
+    assert_eq!(execution["execution"]["status"], "failed");
+    assert_eq!(execution["nodes"], json!([]));
+}
+
+#[tokio::test]
+async fn secrets_that_do_not_open_fail_the_execution() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "rekeyed signer",
+        "trigger": { "type": "manual" },
+        "nodes": [{
+            "id": "sign",
+            "node_type": "crypto",
+            "config": { "operation": "hmac", "value": "hello", "secret": "SIGNING_KEY" }
+        }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "rekeyed"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "rekeyed signer", "definition": definition })).await;
+    let id = workflow["id"].as_str().unwrap();
+    app.post(&format!("/api/v1/workflows/{id}/secrets"), json!({ "key": "SIGNING_KEY", "value": "k3y" })).await;
+
+    // A worker holding another key cannot open the stored secret.
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default())
+        .with_secrets_key(SecretsKey::parse("ZmVkY2JhOTg3NjU0MzIxMGZlZGNiYTk4NzY1NDMyMTA=").unwrap());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["rekeyed".into()]);
+    let (_, job) = app.post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": {} })).await;
+    let error = worker.run_next().await.unwrap().expect("a job").unwrap_err();
+    assert!(error.to_string().contains("SIGNING_KEY"), "{error}");
+
+    let (_, execution) = app.get(&format!("/api/v1/executions/{}", job["execution_id"].as_str().unwrap())).await;
+    assert_eq!(execution["execution"]["status"], "failed");
+    assert!(execution["execution"]["finished_at"].is_string(), "{execution}");
+}
+
+#[tokio::test]
+async fn rotation_moves_secrets_and_credentials_to_the_new_key() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "rotated",
+        "trigger": { "type": "manual" },
+        "nodes": [],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "rotated", "definition": definition })).await;
+    let workflow_id: uuid::Uuid = workflow["id"].as_str().unwrap().parse().unwrap();
+    app.post(&format!("/api/v1/workflows/{workflow_id}/secrets"), json!({ "key": "TOKEN", "value": "t0ken" })).await;
+    let credential = json!({ "name": "bot", "type": "api_token", "data": { "token": "s3cret" } });
+    let (_, credential) = app.post("/api/v1/credentials", credential).await;
+    let credential_id: uuid::Uuid = credential["id"].as_str().unwrap().parse().unwrap();
+
+    let old = SecretsKey::parse(SECRETS_KEY).unwrap();
+    let new = SecretsKey::parse("ZmVkY2JhOTg3NjU0MzIxMGZlZGNiYTk4NzY1NDMyMTA=").unwrap();
+    let ring = new.clone().with_previous(vec![old.clone()]);
+
+    // Other tests' values share the database, so counts are at least ours.
+    let planned = engine::secrets::rotate(&app.pool, &ring, true).await.unwrap();
+    assert!(planned.rotated >= 2 && planned.current == 0);
+    let stored = db::repository::secrets::list_secrets(&app.pool, workflow_id).await.unwrap();
+    assert!(!ring.is_current(&stored[0].encrypted_value), "a dry run changes nothing");
+
+    let rotation = engine::secrets::rotate(&app.pool, &ring, false).await.unwrap();
+    assert_eq!((rotation.rotated, rotation.current, rotation.changed), (planned.rotated, 0, 0));
+    assert!(rotation.unreadable.is_empty());
+    let stored = db::repository::secrets::list_secrets(&app.pool, workflow_id).await.unwrap();
+    assert_eq!(new.decrypt(workflow_id, "TOKEN", &stored[0].encrypted_value).unwrap(), "t0ken");
+    let stored = db::repository::credentials::get_credential(&app.pool, credential_id).await.unwrap();
+    assert!(new.decrypt_credential(credential_id, &stored.encrypted_data).is_ok());
+
+    let again = engine::secrets::rotate(&app.pool, &ring, false).await.unwrap();
+    assert_eq!((again.rotated, again.current), (0, planned.rotated));
+
+    // Back to the key the other tests use.
+    let back = engine::secrets::rotate(&app.pool, &old.with_previous(vec![new]), false).await.unwrap();
+    assert_eq!(back.rotated, planned.rotated);
+}
@@ -0,0 +1,72 @@
+//! `GET /api/v1/workflows/:id/stats` aggregates a workflow's executions
+//! over a window, and its nodes' runs from the daily rollup.
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use engine::executor::ExecutorConfig;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn stats_summarize_recent_executions() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "stats",
+        "trigger": { "type": "manual" },
+        "nodes": [{
+            "id": "check",
+            "node_type": "validate_json",
+            "config": { "schema": { "type": "object", "required": ["order"] } }
+        }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "stats"
+    });
+    let (status, workflow) = app.post("/api/v1/workflows", json!({ "name": "stats", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = workflow["id"].as_str().unwrap();
+
+    let (_, idle) = app.get(&format!("/api/v1/workflows/{id}/stats")).await;
+    assert_eq!(idle["executions"]["total"], 0);
+    assert_eq!((&idle["success_rate"], &idle["duration_ms"]["p50"]), (&json!(null), &json!(null)));
+    assert_eq!(idle["most_failing_node"], json!(null));
+    assert_eq!((&idle["bottleneck"], &idle["nodes"]), (&json!(null), &json!([])));
+
+    for input in [json!({ "order": 1 }), json!({ "order": 2 }), json!({})] {
+        let (status, _) = app.post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": input })).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["stats".into()]);
+    while worker.run_next().await.unwrap().is_some() {}
+
+    let (status, stats) = app.get(&format!("/api/v1/workflows/{id}/stats?days=7")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stats["window"]["days"], 7);
+    assert_eq!(stats["executions"], json!({ "total": 3, "succeeded": 2, "failed": 1 }));
+    assert!((stats["success_rate"].as_f64().unwrap() - 2.0 / 3.0).abs() < 1e-9);
+    let durations = &stats["duration_ms"];
+    let (p50, p99) = (durations["p50"].as_f64().unwrap(), durations["p99"].as_f64().unwrap());
+    assert!(0.0 <= p50 && p50 <= p99, "{durations}");
+    assert!(durations["avg"].as_f64().unwrap() >= 0.0);
+
+    let days = stats["per_day"].as_array().unwrap();
+    assert_eq!(days.len(), 8, "every day the window touches");
+    assert!(days[..7].iter().all(|day| day["total"] == 0));
+    assert_eq!((&days[7]["total"], &days[7]["failed"]), (&json!(3), &json!(1)));
+    assert_eq!(stats["most_failing_node"], json!({ "node_id": "check", "failures": 1 }));
+
+    assert_eq!(stats["bottleneck"], "check");
+    let node = &stats["nodes"][0];
+    assert_eq!((&node["node_id"], &node["runs"], &node["failures"]), (&json!("check"), &json!(3), &json!(1)));
+    assert!(node["max_ms"].as_i64().unwrap() >= 0 && node["total_ms"].as_i64() >= node["max_ms"].as_i64());
+
+    let (status, _) = app.get(&format!("/api/v1/workflows/{id}/stats?days=0")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app.get(&format!("/api/v1/workflows/{}/stats", uuid::Uuid::new_v4())).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,90 @@
+//! Support access is audited under the caller's identity, with the named
+//! staff member kept alongside the reason.
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+
+use api::auth::{ApiKey, Auth};
+
+use crate::harness::TestApp;
+
+/// A `GET` of the `ops` admin, with the given support headers.
+fn as_ops(uri: &str, support: &[(&str, &str)]) -> Request<Body> {
+    let mut request = Request::builder().method(Method::GET).uri(uri).header(header::AUTHORIZATION, "Bearer 0ps");
+    for (name, value) in support {
+        request = request.header(*name, *value);
+    }
+    request.body(Body::empty()).unwrap()
+}
+
+/// Create a workflow, as `bearer` when given.
+async fn create_workflow(app: &TestApp, bearer: Option<&str>, name: &str) -> String {
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": name,
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let mut request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/workflows")
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(bearer) = bearer {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {bearer}"));
+    }
+    let body = json!({ "name": name, "definition": definition });
+    let (status, workflow) = app.send(request.body(Body::from(body.to_string())).unwrap()).await;
+    assert_eq!(status, StatusCode::CREATED, "{workflow}");
+    workflow["id"].as_str().unwrap().to_owned()
+}
+
+/// The audit entries of support views of `target`.
+fn views_of<'a>(log: &'a Value, target: &str) -> Vec<&'a Value> {
+    log.as_array().unwrap().iter().filter(|entry| entry["target_id"] == target).collect()
+}
+
+#[tokio::test]
+async fn support_views_are_audited_under_the_callers_identity() {
+    let keys = [ApiKey::parse("ops=0ps").unwrap()];
+    let app = TestApp::start_with_auth(Auth::new(keys.to_vec(), None).with_admins(["ops".to_owned()])).await;
+    let id = create_workflow(&app, Some("0ps"), "support audited").await;
+    let uri = format!("/api/v1/support/workflows/{id}");
+
+    let (status, _) = app.send(as_ops(&uri, &[("x-support-actor", "ops")])).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "a reason is still required");
+    let named = [("x-support-actor", "someone-else"), ("x-support-reason", "TICKET-1")];
+    let (status, _) = app.send(as_ops(&uri, &named)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.send(as_ops(&uri, &[("x-support-reason", "TICKET-2")])).await;
+    assert_eq!(status, StatusCode::OK, "the actor header is optional when authenticated");
+
+    let (status, log) = app.send(as_ops("/api/v1/admin/audit-log?limit=10", &[])).await;
+    assert_eq!(status, StatusCode::OK);
+    let views = views_of(&log, &id);
+    assert_eq!(views.len(), 2, "{log}");
+    assert!(views.iter().all(|entry| entry["actor"] == "ops" && entry["action"] == "support.view_workflow"), "{log}");
+    let details: Vec<_> = views.iter().map(|entry| &entry["details"]).collect();
+    assert!(details.contains(&&json!({ "reason": "TICKET-1", "support_actor": "someone-else" })), "{log}");
+    assert!(details.contains(&&json!({ "reason": "TICKET-2" })), "{log}");
+}
+
+#[tokio::test]
+async fn without_authentication_the_support_actor_header_names_the_actor() {
+    let app = TestApp::start().await;
+    let id = create_workflow(&app, None, "support anonymous").await;
+    let uri = format!("/api/v1/support/workflows/{id}");
+
+    let request = Request::builder().uri(&uri).header("x-support-reason", "TICKET-3").body(Body::empty()).unwrap();
+    assert_eq!(app.send(request).await.0, StatusCode::BAD_REQUEST);
+    let (status, _) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, log) = app.get("/api/v1/admin/audit-log?limit=10").await;
+    let views = views_of(&log, &id);
+    assert_eq!(views.len(), 1, "{log}");
+    assert_eq!(views[0]["actor"], "it");
+    assert_eq!(views[0]["details"], json!({ "reason": "integration test", "support_actor": "it" }));
+}
@@ -0,0 +1,106 @@
+//! Webhook throttling and debouncing.
+
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use futures_util::future::join_all;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use engine::triggers::{self, Admission};
+use engine::Workflow;
+use queue::PgJobQueue;
+
+use crate::harness::TestApp;
+
+/// Create a webhook workflow at `/webhook/{path}` with the trigger options
+/// in `options`; returns its id and definition.
+async fn create_webhook(app: &TestApp, path: &str, options: Value) -> (Uuid, Workflow) {
+    let mut trigger = json!({ "type": "webhook", "path": path });
+    trigger.as_object_mut().unwrap().extend(options.as_object().unwrap().clone());
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": path,
+        "trigger": trigger,
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (status, row) = app.post("/api/v1/workflows", json!({ "name": path, "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED, "{row}");
+    let id = row["id"].as_str().unwrap().parse().unwrap();
+    (id, serde_json::from_value(row["definition"].clone()).unwrap())
+}
+
+async fn executions_of(app: &TestApp, workflow_id: Uuid) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM workflow_executions WHERE workflow_id = $1")
+        .bind(workflow_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn events_beyond_max_per_minute_are_rejected() {
+    let app = TestApp::start().await;
+    let (id, _) = create_webhook(&app, "it-throttled", json!({ "throttle": { "max_per_minute": 2 } })).await;
+
+    for n in 0..2 {
+        let (status, _) = app.post("/webhook/it-throttled", json!({ "n": n })).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+    let (status, _) = app.post("/webhook/it-throttled", json!({ "n": 2 })).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(executions_of(&app, id).await, 2);
+
+    while app.run_next_job().await.is_some() {}
+}
+
+#[tokio::test]
+async fn concurrent_events_cannot_overrun_the_throttle() {
+    let app = TestApp::start().await;
+    let (id, workflow) = create_webhook(&app, "it-throttle-race", json!({ "throttle": { "max_per_minute": 3 } })).await;
+    let queue = Arc::new(PgJobQueue::new(app.pool.clone()));
+
+    let admissions = join_all((0..12).map(|n| {
+        let (pool, queue, workflow) = (app.pool.clone(), queue.clone(), workflow.clone());
+        tokio::spawn(async move { triggers::admit(&pool, queue.as_ref(), id, &workflow, json!({ "n": n })).await })
+    }))
+    .await;
+    let admissions: Vec<_> = admissions.into_iter().map(|joined| joined.unwrap().unwrap()).collect();
+    let enqueued = admissions.iter().filter(|a| matches!(a, Admission::Enqueued(_))).count();
+    let throttled = admissions.iter().filter(|a| matches!(a, Admission::Throttled)).count();
+    assert_eq!((enqueued, throttled), (3, 9));
+    assert_eq!(executions_of(&app, id).await, 3);
+
+    while app.run_next_job().await.is_some() {}
+}
+
+#[tokio::test]
+async fn debounced_events_fold_into_the_pending_job_and_push_it_back() {
+    let app = TestApp::start().await;
+    let options = json!({ "debounce": { "window_secs": 60, "payload": "merge" } });
+    let (id, _) = create_webhook(&app, "it-debounced", options).await;
+
+    let run_at = |answer: &Value| answer["run_at"].as_str().unwrap().parse::<DateTime<Utc>>().unwrap();
+    let (status, first) = app.post("/webhook/it-debounced", json!({ "a": 1 })).await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{first}");
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    let (status, second) = app.post("/webhook/it-debounced", json!({ "b": 2 })).await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{second}");
+    assert!(run_at(&second) > run_at(&first), "{first} then {second}");
+
+    let jobs: Vec<(Value, DateTime<Utc>)> =
+        sqlx::query_as("SELECT payload, run_at FROM job_queue WHERE workflow_id = $1 AND status = 'pending'")
+            .bind(id)
+            .fetch_all(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!((&jobs[0].0["body"], jobs[0].1), (&json!({ "a": 1, "b": 2 }), run_at(&second)));
+    assert_eq!(executions_of(&app, id).await, 1);
+
+    // Not due for a minute; no other test should find it.
+    sqlx::query("DELETE FROM job_queue WHERE workflow_id = $1").bind(id).execute(&app.pool).await.unwrap();
+}
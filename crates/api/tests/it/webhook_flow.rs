@@ -0,0 +1,264 @@
+//! Create a workflow, trigger it by webhook, run it, and read it back.
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::assert_redacted_snapshot;
+use crate::harness::TestApp;
+
+fn top_orders_workflow() -> serde_json::Value {
+    json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "top orders",
+        "trigger": { "type": "webhook", "path": "it-top-orders" },
+        "nodes": [
+            {
+                "id": "check",
+                "node_type": "validate_json",
+                "config": { "field": "body", "schema": { "type": "object", "required": ["orders"] } }
+            },
+            {
+                "id": "top",
+                "node_type": "sort_limit",
+                "config": {
+                    "field": "body.orders",
+                    "sort": [{ "field": "total", "order": "desc" }],
+                    "limit": 2
+                }
+            }
+        ],
+        "edges": [{ "from": "check", "to": "top" }],
+        "created_at": "2024-01-01T00:00:00Z"
+    })
+}
+
+#[tokio::test]
+async fn webhook_to_execution_detail() {
+    let app = TestApp::start().await;
+
+    let (status, workflow) = app
+        .post("/api/v1/workflows", json!({ "name": "top orders", "definition": top_orders_workflow() }))
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_redacted_snapshot!("created_workflow", workflow);
+
+    let orders = json!({ "orders": [
+        { "id": "a", "total": 12 },
+        { "id": "b", "total": 40 },
+        { "id": "c", "total": 25 }
+    ] });
+    let (status, accepted) = app.post("/webhook/it-top-orders", orders).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_redacted_snapshot!("webhook_accepted", accepted);
+
+    let result = app.run_next_job().await.expect("a queued job").expect("execution succeeds");
+    assert!(app.run_next_job().await.is_none());
+
+    let (status, detail) = app.get(&format!("/api/v1/support/executions/{}", result.execution_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_redacted_snapshot!("execution_detail", detail);
+}
+
+#[tokio::test]
+async fn unknown_webhook_is_not_found() {
+    let app = TestApp::start().await;
+    let (status, _) = app.post("/webhook/it-nobody-listens", json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn webhook_input_carries_the_whole_request() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "signup form",
+        "trigger": { "type": "webhook", "path": "it-signup" },
+        "nodes": [{
+            "id": "check",
+            "node_type": "validate_json",
+            "config": { "field": "body", "schema": { "type": "object", "required": ["name"] } }
+        }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (status, _) = app.post("/api/v1/workflows", json!({ "name": "signup form", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, _) = app
+        .send_raw(
+            Method::PUT,
+            "/webhook/it-signup?source=landing&tag=a&tag=b",
+            "application/x-www-form-urlencoded",
+            "name=Ada+Lovelace&plan=pro",
+        )
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let form = app.run_next_job().await.expect("a queued job").expect("execution succeeds").output;
+    assert_eq!(form["method"], "PUT");
+    assert_eq!(form["path"], "/webhook/it-signup");
+    assert_eq!(form["query"], json!({ "source": "landing", "tag": ["a", "b"] }));
+    assert_eq!(form["headers"]["content-type"], "application/x-www-form-urlencoded");
+    assert_eq!(form["body"], json!({ "name": "Ada Lovelace", "plan": "pro" }));
+
+    // A text body does not match the schema, so the run fails.
+    let (status, _) = app.send_raw(Method::POST, "/webhook/it-signup", "text/plain", "hello").await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert!(app.run_next_job().await.expect("a queued job").is_err());
+
+    let (status, _) = app.send_raw(Method::POST, "/webhook/it-signup", "application/json", "{ nope").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn sync_webhook_answers_with_the_final_output() {
+    let app = TestApp::start().await;
+    let mut definition = top_orders_workflow();
+    definition["trigger"] = json!({ "type": "webhook", "path": "it-sync", "sync": { "timeout_secs": 10 } });
+    let (status, _) = app.post("/api/v1/workflows", json!({ "name": "sync", "definition": definition })).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let orders = json!({ "orders": [{ "id": "a", "total": 12 }, { "id": "b", "total": 40 }] });
+    let worker = async {
+        loop {
+            if let Some(result) = app.run_next_job().await {
+                return result;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    };
+    let ((status, answer), result) = tokio::join!(app.post("/webhook/it-sync", orders.clone()), worker);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(answer, result.expect("execution succeeds").output);
+    assert_eq!(answer["items"][0]["id"], "b");
+
+    // Nobody runs the job: the caller gets a 202 once the wait is over.
+    definition["trigger"]["sync"] = json!({ "timeout_secs": 0 });
+    definition["trigger"]["path"] = json!("it-sync-late");
+    app.post("/api/v1/workflows", json!({ "name": "late", "definition": definition })).await;
+    let (status, accepted) = app.post("/webhook/it-sync-late", orders).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert!(accepted["execution_id"].is_string());
+    assert!(app.run_next_job().await.expect("the late job").is_ok());
+}
+
+#[tokio::test]
+async fn inactive_workflows_run_manually_but_not_by_webhook() {
+    let app = TestApp::start().await;
+    let mut definition = top_orders_workflow();
+    definition["trigger"]["path"] = json!("it-inactive");
+    let (status, workflow) = app
+        .post("/api/v1/workflows", json!({ "name": "paused", "definition": definition, "active": false }))
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(workflow["active"], json!(false));
+    let id = workflow["id"].as_str().unwrap();
+
+    let orders = json!({ "orders": [{ "id": "a", "total": 1 }] });
+    let (status, _) = app.post("/webhook/it-inactive", orders.clone()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Manual runs are how authors test a workflow before turning it on.
+    let (status, _) = app
+        .post(&format!("/api/v1/workflows/{id}/execute"), json!({ "input": { "body": orders } }))
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    app.run_next_job().await.expect("a queued job").expect("execution succeeds");
+
+    let (status, activated) = app
+        .request(Method::PUT, &format!("/api/v1/workflows/{id}/active"), Some(json!({ "active": true })))
+        .await;
+    assert_eq!((status, &activated["active"]), (StatusCode::OK, &json!(true)));
+    let (status, _) = app.post("/webhook/it-inactive", orders.clone()).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    app.run_next_job().await.expect("a queued job").expect("execution succeeds");
+
+    // Operators pause and resume without a body.
+    let (status, paused) = app.post(&format!("/api/v1/workflows/{id}/deactivate"), Value::Null).await;
+    assert_eq!((status, &paused["active"]), (StatusCode::OK, &json!(false)));
+    let (status, _) = app.post("/webhook/it-inactive", orders.clone()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, resumed) = app.post(&format!("/api/v1/workflows/{id}/activate"), Value::Null).await;
+    assert_eq!((status, &resumed["active"]), (StatusCode::OK, &json!(true)));
+    let (status, _) = app.post("/webhook/it-inactive", orders).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    app.run_next_job().await.expect("a queued job").expect("execution succeeds");
+
+    let missing = "00000000-0000-0000-0000-000000000001";
+    let (status, _) = app
+        .request(Method::PUT, &format!("/api/v1/workflows/{missing}/active"), Some(json!({ "active": true })))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app.post(&format!("/api/v1/workflows/{missing}/deactivate"), Value::Null).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn armed_capture_pins_the_next_request_without_running_it() {
+    let app = TestApp::start().await;
+    let mut definition = top_orders_workflow();
+    definition["trigger"]["path"] = json!("it-capture");
+    let (_, workflow) = app
+        .post("/api/v1/workflows", json!({ "name": "draft", "definition": definition, "active": false }))
+        .await;
+    let capture = format!("/api/v1/workflows/{}/webhook-capture", workflow["id"].as_str().unwrap());
+
+    let (status, armed) = app.request(Method::POST, &capture, None).await;
+    assert_eq!((status, &armed["armed"]), (StatusCode::OK, &json!(true)));
+
+    // The capture answers even though the workflow is inactive, and only once.
+    let orders = json!({ "orders": [{ "id": "a", "total": 1 }] });
+    let (status, captured) = app.post("/webhook/it-capture?source=shop", orders.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(captured["sample"]["body"], orders);
+    assert!(app.run_next_job().await.is_none());
+    let (status, _) = app.post("/webhook/it-capture", json!({ "orders": [] })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, pinned) = app.get(&capture).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(pinned["armed"], json!(false));
+    assert_eq!(pinned["sample"]["query"], json!({ "source": "shop" }));
+    assert_eq!(pinned["sample"]["body"], orders);
+
+    let (status, _) = app.request(Method::DELETE, &capture, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, cleared) = app.get(&capture).await;
+    assert_eq!(cleared["sample"], json!(null));
+
+    // Only webhook-triggered workflows can capture.
+    let mut manual = top_orders_workflow();
+    manual["trigger"] = json!({ "type": "manual" });
+    let (_, other) = app.post("/api/v1/workflows", json!({ "name": "manual", "definition": manual })).await;
+    let (status, _) = app
+        .request(Method::POST, &format!("/api/v1/workflows/{}/webhook-capture", other["id"].as_str().unwrap()), None)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn only_one_active_workflow_answers_a_path() {
+    let app = TestApp::start().await;
+    let mut definition = top_orders_workflow();
+    definition["trigger"] = json!({ "type": "webhook", "path": "it-shared" });
+    let create = |active: bool| json!({ "name": "shared", "definition": definition.clone(), "active": active });
+
+    let (status, first) = app.post("/api/v1/workflows", create(true)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, refused) = app.post("/api/v1/workflows", create(true)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(refused["error"].as_str().unwrap().contains("webhook path"));
+
+    // Inactive workflows may share the path, but not be activated.
+    let (status, second) = app.post("/api/v1/workflows", create(false)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let activate = format!("/api/v1/workflows/{}/activate", second["id"].as_str().unwrap());
+    assert_eq!(app.post(&activate, json!({})).await.0, StatusCode::CONFLICT);
+    let clone = format!("/api/v1/workflows/{}/clone", first["id"].as_str().unwrap());
+    assert_eq!(app.post(&clone, json!({})).await.0, StatusCode::CREATED);
+
+    let (status, _) = app.post("/webhook/it-shared", json!({ "orders": [] })).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let result = app.run_next_job().await.expect("a queued job").expect("execution succeeds");
+    let (_, execution) = app.get(&format!("/api/v1/executions/{}", result.execution_id)).await;
+    assert_eq!(execution["execution"]["workflow_id"], first["id"]);
+}
@@ -0,0 +1,331 @@
+//! A running worker takes queued jobs, several at a time, as soon as they
+//! are announced, settles them, and puts back the ones it cannot finish
+//! when it shuts down; jobs whose worker died are picked up again.  Jobs
+//! that require capability tags only go to workers that have them.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use db::repository::jobs as job_repo;
+use engine::executor::ExecutorConfig;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+use nodes::traits::ExecutionContext;
+use nodes::{ExecutableNode, NodeError};
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn concurrent_worker_completes_every_queued_job() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "check",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": { "required": ["n"] } } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "check", "definition": definition })).await;
+    let execute = format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap());
+
+    let mut executions = Vec::new();
+    for n in 0..5 {
+        // The last input fails validation: a failed execution still
+        // completes its job.
+        let input = if n < 4 { json!({ "n": n }) } else { json!({}) };
+        let (status, job) = app.post(&execute, json!({ "input": input })).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        executions.push(job["execution_id"].as_str().unwrap().parse::<Uuid>().unwrap());
+    }
+
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_concurrency(3);
+    let running = tokio::spawn(worker.run(Duration::from_millis(20)));
+
+    for execution_id in executions {
+        let mut status = String::new();
+        for _ in 0..250 {
+            status = job_repo::list_jobs_for_execution(&app.pool, execution_id).await.unwrap()[0].status.clone();
+            if status == "completed" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(status, "completed", "job of execution {execution_id}");
+    }
+    running.abort();
+}
+
+#[tokio::test]
+async fn idle_worker_wakes_up_for_an_announced_job() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "check",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "check", "definition": definition })).await;
+
+    // The poll interval is far longer than the test: only the
+    // notification can wake the worker.
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let running = tokio::spawn(Worker::new(app.pool.clone(), executor).run(Duration::from_secs(600)));
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let (_, job) = app
+        .post(&format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap()), json!({ "input": {} }))
+        .await;
+    let execution_id: Uuid = job["execution_id"].as_str().unwrap().parse().unwrap();
+    let mut status = String::new();
+    for _ in 0..100 {
+        status = job_repo::list_jobs_for_execution(&app.pool, execution_id).await.unwrap()[0].status.clone();
+        if status == "completed" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(status, "completed");
+    running.abort();
+}
+
+/// A node that never finishes.
+struct Stall;
+
+#[async_trait]
+impl ExecutableNode for Stall {
+    async fn execute(&self, _input: Value, _ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        std::future::pending().await
+    }
+}
+
+#[tokio::test]
+async fn shutdown_puts_unfinished_jobs_back_in_the_queue() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "stall",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "wait", "node_type": "stall", "config": {} }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "stall", "definition": definition })).await;
+    let (_, job) = app
+        .post(&format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap()), json!({ "input": {} }))
+        .await;
+    let execution_id: Uuid = job["execution_id"].as_str().unwrap().parse().unwrap();
+
+    let mut registry = nodes::default_registry();
+    registry.insert("stall".into(), Arc::new(Stall));
+    let executor = WorkflowExecutor::new(app.pool.clone(), registry, ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_grace_period(Duration::from_millis(100));
+    let (stop, stopped) = oneshot::channel::<()>();
+    let running = tokio::spawn(worker.run_until(Duration::from_millis(20), async {
+        let _ = stopped.await;
+    }));
+
+    let job = || async { job_repo::list_jobs_for_execution(&app.pool, execution_id).await.unwrap().remove(0) };
+    for _ in 0..250 {
+        if job().await.status == "processing" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(job().await.status, "processing");
+
+    stop.send(()).unwrap();
+    running.await.unwrap();
+    let released = job().await;
+    assert_eq!((released.status.as_str(), released.attempts), ("pending", 0));
+}
+
+#[tokio::test]
+async fn jobs_with_an_expired_lease_are_reclaimed() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "check",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "check", "definition": definition })).await;
+    app.post(&format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap()), json!({ "input": {} }))
+        .await;
+
+    // A worker claims the job and dies: nobody renews the lease.
+    let lease_end = chrono::Utc::now() + chrono::Duration::seconds(1);
+    let claimed = job_repo::claim_next_job(&app.pool, &[], &[], lease_end).await.unwrap().expect("a due job");
+    assert!(job_repo::reclaim_expired_jobs(&app.pool).await.unwrap().is_empty());
+    assert!(job_repo::heartbeat_job(&app.pool, claimed.id, lease_end).await.unwrap());
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let reclaimed = job_repo::reclaim_expired_jobs(&app.pool).await.unwrap();
+    assert_eq!(reclaimed.len(), 1);
+    assert_eq!((reclaimed[0].id, reclaimed[0].status.as_str()), (claimed.id, "pending"));
+    assert!(!job_repo::heartbeat_job(&app.pool, claimed.id, lease_end).await.unwrap());
+
+    app.run_next_job().await.expect("the reclaimed job").expect("execution succeeds");
+}
+
+#[tokio::test]
+async fn tagged_jobs_only_go_to_workers_with_the_tags() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "infer",
+        "trigger": { "type": "manual" },
+        "nodes": [{
+            "id": "infer",
+            "node_type": "validate_json",
+            "config": { "schema": {} },
+            "worker_tags": ["gpu"]
+        }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "inference",
+        "worker_tags": ["vpn", "gpu"]
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "infer", "definition": definition })).await;
+    let (_, job) = app
+        .post(&format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap()), json!({ "input": {} }))
+        .await;
+    assert_eq!(job["required_tags"], json!(["gpu", "vpn"]));
+
+    let worker = |tags: &[&str]| {
+        let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+        Worker::new(app.pool.clone(), executor)
+            .with_queues(vec!["inference".to_owned()])
+            .with_tags(tags.iter().map(|t| (*t).to_owned()).collect())
+    };
+    assert!(worker(&[]).run_next().await.unwrap().is_none());
+    assert!(worker(&["gpu"]).run_next().await.unwrap().is_none());
+    let result = worker(&["gpu", "vpn", "ssd"]).run_next().await.unwrap().expect("the tagged job");
+    assert_eq!(result.unwrap().execution_id.to_string(), job["execution_id"].as_str().unwrap());
+}
+
+#[tokio::test]
+async fn failed_jobs_wait_for_their_retry() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "flaky",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "flaky"
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "flaky", "definition": definition })).await;
+    app.post(&format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap()), json!({ "input": {} }))
+        .await;
+    let queues = ["flaky".to_owned()];
+    let lease_end = chrono::Utc::now() + chrono::Duration::seconds(60);
+
+    let job = job_repo::claim_next_job(&app.pool, &queues, &[], lease_end).await.unwrap().expect("a due job");
+    let retry_at = chrono::Utc::now() + chrono::Duration::milliseconds(500);
+    job_repo::fail_job(&app.pool, job.id, job.max_attempts, retry_at).await.unwrap();
+    let waiting = job_repo::get_job(&app.pool, job.id).await.unwrap().unwrap();
+    assert_eq!(waiting.status, "pending");
+    assert_eq!((waiting.run_at - retry_at).num_milliseconds(), 0);
+    assert!(job_repo::claim_next_job(&app.pool, &queues, &[], lease_end).await.unwrap().is_none());
+
+    // Out of attempts, the job is dead-lettered instead.
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    let job = job_repo::claim_next_job(&app.pool, &queues, &[], lease_end).await.unwrap().expect("the retry");
+    job_repo::fail_job(&app.pool, job.id, job.attempts, chrono::Utc::now()).await.unwrap();
+    assert_eq!(job_repo::get_job(&app.pool, job.id).await.unwrap().unwrap().status, "dead_lettered");
+}
+
+#[tokio::test]
+async fn attempt_limits_come_from_the_workflow_or_the_call() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "limited",
+        "trigger": { "type": "manual" },
+        "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "queue": "limited",
+        "max_attempts": 5
+    });
+    let (_, workflow) = app.post("/api/v1/workflows", json!({ "name": "limited", "definition": definition })).await;
+    let execute = format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap());
+
+    let (_, job) = app.post(&execute, json!({ "input": {} })).await;
+    assert_eq!(job["max_attempts"], 5);
+    let (_, job) = app.post(&execute, json!({ "input": {}, "max_attempts": 1 })).await;
+    assert_eq!(job["max_attempts"], 1);
+    let (status, _) = app.post(&execute, json!({ "input": {}, "max_attempts": 0 })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // The one-attempt job is dead-lettered on its first failure.
+    let queues = ["limited".to_owned()];
+    let lease_end = chrono::Utc::now() + chrono::Duration::seconds(60);
+    let mut dead = 0;
+    while let Some(job) = job_repo::claim_next_job(&app.pool, &queues, &[], lease_end).await.unwrap() {
+        job_repo::fail_job(&app.pool, job.id, job.max_attempts, chrono::Utc::now() + chrono::Duration::hours(1))
+            .await
+            .unwrap();
+        if job_repo::get_job(&app.pool, job.id).await.unwrap().unwrap().status == "dead_lettered" {
+            dead += 1;
+        }
+    }
+    assert_eq!(dead, 1);
+}
+
+#[tokio::test]
+async fn batches_claim_the_best_due_jobs_once() {
+    let app = TestApp::start().await;
+    let workflow_with = |priority: i32| {
+        json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": "batch",
+            "trigger": { "type": "manual" },
+            "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z",
+            "queue": "batch",
+            "priority": priority
+        })
+    };
+    let mut urgent = Vec::new();
+    for priority in [0, 5] {
+        let (_, workflow) =
+            app.post("/api/v1/workflows", json!({ "name": "batch", "definition": workflow_with(priority) })).await;
+        let execute = format!("/api/v1/workflows/{}/execute", workflow["id"].as_str().unwrap());
+        for _ in 0..2 {
+            let (_, job) = app.post(&execute, json!({ "input": {} })).await;
+            if priority > 0 {
+                urgent.push(job["id"].as_str().unwrap().parse::<Uuid>().unwrap());
+            }
+        }
+    }
+
+    let queues = ["batch".to_owned()];
+    let first = job_repo::fetch_next_jobs(&app.pool, &queues, 3).await.unwrap();
+    assert_eq!(first.len(), 3);
+    assert!(first[..2].iter().all(|j| urgent.contains(&j.id)));
+    assert!(first.iter().all(|j| j.status == "processing" && j.attempts == 1 && j.locked_until.is_some()));
+
+    let rest = job_repo::fetch_next_jobs(&app.pool, &queues, 3).await.unwrap();
+    assert_eq!(rest.len(), 1);
+    assert!(first.iter().all(|j| j.id != rest[0].id));
+    assert!(job_repo::fetch_next_jobs(&app.pool, &queues, 3).await.unwrap().is_empty());
+
+    for job in first.iter().chain(&rest) {
+        job_repo::complete_job(&app.pool, job.id).await.unwrap();
+    }
+}
@@ -0,0 +1,316 @@
+//! Workflow listings are paged summaries of how each last ran; updates
+//! name the version they replace; the definition format is published as a
+//! JSON Schema.
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+
+use engine::executor::ExecutorConfig;
+use engine::worker::Worker;
+use engine::WorkflowExecutor;
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+async fn workflows_are_listed_as_paged_summaries() {
+    let app = TestApp::start().await;
+    for (name, trigger) in [("older", json!({ "type": "manual" })), ("newer", json!({ "type": "webhook", "path": "listed" }))] {
+        let definition = json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": name,
+            "trigger": trigger,
+            "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z",
+            "queue": "listed"
+        });
+        let (status, created) = app.post("/api/v1/workflows", json!({ "name": name, "definition": definition })).await;
+        assert_eq!(status, StatusCode::CREATED);
+        if name == "older" {
+            let execute = format!("/api/v1/workflows/{}/execute", created["id"].as_str().unwrap());
+            assert_eq!(app.post(&execute, json!({ "input": {} })).await.0, StatusCode::ACCEPTED);
+            let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+            let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["listed".to_owned()]);
+            worker.run_next().await.unwrap().expect("a job").expect("execution succeeds");
+        }
+    }
+
+    let (status, page) = app.get("/api/v1/workflows?limit=1").await;
+    assert_eq!(status, StatusCode::OK);
+    let newest = &page["workflows"][0];
+    assert_eq!((&newest["name"], &newest["trigger_type"], &newest["active"]), (&json!("newer"), &json!("webhook"), &json!(true)));
+    assert!(newest.get("definition").is_none());
+    assert_eq!(newest["last_status"], Value::Null);
+    assert_eq!(page["next_offset"], 1);
+
+    let (_, next) = app.get("/api/v1/workflows?limit=1&offset=1").await;
+    assert_eq!((&next["workflows"][0]["name"], &next["workflows"][0]["last_status"]), (&json!("older"), &json!("succeeded")));
+
+    let (_, full) = app.get("/api/v1/workflows?limit=1&full=true").await;
+    assert_eq!(full["workflows"][0]["definition"]["trigger"]["path"], "listed");
+}
+
+#[tokio::test]
+async fn workflows_are_searched_by_name_trigger_and_status() {
+    let app = TestApp::start().await;
+    for (name, trigger, active) in [
+        ("Invoice sync", json!({ "type": "manual" }), true),
+        ("invoice_archive", json!({ "type": "webhook", "path": "invoice-archive" }), false),
+        ("invoiceXarchive", json!({ "type": "manual" }), true),
+    ] {
+        let definition = json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": name,
+            "trigger": trigger,
+            "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z"
+        });
+        app.post("/api/v1/workflows", json!({ "name": name, "definition": definition, "active": active })).await;
+    }
+    let names = |page: &Value| -> Vec<String> {
+        let mut names: Vec<String> =
+            page["workflows"].as_array().unwrap().iter().map(|w| w["name"].as_str().unwrap().to_owned()).collect();
+        names.sort();
+        names
+    };
+
+    let (_, found) = app.get("/api/v1/workflows?name=INVOICE&limit=500").await;
+    assert_eq!(names(&found), ["Invoice sync", "invoiceXarchive", "invoice_archive"]);
+    let (_, literal) = app.get("/api/v1/workflows?name=invoice_&limit=500").await;
+    assert_eq!(names(&literal), ["invoice_archive"], "`_` is not a wildcard");
+    let (_, hooks) = app.get("/api/v1/workflows?name=invoice&trigger=webhook&limit=500").await;
+    assert_eq!(names(&hooks), ["invoice_archive"]);
+    let (_, active) = app.get("/api/v1/workflows?name=invoice&active=true&full=true&limit=500").await;
+    assert_eq!(names(&active), ["Invoice sync", "invoiceXarchive"]);
+}
+
+#[tokio::test]
+async fn workflows_are_searched_by_their_contents() {
+    let app = TestApp::start().await;
+    for (name, base_url) in [
+        ("Charge customers", "https://billing-api.internal"),
+        ("Nightly billing-api report", "https://reports.internal"),
+        ("Open tickets", "https://tickets.internal"),
+    ] {
+        let definition = json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": name,
+            "trigger": { "type": "manual" },
+            "nodes": [{
+                "id": "call",
+                "node_type": "jira",
+                "config": { "base_url": base_url, "operation": "add_comment", "issue": "OPS-1", "body": "Reconciled by zanzibar" }
+            }],
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z"
+        });
+        app.post("/api/v1/workflows", json!({ "name": name, "definition": definition })).await;
+    }
+    let names = |page: &Value| -> Vec<String> {
+        page["workflows"].as_array().unwrap().iter().map(|w| w["name"].as_str().unwrap().to_owned()).collect()
+    };
+
+    // Name matches rank above definition matches.
+    let (status, found) = app.get("/api/v1/workflows?q=billing-api&limit=500").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(names(&found), ["Nightly billing-api report", "Charge customers"]);
+    let (_, full) = app.get("/api/v1/workflows?q=billing-api%20-nightly&full=true").await;
+    assert_eq!(names(&full), ["Charge customers"]);
+    assert_eq!(full["workflows"][0]["definition"]["nodes"][0]["config"]["base_url"], "https://billing-api.internal");
+    let (_, commented) = app.get("/api/v1/workflows?q=zanzibar&name=open").await;
+    assert_eq!(names(&commented), ["Open tickets"]);
+    let (_, none) = app.get("/api/v1/workflows?q=%22billing%20tickets%22").await;
+    assert_eq!(names(&none), Vec::<String>::new());
+}
+
+#[tokio::test]
+async fn workflows_are_tagged_and_filtered_by_tag() {
+    let app = TestApp::start().await;
+    let definition = |name: &str| {
+        json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": name,
+            "trigger": { "type": "manual" },
+            "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": {} } }],
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z"
+        })
+    };
+    let (_, payroll) = app
+        .post("/api/v1/workflows", json!({ "name": "payroll", "definition": definition("payroll"), "tags": [" Team-Finance", "team-finance", ""] }))
+        .await;
+    assert_eq!(payroll["tags"], json!(["team-finance"]));
+    let (_, onboarding) = app.post("/api/v1/workflows", json!({ "name": "onboarding", "definition": definition("onboarding") })).await;
+
+    let tags = format!("/api/v1/workflows/{}/tags", onboarding["id"].as_str().unwrap());
+    let (status, tagged) = app.request(Method::PUT, &tags, Some(json!({ "tags": ["team-people", "Team-Finance"] }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(tagged["tags"], json!(["team-finance", "team-people"]));
+
+    let (_, finance) = app.get("/api/v1/workflows?tag=TEAM-FINANCE&limit=500").await;
+    let names: Vec<&str> = finance["workflows"].as_array().unwrap().iter().map(|w| w["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["onboarding", "payroll"]);
+    assert_eq!(finance["workflows"][0]["tags"], json!(["team-finance", "team-people"]));
+
+    let (_, all) = app.get("/api/v1/workflows/tags").await;
+    let people = all.as_array().unwrap().iter().find(|t| t["tag"] == "team-people").expect("tag listed");
+    assert_eq!(people["workflows"], 1);
+
+    let missing = "/api/v1/workflows/00000000-0000-0000-0000-000000000001/tags";
+    assert_eq!(app.request(Method::PUT, missing, Some(json!({ "tags": [] }))).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn the_definition_schema_is_published() {
+    let app = TestApp::start().await;
+    let (status, schema) = app.get("/api/v1/workflows/schema").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(schema, engine::Workflow::json_schema());
+    assert_eq!(schema["$id"], engine::models::SCHEMA_ID);
+    for field in ["id", "name", "trigger", "nodes", "edges", "created_at"] {
+        assert!(schema["required"].as_array().unwrap().contains(&json!(field)), "{field}");
+    }
+}
+
+#[tokio::test]
+async fn updates_are_versioned_and_can_be_rolled_back() {
+    let app = TestApp::start().await;
+    let definition = |schema: Value| {
+        json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": "versioned",
+            "trigger": { "type": "manual" },
+            "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": schema } }],
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z",
+            "queue": "versioned"
+        })
+    };
+    let (_, created) = app
+        .post("/api/v1/workflows", json!({ "name": "versioned", "definition": definition(json!({})), "author": "ada" }))
+        .await;
+    assert_eq!(created["version"], 1);
+    let workflow = format!("/api/v1/workflows/{}", created["id"].as_str().unwrap());
+
+    let strict = definition(json!({ "required": ["order"] }));
+    let (status, updated) = app
+        .request(Method::PUT, &workflow, Some(json!({ "definition": strict, "author": "grace", "version": 1 })))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((&updated["version"], &updated["name"]), (&json!(2), &json!("versioned")));
+    let invalid = json!({ "definition": { "nodes": 1 }, "version": 2 });
+    let (status, refused) = app.request(Method::PUT, &workflow, Some(invalid)).await;
+    assert_eq!((status, &refused["field"]), (StatusCode::UNPROCESSABLE_ENTITY, &json!("definition.nodes")));
+
+    let (_, job) = app.post(&format!("{workflow}/execute"), json!({ "input": { "order": 1 } })).await;
+    let executor = WorkflowExecutor::new(app.pool.clone(), nodes::default_registry(), ExecutorConfig::default());
+    let worker = Worker::new(app.pool.clone(), executor).with_queues(vec!["versioned".to_owned()]);
+    worker.run_next().await.unwrap().expect("a job").expect("execution succeeds");
+    let (_, run) = app.get(&format!("/api/v1/executions/{}", job["execution_id"].as_str().unwrap())).await;
+    assert_eq!(run["execution"]["workflow_version"], 2);
+
+    let (_, versions) = app.get(&format!("{workflow}/versions")).await;
+    let history: Vec<(&Value, &Value)> =
+        versions.as_array().unwrap().iter().map(|v| (&v["version"], &v["author"])).collect();
+    assert_eq!(history, [(&json!(2), &json!("grace")), (&json!(1), &json!("ada"))]);
+
+    let (status, rolled_back) = app.post(&format!("{workflow}/rollback/1"), json!({ "author": "ada" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rolled_back["version"], 3);
+    assert_eq!(rolled_back["definition"]["nodes"][0]["config"]["schema"], json!({}));
+    assert_eq!(app.post(&format!("{workflow}/rollback/9"), json!({})).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn stale_workflow_updates_are_refused() {
+    let app = TestApp::start().await;
+    let definition = |schema: Value| {
+        json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": "contended",
+            "trigger": { "type": "manual" },
+            "nodes": [{ "id": "check", "node_type": "validate_json", "config": { "schema": schema } }],
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z"
+        })
+    };
+    let (_, created) = app.post("/api/v1/workflows", json!({ "name": "contended", "definition": definition(json!({})) })).await;
+    let workflow = format!("/api/v1/workflows/{}", created["id"].as_str().unwrap());
+    let (_, headers, _) = app.get_bytes(&workflow).await;
+    assert_eq!(headers[header::ETAG], "\"1\"");
+
+    let put = |if_match: Option<&str>, body: Value| {
+        let mut request = Request::builder().method(Method::PUT).uri(&workflow).header("content-type", "application/json");
+        if let Some(tag) = if_match {
+            request = request.header(header::IF_MATCH, tag);
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    };
+    let edit = |required: &str| json!({ "definition": definition(json!({ "required": [required] })) });
+
+    // Two editors load version 1; the first to save wins.
+    let (status, saved) = app.send(put(Some("\"1\""), edit("first"))).await;
+    assert_eq!((status, &saved["version"]), (StatusCode::OK, &json!(2)));
+    let (status, stale) = app.send(put(Some("W/\"1\""), edit("second"))).await;
+    assert_eq!((status, &stale["version"]), (StatusCode::CONFLICT, &json!(2)), "{stale}");
+    let mut body = edit("second");
+    body["version"] = json!(1);
+    assert_eq!(app.send(put(None, body)).await.0, StatusCode::CONFLICT);
+    let (_, current) = app.get(&workflow).await;
+    assert_eq!(current["definition"]["nodes"][0]["config"]["schema"]["required"], json!(["first"]));
+
+    assert_eq!(app.send(put(None, edit("blind"))).await.0, StatusCode::PRECONDITION_REQUIRED);
+    assert_eq!(app.send(put(Some("latest"), edit("blind"))).await.0, StatusCode::BAD_REQUEST);
+    let (status, forced) = app.send(put(Some("*"), edit("forced"))).await;
+    assert_eq!((status, &forced["version"]), (StatusCode::OK, &json!(3)));
+
+    let rollback = |if_match: &str| {
+        let uri = format!("{workflow}/rollback/1");
+        Request::builder().method(Method::POST).uri(uri).header(header::IF_MATCH, if_match).body(Body::empty()).unwrap()
+    };
+    assert_eq!(app.send(rollback("\"2\"")).await.0, StatusCode::CONFLICT);
+    let (status, rolled_back) = app.send(rollback("\"3\"")).await;
+    assert_eq!((status, &rolled_back["version"]), (StatusCode::OK, &json!(4)));
+}
+
+#[tokio::test]
+async fn workflows_are_exported_and_imported_as_bundles() {
+    let app = TestApp::start().await;
+    let definition = json!({
+        "id": "00000000-0000-0000-0000-000000000000",
+        "name": "ticketing",
+        "trigger": { "type": "manual" },
+        "nodes": [{
+            "id": "ticket",
+            "node_type": "jira",
+            "config": { "base_url": "https://jira.local", "api_token_secret": "JIRA_TOKEN", "operation": "create" }
+        }],
+        "edges": [],
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let (_, created) = app
+        .post("/api/v1/workflows", json!({ "name": "ticketing", "definition": definition, "tags": ["support"] }))
+        .await;
+    let (status, mut bundle) = app.get(&format!("/api/v1/workflows/{}/export", created["id"].as_str().unwrap())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((&bundle["format_version"], &bundle["secret_keys"]), (&json!(1), &json!(["JIRA_TOKEN"])));
+    assert_eq!(bundle["tags"], json!(["support"]));
+
+    // Promote it under a name this environment does not have yet, twice.
+    bundle["name"] = json!("ticketing (promoted)");
+    let (status, imported) = app.post("/api/v1/workflows/import", bundle.clone()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!((&imported["workflow"]["version"], &imported["secret_keys"]), (&json!(1), &json!(["JIRA_TOKEN"])));
+
+    bundle["tags"] = json!(["support", "promoted"]);
+    bundle["author"] = json!("release-bot");
+    let (status, again) = app.post("/api/v1/workflows/import", bundle.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(again["workflow"]["id"], imported["workflow"]["id"]);
+    assert_eq!((&again["workflow"]["version"], &again["workflow"]["tags"]), (&json!(2), &json!(["promoted", "support"])));
+
+    bundle["format_version"] = json!(99);
+    assert_eq!(app.post("/api/v1/workflows/import", bundle).await.0, StatusCode::UNPROCESSABLE_ENTITY);
+}
@@ -0,0 +1,99 @@
+//! Where the commands that inspect or change an instance (`export`,
+//! `import`, …) do it: through a running server's API with `--server`, or
+//! straight in the database at `--database-url` (`DATABASE_URL`) without.
+//!
+//! Both act within one project (`--project`, `default` unless named).
+//! Going through the API applies its authentication (`--token`) and
+//! roles; going to the database bypasses them, like `migrate` does.
+
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+
+use db::models::ProjectRow;
+use db::repository::projects as project_repo;
+use db::DbPool;
+
+/// Where commands read and write, and in which project.
+pub enum Backend {
+    Api(Api),
+    Db { pool: DbPool, project: ProjectRow },
+}
+
+impl Backend {
+    /// The server at `server` when given, otherwise the database at
+    /// `database_url`.
+    pub async fn connect(server: Option<&str>, token: Option<&str>, project: &str, database_url: &str) -> Result<Self, String> {
+        if let Some(server) = server {
+            return Ok(Self::Api(Api::new(server, token, project)));
+        }
+        let pool = db::pool::create_pool(database_url, crate::config::COMMAND_POOL_SIZE)
+            .await
+            .map_err(|e| format!("cannot connect to the database: {e}"))?;
+        let project = match project_repo::get_project_by_name(&pool, project).await {
+            Ok(row) => row,
+            Err(db::DbError::NotFound) => return Err(format!("unknown project '{project}'")),
+            Err(e) => return Err(e.to_string()),
+        };
+        Ok(Self::Db { pool, project })
+    }
+}
+
+/// A server's `/api/v1`.
+pub struct Api {
+    client: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+    project: String,
+}
+
+impl Api {
+    pub fn new(server: &str, token: Option<&str>, project: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: format!("{}/api/v1", server.trim_end_matches('/')),
+            token: token.map(str::to_owned),
+            project: project.to_owned(),
+        }
+    }
+
+    /// `GET path` (below `/api/v1`); the JSON body of a 2xx answer.
+    pub async fn get(&self, path: &str) -> Result<Value, String> {
+        self.send(Method::GET, path, None).await.map(|(_, body)| body)
+    }
+
+    /// `method path` with a JSON `body`; the status and JSON body of a 2xx
+    /// answer.
+    pub async fn send(&self, method: Method, path: &str, body: Option<&Value>) -> Result<(StatusCode, Value), String> {
+        let mut request = self
+            .client
+            .request(method.clone(), format!("{}{path}", self.base_url))
+            .header("x-project", &self.project);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request.send().await.map_err(|e| format!("{method} {path}: {e}"))?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| format!("{method} {path}: {e}"))?;
+        let body = if text.is_empty() { Value::Null } else { serde_json::from_str(&text).unwrap_or(Value::String(text)) };
+        if !status.is_success() {
+            let reason = body.get("error").and_then(Value::as_str).map(str::to_owned).unwrap_or_else(|| body.to_string());
+            return Err(format!("{method} {path}: {status}: {reason}"));
+        }
+        Ok((status, body))
+    }
+}
+
+/// `segment` escaped for a URL path, e.g. a node id in
+/// `/executions/:id/nodes/:node_id/logs`.
+pub fn escape(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => char::from(byte).to_string(),
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
@@ -0,0 +1,298 @@
+//! `bench`: start runs of a workflow at a steady rate and report how fast
+//! they complete, to size workers and the database before production.
+//!
+//! Runs are started open-loop, `rps` per second whether or not earlier
+//! ones have finished, so a system that cannot keep up shows it as
+//! growing latency rather than a lower offered rate.
+//!
+//! Against a server, runs go through the execute endpoint and are picked
+//! up by its workers.  Each is timed twice: how long the endpoint took to
+//! accept it, and how long it took from being queued to finishing, by the
+//! execution's own timestamps — collected once every run was started, so
+//! the polling adds no load while measuring.  Locally, runs go through the
+//! in-process executor with no server or database, which is what the
+//! engine and the nodes cost on their own.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::Method;
+use serde_json::{json, Value};
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+use db::models::ExecutionSummaryRow;
+use engine::executor::ExecutorConfig;
+use engine::local::LocalRunner;
+use engine::Workflow;
+
+use crate::backend::Api;
+
+/// Executions fetched per page while collecting the server's timings.
+const PAGE_SIZE: i64 = 500;
+/// Pause between collection passes while runs are still going.
+const COLLECT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What runs.
+pub enum Target {
+    /// The in-process executor.
+    Local(Box<Workflow>),
+    /// Stored workflow `workflow` on the server behind `api`.
+    Server { api: Arc<Api>, workflow: Uuid },
+}
+
+/// How hard and how long.
+pub struct Load {
+    pub rps: f64,
+    pub duration: Duration,
+    pub input: Value,
+    /// How long to wait for the server to finish the runs started.
+    pub drain: Duration,
+}
+
+/// What a benchmark measured.
+#[derive(Default)]
+pub struct Report {
+    pub sent: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Runs that could not be started (or locally, ran into an engine
+    /// error).
+    pub errors: usize,
+    /// The first of those errors, or locally of the failed runs' errors.
+    pub first_error: Option<String>,
+    /// Runs still going when the benchmark stopped waiting.
+    pub unfinished: usize,
+    /// From the first run starting to the last finishing.
+    pub elapsed: Duration,
+    /// How long the execute endpoint took; empty for local runs.
+    pub accept: Vec<Duration>,
+    /// From queued (or started, locally) to finished, for finished runs.
+    pub complete: Vec<Duration>,
+}
+
+impl Report {
+    fn error(&mut self, error: String) {
+        self.errors += 1;
+        self.first_error.get_or_insert(error);
+    }
+}
+
+/// `--duration` and `--drain-timeout`: `500ms`, `30s`, `5m`, `1h`, or
+/// seconds.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("'{value}' is not a duration (500ms, 30s, 5m, 1h)");
+    let split = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: f64 = amount.parse().map_err(|_| invalid())?;
+    let secs = match unit {
+        "ms" => amount / 1000.0,
+        "" | "s" => amount,
+        "m" => amount * 60.0,
+        "h" => amount * 3600.0,
+        _ => return Err(invalid()),
+    };
+    Duration::try_from_secs_f64(secs).map_err(|_| invalid())
+}
+
+/// Run `load` against `target`.
+pub async fn run(target: &Target, load: &Load) -> Result<Report, String> {
+    if !(load.rps.is_finite() && load.rps > 0.0) {
+        return Err("--rps must be positive".into());
+    }
+    let total = (load.rps * load.duration.as_secs_f64()).round().max(1.0) as usize;
+    match target {
+        Target::Local(workflow) => Ok(run_local(workflow, load, total).await),
+        Target::Server { api, workflow } => run_server(api, *workflow, load, total).await,
+    }
+}
+
+async fn run_local(workflow: &Workflow, load: &Load, total: usize) -> Report {
+    let runner = Arc::new(LocalRunner::new(nodes::default_registry(), ExecutorConfig::default()));
+    let workflow = Arc::new(workflow.clone());
+    let started = Instant::now();
+    let mut runs = JoinSet::new();
+    let mut ticks = tokio::time::interval(Duration::from_secs_f64(1.0 / load.rps));
+    for _ in 0..total {
+        ticks.tick().await;
+        let (runner, workflow, input) = (runner.clone(), workflow.clone(), load.input.clone());
+        runs.spawn(async move {
+            let at = Instant::now();
+            let run = runner.run(&workflow, input, |_| {}).await;
+            (run, at.elapsed())
+        });
+    }
+
+    let mut report = Report { sent: total, ..Report::default() };
+    while let Some(joined) = runs.join_next().await {
+        match joined {
+            Ok((Ok(run), took)) => {
+                match run.error {
+                    None => report.succeeded += 1,
+                    Some(error) => {
+                        report.failed += 1;
+                        report.first_error.get_or_insert(error);
+                    }
+                }
+                report.complete.push(took);
+            }
+            Ok((Err(e), _)) => report.error(e.to_string()),
+            Err(e) => report.error(e.to_string()),
+        }
+    }
+    report.elapsed = started.elapsed();
+    report
+}
+
+async fn run_server(api: &Arc<Api>, workflow: Uuid, load: &Load, total: usize) -> Result<Report, String> {
+    // Runs are found again by the server's `started_after`; a second of
+    // slack covers clock skew and the filter's whole seconds.
+    let since = Utc::now() - chrono::Duration::seconds(1);
+    let body = json!({ "input": load.input });
+    let mut requests = JoinSet::new();
+    let mut ticks = tokio::time::interval(Duration::from_secs_f64(1.0 / load.rps));
+    for _ in 0..total {
+        ticks.tick().await;
+        let (api, body) = (api.clone(), body.clone());
+        requests.spawn(async move {
+            let at = Instant::now();
+            let answer = api.send(Method::POST, &format!("/workflows/{workflow}/execute"), Some(&body)).await;
+            let took = at.elapsed();
+            answer.and_then(|(_, job)| {
+                let id = job["execution_id"].as_str().and_then(|id| id.parse::<Uuid>().ok());
+                id.map(|id| (id, took)).ok_or_else(|| format!("unexpected execute answer: {job}"))
+            })
+        });
+    }
+
+    let mut report = Report { sent: total, ..Report::default() };
+    let mut pending = HashSet::new();
+    while let Some(joined) = requests.join_next().await {
+        match joined {
+            Ok(Ok((id, took))) => {
+                pending.insert(id);
+                report.accept.push(took);
+            }
+            Ok(Err(e)) => report.error(e),
+            Err(e) => report.error(e.to_string()),
+        }
+    }
+
+    let deadline = Instant::now() + load.drain;
+    let mut finished: HashMap<Uuid, ExecutionSummaryRow> = HashMap::new();
+    loop {
+        for execution in list_since(api, workflow, since).await? {
+            if execution.finished_at.is_some() && pending.remove(&execution.id) {
+                finished.insert(execution.id, execution);
+            }
+        }
+        if pending.is_empty() || Instant::now() >= deadline {
+            break;
+        }
+        tokio::time::sleep(COLLECT_INTERVAL).await;
+    }
+
+    report.unfinished = pending.len();
+    let mut first: Option<DateTime<Utc>> = None;
+    let mut last: Option<DateTime<Utc>> = None;
+    for execution in finished.values() {
+        let finished_at = execution.finished_at.expect("only finished runs are kept");
+        if execution.status == "succeeded" {
+            report.succeeded += 1;
+        } else {
+            report.failed += 1;
+        }
+        report.complete.push((finished_at - execution.started_at).to_std().unwrap_or_default());
+        first = Some(first.map_or(execution.started_at, |at| at.min(execution.started_at)));
+        last = Some(last.map_or(finished_at, |at| at.max(finished_at)));
+    }
+    if let (Some(first), Some(last)) = (first, last) {
+        report.elapsed = (last - first).to_std().unwrap_or_default();
+    }
+    Ok(report)
+}
+
+/// Every execution of `workflow` started after `since`.
+async fn list_since(api: &Api, workflow: Uuid, since: DateTime<Utc>) -> Result<Vec<ExecutionSummaryRow>, String> {
+    let since = since.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let mut executions = Vec::new();
+    loop {
+        let path = format!(
+            "/workflows/{workflow}/executions?started_after={since}&limit={PAGE_SIZE}&offset={}",
+            executions.len()
+        );
+        let body = api.get(&path).await?;
+        let page: Vec<ExecutionSummaryRow> =
+            serde_json::from_value(body["executions"].clone()).map_err(|e| format!("unexpected executions: {e}"))?;
+        let done = (page.len() as i64) < PAGE_SIZE;
+        executions.extend(page);
+        if done {
+            return Ok(executions);
+        }
+    }
+}
+
+/// Store `workflow` on the server for the benchmark, inactive so its
+/// trigger does not fire.
+pub async fn create_temporary(api: &Api, workflow: &Workflow) -> Result<Uuid, String> {
+    let body = json!({
+        "name": format!("bench: {}", workflow.name),
+        "definition": workflow,
+        "active": false,
+        "tags": ["bench"],
+    });
+    let (_, created) = api.send(Method::POST, "/workflows", Some(&body)).await?;
+    created["id"].as_str().and_then(|id| id.parse().ok()).ok_or_else(|| format!("unexpected workflow: {created}"))
+}
+
+/// Delete the workflow [`create_temporary`] stored, with its executions.
+pub async fn delete_temporary(api: &Api, workflow: Uuid) -> Result<(), String> {
+    api.send(Method::DELETE, &format!("/workflows/{workflow}"), None).await.map(|_| ())
+}
+
+/// `report` for `load`: outcomes, throughput, and latency percentiles.
+pub fn print(report: &Report, load: &Load) {
+    println!(
+        "{} runs at {}/s over {:.1}s: {} succeeded, {} failed, {} errors, {} unfinished",
+        report.sent,
+        load.rps,
+        load.duration.as_secs_f64(),
+        report.succeeded,
+        report.failed,
+        report.errors,
+        report.unfinished
+    );
+    if let Some(error) = &report.first_error {
+        println!("  first error: {error}");
+    }
+    let finished = report.succeeded + report.failed;
+    if finished > 0 && !report.elapsed.is_zero() {
+        println!("  throughput: {:.1} runs/s", finished as f64 / report.elapsed.as_secs_f64());
+    }
+    println!("  {:<9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}", "", "p50", "p90", "p95", "p99", "max", "mean");
+    for (name, timings) in [("accept", &report.accept), ("complete", &report.complete)] {
+        if timings.is_empty() {
+            continue;
+        }
+        let mut sorted = timings.clone();
+        sorted.sort_unstable();
+        let mean = sorted.iter().sum::<Duration>() / sorted.len() as u32;
+        let [p50, p90, p95, p99] = [50.0, 90.0, 95.0, 99.0].map(|p| format_latency(percentile(&sorted, p)));
+        let max = format_latency(*sorted.last().expect("not empty"));
+        println!("  {name:<9} {p50:>9} {p90:>9} {p95:>9} {p99:>9} {max:>9} {:>9}", format_latency(mean));
+    }
+}
+
+/// Nearest-rank `p`th percentile of `sorted`, which is not empty.
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// `latency` in milliseconds below a second, seconds above.
+fn format_latency(latency: Duration) -> String {
+    let ms = latency.as_secs_f64() * 1000.0;
+    if ms < 1000.0 { format!("{ms:.1}ms") } else { format!("{:.2}s", ms / 1000.0) }
+}
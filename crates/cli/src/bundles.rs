@@ -0,0 +1,81 @@
+//! `export` and `import`: workflows as portable bundles (see
+//! [`engine::bundle`]), to keep them in git and promote them between
+//! environments from CI.
+
+use std::path::Path;
+
+use reqwest::Method;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use engine::bundle::{self, WorkflowBundle};
+
+use crate::backend::Backend;
+use crate::workflows;
+
+/// Write workflow `id` as a bundle to `output`, or stdout.
+pub async fn export(backend: &Backend, id: Uuid, output: Option<&Path>) -> Result<(), String> {
+    let bundle: WorkflowBundle = match backend {
+        Backend::Api(api) => {
+            let body = api.get(&format!("/workflows/{id}/export")).await?;
+            serde_json::from_value(body).map_err(|e| format!("unexpected bundle: {e}"))?
+        }
+        Backend::Db { .. } => {
+            let row = workflows::get(backend, id).await?;
+            bundle::export(&row, &nodes::default_registry()).map_err(|e| e.to_string())?
+        }
+    };
+
+    let text = serde_json::to_string_pretty(&bundle).expect("bundles serialize") + "\n";
+    match output {
+        Some(path) => {
+            std::fs::write(path, text).map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+            eprintln!("✅ Exported '{}' to {}", bundle.name, path.display());
+        }
+        None => print!("{text}"),
+    }
+    print_secret_keys(&bundle.secret_keys);
+    Ok(())
+}
+
+/// Import the bundle in `path`: a new workflow, or the next version of the
+/// one with its name.
+pub async fn import(backend: &Backend, path: &Path, author: Option<String>) -> Result<(), String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    let bundle: WorkflowBundle = serde_json::from_str(&text).map_err(|e| format!("invalid bundle: {e}"))?;
+
+    let (workflow, created, secret_keys): (Value, bool, Vec<String>) = match backend {
+        Backend::Api(api) => {
+            let mut body = serde_json::to_value(&bundle).expect("bundles serialize");
+            body["author"] = json!(author);
+            let (status, reply) = api.send(Method::POST, "/workflows/import", Some(&body)).await?;
+            let secret_keys = serde_json::from_value(reply["secret_keys"].clone()).unwrap_or_default();
+            (reply["workflow"].clone(), status == reqwest::StatusCode::CREATED, secret_keys)
+        }
+        Backend::Db { pool, project } => {
+            let workflow = bundle::check(&bundle).map_err(|e| e.to_string())?;
+            let secret_keys = bundle::secret_keys(&workflow, &nodes::default_registry());
+            let tags = bundle.tags.clone();
+            let (row, created) = bundle::import(pool, project.id, bundle, &tags, author.as_deref())
+                .await
+                .map_err(|e| e.to_string())?;
+            (serde_json::to_value(row).expect("rows serialize"), created, secret_keys)
+        }
+    };
+
+    let verb = if created { "Created" } else { "Updated" };
+    println!(
+        "✅ {verb} '{}' ({}, version {})",
+        workflow["name"].as_str().unwrap_or_default(),
+        workflow["id"].as_str().unwrap_or_default(),
+        workflow["version"]
+    );
+    print_secret_keys(&secret_keys);
+    Ok(())
+}
+
+fn print_secret_keys(keys: &[String]) {
+    if !keys.is_empty() {
+        eprintln!("Secrets it reads, to set up where it runs: {}", keys.join(", "));
+    }
+}
@@ -0,0 +1,116 @@
+//! `cron list`: the project's active time-triggered workflows — cron
+//! schedules and polls — with when each fires next, to check the scheduler
+//! will do what was meant before waiting for it.
+//!
+//! Cron fire times are computed the way the scheduler computes them, in
+//! the trigger's timezone.  Polls run once `interval_secs` have passed
+//! since the last one, so their times are counted from now and only
+//! approximate.
+
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use uuid::Uuid;
+
+use engine::scheduler::CronSchedule;
+use engine::{Trigger, Workflow};
+
+use crate::backend::Backend;
+use crate::workflows;
+
+/// How a workflow is triggered over time.
+pub enum Timing {
+    Cron { expression: String, timezone: String },
+    Interval { secs: u64 },
+}
+
+/// One time-triggered workflow.
+pub struct Schedule {
+    pub id: Uuid,
+    pub name: String,
+    pub timing: Timing,
+    /// The next fire times, or why there are none.
+    pub next: Result<Vec<DateTime<Utc>>, String>,
+}
+
+/// Every active cron- or poll-triggered workflow of the project, by name,
+/// with its next `count` fire times.
+pub async fn list(backend: &Backend, count: usize) -> Result<Vec<Schedule>, String> {
+    let now = Utc::now();
+    let mut schedules = Vec::new();
+    for summary in workflows::list(backend).await? {
+        if !summary.active || !matches!(summary.trigger_type.as_deref(), Some("cron" | "poll")) {
+            continue;
+        }
+        let row = workflows::get(backend, summary.id).await?;
+        let workflow: Workflow = match serde_json::from_value(row.definition) {
+            Ok(workflow) => workflow,
+            Err(e) => {
+                eprintln!("⚠️  skipping workflow {} ({}): invalid definition: {e}", row.id, row.name);
+                continue;
+            }
+        };
+        let (timing, next) = match &workflow.trigger {
+            Trigger::Cron { expression, timezone } => {
+                let next = CronSchedule::parse(expression, timezone.as_deref())
+                    .map(|schedule| schedule.upcoming(now, count))
+                    .map_err(|e| e.to_string());
+                let timezone = timezone.clone().unwrap_or_else(|| "UTC".to_owned());
+                (Timing::Cron { expression: expression.clone(), timezone }, next)
+            }
+            Trigger::Poll { interval_secs, .. } => {
+                let step = Duration::seconds(*interval_secs as i64);
+                let next = (1..=count as i32).map(|i| now + step * i).collect();
+                (Timing::Interval { secs: *interval_secs }, Ok(next))
+            }
+            _ => continue,
+        };
+        schedules.push(Schedule { id: row.id, name: row.name, timing, next });
+    }
+    schedules.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(schedules)
+}
+
+/// `schedules`, each with its fire times in its own timezone.
+pub fn print(schedules: &[Schedule]) {
+    if schedules.is_empty() {
+        println!("No active cron or poll workflows.");
+        return;
+    }
+    for (i, schedule) in schedules.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{} ({})", schedule.name, schedule.id);
+        let (tz, approximate) = match &schedule.timing {
+            Timing::Cron { expression, timezone } => {
+                println!("  cron `{expression}` in {timezone}");
+                (timezone.parse::<Tz>().unwrap_or(Tz::UTC), "")
+            }
+            Timing::Interval { secs } => {
+                println!("  polls every {secs}s");
+                (Tz::UTC, "~")
+            }
+        };
+        match &schedule.next {
+            Ok(next) if next.is_empty() => println!("  never fires again"),
+            Ok(next) => {
+                for at in next {
+                    let local = at.with_timezone(&tz).format("%a %Y-%m-%d %H:%M:%S %Z");
+                    println!("  {approximate}{local}  ({})", from_now(*at));
+                }
+            }
+            Err(e) => println!("  ❌ not scheduled: {e}"),
+        }
+    }
+}
+
+/// How long until `at`: `in 45s`, `in 3h 20m`, `in 2d 4h`.
+fn from_now(at: DateTime<Utc>) -> String {
+    let secs = (at - Utc::now()).num_seconds().max(0);
+    match secs {
+        0..=59 => format!("in {secs}s"),
+        60..=3599 => format!("in {}m {}s", secs / 60, secs % 60),
+        3600..=86_399 => format!("in {}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("in {}d {}h", secs / 86_400, secs % 86_400 / 3600),
+    }
+}
@@ -0,0 +1,173 @@
+//! `executions list` and `executions show`: a project's runs and the
+//! per-node timeline of one, for debugging from a shell.
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use db::models::{ExecutionFilter, ExecutionSummaryRow, WorkflowExecutionRow};
+use db::repository::executions as exec_repo;
+
+use crate::backend::Backend;
+
+/// Statuses an execution can have, for `--status`.
+pub const STATUSES: [&str; 5] = ["pending", "running", "waiting", "succeeded", "failed"];
+
+/// One node of an execution's timeline.
+#[derive(Deserialize)]
+pub struct Step {
+    pub node_id: String,
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+/// The latest `limit` executions matching `filter` (its project is the
+/// backend's), newest first.
+pub async fn list(backend: &Backend, filter: ExecutionFilter, limit: i64) -> Result<Vec<ExecutionSummaryRow>, String> {
+    match backend {
+        Backend::Api(api) => {
+            let mut path = format!("/executions?limit={limit}");
+            if let Some(workflow_id) = filter.workflow_id {
+                path = format!("/workflows/{workflow_id}/executions?limit={limit}");
+            }
+            if let Some(status) = &filter.status {
+                path += &format!("&status={status}");
+            }
+            if let Some(since) = filter.started_after {
+                path += &format!("&started_after={}", since.to_rfc3339_opts(SecondsFormat::Secs, true));
+            }
+            let body = api.get(&path).await?;
+            serde_json::from_value(body["executions"].clone()).map_err(|e| format!("unexpected executions: {e}"))
+        }
+        Backend::Db { pool, project } => {
+            let filter = ExecutionFilter { project_id: Some(project.id), ..filter };
+            exec_repo::list_executions(pool, &filter, limit, 0).await.map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Execution `id` and its nodes in the order they ran, when it belongs to
+/// the project.
+pub async fn get(backend: &Backend, id: Uuid) -> Result<(WorkflowExecutionRow, Vec<Step>), String> {
+    #[derive(Deserialize)]
+    struct Detail {
+        execution: WorkflowExecutionRow,
+        nodes: Vec<Step>,
+    }
+
+    match backend {
+        Backend::Api(api) => {
+            let body = api.get(&format!("/executions/{id}")).await?;
+            let detail: Detail = serde_json::from_value(body).map_err(|e| format!("unexpected execution: {e}"))?;
+            Ok((detail.execution, detail.nodes))
+        }
+        Backend::Db { pool, project } => {
+            let execution = match exec_repo::get_execution(pool, id).await {
+                Ok(row) => row,
+                Err(db::DbError::NotFound) => return Err(format!("no execution {id} in project '{}'", project.name)),
+                Err(e) => return Err(e.to_string()),
+            };
+            if exec_repo::execution_project(pool, id).await.map_err(|e| e.to_string())? != project.id {
+                return Err(format!("no execution {id} in project '{}'", project.name));
+            }
+            let steps = exec_repo::list_node_executions(pool, id)
+                .await
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|n| Step {
+                    node_id: n.node_id,
+                    status: n.status,
+                    started_at: n.started_at,
+                    finished_at: n.finished_at,
+                    error: n.error,
+                })
+                .collect();
+            Ok((execution, steps))
+        }
+    }
+}
+
+/// `--since`: an age such as `30m`, `24h`, or `7d`, or an RFC 3339 time.
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return Ok(at.with_timezone(&Utc));
+    }
+    let invalid = || format!("'{value}' is neither an age (30m, 24h, 7d) nor an RFC 3339 time");
+    let (amount, unit) = value.split_at(value.len().saturating_sub(1));
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let age = match unit {
+        "s" => Duration::seconds(amount),
+        "m" => Duration::minutes(amount),
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        "w" => Duration::weeks(amount),
+        _ => return Err(invalid()),
+    };
+    Ok(Utc::now() - age)
+}
+
+/// `executions` as a table.
+pub fn print_table(executions: &[ExecutionSummaryRow]) {
+    if executions.is_empty() {
+        println!("No executions.");
+        return;
+    }
+    let name_width = executions.iter().map(|e| e.workflow_name.chars().count()).max().unwrap_or(0).max(8);
+    println!("{:<36}  {:<name_width$}  {:<9}  {:<20}  DURATION", "ID", "WORKFLOW", "STATUS", "STARTED");
+    for e in executions {
+        println!(
+            "{:<36}  {:<name_width$}  {:<9}  {:<20}  {}",
+            e.id,
+            e.workflow_name,
+            e.status,
+            e.started_at.format("%Y-%m-%d %H:%M:%S"),
+            took(e.started_at, e.finished_at),
+        );
+    }
+}
+
+/// Execution `execution` and the timeline of its `steps`: when each node
+/// started relative to the run, how long it took, and why it failed.
+pub fn print_timeline(execution: &WorkflowExecutionRow, steps: &[Step]) {
+    println!("{} — {}", execution.id, execution.status);
+    println!("  workflow: {} (version {})", execution.workflow_id, execution.workflow_version.map_or("?".to_owned(), |v| v.to_string()));
+    println!("  started:  {}", execution.started_at.to_rfc3339());
+    println!("  duration: {}", took(execution.started_at, execution.finished_at));
+    if let Some(key) = &execution.business_key {
+        println!("  key:      {key}");
+    }
+    if let Some(retry_of) = execution.retry_of {
+        println!("  retries:  {retry_of}");
+    }
+    if steps.is_empty() {
+        println!("  no nodes ran");
+        return;
+    }
+    println!("  {:>9}  {:>9}  {:<9}  NODE", "AT", "TOOK", "STATUS");
+    for step in steps {
+        let at = format_duration(step.started_at - execution.started_at);
+        println!("  {at:>9}  {:>9}  {:<9}  {}", took(step.started_at, step.finished_at), step.status, step.node_id);
+        if let Some(error) = &step.error {
+            for line in error.lines() {
+                println!("  {:>33}{line}", "");
+            }
+        }
+    }
+}
+
+/// How long something that started at `start` took, or `running`.
+fn took(start: DateTime<Utc>, finish: Option<DateTime<Utc>>) -> String {
+    finish.map_or("running".to_owned(), |finish| format_duration(finish - start))
+}
+
+/// `duration` at a readable precision: `850ms`, `12.3s`, `4m05s`.
+fn format_duration(duration: Duration) -> String {
+    let ms = duration.num_milliseconds().max(0);
+    match ms {
+        0..=999 => format!("{ms}ms"),
+        1_000..=59_999 => format!("{:.1}s", ms as f64 / 1000.0),
+        _ => format!("{}m{:02}s", ms / 60_000, ms / 1000 % 60),
+    }
+}
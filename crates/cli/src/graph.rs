@@ -0,0 +1,66 @@
+//! `graph`: a workflow as DOT or Mermaid (see [`engine::graph`]), from a
+//! definition file or a stored workflow, optionally coloured by how one of
+//! its executions went.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use uuid::Uuid;
+
+use engine::graph::{self, GraphFormat};
+use engine::Workflow;
+
+use crate::backend::Backend;
+use crate::{executions, workflows};
+
+/// Where the workflow to draw comes from.
+pub enum Source<'a> {
+    File(&'a Path),
+    Stored(Uuid),
+}
+
+impl<'a> Source<'a> {
+    /// A workflow id, unless a file of that name exists.
+    pub fn parse(value: &'a str) -> Self {
+        match value.parse() {
+            Ok(id) if !Path::new(value).exists() => Self::Stored(id),
+            _ => Self::File(Path::new(value)),
+        }
+    }
+}
+
+/// Draw the workflow from `source` in `format`, with the statuses of
+/// `execution`'s nodes when given; `backend` is only connected to when
+/// needed.
+pub async fn draw<F>(
+    source: Source<'_>,
+    execution: Option<Uuid>,
+    format: GraphFormat,
+    backend: impl FnOnce() -> F,
+) -> Result<String, String>
+where
+    F: std::future::Future<Output = Backend>,
+{
+    let backend = match (&source, execution) {
+        (Source::File(_), None) => None,
+        _ => Some(backend().await),
+    };
+    let workflow: Workflow = match source {
+        Source::File(path) => {
+            let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+            serde_json::from_str(&text).map_err(|e| format!("invalid workflow JSON: {e}"))?
+        }
+        Source::Stored(id) => {
+            let row = workflows::get(backend.as_ref().expect("connected for stored workflows"), id).await?;
+            serde_json::from_value(row.definition).map_err(|e| format!("invalid stored definition: {e}"))?
+        }
+    };
+
+    let mut statuses = HashMap::new();
+    if let Some(id) = execution {
+        let (_, steps) = executions::get(backend.as_ref().expect("connected for executions"), id).await?;
+        // A node run more than once shows how it last went.
+        statuses.extend(steps.into_iter().map(|step| (step.node_id, step.status)));
+    }
+    Ok(graph::render(&workflow, format, &statuses))
+}
@@ -0,0 +1,111 @@
+//! `logs`: the lines an execution's nodes logged, and with `--follow` the
+//! ones they log next, until the execution finishes.
+//!
+//! Both backends are polled: the API's paged logs endpoint, or the
+//! `execution_logs` table.
+
+use std::time::Duration;
+
+use serde_json::Value;
+use uuid::Uuid;
+
+use db::models::{ExecutionLogRow, LogFilter};
+use db::repository::logs as log_repo;
+use nodes::traits::LogLevel;
+
+use crate::backend::{self, Backend};
+use crate::executions;
+
+/// Lines fetched per request.
+const PAGE_SIZE: i64 = 1000;
+
+/// What to print and how.
+pub struct Tail {
+    pub execution_id: Uuid,
+    pub node_id: Option<String>,
+    /// Least severe level to print.
+    pub level: Option<LogLevel>,
+    pub follow: bool,
+    /// Pause between polls while following.
+    pub interval: Duration,
+}
+
+/// Print the execution's lines, following them as `tail` says.
+pub async fn tail(backend: &Backend, tail: &Tail) -> Result<(), String> {
+    // Checks the execution exists in the project before any polling.
+    let (mut execution, _) = executions::get(backend, tail.execution_id).await?;
+    let mut offset = 0;
+    loop {
+        let finished = is_finished(&execution.status);
+        loop {
+            let lines = page(backend, tail, offset).await?;
+            offset += lines.len() as i64;
+            lines.iter().for_each(print_line);
+            if (lines.len() as i64) < PAGE_SIZE {
+                break;
+            }
+        }
+        // Lines logged before the execution finished have all been read.
+        if !tail.follow || finished {
+            break;
+        }
+        tokio::time::sleep(tail.interval).await;
+        execution = executions::get(backend, tail.execution_id).await?.0;
+    }
+    if tail.follow {
+        eprintln!("— execution {}", execution.status);
+    }
+    Ok(())
+}
+
+/// Whether an execution with `status` will log no more.
+fn is_finished(status: &str) -> bool {
+    matches!(status, "succeeded" | "failed")
+}
+
+async fn page(backend: &Backend, tail: &Tail, offset: i64) -> Result<Vec<ExecutionLogRow>, String> {
+    match backend {
+        Backend::Api(api) => {
+            let mut path = match &tail.node_id {
+                Some(node_id) => format!("/executions/{}/nodes/{}/logs", tail.execution_id, backend::escape(node_id)),
+                None => format!("/executions/{}/logs", tail.execution_id),
+            };
+            path += &format!("?limit={PAGE_SIZE}&offset={offset}");
+            if let Some(level) = tail.level {
+                path += &format!("&level={}", level.as_str());
+            }
+            let body = api.get(&path).await?;
+            serde_json::from_value(body["logs"].clone()).map_err(|e| format!("unexpected logs: {e}"))
+        }
+        Backend::Db { pool, .. } => {
+            let levels = tail.level.map(|least| {
+                LogLevel::ALL.into_iter().filter(|level| *level >= least).map(|level| level.as_str().to_owned()).collect()
+            });
+            let filter = LogFilter { node_id: tail.node_id.clone(), levels };
+            log_repo::list_logs(pool, tail.execution_id, &filter, PAGE_SIZE, offset)
+                .await
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+fn print_line(line: &ExecutionLogRow) {
+    println!(
+        "{} {:<5} [{}] {}{}",
+        line.logged_at.format("%H:%M:%S%.3f"),
+        line.level.to_uppercase(),
+        line.node_id,
+        line.message,
+        format_fields(&line.fields)
+    );
+}
+
+/// `fields` as ` key=value` pairs to follow a line's message, or nothing
+/// when there are none.
+pub fn format_fields(fields: &Value) -> String {
+    match fields {
+        Value::Object(map) => map.iter().map(|(key, value)| format!(" {key}={value}")).collect(),
+        Value::Null => String::new(),
+        other => format!(" {other}"),
+    }
+}
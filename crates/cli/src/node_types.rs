@@ -0,0 +1,101 @@
+//! `nodes list` and `nodes describe`: the node types workflows can use and
+//! the config each accepts, from their descriptors (see
+//! [`nodes::descriptor`]).
+//!
+//! Without `--server` these are the node types built into this binary;
+//! with it, the ones registered on the server, plugins included.
+
+use serde_json::Value;
+
+use nodes::registry::{self, CatalogEntry};
+
+use crate::backend::Api;
+
+/// Every node type, sorted by type.
+pub async fn catalog(api: Option<&Api>) -> Result<Vec<CatalogEntry>, String> {
+    match api {
+        Some(api) => {
+            let body = api.get("/node-types").await?;
+            serde_json::from_value(body).map_err(|e| format!("unexpected node types: {e}"))
+        }
+        None => Ok(registry::catalog(&nodes::default_registry())),
+    }
+}
+
+/// `entries` as a table.
+pub fn print_table(entries: &[CatalogEntry]) {
+    let type_width = entries.iter().map(|e| e.node_type.len()).max().unwrap_or(0).max(4);
+    println!("{:<type_width$}  {:<11}  DESCRIPTION", "TYPE", "CATEGORY");
+    for entry in entries {
+        let summary = entry.descriptor.description.lines().next().unwrap_or_default();
+        println!("{:<type_width$}  {:<11}  {summary}", entry.node_type, category(entry));
+    }
+}
+
+/// Everything the descriptor of `entry` says, ending with its config's
+/// JSON Schema.
+pub fn print_details(entry: &CatalogEntry) {
+    let descriptor = &entry.descriptor;
+    println!("{} ({})", descriptor.display_name, entry.node_type);
+    println!("  category: {}", category(entry));
+    if !descriptor.description.is_empty() {
+        println!();
+        for line in descriptor.description.lines() {
+            println!("  {line}");
+        }
+    }
+    println!();
+    if let Some(input) = &descriptor.input {
+        println!("  input:    {input}");
+    }
+    if let Some(output) = &descriptor.output {
+        println!("  output:   {output}");
+    }
+    if !descriptor.branches.is_empty() {
+        println!("  branches: {}", descriptor.branches.join(", "));
+    }
+    for credential in &descriptor.credentials {
+        let optional = if credential.optional { " (optional)" } else { "" };
+        println!("  secret:   {}{optional} — {}", credential.config_field, credential.description);
+    }
+
+    let schema = &descriptor.config_schema;
+    if schema.is_null() {
+        println!("\n  The config is not described.");
+        return;
+    }
+    if let Some(properties) = schema["properties"].as_object() {
+        let required: Vec<&str> = schema["required"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+        let name_width = properties.keys().map(String::len).max().unwrap_or(0);
+        println!("\n  config fields:");
+        for (name, property) in properties {
+            let required = if required.contains(&name.as_str()) { "required" } else { "optional" };
+            let description = property["description"].as_str().and_then(|d| d.lines().next()).unwrap_or_default();
+            println!("    {name:<name_width$}  {:<10}  {required:<8}  {description}", type_of(property));
+        }
+    }
+    println!("\n  config schema:");
+    let pretty = serde_json::to_string_pretty(schema).expect("JSON values serialize");
+    for line in pretty.lines() {
+        println!("    {line}");
+    }
+}
+
+/// The category of `entry`, as it is serialized (e.g. `integration`).
+pub fn category(entry: &CatalogEntry) -> String {
+    serde_json::to_value(entry.descriptor.category)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_owned))
+        .unwrap_or_default()
+}
+
+/// The JSON type of a schema `property`, e.g. `string` or `integer|null`.
+fn type_of(property: &Value) -> String {
+    match &property["type"] {
+        Value::String(t) => t.clone(),
+        Value::Array(types) => types.iter().filter_map(Value::as_str).collect::<Vec<_>>().join("|"),
+        _ if property.get("$ref").is_some() || property.get("allOf").is_some() => "object".to_owned(),
+        _ if property.get("enum").is_some() => "enum".to_owned(),
+        _ => "any".to_owned(),
+    }
+}
@@ -0,0 +1,223 @@
+//! `plugin new`: a cargo project for a custom node type.
+//!
+//! The project is a library crate whose node registers itself with
+//! `#[register_node]` (see [`nodes::registry`]), so linking it into a binary
+//! is all it takes to make the node type available.  It comes with a
+//! described config, tests that run the node the way the engine does, and
+//! a README on registering it.
+
+use std::path::{Path, PathBuf};
+
+/// Where the generated crate gets the `nodes` crate from, unless told.
+const NODES_GIT: &str = "https://github.com/satwikambashta/rusty-automation-tool";
+
+/// Names derived from the plugin's name.
+struct Names {
+    /// Cargo package, e.g. `acme-greeter`.
+    package: String,
+    /// Node type and crate identifier, e.g. `acme_greeter`.
+    node_type: String,
+    /// Rust type prefix, e.g. `AcmeGreeter`.
+    type_prefix: String,
+}
+
+impl Names {
+    fn new(name: &str) -> Result<Self, String> {
+        let valid = name.starts_with(|c: char| c.is_ascii_lowercase())
+            && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if !valid {
+            return Err(format!("'{name}' is not a plugin name: use lowercase letters, digits, '-' and '_', starting with a letter"));
+        }
+        let type_prefix = name
+            .split(['-', '_'])
+            .filter(|part| !part.is_empty())
+            .map(|part| part[..1].to_uppercase() + &part[1..])
+            .collect();
+        Ok(Self { package: name.to_owned(), node_type: name.replace('-', "_"), type_prefix })
+    }
+}
+
+/// Create the project for plugin `name` in `dir` (default: `./<name>`),
+/// depending on the `nodes` crate at `nodes_path` when given.  Returns the
+/// project's directory.
+pub fn create(name: &str, dir: Option<&Path>, nodes_path: Option<&Path>) -> Result<PathBuf, String> {
+    let names = Names::new(name)?;
+    let dir = dir.map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from(&names.package));
+    if dir.exists() {
+        return Err(format!("{} already exists", dir.display()));
+    }
+
+    let nodes_dependency = match nodes_path {
+        Some(path) => {
+            let path = path.canonicalize().map_err(|e| format!("cannot find {}: {e}", path.display()))?;
+            format!("path = \"{}\"", path.display())
+        }
+        None => format!("git = \"{NODES_GIT}\""),
+    };
+    let render = |template: &str| {
+        template
+            .replace("{{package}}", &names.package)
+            .replace("{{node_type}}", &names.node_type)
+            .replace("{{Type}}", &names.type_prefix)
+            .replace("{{nodes_dependency}}", &nodes_dependency)
+    };
+
+    let write = |relative: &str, contents: String| {
+        let path = dir.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("cannot create {}: {e}", parent.display()))?;
+        }
+        std::fs::write(&path, contents).map_err(|e| format!("cannot write {}: {e}", path.display()))
+    };
+    write("Cargo.toml", render(CARGO_TOML))?;
+    write("src/lib.rs", render(LIB_RS))?;
+    write("README.md", render(README_MD))?;
+    write(".gitignore", "/target\nCargo.lock\n".to_owned())?;
+    Ok(dir)
+}
+
+const CARGO_TOML: &str = r#"[package]
+name = "{{package}}"
+version = "0.1.0"
+edition = "2021"
+description = "The `{{node_type}}` node for rusty-automation-tool."
+
+[dependencies]
+# The node API: `ExecutableNode`, descriptors, and `#[register_node]`.
+nodes = { {{nodes_dependency}}, default-features = false }
+async-trait = "0.1"
+schemars = "1"
+serde = { version = "1", features = ["derive"] }
+serde_json = "1"
+
+[dev-dependencies]
+tokio = { version = "1", features = ["macros", "rt"] }
+uuid = { version = "1", features = ["v4"] }
+"#;
+
+const LIB_RS: &str = r#"//! The `{{node_type}}` node: greets whoever `input[field]` names.
+//!
+//! ```json
+//! { "field": "name", "greeting": "Hello" }
+//! ```
+//!
+//! Output: `{ "message": "Hello, Ada!" }`.
+
+use async_trait::async_trait;
+use nodes::traits::ExecutionContext;
+use nodes::{register_node, ExecutableNode, NodeCategory, NodeDescriptor, NodeError};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Configuration for the `{{node_type}}` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct {{Type}}Config {
+    /// Input field holding the name to greet.
+    pub field: String,
+    /// Word to greet with.
+    #[serde(default = "default_greeting")]
+    pub greeting: String,
+}
+
+fn default_greeting() -> String {
+    "Hello".into()
+}
+
+/// The `{{node_type}}` node.
+#[register_node("{{node_type}}")]
+#[derive(Debug, Default)]
+pub struct {{Type}}Node;
+
+#[async_trait]
+impl ExecutableNode for {{Type}}Node {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        // A bad config never succeeds on retry, so it is a fatal error;
+        // return `NodeError::Retryable` for failures worth retrying.
+        let config: {{Type}}Config = serde_json::from_value(ctx.config.clone()).map_err(|e| {
+            NodeError::Fatal(format!("invalid {{node_type}} config for node '{}': {e}", ctx.node_id))
+        })?;
+        let name = input[config.field.as_str()]
+            .as_str()
+            .ok_or_else(|| NodeError::Fatal(format!("{{node_type}}: input has no text field '{}'", config.field)))?;
+        Ok(json!({ "message": format!("{}, {name}!", config.greeting) }))
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("{{Type}}", "Greet whoever the input names.")
+            .category(NodeCategory::Other)
+            .config::<{{Type}}Config>()
+            .input("an object with the name in input[field]")
+            .output("{ message }")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    /// Run the node on `input` with `config`, as the engine would.
+    async fn run(config: Value, input: Value) -> Result<Value, NodeError> {
+        let ctx = ExecutionContext::new(Uuid::new_v4(), Uuid::new_v4(), input.clone()).for_node("test", config);
+        {{Type}}Node.execute(input, &ctx).await
+    }
+
+    #[tokio::test]
+    async fn greets_the_named_field() {
+        let output = run(json!({ "field": "name", "greeting": "Hi" }), json!({ "name": "Ada" })).await.unwrap();
+        assert_eq!(output, json!({ "message": "Hi, Ada!" }));
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_configs_and_inputs() {
+        assert!(matches!(run(json!({}), json!({ "name": "Ada" })).await, Err(NodeError::Fatal(_))));
+        assert!(matches!(run(json!({ "field": "name" }), json!({})).await, Err(NodeError::Fatal(_))));
+    }
+
+    #[test]
+    fn is_registered_and_described() {
+        let registry = nodes::default_registry();
+        let node = registry.get("{{node_type}}").expect("registered by #[register_node]");
+        assert!(node.descriptor().config_schema.is_object());
+    }
+}
+"#;
+
+const README_MD: &str = r#"# {{package}}
+
+The `{{node_type}}` node type for rusty-automation-tool.
+
+## Develop
+
+```sh
+cargo test
+```
+
+The node's config is described by `{{Type}}Config`; its doc comments end up
+in the JSON Schema that `rusty-automation-tool nodes describe {{node_type}}`
+and the editor show.
+
+## Register
+
+`#[register_node("{{node_type}}")]` registers the node in every binary this
+crate is linked into.  Add it to the binary's dependencies:
+
+```toml
+[dependencies]
+{{package}} = { path = "../{{package}}" }
+```
+
+and make sure it is linked, e.g. in `main.rs`:
+
+```rust
+use {{node_type}} as _;
+```
+
+Then `rusty-automation-tool nodes list` includes `{{node_type}}`, and
+workflows can use it:
+
+```json
+{ "id": "greet", "node_type": "{{node_type}}", "config": { "field": "name" } }
+```
+"#;
@@ -0,0 +1,86 @@
+//! `replay`: run a finished execution again from the start or from one of
+//! its nodes, keeping the recorded outputs of the nodes before it — the
+//! quick way to try a fix against the run that went wrong.
+//!
+//! Straight in the database the replay's job is queued in Postgres; with
+//! workers on Redis, go through `--server`.
+
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use db::models::JobRow;
+use db::repository::executions as exec_repo;
+use engine::retry::ReplayPlan;
+use queue::PgJobQueue;
+
+use crate::backend::Backend;
+
+/// Replay execution `id` from `from_node` (or the start), or with
+/// `dry_run` only plan it.  Returns the plan and, unless a dry run, the
+/// new execution.
+pub async fn replay(
+    backend: &Backend,
+    id: Uuid,
+    from_node: Option<&str>,
+    dry_run: bool,
+) -> Result<(ReplayPlan, Option<Uuid>), String> {
+    match backend {
+        Backend::Api(api) => {
+            #[derive(Deserialize)]
+            struct Replayed {
+                plan: ReplayPlan,
+                job: Option<JobRow>,
+            }
+
+            let body = json!({ "from_node": from_node, "dry_run": dry_run });
+            let (_, answer) = api.send(Method::POST, &format!("/executions/{id}/replay"), Some(&body)).await?;
+            let replayed: Replayed = serde_json::from_value(answer).map_err(|e| format!("unexpected replay: {e}"))?;
+            Ok((replayed.plan, replayed.job.map(|job| job.execution_id)))
+        }
+        Backend::Db { pool, project } => {
+            let not_found = || format!("no execution {id} in project '{}'", project.name);
+            match exec_repo::execution_project(pool, id).await {
+                Ok(project_id) if project_id == project.id => {}
+                Ok(_) | Err(db::DbError::NotFound) => return Err(not_found()),
+                Err(e) => return Err(e.to_string()),
+            }
+            let replayed = if dry_run {
+                engine::retry::plan_replay(pool, id, from_node).await.map(|(plan, _)| (plan, None))
+            } else {
+                let queue = PgJobQueue::new(pool.clone());
+                engine::retry::replay(pool, &queue, id, from_node).await.map(|(plan, job)| (plan, Some(job.execution_id)))
+            };
+            replayed.map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// `plan`, and the execution it started unless `replay` is `None`.
+pub fn print(plan: &ReplayPlan, replay: Option<Uuid>) {
+    let from = plan.from_node.as_deref().map_or("the start".to_owned(), |node| format!("node '{node}'"));
+    println!("{} execution {} from {from}", if replay.is_some() { "Replaying" } else { "Would replay" }, plan.execution_id);
+    println!("  workflow: {}", plan.workflow_id);
+    if !plan.reused.is_empty() {
+        println!("  reused:   {}", plan.reused.join(", "));
+    }
+    println!("  runs:     {}", plan.runs.join(" → "));
+    println!("  input:    {}", compact(&plan.input));
+    match replay {
+        Some(id) => {
+            println!("✅ Queued execution {id}");
+            println!("   follow it with `rusty-automation-tool logs --execution {id} --follow`");
+        }
+        None => println!("Nothing queued (--dry-run)."),
+    }
+}
+
+/// `value` on one line, cut short past 200 characters.
+fn compact(value: &Value) -> String {
+    let text = value.to_string();
+    match text.char_indices().nth(200) {
+        Some((cut, _)) => format!("{}…", &text[..cut]),
+        None => text,
+    }
+}
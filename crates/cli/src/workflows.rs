@@ -0,0 +1,115 @@
+//! `workflows list` and `workflows show`: the workflows of a project, from
+//! a terminal.
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use db::models::{WorkflowFilter, WorkflowRow, WorkflowSummaryRow};
+use db::repository::workflows as wf_repo;
+
+use crate::backend::Backend;
+
+/// Rows fetched per request while listing.
+const PAGE_SIZE: i64 = 500;
+
+/// Every workflow of the project, newest first.
+pub async fn list(backend: &Backend) -> Result<Vec<WorkflowSummaryRow>, String> {
+    let mut workflows = Vec::new();
+    let mut offset = 0;
+    loop {
+        let page: Vec<WorkflowSummaryRow> = match backend {
+            Backend::Api(api) => {
+                let body = api.get(&format!("/workflows?limit={PAGE_SIZE}&offset={offset}")).await?;
+                serde_json::from_value(body["workflows"].clone()).map_err(|e| format!("unexpected workflows: {e}"))?
+            }
+            Backend::Db { pool, project } => {
+                let filter = WorkflowFilter { project_id: Some(project.id), ..Default::default() };
+                wf_repo::list_workflow_summaries(pool, &filter, PAGE_SIZE, offset)
+                    .await
+                    .map_err(|e| e.to_string())?
+            }
+        };
+        let done = (page.len() as i64) < PAGE_SIZE;
+        workflows.extend(page);
+        if done {
+            return Ok(workflows);
+        }
+        offset += PAGE_SIZE;
+    }
+}
+
+/// Workflow `id`, when it belongs to the project.
+pub async fn get(backend: &Backend, id: Uuid) -> Result<WorkflowRow, String> {
+    match backend {
+        Backend::Api(api) => {
+            let body = api.get(&format!("/workflows/{id}")).await?;
+            serde_json::from_value(body).map_err(|e| format!("unexpected workflow: {e}"))
+        }
+        Backend::Db { pool, project } => match wf_repo::get_workflow(pool, id).await {
+            Ok(row) if row.project_id == project.id => Ok(row),
+            Ok(_) | Err(db::DbError::NotFound) => Err(format!("no workflow {id} in project '{}'", project.name)),
+            Err(e) => Err(e.to_string()),
+        },
+    }
+}
+
+/// `workflows` as a table.
+pub fn print_table(workflows: &[WorkflowSummaryRow]) {
+    if workflows.is_empty() {
+        println!("No workflows.");
+        return;
+    }
+    let name_width = workflows.iter().map(|w| w.name.chars().count()).max().unwrap_or(0).max(4);
+    println!("{:<36}  {:<name_width$}  {:<10}  {:<6}  LAST RUN", "ID", "NAME", "TRIGGER", "ACTIVE");
+    for w in workflows {
+        let last_run = match (&w.last_status, w.last_started_at) {
+            (Some(status), Some(at)) => format!("{status} ({})", ago(at)),
+            _ => "never".to_owned(),
+        };
+        println!(
+            "{:<36}  {:<name_width$}  {:<10}  {:<6}  {last_run}",
+            w.id,
+            w.name,
+            w.trigger_type.as_deref().unwrap_or("-"),
+            if w.active { "yes" } else { "no" },
+        );
+    }
+}
+
+/// Workflow `row`: its settings, trigger, and nodes.
+pub fn print_details(row: &WorkflowRow) {
+    let definition = &row.definition;
+    println!("{} ({})", row.name, row.id);
+    println!("  version:  {}", row.version);
+    println!("  active:   {}", if row.active { "yes" } else { "no" });
+    println!("  created:  {}", row.created_at.to_rfc3339());
+    if !row.tags.is_empty() {
+        println!("  tags:     {}", row.tags.join(", "));
+    }
+    println!("  trigger:  {}", definition["trigger"]);
+
+    let nodes = definition["nodes"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let edges = definition["edges"].as_array().map(Vec::as_slice).unwrap_or_default();
+    println!("  nodes ({}):", nodes.len());
+    for node in nodes {
+        let id = node["id"].as_str().unwrap_or_default();
+        let next: Vec<&str> = edges
+            .iter()
+            .filter(|edge| edge["from"].as_str() == Some(id))
+            .filter_map(|edge| edge["to"].as_str())
+            .collect();
+        let arrow = if next.is_empty() { String::new() } else { format!("  → {}", next.join(", ")) };
+        println!("    {id:<20} {}{arrow}", node["node_type"].as_str().unwrap_or("?"));
+    }
+}
+
+/// `at` relative to now, e.g. `5m ago`.
+fn ago(at: DateTime<Utc>) -> String {
+    let secs = (Utc::now() - at).num_seconds().max(0);
+    match secs {
+        0..=59 => format!("{secs}s ago"),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
@@ -0,0 +1,78 @@
+//! Change notifications for live views.
+//!
+//! Repository functions that create, change, or delete a workflow, or move
+//! an execution to a new status, publish a [`Change`] on [`CHANNEL`] once
+//! the change is committed.  Publishing is best effort: a lost
+//! notification only leaves a live view stale until it reloads.
+
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{listener, models::WorkflowRow};
+
+/// Channel on which changes are published; the payload is a JSON [`Change`].
+pub const CHANNEL: &str = "live_changes";
+
+/// Something that changed, as published on [`CHANNEL`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Change {
+    Workflow {
+        action: WorkflowAction,
+        id: Uuid,
+        project_id: Uuid,
+        name: String,
+        /// `None` for deleted workflows.
+        active: Option<bool>,
+        version: Option<i32>,
+    },
+    Execution {
+        id: Uuid,
+        workflow_id: Uuid,
+        project_id: Uuid,
+        status: String,
+    },
+}
+
+/// What happened to a workflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowAction {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl Change {
+    /// `action` happened to the workflow now stored as `row`.
+    pub fn workflow(action: WorkflowAction, row: &WorkflowRow) -> Self {
+        Self::Workflow {
+            action,
+            id: row.id,
+            project_id: row.project_id,
+            name: row.name.clone(),
+            active: Some(row.active),
+            version: Some(row.version),
+        }
+    }
+
+    /// The project the changed workflow or execution belongs to.
+    pub fn project_id(&self) -> Uuid {
+        match self {
+            Self::Workflow { project_id, .. } | Self::Execution { project_id, .. } => *project_id,
+        }
+    }
+}
+
+/// Publish `change` on [`CHANNEL`].
+pub async fn publish(pool: &PgPool, change: &Change) {
+    let payload = match serde_json::to_string(change) {
+        Ok(payload) => payload,
+        Err(e) => return warn!("cannot encode a change notification: {}", e),
+    };
+    if let Err(e) = listener::notify(pool, CHANNEL, &payload).await {
+        warn!("cannot publish a change notification: {}", e);
+    }
+}
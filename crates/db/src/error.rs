@@ -5,11 +5,41 @@ use thiserror::Error;
 #[derive(Debug, Error)]
 pub enum DbError {
     #[error("sqlx error: {0}")]
-    Sqlx(#[from] sqlx::Error),
+    Sqlx(sqlx::Error),
 
     #[error("row not found")]
     NotFound,
 
+    #[error("record is under legal hold")]
+    LegalHold,
+
+    #[error("another active workflow has this webhook path")]
+    WebhookPathTaken,
+
+    #[error("the workflow has changed since: it is at version {0}")]
+    VersionConflict(i32),
+
+    #[error("the project already has a credential with this name")]
+    CredentialNameTaken,
+
+    #[error("unsupported database '{0}': only PostgreSQL (postgres:// or postgresql:// URLs) is supported")]
+    UnsupportedDatabase(String),
+
+    #[error("cannot decrypt a stored payload: {0}")]
+    Sealed(String),
+
+    #[error("cannot decompress a stored payload: {0}")]
+    Compressed(String),
+
     #[error("migration error: {0}")]
     Migration(#[from] sqlx::migrate::MigrateError),
 }
+
+impl From<sqlx::Error> for DbError {
+    fn from(error: sqlx::Error) -> Self {
+        if matches!(error, sqlx::Error::PoolTimedOut) {
+            crate::pool::note_acquire_timeout();
+        }
+        Self::Sqlx(error)
+    }
+}
@@ -1,9 +1,17 @@
 //! `db` crate — pure persistence layer.
 //!
 //! Provides a connection pool, typed row structs, and repository functions
-//! for every table in the rusty-automation schema.  No business logic lives here.
+//! for every table in the rusty-automation schema, plus `LISTEN`/`NOTIFY`
+//! channels ([`listener`]), the change notifications published on one
+//! ([`changes`]), advisory locks for singleton services ([`lock`]), and
+//! compression and encryption of execution payloads at rest ([`payloads`]).
+//! No business logic lives here.
 
+pub mod changes;
 pub mod error;
+pub mod listener;
+pub mod lock;
+pub mod payloads;
 pub mod pool;
 pub mod repository;
 pub mod models;
@@ -0,0 +1,63 @@
+//! `LISTEN`/`NOTIFY` on Postgres channels.
+//!
+//! A [`Listener`] holds one connection for as long as it lives and
+//! reconnects (re-subscribing to its channels) when the connection drops;
+//! notifications sent while it was disconnected are lost.
+
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+
+use crate::DbError;
+
+/// A notification received on a channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub channel: String,
+    pub payload: String,
+}
+
+/// Receives notifications on a set of channels.
+pub struct Listener {
+    inner: PgListener,
+}
+
+impl Listener {
+    /// Listen through a connection taken from `pool` (and kept).
+    pub async fn connect_with(pool: &PgPool) -> Result<Self, DbError> {
+        Ok(Self { inner: PgListener::connect_with(pool).await? })
+    }
+
+    /// Listen through a dedicated connection to `database_url`.
+    pub async fn connect(database_url: &str) -> Result<Self, DbError> {
+        Ok(Self { inner: PgListener::connect(database_url).await? })
+    }
+
+    /// Subscribe to `channel` (case-sensitive).
+    pub async fn listen(&mut self, channel: &str) -> Result<(), DbError> {
+        self.inner.listen(channel).await?;
+        Ok(())
+    }
+
+    /// Unsubscribe from `channel`.
+    pub async fn unlisten(&mut self, channel: &str) -> Result<(), DbError> {
+        self.inner.unlisten(channel).await?;
+        Ok(())
+    }
+
+    /// Wait for the next notification on any subscribed channel.
+    pub async fn recv(&mut self) -> Result<Notification, DbError> {
+        let notification = self.inner.recv().await?;
+        Ok(Notification {
+            channel: notification.channel().to_owned(),
+            payload: notification.payload().to_owned(),
+        })
+    }
+}
+
+/// Send `payload` to everyone listening on `channel`, as `NOTIFY` does.
+pub async fn notify(pool: &PgPool, channel: &str, payload: &str) -> Result<(), DbError> {
+    sqlx::query!("SELECT pg_notify($1, $2)", channel, payload)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,95 @@
+//! Postgres advisory locks for services that must run on one instance only.
+//!
+//! An [`AdvisoryLock`] takes a session-level lock on a dedicated
+//! connection, outside the pool, and holds it for as long as that
+//! connection lives.  When the holder dies its connection closes, Postgres
+//! drops the lock, and the next instance to call [`AdvisoryLock::hold`]
+//! takes over.
+
+use sqlx::{Connection, PgConnection, PgPool};
+
+use crate::DbError;
+
+/// A named advisory lock shared by every instance using the same database.
+pub struct AdvisoryLock {
+    pool: PgPool,
+    name: String,
+    key: i64,
+    conn: Option<PgConnection>,
+}
+
+impl AdvisoryLock {
+    /// The lock called `name`, connecting like `pool` does.  Not acquired
+    /// until [`acquire`](Self::acquire) or [`hold`](Self::hold) succeeds.
+    pub fn new(pool: PgPool, name: &str) -> Self {
+        Self { pool, name: name.to_owned(), key: lock_key(name), conn: None }
+    }
+
+    /// The lock's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether this instance held the lock when last checked.
+    pub fn is_held(&self) -> bool {
+        self.conn.is_some()
+    }
+
+    /// Try to take the lock without waiting; `false` when another session
+    /// holds it.
+    pub async fn acquire(&mut self) -> Result<bool, DbError> {
+        if self.conn.is_some() {
+            return Ok(true);
+        }
+        let mut conn = PgConnection::connect_with(&self.pool.connect_options()).await?;
+        let acquired = sqlx::query_scalar!("SELECT pg_try_advisory_lock($1) AS \"acquired!\"", self.key)
+            .fetch_one(&mut conn)
+            .await?;
+        if acquired {
+            self.conn = Some(conn);
+        } else {
+            conn.close().await?;
+        }
+        Ok(acquired)
+    }
+
+    /// Check the lock is still held; `false` when its connection has been
+    /// lost, and with it the lock.
+    pub async fn renew(&mut self) -> Result<bool, DbError> {
+        let Some(conn) = &mut self.conn else { return Ok(false) };
+        if conn.ping().await.is_err() {
+            self.conn = None;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Renew the lock if held, or try to take it if not; `true` while this
+    /// instance is the holder.
+    pub async fn hold(&mut self) -> Result<bool, DbError> {
+        if self.renew().await? {
+            return Ok(true);
+        }
+        self.acquire().await
+    }
+
+    /// Give the lock up, letting another instance take it.
+    pub async fn release(&mut self) -> Result<(), DbError> {
+        let Some(mut conn) = self.conn.take() else { return Ok(()) };
+        sqlx::query_scalar!("SELECT pg_advisory_unlock($1)", self.key)
+            .fetch_one(&mut conn)
+            .await?;
+        conn.close().await?;
+        Ok(())
+    }
+}
+
+/// The 64-bit advisory lock key for `name` (FNV-1a, so every instance and
+/// release derives the same key).
+pub(crate) fn lock_key(name: &str) -> i64 {
+    let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    hash as i64
+}
+
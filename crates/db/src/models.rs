@@ -20,6 +20,64 @@ pub struct WorkflowRow {
     /// Full JSON workflow definition (nodes, edges, trigger, …)
     pub definition: serde_json::Value,
     pub created_at: DateTime<Utc>,
+    /// Whether triggers start the workflow; manual runs ignore it.
+    pub active: bool,
+    /// Tags for organising workflows (owner, project, …).
+    pub tags: Vec<String>,
+    /// Number of the current definition, counting from 1.
+    pub version: i32,
+    pub project_id: Uuid,
+}
+
+/// Which workflows a listing returns; `None` fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct WorkflowFilter {
+    /// Case-insensitive substring of the name.
+    pub name: Option<String>,
+    /// The trigger's `type`, e.g. `webhook`.
+    pub trigger_type: Option<String>,
+    pub active: Option<bool>,
+    /// A tag the workflow must have.
+    pub tag: Option<String>,
+    pub project_id: Option<Uuid>,
+    /// Full-text query over the name and definition, in web search syntax
+    /// (`billing-api -deprecated`, `"send invoice"`); matches come best
+    /// first.
+    pub search: Option<String>,
+}
+
+/// A workflow without its definition, for listings.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WorkflowSummaryRow {
+    pub id: Uuid,
+    pub name: String,
+    /// The trigger's `type`, e.g. `webhook`.
+    pub trigger_type: Option<String>,
+    pub active: bool,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    /// Status of the latest execution, if it has run.
+    pub last_status: Option<String>,
+    pub last_started_at: Option<DateTime<Utc>>,
+}
+
+/// A stored definition of a workflow.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WorkflowVersionRow {
+    pub workflow_id: Uuid,
+    pub version: i32,
+    pub name: String,
+    pub definition: serde_json::Value,
+    /// Who stored this version, if they said.
+    pub author: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A tag and the number of workflows that have it.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WorkflowTagRow {
+    pub tag: String,
+    pub workflows: i64,
 }
 
 // ---------------------------------------------------------------------------
@@ -32,6 +90,8 @@ pub struct WorkflowRow {
 pub enum ExecutionStatus {
     Pending,
     Running,
+    /// Deferred by a node; a delayed job will resume it.
+    Waiting,
     Succeeded,
     Failed,
 }
@@ -41,6 +101,7 @@ impl std::fmt::Display for ExecutionStatus {
         match self {
             Self::Pending => write!(f, "pending"),
             Self::Running => write!(f, "running"),
+            Self::Waiting => write!(f, "waiting"),
             Self::Succeeded => write!(f, "succeeded"),
             Self::Failed => write!(f, "failed"),
         }
@@ -53,6 +114,7 @@ impl std::str::FromStr for ExecutionStatus {
         match s {
             "pending"   => Ok(Self::Pending),
             "running"   => Ok(Self::Running),
+            "waiting"   => Ok(Self::Waiting),
             "succeeded" => Ok(Self::Succeeded),
             "failed"    => Ok(Self::Failed),
             other       => Err(format!("unknown execution status: {other}")),
@@ -68,6 +130,77 @@ pub struct WorkflowExecutionRow {
     pub status: String,
     pub started_at: DateTime<Utc>,
     pub finished_at: Option<DateTime<Utc>>,
+    /// Business entity the run dealt with (e.g. an order ID), if configured.
+    pub business_key: Option<String>,
+    pub labels: Vec<String>,
+    /// Jobs of higher-priority executions are picked up first.
+    pub priority: i32,
+    /// Queue the execution's jobs are placed on.
+    pub queue: String,
+    /// Execution that started this one (sub-workflow or chained run).
+    pub parent_execution_id: Option<Uuid>,
+    /// Capability tags a worker needs to run the execution's jobs.
+    pub required_tags: Vec<String>,
+    /// Attempts each of the execution's jobs gets before it is
+    /// dead-lettered.
+    pub max_attempts: i32,
+    /// Failed execution this one retries.
+    pub retry_of: Option<Uuid>,
+    /// Version of the workflow definition the execution ran against.
+    pub workflow_version: Option<i32>,
+}
+
+/// Metadata recorded on an execution when it is created.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionMeta {
+    pub business_key: Option<String>,
+    pub labels: Vec<String>,
+    pub priority: i32,
+    /// `None` places the execution on the `default` queue.
+    pub queue: Option<String>,
+    pub parent_execution_id: Option<Uuid>,
+    pub required_tags: Vec<String>,
+    /// `None` gives jobs [`DEFAULT_MAX_ATTEMPTS`].
+    pub max_attempts: Option<i32>,
+    pub retry_of: Option<Uuid>,
+    /// Workflow parameters given for this run, overriding their defaults.
+    pub params: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A cap on how many executions of a workflow may start in a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartLimit {
+    /// Most executions that may have started at or after `since`.
+    pub max: i64,
+    pub since: DateTime<Utc>,
+}
+
+/// Attempts a job gets unless its execution says otherwise.
+pub const DEFAULT_MAX_ATTEMPTS: i32 = 3;
+
+/// An execution joined with the name of its workflow.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ExecutionSummaryRow {
+    pub id: Uuid,
+    pub workflow_id: Uuid,
+    pub workflow_name: String,
+    pub status: String,
+    pub business_key: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Which executions [`list_executions`](crate::repository::executions::list_executions)
+/// returns; `None` fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionFilter {
+    pub workflow_id: Option<Uuid>,
+    pub status: Option<String>,
+    pub started_after: Option<DateTime<Utc>>,
+    pub started_before: Option<DateTime<Utc>>,
+    /// The workflow trigger's `type`, e.g. `webhook` or `cron`.
+    pub trigger_type: Option<String>,
+    pub project_id: Option<Uuid>,
 }
 
 // ---------------------------------------------------------------------------
@@ -85,20 +218,288 @@ pub struct NodeExecutionRow {
     pub status: String,
     pub started_at: DateTime<Utc>,
     pub finished_at: Option<DateTime<Utc>>,
+    /// SHA-256 of the canonical input, recorded for determinism reports.
+    pub input_hash: Option<String>,
+    pub output_hash: Option<String>,
+    /// Why the node failed, for `failed` nodes.
+    pub error: Option<String>,
+    /// Branch the node picked, for branching nodes.
+    pub branch: Option<String>,
+    /// Whether the node stopped the flow after it.
+    pub halted: bool,
+}
+
+/// Input/output hashes recorded with a node execution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeHashes {
+    pub input: String,
+    pub output: Option<String>,
+}
+
+/// Where the flow went after a node, recorded with its execution.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeFlow {
+    pub branch: Option<String>,
+    pub halted: bool,
+}
+
+/// A node execution to store with
+/// [`record_step`](crate::repository::executions::record_step).
+#[derive(Debug, Clone, Copy)]
+pub struct NewNodeExecution<'a> {
+    pub node_id: &'a str,
+    pub input: &'a serde_json::Value,
+    pub output: Option<&'a serde_json::Value>,
+    pub status: &'a str,
+    pub started_at: DateTime<Utc>,
+    /// Input/output hashes, when determinism reporting is on.
+    pub hashes: Option<&'a NodeHashes>,
+    pub error: Option<&'a str>,
+    pub flow: &'a NodeFlow,
+    /// Every attempt at the node, in order.
+    pub attempts: &'a [NodeAttempt],
+}
+
+/// One attempt at a node, recorded with its execution; a node that was
+/// retried has one per try.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeAttempt {
+    /// 1 for the first attempt.
+    pub attempt: i32,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Why it failed; `None` when it succeeded.
+    pub error: Option<String>,
+}
+
+/// When an execution was queued, picked up, and finished, for its timeline.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ExecutionTimingRow {
+    pub id: Uuid,
+    pub status: String,
+    /// When the execution was created, i.e. its first job was queued.
+    pub queued_at: DateTime<Utc>,
+    /// When a worker first picked it up; `None` while still queued.
+    pub running_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// The timings of one node execution, for its execution's timeline.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NodeTimingRow {
+    pub node_id: String,
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// [`NodeAttempt`]s as JSON.
+    pub attempts: serde_json::Value,
+}
+
+// ---------------------------------------------------------------------------
+// binary_data
+// ---------------------------------------------------------------------------
+
+/// A blob of binary data in the blob store; `execution_id` is `None` once
+/// its execution is deleted and the blob awaits cleanup.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct BinaryDataRow {
+    pub id: Uuid,
+    pub execution_id: Option<Uuid>,
+    pub workflow_id: Uuid,
+    /// Where the blob lives in the blob store.
+    pub storage_key: String,
+    pub mime_type: String,
+    pub file_name: Option<String>,
+    pub size: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+// ---------------------------------------------------------------------------
+// execution_logs
+// ---------------------------------------------------------------------------
+
+/// A line a node logged during an execution.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ExecutionLogRow {
+    pub id: i64,
+    pub execution_id: Uuid,
+    pub node_id: String,
+    /// `debug`, `info`, `warn`, or `error`.
+    pub level: String,
+    pub message: String,
+    /// Structured context the node attached to the line.
+    pub fields: serde_json::Value,
+    pub logged_at: DateTime<Utc>,
+}
+
+/// A line to store with [`insert_logs`](crate::repository::logs::insert_logs).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewLogLine {
+    pub level: String,
+    pub message: String,
+    pub fields: serde_json::Value,
+    pub logged_at: DateTime<Utc>,
+}
+
+/// Which lines [`list_logs`](crate::repository::logs::list_logs) returns;
+/// `None` fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    pub node_id: Option<String>,
+    /// Levels to include.
+    pub levels: Option<Vec<String>>,
 }
 
 // ---------------------------------------------------------------------------
 // secrets
 // ---------------------------------------------------------------------------
 
-/// A persisted secret row.
+/// A persisted secret row: a workflow's, or with no workflow its
+/// project's.
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct SecretRow {
     pub id: Uuid,
-    pub workflow_id: Uuid,
+    pub project_id: Uuid,
+    pub workflow_id: Option<Uuid>,
     pub key: String,
-    /// AES-256 encrypted value (base64-encoded ciphertext).
+    /// Environment the value is for; empty for the key's default, used
+    /// where it has none of its own.
+    pub environment: String,
+    /// AES-256-GCM encrypted value, `v1:<key id>:<base64>` (see
+    /// `engine::secrets`).
     pub encrypted_value: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SecretRow {
+    /// The workflow the secret belongs to, or else its project: what its
+    /// value is sealed to.
+    pub fn owner_id(&self) -> Uuid {
+        self.workflow_id.unwrap_or(self.project_id)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// credentials
+// ---------------------------------------------------------------------------
+
+/// A persisted credential, shared by the workflows of its project.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CredentialRow {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub name: String,
+    /// `http_basic`, `oauth2`, `api_token`, or `ssh_key`.
+    pub credential_type: String,
+    /// The credential's fields as encrypted JSON, in the form of
+    /// [`SecretRow::encrypted_value`].
+    pub encrypted_data: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ---------------------------------------------------------------------------
+// workflow_state
+// ---------------------------------------------------------------------------
+
+/// A persisted per-workflow state entry.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WorkflowStateRow {
+    pub workflow_id: Uuid,
+    pub key: String,
+    /// `NULL` while the entry is reserved but not yet written.
+    pub value: Option<serde_json::Value>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ---------------------------------------------------------------------------
+// legal_hold_events
+// ---------------------------------------------------------------------------
+
+/// The kind of record a legal hold applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HoldTarget {
+    Workflow,
+    Execution,
+}
+
+impl std::fmt::Display for HoldTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Workflow  => write!(f, "workflow"),
+            Self::Execution => write!(f, "execution"),
+        }
+    }
+}
+
+/// One audit entry: a hold placed on (`held = true`) or lifted from a record.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LegalHoldEventRow {
+    pub id: Uuid,
+    pub target_type: String,
+    pub target_id: Uuid,
+    pub held: bool,
+    pub reason: Option<String>,
+    pub actor: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// ---------------------------------------------------------------------------
+// privacy_erasures
+// ---------------------------------------------------------------------------
+
+/// An execution matched by an erasure request.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ErasureCandidateRow {
+    pub id: Uuid,
+    pub workflow_id: Uuid,
+    /// The execution or its workflow is under legal hold.
+    pub legal_hold: bool,
+}
+
+/// A logged erasure request.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PrivacyErasureRow {
+    pub id: Uuid,
+    pub actor: String,
+    pub criteria_type: String,
+    pub report: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+// ---------------------------------------------------------------------------
+// audit_log
+// ---------------------------------------------------------------------------
+
+/// One audited privileged action.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AuditLogRow {
+    pub id: Uuid,
+    pub actor: String,
+    /// Dotted action name, e.g. `support.view_execution`.
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Option<Uuid>,
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+// ---------------------------------------------------------------------------
+// feature_flags
+// ---------------------------------------------------------------------------
+
+/// A stored feature flag override.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FeatureFlagRow {
+    pub flag: String,
+    /// `global`, `organization`, or `workflow`.
+    pub scope: String,
+    /// Organization or workflow ID; empty for `global`.
+    pub scope_id: String,
+    pub enabled: bool,
+    pub updated_at: DateTime<Utc>,
 }
 
 // ---------------------------------------------------------------------------
@@ -139,4 +540,190 @@ pub struct JobRow {
     pub payload: serde_json::Value,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Workers do not pick the job up before this time.
+    pub run_at: DateTime<Utc>,
+    /// Set on jobs created by a debounced trigger.
+    pub debounce_key: Option<String>,
+    /// Copied from the job's execution.
+    pub priority: i32,
+    pub queue: String,
+    /// While `processing`: when the worker's lease on the job runs out
+    /// unless it sends a heartbeat.
+    pub locked_until: Option<DateTime<Utc>>,
+    /// Copied from the job's execution: only workers with every one of
+    /// these tags claim the job.
+    pub required_tags: Vec<String>,
+    /// Set on jobs that continue an execution from saved progress rather
+    /// than from its first node; the payload then holds the trigger input.
+    pub checkpoint: Option<serde_json::Value>,
+}
+
+/// Backlog and throughput of one queue.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct QueueStatsRow {
+    pub queue: String,
+    /// Pending jobs, due or not.
+    pub pending: i64,
+    /// Pending jobs whose `run_at` has passed: the backlog workers have
+    /// not caught up with.
+    pub due: i64,
+    pub processing: i64,
+    pub dead_lettered: i64,
+    /// How long the longest-waiting due job has been due, in seconds.
+    pub oldest_due_secs: Option<f64>,
+    /// Jobs completed in the last minute and the last hour.
+    pub completed_last_minute: i64,
+    pub completed_last_hour: i64,
+}
+
+/// How a workflow's executions went over a window.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WorkflowStatsRow {
+    pub total: i64,
+    pub succeeded: i64,
+    pub failed: i64,
+    /// Durations of the finished executions, in milliseconds; `None` when
+    /// none finished.
+    pub avg_duration_ms: Option<f64>,
+    pub p50_duration_ms: Option<f64>,
+    pub p90_duration_ms: Option<f64>,
+    pub p99_duration_ms: Option<f64>,
+}
+
+/// A workflow's executions started on one (UTC) day.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DailyExecutionsRow {
+    pub day: chrono::NaiveDate,
+    pub total: i64,
+    pub succeeded: i64,
+    pub failed: i64,
+}
+
+/// How long a node's successful runs took.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NodeDurationRow {
+    /// 95th percentile in milliseconds; `None` without runs.
+    pub p95_ms: Option<f64>,
+    pub samples: i64,
+}
+
+/// How often a node failed.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NodeFailuresRow {
+    pub node_id: String,
+    pub failures: i64,
+}
+
+/// How a node's runs went over a window, summed from `node_daily_stats`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NodeStatsRow {
+    pub node_id: String,
+    pub runs: i64,
+    pub failures: i64,
+    /// Attempts beyond the first.
+    pub retries: i64,
+    /// Time spent in the node over all runs, in milliseconds.
+    pub total_ms: i64,
+    pub avg_ms: f64,
+    pub max_ms: i64,
+}
+
+/// A project's workflows, and how its executions went since a point in
+/// time, for the dashboard.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DashboardStatsRow {
+    pub workflows: i64,
+    pub active_workflows: i64,
+    pub executions: i64,
+    pub succeeded: i64,
+    pub failed: i64,
+    /// Executions that have not finished yet.
+    pub in_flight: i64,
+}
+
+/// A workflow and how many executions it started over a window.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct BusyWorkflowRow {
+    pub workflow_id: Uuid,
+    pub name: String,
+    pub executions: i64,
+    pub failed: i64,
+}
+
+// ---------------------------------------------------------------------------
+// cron_schedules
+// ---------------------------------------------------------------------------
+
+/// The persisted schedule of a workflow with a cron trigger.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CronScheduleRow {
+    pub workflow_id: Uuid,
+    pub expression: String,
+    /// IANA timezone the expression is evaluated in.
+    pub timezone: String,
+    /// When the next run is due.
+    pub next_run_at: DateTime<Utc>,
+    /// Due time of the most recent run enqueued.
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ---------------------------------------------------------------------------
+// webhook_captures
+// ---------------------------------------------------------------------------
+
+/// A workflow's captured webhook sample, and whether the next request will
+/// replace it.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WebhookCaptureRow {
+    pub workflow_id: Uuid,
+    /// While set, the next request before this time is captured instead of
+    /// executed.
+    pub armed_until: Option<DateTime<Utc>>,
+    /// The execution input of the captured request.
+    pub payload: Option<serde_json::Value>,
+    pub captured_at: Option<DateTime<Utc>>,
+}
+
+// ---------------------------------------------------------------------------
+// users
+// ---------------------------------------------------------------------------
+
+/// A user of the API and their role.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UserRow {
+    /// What they authenticate as: an API key's name or a token's user claim.
+    pub subject: String,
+    /// `viewer`, `editor`, or `admin`.
+    pub role: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ---------------------------------------------------------------------------
+// projects
+// ---------------------------------------------------------------------------
+
+/// The project rows from before projects existed belong to.
+pub const DEFAULT_PROJECT_ID: Uuid = Uuid::nil();
+
+/// A project (team, organization) owning workflows and their executions,
+/// jobs, and secrets.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ProjectRow {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a workflow's or project's executions used of its quota: executions
+/// started in the last hour and since midnight (UTC), and node runtime
+/// today.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct QuotaUsageRow {
+    pub executions_last_hour: i64,
+    pub executions_today: i64,
+    pub node_runtime_ms_today: i64,
 }
@@ -0,0 +1,201 @@
+//! Execution payloads compressed and encrypted at rest.
+//!
+//! Node inputs and outputs and job payloads routinely carry personal data,
+//! and whole API responses.  Payloads larger than the threshold set with
+//! [`compress_above`] are zstd-compressed before they are written, storing
+//! `{ "$zstd": "…" }` in their place.  With a [`PayloadCipher`] installed
+//! to seal ([`install`]), the repository then encrypts them, storing
+//! `{ "$sealed": "…" }`, sealed for the execution the payload belongs to.
+//! Every read undoes both, so callers only ever see plain values.
+//! Payloads stored as they are still read as they are, compressed ones
+//! always read, and sealed ones read for as long as a cipher that opens
+//! them is installed, even one no longer sealing.
+//!
+//! A payload that is itself shaped like one of these objects, such as a
+//! webhook body of `{ "$sealed": "x" }`, is stored wrapped in
+//! `{ "$plain": … }` so it reads back as it was written.
+//!
+//! SQL cannot look inside a compressed or sealed payload, so the queries
+//! that did — merging debounced payloads, finding a privacy erasure's
+//! subject — work on the decoded values instead.
+//!
+//! The settings belong to the process, like the pool's timeout counter:
+//! repository functions take nothing but a pool.
+
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::DbError;
+
+/// Key of the object standing in for a sealed payload.
+const SEALED: &str = "$sealed";
+
+/// Key of the object standing in for a compressed payload.
+const COMPRESSED: &str = "$zstd";
+
+/// Key of the object a payload is wrapped in when it looks like one of
+/// [`MARKERS`] itself, so it reads back as it was written.
+const ESCAPED: &str = "$plain";
+
+/// Keys of the single-key objects [`decode`] does not take literally.
+const MARKERS: [&str; 3] = [SEALED, COMPRESSED, ESCAPED];
+
+/// zstd's default level: most of the gain on JSON, at little cost.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Size in bytes, as JSON, above which payloads are compressed; `usize::MAX`
+/// while compression is off.
+static COMPRESS_ABOVE: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Seals payloads, as JSON text, and opens them again.
+///
+/// Payloads are sealed for the execution they belong to, so one copied
+/// into another execution's rows does not open.
+pub trait PayloadCipher: Send + Sync {
+    fn seal(&self, execution_id: Uuid, json: &str) -> String;
+
+    /// What [`PayloadCipher::seal`] sealed for `execution_id`.
+    ///
+    /// # Errors
+    /// Why it does not open: another key or execution, or a corrupted
+    /// value.
+    fn open(&self, execution_id: Uuid, sealed: &str) -> Result<String, String>;
+}
+
+struct Installed {
+    cipher: Arc<dyn PayloadCipher>,
+    seal: bool,
+}
+
+static CIPHER: RwLock<Option<Installed>> = RwLock::new(None);
+
+/// Open sealed payloads with `cipher` from now on, and with `seal` also
+/// seal those written.
+pub fn install(cipher: Arc<dyn PayloadCipher>, seal: bool) {
+    *CIPHER.write().expect("payload cipher lock") = Some(Installed { cipher, seal });
+}
+
+/// Whether payloads are sealed as they are written.
+pub fn sealing() -> bool {
+    CIPHER.read().expect("payload cipher lock").as_ref().is_some_and(|installed| installed.seal)
+}
+
+/// Compress payloads written from now on whose JSON is larger than
+/// `threshold` bytes; `None` writes them as they are.
+pub fn compress_above(threshold: Option<usize>) {
+    COMPRESS_ABOVE.store(threshold.unwrap_or(usize::MAX), Ordering::Relaxed);
+}
+
+/// `value` as execution `execution_id` writes it: escaped when it looks
+/// like an encoded payload, compressed when it is large, then sealed when
+/// payloads are; `None` when it is written as it is.
+///
+/// A payload neither compressed nor sealed is never turned into text here;
+/// the statement encodes it straight into its parameter buffer.
+pub(crate) fn encode(value: &Value, execution_id: Uuid) -> Option<Value> {
+    let escaped = looks_encoded(value).then(|| marker(ESCAPED, value.clone()));
+    let plain = escaped.as_ref().unwrap_or(value);
+    let compressed = compress(plain);
+    let cipher = {
+        let guard = CIPHER.read().expect("payload cipher lock");
+        guard.as_ref().filter(|installed| installed.seal).map(|installed| Arc::clone(&installed.cipher))
+    };
+    let sealed = cipher.map(|cipher| {
+        let json = serde_json::to_string(compressed.as_ref().unwrap_or(plain)).expect("JSON values serialize");
+        marker(SEALED, Value::String(cipher.seal(execution_id, &json)))
+    });
+    sealed.or(compressed).or(escaped)
+}
+
+/// `value`, stored by execution `execution_id`, as it was before
+/// [`encode`].
+///
+/// # Errors
+/// [`DbError::Sealed`] for a sealed value without a cipher that opens it,
+/// and [`DbError::Compressed`] for a compressed one that does not
+/// decompress.
+pub(crate) fn decode(value: Value, execution_id: Uuid) -> Result<Value, DbError> {
+    let Value::Object(mut fields) = value else { return Ok(value) };
+    let marked = fields.keys().next().filter(|key| fields.len() == 1 && MARKERS.contains(&key.as_str())).cloned();
+    let Some(key) = marked else { return Ok(Value::Object(fields)) };
+    let inner = fields.remove(&key).expect("the marker key");
+    match (key.as_str(), inner) {
+        (ESCAPED, inner) => Ok(inner),
+        (SEALED, Value::String(sealed)) => decode(open(&sealed, execution_id)?, execution_id),
+        (COMPRESSED, Value::String(compressed)) => decode(decompress(&compressed)?, execution_id),
+        // Stored before payloads were escaped.
+        (_, inner) => Ok(marker(&key, inner)),
+    }
+}
+
+/// Whether `value` is a single-key object [`decode`] would not take
+/// literally.
+fn looks_encoded(value: &Value) -> bool {
+    match value {
+        Value::Object(fields) if fields.len() == 1 => fields.keys().all(|key| MARKERS.contains(&key.as_str())),
+        _ => false,
+    }
+}
+
+/// `value` compressed, when its JSON is over the threshold and compressing
+/// it saves space.  The JSON is measured, then serialized straight into
+/// the compressor, so a large payload is never held as text.
+fn compress(value: &Value) -> Option<Value> {
+    let threshold = COMPRESS_ABOVE.load(Ordering::Relaxed);
+    if threshold == usize::MAX {
+        return None;
+    }
+    let len = json_len(value);
+    if len <= threshold {
+        return None;
+    }
+    let mut encoder = zstd::Encoder::new(Vec::new(), COMPRESSION_LEVEL).expect("compressing in memory");
+    serde_json::to_writer(&mut encoder, value).expect("JSON values serialize");
+    let bytes = encoder.finish().expect("compressing in memory");
+    let encoded = STANDARD.encode(bytes);
+    (encoded.len() < len).then(|| marker(COMPRESSED, Value::String(encoded)))
+}
+
+/// Length in bytes of `value` as JSON, counted as it is serialized.
+fn json_len(value: &Value) -> usize {
+    struct Counter(usize);
+
+    impl io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    serde_json::to_writer(&mut counter, value).expect("JSON values serialize");
+    counter.0
+}
+
+fn decompress(compressed: &str) -> Result<Value, DbError> {
+    let bytes = STANDARD.decode(compressed).map_err(|e| DbError::Compressed(e.to_string()))?;
+    let json = zstd::decode_all(bytes.as_slice()).map_err(|e| DbError::Compressed(e.to_string()))?;
+    serde_json::from_slice(&json).map_err(|e| DbError::Compressed(e.to_string()))
+}
+
+fn open(sealed: &str, execution_id: Uuid) -> Result<Value, DbError> {
+    let guard = CIPHER.read().expect("payload cipher lock");
+    let installed = guard.as_ref().ok_or_else(|| DbError::Sealed("no secrets key is set".into()))?;
+    let json = installed.cipher.open(execution_id, sealed).map_err(DbError::Sealed)?;
+    serde_json::from_str(&json).map_err(|e| DbError::Sealed(e.to_string()))
+}
+
+fn marker(key: &str, value: Value) -> Value {
+    let mut fields = Map::new();
+    fields.insert(key.to_owned(), value);
+    Value::Object(fields)
+}
@@ -1,30 +1,286 @@
 //! Postgres connection pool.
+//!
+//! Pools test connections before handing them out, so one lost with the
+//! server (a restart, a failover) is replaced rather than failing the next
+//! query, and creating a pool retries with back-off while the server is
+//! unreachable.  [`check`] reports a pool's [`PoolStats`] and how long a
+//! connection takes to get, for readiness checks and metrics;
+//! [`migration_status`] which migrations the database is missing.
 
-use sqlx::postgres::PgPoolOptions;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use sqlx::migrate::Migrator;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 use sqlx::PgPool;
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::DbError;
 
 /// Type alias for the shared Postgres pool used across the whole application.
 pub type DbPool = PgPool;
 
+/// URL schemes of the databases the repository can talk to.  Its queries
+/// are checked against Postgres at build time and use Postgres features
+/// (`SKIP LOCKED` job claims, `LISTEN`/`NOTIFY`, JSONB, array and
+/// percentile aggregates), so other databases are refused up front rather
+/// than failing on their first query.  A MySQL/MariaDB backend is still
+/// open work; see the roadmap in the README.
+const SUPPORTED_SCHEMES: [&str; 2] = ["postgres", "postgresql"];
+
+/// How long a query waits for a free connection before failing, unless
+/// configured.
+pub const DEFAULT_ACQUIRE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Attempts at connecting while creating a pool, unless configured.
+pub const DEFAULT_CONNECT_ATTEMPTS: u32 = 5;
+
+/// Wait before the second connection attempt; it doubles for every further
+/// one, up to [`MAX_RETRY_DELAY`].
+const FIRST_RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Queries that failed waiting for a free connection, across every pool of
+/// the process.
+static ACQUIRE_TIMEOUTS: AtomicU64 = AtomicU64::new(0);
+
+/// How a pool is sized and how patient it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolSettings {
+    pub max_connections: u32,
+    pub acquire_timeout: Duration,
+    pub connect_attempts: u32,
+}
+
+impl PoolSettings {
+    /// A pool of at most `max_connections`, otherwise the defaults.
+    pub fn new(max_connections: u32) -> Self {
+        Self {
+            max_connections,
+            acquire_timeout: DEFAULT_ACQUIRE_TIMEOUT,
+            connect_attempts: DEFAULT_CONNECT_ATTEMPTS,
+        }
+    }
+
+    /// Fail queries that wait longer than `timeout` for a connection.
+    pub fn with_acquire_timeout(mut self, timeout: Duration) -> Self {
+        self.acquire_timeout = timeout;
+        self
+    }
+
+    /// Try connecting `attempts` times before giving up on an unreachable
+    /// server.
+    pub fn with_connect_attempts(mut self, attempts: u32) -> Self {
+        self.connect_attempts = attempts.max(1);
+        self
+    }
+}
+
+/// A pool's connections at one moment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PoolStats {
+    /// Connections open, idle or in use.
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    pub max_connections: u32,
+}
+
+/// Result of [`check`]ing a pool.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PoolHealth {
+    #[serde(flatten)]
+    pub stats: PoolStats,
+    /// Every connection was in use when checked.
+    pub exhausted: bool,
+    /// How long getting a connection took, when it did not fail.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acquire_secs: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl PoolHealth {
+    /// Whether a connection could be had.
+    pub fn is_healthy(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
 /// Create a new connection pool from the given `database_url`.
 ///
 /// `max_connections` controls the pool ceiling.
 pub async fn create_pool(database_url: &str, max_connections: u32) -> Result<DbPool, DbError> {
-    info!("Connecting to database (max_connections={})", max_connections);
-    let pool = PgPoolOptions::new()
-        .max_connections(max_connections)
-        .connect(database_url)
-        .await?;
-    Ok(pool)
+    create_pool_with(database_url, &PoolSettings::new(max_connections)).await
+}
+
+/// Create a new connection pool from the given `database_url`, as
+/// `settings` say.
+pub async fn create_pool_with(database_url: &str, settings: &PoolSettings) -> Result<DbPool, DbError> {
+    let options = connect_options(database_url)?;
+    info!("Connecting to database (max_connections={})", settings.max_connections);
+    open(options, settings).await
+}
+
+/// Create a connection pool for a read replica at `database_url`, like
+/// [`create_pool`].  Its sessions are read-only, so a write sent to it by
+/// mistake fails even when the server would accept it.
+pub async fn create_read_pool(database_url: &str, max_connections: u32) -> Result<DbPool, DbError> {
+    create_read_pool_with(database_url, &PoolSettings::new(max_connections)).await
+}
+
+/// Create a connection pool for a read replica, like [`create_read_pool`],
+/// as `settings` say.
+pub async fn create_read_pool_with(database_url: &str, settings: &PoolSettings) -> Result<DbPool, DbError> {
+    let options = connect_options(database_url)?.options([("default_transaction_read_only", "on")]);
+    info!("Connecting to read replica (max_connections={})", settings.max_connections);
+    open(options, settings).await
+}
+
+async fn open(options: PgConnectOptions, settings: &PoolSettings) -> Result<DbPool, DbError> {
+    let mut delay = FIRST_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        let result = PgPoolOptions::new()
+            .max_connections(settings.max_connections)
+            .acquire_timeout(settings.acquire_timeout)
+            .test_before_acquire(true)
+            .connect_with(options.clone())
+            .await;
+        match result {
+            Ok(pool) => return Ok(pool),
+            Err(e) if attempt < settings.connect_attempts && is_transient(&e) => {
+                warn!(
+                    "Cannot connect to database (attempt {} of {}): {}; retrying in {:?}",
+                    attempt, settings.connect_attempts, e, delay
+                );
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+                attempt += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Whether `error` comes from losing or not reaching the server, so the
+/// same request may succeed later.
+pub fn is_transient(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::WorkerCrashed => true,
+        // Class 08 is connection exceptions; 57P01–57P03 are the server
+        // shutting down or starting up.
+        sqlx::Error::Database(e) => e
+            .code()
+            .is_some_and(|code| code.starts_with("08") || matches!(&*code, "57P01" | "57P02" | "57P03")),
+        _ => false,
+    }
+}
+
+/// The connections of `pool` right now.
+pub fn stats(pool: &DbPool) -> PoolStats {
+    let size = pool.size();
+    let idle = u32::try_from(pool.num_idle()).unwrap_or(u32::MAX).min(size);
+    PoolStats { size, idle, in_use: size - idle, max_connections: pool.options().get_max_connections() }
+}
+
+/// Get a connection from `pool`, waiting at most `timeout`, and report how
+/// that went along with the pool's [`stats`].
+pub async fn check(pool: &DbPool, timeout: Duration) -> PoolHealth {
+    let stats = stats(pool);
+    let started = Instant::now();
+    let (acquire_secs, error) = match tokio::time::timeout(timeout, pool.acquire()).await {
+        Ok(Ok(_)) => (Some(started.elapsed().as_secs_f64()), None),
+        Ok(Err(e)) => (None, Some(DbError::from(e).to_string())),
+        Err(_) => {
+            note_acquire_timeout();
+            (None, Some(format!("no connection within {timeout:?}")))
+        }
+    };
+    PoolHealth { exhausted: stats.in_use >= stats.max_connections, stats, acquire_secs, error }
+}
+
+/// Queries that failed waiting for a free connection since the process
+/// started.
+pub fn acquire_timeouts() -> u64 {
+    ACQUIRE_TIMEOUTS.load(Ordering::Relaxed)
+}
+
+pub(crate) fn note_acquire_timeout() {
+    ACQUIRE_TIMEOUTS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Whether `database_url` names a database this repository can use, without
+/// connecting to it.
+pub fn check_url(database_url: &str) -> Result<(), DbError> {
+    connect_options(database_url).map(|_| ())
 }
 
-/// Run embedded SQLx migrations located in `./migrations` (relative to the
-/// workspace root at build time).
+fn connect_options(database_url: &str) -> Result<PgConnectOptions, DbError> {
+    // Only the scheme is reported; the rest may hold credentials.
+    let scheme = database_url.split_once("://").map_or("", |(scheme, _)| scheme);
+    if !SUPPORTED_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()) {
+        return Err(DbError::UnsupportedDatabase(scheme.to_owned()));
+    }
+    Ok(database_url.parse()?)
+}
+
+/// The migrations in `./migrations` (relative to the workspace root at
+/// build time), embedded.
+static MIGRATOR: Migrator = sqlx::migrate!("../../migrations");
+
+/// Run the embedded SQLx migrations.
 pub async fn run_migrations(pool: &DbPool) -> Result<(), DbError> {
     info!("Running database migrations");
-    sqlx::migrate!("../../migrations").run(pool).await?;
+    MIGRATOR.run(pool).await?;
     Ok(())
 }
+
+/// How a database's applied migrations compare with the embedded ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MigrationStatus {
+    /// Embedded migrations applied as built.
+    pub applied: usize,
+    /// Embedded migrations not yet applied, as `version description`.
+    pub pending: Vec<String>,
+    /// Migrations that started but did not finish.
+    pub failed: Vec<String>,
+    /// Applied migrations whose embedded file has since changed.
+    pub modified: Vec<String>,
+    /// Applied migrations this build does not know, e.g. from a newer one.
+    pub unknown: Vec<i64>,
+}
+
+/// Compare the migrations applied to `pool`'s database with the embedded
+/// ones, without running any.
+pub async fn migration_status(pool: &DbPool) -> Result<MigrationStatus, DbError> {
+    let tracked = sqlx::query_scalar!(r#"SELECT to_regclass('_sqlx_migrations') IS NOT NULL AS "tracked!""#)
+        .fetch_one(pool)
+        .await?;
+    // The table is SQLx's own, so it is not known when queries are checked.
+    let applied: Vec<(i64, bool, Vec<u8>)> = if tracked {
+        sqlx::query_as("SELECT version, success, checksum FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(pool)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    let mut status = MigrationStatus::default();
+    for migration in MIGRATOR.iter().filter(|m| !m.migration_type.is_down_migration()) {
+        let name = format!("{} {}", migration.version, migration.description);
+        match applied.iter().find(|(version, ..)| *version == migration.version) {
+            None => status.pending.push(name),
+            Some((_, false, _)) => status.failed.push(name),
+            Some((_, true, checksum)) if *checksum != *migration.checksum => status.modified.push(name),
+            Some(_) => status.applied += 1,
+        }
+    }
+    status.unknown = applied
+        .iter()
+        .map(|(version, ..)| *version)
+        .filter(|version| !MIGRATOR.iter().any(|m| m.version == *version))
+        .collect();
+    Ok(status)
+}
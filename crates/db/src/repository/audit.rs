@@ -0,0 +1,55 @@
+//! Audit log repository functions.
+
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{DbError, models::AuditLogRow};
+
+/// Append an entry to the audit log.
+pub async fn record(
+    pool: &PgPool,
+    actor: &str,
+    action: &str,
+    target_type: &str,
+    target_id: Option<Uuid>,
+    details: serde_json::Value,
+) -> Result<AuditLogRow, DbError> {
+    let row = sqlx::query_as!(
+        AuditLogRow,
+        r#"
+        INSERT INTO audit_log (id, actor, action, target_type, target_id, details, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, actor, action, target_type, target_id, details, created_at
+        "#,
+        Uuid::new_v4(),
+        actor,
+        action,
+        target_type,
+        target_id,
+        details,
+        Utc::now(),
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Most recent audit entries, newest first.
+pub async fn list_recent(pool: &PgPool, limit: i64) -> Result<Vec<AuditLogRow>, DbError> {
+    let rows = sqlx::query_as!(
+        AuditLogRow,
+        r#"
+        SELECT id, actor, action, target_type, target_id, details, created_at
+        FROM audit_log
+        ORDER BY created_at DESC
+        LIMIT $1
+        "#,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
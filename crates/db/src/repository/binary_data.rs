@@ -0,0 +1,131 @@
+//! Binary data repository functions.
+//!
+//! Only the metadata of blobs is kept here; the bytes live in the blob
+//! store under `storage_key`.
+
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{DbError, models::BinaryDataRow};
+
+/// Record a blob stored for execution `execution_id`.
+pub async fn insert_binary_data(
+    pool: &PgPool,
+    id: Uuid,
+    execution_id: Uuid,
+    storage_key: &str,
+    mime_type: &str,
+    file_name: Option<&str>,
+    size: i64,
+) -> Result<BinaryDataRow, DbError> {
+    let row = sqlx::query_as!(
+        BinaryDataRow,
+        r#"
+        INSERT INTO binary_data (id, execution_id, workflow_id, storage_key, mime_type, file_name, size, created_at)
+        SELECT $1, e.id, e.workflow_id, $3, $4, $5, $6, $7
+        FROM workflow_executions e
+        WHERE e.id = $2
+        RETURNING id, execution_id, workflow_id, storage_key, mime_type, file_name, size, created_at
+        "#,
+        id,
+        execution_id,
+        storage_key,
+        mime_type,
+        file_name,
+        size,
+        Utc::now(),
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(DbError::NotFound)?;
+
+    Ok(row)
+}
+
+/// Fetch blob `id`, unless its execution is gone.
+pub async fn get_binary_data(pool: &PgPool, id: Uuid) -> Result<BinaryDataRow, DbError> {
+    let row = sqlx::query_as!(
+        BinaryDataRow,
+        r#"
+        SELECT id, execution_id, workflow_id, storage_key, mime_type, file_name, size, created_at
+        FROM binary_data
+        WHERE id = $1 AND execution_id IS NOT NULL
+        "#,
+        id,
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(DbError::NotFound)?;
+
+    Ok(row)
+}
+
+/// Fetch blob `id` for execution `execution_id`: only blobs stored by an
+/// execution of the same project are found.
+pub async fn get_project_binary_data(pool: &PgPool, id: Uuid, execution_id: Uuid) -> Result<BinaryDataRow, DbError> {
+    let row = sqlx::query_as!(
+        BinaryDataRow,
+        r#"
+        SELECT b.id, b.execution_id, b.workflow_id, b.storage_key, b.mime_type, b.file_name, b.size, b.created_at
+        FROM binary_data b
+        JOIN workflows owner ON owner.id = b.workflow_id
+        JOIN workflow_executions e ON e.id = $2
+        JOIN workflows caller ON caller.id = e.workflow_id
+        WHERE b.id = $1 AND b.execution_id IS NOT NULL AND owner.project_id = caller.project_id
+        "#,
+        id,
+        execution_id,
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(DbError::NotFound)?;
+
+    Ok(row)
+}
+
+/// Every blob of execution `execution_id`, oldest first.
+pub async fn list_binary_data(pool: &PgPool, execution_id: Uuid) -> Result<Vec<BinaryDataRow>, DbError> {
+    let rows = sqlx::query_as!(
+        BinaryDataRow,
+        r#"
+        SELECT id, execution_id, workflow_id, storage_key, mime_type, file_name, size, created_at
+        FROM binary_data
+        WHERE execution_id = $1
+        ORDER BY created_at ASC, id ASC
+        "#,
+        execution_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Up to `limit` blobs whose execution was deleted, oldest first.
+pub async fn list_orphaned_binary_data(pool: &PgPool, limit: i64) -> Result<Vec<BinaryDataRow>, DbError> {
+    let rows = sqlx::query_as!(
+        BinaryDataRow,
+        r#"
+        SELECT id, execution_id, workflow_id, storage_key, mime_type, file_name, size, created_at
+        FROM binary_data
+        WHERE execution_id IS NULL
+        ORDER BY created_at ASC
+        LIMIT $1
+        "#,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Forget blob `id`, once it is deleted from the blob store.
+pub async fn delete_binary_data(pool: &PgPool, id: Uuid) -> Result<(), DbError> {
+    sqlx::query!("DELETE FROM binary_data WHERE id = $1", id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! Webhook capture repository functions.
+//!
+//! A capture is armed with [`arm_capture`]; the webhook handler then offers
+//! each request to [`capture_payload`], a conditional update that only one
+//! request can win while the capture is armed.
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{DbError, models::WebhookCaptureRow};
+
+/// Arm the capture of `workflow_id` until `until`, keeping any previous
+/// sample until a request replaces it.
+pub async fn arm_capture(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    until: DateTime<Utc>,
+) -> Result<WebhookCaptureRow, DbError> {
+    let row = sqlx::query_as!(
+        WebhookCaptureRow,
+        r#"
+        INSERT INTO webhook_captures (workflow_id, armed_until)
+        VALUES ($1, $2)
+        ON CONFLICT (workflow_id) DO UPDATE SET armed_until = EXCLUDED.armed_until
+        RETURNING workflow_id, armed_until, payload, captured_at
+        "#,
+        workflow_id,
+        until,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Store `payload` as the sample of `workflow_id` and disarm its capture,
+/// if it is armed; returns `None` (and stores nothing) otherwise.
+pub async fn capture_payload(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    payload: serde_json::Value,
+) -> Result<Option<WebhookCaptureRow>, DbError> {
+    let row = sqlx::query_as!(
+        WebhookCaptureRow,
+        r#"
+        UPDATE webhook_captures
+        SET payload = $2, captured_at = NOW(), armed_until = NULL
+        WHERE workflow_id = $1 AND armed_until > NOW()
+        RETURNING workflow_id, armed_until, payload, captured_at
+        "#,
+        workflow_id,
+        payload,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// The capture of `workflow_id`, if it was ever armed.
+pub async fn get_capture(pool: &PgPool, workflow_id: Uuid) -> Result<Option<WebhookCaptureRow>, DbError> {
+    let row = sqlx::query_as!(
+        WebhookCaptureRow,
+        r#"
+        SELECT workflow_id, armed_until, payload, captured_at
+        FROM webhook_captures
+        WHERE workflow_id = $1
+        "#,
+        workflow_id,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Disarm the capture of `workflow_id` and drop its sample; returns
+/// whether there was anything to delete.
+pub async fn delete_capture(pool: &PgPool, workflow_id: Uuid) -> Result<bool, DbError> {
+    let result = sqlx::query!("DELETE FROM webhook_captures WHERE workflow_id = $1", workflow_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
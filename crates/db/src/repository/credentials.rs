@@ -0,0 +1,214 @@
+//! Shared credential repository functions.
+//!
+//! Credential fields are stored as given — encrypting them is up to the
+//! caller.
+
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{DbError, models::CredentialRow};
+
+/// Every credential of project `project_id`, by name.
+pub async fn list_credentials(pool: &PgPool, project_id: Uuid) -> Result<Vec<CredentialRow>, DbError> {
+    let rows = sqlx::query_as!(
+        CredentialRow,
+        r#"
+        SELECT id, project_id, name, credential_type, encrypted_data, created_at, updated_at
+        FROM credentials
+        WHERE project_id = $1
+        ORDER BY name
+        "#,
+        project_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Fetch credential `id`.
+pub async fn get_credential(pool: &PgPool, id: Uuid) -> Result<CredentialRow, DbError> {
+    sqlx::query_as!(
+        CredentialRow,
+        r#"
+        SELECT id, project_id, name, credential_type, encrypted_data, created_at, updated_at
+        FROM credentials
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(DbError::NotFound)
+}
+
+/// The credentials among `ids` that workflow `workflow_id` may use: those
+/// in the workflow's project.
+pub async fn credentials_for_workflow(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    ids: &[Uuid],
+) -> Result<Vec<CredentialRow>, DbError> {
+    let rows = sqlx::query_as!(
+        CredentialRow,
+        r#"
+        SELECT c.id, c.project_id, c.name, c.credential_type, c.encrypted_data, c.created_at, c.updated_at
+        FROM credentials c
+        JOIN workflows w ON w.project_id = c.project_id
+        WHERE w.id = $1 AND c.id = ANY($2)
+        "#,
+        workflow_id,
+        ids,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Add credential `id` named `name` to project `project_id`; `None` when
+/// the project already has a credential with that name.
+pub async fn create_credential(
+    pool: &PgPool,
+    id: Uuid,
+    project_id: Uuid,
+    name: &str,
+    credential_type: &str,
+    encrypted_data: &str,
+) -> Result<Option<CredentialRow>, DbError> {
+    let row = sqlx::query_as!(
+        CredentialRow,
+        r#"
+        INSERT INTO credentials (id, project_id, name, credential_type, encrypted_data, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $6)
+        ON CONFLICT (project_id, name) DO NOTHING
+        RETURNING id, project_id, name, credential_type, encrypted_data, created_at, updated_at
+        "#,
+        id,
+        project_id,
+        name,
+        credential_type,
+        encrypted_data,
+        Utc::now(),
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Replace the name, type, and fields of credential `id`.
+///
+/// Returns `DbError::NotFound` if there is no such credential and
+/// `DbError::CredentialNameTaken` if another one of its project has `name`.
+pub async fn update_credential(
+    pool: &PgPool,
+    id: Uuid,
+    name: &str,
+    credential_type: &str,
+    encrypted_data: &str,
+) -> Result<CredentialRow, DbError> {
+    let result = sqlx::query_as!(
+        CredentialRow,
+        r#"
+        UPDATE credentials SET name = $2, credential_type = $3, encrypted_data = $4, updated_at = $5
+        WHERE id = $1
+        RETURNING id, project_id, name, credential_type, encrypted_data, created_at, updated_at
+        "#,
+        id,
+        name,
+        credential_type,
+        encrypted_data,
+        Utc::now(),
+    )
+    .fetch_optional(pool)
+    .await;
+
+    match result {
+        Ok(row) => row.ok_or(DbError::NotFound),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => Err(DbError::CredentialNameTaken),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Delete credential `id`.
+///
+/// Returns `DbError::NotFound` if there is no such credential.
+pub async fn delete_credential(pool: &PgPool, id: Uuid) -> Result<(), DbError> {
+    let result = sqlx::query!("DELETE FROM credentials WHERE id = $1", id).execute(pool).await?;
+
+    if result.rows_affected() == 0 {
+        return Err(DbError::NotFound);
+    }
+    Ok(())
+}
+
+/// The project credential `id` belongs to.
+pub async fn credential_project(pool: &PgPool, id: Uuid) -> Result<Uuid, DbError> {
+    sqlx::query_scalar!("SELECT project_id FROM credentials WHERE id = $1", id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(DbError::NotFound)
+}
+
+/// Credentials stored across every project.
+pub async fn count_credentials(pool: &PgPool) -> Result<i64, DbError> {
+    let count = sqlx::query_scalar!(r#"SELECT COUNT(*) AS "count!" FROM credentials"#).fetch_one(pool).await?;
+    Ok(count)
+}
+
+/// The most recently written credential of any project, to check a key
+/// against.
+pub async fn newest_credential(pool: &PgPool) -> Result<Option<CredentialRow>, DbError> {
+    let row = sqlx::query_as!(
+        CredentialRow,
+        r#"
+        SELECT id, project_id, name, credential_type, encrypted_data, created_at, updated_at
+        FROM credentials
+        ORDER BY updated_at DESC
+        LIMIT 1
+        "#,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Every credential of every project, oldest first.
+pub async fn list_all_credentials(pool: &PgPool) -> Result<Vec<CredentialRow>, DbError> {
+    let rows = sqlx::query_as!(
+        CredentialRow,
+        r#"
+        SELECT id, project_id, name, credential_type, encrypted_data, created_at, updated_at
+        FROM credentials
+        ORDER BY created_at, id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Store credential `id` encrypted anew as `encrypted_data`, unless it was
+/// changed from `previous` meanwhile; `false` then.  Its `updated_at`
+/// stays, as the credential does.
+pub async fn reencrypt_credential(
+    pool: &PgPool,
+    id: Uuid,
+    previous: &str,
+    encrypted_data: &str,
+) -> Result<bool, DbError> {
+    let result = sqlx::query!(
+        "UPDATE credentials SET encrypted_data = $3 WHERE id = $1 AND encrypted_data = $2",
+        id,
+        previous,
+        encrypted_data,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
@@ -1,12 +1,16 @@
 //! Execution and node-execution repository functions.
 
 use chrono::Utc;
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
 use crate::{
+    changes::{self, Change},
+    lock, payloads,
     DbError,
-    models::{WorkflowExecutionRow, NodeExecutionRow},
+    models::{
+        BusyWorkflowRow, DailyExecutionsRow, DashboardStatsRow, ExecutionFilter, ExecutionMeta, ExecutionSummaryRow, ExecutionTimingRow, NewNodeExecution, NodeDurationRow, NodeExecutionRow, NodeFailuresRow, NodeStatsRow, NodeTimingRow, StartLimit, WorkflowExecutionRow, WorkflowStatsRow, DEFAULT_MAX_ATTEMPTS,
+    },
 };
 
 // ---------------------------------------------------------------------------
@@ -17,6 +21,72 @@ use crate::{
 pub async fn create_execution(
     pool: &PgPool,
     workflow_id: Uuid,
+    meta: &ExecutionMeta,
+) -> Result<WorkflowExecutionRow, DbError> {
+    let row = insert_execution(pool, workflow_id, meta).await?;
+    publish_status(pool, row.id, row.workflow_id, &row.status).await;
+    Ok(row)
+}
+
+/// [`create_execution`], unless the workflow already started `limit.max`
+/// executions since `limit.since`; `None` then, and nothing is created.
+///
+/// The count and the insert happen under the workflow's admission lock
+/// (see [`within_limit`]), so concurrent callers cannot both take the
+/// last slot.
+pub async fn create_execution_within(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    meta: &ExecutionMeta,
+    limit: StartLimit,
+) -> Result<Option<WorkflowExecutionRow>, DbError> {
+    let mut tx = pool.begin().await?;
+    if !within_limit(&mut tx, workflow_id, limit).await? {
+        tx.rollback().await?;
+        return Ok(None);
+    }
+    let row = insert_execution(&mut *tx, workflow_id, meta).await?;
+    tx.commit().await?;
+    publish_status(pool, row.id, row.workflow_id, &row.status).await;
+    Ok(Some(row))
+}
+
+/// Whether `limit` leaves room for another execution of `workflow_id`.
+///
+/// Takes the workflow's admission lock for the rest of `tx` first, so the
+/// next caller waits until this transaction ends and then counts what it
+/// inserted.  The count is a statement of its own: one that also took the
+/// lock would count from a snapshot taken before the wait.
+pub(crate) async fn within_limit(
+    tx: &mut Transaction<'_, Postgres>,
+    workflow_id: Uuid,
+    limit: StartLimit,
+) -> Result<bool, DbError> {
+    sqlx::query_scalar!(
+        r#"SELECT 1 AS "locked!" FROM pg_advisory_xact_lock($1)"#,
+        lock::lock_key(&format!("admission:{workflow_id}")),
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    let started = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM workflow_executions
+        WHERE workflow_id = $1 AND started_at >= $2
+        "#,
+        workflow_id,
+        limit.since,
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(started < limit.max)
+}
+
+async fn insert_execution(
+    executor: impl PgExecutor<'_>,
+    workflow_id: Uuid,
+    meta: &ExecutionMeta,
 ) -> Result<WorkflowExecutionRow, DbError> {
     let id = Uuid::new_v4();
     let now = Utc::now();
@@ -24,20 +94,81 @@ pub async fn create_execution(
     let row = sqlx::query_as!(
         WorkflowExecutionRow,
         r#"
-        INSERT INTO workflow_executions (id, workflow_id, status, started_at)
-        VALUES ($1, $2, 'pending', $3)
-        RETURNING id, workflow_id, status, started_at, finished_at
+        INSERT INTO workflow_executions
+            (id, workflow_id, status, started_at, business_key, labels, priority, queue, parent_execution_id,
+             required_tags, max_attempts, retry_of, workflow_version, project_id, params)
+        VALUES ($1, $2, 'pending', $3, $4, $5, $6, COALESCE($7, 'default'), $8, $9, $10, $11,
+                (SELECT version FROM workflows WHERE id = $2),
+                (SELECT project_id FROM workflows WHERE id = $2), $12)
+        RETURNING id, workflow_id, status, started_at, finished_at, business_key, labels,
+                  priority, queue, parent_execution_id, required_tags, max_attempts, retry_of, workflow_version
         "#,
         id,
         workflow_id,
         now,
+        meta.business_key.as_deref(),
+        &meta.labels,
+        meta.priority,
+        meta.queue.as_deref(),
+        meta.parent_execution_id,
+        &meta.required_tags,
+        meta.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS),
+        meta.retry_of,
+        serde_json::Value::Object(meta.params.clone()),
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
 
     Ok(row)
 }
 
+/// The workflow parameters execution `id` was started with (see
+/// [`ExecutionMeta::params`]).
+pub async fn execution_params(pool: &PgPool, id: Uuid) -> Result<serde_json::Map<String, serde_json::Value>, DbError> {
+    let params = sqlx::query_scalar!("SELECT params FROM workflow_executions WHERE id = $1", id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(DbError::NotFound)?;
+    match params {
+        serde_json::Value::Object(params) => Ok(params),
+        _ => Ok(serde_json::Map::new()),
+    }
+}
+
+/// Publish that execution `id` of workflow `workflow_id` is now `status`.
+pub(crate) async fn publish_status(pool: &PgPool, id: Uuid, workflow_id: Uuid, status: &str) {
+    let Ok(project_id) = execution_project(pool, id).await else { return };
+    let change = Change::Execution { id, workflow_id, project_id, status: status.to_owned() };
+    changes::publish(pool, &change).await;
+}
+
+/// The project execution `id` belongs to.
+pub async fn execution_project(pool: &PgPool, id: Uuid) -> Result<Uuid, DbError> {
+    sqlx::query_scalar!("SELECT project_id FROM workflow_executions WHERE id = $1", id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(DbError::NotFound)
+}
+
+/// Fetch a single execution by ID.
+pub async fn get_execution(pool: &PgPool, id: Uuid) -> Result<WorkflowExecutionRow, DbError> {
+    let row = sqlx::query_as!(
+        WorkflowExecutionRow,
+        r#"
+        SELECT id, workflow_id, status, started_at, finished_at, business_key, labels,
+               priority, queue, parent_execution_id, required_tags, max_attempts, retry_of, workflow_version
+        FROM workflow_executions
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(DbError::NotFound)?;
+
+    Ok(row)
+}
+
 /// Update the `status` (and optionally `finished_at`) of a workflow execution.
 pub async fn update_execution_status(
     pool: &PgPool,
@@ -45,68 +176,646 @@ pub async fn update_execution_status(
     status: &str,
     finished: bool,
 ) -> Result<(), DbError> {
-    if finished {
+    record_step(pool, execution_id, &[], Some((status, finished))).await.map(|_| ())
+}
+
+/// Set the status of an execution within `tx`; returns its workflow and
+/// project for the change notification, or `None` if it does not exist.
+async fn set_execution_status(
+    tx: &mut Transaction<'_, Postgres>,
+    execution_id: Uuid,
+    status: &str,
+    finished: bool,
+) -> Result<Option<(Uuid, Uuid)>, DbError> {
+    let changed = if finished {
         sqlx::query!(
             r#"
             UPDATE workflow_executions
             SET status = $1, finished_at = $2
             WHERE id = $3
+            RETURNING workflow_id, project_id
             "#,
             status,
             Utc::now(),
             execution_id,
         )
-        .execute(pool)
-        .await?;
+        .fetch_optional(&mut **tx)
+        .await?
+        .map(|row| (row.workflow_id, row.project_id))
     } else {
+        // A run starts with the workflow's definition as it is now;
+        // `running_at` keeps the first time it started.
         sqlx::query!(
-            r#"UPDATE workflow_executions SET status = $1 WHERE id = $2"#,
+            r#"
+            UPDATE workflow_executions
+            SET status = $1,
+                workflow_version = CASE
+                    WHEN $1 = 'running' THEN (SELECT version FROM workflows w WHERE w.id = workflow_id)
+                    ELSE workflow_version
+                END,
+                running_at = CASE
+                    WHEN $1 = 'running' THEN COALESCE(running_at, NOW())
+                    ELSE running_at
+                END
+            WHERE id = $2
+            RETURNING workflow_id, project_id
+            "#,
             status,
             execution_id,
         )
-        .execute(pool)
-        .await?;
-    }
+        .fetch_optional(&mut **tx)
+        .await?
+        .map(|row| (row.workflow_id, row.project_id))
+    };
+
+    Ok(changed)
+}
+
+/// Every execution, across all workflows (of project `project_id`, if
+/// given), tagged with `business_key` (newest first).
+pub async fn list_executions_by_business_key(
+    pool: &PgPool,
+    project_id: Option<Uuid>,
+    business_key: &str,
+) -> Result<Vec<ExecutionSummaryRow>, DbError> {
+    let rows = sqlx::query_as!(
+        ExecutionSummaryRow,
+        r#"
+        SELECT e.id, e.workflow_id, w.name AS workflow_name, e.status, e.business_key,
+               e.started_at, e.finished_at
+        FROM workflow_executions e
+        JOIN workflows w ON w.id = e.workflow_id
+        WHERE e.business_key = $1
+          AND ($2::uuid IS NULL OR e.project_id = $2)
+        ORDER BY e.started_at DESC
+        "#,
+        business_key,
+        project_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Executions matching `filter`, newest first, skipping `offset` and
+/// returning at most `limit`.
+pub async fn list_executions(
+    pool: &PgPool,
+    filter: &ExecutionFilter,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<ExecutionSummaryRow>, DbError> {
+    let rows = sqlx::query_as!(
+        ExecutionSummaryRow,
+        r#"
+        SELECT e.id, e.workflow_id, w.name AS workflow_name, e.status, e.business_key,
+               e.started_at, e.finished_at
+        FROM workflow_executions e
+        JOIN workflows w ON w.id = e.workflow_id
+        WHERE ($1::uuid IS NULL OR e.workflow_id = $1)
+          AND ($2::text IS NULL OR e.status = $2)
+          AND ($3::timestamptz IS NULL OR e.started_at >= $3)
+          AND ($4::timestamptz IS NULL OR e.started_at < $4)
+          AND ($5::text IS NULL OR w.definition->'trigger'->>'type' = $5)
+          AND ($8::uuid IS NULL OR e.project_id = $8)
+        ORDER BY e.started_at DESC, e.id
+        LIMIT $6 OFFSET $7
+        "#,
+        filter.workflow_id,
+        filter.status,
+        filter.started_after,
+        filter.started_before,
+        filter.trigger_type,
+        limit,
+        offset,
+        filter.project_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Delete execution `id` (with its node records and jobs) if it has
+/// finished and is not under legal hold, directly or through its workflow.
+/// Returns whether it was deleted.
+pub async fn delete_execution(pool: &PgPool, id: Uuid) -> Result<bool, DbError> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM workflow_executions e
+        WHERE e.id = $1
+          AND e.status IN ('succeeded', 'failed')
+          AND NOT e.legal_hold
+          AND NOT EXISTS (SELECT 1 FROM workflows w WHERE w.id = e.workflow_id AND w.legal_hold)
+        "#,
+        id,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete up to `limit` finished executions, of one workflow and/or
+/// finished before `before` (in project `project_id`, if given), skipping
+/// those under legal hold.  Returns the number deleted.
+pub async fn delete_finished_executions(
+    pool: &PgPool,
+    project_id: Option<Uuid>,
+    workflow_id: Option<Uuid>,
+    before: Option<chrono::DateTime<Utc>>,
+    limit: i64,
+) -> Result<u64, DbError> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM workflow_executions
+        WHERE id IN (
+            SELECT e.id FROM workflow_executions e
+            JOIN workflows w ON w.id = e.workflow_id
+            WHERE ($1::uuid IS NULL OR e.workflow_id = $1)
+              AND ($2::timestamptz IS NULL OR COALESCE(e.finished_at, e.started_at) < $2)
+              AND e.status IN ('succeeded', 'failed')
+              AND NOT e.legal_hold
+              AND NOT w.legal_hold
+              AND ($4::uuid IS NULL OR e.project_id = $4)
+            LIMIT $3
+        )
+        "#,
+        workflow_id,
+        before,
+        limit,
+        project_id,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Counts and durations of the executions of a workflow started at or
+/// after `since`.
+pub async fn workflow_stats(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    since: chrono::DateTime<Utc>,
+) -> Result<WorkflowStatsRow, DbError> {
+    let row = sqlx::query_as!(
+        WorkflowStatsRow,
+        r#"
+        WITH runs AS (
+            SELECT status,
+                   EXTRACT(EPOCH FROM finished_at - started_at)::float8 * 1000 AS duration_ms
+            FROM workflow_executions
+            WHERE workflow_id = $1 AND started_at >= $2
+        )
+        SELECT COUNT(*) AS "total!",
+               COUNT(*) FILTER (WHERE status = 'succeeded') AS "succeeded!",
+               COUNT(*) FILTER (WHERE status = 'failed') AS "failed!",
+               AVG(duration_ms) AS avg_duration_ms,
+               percentile_cont(0.5) WITHIN GROUP (ORDER BY duration_ms) AS p50_duration_ms,
+               percentile_cont(0.9) WITHIN GROUP (ORDER BY duration_ms) AS p90_duration_ms,
+               percentile_cont(0.99) WITHIN GROUP (ORDER BY duration_ms) AS p99_duration_ms
+        FROM runs
+        "#,
+        workflow_id,
+        since,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// The executions of a workflow started on each UTC day from `since`'s
+/// through today's, days without any included.
+pub async fn daily_executions(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    since: chrono::DateTime<Utc>,
+) -> Result<Vec<DailyExecutionsRow>, DbError> {
+    let rows = sqlx::query_as!(
+        DailyExecutionsRow,
+        r#"
+        SELECT d.day::date AS "day!",
+               COUNT(e.id) AS "total!",
+               COUNT(e.id) FILTER (WHERE e.status = 'succeeded') AS "succeeded!",
+               COUNT(e.id) FILTER (WHERE e.status = 'failed') AS "failed!"
+        FROM generate_series(
+                 date_trunc('day', $2::timestamptz AT TIME ZONE 'UTC'),
+                 date_trunc('day', NOW() AT TIME ZONE 'UTC'),
+                 INTERVAL '1 day'
+             ) AS d (day)
+        LEFT JOIN workflow_executions e
+               ON e.workflow_id = $1
+              AND e.started_at >= $2
+              AND date_trunc('day', e.started_at AT TIME ZONE 'UTC') = d.day
+        GROUP BY d.day
+        ORDER BY d.day
+        "#,
+        workflow_id,
+        since,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// The 95th percentile of how long node `node_id` of a workflow took, in
+/// milliseconds, over its successful runs started at or after `since`, and
+/// how many runs that is.
+pub async fn node_duration_p95(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    node_id: &str,
+    since: chrono::DateTime<Utc>,
+) -> Result<NodeDurationRow, DbError> {
+    let row = sqlx::query_as!(
+        NodeDurationRow,
+        r#"
+        SELECT PERCENTILE_CONT(0.95) WITHIN GROUP (
+                   ORDER BY EXTRACT(EPOCH FROM n.finished_at - n.started_at)::float8 * 1000
+               ) AS p95_ms,
+               COUNT(*) AS "samples!"
+        FROM node_executions n
+        JOIN workflow_executions e ON e.id = n.execution_id
+        WHERE e.workflow_id = $1 AND n.node_id = $2 AND n.started_at >= $3
+          AND n.status = 'succeeded' AND n.finished_at IS NOT NULL
+        "#,
+        workflow_id,
+        node_id,
+        since,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// The node of a workflow that failed most often in executions started at
+/// or after `since` (ties go to the first node id), if any failed.
+pub async fn most_failing_node(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    since: chrono::DateTime<Utc>,
+) -> Result<Option<NodeFailuresRow>, DbError> {
+    let row = sqlx::query_as!(
+        NodeFailuresRow,
+        r#"
+        SELECT n.node_id, COUNT(*) AS "failures!"
+        FROM node_executions n
+        JOIN workflow_executions e ON e.id = n.execution_id
+        WHERE e.workflow_id = $1 AND e.started_at >= $2 AND n.status = 'failed'
+        GROUP BY n.node_id
+        ORDER BY COUNT(*) DESC, n.node_id
+        LIMIT 1
+        "#,
+        workflow_id,
+        since,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// How each node of a workflow ran on the UTC days from `since` through
+/// today, read from `node_daily_stats`, the node that took the most time
+/// in total first (ties go to the first node id).
+pub async fn node_stats(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    since: chrono::NaiveDate,
+) -> Result<Vec<NodeStatsRow>, DbError> {
+    let rows = sqlx::query_as!(
+        NodeStatsRow,
+        r#"
+        SELECT node_id,
+               SUM(runs)::bigint AS "runs!",
+               SUM(failures)::bigint AS "failures!",
+               SUM(retries)::bigint AS "retries!",
+               SUM(total_ms)::bigint AS "total_ms!",
+               (SUM(total_ms)::float8 / GREATEST(SUM(runs), 1)) AS "avg_ms!",
+               MAX(max_ms) AS "max_ms!"
+        FROM node_daily_stats
+        WHERE workflow_id = $1 AND day >= $2
+        GROUP BY node_id
+        ORDER BY SUM(total_ms) DESC, node_id
+        "#,
+        workflow_id,
+        since,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// A project's workflow counts and the executions of its workflows started
+/// at or after `since`.
+pub async fn dashboard_stats(
+    pool: &PgPool,
+    project_id: Uuid,
+    since: chrono::DateTime<Utc>,
+) -> Result<DashboardStatsRow, DbError> {
+    let row = sqlx::query_as!(
+        DashboardStatsRow,
+        r#"
+        WITH runs AS (
+            SELECT e.status
+            FROM workflow_executions e
+            JOIN workflows w ON w.id = e.workflow_id
+            WHERE w.project_id = $1 AND e.started_at >= $2
+        )
+        SELECT (SELECT COUNT(*) FROM workflows WHERE project_id = $1) AS "workflows!",
+               (SELECT COUNT(*) FROM workflows WHERE project_id = $1 AND active) AS "active_workflows!",
+               COUNT(*) AS "executions!",
+               COUNT(*) FILTER (WHERE status = 'succeeded') AS "succeeded!",
+               COUNT(*) FILTER (WHERE status = 'failed') AS "failed!",
+               COUNT(*) FILTER (WHERE status NOT IN ('succeeded', 'failed')) AS "in_flight!"
+        FROM runs
+        "#,
+        project_id,
+        since,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// The `limit` workflows of a project that started the most executions at
+/// or after `since`, busiest first (ties go to the first name).
+pub async fn busiest_workflows(
+    pool: &PgPool,
+    project_id: Uuid,
+    since: chrono::DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<BusyWorkflowRow>, DbError> {
+    let rows = sqlx::query_as!(
+        BusyWorkflowRow,
+        r#"
+        SELECT w.id AS workflow_id, w.name,
+               COUNT(*) AS "executions!",
+               COUNT(*) FILTER (WHERE e.status = 'failed') AS "failed!"
+        FROM workflow_executions e
+        JOIN workflows w ON w.id = e.workflow_id
+        WHERE w.project_id = $1 AND e.started_at >= $2
+        GROUP BY w.id, w.name
+        ORDER BY COUNT(*) DESC, w.name, w.id
+        LIMIT $3
+        "#,
+        project_id,
+        since,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Delete up to `limit` executions of a workflow with `status` that finished
+/// before `before`, skipping any carrying one of `keep_labels` and any under
+/// legal hold (directly or through their workflow).
+///
+/// Node executions and queued jobs go with them (`ON DELETE CASCADE`).
+/// Returns the number of executions deleted; call repeatedly until it is
+/// below `limit` to prune everything eligible in small transactions.
+pub async fn delete_expired_executions(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    status: &str,
+    before: chrono::DateTime<Utc>,
+    keep_labels: &[String],
+    limit: i64,
+) -> Result<u64, DbError> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM workflow_executions
+        WHERE id IN (
+            SELECT id FROM workflow_executions
+            WHERE workflow_id = $1
+              AND status = $2
+              AND COALESCE(finished_at, started_at) < $3
+              AND NOT (labels && $4)
+              AND NOT legal_hold
+              AND NOT EXISTS (SELECT 1 FROM workflows w WHERE w.id = $1 AND w.legal_hold)
+            LIMIT $5
+        )
+        "#,
+        workflow_id,
+        status,
+        before,
+        keep_labels,
+        limit,
+    )
+    .execute(pool)
+    .await?;
 
-    Ok(())
+    Ok(result.rows_affected())
+}
+
+/// Delete up to `limit` finished executions of a workflow beyond the `keep`
+/// most recently started ones, with the exemptions of
+/// [`delete_expired_executions`]; exempt executions do not count towards
+/// `keep`.
+///
+/// Returns the number of executions deleted; call repeatedly until it is
+/// below `limit`.
+pub async fn delete_excess_executions(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    keep: i64,
+    keep_labels: &[String],
+    limit: i64,
+) -> Result<u64, DbError> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM workflow_executions
+        WHERE id IN (
+            SELECT id FROM workflow_executions
+            WHERE workflow_id = $1
+              AND status IN ('succeeded', 'failed')
+              AND NOT (labels && $3)
+              AND NOT legal_hold
+              AND NOT EXISTS (SELECT 1 FROM workflows w WHERE w.id = $1 AND w.legal_hold)
+            ORDER BY started_at DESC, id DESC
+            OFFSET $2
+            LIMIT $4
+        )
+        "#,
+        workflow_id,
+        keep,
+        keep_labels,
+        limit,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
 }
 
 // ---------------------------------------------------------------------------
 // node_executions
 // ---------------------------------------------------------------------------
 
-/// Insert a completed node execution record.
-pub async fn insert_node_execution(
+/// All node execution records of an execution, in the order they ran.
+pub async fn list_node_executions(
     pool: &PgPool,
     execution_id: Uuid,
-    node_id: &str,
-    input: serde_json::Value,
-    output: Option<serde_json::Value>,
-    status: &str,
-    started_at: chrono::DateTime<Utc>,
-) -> Result<NodeExecutionRow, DbError> {
+) -> Result<Vec<NodeExecutionRow>, DbError> {
+    let rows = sqlx::query_as!(
+        NodeExecutionRow,
+        r#"
+        SELECT id, execution_id, node_id, input, output, status, started_at, finished_at,
+               input_hash, output_hash, error, branch, halted
+        FROM node_executions
+        WHERE execution_id = $1
+        ORDER BY started_at ASC, finished_at ASC
+        "#,
+        execution_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|mut row| {
+            row.input = payloads::decode(row.input, execution_id)?;
+            row.output = row.output.map(|output| payloads::decode(output, execution_id)).transpose()?;
+            Ok(row)
+        })
+        .collect()
+}
+
+/// The queue and run times of an execution and the attempts of each of its
+/// nodes, in the order they ran.
+pub async fn execution_timings(
+    pool: &PgPool,
+    execution_id: Uuid,
+) -> Result<(ExecutionTimingRow, Vec<NodeTimingRow>), DbError> {
+    let execution = sqlx::query_as!(
+        ExecutionTimingRow,
+        r#"
+        SELECT id, status, started_at AS queued_at, running_at, finished_at
+        FROM workflow_executions
+        WHERE id = $1
+        "#,
+        execution_id,
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(DbError::NotFound)?;
+    let nodes = sqlx::query_as!(
+        NodeTimingRow,
+        r#"
+        SELECT node_id, status, started_at, finished_at, attempts
+        FROM node_executions
+        WHERE execution_id = $1
+        ORDER BY started_at ASC, finished_at ASC
+        "#,
+        execution_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok((execution, nodes))
+}
+
+/// Store the node executions and status change (`(status, finished)`, as
+/// for [`update_execution_status`]) of one step of an execution in a single
+/// transaction: either all of them are written or none.  Returns the new
+/// node execution rows' IDs.
+pub async fn record_step(
+    pool: &PgPool,
+    execution_id: Uuid,
+    nodes: &[NewNodeExecution<'_>],
+    status: Option<(&str, bool)>,
+) -> Result<Vec<Uuid>, DbError> {
+    let mut tx = pool.begin().await?;
+    let mut ids = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        ids.push(insert_node_execution(&mut tx, execution_id, node).await?);
+    }
+    let changed = match status {
+        Some((status, finished)) => set_execution_status(&mut tx, execution_id, status, finished).await?,
+        None => None,
+    };
+    tx.commit().await?;
+
+    if let (Some((workflow_id, project_id)), Some((status, _))) = (changed, status) {
+        let change = Change::Execution { id: execution_id, workflow_id, project_id, status: status.to_owned() };
+        changes::publish(pool, &change).await;
+    }
+    Ok(ids)
+}
+
+/// Insert a completed node execution record within `tx`, and count it in
+/// its day's `node_daily_stats`.
+///
+/// The payloads are bound by reference and encoded straight into the
+/// statement's parameter buffer; nothing is read back, so large inputs and
+/// outputs cross the wire once.  They are compressed and encrypted first
+/// as [`payloads`] are written.
+async fn insert_node_execution(
+    tx: &mut Transaction<'_, Postgres>,
+    execution_id: Uuid,
+    node: &NewNodeExecution<'_>,
+) -> Result<Uuid, DbError> {
     let id = Uuid::new_v4();
     let now = Utc::now();
+    let input = payloads::encode(node.input, execution_id);
+    let output = node.output.and_then(|output| payloads::encode(output, execution_id));
 
-    let row = sqlx::query_as!(
-        NodeExecutionRow,
+    sqlx::query!(
         r#"
         INSERT INTO node_executions
-            (id, execution_id, node_id, input, output, status, started_at, finished_at)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-        RETURNING id, execution_id, node_id, input, output, status, started_at, finished_at
+            (id, execution_id, node_id, input, output, status, started_at, finished_at,
+             input_hash, output_hash, error, branch, halted, attempts)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
         "#,
         id,
         execution_id,
-        node_id,
-        input,
-        output,
-        status,
-        started_at,
+        node.node_id,
+        input.as_ref().unwrap_or(node.input),
+        output.as_ref().or(node.output),
+        node.status,
+        node.started_at,
         now,
+        node.hashes.map(|h| h.input.as_str()),
+        node.hashes.and_then(|h| h.output.as_deref()),
+        node.error,
+        node.flow.branch.as_deref(),
+        node.flow.halted,
+        serde_json::json!(node.attempts),
     )
-    .fetch_one(pool)
+    .execute(&mut **tx)
     .await?;
 
-    Ok(row)
+    let took_ms = (now - node.started_at).num_milliseconds().max(0);
+    let retries = node.attempts.len().saturating_sub(1) as i64;
+    sqlx::query!(
+        r#"
+        INSERT INTO node_daily_stats (workflow_id, node_id, day, runs, failures, retries, total_ms, max_ms)
+        SELECT workflow_id, $2, ($3::timestamptz AT TIME ZONE 'UTC')::date, 1, $4, $5, $6, $6
+        FROM workflow_executions WHERE id = $1
+        ON CONFLICT (workflow_id, node_id, day) DO UPDATE
+        SET runs     = node_daily_stats.runs + 1,
+            failures = node_daily_stats.failures + EXCLUDED.failures,
+            retries  = node_daily_stats.retries + EXCLUDED.retries,
+            total_ms = node_daily_stats.total_ms + EXCLUDED.total_ms,
+            max_ms   = GREATEST(node_daily_stats.max_ms, EXCLUDED.max_ms)
+        "#,
+        execution_id,
+        node.node_id,
+        node.started_at,
+        i64::from(node.status == "failed"),
+        retries,
+        took_ms,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(id)
 }
@@ -0,0 +1,76 @@
+//! Feature flag override repository functions.
+
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::{DbError, models::FeatureFlagRow};
+
+/// Every stored override.
+pub async fn list_flags(pool: &PgPool) -> Result<Vec<FeatureFlagRow>, DbError> {
+    let rows = sqlx::query_as!(
+        FeatureFlagRow,
+        r#"
+        SELECT flag, scope, scope_id, enabled, updated_at
+        FROM feature_flags
+        ORDER BY flag, scope, scope_id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Create or replace the override of `flag` for one scope.
+pub async fn upsert_flag(
+    pool: &PgPool,
+    flag: &str,
+    scope: &str,
+    scope_id: &str,
+    enabled: bool,
+) -> Result<FeatureFlagRow, DbError> {
+    let row = sqlx::query_as!(
+        FeatureFlagRow,
+        r#"
+        INSERT INTO feature_flags (flag, scope, scope_id, enabled, updated_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (flag, scope, scope_id)
+        DO UPDATE SET enabled = EXCLUDED.enabled, updated_at = EXCLUDED.updated_at
+        RETURNING flag, scope, scope_id, enabled, updated_at
+        "#,
+        flag,
+        scope,
+        scope_id,
+        enabled,
+        Utc::now(),
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Remove the override of `flag` for one scope.
+///
+/// Returns `DbError::NotFound` if there was none.
+pub async fn delete_flag(
+    pool: &PgPool,
+    flag: &str,
+    scope: &str,
+    scope_id: &str,
+) -> Result<(), DbError> {
+    let result = sqlx::query!(
+        "DELETE FROM feature_flags WHERE flag = $1 AND scope = $2 AND scope_id = $3",
+        flag,
+        scope,
+        scope_id,
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(DbError::NotFound);
+    }
+
+    Ok(())
+}
@@ -2,13 +2,38 @@
 //!
 //! The MVP queue is backed by the `job_queue` Postgres table.
 //! Workers poll the table and use `SELECT … FOR UPDATE SKIP LOCKED`
-//! for safe concurrent processing.
+//! for safe concurrent processing.  A job only becomes visible to workers
+//! once its `run_at` time has passed.
+//!
+//! Jobs that are due right away are announced on [`JOBS_CHANNEL`] so idle
+//! workers can wake up instead of waiting for their next poll.
 
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
 use sqlx::PgPool;
+use tracing::warn;
 use uuid::Uuid;
 
-use crate::{DbError, models::JobRow};
+use crate::{DbError, listener, payloads, repository::executions, models::{ExecutionMeta, JobRow, QueueStatsRow, StartLimit, DEFAULT_MAX_ATTEMPTS}};
+
+/// Channel on which due jobs are announced; the payload is the job's queue.
+pub const JOBS_CHANNEL: &str = "job_queue";
+
+/// Announce a due job on `queue`.  Best effort: a lost notification only
+/// delays the job until workers poll again.
+async fn announce(pool: &PgPool, queue: &str) {
+    if let Err(e) = listener::notify(pool, JOBS_CHANNEL, queue).await {
+        warn!("cannot announce a job on queue '{}': {}", queue, e);
+    }
+}
+
+/// `job` with its payload and checkpoint decoded, if they were stored
+/// compressed or encrypted (see [`payloads`]).
+fn decoded(mut job: JobRow) -> Result<JobRow, DbError> {
+    job.payload = payloads::decode(job.payload, job.execution_id)?;
+    job.checkpoint = job.checkpoint.map(|checkpoint| payloads::decode(checkpoint, job.execution_id)).transpose()?;
+    Ok(job)
+}
 
 /// Enqueue a new job for the given execution.
 ///
@@ -18,49 +43,308 @@ pub async fn enqueue_job(
     execution_id: Uuid,
     workflow_id: Uuid,
     payload: serde_json::Value,
+) -> Result<JobRow, DbError> {
+    enqueue_job_at(pool, execution_id, workflow_id, payload, Utc::now()).await
+}
+
+/// Enqueue a job that workers will not pick up before `run_at`.
+///
+/// The job takes the priority, queue, required tags, and attempt limit of
+/// its execution.
+pub async fn enqueue_job_at(
+    pool: &PgPool,
+    execution_id: Uuid,
+    workflow_id: Uuid,
+    payload: serde_json::Value,
+    run_at: DateTime<Utc>,
+) -> Result<JobRow, DbError> {
+    insert_job(pool, execution_id, workflow_id, payload, None, run_at).await
+}
+
+/// Enqueue a job that continues an execution from `checkpoint` — saved
+/// progress, which the worker takes from the job's `checkpoint` column and
+/// never from its payload.  `input` is the execution's trigger input.
+pub async fn enqueue_resume_job(
+    pool: &PgPool,
+    execution_id: Uuid,
+    workflow_id: Uuid,
+    input: serde_json::Value,
+    checkpoint: serde_json::Value,
+    run_at: DateTime<Utc>,
+) -> Result<JobRow, DbError> {
+    insert_job(pool, execution_id, workflow_id, input, Some(checkpoint), run_at).await
+}
+
+async fn insert_job(
+    pool: &PgPool,
+    execution_id: Uuid,
+    workflow_id: Uuid,
+    payload: serde_json::Value,
+    checkpoint: Option<serde_json::Value>,
+    run_at: DateTime<Utc>,
 ) -> Result<JobRow, DbError> {
     let id = Uuid::new_v4();
     let now = Utc::now();
+    let stored = payloads::encode(&payload, execution_id);
+    let stored_checkpoint = checkpoint.as_ref().map(|checkpoint| {
+        payloads::encode(checkpoint, execution_id).unwrap_or_else(|| checkpoint.clone())
+    });
 
-    let row = sqlx::query_as!(
+    let mut row = sqlx::query_as!(
         JobRow,
         r#"
         INSERT INTO job_queue
-            (id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at)
-        VALUES ($1, $2, $3, 'pending', 0, 3, $4, $5, $5)
-        RETURNING id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at
+            (id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at, run_at,
+             priority, queue, required_tags, project_id, checkpoint)
+        VALUES ($1, $2, $3, 'pending', 0,
+                COALESCE((SELECT max_attempts FROM workflow_executions WHERE id = $2), $7),
+                $4, $5, $5, $6,
+                COALESCE((SELECT priority FROM workflow_executions WHERE id = $2), 0),
+                COALESCE((SELECT queue FROM workflow_executions WHERE id = $2), 'default'),
+                COALESCE((SELECT required_tags FROM workflow_executions WHERE id = $2), '{}'),
+                (SELECT project_id FROM workflows WHERE id = $3),
+                $8)
+        RETURNING id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at, run_at, debounce_key, priority, queue, locked_until, required_tags, checkpoint
         "#,
         id,
         execution_id,
         workflow_id,
-        payload,
+        stored.as_ref().unwrap_or(&payload),
         now,
+        run_at,
+        DEFAULT_MAX_ATTEMPTS,
+        stored_checkpoint,
     )
     .fetch_one(pool)
     .await?;
+    row.payload = payload;
+    row.checkpoint = checkpoint;
 
+    if row.run_at <= now {
+        announce(pool, &row.queue).await;
+    }
     Ok(row)
 }
 
-/// Atomically fetch the oldest pending job and mark it as `processing`.
+/// Enqueue a job that collapses with the pending job sharing its `debounce_key`.
 ///
-/// Uses `SELECT … FOR UPDATE SKIP LOCKED` so multiple workers can poll
-/// safely without stepping on each other.
+/// If the workflow already has a pending job with `debounce_key`, that job's
+/// payload is replaced by `payload` — or, with `merge`, shallow-merged with
+/// it (top-level keys of `payload` win; when both carry an object `body`,
+/// as webhook requests do, the bodies are merged the same way; see
+/// [`merge_payloads`]) — and its
+/// `run_at` is pushed back
+/// to `run_at`.  Otherwise a new execution and a job due at `run_at` are
+/// created (tagged with `meta`).  Returns the job and whether it was newly
+/// created.
 ///
-/// Returns `None` if no pending jobs exist.
-pub async fn fetch_next_job(pool: &PgPool) -> Result<Option<JobRow>, DbError> {
+/// With a `limit`, nothing happens — `None` — once the workflow started
+/// `limit.max` executions since `limit.since`, counted under the same
+/// admission lock as
+/// [`create_execution_within`](executions::create_execution_within).
+#[allow(clippy::too_many_arguments)]
+pub async fn enqueue_debounced_job(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    debounce_key: &str,
+    payload: serde_json::Value,
+    run_at: DateTime<Utc>,
+    merge: bool,
+    meta: &ExecutionMeta,
+    limit: Option<StartLimit>,
+) -> Result<Option<(JobRow, bool)>, DbError> {
+    loop {
+        let mut tx = pool.begin().await?;
+        let now = Utc::now();
+
+        if let Some(limit) = limit {
+            if !executions::within_limit(&mut tx, workflow_id, limit).await? {
+                tx.rollback().await?;
+                return Ok(None);
+            }
+        }
+
+        // Merged in Rust rather than SQL, which cannot see into encrypted
+        // payloads.
+        let pending = sqlx::query!(
+            r#"
+            SELECT id, execution_id, payload
+            FROM job_queue
+            WHERE workflow_id = $1 AND debounce_key = $2 AND status = 'pending'
+            FOR UPDATE
+            "#,
+            workflow_id,
+            debounce_key,
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(pending) = pending {
+            let payload = if merge { merge_payloads(payloads::decode(pending.payload, pending.execution_id)?, payload) } else { payload };
+            let stored = payloads::encode(&payload, pending.execution_id);
+            let mut job = sqlx::query_as!(
+                JobRow,
+                r#"
+                UPDATE job_queue
+                SET payload = $1, run_at = $2, updated_at = $3
+                WHERE id = $4
+                RETURNING id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at, run_at, debounce_key, priority, queue, locked_until, required_tags, checkpoint
+                "#,
+                stored.as_ref().unwrap_or(&payload),
+                run_at,
+                now,
+                pending.id,
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            job.payload = payload;
+            return Ok(Some((job, false)));
+        }
+
+        let execution_id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            INSERT INTO workflow_executions
+                (id, workflow_id, status, started_at, business_key, labels, priority, queue, parent_execution_id,
+                 required_tags, max_attempts, workflow_version, project_id, params)
+            VALUES ($1, $2, 'pending', $3, $4, $5, $6, COALESCE($7, 'default'), $8, $9, $10,
+                    (SELECT version FROM workflows WHERE id = $2),
+                    (SELECT project_id FROM workflows WHERE id = $2), $11)
+            "#,
+            execution_id,
+            workflow_id,
+            now,
+            meta.business_key.as_deref(),
+            &meta.labels,
+            meta.priority,
+            meta.queue.as_deref(),
+            meta.parent_execution_id,
+            &meta.required_tags,
+            meta.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS),
+            serde_json::Value::Object(meta.params.clone()),
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let stored = payloads::encode(&payload, execution_id);
+        let inserted = sqlx::query_as!(
+            JobRow,
+            r#"
+            INSERT INTO job_queue
+                (id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at, run_at, debounce_key, priority, queue,
+                 required_tags, project_id)
+            VALUES ($1, $2, $3, 'pending', 0, $11, $4, $5, $5, $6, $7, $8, COALESCE($9, 'default'), $10,
+                    (SELECT project_id FROM workflows WHERE id = $3))
+            RETURNING id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at, run_at, debounce_key, priority, queue, locked_until, required_tags, checkpoint
+            "#,
+            Uuid::new_v4(),
+            execution_id,
+            workflow_id,
+            stored.as_ref().unwrap_or(&payload),
+            now,
+            run_at,
+            debounce_key,
+            meta.priority,
+            meta.queue.as_deref(),
+            &meta.required_tags,
+            meta.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS),
+        )
+        .fetch_one(&mut *tx)
+        .await;
+
+        match inserted {
+            Ok(mut job) => {
+                tx.commit().await?;
+                job.payload = payload;
+                executions::publish_status(pool, execution_id, workflow_id, "pending").await;
+                return Ok(Some((job, true)));
+            }
+            // A concurrent request created the pending job first — fold
+            // into it instead.
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                tx.rollback().await?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// A pending debounced payload `old` with `new` merged in: `old || new`,
+/// and when both carry an object `body`, the bodies merged the same way.
+fn merge_payloads(old: Value, new: Value) -> Value {
+    let body = match (old.get("body"), new.get("body")) {
+        (Some(old @ Value::Object(_)), Some(new @ Value::Object(_))) => Some(concat(old.clone(), new.clone())),
+        _ => None,
+    };
+    let mut merged = concat(old, new);
+    if let (Some(body), Value::Object(fields)) = (body, &mut merged) {
+        fields.insert("body".to_owned(), body);
+    }
+    merged
+}
+
+/// `left || right` as Postgres computes it for JSONB: objects are merged,
+/// right-hand keys winning; anything else is concatenated as arrays.
+fn concat(left: Value, right: Value) -> Value {
+    let items = |value| match value {
+        Value::Array(items) => items,
+        other => vec![other],
+    };
+    match (left, right) {
+        (Value::Object(mut left), Value::Object(right)) => {
+            left.extend(right);
+            Value::Object(left)
+        }
+        (left, right) => Value::Array(items(left).into_iter().chain(items(right)).collect()),
+    }
+}
+
+/// How long a claimed job stays locked without a heartbeat, unless the
+/// claim says otherwise.
+pub const DEFAULT_LEASE: Duration = Duration::seconds(60);
+
+/// Atomically fetch the highest-priority due pending job (oldest first
+/// within a priority) and mark it as `processing`.
+///
+/// Only jobs on one of `queues` are considered; an empty slice means every
+/// queue.  Jobs that require capability tags are left to tagged workers
+/// (see [`claim_next_job`]).  Uses `SELECT … FOR UPDATE SKIP LOCKED` so multiple workers can
+/// poll safely without stepping on each other.
+///
+/// Returns `None` if no pending job is due.
+/// The job is leased for [`DEFAULT_LEASE`]; see [`claim_next_job`].
+pub async fn fetch_next_job(pool: &PgPool, queues: &[String]) -> Result<Option<JobRow>, DbError> {
+    claim_next_job(pool, queues, &[], Utc::now() + DEFAULT_LEASE).await
+}
+
+/// Like [`fetch_next_job`] for a worker with the capability `tags`: only
+/// jobs whose required tags are all among them are considered.  The job is
+/// leased until `locked_until`.  Keep
+/// the lease with [`heartbeat_job`] while the job runs; once it expires,
+/// [`reclaim_expired_jobs`] hands the job to another worker.
+pub async fn claim_next_job(
+    pool: &PgPool,
+    queues: &[String],
+    tags: &[String],
+    locked_until: DateTime<Utc>,
+) -> Result<Option<JobRow>, DbError> {
     let mut tx = pool.begin().await?;
 
     let row = sqlx::query_as!(
         JobRow,
         r#"
-        SELECT id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at
+        SELECT id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at, run_at, debounce_key, priority, queue, locked_until, required_tags, checkpoint
         FROM job_queue
-        WHERE status = 'pending'
-        ORDER BY created_at ASC
+        WHERE status = 'pending' AND run_at <= NOW()
+          AND (cardinality($1::text[]) = 0 OR queue = ANY($1))
+          AND required_tags <@ $2::text[]
+        ORDER BY priority DESC, run_at ASC
         LIMIT 1
         FOR UPDATE SKIP LOCKED
         "#,
+        queues,
+        tags,
     )
     .fetch_optional(&mut *tx)
     .await?;
@@ -70,10 +354,11 @@ pub async fn fetch_next_job(pool: &PgPool) -> Result<Option<JobRow>, DbError> {
         sqlx::query!(
             r#"
             UPDATE job_queue
-            SET status = 'processing', attempts = attempts + 1, updated_at = $1
-            WHERE id = $2
+            SET status = 'processing', attempts = attempts + 1, updated_at = $1, locked_until = $2
+            WHERE id = $3
             "#,
             now,
+            locked_until,
             job.id,
         )
         .execute(&mut *tx)
@@ -83,13 +368,166 @@ pub async fn fetch_next_job(pool: &PgPool) -> Result<Option<JobRow>, DbError> {
         tx.rollback().await?;
     }
 
-    Ok(row)
+    row.map(decoded).transpose()
+}
+
+/// Claim up to `n` due pending jobs in one statement, leased for
+/// [`DEFAULT_LEASE`]: the batch form of [`fetch_next_job`], for workers
+/// with many free slots.  Returns the claimed jobs, already `processing`,
+/// highest priority first.
+pub async fn fetch_next_jobs(pool: &PgPool, queues: &[String], n: i64) -> Result<Vec<JobRow>, DbError> {
+    claim_next_jobs(pool, queues, &[], n, Utc::now() + DEFAULT_LEASE).await
+}
+
+/// Like [`fetch_next_jobs`] for a worker with the capability `tags` (see
+/// [`claim_next_job`]), leasing the jobs until `locked_until`.
+pub async fn claim_next_jobs(
+    pool: &PgPool,
+    queues: &[String],
+    tags: &[String],
+    n: i64,
+    locked_until: DateTime<Utc>,
+) -> Result<Vec<JobRow>, DbError> {
+    let mut rows = sqlx::query_as!(
+        JobRow,
+        r#"
+        UPDATE job_queue
+        SET status = 'processing', attempts = attempts + 1, updated_at = NOW(), locked_until = $4
+        WHERE id IN (
+            SELECT id
+            FROM job_queue
+            WHERE status = 'pending' AND run_at <= NOW()
+              AND (cardinality($1::text[]) = 0 OR queue = ANY($1))
+              AND required_tags <@ $2::text[]
+            ORDER BY priority DESC, run_at ASC
+            LIMIT $3
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at, run_at, debounce_key, priority, queue, locked_until, required_tags, checkpoint
+        "#,
+        queues,
+        tags,
+        n.max(0),
+        locked_until,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.run_at.cmp(&b.run_at)));
+    rows.into_iter().map(decoded).collect()
+}
+
+/// Make an execution's delayed pending jobs due now, e.g. to resume an
+/// execution that was waiting for an external event.  Returns the released
+/// jobs.
+pub async fn release_delayed_jobs(pool: &PgPool, execution_id: Uuid) -> Result<Vec<JobRow>, DbError> {
+    let now = Utc::now();
+    let rows = sqlx::query_as!(
+        JobRow,
+        r#"
+        UPDATE job_queue
+        SET run_at = $1, updated_at = $1
+        WHERE execution_id = $2 AND status = 'pending' AND run_at > $1
+        RETURNING id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at, run_at, debounce_key, priority, queue, locked_until, required_tags, checkpoint
+        "#,
+        now,
+        execution_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if let Some(job) = rows.first() {
+        announce(pool, &job.queue).await;
+    }
+    rows.into_iter().map(decoded).collect()
+}
+
+/// A job by id, if it exists.
+pub async fn get_job(pool: &PgPool, job_id: Uuid) -> Result<Option<JobRow>, DbError> {
+    let row = sqlx::query_as!(
+        JobRow,
+        r#"
+        SELECT id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at, run_at, debounce_key, priority, queue, locked_until, required_tags, checkpoint
+        FROM job_queue
+        WHERE id = $1
+        "#,
+        job_id,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(decoded).transpose()
+}
+
+/// Claim job `job_id`, leasing it until `locked_until`, if it is pending
+/// and due; `None` otherwise.  For queue backends that pick the job
+/// elsewhere (see the `queue` crate).
+pub async fn claim_job(
+    pool: &PgPool,
+    job_id: Uuid,
+    locked_until: DateTime<Utc>,
+) -> Result<Option<JobRow>, DbError> {
+    let now = Utc::now();
+    let row = sqlx::query_as!(
+        JobRow,
+        r#"
+        UPDATE job_queue
+        SET status = 'processing', attempts = attempts + 1, updated_at = $1, locked_until = $2
+        WHERE id = $3 AND status = 'pending' AND run_at <= $1
+        RETURNING id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at, run_at, debounce_key, priority, queue, locked_until, required_tags, checkpoint
+        "#,
+        now,
+        locked_until,
+        job_id,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(decoded).transpose()
+}
+
+/// Every pending job, due or not, oldest first.
+pub async fn list_pending_jobs(pool: &PgPool) -> Result<Vec<JobRow>, DbError> {
+    let rows = sqlx::query_as!(
+        JobRow,
+        r#"
+        SELECT id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at, run_at, debounce_key, priority, queue, locked_until, required_tags, checkpoint
+        FROM job_queue
+        WHERE status = 'pending'
+        ORDER BY run_at ASC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter().map(decoded).collect()
+}
+
+/// Every job ever queued for an execution, oldest first.
+pub async fn list_jobs_for_execution(
+    pool: &PgPool,
+    execution_id: Uuid,
+) -> Result<Vec<JobRow>, DbError> {
+    let rows = sqlx::query_as!(
+        JobRow,
+        r#"
+        SELECT id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at, run_at, debounce_key, priority, queue, locked_until, required_tags, checkpoint
+        FROM job_queue
+        WHERE execution_id = $1
+        ORDER BY created_at ASC
+        "#,
+        execution_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter().map(decoded).collect()
 }
 
 /// Mark a job as completed.
 pub async fn complete_job(pool: &PgPool, job_id: Uuid) -> Result<(), DbError> {
     sqlx::query!(
-        "UPDATE job_queue SET status = 'completed', updated_at = $1 WHERE id = $2",
+        "UPDATE job_queue SET status = 'completed', updated_at = $1, locked_until = NULL WHERE id = $2",
         Utc::now(),
         job_id,
     )
@@ -98,20 +536,149 @@ pub async fn complete_job(pool: &PgPool, job_id: Uuid) -> Result<(), DbError> {
     Ok(())
 }
 
-/// Mark a job as failed (or dead-lettered when `max_attempts` is reached).
-pub async fn fail_job(pool: &PgPool, job_id: Uuid, max_attempts: i32) -> Result<(), DbError> {
+/// Mark a job as failed: pending again from `retry_at` on, or
+/// dead-lettered when `max_attempts` is reached.
+pub async fn fail_job(
+    pool: &PgPool,
+    job_id: Uuid,
+    max_attempts: i32,
+    retry_at: DateTime<Utc>,
+) -> Result<(), DbError> {
     sqlx::query!(
         r#"
         UPDATE job_queue
         SET status = CASE WHEN attempts >= $1 THEN 'dead_lettered' ELSE 'pending' END,
-            updated_at = $2
+            run_at = CASE WHEN attempts >= $1 THEN run_at ELSE $4 END,
+            updated_at = $2,
+            locked_until = NULL
         WHERE id = $3
         "#,
         max_attempts,
         Utc::now(),
         job_id,
+        retry_at,
     )
     .execute(pool)
     .await?;
     Ok(())
 }
+
+/// Put a `processing` job back to `pending` without counting the attempt,
+/// e.g. when a worker shuts down before finishing it.
+pub async fn release_job(pool: &PgPool, job_id: Uuid) -> Result<(), DbError> {
+    let queue = sqlx::query_scalar!(
+        r#"
+        UPDATE job_queue
+        SET status = 'pending', attempts = GREATEST(attempts - 1, 0), updated_at = $1, locked_until = NULL
+        WHERE id = $2 AND status = 'processing'
+        RETURNING queue
+        "#,
+        Utc::now(),
+        job_id,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(queue) = queue {
+        announce(pool, &queue).await;
+    }
+    Ok(())
+}
+
+/// When the next pending job on one of `queues` (every queue when empty)
+/// that a worker with the capability `tags` may claim is due, if there is
+/// one.
+pub async fn next_due_at(
+    pool: &PgPool,
+    queues: &[String],
+    tags: &[String],
+) -> Result<Option<DateTime<Utc>>, DbError> {
+    let next = sqlx::query_scalar!(
+        r#"
+        SELECT MIN(run_at)
+        FROM job_queue
+        WHERE status = 'pending'
+          AND (cardinality($1::text[]) = 0 OR queue = ANY($1))
+          AND required_tags <@ $2::text[]
+        "#,
+        queues,
+        tags,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(next)
+}
+
+/// Backlog and throughput of every queue that has pending, running, or
+/// dead-lettered jobs or completed one in the last hour, by queue name;
+/// only jobs of project `project_id` count when it is given.
+pub async fn queue_stats(pool: &PgPool, project_id: Option<Uuid>) -> Result<Vec<QueueStatsRow>, DbError> {
+    let rows = sqlx::query_as!(
+        QueueStatsRow,
+        r#"
+        SELECT queue AS "queue!",
+               COUNT(*) FILTER (WHERE status = 'pending') AS "pending!",
+               COUNT(*) FILTER (WHERE status = 'pending' AND run_at <= NOW()) AS "due!",
+               COUNT(*) FILTER (WHERE status = 'processing') AS "processing!",
+               COUNT(*) FILTER (WHERE status = 'dead_lettered') AS "dead_lettered!",
+               EXTRACT(EPOCH FROM NOW() - MIN(run_at) FILTER (WHERE status = 'pending' AND run_at <= NOW()))::float8
+                   AS oldest_due_secs,
+               COUNT(*) FILTER (WHERE status = 'completed' AND updated_at > NOW() - INTERVAL '1 minute')
+                   AS "completed_last_minute!",
+               COUNT(*) FILTER (WHERE status = 'completed' AND updated_at > NOW() - INTERVAL '1 hour')
+                   AS "completed_last_hour!"
+        FROM job_queue
+        WHERE (status IN ('pending', 'processing', 'dead_lettered')
+               OR (status = 'completed' AND updated_at > NOW() - INTERVAL '1 hour'))
+          AND ($1::uuid IS NULL OR project_id = $1)
+        GROUP BY queue
+        ORDER BY queue
+        "#,
+        project_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Extend the lease on a `processing` job to `locked_until`; returns
+/// `false` when the job is no longer processing (it was settled, or
+/// reclaimed after its lease expired).
+pub async fn heartbeat_job(pool: &PgPool, job_id: Uuid, locked_until: DateTime<Utc>) -> Result<bool, DbError> {
+    let result = sqlx::query!(
+        "UPDATE job_queue SET locked_until = $1 WHERE id = $2 AND status = 'processing'",
+        locked_until,
+        job_id,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Return `processing` jobs whose lease expired to `pending` — their worker
+/// died — or dead-letter them when they are out of attempts.  Returns the
+/// reclaimed jobs.
+pub async fn reclaim_expired_jobs(pool: &PgPool) -> Result<Vec<JobRow>, DbError> {
+    let now = Utc::now();
+    let rows = sqlx::query_as!(
+        JobRow,
+        r#"
+        UPDATE job_queue
+        SET status = CASE WHEN attempts >= max_attempts THEN 'dead_lettered' ELSE 'pending' END,
+            updated_at = $1,
+            locked_until = NULL
+        WHERE status = 'processing' AND locked_until < $1
+        RETURNING id, execution_id, workflow_id, status, attempts, max_attempts, payload, created_at, updated_at, run_at, debounce_key, priority, queue, locked_until, required_tags, checkpoint
+        "#,
+        now,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if let Some(job) = rows.iter().find(|job| job.status == "pending") {
+        announce(pool, &job.queue).await;
+    }
+    rows.into_iter().map(decoded).collect()
+}
@@ -0,0 +1,137 @@
+//! Legal hold flags and their audit trail.
+//!
+//! A hold is a boolean on `workflows` / `workflow_executions`; every change
+//! is written to `legal_hold_events` in the same transaction.
+
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    DbError,
+    models::{HoldTarget, LegalHoldEventRow},
+};
+
+/// Place (`held = true`) or lift a legal hold on a workflow or execution.
+///
+/// Returns the audit entry, or `None` when the record was already in the
+/// requested state (nothing is recorded then).  Returns
+/// `DbError::NotFound` if the record does not exist.
+pub async fn set_legal_hold(
+    pool: &PgPool,
+    target: HoldTarget,
+    target_id: Uuid,
+    held: bool,
+    actor: &str,
+    reason: Option<&str>,
+) -> Result<Option<LegalHoldEventRow>, DbError> {
+    let mut tx = pool.begin().await?;
+
+    let current = match target {
+        HoldTarget::Workflow => {
+            sqlx::query_scalar!(
+                "SELECT legal_hold FROM workflows WHERE id = $1 FOR UPDATE",
+                target_id,
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+        }
+        HoldTarget::Execution => {
+            sqlx::query_scalar!(
+                "SELECT legal_hold FROM workflow_executions WHERE id = $1 FOR UPDATE",
+                target_id,
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+        }
+    }
+    .ok_or(DbError::NotFound)?;
+
+    if current == held {
+        return Ok(None);
+    }
+
+    match target {
+        HoldTarget::Workflow => {
+            sqlx::query!("UPDATE workflows SET legal_hold = $1 WHERE id = $2", held, target_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        HoldTarget::Execution => {
+            sqlx::query!(
+                "UPDATE workflow_executions SET legal_hold = $1 WHERE id = $2",
+                held,
+                target_id,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    let event = sqlx::query_as!(
+        LegalHoldEventRow,
+        r#"
+        INSERT INTO legal_hold_events (id, target_type, target_id, held, reason, actor, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, target_type, target_id, held, reason, actor, created_at
+        "#,
+        Uuid::new_v4(),
+        target.to_string(),
+        target_id,
+        held,
+        reason,
+        actor,
+        Utc::now(),
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(event))
+}
+
+/// Whether a workflow or execution is currently held.
+///
+/// Returns `DbError::NotFound` if the record does not exist.
+pub async fn is_held(pool: &PgPool, target: HoldTarget, target_id: Uuid) -> Result<bool, DbError> {
+    let held = match target {
+        HoldTarget::Workflow => {
+            sqlx::query_scalar!("SELECT legal_hold FROM workflows WHERE id = $1", target_id)
+                .fetch_optional(pool)
+                .await?
+        }
+        HoldTarget::Execution => {
+            sqlx::query_scalar!(
+                "SELECT legal_hold FROM workflow_executions WHERE id = $1",
+                target_id,
+            )
+            .fetch_optional(pool)
+            .await?
+        }
+    };
+
+    held.ok_or(DbError::NotFound)
+}
+
+/// Hold history of one record, newest first.
+pub async fn list_legal_hold_events(
+    pool: &PgPool,
+    target: HoldTarget,
+    target_id: Uuid,
+) -> Result<Vec<LegalHoldEventRow>, DbError> {
+    let rows = sqlx::query_as!(
+        LegalHoldEventRow,
+        r#"
+        SELECT id, target_type, target_id, held, reason, actor, created_at
+        FROM legal_hold_events
+        WHERE target_type = $1 AND target_id = $2
+        ORDER BY created_at DESC
+        "#,
+        target.to_string(),
+        target_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
@@ -0,0 +1,75 @@
+//! Execution log repository functions.
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{ExecutionLogRow, LogFilter, NewLogLine};
+use crate::DbError;
+
+/// Store `lines`, logged by node `node_id` of execution `execution_id`, in
+/// order.
+pub async fn insert_logs(
+    pool: &PgPool,
+    execution_id: Uuid,
+    node_id: &str,
+    lines: &[NewLogLine],
+) -> Result<(), DbError> {
+    if lines.is_empty() {
+        return Ok(());
+    }
+    let levels: Vec<String> = lines.iter().map(|line| line.level.clone()).collect();
+    let messages: Vec<String> = lines.iter().map(|line| line.message.clone()).collect();
+    let fields: Vec<_> = lines.iter().map(|line| line.fields.clone()).collect();
+    let logged_at: Vec<_> = lines.iter().map(|line| line.logged_at).collect();
+    sqlx::query!(
+        r#"
+        INSERT INTO execution_logs (execution_id, node_id, level, message, fields, logged_at)
+        SELECT $1, $2, level, message, fields, logged_at
+        FROM UNNEST($3::text[], $4::text[], $5::jsonb[], $6::timestamptz[]) WITH ORDINALITY
+            AS line (level, message, fields, logged_at, n)
+        ORDER BY n
+        "#,
+        execution_id,
+        node_id,
+        &levels,
+        &messages,
+        &fields,
+        &logged_at,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// The lines of execution `execution_id` matching `filter`, in the order
+/// they were logged, skipping `offset` and returning at most `limit`.
+pub async fn list_logs(
+    pool: &PgPool,
+    execution_id: Uuid,
+    filter: &LogFilter,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<ExecutionLogRow>, DbError> {
+    let rows = sqlx::query_as!(
+        ExecutionLogRow,
+        r#"
+        SELECT id, execution_id, node_id, level, message, fields, logged_at
+        FROM execution_logs
+        WHERE execution_id = $1
+          AND ($2::text IS NULL OR node_id = $2)
+          AND ($3::text[] IS NULL OR level = ANY($3))
+        ORDER BY id
+        LIMIT $4 OFFSET $5
+        "#,
+        execution_id,
+        filter.node_id,
+        filter.levels.as_deref(),
+        limit,
+        offset,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
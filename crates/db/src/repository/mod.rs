@@ -6,3 +6,18 @@
 pub mod workflows;
 pub mod executions;
 pub mod jobs;
+pub mod legal_holds;
+pub mod privacy;
+pub mod state;
+pub mod audit;
+pub mod feature_flags;
+pub mod schedules;
+pub mod captures;
+pub mod users;
+pub mod projects;
+pub mod secrets;
+pub mod credentials;
+pub mod logs;
+pub mod partitions;
+pub mod binary_data;
+pub mod quotas;
@@ -0,0 +1,44 @@
+//! Monthly partitions of `node_executions`.
+//!
+//! The partitions are named `node_executions_pYYYYMM`; the functions doing
+//! the DDL live in the database (migration 029), so they stay next to the
+//! table definition.
+
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::DbError;
+
+/// Create the partition for the month `month` falls in; returns whether it
+/// did not exist yet.
+pub async fn create_node_execution_partition(pool: &PgPool, month: NaiveDate) -> Result<bool, DbError> {
+    let created = sqlx::query_scalar!("SELECT node_execution_partition_create($1)", month)
+        .fetch_one(pool)
+        .await?;
+    Ok(created.unwrap_or(false))
+}
+
+/// Drop the empty monthly partitions that end on or before `before`;
+/// returns how many were dropped.  Partitions still holding rows are kept
+/// until retention has emptied them.
+pub async fn drop_empty_node_execution_partitions(pool: &PgPool, before: NaiveDate) -> Result<u64, DbError> {
+    let dropped = sqlx::query_scalar!("SELECT node_execution_partitions_drop_empty($1)", before)
+        .fetch_one(pool)
+        .await?;
+    Ok(dropped.unwrap_or(0).max(0) as u64)
+}
+
+/// Names of the partitions of `node_executions`, the default one included.
+pub async fn list_node_execution_partitions(pool: &PgPool) -> Result<Vec<String>, DbError> {
+    let names = sqlx::query_scalar!(
+        r#"
+        SELECT c.relname::text AS "name!"
+        FROM pg_inherits i JOIN pg_class c ON c.oid = i.inhrelid
+        WHERE i.inhparent = 'node_executions'::regclass
+        ORDER BY c.relname
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(names)
+}
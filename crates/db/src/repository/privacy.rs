@@ -0,0 +1,199 @@
+//! Lookups and writes for privacy erasure requests.
+//!
+//! Finding what to erase and rewriting the redacted payloads are separate
+//! steps; the redaction itself is domain logic and lives in the engine.
+
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    DbError, payloads,
+    models::{ErasureCandidateRow, PrivacyErasureRow, WorkflowStateRow},
+};
+
+/// Executions tagged with `business_key`.
+pub async fn find_candidates_by_business_key(
+    pool: &PgPool,
+    business_key: &str,
+) -> Result<Vec<ErasureCandidateRow>, DbError> {
+    let rows = sqlx::query_as!(
+        ErasureCandidateRow,
+        r#"
+        SELECT e.id, e.workflow_id, (e.legal_hold OR w.legal_hold) AS "legal_hold!"
+        FROM workflow_executions e
+        JOIN workflows w ON w.id = e.workflow_id
+        WHERE e.business_key = $1
+        ORDER BY e.started_at ASC
+        "#,
+        business_key,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Executions with a node input/output or job payload that contains
+/// `document` (JSONB `@>`), and those with compressed, encrypted, or
+/// escaped ones, which SQL cannot look into: callers check those against
+/// the decoded payloads.
+pub async fn find_candidates_containing(
+    pool: &PgPool,
+    document: serde_json::Value,
+) -> Result<Vec<ErasureCandidateRow>, DbError> {
+    let rows = sqlx::query_as!(
+        ErasureCandidateRow,
+        r#"
+        SELECT e.id, e.workflow_id, (e.legal_hold OR w.legal_hold) AS "legal_hold!"
+        FROM workflow_executions e
+        JOIN workflows w ON w.id = e.workflow_id
+        WHERE EXISTS (
+                  SELECT 1 FROM node_executions n
+                  WHERE n.execution_id = e.id
+                    AND (n.input @> $1 OR n.output @> $1
+                         OR n.input ?| ARRAY['$sealed', '$zstd', '$plain']
+                         OR n.output ?| ARRAY['$sealed', '$zstd', '$plain'])
+              )
+           OR EXISTS (
+                  SELECT 1 FROM job_queue j
+                  WHERE j.execution_id = e.id AND (j.payload @> $1 OR j.payload ?| ARRAY['$sealed', '$zstd', '$plain'])
+              )
+        ORDER BY e.started_at ASC
+        "#,
+        document,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Rewrite an execution's stored payloads in one transaction.
+///
+/// `nodes` holds `(node_execution_id, input, output)` and `jobs` holds
+/// `(job_id, payload)`; with `clear_business_key` the execution's business
+/// key is removed as well.  Node hashes are cleared with the payloads they
+/// were computed from.  The payloads are compressed and encrypted again as
+/// [`payloads`] are written.
+pub async fn redact_execution(
+    pool: &PgPool,
+    execution_id: Uuid,
+    nodes: &[(Uuid, serde_json::Value, Option<serde_json::Value>)],
+    jobs: &[(Uuid, serde_json::Value)],
+    clear_business_key: bool,
+) -> Result<(), DbError> {
+    let mut tx = pool.begin().await?;
+
+    for (id, input, output) in nodes {
+        let stored_input = payloads::encode(input, execution_id);
+        let stored_output = output.as_ref().and_then(|output| payloads::encode(output, execution_id));
+        sqlx::query!(
+            r#"
+            UPDATE node_executions
+            SET input = $1, output = $2, input_hash = NULL, output_hash = NULL
+            WHERE id = $3 AND execution_id = $4
+            "#,
+            stored_input.as_ref().unwrap_or(input),
+            stored_output.as_ref().or(output.as_ref()),
+            id,
+            execution_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    for (id, payload) in jobs {
+        let stored = payloads::encode(payload, execution_id);
+        sqlx::query!(
+            "UPDATE job_queue SET payload = $1, updated_at = $2 WHERE id = $3 AND execution_id = $4",
+            stored.as_ref().unwrap_or(payload),
+            Utc::now(),
+            id,
+            execution_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    if clear_business_key {
+        sqlx::query!(
+            "UPDATE workflow_executions SET business_key = NULL WHERE id = $1",
+            execution_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// State entries of workflows not under legal hold whose value contains the
+/// scalar `needle` anywhere.
+pub async fn find_state_containing(
+    pool: &PgPool,
+    needle: serde_json::Value,
+) -> Result<Vec<WorkflowStateRow>, DbError> {
+    let rows = sqlx::query_as!(
+        WorkflowStateRow,
+        r#"
+        SELECT s.workflow_id, s.key, s.value, s.updated_at
+        FROM workflow_state s
+        JOIN workflows w ON w.id = s.workflow_id
+        WHERE NOT w.legal_hold
+          AND s.value IS NOT NULL
+          AND jsonb_path_exists(s.value, '$.** ? (@ == $v)', jsonb_build_object('v', $1::jsonb))
+        "#,
+        needle,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Overwrite a state entry's value.
+pub async fn redact_state(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    key: &str,
+    value: serde_json::Value,
+) -> Result<(), DbError> {
+    sqlx::query!(
+        "UPDATE workflow_state SET value = $1, updated_at = $2 WHERE workflow_id = $3 AND key = $4",
+        value,
+        Utc::now(),
+        workflow_id,
+        key,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Log a completed erasure request.
+pub async fn record_erasure(
+    pool: &PgPool,
+    actor: &str,
+    criteria_type: &str,
+    report: serde_json::Value,
+) -> Result<PrivacyErasureRow, DbError> {
+    let row = sqlx::query_as!(
+        PrivacyErasureRow,
+        r#"
+        INSERT INTO privacy_erasures (id, actor, criteria_type, report, created_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, actor, criteria_type, report, created_at
+        "#,
+        Uuid::new_v4(),
+        actor,
+        criteria_type,
+        report,
+        Utc::now(),
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row)
+}
@@ -0,0 +1,46 @@
+//! Project repository functions.
+
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{DbError, models::ProjectRow};
+
+/// Every project, by name.
+pub async fn list_projects(pool: &PgPool) -> Result<Vec<ProjectRow>, DbError> {
+    let rows = sqlx::query_as!(ProjectRow, "SELECT id, name, created_at FROM projects ORDER BY name")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
+/// The project called `name`.
+///
+/// Returns `DbError::NotFound` if there is none.
+pub async fn get_project_by_name(pool: &PgPool, name: &str) -> Result<ProjectRow, DbError> {
+    sqlx::query_as!(ProjectRow, "SELECT id, name, created_at FROM projects WHERE name = $1", name)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(DbError::NotFound)
+}
+
+/// Create a project; `None` when the name is taken.
+pub async fn create_project(pool: &PgPool, name: &str) -> Result<Option<ProjectRow>, DbError> {
+    let row = sqlx::query_as!(
+        ProjectRow,
+        r#"
+        INSERT INTO projects (id, name, created_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (name) DO NOTHING
+        RETURNING id, name, created_at
+        "#,
+        Uuid::new_v4(),
+        name,
+        Utc::now(),
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
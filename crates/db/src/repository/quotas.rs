@@ -0,0 +1,109 @@
+//! Quota settings of projects, and the usage they are checked against.
+//!
+//! Usage is counted from `workflow_executions` and the `node_daily_stats`
+//! rollup; nothing is kept just for quotas.
+
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{DbError, models::QuotaUsageRow};
+
+/// The quota of the project called `name`, if it has one.
+///
+/// Returns `DbError::NotFound` if there is no such project.
+pub async fn project_quota(pool: &PgPool, name: &str) -> Result<Option<serde_json::Value>, DbError> {
+    sqlx::query_scalar!("SELECT quota FROM projects WHERE name = $1", name)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(DbError::NotFound)
+}
+
+/// The project owning workflow `workflow_id` and its quota; `None` when it
+/// has none or there is no such workflow.
+pub async fn workflow_project_quota(
+    pool: &PgPool,
+    workflow_id: Uuid,
+) -> Result<Option<(Uuid, serde_json::Value)>, DbError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT p.id, p.quota AS "quota!"
+        FROM projects p
+        JOIN workflows w ON w.project_id = p.id
+        WHERE w.id = $1 AND p.quota IS NOT NULL
+        "#,
+        workflow_id,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| (row.id, row.quota)))
+}
+
+/// Replace the quota of the project called `name`, returning its id;
+/// `None` removes it.
+///
+/// Returns `DbError::NotFound` if there is no such project.
+pub async fn set_project_quota(pool: &PgPool, name: &str, quota: Option<serde_json::Value>) -> Result<Uuid, DbError> {
+    sqlx::query_scalar!("UPDATE projects SET quota = $1 WHERE name = $2 RETURNING id", quota, name)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(DbError::NotFound)
+}
+
+/// What workflow `workflow_id`'s executions used as of `now`.
+pub async fn workflow_usage(pool: &PgPool, workflow_id: Uuid, now: DateTime<Utc>) -> Result<QuotaUsageRow, DbError> {
+    let (hour_ago, midnight) = windows(now);
+    let row = sqlx::query_as!(
+        QuotaUsageRow,
+        r#"
+        SELECT COUNT(*) FILTER (WHERE started_at >= $2) AS "executions_last_hour!",
+               COUNT(*) FILTER (WHERE started_at >= $3) AS "executions_today!",
+               (SELECT COALESCE(SUM(total_ms), 0)::bigint
+                FROM node_daily_stats
+                WHERE workflow_id = $1 AND day = $4) AS "node_runtime_ms_today!"
+        FROM workflow_executions
+        WHERE workflow_id = $1 AND started_at >= LEAST($2, $3)
+        "#,
+        workflow_id,
+        hour_ago,
+        midnight,
+        now.date_naive(),
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// What the executions of project `project_id`'s workflows used as of
+/// `now`.
+pub async fn project_usage(pool: &PgPool, project_id: Uuid, now: DateTime<Utc>) -> Result<QuotaUsageRow, DbError> {
+    let (hour_ago, midnight) = windows(now);
+    let row = sqlx::query_as!(
+        QuotaUsageRow,
+        r#"
+        SELECT COUNT(*) FILTER (WHERE started_at >= $2) AS "executions_last_hour!",
+               COUNT(*) FILTER (WHERE started_at >= $3) AS "executions_today!",
+               (SELECT COALESCE(SUM(s.total_ms), 0)::bigint
+                FROM node_daily_stats s
+                JOIN workflows w ON w.id = s.workflow_id
+                WHERE w.project_id = $1 AND s.day = $4) AS "node_runtime_ms_today!"
+        FROM workflow_executions
+        WHERE project_id = $1 AND started_at >= LEAST($2, $3)
+        "#,
+        project_id,
+        hour_ago,
+        midnight,
+        now.date_naive(),
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Start of the last hour and of the (UTC) day before `now`.
+fn windows(now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    (now - Duration::hours(1), now.date_naive().and_time(NaiveTime::MIN).and_utc())
+}
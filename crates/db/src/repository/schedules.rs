@@ -0,0 +1,122 @@
+//! Cron schedule repository functions.
+//!
+//! Several schedulers may run against the same database, so a due run is
+//! claimed with [`advance_schedule`], a compare-and-set on `next_run_at`:
+//! only the scheduler whose update matches enqueues it.
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{DbError, models::CronScheduleRow};
+
+/// Every schedule, soonest first.
+pub async fn list_schedules(pool: &PgPool) -> Result<Vec<CronScheduleRow>, DbError> {
+    let rows = sqlx::query_as!(
+        CronScheduleRow,
+        r#"
+        SELECT workflow_id, expression, timezone, next_run_at, last_run_at, updated_at
+        FROM cron_schedules
+        ORDER BY next_run_at ASC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Schedules due at or before `until`, soonest first.
+pub async fn list_due_schedules(
+    pool: &PgPool,
+    until: DateTime<Utc>,
+) -> Result<Vec<CronScheduleRow>, DbError> {
+    let rows = sqlx::query_as!(
+        CronScheduleRow,
+        r#"
+        SELECT workflow_id, expression, timezone, next_run_at, last_run_at, updated_at
+        FROM cron_schedules
+        WHERE next_run_at <= $1
+        ORDER BY next_run_at ASC
+        "#,
+        until,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Create the schedule of `workflow_id`, or reset it to `next_run_at` when
+/// its expression or timezone changed.  An unchanged schedule keeps its persisted next
+/// run; returns whether anything was written.
+pub async fn upsert_schedule(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    expression: &str,
+    timezone: &str,
+    next_run_at: DateTime<Utc>,
+) -> Result<bool, DbError> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO cron_schedules (workflow_id, expression, timezone, next_run_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (workflow_id) DO UPDATE
+            SET expression = EXCLUDED.expression,
+                timezone = EXCLUDED.timezone,
+                next_run_at = EXCLUDED.next_run_at,
+                updated_at = EXCLUDED.updated_at
+            WHERE cron_schedules.expression <> EXCLUDED.expression
+               OR cron_schedules.timezone <> EXCLUDED.timezone
+        "#,
+        workflow_id,
+        expression,
+        timezone,
+        next_run_at,
+        Utc::now(),
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Claim the run due at `due` and move the schedule on to `next`.
+///
+/// Returns `false` when the schedule no longer has `due` as its next run —
+/// another scheduler claimed it, or the expression was changed meanwhile.
+pub async fn advance_schedule(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    due: DateTime<Utc>,
+    next: DateTime<Utc>,
+) -> Result<bool, DbError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE cron_schedules
+        SET next_run_at = $3, last_run_at = $2, updated_at = $4
+        WHERE workflow_id = $1 AND next_run_at = $2
+        "#,
+        workflow_id,
+        due,
+        next,
+        Utc::now(),
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete the schedules of every workflow not in `keep`; returns how many
+/// were deleted.
+pub async fn delete_schedules_except(pool: &PgPool, keep: &[Uuid]) -> Result<u64, DbError> {
+    let result = sqlx::query!(
+        "DELETE FROM cron_schedules WHERE NOT (workflow_id = ANY($1))",
+        keep,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,297 @@
+//! Secret repository functions: a workflow's own secrets, and its
+//! project's, which every workflow of the project reads.
+//!
+//! Values are stored as given — encrypting them is up to the caller.
+
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{DbError, models::SecretRow};
+
+/// Every secret of workflow `workflow_id` in every environment, by key,
+/// each key's default first.
+pub async fn list_secrets(pool: &PgPool, workflow_id: Uuid) -> Result<Vec<SecretRow>, DbError> {
+    let rows = sqlx::query_as!(
+        SecretRow,
+        r#"
+        SELECT id, project_id, workflow_id, key, environment, encrypted_value, created_at, updated_at
+        FROM secrets
+        WHERE workflow_id = $1
+        ORDER BY key, environment
+        "#,
+        workflow_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// The secrets of workflow `workflow_id` a worker in `environment` sees,
+/// one per key: the workflow's own for that environment or else its
+/// default, or failing both the project's, likewise.  Without an
+/// environment, only defaults count.
+pub async fn secrets_for_environment(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    environment: Option<&str>,
+) -> Result<Vec<SecretRow>, DbError> {
+    let rows = sqlx::query_as!(
+        SecretRow,
+        r#"
+        SELECT DISTINCT ON (key) id, project_id, workflow_id, key, environment, encrypted_value, created_at, updated_at
+        FROM secrets
+        WHERE (workflow_id = $1 OR (workflow_id IS NULL AND project_id = (SELECT project_id FROM workflows WHERE id = $1)))
+          AND (environment = '' OR environment = $2)
+        ORDER BY key, workflow_id IS NULL, environment = ''
+        "#,
+        workflow_id,
+        environment,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Add secret `key` to workflow `workflow_id` (in the workflow's project),
+/// for `environment` or, with `""`, as the default; `None` when the
+/// workflow already has it.
+pub async fn create_secret(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    key: &str,
+    environment: &str,
+    encrypted_value: &str,
+) -> Result<Option<SecretRow>, DbError> {
+    let now = Utc::now();
+    let row = sqlx::query_as!(
+        SecretRow,
+        r#"
+        INSERT INTO secrets (id, workflow_id, key, environment, encrypted_value, created_at, updated_at, project_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $6, (SELECT project_id FROM workflows WHERE id = $2))
+        ON CONFLICT (workflow_id, key, environment) DO NOTHING
+        RETURNING id, project_id, workflow_id, key, environment, encrypted_value, created_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        workflow_id,
+        key,
+        environment,
+        encrypted_value,
+        now,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Replace the value of secret `key` of workflow `workflow_id` in
+/// `environment` (`""` for the default).
+///
+/// Returns `DbError::NotFound` if the workflow has no such secret.
+pub async fn update_secret(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    key: &str,
+    environment: &str,
+    encrypted_value: &str,
+) -> Result<SecretRow, DbError> {
+    sqlx::query_as!(
+        SecretRow,
+        r#"
+        UPDATE secrets SET encrypted_value = $4, updated_at = $5
+        WHERE workflow_id = $1 AND key = $2 AND environment = $3
+        RETURNING id, project_id, workflow_id, key, environment, encrypted_value, created_at, updated_at
+        "#,
+        workflow_id,
+        key,
+        environment,
+        encrypted_value,
+        Utc::now(),
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(DbError::NotFound)
+}
+
+/// Delete secret `key` of workflow `workflow_id` in `environment` (`""`
+/// for the default).
+///
+/// Returns `DbError::NotFound` if the workflow has no such secret.
+pub async fn delete_secret(pool: &PgPool, workflow_id: Uuid, key: &str, environment: &str) -> Result<(), DbError> {
+    let result = sqlx::query!(
+        "DELETE FROM secrets WHERE workflow_id = $1 AND key = $2 AND environment = $3",
+        workflow_id,
+        key,
+        environment,
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(DbError::NotFound);
+    }
+    Ok(())
+}
+
+/// Every secret of project `project_id` itself in every environment, by
+/// key, each key's default first.
+pub async fn list_project_secrets(pool: &PgPool, project_id: Uuid) -> Result<Vec<SecretRow>, DbError> {
+    let rows = sqlx::query_as!(
+        SecretRow,
+        r#"
+        SELECT id, project_id, workflow_id, key, environment, encrypted_value, created_at, updated_at
+        FROM secrets
+        WHERE project_id = $1 AND workflow_id IS NULL
+        ORDER BY key, environment
+        "#,
+        project_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Add secret `key` to project `project_id`, for `environment` or, with
+/// `""`, as the default; `None` when the project already has it.
+pub async fn create_project_secret(
+    pool: &PgPool,
+    project_id: Uuid,
+    key: &str,
+    environment: &str,
+    encrypted_value: &str,
+) -> Result<Option<SecretRow>, DbError> {
+    let now = Utc::now();
+    let row = sqlx::query_as!(
+        SecretRow,
+        r#"
+        INSERT INTO secrets (id, project_id, key, environment, encrypted_value, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $6)
+        ON CONFLICT (project_id, key, environment) WHERE workflow_id IS NULL DO NOTHING
+        RETURNING id, project_id, workflow_id, key, environment, encrypted_value, created_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        project_id,
+        key,
+        environment,
+        encrypted_value,
+        now,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Replace the value of secret `key` of project `project_id` in
+/// `environment` (`""` for the default).
+///
+/// Returns `DbError::NotFound` if the project has no such secret.
+pub async fn update_project_secret(
+    pool: &PgPool,
+    project_id: Uuid,
+    key: &str,
+    environment: &str,
+    encrypted_value: &str,
+) -> Result<SecretRow, DbError> {
+    sqlx::query_as!(
+        SecretRow,
+        r#"
+        UPDATE secrets SET encrypted_value = $4, updated_at = $5
+        WHERE project_id = $1 AND workflow_id IS NULL AND key = $2 AND environment = $3
+        RETURNING id, project_id, workflow_id, key, environment, encrypted_value, created_at, updated_at
+        "#,
+        project_id,
+        key,
+        environment,
+        encrypted_value,
+        Utc::now(),
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(DbError::NotFound)
+}
+
+/// Delete secret `key` of project `project_id` in `environment` (`""` for
+/// the default).
+///
+/// Returns `DbError::NotFound` if the project has no such secret.
+pub async fn delete_project_secret(pool: &PgPool, project_id: Uuid, key: &str, environment: &str) -> Result<(), DbError> {
+    let result = sqlx::query!(
+        "DELETE FROM secrets WHERE project_id = $1 AND workflow_id IS NULL AND key = $2 AND environment = $3",
+        project_id,
+        key,
+        environment,
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(DbError::NotFound);
+    }
+    Ok(())
+}
+
+/// Secrets stored across every workflow and project.
+pub async fn count_secrets(pool: &PgPool) -> Result<i64, DbError> {
+    let count = sqlx::query_scalar!(r#"SELECT COUNT(*) AS "count!" FROM secrets"#).fetch_one(pool).await?;
+    Ok(count)
+}
+
+/// The most recently written secret of any workflow or project, to check
+/// a key against.
+pub async fn newest_secret(pool: &PgPool) -> Result<Option<SecretRow>, DbError> {
+    let row = sqlx::query_as!(
+        SecretRow,
+        r#"
+        SELECT id, project_id, workflow_id, key, environment, encrypted_value, created_at, updated_at
+        FROM secrets
+        ORDER BY updated_at DESC
+        LIMIT 1
+        "#,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Every secret of every workflow and project, oldest first.
+pub async fn list_all_secrets(pool: &PgPool) -> Result<Vec<SecretRow>, DbError> {
+    let rows = sqlx::query_as!(
+        SecretRow,
+        r#"
+        SELECT id, project_id, workflow_id, key, environment, encrypted_value, created_at, updated_at
+        FROM secrets
+        ORDER BY created_at, id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Store secret `id` encrypted anew as `encrypted_value`, unless it was
+/// changed from `previous` meanwhile; `false` then.  Its `updated_at`
+/// stays, as the value does.
+pub async fn reencrypt_secret(
+    pool: &PgPool,
+    id: Uuid,
+    previous: &str,
+    encrypted_value: &str,
+) -> Result<bool, DbError> {
+    let result = sqlx::query!(
+        "UPDATE secrets SET encrypted_value = $3 WHERE id = $1 AND encrypted_value = $2",
+        id,
+        previous,
+        encrypted_value,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
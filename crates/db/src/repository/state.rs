@@ -0,0 +1,118 @@
+//! Per-workflow key/value state repository functions.
+//!
+//! Read-modify-write cycles must be serialised, so the locking functions
+//! take a `&mut PgConnection` and are meant to run inside a transaction:
+//!
+//! 1. [`ensure_state_row`] — make sure a row exists to lock.
+//! 2. [`lock_state`]       — `SELECT … FOR UPDATE` the current value.
+//! 3. [`write_state`] / [`delete_state`] — persist the new value, then commit.
+
+use chrono::Utc;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{DbError, models::WorkflowStateRow};
+
+/// Fetch a state entry without locking it.
+pub async fn get_state(
+    pool: &PgPool,
+    workflow_id: Uuid,
+    key: &str,
+) -> Result<Option<WorkflowStateRow>, DbError> {
+    let row = sqlx::query_as!(
+        WorkflowStateRow,
+        r#"
+        SELECT workflow_id, key, value, updated_at
+        FROM workflow_state
+        WHERE workflow_id = $1 AND key = $2
+        "#,
+        workflow_id,
+        key,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Insert an empty (`NULL`) entry if none exists yet, so it can be locked.
+pub async fn ensure_state_row(
+    conn: &mut PgConnection,
+    workflow_id: Uuid,
+    key: &str,
+) -> Result<(), DbError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO workflow_state (workflow_id, key, value, updated_at)
+        VALUES ($1, $2, NULL, $3)
+        ON CONFLICT (workflow_id, key) DO NOTHING
+        "#,
+        workflow_id,
+        key,
+        Utc::now(),
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Lock an entry for the rest of the transaction and return its value.
+pub async fn lock_state(
+    conn: &mut PgConnection,
+    workflow_id: Uuid,
+    key: &str,
+) -> Result<Option<serde_json::Value>, DbError> {
+    let value = sqlx::query_scalar!(
+        r#"
+        SELECT value FROM workflow_state
+        WHERE workflow_id = $1 AND key = $2
+        FOR UPDATE
+        "#,
+        workflow_id,
+        key,
+    )
+    .fetch_optional(conn)
+    .await?
+    .flatten();
+
+    Ok(value)
+}
+
+/// Overwrite the value of an entry.
+pub async fn write_state(
+    conn: &mut PgConnection,
+    workflow_id: Uuid,
+    key: &str,
+    value: serde_json::Value,
+) -> Result<(), DbError> {
+    sqlx::query!(
+        r#"
+        UPDATE workflow_state
+        SET value = $1, updated_at = $2
+        WHERE workflow_id = $3 AND key = $4
+        "#,
+        value,
+        Utc::now(),
+        workflow_id,
+        key,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Remove an entry.
+pub async fn delete_state(
+    conn: &mut PgConnection,
+    workflow_id: Uuid,
+    key: &str,
+) -> Result<(), DbError> {
+    sqlx::query!(
+        "DELETE FROM workflow_state WHERE workflow_id = $1 AND key = $2",
+        workflow_id,
+        key,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! User and role repository functions.
+
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::{DbError, models::UserRow};
+
+/// Every user, by subject.
+pub async fn list_users(pool: &PgPool) -> Result<Vec<UserRow>, DbError> {
+    let rows = sqlx::query_as!(
+        UserRow,
+        r#"
+        SELECT subject, role, name, email, created_at, updated_at
+        FROM users
+        ORDER BY subject
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// The user who authenticates as `subject`.
+///
+/// Returns `DbError::NotFound` if there is none.
+pub async fn get_user(pool: &PgPool, subject: &str) -> Result<UserRow, DbError> {
+    sqlx::query_as!(
+        UserRow,
+        r#"
+        SELECT subject, role, name, email, created_at, updated_at
+        FROM users
+        WHERE subject = $1
+        "#,
+        subject,
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(DbError::NotFound)
+}
+
+/// Create the user `subject` or replace their role, name, and email.
+pub async fn upsert_user(
+    pool: &PgPool,
+    subject: &str,
+    role: &str,
+    name: Option<&str>,
+    email: Option<&str>,
+) -> Result<UserRow, DbError> {
+    let now = Utc::now();
+    let row = sqlx::query_as!(
+        UserRow,
+        r#"
+        INSERT INTO users (subject, role, name, email, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $5)
+        ON CONFLICT (subject)
+        DO UPDATE SET role = EXCLUDED.role, name = EXCLUDED.name, email = EXCLUDED.email,
+                      updated_at = EXCLUDED.updated_at
+        RETURNING subject, role, name, email, created_at, updated_at
+        "#,
+        subject,
+        role,
+        name,
+        email,
+        now,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Remove the user `subject`.
+///
+/// Returns `DbError::NotFound` if there was none.
+pub async fn delete_user(pool: &PgPool, subject: &str) -> Result<(), DbError> {
+    let result = sqlx::query!("DELETE FROM users WHERE subject = $1", subject)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(DbError::NotFound);
+    }
+
+    Ok(())
+}
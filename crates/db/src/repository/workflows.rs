@@ -4,9 +4,10 @@ use chrono::Utc;
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::{DbError, models::WorkflowRow};
+use crate::{changes::{self, Change, WorkflowAction}, DbError, models::{WorkflowFilter, WorkflowRow, WorkflowSummaryRow, WorkflowTagRow, WorkflowVersionRow}};
 
-/// Insert a new workflow into the database.
+/// Insert a new workflow into project `project_id`, as version 1 by
+/// `author`.
 ///
 /// `definition` must be a valid JSON object produced by serialising the
 /// domain `Workflow` type from the `engine` crate.
@@ -14,25 +15,140 @@ pub async fn create_workflow(
     pool: &PgPool,
     name: &str,
     definition: serde_json::Value,
+    active: bool,
+    tags: &[String],
+    author: Option<&str>,
+    project_id: Uuid,
 ) -> Result<WorkflowRow, DbError> {
     let id = Uuid::new_v4();
     let now = Utc::now();
 
+    let mut tx = pool.begin().await?;
     let row = sqlx::query_as!(
         WorkflowRow,
         r#"
-        INSERT INTO workflows (id, name, definition, created_at)
-        VALUES ($1, $2, $3, $4)
-        RETURNING id, name, definition, created_at
+        INSERT INTO workflows (id, name, definition, created_at, active, tags, project_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, name, definition, created_at, active, tags, version, project_id
         "#,
         id,
         name,
         definition,
         now,
+        active,
+        tags,
+        project_id,
     )
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(path_conflict)?;
+    insert_version(&mut tx, &row, author).await?;
+    tx.commit().await?;
+    changes::publish(pool, &Change::workflow(WorkflowAction::Created, &row)).await;
+
+    Ok(row)
+}
+
+/// Store a new definition (and name) for workflow `id` by `author`, as its
+/// next version; returns the updated row.
+///
+/// With `expected_version`, the update only applies to that version:
+/// returns `DbError::VersionConflict` with the current one if the workflow
+/// has moved on, so concurrent editors do not overwrite each other.
+pub async fn update_workflow(
+    pool: &PgPool,
+    id: Uuid,
+    name: &str,
+    definition: serde_json::Value,
+    author: Option<&str>,
+    expected_version: Option<i32>,
+) -> Result<WorkflowRow, DbError> {
+    let mut tx = pool.begin().await?;
+    let updated = sqlx::query_as!(
+        WorkflowRow,
+        r#"
+        UPDATE workflows SET name = $2, definition = $3, version = version + 1
+        WHERE id = $1 AND ($4::int IS NULL OR version = $4)
+        RETURNING id, name, definition, created_at, active, tags, version, project_id
+        "#,
+        id,
+        name,
+        definition,
+        expected_version,
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(path_conflict)?;
+    let Some(row) = updated else {
+        let current = sqlx::query_scalar!("SELECT version FROM workflows WHERE id = $1", id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        return Err(current.map_or(DbError::NotFound, DbError::VersionConflict));
+    };
+    insert_version(&mut tx, &row, author).await?;
+    tx.commit().await?;
+    changes::publish(pool, &Change::workflow(WorkflowAction::Updated, &row)).await;
+
+    Ok(row)
+}
+
+async fn insert_version(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    row: &WorkflowRow,
+    author: Option<&str>,
+) -> Result<(), DbError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO workflow_versions (workflow_id, version, name, definition, author, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        row.id,
+        row.version,
+        row.name,
+        row.definition,
+        author,
+        Utc::now(),
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Every version of workflow `id`, newest first.
+pub async fn list_workflow_versions(pool: &PgPool, id: Uuid) -> Result<Vec<WorkflowVersionRow>, DbError> {
+    let rows = sqlx::query_as!(
+        WorkflowVersionRow,
+        r#"
+        SELECT workflow_id, version, name, definition, author, created_at
+        FROM workflow_versions
+        WHERE workflow_id = $1
+        ORDER BY version DESC
+        "#,
+        id,
+    )
+    .fetch_all(pool)
     .await?;
 
+    Ok(rows)
+}
+
+/// Version `version` of workflow `id`.
+pub async fn get_workflow_version(pool: &PgPool, id: Uuid, version: i32) -> Result<WorkflowVersionRow, DbError> {
+    let row = sqlx::query_as!(
+        WorkflowVersionRow,
+        r#"
+        SELECT workflow_id, version, name, definition, author, created_at
+        FROM workflow_versions
+        WHERE workflow_id = $1 AND version = $2
+        "#,
+        id,
+        version,
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(DbError::NotFound)?;
+
     Ok(row)
 }
 
@@ -40,7 +156,7 @@ pub async fn create_workflow(
 pub async fn get_workflow(pool: &PgPool, id: Uuid) -> Result<WorkflowRow, DbError> {
     let row = sqlx::query_as!(
         WorkflowRow,
-        r#"SELECT id, name, definition, created_at FROM workflows WHERE id = $1"#,
+        r#"SELECT id, name, definition, created_at, active, tags, version, project_id FROM workflows WHERE id = $1"#,
         id,
     )
     .fetch_optional(pool)
@@ -50,11 +166,204 @@ pub async fn get_workflow(pool: &PgPool, id: Uuid) -> Result<WorkflowRow, DbErro
     Ok(row)
 }
 
+/// The project workflow `id` belongs to.
+pub async fn workflow_project(pool: &PgPool, id: Uuid) -> Result<Uuid, DbError> {
+    sqlx::query_scalar!("SELECT project_id FROM workflows WHERE id = $1", id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(DbError::NotFound)
+}
+
+/// The workflow answering webhook requests to `path`: the active one with
+/// that path, or else the newest inactive one (which only takes captures).
+pub async fn find_workflow_by_webhook_path(pool: &PgPool, path: &str) -> Result<Option<WorkflowRow>, DbError> {
+    let row = sqlx::query_as!(
+        WorkflowRow,
+        r#"
+        SELECT id, name, definition, created_at, active, tags, version, project_id
+        FROM workflows
+        WHERE webhook_path = $1
+        ORDER BY active DESC, created_at DESC, id DESC
+        LIMIT 1
+        "#,
+        path,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
 /// Return all workflows ordered by creation time (newest first).
 pub async fn list_workflows(pool: &PgPool) -> Result<Vec<WorkflowRow>, DbError> {
     let rows = sqlx::query_as!(
         WorkflowRow,
-        r#"SELECT id, name, definition, created_at FROM workflows ORDER BY created_at DESC"#,
+        r#"SELECT id, name, definition, created_at, active, tags, version, project_id FROM workflows ORDER BY created_at DESC"#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Every workflow of project `project_id` named exactly `name`, newest
+/// first.
+pub async fn find_workflows_by_name(pool: &PgPool, project_id: Uuid, name: &str) -> Result<Vec<WorkflowRow>, DbError> {
+    let rows = sqlx::query_as!(
+        WorkflowRow,
+        r#"
+        SELECT id, name, definition, created_at, active, tags, version, project_id
+        FROM workflows
+        WHERE project_id = $1 AND name = $2
+        ORDER BY created_at DESC
+        "#,
+        project_id,
+        name,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// A page of the workflows matching `filter`, newest first — or, with a
+/// search, best match first.
+pub async fn list_workflows_page(
+    pool: &PgPool,
+    filter: &WorkflowFilter,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<WorkflowRow>, DbError> {
+    let rows = sqlx::query_as!(
+        WorkflowRow,
+        r#"
+        SELECT id, name, definition, created_at, active, tags, version, project_id
+        FROM workflows
+        WHERE ($1::text IS NULL OR name ILIKE '%' || $1 || '%')
+          AND ($2::text IS NULL OR definition->'trigger'->>'type' = $2)
+          AND ($3::bool IS NULL OR active = $3)
+          AND ($4::text IS NULL OR tags @> ARRAY[$4])
+          AND ($7::uuid IS NULL OR project_id = $7)
+          AND ($8::text IS NULL OR search_vector @@ websearch_to_tsquery('simple', $8))
+        ORDER BY CASE WHEN $8::text IS NULL THEN 0 ELSE ts_rank(search_vector, websearch_to_tsquery('simple', $8)) END DESC,
+                 created_at DESC, id
+        LIMIT $5 OFFSET $6
+        "#,
+        filter.name.as_deref().map(escape_like),
+        filter.trigger_type,
+        filter.active,
+        filter.tag,
+        limit,
+        offset,
+        filter.project_id,
+        filter.search,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Like [`list_workflows_page`], as summaries with the status of each
+/// workflow's latest execution; definitions are not read past their
+/// trigger type.
+pub async fn list_workflow_summaries(
+    pool: &PgPool,
+    filter: &WorkflowFilter,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<WorkflowSummaryRow>, DbError> {
+    let rows = sqlx::query_as!(
+        WorkflowSummaryRow,
+        r#"
+        SELECT w.id, w.name, w.definition->'trigger'->>'type' AS trigger_type, w.active, w.tags, w.created_at,
+               last.status AS "last_status?", last.started_at AS "last_started_at?"
+        FROM workflows w
+        LEFT JOIN LATERAL (
+            SELECT status, started_at FROM workflow_executions
+            WHERE workflow_id = w.id
+            ORDER BY started_at DESC
+            LIMIT 1
+        ) last ON true
+        WHERE ($1::text IS NULL OR w.name ILIKE '%' || $1 || '%')
+          AND ($2::text IS NULL OR w.definition->'trigger'->>'type' = $2)
+          AND ($3::bool IS NULL OR w.active = $3)
+          AND ($4::text IS NULL OR w.tags @> ARRAY[$4])
+          AND ($7::uuid IS NULL OR w.project_id = $7)
+          AND ($8::text IS NULL OR w.search_vector @@ websearch_to_tsquery('simple', $8))
+        ORDER BY CASE WHEN $8::text IS NULL THEN 0 ELSE ts_rank(w.search_vector, websearch_to_tsquery('simple', $8)) END DESC,
+                 w.created_at DESC, w.id
+        LIMIT $5 OFFSET $6
+        "#,
+        filter.name.as_deref().map(escape_like),
+        filter.trigger_type,
+        filter.active,
+        filter.tag,
+        limit,
+        offset,
+        filter.project_id,
+        filter.search,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Activate or deactivate a workflow; returns the updated row.
+pub async fn set_workflow_active(pool: &PgPool, id: Uuid, active: bool) -> Result<WorkflowRow, DbError> {
+    let row = sqlx::query_as!(
+        WorkflowRow,
+        r#"
+        UPDATE workflows SET active = $2 WHERE id = $1
+        RETURNING id, name, definition, created_at, active, tags, version, project_id
+        "#,
+        id,
+        active,
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(path_conflict)?
+    .ok_or(DbError::NotFound)?;
+
+    changes::publish(pool, &Change::workflow(WorkflowAction::Updated, &row)).await;
+
+    Ok(row)
+}
+
+/// Replace a workflow's tags; returns the updated row.
+pub async fn set_workflow_tags(pool: &PgPool, id: Uuid, tags: &[String]) -> Result<WorkflowRow, DbError> {
+    let row = sqlx::query_as!(
+        WorkflowRow,
+        r#"
+        UPDATE workflows SET tags = $2 WHERE id = $1
+        RETURNING id, name, definition, created_at, active, tags, version, project_id
+        "#,
+        id,
+        tags,
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(DbError::NotFound)?;
+
+    changes::publish(pool, &Change::workflow(WorkflowAction::Updated, &row)).await;
+
+    Ok(row)
+}
+
+/// Every tag in use (in project `project_id`, if given), with the number
+/// of workflows that have it.
+pub async fn list_workflow_tags(pool: &PgPool, project_id: Option<Uuid>) -> Result<Vec<WorkflowTagRow>, DbError> {
+    let rows = sqlx::query_as!(
+        WorkflowTagRow,
+        r#"
+        SELECT tag AS "tag!", COUNT(*) AS "workflows!"
+        FROM workflows, unnest(tags) AS tag
+        WHERE $1::uuid IS NULL OR project_id = $1
+        GROUP BY tag
+        ORDER BY tag
+        "#,
+        project_id,
     )
     .fetch_all(pool)
     .await?;
@@ -64,15 +373,59 @@ pub async fn list_workflows(pool: &PgPool) -> Result<Vec<WorkflowRow>, DbError>
 
 /// Permanently delete a workflow by its primary key.
 ///
-/// Returns `DbError::NotFound` if no row was deleted.
+/// Returns `DbError::NotFound` if no row was deleted, and
+/// `DbError::LegalHold` if the workflow or any of its executions is under
+/// legal hold (its executions would otherwise be deleted with it).
 pub async fn delete_workflow(pool: &PgPool, id: Uuid) -> Result<(), DbError> {
-    let result = sqlx::query!("DELETE FROM workflows WHERE id = $1", id)
-        .execute(pool)
-        .await?;
+    let deleted = sqlx::query!(
+        r#"
+        DELETE FROM workflows w
+        WHERE w.id = $1
+          AND NOT w.legal_hold
+          AND NOT EXISTS (
+              SELECT 1 FROM workflow_executions e WHERE e.workflow_id = w.id AND e.legal_hold
+          )
+        RETURNING name, project_id
+        "#,
+        id,
+    )
+    .fetch_optional(pool)
+    .await?;
 
-    if result.rows_affected() == 0 {
-        return Err(DbError::NotFound);
-    }
+    let Some(deleted) = deleted else {
+        let exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM workflows WHERE id = $1) AS "exists!""#,
+            id,
+        )
+        .fetch_one(pool)
+        .await?;
+        return Err(if exists { DbError::LegalHold } else { DbError::NotFound });
+    };
+    let change = Change::Workflow {
+        action: WorkflowAction::Deleted,
+        id,
+        project_id: deleted.project_id,
+        name: deleted.name,
+        active: None,
+        version: None,
+    };
+    changes::publish(pool, &change).await;
 
     Ok(())
 }
+
+/// `text` with the `LIKE` wildcards escaped, to match literally.
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Unique index keeping the webhook paths of active workflows apart.
+const WEBHOOK_PATH_INDEX: &str = "idx_workflows_webhook_path";
+
+/// `e`, as [`DbError::WebhookPathTaken`] when it broke that index.
+fn path_conflict(e: sqlx::Error) -> DbError {
+    match e.as_database_error().and_then(|db| db.constraint()) {
+        Some(WEBHOOK_PATH_INDEX) => DbError::WebhookPathTaken,
+        _ => e.into(),
+    }
+}
@@ -0,0 +1,95 @@
+//! Data-flow cost of multi-megabyte payloads.
+//!
+//! Run with `cargo bench -p engine --bench payload`.  Each benchmark pushes
+//! a payload of roughly 1 MB or 4 MB through a ten-node chain the way the
+//! executor does: a per-node context is derived from the execution context,
+//! the node runs through [`NodeRunner`], and its output feeds the next node.
+//! Before payloads were shared, every step deep-copied the trigger input and
+//! the node's input and output several times over.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde_json::{json, Value};
+
+use engine::executor::{ExecutorConfig, NodeRunner};
+use nodes::traits::ExecutionContext;
+use nodes::{ExecutableNode, NodeError};
+
+const CHAIN: usize = 10;
+
+/// Returns its input, like a filter or router node that passes data on.
+struct PassThrough;
+
+#[async_trait]
+impl ExecutableNode for PassThrough {
+    async fn execute(&self, input: Value, _ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        Ok(input)
+    }
+}
+
+/// An array of `rows` order-like objects (~200 bytes each).
+fn payload(rows: usize) -> Value {
+    let items: Vec<Value> = (0..rows)
+        .map(|i| {
+            json!({
+                "id": i,
+                "sku": format!("SKU-{i:08}"),
+                "customer": { "name": "Ada Lovelace", "email": "ada@example.com" },
+                "lines": [{ "qty": 2, "price": 19.99 }, { "qty": 1, "price": 5.0 }],
+                "note": "x".repeat(32),
+            })
+        })
+        .collect();
+    json!({ "items": items })
+}
+
+fn contexts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("context_for_node");
+    for (label, rows) in [("1MB", 5_000), ("4MB", 20_000)] {
+        let input = payload(rows);
+        group.throughput(Throughput::Bytes(input.to_string().len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(label), &input, |b, input| {
+            let ctx = ExecutionContext::new(uuid::Uuid::nil(), uuid::Uuid::nil(), input.clone());
+            b.iter(|| {
+                for i in 0..CHAIN {
+                    criterion::black_box(ctx.for_node(format!("n{i}"), json!({})));
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+fn chain(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    let runner = NodeRunner::new(ExecutorConfig::default());
+    let node: Arc<dyn ExecutableNode> = Arc::new(PassThrough);
+
+    let mut group = c.benchmark_group("node_chain");
+    group.sample_size(20);
+    for (label, rows) in [("1MB", 5_000), ("4MB", 20_000)] {
+        let input = payload(rows);
+        group.throughput(Throughput::Bytes(input.to_string().len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(label), &input, |b, input| {
+            let ctx = ExecutionContext::new(uuid::Uuid::nil(), uuid::Uuid::nil(), input.clone());
+            b.iter(|| {
+                rt.block_on(async {
+                    let mut current = Arc::new(input.clone());
+                    for i in 0..CHAIN {
+                        let id = format!("n{i}");
+                        let node_ctx = ctx.for_node(id.as_str(), json!({}));
+                        let output = runner.run(&id, &node, &current, &node_ctx).await.unwrap();
+                        current = Arc::new(output);
+                    }
+                    current
+                })
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, contexts, chain);
+criterion_main!(benches);
@@ -0,0 +1,346 @@
+//! AMQP queue trigger — starts workflows whose trigger is
+//! [`Trigger::Amqp`] for each message on a RabbitMQ queue.
+//!
+//! Each pass of [`AmqpTriggers::run`] starts a consumer for every active
+//! workflow with an AMQP trigger, restarts the consumers of workflows whose
+//! definition changed, and stops those of deleted, deactivated, or
+//! re-triggered ones.  A consumer reconnects with a growing back-off
+//! whenever the broker goes away.
+//!
+//! Messages are acknowledged only once [`triggers::admit`] has recorded
+//! the execution and its job, so a crash at any point leaves the message
+//! on the queue for redelivery: delivery is at-least-once, and a message
+//! that was admitted but not yet acknowledged starts a second execution
+//! (its input has `redelivered: true`).  A message the trigger throttles,
+//! or that cannot be recorded, is requeued after a pause; one whose body
+//! cannot be read is rejected without requeueing, so it goes to the
+//! queue's dead-letter exchange if one is configured.
+//!
+//! The execution's input is:
+//!
+//! ```json
+//! {
+//!   "body": { "order_id": 42 },
+//!   "exchange": "orders",
+//!   "routing_key": "order.paid",
+//!   "headers": { "x-tenant": "acme" },
+//!   "message_id": "b5c1…",
+//!   "redelivered": false
+//! }
+//! ```
+//!
+//! Bodies are parsed as JSON when the content type says so (or is missing
+//! and the body parses), and are text otherwise.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use lapin::message::Delivery;
+use lapin::options::{BasicAckOptions, BasicConsumeOptions, BasicNackOptions, BasicQosOptions};
+use lapin::types::{AMQPValue, FieldTable};
+use lapin::{Connection, ConnectionProperties};
+use serde_json::{json, Map, Value};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use db::DbPool;
+use db::repository::workflows as wf_repo;
+use queue::{JobQueue, PgJobQueue, SharedQueue};
+
+use crate::triggers::{self, Admission};
+use crate::{EngineError, Trigger, Workflow};
+
+/// Longest pause between reconnection attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Pause before a message that could not be admitted is requeued, so a
+/// throttled workflow or an unavailable database is not hammered.
+const REQUEUE_DELAY: Duration = Duration::from_secs(1);
+
+/// A running consumer and the definition it was started from.
+struct Consumer {
+    definition: Value,
+    task: JoinHandle<()>,
+}
+
+/// Keeps one queue consumer running per AMQP-triggered workflow.
+pub struct AmqpTriggers {
+    pool: DbPool,
+    jobs: SharedQueue,
+    consumers: HashMap<Uuid, Consumer>,
+}
+
+impl AmqpTriggers {
+    pub fn new(pool: DbPool) -> Self {
+        Self { jobs: Arc::new(PgJobQueue::new(pool.clone())), pool, consumers: HashMap::new() }
+    }
+
+    /// Hand admitted jobs to `jobs`.
+    pub fn with_queue(mut self, jobs: SharedQueue) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Start, restart, or stop consumers to match the stored workflows;
+    /// returns the number of AMQP-triggered workflows.
+    pub async fn sync(&mut self) -> Result<usize, EngineError> {
+        let mut wanted: HashMap<Uuid, (Value, Workflow)> = HashMap::new();
+        for row in wf_repo::list_workflows(&self.pool).await? {
+            if !row.active {
+                continue;
+            }
+            let Ok(workflow) = serde_json::from_value::<Workflow>(row.definition.clone()) else {
+                continue;
+            };
+            if matches!(workflow.trigger, Trigger::Amqp { .. }) {
+                wanted.insert(row.id, (row.definition, workflow));
+            }
+        }
+
+        self.consumers.retain(|id, consumer| {
+            let keep = wanted.get(id).is_some_and(|(definition, _)| *definition == consumer.definition)
+                && !consumer.task.is_finished();
+            if !keep {
+                consumer.task.abort();
+            }
+            keep
+        });
+
+        let count = wanted.len();
+        for (workflow_id, (definition, workflow)) in wanted {
+            if self.consumers.contains_key(&workflow_id) {
+                continue;
+            }
+            let Trigger::Amqp { url_env, .. } = &workflow.trigger else {
+                continue;
+            };
+            let Ok(url) = std::env::var(url_env) else {
+                warn!("amqp: workflow {} not consuming: {} is not set", workflow_id, url_env);
+                continue;
+            };
+            let task = tokio::spawn(consume(self.pool.clone(), Arc::clone(&self.jobs), workflow_id, workflow, url));
+            self.consumers.insert(workflow_id, Consumer { definition, task });
+        }
+        Ok(count)
+    }
+
+    /// Sync every `interval` until the task is dropped.
+    pub async fn run(mut self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.sync().await {
+                warn!("amqp: sync failed: {}", e);
+            }
+        }
+    }
+}
+
+impl Drop for AmqpTriggers {
+    fn drop(&mut self) {
+        for consumer in self.consumers.values() {
+            consumer.task.abort();
+        }
+    }
+}
+
+/// Consume the workflow's queue, reconnecting until the task is aborted.
+async fn consume(pool: DbPool, jobs: SharedQueue, workflow_id: Uuid, workflow: Workflow, url: String) {
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        match consume_once(&pool, jobs.as_ref(), workflow_id, &workflow, &url).await {
+            Ok(()) => {
+                warn!("amqp: workflow {} lost its consumer; reconnecting", workflow_id);
+                backoff = Duration::from_secs(1);
+            }
+            Err(e) => warn!("amqp: workflow {}: {}; retrying in {:?}", workflow_id, e, backoff),
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Connect and handle deliveries until the consumer ends.
+async fn consume_once(
+    pool: &DbPool,
+    jobs: &dyn JobQueue,
+    workflow_id: Uuid,
+    workflow: &Workflow,
+    url: &str,
+) -> Result<(), lapin::Error> {
+    let Trigger::Amqp { queue, prefetch, .. } = &workflow.trigger else {
+        return Ok(());
+    };
+    let connection = Connection::connect(url, ConnectionProperties::default()).await?;
+    let channel = connection.create_channel().await?;
+    channel.basic_qos(*prefetch, BasicQosOptions::default()).await?;
+    let mut consumer = channel
+        .basic_consume(
+            queue,
+            &format!("workflow-{workflow_id}"),
+            BasicConsumeOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+    info!("amqp: workflow {} consuming from '{}'", workflow_id, queue);
+
+    while let Some(delivery) = consumer.next().await {
+        handle(pool, jobs, workflow_id, workflow, delivery?).await?;
+    }
+    Ok(())
+}
+
+/// Admit one message, then acknowledge, requeue, or reject it.
+async fn handle(
+    pool: &DbPool,
+    jobs: &dyn JobQueue,
+    workflow_id: Uuid,
+    workflow: &Workflow,
+    delivery: Delivery,
+) -> Result<(), lapin::Error> {
+    let input = match message_input(&delivery) {
+        Ok(input) => input,
+        Err(reason) => {
+            warn!("amqp: workflow {} rejected a message: {}", workflow_id, reason);
+            return delivery.nack(BasicNackOptions { requeue: false, ..Default::default() }).await;
+        }
+    };
+
+    match triggers::admit(pool, jobs, workflow_id, workflow, input).await {
+        Ok(Admission::Enqueued(_) | Admission::Debounced(_)) => delivery.ack(BasicAckOptions::default()).await,
+        outcome => {
+            match outcome {
+                Ok(Admission::OverQuota(reason)) => {
+                    info!("amqp: workflow {} over quota ({}); requeueing a message", workflow_id, reason)
+                }
+                Ok(_) => info!("amqp: workflow {} throttled; requeueing a message", workflow_id),
+                Err(e) => warn!("amqp: workflow {} cannot admit a message: {}", workflow_id, e),
+            }
+            tokio::time::sleep(REQUEUE_DELAY).await;
+            delivery.nack(BasicNackOptions { requeue: true, ..Default::default() }).await
+        }
+    }
+}
+
+/// The execution input for a delivered message.
+fn message_input(delivery: &Delivery) -> Result<Value, String> {
+    let properties = &delivery.properties;
+    let content_type = properties.content_type().as_ref().map(|t| t.as_str());
+    let headers = properties
+        .headers()
+        .as_ref()
+        .map(table_to_json)
+        .unwrap_or_else(|| json!({}));
+    Ok(json!({
+        "body": decode_body(&delivery.data, content_type)?,
+        "exchange": delivery.exchange.as_str(),
+        "routing_key": delivery.routing_key.as_str(),
+        "headers": headers,
+        "message_id": properties.message_id().as_ref().map(|id| id.as_str()),
+        "redelivered": delivery.redelivered,
+    }))
+}
+
+/// Parse a message body by its content type.
+fn decode_body(data: &[u8], content_type: Option<&str>) -> Result<Value, String> {
+    if data.is_empty() {
+        return Ok(Value::Null);
+    }
+    let text = || {
+        String::from_utf8(data.to_vec())
+            .map(Value::String)
+            .map_err(|_| "body is neither JSON nor UTF-8 text".to_owned())
+    };
+    let mime = content_type.map(|t| t.split(';').next().unwrap_or_default().trim().to_ascii_lowercase());
+    match mime.as_deref() {
+        Some(mime) if mime == "application/json" || mime.ends_with("+json") => {
+            serde_json::from_slice(data).map_err(|e| format!("malformed JSON body: {e}"))
+        }
+        None => serde_json::from_slice(data).or_else(|_| text()),
+        Some(_) => text(),
+    }
+}
+
+/// Message headers as a JSON object.
+fn table_to_json(table: &FieldTable) -> Value {
+    let map: Map<String, Value> = table
+        .inner()
+        .iter()
+        .map(|(key, value)| (key.as_str().to_owned(), amqp_to_json(value)))
+        .collect();
+    Value::Object(map)
+}
+
+/// A header value as JSON; values without a JSON counterpart (decimals,
+/// byte arrays) become `null`.
+fn amqp_to_json(value: &AMQPValue) -> Value {
+    match value {
+        AMQPValue::Boolean(b) => json!(b),
+        AMQPValue::ShortShortInt(n) => json!(n),
+        AMQPValue::ShortShortUInt(n) => json!(n),
+        AMQPValue::ShortInt(n) => json!(n),
+        AMQPValue::ShortUInt(n) => json!(n),
+        AMQPValue::LongInt(n) => json!(n),
+        AMQPValue::LongUInt(n) => json!(n),
+        AMQPValue::LongLongInt(n) => json!(n),
+        AMQPValue::Float(n) => json!(n),
+        AMQPValue::Double(n) => json!(n),
+        AMQPValue::Timestamp(n) => json!(n),
+        AMQPValue::ShortString(s) => json!(s.as_str()),
+        AMQPValue::LongString(s) => json!(String::from_utf8_lossy(s.as_bytes())),
+        AMQPValue::FieldArray(items) => Value::Array(items.as_slice().iter().map(amqp_to_json).collect()),
+        AMQPValue::FieldTable(table) => table_to_json(table),
+        AMQPValue::DecimalValue(_) | AMQPValue::ByteArray(_) | AMQPValue::Void => Value::Null,
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lapin::types::{FieldArray, LongString, ShortString};
+
+    #[test]
+    fn decodes_bodies_by_content_type() {
+        let order = br#"{"order_id":42}"#;
+        assert_eq!(decode_body(order, Some("application/json; charset=utf-8")).unwrap(), json!({ "order_id": 42 }));
+        assert_eq!(decode_body(order, None).unwrap(), json!({ "order_id": 42 }));
+        assert_eq!(decode_body(order, Some("text/plain")).unwrap(), json!(r#"{"order_id":42}"#));
+        assert_eq!(decode_body(b"hello", None).unwrap(), json!("hello"));
+        assert_eq!(decode_body(b"", Some("application/json")).unwrap(), Value::Null);
+
+        assert!(decode_body(b"{oops", Some("application/vnd.api+json")).unwrap_err().contains("malformed JSON"));
+        assert!(decode_body(&[0xff, 0xfe], None).is_err());
+    }
+
+    #[test]
+    fn converts_headers_to_json() {
+        let mut nested = FieldTable::default();
+        nested.insert(ShortString::from("retries"), AMQPValue::LongInt(2));
+        let mut table = FieldTable::default();
+        table.insert(ShortString::from("x-tenant"), AMQPValue::LongString(LongString::from("acme")));
+        table.insert(ShortString::from("x-urgent"), AMQPValue::Boolean(true));
+        table.insert(
+            ShortString::from("x-tags"),
+            AMQPValue::FieldArray(FieldArray::from(vec![AMQPValue::ShortString(ShortString::from("a"))])),
+        );
+        table.insert(ShortString::from("x-death"), AMQPValue::FieldTable(nested));
+        table.insert(ShortString::from("x-void"), AMQPValue::Void);
+
+        assert_eq!(
+            table_to_json(&table),
+            json!({
+                "x-tenant": "acme",
+                "x-urgent": true,
+                "x-tags": ["a"],
+                "x-death": { "retries": 2 },
+                "x-void": null,
+            })
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Decisions on human approvals requested by the `approval` node.
+//!
+//! The node keeps its pending approval in workflow state (see
+//! [`nodes::builtin::approval`]) and defers the execution until the
+//! approval times out.  [`decide`] records an approve/reject decision made
+//! through the API and releases the deferred job, so the execution resumes
+//! immediately and the node routes it down the matching branch.
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use db::DbPool;
+use db::repository::{executions as exec_repo, jobs as job_repo, state as state_repo};
+use nodes::builtin::approval::{state_key, ApprovalRecord, ApprovalStatus};
+use queue::JobQueue;
+
+use crate::EngineError;
+
+/// An approve or reject decision.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+    pub approve: bool,
+    /// Who decided, for the record.
+    pub actor: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// The approval requested by `node_id` in an execution.
+///
+/// # Errors
+/// [`EngineError::ApprovalNotFound`] when there is none (or it has already
+/// been consumed by the resumed node), and database errors.
+pub async fn get(pool: &DbPool, execution_id: Uuid, node_id: &str) -> Result<ApprovalRecord, EngineError> {
+    let exec = exec_repo::get_execution(pool, execution_id).await?;
+    let row = state_repo::get_state(pool, exec.workflow_id, &state_key(execution_id, node_id)).await?;
+    row.and_then(|r| r.value)
+        .and_then(|v| serde_json::from_value(v).ok())
+        .ok_or_else(|| EngineError::ApprovalNotFound { node_id: node_id.to_owned() })
+}
+
+/// Record `decision` on the approval requested by `node_id`, presenting
+/// `token`, and resume the execution through `queue`.  Returns the decided
+/// approval.
+///
+/// # Errors
+/// [`EngineError::ApprovalNotFound`], [`EngineError::ApprovalTokenInvalid`],
+/// [`EngineError::ApprovalClosed`] when it was already decided or has
+/// expired, and database errors.
+pub async fn decide(
+    pool: &DbPool,
+    queue: &dyn JobQueue,
+    execution_id: Uuid,
+    node_id: &str,
+    token: &str,
+    decision: Decision,
+) -> Result<ApprovalRecord, EngineError> {
+    let exec = exec_repo::get_execution(pool, execution_id).await?;
+    let key = state_key(execution_id, node_id);
+
+    let mut tx = pool.begin().await.map_err(db::DbError::from)?;
+    let mut record: ApprovalRecord = state_repo::lock_state(&mut tx, exec.workflow_id, &key)
+        .await?
+        .and_then(|v| serde_json::from_value(v).ok())
+        .ok_or_else(|| EngineError::ApprovalNotFound { node_id: node_id.to_owned() })?;
+    apply(&mut record, node_id, token, decision, Utc::now())?;
+    let value = record.to_state();
+    state_repo::write_state(&mut tx, exec.workflow_id, &key, value).await?;
+    tx.commit().await.map_err(db::DbError::from)?;
+
+    // Without this the node would only see the decision at its timeout.
+    for job in job_repo::release_delayed_jobs(pool, execution_id).await? {
+        queue.push(&job).await?;
+    }
+    Ok(record)
+}
+
+/// Check `token` and record `decision` on a pending, unexpired approval.
+fn apply(
+    record: &mut ApprovalRecord,
+    node_id: &str,
+    token: &str,
+    decision: Decision,
+    now: DateTime<Utc>,
+) -> Result<(), EngineError> {
+    if !constant_time_eq(record.token.as_bytes(), token.as_bytes()) {
+        return Err(EngineError::ApprovalTokenInvalid { node_id: node_id.to_owned() });
+    }
+    let closed = match record.status {
+        ApprovalStatus::Approved => Some("approved"),
+        ApprovalStatus::Rejected => Some("rejected"),
+        ApprovalStatus::Pending if now >= record.expires_at => Some("expired"),
+        ApprovalStatus::Pending => None,
+    };
+    if let Some(status) = closed {
+        return Err(EngineError::ApprovalClosed { node_id: node_id.to_owned(), status: status.into() });
+    }
+
+    record.status = if decision.approve { ApprovalStatus::Approved } else { ApprovalStatus::Rejected };
+    record.actor = decision.actor;
+    record.comment = decision.comment;
+    record.decided_at = Some(now);
+    Ok(())
+}
+
+/// Compare secrets without leaking how much of a guess was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn pending(now: DateTime<Utc>) -> ApprovalRecord {
+        ApprovalRecord {
+            token: "secret".into(),
+            status: ApprovalStatus::Pending,
+            message: None,
+            requested_at: now,
+            expires_at: now + Duration::hours(1),
+            actor: None,
+            comment: None,
+            decided_at: None,
+        }
+    }
+
+    fn approve(actor: &str) -> Decision {
+        Decision { approve: true, actor: Some(actor.into()), comment: None }
+    }
+
+    #[test]
+    fn records_a_decision_once() {
+        let now = Utc::now();
+        let mut record = pending(now);
+        apply(&mut record, "n", "secret", approve("dana"), now).unwrap();
+        assert_eq!(record.status, ApprovalStatus::Approved);
+        assert_eq!((record.actor.as_deref(), record.decided_at), (Some("dana"), Some(now)));
+
+        let reject = Decision { approve: false, actor: None, comment: None };
+        let err = apply(&mut record, "n", "secret", reject, now).unwrap_err();
+        assert!(matches!(err, EngineError::ApprovalClosed { status, .. } if status == "approved"));
+    }
+
+    #[test]
+    fn rejects_wrong_tokens_and_expired_approvals() {
+        let now = Utc::now();
+        let err = apply(&mut pending(now), "n", "guess!", approve("x"), now).unwrap_err();
+        assert!(matches!(err, EngineError::ApprovalTokenInvalid { .. }));
+
+        let err = apply(&mut pending(now), "n", "secret", approve("x"), now + Duration::hours(2)).unwrap_err();
+        assert!(matches!(err, EngineError::ApprovalClosed { status, .. } if status == "expired"));
+    }
+}
@@ -0,0 +1,212 @@
+//! Embedding the engine — workflows as a library.
+//!
+//! [`Automation`] is the one entry point for applications that define,
+//! check, and run workflows without the HTTP server.  It is built from a
+//! node registry, with an optional executor config and an optional store:
+//!
+//! ```ignore
+//! let automation = Automation::new(nodes::default_registry());
+//! let workflow = automation.parse(&std::fs::read_to_string("orders.json")?)?;
+//! let checked = automation.check(&workflow)?;
+//! let run = automation.run(&workflow, json!({ "order": 1 }), |_| {}).await?;
+//!
+//! // With a store, workflows are saved and runs recorded like a worker's.
+//! let automation = automation.with_store(pool);
+//! let saved = automation.save("orders", &workflow).await?;
+//! let result = automation.execute(&saved, json!({ "order": 1 })).await?;
+//! ```
+//!
+//! Without a store, runs happen in memory ([`crate::local`]), so nodes
+//! needing more than that fail.  The API server is one consumer: it reads
+//! node types through the `Automation` in its state.
+
+use std::sync::Arc;
+
+use serde_json::Value;
+use uuid::Uuid;
+
+use db::models::DEFAULT_PROJECT_ID;
+use db::DbPool;
+
+use crate::dag::validate_dag;
+use crate::executor::{ExecutionResult, ExecutorConfig, NodeRegistry};
+use crate::lint::{self, Lint, Severity};
+use crate::local::{LocalRun, LocalRunner, NodeReport};
+use crate::{EngineError, Workflow, WorkflowExecutor};
+
+/// What [`Automation::check`] found in a workflow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checked {
+    /// Node ids in the order they run.
+    pub order: Vec<String>,
+    pub lints: Vec<Lint>,
+}
+
+impl Checked {
+    /// Whether no lint is an error; warnings are allowed.
+    pub fn is_valid(&self) -> bool {
+        self.lints.iter().all(|lint| lint.severity != Severity::Error)
+    }
+}
+
+/// Defines, checks, and runs workflows with the nodes of a registry.
+#[derive(Clone)]
+pub struct Automation {
+    registry: Arc<NodeRegistry>,
+    config: ExecutorConfig,
+    /// Where workflows and executions are kept; in memory without one.
+    store: Option<DbPool>,
+    /// Project saved workflows belong to.
+    project_id: Uuid,
+}
+
+impl Automation {
+    pub fn new(registry: NodeRegistry) -> Self {
+        Self {
+            registry: Arc::new(registry),
+            config: ExecutorConfig::default(),
+            store: None,
+            project_id: DEFAULT_PROJECT_ID,
+        }
+    }
+
+    /// Retry nodes as `config` says.
+    pub fn with_config(mut self, config: ExecutorConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Save workflows to, and record executions in, the database of `pool`.
+    pub fn with_store(mut self, pool: DbPool) -> Self {
+        self.store = Some(pool);
+        self
+    }
+
+    /// Save workflows in project `project_id` rather than the default one.
+    pub fn with_project(mut self, project_id: Uuid) -> Self {
+        self.project_id = project_id;
+        self
+    }
+
+    pub fn registry(&self) -> &NodeRegistry {
+        &self.registry
+    }
+
+    pub fn store(&self) -> Option<&DbPool> {
+        self.store.as_ref()
+    }
+
+    /// The workflow defined by JSON `definition`, with its DAG validated.
+    ///
+    /// # Errors
+    /// [`EngineError::InvalidDefinition`] for JSON that is not a workflow,
+    /// and the DAG's validation errors.
+    pub fn parse(&self, definition: &str) -> Result<Workflow, EngineError> {
+        let workflow: Workflow =
+            serde_json::from_str(definition).map_err(|e| EngineError::InvalidDefinition(e.to_string()))?;
+        validate_dag(&workflow)?;
+        Ok(workflow)
+    }
+
+    /// The order `workflow` runs in, and what [`lint::lint`] finds in it
+    /// against this registry.
+    ///
+    /// # Errors
+    /// The DAG's validation errors.
+    pub fn check(&self, workflow: &Workflow) -> Result<Checked, EngineError> {
+        let order = validate_dag(workflow)?;
+        Ok(Checked { order, lints: lint::lint(workflow, &self.registry) })
+    }
+
+    /// Run `workflow` on `input` in memory, calling `on_node` as each node
+    /// finishes; nothing is recorded, even with a store.
+    ///
+    /// # Errors
+    /// As for [`LocalRunner::run`].
+    pub async fn run(
+        &self,
+        workflow: &Workflow,
+        input: Value,
+        on_node: impl FnMut(&NodeReport),
+    ) -> Result<LocalRun, EngineError> {
+        let runner = LocalRunner::new((*self.registry).clone(), self.config.clone());
+        runner.run(workflow, input, on_node).await
+    }
+
+    /// An executor recording runs in the store, for settings beyond these
+    /// (secrets, tracing, queues).
+    ///
+    /// # Errors
+    /// [`EngineError::StoreRequired`] without a store.
+    pub fn executor(&self) -> Result<WorkflowExecutor, EngineError> {
+        let pool = self.store.clone().ok_or(EngineError::StoreRequired("executing a workflow"))?;
+        Ok(WorkflowExecutor::new(pool, (*self.registry).clone(), self.config.clone()))
+    }
+
+    /// Save `workflow` as a new active workflow named `name`; returns it
+    /// with the id it was saved under, for [`Automation::execute`].
+    ///
+    /// # Errors
+    /// [`EngineError::StoreRequired`] without a store, the DAG's validation
+    /// errors, and database errors.
+    pub async fn save(&self, name: &str, workflow: &Workflow) -> Result<Workflow, EngineError> {
+        let pool = self.store.as_ref().ok_or(EngineError::StoreRequired("saving a workflow"))?;
+        validate_dag(workflow)?;
+        let definition = serde_json::to_value(workflow).map_err(|e| EngineError::InvalidDefinition(e.to_string()))?;
+        let row = db::repository::workflows::create_workflow(pool, name, definition, true, &[], None, self.project_id)
+            .await?;
+        Ok(Workflow { id: row.id, ..workflow.clone() })
+    }
+
+    /// Run saved `workflow` on `input` as a recorded execution.
+    ///
+    /// # Errors
+    /// [`EngineError::StoreRequired`] without a store, and as for
+    /// [`WorkflowExecutor::run`].
+    pub async fn execute(&self, workflow: &Workflow, input: Value) -> Result<ExecutionResult, EngineError> {
+        self.executor()?.run(workflow, input).await
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn definition() -> String {
+        json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": "embedded",
+            "trigger": { "type": "manual" },
+            "nodes": [{
+                "id": "check",
+                "node_type": "validate_json",
+                "config": { "schema": { "type": "object", "required": ["order"] } }
+            }],
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z"
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn defines_checks_and_runs_workflows_without_a_store() {
+        let automation = Automation::new(nodes::default_registry());
+        let workflow = automation.parse(&definition()).expect("parses");
+        let checked = automation.check(&workflow).expect("valid DAG");
+        assert_eq!(checked.order, vec!["check"]);
+        assert!(checked.is_valid(), "{:?}", checked.lints);
+
+        let run = automation.run(&workflow, json!({ "order": 1 }), |_| {}).await.expect("runs");
+        assert!(run.succeeded(), "{:?}", run.error);
+        let run = automation.run(&workflow, json!({}), |_| {}).await.expect("runs");
+        assert!(!run.succeeded());
+
+        assert!(matches!(automation.parse("{}"), Err(EngineError::InvalidDefinition(_))));
+        assert!(matches!(automation.executor(), Err(EngineError::StoreRequired(_))));
+        assert!(matches!(automation.save("embedded", &workflow).await, Err(EngineError::StoreRequired(_))));
+    }
+}
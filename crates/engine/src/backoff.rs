@@ -0,0 +1,84 @@
+//! Job retry back-off — how long a job whose attempt failed (see
+//! [`Worker`](crate::worker::Worker)) waits before it is claimed again.
+//!
+//! Without a delay a job that fails on a database hiccup is retried at
+//! once, by every worker, until it runs out of attempts.  A
+//! [`RetryBackoff`] grows the delay exponentially and jitters it, so a
+//! burst of failures spreads out instead of coming back as a burst.  Each
+//! workflow's policy falls back field by field to the worker's default.
+
+use std::time::Duration;
+
+use crate::RetryBackoff;
+
+/// Delay before the second attempt, unless configured.
+pub const DEFAULT_BASE_SECS: u64 = 5;
+
+/// Longest delay, unless configured.
+pub const DEFAULT_MAX_SECS: u64 = 600;
+
+/// Fraction of the delay that is randomized, unless configured.
+pub const DEFAULT_JITTER: f64 = 0.5;
+
+impl RetryBackoff {
+    /// The built-in defaults, every field set.
+    pub fn standard() -> Self {
+        Self {
+            base_secs: Some(DEFAULT_BASE_SECS),
+            max_secs: Some(DEFAULT_MAX_SECS),
+            jitter: Some(DEFAULT_JITTER),
+        }
+    }
+
+    /// This policy with unset fields taken from `fallback`.
+    pub fn or(&self, fallback: &RetryBackoff) -> RetryBackoff {
+        RetryBackoff {
+            base_secs: self.base_secs.or(fallback.base_secs),
+            max_secs: self.max_secs.or(fallback.max_secs),
+            jitter: self.jitter.or(fallback.jitter),
+        }
+    }
+
+    /// Delay after failed attempt number `attempt` (1-based), with `unit`
+    /// — a random number in `[0, 1)` — picking the jitter.
+    pub fn delay(&self, attempt: u32, unit: f64) -> Duration {
+        let base = self.base_secs.unwrap_or(DEFAULT_BASE_SECS);
+        let max = self.max_secs.unwrap_or(DEFAULT_MAX_SECS);
+        let jitter = self.jitter.unwrap_or(DEFAULT_JITTER).clamp(0.0, 1.0);
+        let exponential = base.saturating_mul(2u64.saturating_pow(attempt.saturating_sub(1))).min(max);
+        Duration::from_secs(exponential).mul_f64(1.0 - jitter * unit.clamp(0.0, 1.0))
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_double_up_to_the_cap() {
+        let policy = RetryBackoff { base_secs: Some(10), max_secs: Some(60), jitter: Some(0.0) };
+        let delays: Vec<u64> = (1..=5).map(|n| policy.delay(n, 0.7).as_secs()).collect();
+        assert_eq!(delays, vec![10, 20, 40, 60, 60]);
+        assert_eq!(policy.delay(200, 0.0), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn jitter_shortens_by_up_to_its_fraction() {
+        let policy = RetryBackoff { base_secs: Some(100), jitter: Some(0.25), ..RetryBackoff::default() };
+        assert_eq!(policy.delay(1, 0.0), Duration::from_secs(100));
+        assert_eq!(policy.delay(1, 0.5), Duration::from_millis(87_500));
+        assert!(policy.delay(1, 0.999_999) > Duration::from_secs(75));
+    }
+
+    #[test]
+    fn workflow_policy_overrides_default_field_by_field() {
+        let workflow = RetryBackoff { base_secs: Some(1), ..RetryBackoff::default() };
+        let merged = workflow.or(&RetryBackoff::standard());
+        assert_eq!(merged.base_secs, Some(1));
+        assert_eq!(merged.max_secs, Some(DEFAULT_MAX_SECS));
+        assert_eq!(merged.jitter, Some(DEFAULT_JITTER));
+    }
+}
@@ -0,0 +1,157 @@
+//! Binary data of executions, kept in a blob store.
+//!
+//! [`BinaryData`] is the engine's [`BinaryStore`]: blobs go to a directory
+//! (`file:///var/lib/rusty/binary`) or an S3 bucket
+//! (`s3://bucket/prefix`, credentials from the usual `AWS_*` variables),
+//! and their metadata to the `binary_data` table.  Nodes only ever see
+//! [`BinaryRef`]s, and only load blobs stored within their own project.
+//!
+//! A blob lives as long as the execution that stored it.  Deleting the
+//! execution — retention, erasure, or deleting its workflow — orphans the
+//! blob's row; [`BinaryData::sweep_once`] (run by the pruner) deletes
+//! orphaned blobs from the store and then forgets them.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use object_store::{path::Path, prefix::PrefixStore, ObjectStore};
+use tracing::warn;
+use uuid::Uuid;
+
+use db::DbPool;
+use db::models::BinaryDataRow;
+use db::repository::binary_data as binary_repo;
+use nodes::binary::{BinaryRef, BinaryStore};
+use nodes::NodeError;
+
+use crate::EngineError;
+
+/// Blob store plus metadata for the binary data of executions.
+#[derive(Debug, Clone)]
+pub struct BinaryData {
+    pool: DbPool,
+    store: Arc<dyn ObjectStore>,
+}
+
+impl BinaryData {
+    /// Keep blobs in `store`.
+    pub fn new(pool: DbPool, store: Arc<dyn ObjectStore>) -> Self {
+        Self { pool, store }
+    }
+
+    /// Keep blobs where `url` says: `file:///dir` (created if missing) or
+    /// `s3://bucket[/prefix]`.
+    pub fn from_url(pool: DbPool, url: &str) -> Result<Self, EngineError> {
+        let store: Arc<dyn ObjectStore> = if let Some(dir) = url.strip_prefix("file://") {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| EngineError::BinaryData(format!("cannot create '{dir}': {e}")))?;
+            Arc::new(object_store::local::LocalFileSystem::new_with_prefix(dir).map_err(storage_error)?)
+        } else if let Some(location) = url.strip_prefix("s3://") {
+            let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+            let s3 = object_store::aws::AmazonS3Builder::from_env()
+                .with_bucket_name(bucket)
+                .build()
+                .map_err(storage_error)?;
+            match prefix.trim_matches('/') {
+                "" => Arc::new(s3),
+                prefix => Arc::new(PrefixStore::new(s3, prefix)),
+            }
+        } else {
+            return Err(EngineError::BinaryData(format!(
+                "unsupported binary data store '{url}'; use file:///path or s3://bucket/prefix"
+            )));
+        };
+        Ok(Self::new(pool, store))
+    }
+
+    /// Blob `id` of execution `execution_id` and its bytes.
+    ///
+    /// # Errors
+    /// [`db::DbError::NotFound`] if the execution has no such blob.
+    pub async fn load(&self, execution_id: Uuid, id: Uuid) -> Result<(BinaryDataRow, Vec<u8>), EngineError> {
+        let row = binary_repo::get_binary_data(&self.pool, id).await?;
+        if row.execution_id != Some(execution_id) {
+            return Err(db::DbError::NotFound.into());
+        }
+        let bytes = self.read(&row).await?;
+        Ok((row, bytes))
+    }
+
+    /// Delete up to `limit` blobs whose execution is gone; returns how many
+    /// were deleted.
+    pub async fn sweep_once(&self, limit: i64) -> Result<u64, EngineError> {
+        let mut deleted = 0;
+        for row in binary_repo::list_orphaned_binary_data(&self.pool, limit).await? {
+            match self.store.delete(&Path::from(row.storage_key.as_str())).await {
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => {}
+                Err(e) => {
+                    warn!("cannot delete binary data {}: {}", row.id, e);
+                    continue;
+                }
+            }
+            binary_repo::delete_binary_data(&self.pool, row.id).await?;
+            deleted += 1;
+        }
+        Ok(deleted)
+    }
+
+    async fn read(&self, row: &BinaryDataRow) -> Result<Vec<u8>, EngineError> {
+        let blob = self.store.get(&Path::from(row.storage_key.as_str())).await.map_err(storage_error)?;
+        let bytes = blob.bytes().await.map_err(storage_error)?;
+        Ok(bytes.to_vec())
+    }
+}
+
+#[async_trait]
+impl BinaryStore for BinaryData {
+    async fn put(
+        &self,
+        execution_id: Uuid,
+        data: Vec<u8>,
+        mime_type: &str,
+        file_name: Option<&str>,
+    ) -> Result<BinaryRef, NodeError> {
+        let id = Uuid::new_v4();
+        let storage_key = format!("executions/{execution_id}/{id}");
+        let size = data.len() as u64;
+        self.store
+            .put(&Path::from(storage_key.as_str()), data.into())
+            .await
+            .map_err(|e| NodeError::Retryable(format!("cannot store binary data: {e}")))?;
+
+        let recorded = binary_repo::insert_binary_data(
+            &self.pool, id, execution_id, &storage_key, mime_type, file_name, size as i64,
+        )
+        .await;
+        if let Err(e) = recorded {
+            // Nothing refers to the blob; do not leave it behind.
+            let _ = self.store.delete(&Path::from(storage_key.as_str())).await;
+            return Err(NodeError::Retryable(format!("cannot record binary data: {e}")));
+        }
+
+        Ok(BinaryRef {
+            id,
+            mime_type: mime_type.to_owned(),
+            file_name: file_name.map(str::to_owned),
+            size,
+        })
+    }
+
+    async fn get(&self, execution_id: Uuid, reference: &BinaryRef) -> Result<Vec<u8>, NodeError> {
+        let row = binary_repo::get_project_binary_data(&self.pool, reference.id, execution_id)
+            .await
+            .map_err(|e| match e {
+                // Blobs of other projects do not exist as far as the caller
+                // can tell.
+                db::DbError::NotFound => NodeError::Fatal(format!("binary data {} does not exist", reference.id)),
+                e => NodeError::Retryable(format!("cannot look up binary data {}: {e}", reference.id)),
+            })?;
+        self.read(&row)
+            .await
+            .map_err(|e| NodeError::Retryable(format!("cannot load binary data {}: {e}", reference.id)))
+    }
+}
+
+fn storage_error(e: object_store::Error) -> EngineError {
+    EngineError::BinaryData(e.to_string())
+}
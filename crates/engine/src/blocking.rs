@@ -0,0 +1,148 @@
+//! Bounded pool for CPU-heavy node calls.
+//!
+//! Nodes that report [`ExecutableNode::is_blocking`] (CSV parsing, hashing,
+//! XML and HTML parsing) would otherwise hold an async worker thread for the
+//! whole call and delay every other task scheduled on it — webhook handlers
+//! included.  The runner moves such calls onto tokio's blocking threads via
+//! `spawn_blocking`, with a semaphore capping how many run at once so a
+//! burst of heavy nodes cannot oversubscribe the CPU either.
+//!
+//! One pool is meant to be shared by every executor in the process; clones
+//! share the same permits.
+
+use std::sync::Arc;
+
+use serde_json::Value;
+use tokio::runtime::Handle;
+use tokio::sync::Semaphore;
+
+use nodes::{ExecutableNode, NodeError};
+use nodes::traits::ExecutionContext;
+
+/// Runs blocking node calls off the async runtime, at most `size` at a time.
+#[derive(Debug, Clone)]
+pub struct BlockingPool {
+    permits: Arc<Semaphore>,
+    size: usize,
+}
+
+impl BlockingPool {
+    /// A pool running at most `size` node calls concurrently (minimum 1).
+    pub fn new(size: usize) -> Self {
+        let size = size.max(1);
+        Self { permits: Arc::new(Semaphore::new(size)), size }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// How many more node calls could start right now.
+    pub fn available(&self) -> usize {
+        self.permits.available_permits()
+    }
+
+    /// Execute `node` on a blocking thread once a slot is free.
+    ///
+    /// # Errors
+    /// The node's own error, or [`NodeError::Fatal`] if it panicked.
+    pub async fn run(
+        &self,
+        node: Arc<dyn ExecutableNode>,
+        input: Value,
+        ctx: &ExecutionContext,
+    ) -> Result<Value, NodeError> {
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("blocking pool semaphore is never closed");
+        let ctx = ctx.clone();
+        let handle = Handle::current();
+        let task = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            handle.block_on(node.execute(input, &ctx))
+        });
+        match task.await {
+            Ok(result) => result,
+            Err(e) => Err(NodeError::Fatal(format!("node panicked on the blocking pool: {e}"))),
+        }
+    }
+}
+
+impl Default for BlockingPool {
+    /// One slot per available CPU.
+    fn default() -> Self {
+        Self::new(std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4))
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    /// Sleeps on the thread (not the runtime) and records peak concurrency.
+    #[derive(Default)]
+    struct Busy {
+        running: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ExecutableNode for Busy {
+        async fn execute(&self, input: Value, _ctx: &ExecutionContext) -> Result<Value, NodeError> {
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(30));
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            if input == json!("boom") {
+                panic!("boom");
+            }
+            Ok(input)
+        }
+
+        fn is_blocking(&self) -> bool {
+            true
+        }
+    }
+
+    fn ctx() -> ExecutionContext {
+        ExecutionContext::new(uuid::Uuid::new_v4(), uuid::Uuid::new_v4(), json!({}))
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn parallelism_is_bounded_by_the_pool_size() {
+        let pool = BlockingPool::new(2);
+        let busy = Arc::new(Busy::default());
+
+        let calls: Vec<_> = (0..6)
+            .map(|i| {
+                let (pool, busy, ctx) = (pool.clone(), busy.clone(), ctx());
+                tokio::spawn(async move { pool.run(busy, json!(i), &ctx).await })
+            })
+            .collect();
+        for (i, call) in calls.into_iter().enumerate() {
+            assert_eq!(call.await.unwrap().unwrap(), json!(i));
+        }
+
+        assert_eq!(busy.peak.load(Ordering::SeqCst), 2);
+        assert_eq!(pool.available(), 2);
+    }
+
+    #[tokio::test]
+    async fn panics_become_fatal_errors_and_release_the_slot() {
+        let pool = BlockingPool::new(1);
+        let err = pool.run(Arc::new(Busy::default()), json!("boom"), &ctx()).await.unwrap_err();
+        assert!(matches!(err, NodeError::Fatal(msg) if msg.contains("panicked")));
+        assert_eq!(pool.available(), 1);
+    }
+}
@@ -0,0 +1,184 @@
+//! Portable workflow bundles, for promoting workflows between environments.
+//!
+//! A [`WorkflowBundle`] carries a workflow's name, definition, and tags,
+//! plus the names of the secrets its nodes read — found through the
+//! credential fields each node type declares in its descriptor.  Secret
+//! values never leave the environment; whoever imports the bundle has to
+//! provide them there.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use db::DbPool;
+use db::models::WorkflowRow;
+use db::repository::workflows as wf_repo;
+
+use crate::executor::NodeRegistry;
+use crate::{EngineError, Workflow};
+
+/// Bundle format written by [`export`]; imports accept this version and
+/// older ones.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// A workflow as exported from one environment for import into another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowBundle {
+    pub format_version: u32,
+    pub name: String,
+    pub definition: Value,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Secrets the workflow's nodes read, by name; values are not included.
+    #[serde(default)]
+    pub secret_keys: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exported_at: Option<DateTime<Utc>>,
+}
+
+/// Bundle the stored workflow `row`, naming the secrets its nodes read as
+/// `registry` describes them.
+///
+/// # Errors
+/// [`EngineError::InvalidWorkflow`] when the stored definition does not
+/// parse.
+pub fn export(row: &WorkflowRow, registry: &NodeRegistry) -> Result<WorkflowBundle, EngineError> {
+    let workflow: Workflow = serde_json::from_value(row.definition.clone())
+        .map_err(|e| EngineError::InvalidWorkflow { workflow_id: row.id, message: e.to_string() })?;
+    Ok(WorkflowBundle {
+        format_version: FORMAT_VERSION,
+        name: row.name.clone(),
+        definition: row.definition.clone(),
+        tags: row.tags.clone(),
+        secret_keys: secret_keys(&workflow, registry),
+        exported_at: Some(Utc::now()),
+    })
+}
+
+/// Names of the secrets `workflow`'s nodes read: the values of the config
+/// fields their node types declare as credentials, sorted and deduplicated.
+/// Nodes of types missing from `registry` are skipped.
+pub fn secret_keys(workflow: &Workflow, registry: &NodeRegistry) -> Vec<String> {
+    let mut keys: Vec<String> = workflow
+        .nodes
+        .iter()
+        .filter_map(|node| Some((node, registry.get(&node.node_type)?.descriptor())))
+        .flat_map(|(node, descriptor)| {
+            descriptor.credentials.into_iter().filter_map(|credential| {
+                let pointer = format!("/{}", credential.config_field.replace('.', "/"));
+                node.config.pointer(&pointer)?.as_str().filter(|key| !key.is_empty()).map(str::to_owned)
+            })
+        })
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// Check that `bundle` can be imported: a known format and a valid
+/// definition.  Returns the parsed workflow.
+///
+/// # Errors
+/// [`EngineError::InvalidWorkflow`] (with a nil id) describing the problem,
+/// and the DAG errors of [`crate::validate_dag`].
+pub fn check(bundle: &WorkflowBundle) -> Result<Workflow, EngineError> {
+    let invalid = |message: String| EngineError::InvalidWorkflow { workflow_id: uuid::Uuid::nil(), message };
+    if bundle.format_version == 0 || bundle.format_version > FORMAT_VERSION {
+        return Err(invalid(format!("unsupported bundle format {}", bundle.format_version)));
+    }
+    let workflow: Workflow = serde_json::from_value(bundle.definition.clone()).map_err(|e| invalid(e.to_string()))?;
+    crate::validate_dag(&workflow)?;
+    Ok(workflow)
+}
+
+/// Store `bundle` in project `project_id` with `tags`: as a new workflow,
+/// or as the next version of the one with the bundle's name.  Returns the
+/// stored row and whether it was created.
+///
+/// # Errors
+/// Everything [`check`] returns, [`EngineError::AmbiguousWorkflowName`]
+/// when several workflows have the name, and database errors — among them
+/// `DbError::WebhookPathTaken`, and `DbError::VersionConflict` when the
+/// workflow changed while it was being replaced.
+pub async fn import(
+    pool: &DbPool,
+    project_id: uuid::Uuid,
+    bundle: WorkflowBundle,
+    tags: &[String],
+    author: Option<&str>,
+) -> Result<(WorkflowRow, bool), EngineError> {
+    check(&bundle)?;
+    let existing = wf_repo::find_workflows_by_name(pool, project_id, &bundle.name).await?;
+    match existing.as_slice() {
+        [] => {
+            let row = wf_repo::create_workflow(pool, &bundle.name, bundle.definition, true, tags, author, project_id).await?;
+            Ok((row, true))
+        }
+        [current] => {
+            let updated = wf_repo::update_workflow(
+                pool,
+                current.id,
+                &bundle.name,
+                bundle.definition,
+                author,
+                Some(current.version),
+            )
+            .await?;
+            Ok((wf_repo::set_workflow_tags(pool, updated.id, tags).await?, false))
+        }
+        _ => Err(EngineError::AmbiguousWorkflowName(bundle.name)),
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn workflow(nodes: Value) -> Workflow {
+        serde_json::from_value(json!({
+            "id": uuid::Uuid::nil(),
+            "name": "deploy",
+            "trigger": { "type": "manual" },
+            "nodes": nodes,
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn secret_keys_come_from_declared_credential_fields() {
+        let workflow = workflow(json!([
+            { "id": "jira", "node_type": "jira", "config": { "base_url": "https://jira.local", "api_token_secret": "JIRA_TOKEN" } },
+            { "id": "tag", "node_type": "classify", "config": { "provider": { "type": "openai", "api_key_secret": "OPENAI" } } },
+            { "id": "again", "node_type": "jira", "config": { "api_token_secret": "JIRA_TOKEN", "token": "not a field" } },
+            { "id": "custom", "node_type": "not_registered", "config": { "api_token_secret": "IGNORED" } }
+        ]));
+        assert_eq!(secret_keys(&workflow, &nodes::default_registry()), ["JIRA_TOKEN", "OPENAI"]);
+    }
+
+    #[test]
+    fn check_rejects_unknown_formats_and_bad_definitions() {
+        let definition = serde_json::to_value(workflow(json!([{ "id": "a", "node_type": "noop", "config": null }]))).unwrap();
+        let mut bundle = WorkflowBundle {
+            format_version: FORMAT_VERSION,
+            name: "deploy".into(),
+            definition,
+            tags: Vec::new(),
+            secret_keys: Vec::new(),
+            exported_at: None,
+        };
+        assert!(check(&bundle).is_ok());
+
+        bundle.format_version = FORMAT_VERSION + 1;
+        assert!(check(&bundle).is_err());
+
+        bundle.format_version = FORMAT_VERSION;
+        bundle.definition = json!({ "nodes": 1 });
+        assert!(check(&bundle).is_err());
+    }
+}
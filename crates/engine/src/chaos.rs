@@ -0,0 +1,223 @@
+//! Chaos mode — fault injection for resilience testing.
+//!
+//! When an executor is built with [`WorkflowExecutor::with_chaos`], every
+//! node attempt first rolls the dice:
+//!
+//! * **latency** — sleep for a random duration in `latency_min..=latency_max`
+//!   before running the node;
+//! * **failure** — skip the node and report a retryable error, exercising
+//!   the retry/back-off path;
+//! * **crash** — stop the execution on the spot with
+//!   [`EngineError::InjectedCrash`], persisting nothing, exactly as if the
+//!   worker process had died.  The execution stays `running` and its job
+//!   `processing`, which is what recovery has to cope with.
+//!
+//! Chaos is strictly opt-in and meant for tests and staging; it is
+//! configured from `CHAOS_*` environment variables (see
+//! [`ChaosConfig::from_env`]).  With a `seed`, the sequence of injected
+//! faults is reproducible.
+//!
+//! [`WorkflowExecutor::with_chaos`]: crate::WorkflowExecutor::with_chaos
+//! [`EngineError::InjectedCrash`]: crate::EngineError::InjectedCrash
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Fault injection rates.  Each rate is a probability per node attempt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChaosConfig {
+    pub failure_rate: f64,
+    pub latency_rate: f64,
+    pub latency_min: Duration,
+    pub latency_max: Duration,
+    pub crash_rate: f64,
+    /// Seed for a reproducible fault sequence; random when unset.
+    pub seed: Option<u64>,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            failure_rate: 0.0,
+            latency_rate: 0.0,
+            latency_min: Duration::from_millis(100),
+            latency_max: Duration::from_millis(1000),
+            crash_rate: 0.0,
+            seed: None,
+        }
+    }
+}
+
+impl ChaosConfig {
+    /// Read `CHAOS_FAILURE_RATE`, `CHAOS_LATENCY_RATE`, `CHAOS_LATENCY_MS`
+    /// (`"250"` or a range like `"50-500"`), `CHAOS_CRASH_RATE`, and
+    /// `CHAOS_SEED`.  Returns `Ok(None)` unless some rate is non-zero.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Option<Self>, String> {
+        let rate = |name: &str| -> Result<f64, String> {
+            match var(name) {
+                None => Ok(0.0),
+                Some(v) => match v.trim().parse::<f64>() {
+                    Ok(r) if (0.0..=1.0).contains(&r) => Ok(r),
+                    _ => Err(format!("{name} must be a number between 0 and 1, got '{v}'")),
+                },
+            }
+        };
+
+        let mut config = Self {
+            failure_rate: rate("CHAOS_FAILURE_RATE")?,
+            latency_rate: rate("CHAOS_LATENCY_RATE")?,
+            crash_rate: rate("CHAOS_CRASH_RATE")?,
+            ..Self::default()
+        };
+        if let Some(v) = var("CHAOS_LATENCY_MS") {
+            let invalid = || format!("CHAOS_LATENCY_MS must be '<ms>' or '<min>-<max>', got '{v}'");
+            let (min, max) = v.split_once('-').unwrap_or((&v, &v));
+            let min: u64 = min.trim().parse().map_err(|_| invalid())?;
+            let max: u64 = max.trim().parse().map_err(|_| invalid())?;
+            if min > max {
+                return Err(invalid());
+            }
+            config.latency_min = Duration::from_millis(min);
+            config.latency_max = Duration::from_millis(max);
+        }
+        if let Some(v) = var("CHAOS_SEED") {
+            config.seed =
+                Some(v.trim().parse().map_err(|_| format!("CHAOS_SEED must be an integer, got '{v}'"))?);
+        }
+
+        Ok(config.is_active().then_some(config))
+    }
+
+    /// Whether any fault can be injected.
+    pub fn is_active(&self) -> bool {
+        self.failure_rate > 0.0 || self.latency_rate > 0.0 || self.crash_rate > 0.0
+    }
+}
+
+/// A fault that replaces a node attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Report a retryable error instead of running the node.
+    Failure,
+    /// Abandon the execution as if the worker died.
+    Crash,
+}
+
+/// What chaos decided for one node attempt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Injection {
+    /// Delay before the attempt.
+    pub latency: Option<Duration>,
+    pub fault: Option<Fault>,
+}
+
+/// Seeded fault injector shared by all node attempts of an executor.
+#[derive(Debug)]
+pub struct Chaos {
+    config: ChaosConfig,
+    rng: Mutex<StdRng>,
+}
+
+impl Chaos {
+    pub fn new(config: ChaosConfig) -> Self {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self { config, rng: Mutex::new(rng) }
+    }
+
+    pub fn config(&self) -> &ChaosConfig {
+        &self.config
+    }
+
+    /// Decide the faults for the next node attempt.
+    pub fn roll(&self) -> Injection {
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        let mut hit = |rate: f64| rate > 0.0 && rng.gen_bool(rate.min(1.0));
+
+        let crash = hit(self.config.crash_rate);
+        let failure = hit(self.config.failure_rate);
+        let latency = hit(self.config.latency_rate);
+
+        let fault = if crash {
+            Some(Fault::Crash)
+        } else if failure {
+            Some(Fault::Failure)
+        } else {
+            None
+        };
+        let latency = latency.then(|| {
+            let (min, max) = (self.config.latency_min, self.config.latency_max);
+            if min >= max { min } else { rng.gen_range(min..=max) }
+        });
+        Injection { latency, fault }
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn from(vars: &[(&str, &str)]) -> Result<Option<ChaosConfig>, String> {
+        let vars: HashMap<String, String> =
+            vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        ChaosConfig::from_vars(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn reads_configuration_from_environment() {
+        assert_eq!(from(&[]).unwrap(), None);
+        let config = from(&[
+            ("CHAOS_FAILURE_RATE", "0.2"),
+            ("CHAOS_LATENCY_MS", "50-500"),
+            ("CHAOS_SEED", "7"),
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(config.failure_rate, 0.2);
+        assert_eq!(config.latency_min, Duration::from_millis(50));
+        assert_eq!(config.latency_max, Duration::from_millis(500));
+        assert_eq!(config.seed, Some(7));
+
+        assert!(from(&[("CHAOS_CRASH_RATE", "1.5")]).is_err());
+        assert!(from(&[("CHAOS_FAILURE_RATE", "0.1"), ("CHAOS_LATENCY_MS", "9-3")]).is_err());
+    }
+
+    #[test]
+    fn seeded_rolls_are_reproducible_and_respect_rates() {
+        let config = ChaosConfig {
+            failure_rate: 0.5,
+            latency_rate: 1.0,
+            latency_min: Duration::from_millis(10),
+            latency_max: Duration::from_millis(20),
+            seed: Some(42),
+            ..ChaosConfig::default()
+        };
+        let a: Vec<Injection> = (0..50).map({
+            let chaos = Chaos::new(config.clone());
+            move |_| chaos.roll()
+        }).collect();
+        let b: Vec<Injection> = (0..50).map({
+            let chaos = Chaos::new(config.clone());
+            move |_| chaos.roll()
+        }).collect();
+        assert_eq!(a, b);
+
+        assert!(a.iter().all(|i| i.fault != Some(Fault::Crash)));
+        assert!(a.iter().all(|i| matches!(i.latency, Some(d) if d >= config.latency_min && d <= config.latency_max)));
+        let failures = a.iter().filter(|i| i.fault == Some(Fault::Failure)).count();
+        assert!((10..=40).contains(&failures), "{failures} failures out of 50");
+    }
+}
@@ -4,12 +4,19 @@
 //! 1. Node IDs must be unique within the workflow.
 //! 2. Every edge must reference valid node IDs (both `from` and `to`).
 //! 3. The directed graph must be acyclic (topological sort must succeed).
+//! 4. A cron trigger's expression and timezone must parse and fire at least
+//!    once more.
+//! 5. A manual trigger's input schema must be a valid JSON Schema.
+//! 6. A debounced trigger's window must be at most a day.
 //!
-//! Returns a topologically-sorted list of node IDs on success.
+//! Returns a topologically-sorted list of node IDs on success.  The order is
+//! deterministic: ties are broken by node declaration and edge order.
 
 use std::collections::{HashMap, HashSet, VecDeque};
 
-use crate::{EngineError, models::Workflow};
+use serde_json::Value;
+
+use crate::{EngineError, models::{Debounce, Workflow}, scheduler::CronSchedule, triggers};
 
 /// Validate the workflow's DAG and return nodes in topological execution order.
 ///
@@ -17,6 +24,9 @@ use crate::{EngineError, models::Workflow};
 /// - [`EngineError::DuplicateNodeId`] if two nodes share an ID.
 /// - [`EngineError::UnknownNodeReference`] if an edge references a missing node.
 /// - [`EngineError::CycleDetected`] if the graph is not acyclic.
+/// - [`EngineError::InvalidCron`] if the cron trigger cannot be scheduled.
+/// - [`EngineError::InvalidInputSchema`] if the input schema does not compile.
+/// - [`EngineError::InvalidDefinition`] if the debounce window is too long.
 pub fn validate_dag(workflow: &Workflow) -> Result<Vec<String>, EngineError> {
     // -----------------------------------------------------------------------
     // 1. Ensure node IDs are unique
@@ -68,11 +78,13 @@ pub fn validate_dag(workflow: &Workflow) -> Result<Vec<String>, EngineError> {
         *in_degree.entry(edge.to.as_str()).or_insert(0) += 1;
     }
 
-    // Seed the queue with nodes that have no incoming edges.
-    let mut queue: VecDeque<&str> = in_degree
+    // Seed the queue with nodes that have no incoming edges, in declaration
+    // order so the result does not depend on hash map iteration.
+    let mut queue: VecDeque<&str> = workflow
+        .nodes
         .iter()
-        .filter(|(_, &d)| d == 0)
-        .map(|(&id, _)| id)
+        .map(|n| n.id.as_str())
+        .filter(|id| in_degree[id] == 0)
         .collect();
 
     let mut sorted: Vec<String> = Vec::with_capacity(workflow.nodes.len());
@@ -96,6 +108,31 @@ pub fn validate_dag(workflow: &Workflow) -> Result<Vec<String>, EngineError> {
         return Err(EngineError::CycleDetected);
     }
 
+    // -----------------------------------------------------------------------
+    // 4. Check the cron schedule
+    // -----------------------------------------------------------------------
+    if let Some(schedule) = CronSchedule::of(&workflow.trigger) {
+        schedule?;
+    }
+
+    // -----------------------------------------------------------------------
+    // 5. Check the input schema
+    // -----------------------------------------------------------------------
+    triggers::check_input(&workflow.trigger, &Value::Null)?;
+
+    // -----------------------------------------------------------------------
+    // 6. Bound the debounce window
+    // -----------------------------------------------------------------------
+    if let Some(debounce) = workflow.trigger.debounce() {
+        if debounce.window_secs > Debounce::MAX_WINDOW_SECS {
+            return Err(EngineError::InvalidDefinition(format!(
+                "debounce window_secs is {}, at most {} is allowed",
+                debounce.window_secs,
+                Debounce::MAX_WINDOW_SECS
+            )));
+        }
+    }
+
     Ok(sorted)
 }
 
@@ -114,6 +151,7 @@ mod tests {
             id: id.to_string(),
             node_type: "mock".into(),
             config: serde_json::Value::Null,
+            worker_tags: Vec::new(),
         }
     }
 
@@ -121,10 +159,23 @@ mod tests {
         Workflow {
             id: Uuid::new_v4(),
             name: "test".into(),
-            trigger: Trigger::Manual,
+            trigger: Trigger::Manual { input_schema: None },
             nodes,
             edges,
             created_at: Utc::now(),
+            business_key: None,
+            labels: Vec::new(),
+            retention: None,
+            quota: None,
+            priority: 0,
+            queue: None,
+            max_attempts: None,
+            retry_backoff: None,
+            worker_tags: Vec::new(),
+            inheritance: None,
+            error_workflow_id: None,
+            slow_node_alerts: false,
+            params: Default::default(),
         }
     }
 
@@ -134,8 +185,8 @@ mod tests {
         let workflow = make_workflow(
             vec![make_node("a"), make_node("b"), make_node("c")],
             vec![
-                Edge { from: "a".into(), to: "b".into() },
-                Edge { from: "b".into(), to: "c".into() },
+                Edge::new("a", "b"),
+                Edge::new("b", "c"),
             ],
         );
 
@@ -153,10 +204,10 @@ mod tests {
         let workflow = make_workflow(
             vec![make_node("a"), make_node("b"), make_node("c"), make_node("d")],
             vec![
-                Edge { from: "a".into(), to: "b".into() },
-                Edge { from: "a".into(), to: "c".into() },
-                Edge { from: "b".into(), to: "d".into() },
-                Edge { from: "c".into(), to: "d".into() },
+                Edge::new("a", "b"),
+                Edge::new("a", "c"),
+                Edge::new("b", "d"),
+                Edge::new("c", "d"),
             ],
         );
 
@@ -183,7 +234,7 @@ mod tests {
     fn edge_referencing_missing_node_is_rejected() {
         let workflow = make_workflow(
             vec![make_node("a")],
-            vec![Edge { from: "a".into(), to: "ghost".into() }], // ghost doesn't exist
+            vec![Edge::new("a", "ghost")], // ghost doesn't exist
         );
         assert!(matches!(
             validate_dag(&workflow),
@@ -197,9 +248,9 @@ mod tests {
         let workflow = make_workflow(
             vec![make_node("a"), make_node("b"), make_node("c")],
             vec![
-                Edge { from: "a".into(), to: "b".into() },
-                Edge { from: "b".into(), to: "c".into() },
-                Edge { from: "c".into(), to: "a".into() }, // back-edge
+                Edge::new("a", "b"),
+                Edge::new("b", "c"),
+                Edge::new("c", "a"), // back-edge
             ],
         );
         assert!(matches!(validate_dag(&workflow), Err(EngineError::CycleDetected)));
@@ -211,4 +262,45 @@ mod tests {
         let sorted = validate_dag(&workflow).expect("single node should be valid");
         assert_eq!(sorted, vec!["solo"]);
     }
+
+    #[test]
+    fn unschedulable_cron_trigger_is_rejected() {
+        let mut workflow = make_workflow(vec![make_node("solo")], vec![]);
+        workflow.trigger = Trigger::Cron { expression: "0 30 8 * * 1-5".into(), timezone: Some("Asia/Tokyo".into()) };
+        assert!(validate_dag(&workflow).is_ok());
+
+        workflow.trigger = Trigger::Cron { expression: "0 9 * *".into(), timezone: None };
+        assert!(matches!(validate_dag(&workflow), Err(EngineError::InvalidCron { .. })));
+        workflow.trigger = Trigger::Cron { expression: "0 9 * * *".into(), timezone: Some("Nowhere".into()) };
+        assert!(matches!(validate_dag(&workflow), Err(EngineError::InvalidCron { .. })));
+    }
+
+    #[test]
+    fn invalid_input_schema_is_rejected() {
+        let mut workflow = make_workflow(vec![make_node("solo")], vec![]);
+        workflow.trigger = Trigger::Manual { input_schema: Some(serde_json::json!({ "type": "object" })) };
+        assert!(validate_dag(&workflow).is_ok());
+
+        workflow.trigger = Trigger::Manual { input_schema: Some(serde_json::json!({ "type": "thing" })) };
+        assert!(matches!(validate_dag(&workflow), Err(EngineError::InvalidInputSchema { .. })));
+    }
+
+    #[test]
+    fn debounce_windows_longer_than_a_day_are_rejected() {
+        let mut workflow = make_workflow(vec![make_node("solo")], vec![]);
+        let debounced = |window_secs: u64| {
+            serde_json::from_value(serde_json::json!({
+                "type": "webhook",
+                "path": "orders",
+                "debounce": { "window_secs": window_secs }
+            }))
+            .unwrap()
+        };
+        workflow.trigger = debounced(Debounce::MAX_WINDOW_SECS);
+        assert!(validate_dag(&workflow).is_ok());
+        for window_secs in [Debounce::MAX_WINDOW_SECS + 1, u64::MAX] {
+            workflow.trigger = debounced(window_secs);
+            assert!(matches!(validate_dag(&workflow), Err(EngineError::InvalidDefinition(_))));
+        }
+    }
 }
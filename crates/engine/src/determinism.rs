@@ -0,0 +1,334 @@
+//! Determinism report — why "same input, different result" can happen.
+//!
+//! Two halves:
+//!
+//! * [`analyze`] statically flags constructs in a workflow that can make
+//!   repeated runs disagree: random nodes (`split_ab` without a sticky key,
+//!   `crypto` random tokens, sampled `llm` completions), time-dependent
+//!   nodes (`datetime` `now`, `time_gate`, `batch_collect`), and fan-in
+//!   nodes, whose input is the output of whichever active parent sorts last.
+//! * [`compare`] lines up the node runs of two executions by their
+//!   input/output hashes (see [`hash_value`]) and reports where they
+//!   diverge.  A node that received the same input but produced a different
+//!   output is the source of the divergence; a differing input only means
+//!   something upstream diverged first.
+//!
+//! The executor records the hashes for workflows with the
+//! `determinism_report` flag on; for other runs they are computed from the
+//! stored node input/output.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use db::models::{NodeExecutionRow, NodeHashes};
+
+use crate::Workflow;
+
+/// Category of a nondeterministic construct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    Random,
+    TimeDependent,
+    UnorderedFanIn,
+}
+
+/// One nondeterministic construct found by [`analyze`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub node_id: String,
+    pub kind: FindingKind,
+    pub message: String,
+}
+
+/// Flag the nodes of `workflow` whose output may differ between runs with
+/// the same trigger input.
+pub fn analyze(workflow: &Workflow) -> Vec<Finding> {
+    let mut parents: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for edge in &workflow.edges {
+        parents.entry(edge.to.as_str()).or_default().insert(edge.from.as_str());
+    }
+
+    let mut findings = Vec::new();
+    for node in &workflow.nodes {
+        let config = &node.config;
+        let operation = config.get("operation").and_then(Value::as_str);
+        let mut flag = |kind, message: &str| {
+            findings.push(Finding { node_id: node.id.clone(), kind, message: message.into() })
+        };
+
+        match node.node_type.as_str() {
+            "split_ab" if config.get("sticky_key").is_none() => {
+                flag(FindingKind::Random, "picks a branch at random; set `sticky_key` to make it stable")
+            }
+            "crypto" if operation == Some("random") => {
+                flag(FindingKind::Random, "generates a random token")
+            }
+            "llm" if config.get("temperature").and_then(Value::as_f64) != Some(0.0) => {
+                flag(FindingKind::Random, "samples model output; set `temperature` to 0 to reduce variation")
+            }
+            "datetime" if operation == Some("now") => {
+                flag(FindingKind::TimeDependent, "outputs the current time")
+            }
+            "time_gate" => flag(FindingKind::TimeDependent, "passes or defers depending on the time of day"),
+            "batch_collect" => {
+                flag(FindingKind::TimeDependent, "flushes depending on arrival times and batch age")
+            }
+            _ => {}
+        }
+
+        if let Some(node_parents) = parents.get(node.id.as_str()).filter(|p| p.len() > 1) {
+            let mut names: Vec<&str> = node_parents.iter().copied().collect();
+            names.sort_unstable();
+            flag(
+                FindingKind::UnorderedFanIn,
+                &format!(
+                    "receives the output of only one of its parents ({}), whichever active one sorts last",
+                    names.join(", ")
+                ),
+            );
+        }
+    }
+    findings
+}
+
+/// Stable SHA-256 hex digest of a JSON value.
+///
+/// Object keys are serialized in sorted order, so equal values always hash
+/// the same regardless of how they were built.
+pub fn hash_value(value: &Value) -> String {
+    let mut hasher = Sha256::new();
+    write_canonical(&mut hasher, value);
+    format!("{:x}", hasher.finalize())
+}
+
+fn write_canonical(hasher: &mut Sha256, value: &Value) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort_unstable();
+            hasher.update(b"{");
+            for key in keys {
+                hasher.update(Value::String(key.clone()).to_string().as_bytes());
+                hasher.update(b":");
+                write_canonical(hasher, &map[key]);
+                hasher.update(b",");
+            }
+            hasher.update(b"}");
+        }
+        Value::Array(items) => {
+            hasher.update(b"[");
+            for item in items {
+                write_canonical(hasher, item);
+                hasher.update(b",");
+            }
+            hasher.update(b"]");
+        }
+        scalar => hasher.update(scalar.to_string().as_bytes()),
+    }
+}
+
+/// Hashes of a node run's input and output.
+pub fn node_hashes(input: &Value, output: Option<&Value>) -> NodeHashes {
+    NodeHashes { input: hash_value(input), output: output.map(hash_value) }
+}
+
+/// How one node's runs differ between two executions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DivergenceKind {
+    /// Same input, different output (or outcome): this node is
+    /// nondeterministic.
+    Output,
+    /// Different input: something upstream diverged.
+    Input,
+    /// The node ran in the first execution only.
+    OnlyInFirst,
+    /// The node ran in the second execution only.
+    OnlyInSecond,
+}
+
+/// A node whose runs differ.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Divergence {
+    pub node_id: String,
+    pub kind: DivergenceKind,
+}
+
+/// Result of [`compare`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Comparison {
+    pub identical: bool,
+    /// The first node (in the first execution's order) that produced a
+    /// different output from the same input.
+    pub first_divergent_node: Option<String>,
+    pub divergences: Vec<Divergence>,
+}
+
+/// Compare the node runs of two executions; a node's last recorded run
+/// counts.
+pub fn compare(first: &[NodeExecutionRow], second: &[NodeExecutionRow]) -> Comparison {
+    let (first_order, first_runs) = index_runs(first);
+    let (second_order, second_runs) = index_runs(second);
+
+    let mut divergences = Vec::new();
+    for node_id in &first_order {
+        let kind = match second_runs.get(node_id) {
+            None => Some(DivergenceKind::OnlyInFirst),
+            Some(theirs) => {
+                let ours = &first_runs[node_id];
+                if ours.input != theirs.input {
+                    Some(DivergenceKind::Input)
+                } else if ours != theirs {
+                    Some(DivergenceKind::Output)
+                } else {
+                    None
+                }
+            }
+        };
+        if let Some(kind) = kind {
+            divergences.push(Divergence { node_id: node_id.to_string(), kind });
+        }
+    }
+    for node_id in second_order.iter().filter(|id| !first_runs.contains_key(*id)) {
+        divergences.push(Divergence { node_id: node_id.to_string(), kind: DivergenceKind::OnlyInSecond });
+    }
+
+    Comparison {
+        identical: divergences.is_empty(),
+        first_divergent_node: divergences
+            .iter()
+            .find(|d| d.kind == DivergenceKind::Output)
+            .map(|d| d.node_id.clone()),
+        divergences,
+    }
+}
+
+/// What is compared of one node run.
+#[derive(Debug, PartialEq, Eq)]
+struct Run {
+    input: String,
+    output: Option<String>,
+    succeeded: bool,
+}
+
+/// Node IDs in first-run order, and each node's last run.
+fn index_runs(rows: &[NodeExecutionRow]) -> (Vec<&str>, HashMap<&str, Run>) {
+    let mut order = Vec::new();
+    let mut runs = HashMap::new();
+    for row in rows {
+        let run = Run {
+            input: row.input_hash.clone().unwrap_or_else(|| hash_value(&row.input)),
+            output: row.output_hash.clone().or_else(|| row.output.as_ref().map(hash_value)),
+            succeeded: row.status == "succeeded",
+        };
+        if runs.insert(row.node_id.as_str(), run).is_none() {
+            order.push(row.node_id.as_str());
+        }
+    }
+    (order, runs)
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Edge, NodeDefinition, Trigger};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn node(id: &str, node_type: &str, config: Value) -> NodeDefinition {
+        NodeDefinition { id: id.into(), node_type: node_type.into(), config, worker_tags: Vec::new() }
+    }
+
+    fn run(node_id: &str, input: Value, output: Value) -> NodeExecutionRow {
+        NodeExecutionRow {
+            id: Uuid::new_v4(),
+            execution_id: Uuid::nil(),
+            node_id: node_id.into(),
+            input,
+            output: Some(output),
+            status: "succeeded".into(),
+            started_at: chrono::Utc::now(),
+            finished_at: None,
+            input_hash: None,
+            output_hash: None,
+            error: None,
+            branch: None,
+            halted: false,
+        }
+    }
+
+    #[test]
+    fn flags_nondeterministic_constructs() {
+        let workflow = Workflow::new(
+            "wf",
+            Trigger::Manual { input_schema: None },
+            vec![
+                node("split", "split_ab", json!({ "branches": [] })),
+                node("sticky", "split_ab", json!({ "branches": [], "sticky_key": "{{ input.id }}" })),
+                node("clock", "datetime", json!({ "operation": "now" })),
+                node("fmt", "datetime", json!({ "operation": "format" })),
+                node("merge", "mock", json!({})),
+            ],
+            vec![Edge::new("split", "merge"), Edge::new("clock", "merge"), Edge::new("sticky", "fmt")],
+        );
+
+        let findings = analyze(&workflow);
+        let kinds: Vec<(&str, FindingKind)> =
+            findings.iter().map(|f| (f.node_id.as_str(), f.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("split", FindingKind::Random),
+                ("clock", FindingKind::TimeDependent),
+                ("merge", FindingKind::UnorderedFanIn),
+            ]
+        );
+    }
+
+    #[test]
+    fn hashes_ignore_key_order() {
+        let a: Value = serde_json::from_str(r#"{"b": 1, "a": {"y": [1, 2], "x": null}}"#).unwrap();
+        let b: Value = serde_json::from_str(r#"{"a": {"x": null, "y": [1, 2]}, "b": 1}"#).unwrap();
+        assert_eq!(hash_value(&a), hash_value(&b));
+        assert_ne!(hash_value(&a), hash_value(&json!({ "a": { "x": null, "y": [2, 1] }, "b": 1 })));
+        assert_ne!(hash_value(&json!("1")), hash_value(&json!(1)));
+    }
+
+    #[test]
+    fn finds_the_node_that_diverged_first() {
+        let first = vec![
+            run("fetch", json!({ "id": 1 }), json!({ "n": 1 })),
+            run("pick", json!({ "n": 1 }), json!({ "variant": "a" })),
+            run("send", json!({ "variant": "a" }), json!({ "ok": true })),
+        ];
+        let second = vec![
+            run("fetch", json!({ "id": 1 }), json!({ "n": 1 })),
+            run("pick", json!({ "n": 1 }), json!({ "variant": "b" })),
+            run("send", json!({ "variant": "b" }), json!({ "ok": true })),
+            run("audit", json!({}), json!({})),
+        ];
+
+        let comparison = compare(&first, &second);
+        assert!(!comparison.identical);
+        assert_eq!(comparison.first_divergent_node.as_deref(), Some("pick"));
+        let kinds: Vec<(&str, DivergenceKind)> =
+            comparison.divergences.iter().map(|d| (d.node_id.as_str(), d.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("pick", DivergenceKind::Output),
+                ("send", DivergenceKind::Input),
+                ("audit", DivergenceKind::OnlyInSecond),
+            ]
+        );
+
+        assert!(compare(&first, &first).identical);
+    }
+}
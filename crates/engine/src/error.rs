@@ -22,6 +22,21 @@ pub enum EngineError {
     #[error("workflow graph contains a cycle")]
     CycleDetected,
 
+    /// A workflow definition does not parse.
+    #[error("invalid workflow definition: {0}")]
+    InvalidDefinition(String),
+
+    /// A run's workflow parameters are unknown, of the wrong type, or
+    /// missing (see [`crate::params`]).
+    #[error("invalid workflow parameters: {0}")]
+    InvalidParams(String),
+
+    /// A manual trigger's input schema is not a valid JSON Schema.
+    #[error("invalid input schema: {message}")]
+    InvalidInputSchema {
+        message: String,
+    },
+
     // ------ Execution errors ------
 
     /// A node failed with a fatal error; the whole execution is aborted.
@@ -38,7 +53,119 @@ pub enum EngineError {
         message: String,
     },
 
+    /// Chaos mode simulated a worker crash; nothing about the failure was
+    /// persisted (see [`crate::chaos`]).
+    #[error("chaos: simulated worker crash before node '{node_id}'")]
+    InjectedCrash {
+        node_id: String,
+    },
+
+    /// A resumption job's stored checkpoint does not parse.
+    #[error("job {job_id} has an invalid checkpoint: {message}")]
+    InvalidCheckpoint {
+        job_id: uuid::Uuid,
+        message: String,
+    },
+
+    // ------ Approval errors ------
+
+    /// The execution has no approval requested by this node.
+    #[error("no approval requested by node '{node_id}'")]
+    ApprovalNotFound {
+        node_id: String,
+    },
+
+    /// The decision presented the wrong token.
+    #[error("invalid approval token for node '{node_id}'")]
+    ApprovalTokenInvalid {
+        node_id: String,
+    },
+
+    /// The approval was already decided, or has expired.
+    #[error("approval for node '{node_id}' is already {status}")]
+    ApprovalClosed {
+        node_id: String,
+        status: String,
+    },
+
+    // ------ Sub-workflow errors ------
+
+    /// A stored workflow definition does not parse.
+    #[error("invalid definition for workflow {workflow_id}: {message}")]
+    InvalidWorkflow {
+        workflow_id: uuid::Uuid,
+        message: String,
+    },
+
+    /// Sub-workflows nested deeper than the engine allows.
+    #[error("sub-workflows nested more than {max} levels deep")]
+    SubWorkflowDepthExceeded {
+        max: u32,
+    },
+
+    // ------ Scheduling errors ------
+
+    /// A cron trigger's expression does not parse, or never fires.
+    #[error("invalid cron expression '{expression}': {message}")]
+    InvalidCron {
+        expression: String,
+        message: String,
+    },
+
+    /// A poll trigger's source could not be read, or its response holds no
+    /// item array.
+    #[error("polling workflow {workflow_id} failed: {message}")]
+    PollFailed {
+        workflow_id: uuid::Uuid,
+        message: String,
+    },
+
+    // ------ Retry errors ------
+
+    /// The execution cannot be retried (as asked).
+    #[error("execution {execution_id} cannot be retried: {reason}")]
+    NotRetryable {
+        execution_id: uuid::Uuid,
+        reason: String,
+    },
+
+    // ------ Quota errors ------
+
+    /// Starting another execution would go over a workflow's or project's
+    /// quota (see [`crate::quotas`]).
+    #[error("quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    // ------ Bundle errors ------
+
+    /// An imported bundle's name matches more than one workflow.
+    #[error("several workflows are named '{0}'")]
+    AmbiguousWorkflowName(String),
+
+    // ------ Binary data errors ------
+
+    /// The blob store is misconfigured or failed.
+    #[error("binary data error: {0}")]
+    BinaryData(String),
+
+    // ------ Secret provider errors ------
+
+    /// The secrets provider is misconfigured or could not be read.
+    #[error("secrets provider error: {0}")]
+    SecretProvider(String),
+
+    // ------ Embedding errors ------
+
+    /// An [`Automation`](crate::automation::Automation) without a store
+    /// was asked for something that needs one.
+    #[error("{0} needs a store")]
+    StoreRequired(&'static str),
+
     /// Persistence error from the db crate.
     #[error("database error: {0}")]
     Database(#[from] db::DbError),
+
+    /// The queue backend failed.
+    #[error("queue error: {0}")]
+    Queue(#[from] queue::QueueError),
 }
@@ -0,0 +1,336 @@
+//! Reporting of errors worth a human's attention to Sentry or to a
+//! webhook: panics, fatal node errors, and nodes out of retries.
+//!
+//! Each report is tagged with the workflow, execution, and node it came
+//! from, when known.  The executor reports node errors itself; panics are
+//! caught by a panic hook ([`ErrorReporter::install_panic_hook`]), which
+//! takes the tags from the task it panicked in: workers tag each job's
+//! task with [`tagged`], and the executor each node call.
+//!
+//! Sentry is spoken to over its envelope endpoint, from the project's DSN
+//! (`https://<key>@o0.ingest.sentry.io/<project>`).  A webhook receives
+//! each report as JSON:
+//!
+//! ```json
+//! {
+//!   "kind": "node_fatal",
+//!   "message": "node 'push' failed fatally: HTTP 500",
+//!   "service": "rusty-automation-worker",
+//!   "environment": "prod",
+//!   "workflow_id": "...",
+//!   "execution_id": "...",
+//!   "node_id": "push",
+//!   "reported_at": "2024-01-01T12:00:00Z"
+//! }
+//! ```
+//!
+//! Reports are sent in the background; a sink that cannot be reached
+//! costs a warning in the log, never the execution.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::EngineError;
+
+/// How long a request to Sentry or the webhook may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+tokio::task_local! {
+    static TAGS: Tags;
+}
+
+/// What a report is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportKind {
+    Panic,
+    NodeFatal,
+    RetriesExhausted,
+}
+
+impl ReportKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Panic => "panic",
+            Self::NodeFatal => "node_fatal",
+            Self::RetriesExhausted => "retries_exhausted",
+        }
+    }
+}
+
+/// Where an error happened.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Tags {
+    pub workflow_id: Option<Uuid>,
+    pub execution_id: Option<Uuid>,
+    pub node_id: Option<String>,
+}
+
+/// One error to report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorReport {
+    pub kind: ReportKind,
+    pub message: String,
+    #[serde(flatten)]
+    pub tags: Tags,
+    pub reported_at: DateTime<Utc>,
+}
+
+impl ErrorReport {
+    /// The report for a node error that failed an execution, if it is
+    /// one worth reporting.
+    pub fn for_node_error(error: &EngineError, workflow_id: Uuid, execution_id: Uuid) -> Option<Self> {
+        let (kind, node_id) = match error {
+            EngineError::NodeFatal { node_id, .. } => (ReportKind::NodeFatal, node_id),
+            EngineError::NodeRetryExhausted { node_id, .. } => (ReportKind::RetriesExhausted, node_id),
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            message: error.to_string(),
+            tags: Tags {
+                workflow_id: Some(workflow_id),
+                execution_id: Some(execution_id),
+                node_id: Some(node_id.clone()),
+            },
+            reported_at: Utc::now(),
+        })
+    }
+}
+
+/// Run `future` with `tags` on the errors it panics with.
+pub async fn tagged<F: Future>(tags: Tags, future: F) -> F::Output {
+    TAGS.scope(tags, future).await
+}
+
+/// The tags of the task this is called from.
+pub fn current_tags() -> Tags {
+    TAGS.try_with(Tags::clone).unwrap_or_default()
+}
+
+#[derive(Debug, Clone)]
+enum Sink {
+    Sentry { url: String, auth: String, dsn: String },
+    Webhook { url: String },
+}
+
+/// Sends error reports to Sentry and webhooks.
+#[derive(Debug, Clone)]
+pub struct ErrorReporter {
+    client: reqwest::Client,
+    sinks: Arc<Vec<Sink>>,
+    service: String,
+    environment: Option<String>,
+}
+
+impl ErrorReporter {
+    /// A reporter sending nowhere yet, reporting as `service`.
+    pub fn new(service: &str) -> Self {
+        Self {
+            client: reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().expect("a default HTTP client builds"),
+            sinks: Arc::new(Vec::new()),
+            service: service.to_owned(),
+            environment: None,
+        }
+    }
+
+    /// Also send reports to the Sentry project of `dsn`.
+    ///
+    /// # Errors
+    /// A DSN that is not `<scheme>://<key>@<host>/<project>`.
+    pub fn with_sentry(self, dsn: &str) -> Result<Self, String> {
+        let parsed = reqwest::Url::parse(dsn).map_err(|e| format!("invalid Sentry DSN: {e}"))?;
+        let key = parsed.username();
+        let path = parsed.path().trim_end_matches('/');
+        let (prefix, project) = path.rsplit_once('/').unwrap_or_default();
+        if key.is_empty() || project.is_empty() || parsed.host_str().is_none() {
+            return Err("invalid Sentry DSN: expected <scheme>://<key>@<host>/<project>".into());
+        }
+        let port = parsed.port().map(|port| format!(":{port}")).unwrap_or_default();
+        let url = format!(
+            "{}://{}{port}{prefix}/api/{project}/envelope/",
+            parsed.scheme(),
+            parsed.host_str().unwrap_or_default()
+        );
+        let auth = format!(
+            "Sentry sentry_version=7, sentry_key={key}, sentry_client=rusty-automation/{}",
+            env!("CARGO_PKG_VERSION")
+        );
+        Ok(self.with_sink(Sink::Sentry { url, auth, dsn: dsn.to_owned() }))
+    }
+
+    /// Also post reports to `url`.
+    pub fn with_webhook(self, url: &str) -> Self {
+        self.with_sink(Sink::Webhook { url: url.to_owned() })
+    }
+
+    /// Report as running in `environment` (`staging`, `prod`).
+    pub fn with_environment(mut self, environment: &str) -> Self {
+        self.environment = Some(environment.to_owned());
+        self
+    }
+
+    fn with_sink(mut self, sink: Sink) -> Self {
+        Arc::make_mut(&mut self.sinks).push(sink);
+        self
+    }
+
+    /// Whether reports go anywhere.
+    pub fn is_enabled(&self) -> bool {
+        !self.sinks.is_empty()
+    }
+
+    /// Send `report` in the background.  Outside a Tokio runtime it is
+    /// dropped.
+    pub fn report(&self, report: ErrorReport) {
+        if !self.is_enabled() {
+            return;
+        }
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let reporter = self.clone();
+            runtime.spawn(async move { reporter.send(&report).await });
+        }
+    }
+
+    /// Send `report` to every sink and wait until they answered.
+    pub async fn send(&self, report: &ErrorReport) {
+        for sink in self.sinks.iter() {
+            let request = match sink {
+                Sink::Sentry { url, auth, dsn } => self
+                    .client
+                    .post(url)
+                    .header("X-Sentry-Auth", auth)
+                    .header("Content-Type", "application/x-sentry-envelope")
+                    .body(self.envelope(report, dsn)),
+                Sink::Webhook { url } => self.client.post(url).json(&self.webhook_body(report)),
+            };
+            match request.send().await {
+                Ok(response) if response.status().is_success() => {}
+                Ok(response) => warn!("{} error report answered {}", report.kind.as_str(), response.status()),
+                Err(e) => warn!("cannot send a {} error report: {}", report.kind.as_str(), e),
+            }
+        }
+    }
+
+    /// Report every panic from now on, tagged from the panicking task,
+    /// then carry on with the panic hook installed before.
+    pub fn install_panic_hook(&self) {
+        let reporter = self.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let payload = info
+                .payload()
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| info.payload().downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "panic".into());
+            let message = match info.location() {
+                Some(location) => format!("{payload} at {}:{}", location.file(), location.line()),
+                None => payload,
+            };
+            reporter.report(ErrorReport {
+                kind: ReportKind::Panic,
+                message,
+                tags: current_tags(),
+                reported_at: Utc::now(),
+            });
+            previous(info);
+        }));
+    }
+
+    fn webhook_body(&self, report: &ErrorReport) -> Value {
+        let mut body = serde_json::to_value(report).expect("reports serialize");
+        body["service"] = json!(self.service);
+        body["environment"] = json!(self.environment);
+        body
+    }
+
+    /// `report` as a Sentry envelope holding one event.
+    fn envelope(&self, report: &ErrorReport, dsn: &str) -> String {
+        let event_id = Uuid::new_v4().simple().to_string();
+        let mut tags = json!({ "kind": report.kind.as_str(), "service": self.service });
+        if let Some(workflow_id) = report.tags.workflow_id {
+            tags["workflow_id"] = json!(workflow_id);
+        }
+        if let Some(execution_id) = report.tags.execution_id {
+            tags["execution_id"] = json!(execution_id);
+        }
+        if let Some(node_id) = &report.tags.node_id {
+            tags["node_id"] = json!(node_id);
+        }
+        let event = json!({
+            "event_id": event_id,
+            "timestamp": report.reported_at.to_rfc3339(),
+            "platform": "other",
+            "level": if report.kind == ReportKind::Panic { "fatal" } else { "error" },
+            "logger": "rusty-automation",
+            "server_name": self.service,
+            "environment": self.environment,
+            "message": { "formatted": report.message },
+            "exception": { "values": [{ "type": report.kind.as_str(), "value": report.message }] },
+            "tags": tags,
+        });
+        let header = json!({ "event_id": event_id, "dsn": dsn, "sent_at": Utc::now().to_rfc3339() });
+        format!("{header}\n{}\n{event}\n", json!({ "type": "event" }))
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sentry_dsns_give_the_envelope_endpoint_and_key() {
+        let reporter = ErrorReporter::new("worker").with_sentry("https://abc123@o1.ingest.sentry.io/42").unwrap();
+        let Sink::Sentry { url, auth, .. } = &reporter.sinks[0] else { panic!("a Sentry sink") };
+        assert_eq!(url, "https://o1.ingest.sentry.io/api/42/envelope/");
+        assert!(auth.contains("sentry_key=abc123"), "{auth}");
+
+        let reporter = ErrorReporter::new("worker").with_sentry("http://key@localhost:9000/sentry/7").unwrap();
+        let Sink::Sentry { url, .. } = &reporter.sinks[0] else { panic!("a Sentry sink") };
+        assert_eq!(url, "http://localhost:9000/sentry/api/7/envelope/");
+
+        assert!(ErrorReporter::new("worker").with_sentry("https://o1.ingest.sentry.io/42").is_err());
+        assert!(ErrorReporter::new("worker").with_sentry("https://key@o1.ingest.sentry.io/").is_err());
+    }
+
+    #[test]
+    fn envelopes_hold_one_tagged_event() {
+        let error = EngineError::NodeRetryExhausted { node_id: "push".into(), message: "HTTP 503".into() };
+        let (workflow_id, execution_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let report = ErrorReport::for_node_error(&error, workflow_id, execution_id).unwrap();
+        assert_eq!(report.kind, ReportKind::RetriesExhausted);
+        let reporter = ErrorReporter::new("worker").with_environment("prod");
+
+        let envelope = reporter.envelope(&report, "https://k@sentry.example/1");
+        let lines: Vec<Value> = envelope.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines[0]["event_id"], lines[2]["event_id"]);
+        assert_eq!(lines[1]["type"], "event");
+        let event = &lines[2];
+        assert_eq!(event["environment"], "prod");
+        assert_eq!(event["tags"]["kind"], "retries_exhausted");
+        assert_eq!(event["tags"]["workflow_id"], json!(workflow_id));
+        assert_eq!(event["tags"]["execution_id"], json!(execution_id));
+        assert_eq!(event["tags"]["node_id"], "push");
+
+        let running = EngineError::NotRetryable { execution_id, reason: "still running".into() };
+        assert_eq!(ErrorReport::for_node_error(&running, workflow_id, execution_id), None);
+    }
+
+    #[tokio::test]
+    async fn tags_follow_the_task_they_were_set_on() {
+        assert_eq!(current_tags(), Tags::default());
+        let tags = Tags { node_id: Some("push".into()), ..Default::default() };
+        assert_eq!(tagged(tags.clone(), async { current_tags() }).await, tags);
+    }
+}
@@ -0,0 +1,40 @@
+//! Error workflows, started when an execution fails.
+//!
+//! A workflow sets `error_workflow_id`; when one of its executions fails,
+//! the executor queues a run of that workflow with a [`Failure`] as input
+//! (see the `error_trigger` node).  The run is a child of the failed
+//! execution, so it inherits its priority, queue, and labels.
+//!
+//! A workflow that names itself as its error workflow is not re-run for
+//! its own failures, which would otherwise loop forever.
+
+pub use nodes::builtin::error_trigger::{Failure, FailureKind};
+
+use crate::subworkflow::load_workflow;
+use crate::{EngineError, WorkflowExecutor};
+
+/// Queue a run of `error_workflow_id` for `failure`.  Returns the error
+/// run's execution id, or `None` when the failed workflow is its own error
+/// workflow.
+///
+/// # Errors
+/// Database errors, including an unknown error workflow, and
+/// [`EngineError::InvalidWorkflow`].
+pub async fn launch(
+    executor: &WorkflowExecutor,
+    error_workflow_id: uuid::Uuid,
+    failure: Failure,
+) -> Result<Option<uuid::Uuid>, EngineError> {
+    if error_workflow_id == failure.workflow_id {
+        return Ok(None);
+    }
+    let workflow = load_workflow(executor.pool(), error_workflow_id).await?;
+    let parent_execution_id = failure.execution_id;
+    let input = serde_json::to_value(&failure).expect("failures serialize");
+    let job = executor.start_child(parent_execution_id, &workflow, input).await?;
+    tracing::info!(
+        "execution {} failed; started error workflow {} as execution {}",
+        parent_execution_id, error_workflow_id, job.execution_id
+    );
+    Ok(Some(job.execution_id))
+}
@@ -3,25 +3,70 @@
 //! `WorkflowExecutor` is the central orchestrator:
 //! 1. Validates the DAG and produces a topological ordering.
 //! 2. Iterates through nodes in order, dispatching each via `ExecutableNode`.
-//! 3. Passes the previous node's JSON output as input to the next node.
+//! 3. Passes each node's JSON output along its outgoing edges as the input of
+//!    the downstream nodes; nodes whose upstream halted, or picked a branch
+//!    other than the edge's label, are skipped.
 //! 4. Persists per-node results via the `db` crate.
 //! 5. Handles `NodeError::Retryable` (up to `max_retries`) and
 //!    `NodeError::Fatal` (abort immediately).
+//! 6. Suspends the execution when a node defers it ([`Flow::Defer`]):
+//!    progress is saved as a [`Checkpoint`] in a delayed job, and
+//!    [`WorkflowExecutor::resume`] picks it up from there.
+//! 7. Optionally injects latency, failures, and crashes into node attempts
+//!    ([`crate::chaos`]) to exercise the paths above.
+//! 8. Records input/output hashes per node for workflows with the
+//!    `determinism_report` flag ([`crate::determinism`]).
+//! 9. Runs CPU-heavy nodes on a bounded blocking pool
+//!    ([`crate::blocking`]) so they cannot starve the async runtime.
+//! 10. Persists node records write-behind ([`crate::persistence`]), off the
+//!     path between one node and the next.
+//! 11. Lets nodes run other workflows as children of the execution
+//!     ([`crate::subworkflow`]).
+//! 12. Starts the workflow's error workflow when an execution fails
+//!     ([`crate::error_workflow`]).
+//! 13. Records a trace span per execution run and node attempt when
+//!     given a tracer ([`crate::otel`]).
+//! 14. Warns about node runs far slower than their baseline when given a
+//!     detector ([`crate::slow_nodes`]).
+//! 15. Reports fatal node errors and nodes out of retries to Sentry or a
+//!     webhook when given a reporter ([`crate::error_reporting`]).
+//! 16. Fills the run's workflow parameters into node configs
+//!     ([`crate::params`]).
 
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tracing::{info, warn, error, instrument};
 
 use db::DbPool;
+use db::models::{NewLogLine, NodeAttempt, NodeFlow};
+use nodes::binary::BinaryStore;
 use nodes::{ExecutableNode, NodeError};
-use nodes::traits::ExecutionContext;
+use queue::{PgJobQueue, SharedQueue};
+use nodes::traits::{Clock, ExecutionContext, Flow, RandomSource, SystemClock};
 
-use crate::{EngineError, Workflow};
+use crate::{EngineError, InheritancePolicy, Workflow};
+use crate::blocking::BlockingPool;
+use crate::chaos::{Chaos, ChaosConfig, Fault};
 use crate::dag::validate_dag;
+use crate::error_reporting::{self, ErrorReport, ErrorReporter, Tags};
+use crate::error_workflow::{self, Failure, FailureKind};
+use crate::flags::{self, FeatureFlags, FlagScope};
+use crate::inheritance;
+use crate::metrics;
+use crate::persistence::{self, NodeRecord, NodeWriter, Transition};
+use crate::otel::{Attempt, ExecutionSpan, SpanContext, Tracer};
+use crate::params;
+use crate::quotas;
+use crate::secret_providers::SecretProvider;
+use crate::secrets::{self, SecretsKey};
+use crate::slow_nodes::{SlowNodeConfig, SlowNodeDetector};
+use crate::state::PgWorkflowStateStore;
+use crate::subworkflow::{self, ExecutorSubWorkflows};
 
 // ---------------------------------------------------------------------------
 // Configuration
@@ -36,6 +81,15 @@ pub struct ExecutorConfig {
     pub retry_base_delay: Duration,
 }
 
+impl ExecutorConfig {
+    /// Back-off before retry number `attempt` (1-based): the base delay,
+    /// doubled for every further attempt.
+    pub fn retry_delay(&self, attempt: u32) -> Duration {
+        self.retry_base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+}
+
 impl Default for ExecutorConfig {
     fn default() -> Self {
         Self {
@@ -49,8 +103,7 @@ impl Default for ExecutorConfig {
 // Node registry
 // ---------------------------------------------------------------------------
 
-/// Maps `node_type` strings to boxed `ExecutableNode` implementations.
-pub type NodeRegistry = HashMap<String, Arc<dyn ExecutableNode>>;
+pub use nodes::registry::NodeRegistry;
 
 // ---------------------------------------------------------------------------
 // Output of a completed execution
@@ -61,8 +114,142 @@ pub type NodeRegistry = HashMap<String, Arc<dyn ExecutableNode>>;
 pub struct ExecutionResult {
     /// ID of the `workflow_executions` row created for this run.
     pub execution_id: uuid::Uuid,
-    /// The JSON output produced by the *last* node in the sorted order.
+    /// The JSON output produced by the *last* node that ran.
     pub output: Value,
+    /// Set when a node deferred the execution; it resumes at this time.
+    pub deferred_until: Option<DateTime<Utc>>,
+}
+
+/// Saved progress of an execution suspended by a deferring node.
+///
+/// Stored in the `checkpoint` column of the delayed job that continues the
+/// execution, never in its payload; pass it to [`WorkflowExecutor::resume`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Node to run first; every node sorted before it is already done.
+    pub node_id: String,
+    /// The execution's original trigger input.
+    pub input: Value,
+    /// Outputs of completed nodes that did not halt the flow.
+    pub outputs: HashMap<String, Value>,
+    /// Branch picked by each completed branching node.
+    #[serde(default)]
+    pub branches: HashMap<String, String>,
+    /// Output of the last node that ran before the suspension.
+    pub last_output: Value,
+}
+
+impl Checkpoint {
+    /// The checkpoint `job` continues from, if it is a resumption.
+    ///
+    /// # Errors
+    /// [`EngineError::InvalidCheckpoint`] when the stored checkpoint does not
+    /// parse.
+    pub fn from_job(job: &db::models::JobRow) -> Result<Option<Self>, EngineError> {
+        let Some(checkpoint) = &job.checkpoint else {
+            return Ok(None);
+        };
+        serde_json::from_value(checkpoint.clone())
+            .map(Some)
+            .map_err(|e| EngineError::InvalidCheckpoint { job_id: job.id, message: e.to_string() })
+    }
+}
+
+/// In-memory form of a [`Checkpoint`] while nodes run.
+///
+/// Payloads are shared behind `Arc`s: a node's output is handed to each
+/// child, persisted, and kept as the last output without being copied, so
+/// multi-megabyte payloads are only cloned when a node takes ownership of
+/// its input.
+pub(crate) struct FlowState {
+    pub(crate) input: Arc<Value>,
+    pub(crate) outputs: HashMap<String, Arc<Value>>,
+    pub(crate) branches: HashMap<String, String>,
+    pub(crate) last_output: Arc<Value>,
+}
+
+impl FlowState {
+    pub(crate) fn new(input: Value) -> Self {
+        let input = Arc::new(input);
+        Self {
+            input: input.clone(),
+            outputs: HashMap::new(),
+            branches: HashMap::new(),
+            last_output: input,
+        }
+    }
+
+    fn from_checkpoint(checkpoint: Checkpoint) -> Self {
+        Self {
+            input: Arc::new(checkpoint.input),
+            outputs: checkpoint.outputs.into_iter().map(|(id, v)| (id, Arc::new(v))).collect(),
+            branches: checkpoint.branches,
+            last_output: Arc::new(checkpoint.last_output),
+        }
+    }
+
+    fn into_checkpoint(self, node_id: String) -> Checkpoint {
+        Checkpoint {
+            node_id,
+            input: Arc::unwrap_or_clone(self.input),
+            outputs: self.outputs.into_iter().map(|(id, v)| (id, Arc::unwrap_or_clone(v))).collect(),
+            branches: self.branches,
+            last_output: Arc::unwrap_or_clone(self.last_output),
+        }
+    }
+}
+
+/// Which output feeds each node, from the workflow's edges.
+pub(crate) struct Routing<'a> {
+    /// Parents of each node, with the branch label of the edge.
+    parents: HashMap<&'a str, Vec<(&'a str, Option<&'a str>)>>,
+    /// Each node's place in the sorted order.
+    position: HashMap<&'a str, usize>,
+}
+
+impl<'a> Routing<'a> {
+    pub(crate) fn new(workflow: &'a Workflow, sorted_ids: &'a [String]) -> Self {
+        let mut parents: HashMap<&str, Vec<(&str, Option<&str>)>> = HashMap::new();
+        for edge in &workflow.edges {
+            parents
+                .entry(edge.to.as_str())
+                .or_default()
+                .push((edge.from.as_str(), edge.branch.as_deref()));
+        }
+        let position = sorted_ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.as_str(), i))
+            .collect();
+        Self { parents, position }
+    }
+
+    /// Where `node_id` is in the sorted order.
+    pub(crate) fn position(&self, node_id: &str) -> Option<usize> {
+        self.position.get(node_id).copied()
+    }
+
+    /// The input of `node_id`, or `None` when it is skipped.
+    ///
+    /// Root nodes receive the trigger input; every other node receives the
+    /// output of its most recently executed, still-active parent whose edge
+    /// to this node was taken.
+    pub(crate) fn input(&self, node_id: &str, state: &FlowState) -> Option<Arc<Value>> {
+        let Some(node_parents) = self.parents.get(node_id) else {
+            return Some(state.input.clone());
+        };
+        let (parent, _) = node_parents
+            .iter()
+            .filter(|(parent, label)| {
+                state.outputs.contains_key(*parent)
+                    && match label {
+                        None => true,
+                        Some(label) => state.branches.get(*parent).map(String::as_str) == Some(*label),
+                    }
+            })
+            .max_by_key(|(parent, _)| self.position[*parent])?;
+        Some(state.outputs[*parent].clone())
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -73,23 +260,292 @@ pub struct ExecutionResult {
 ///
 /// Construct one executor per process (or even per execution) and call
 /// [`WorkflowExecutor::run`] with the workflow and initial input.
+///
+/// Cloning is cheap: clones share the registry, runner, and flags.
+#[derive(Clone)]
 pub struct WorkflowExecutor {
     pool: DbPool,
-    registry: NodeRegistry,
-    config: ExecutorConfig,
+    registry: Arc<NodeRegistry>,
+    runner: Arc<NodeRunner>,
+    flags: FeatureFlags,
+    clock: Arc<dyn Clock>,
+    random: Option<Arc<dyn RandomSource>>,
+    inheritance: InheritancePolicy,
+    queue: SharedQueue,
+    write_buffer: usize,
+    /// How many sub-workflow levels deep this executor runs.
+    depth: u32,
+    /// Opens workflow secrets for the execution context; without it nodes
+    /// see none.
+    secrets: Option<SecretsKey>,
+    /// Where workflow secrets come from instead of the database (see
+    /// [`crate::secret_providers`]).
+    secret_provider: Option<Arc<dyn SecretProvider>>,
+    /// Environment whose secret values are read, rather than the defaults.
+    environment: Option<String>,
+    /// Where spans of executions and node attempts go (see [`crate::otel`]).
+    tracer: Option<Tracer>,
+    /// Where nodes keep files; without it nodes handling binary data fail.
+    binary: Option<Arc<dyn BinaryStore>>,
+    /// Judges node runs against their baselines (see
+    /// [`crate::slow_nodes`]).
+    slow_nodes: Option<Arc<SlowNodeDetector>>,
+    /// Where fatal node errors are reported (see
+    /// [`crate::error_reporting`]).
+    errors: Option<ErrorReporter>,
 }
 
 impl WorkflowExecutor {
     /// Create a new executor.
     pub fn new(pool: DbPool, registry: NodeRegistry, config: ExecutorConfig) -> Self {
-        Self { pool, registry, config }
+        Self {
+            queue: Arc::new(PgJobQueue::new(pool.clone())),
+            pool,
+            registry: Arc::new(registry),
+            runner: Arc::new(NodeRunner::new(config)),
+            flags: FeatureFlags::default(),
+            clock: Arc::new(SystemClock),
+            random: None,
+            inheritance: InheritancePolicy::default(),
+            write_buffer: persistence::DEFAULT_CAPACITY,
+            depth: 0,
+            secrets: None,
+            secret_provider: None,
+            environment: None,
+            tracer: None,
+            binary: None,
+            slow_nodes: None,
+            errors: None,
+        }
+    }
+
+    /// Inject faults into node attempts (tests and staging only; see
+    /// [`crate::chaos`]).
+    pub fn with_chaos(mut self, config: ChaosConfig) -> Self {
+        self.runner = Arc::new(Arc::unwrap_or_clone(self.runner).with_chaos(config));
+        self
+    }
+
+    /// Run blocking nodes on `pool` instead of a pool of the executor's own
+    /// (see [`crate::blocking`]).
+    pub fn with_blocking_pool(mut self, pool: BlockingPool) -> Self {
+        self.runner = Arc::new(Arc::unwrap_or_clone(self.runner).with_blocking_pool(pool));
+        self
+    }
+
+    /// Let up to `capacity` node records queue for the write-behind writer
+    /// before execution waits for the database.
+    pub fn with_write_buffer(mut self, capacity: usize) -> Self {
+        self.write_buffer = capacity;
+        self
+    }
+
+    /// Give nodes `clock` as their time source (see [`ExecutionContext::now`]).
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Give nodes a seeded random source.
+    pub fn with_random(mut self, random: Arc<dyn RandomSource>) -> Self {
+        self.random = Some(random);
+        self
+    }
+
+    /// Use `flags` to decide on gradually rolled-out engine behaviours.
+    pub fn with_flags(mut self, flags: FeatureFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// What child executions inherit from workflows that set no policy of
+    /// their own.
+    pub fn with_inheritance(mut self, policy: InheritancePolicy) -> Self {
+        self.inheritance = policy;
+        self
+    }
+
+    /// Decrypt workflow secrets with `key` into each execution's context.
+    pub fn with_secrets_key(mut self, key: SecretsKey) -> Self {
+        self.secrets = Some(key);
+        self
+    }
+
+    /// Read the secret values stored for `environment` (`prod`, `staging`),
+    /// falling back to each key's default.
+    pub fn with_environment(mut self, environment: &str) -> Self {
+        self.environment = Some(environment.to_owned());
+        self
+    }
+
+    /// Record a trace span for every execution run and node attempt with
+    /// `tracer`.
+    pub fn with_tracer(mut self, tracer: Tracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// Report fatal node errors and nodes out of retries (see
+    /// [`crate::error_reporting`]).
+    pub fn with_error_reporter(mut self, reporter: ErrorReporter) -> Self {
+        self.errors = Some(reporter);
+        self
+    }
+
+    /// Warn about node runs far slower than their baseline (see
+    /// [`crate::slow_nodes`]).
+    pub fn with_slow_node_detection(mut self, config: SlowNodeConfig) -> Self {
+        self.slow_nodes = Some(Arc::new(SlowNodeDetector::new(config)));
+        self
+    }
+
+    /// Fetch workflow secrets from `provider` rather than the database.
+    /// The secrets key still opens shared credentials.
+    pub fn with_secret_provider(mut self, provider: Arc<dyn SecretProvider>) -> Self {
+        self.secret_provider = Some(provider);
+        self
+    }
+
+    /// Let nodes store and load binary data in `store` (see
+    /// [`crate::binary`]).
+    pub fn with_binary(mut self, store: Arc<dyn BinaryStore>) -> Self {
+        self.binary = Some(store);
+        self
+    }
+
+    /// Hand the jobs this executor queues (deferrals, sub-workflows) to
+    /// `queue`.
+    pub fn with_queue(mut self, queue: SharedQueue) -> Self {
+        self.queue = queue;
+        self
+    }
+
+    /// Queue a run of `child` on behalf of execution `parent_execution_id`,
+    /// inheriting its priority, queue, and labels (see [`crate::inheritance`]).
+    ///
+    /// # Errors
+    /// Database problems, including an unknown parent execution.
+    pub async fn start_child(
+        &self,
+        parent_execution_id: uuid::Uuid,
+        child: &Workflow,
+        input: Value,
+    ) -> Result<db::models::JobRow, EngineError> {
+        inheritance::start_child(&self.pool, self.queue.as_ref(), parent_execution_id, child, input, &self.inheritance)
+            .await
+    }
+
+    /// Run `child` to completion on behalf of execution
+    /// `parent_execution_id`, inheriting like [`WorkflowExecutor::start_child`].
+    ///
+    /// # Errors
+    /// [`EngineError::SubWorkflowDepthExceeded`] when sub-workflows nest more
+    /// than [`subworkflow::MAX_DEPTH`] levels deep (typically a workflow that
+    /// ends up calling itself), [`EngineError::QuotaExceeded`] when the
+    /// child's quota is used up, and everything [`WorkflowExecutor::run`]
+    /// returns.
+    pub async fn run_child(
+        &self,
+        parent_execution_id: uuid::Uuid,
+        child: &Workflow,
+        input: Value,
+    ) -> Result<ExecutionResult, EngineError> {
+        if self.depth >= subworkflow::MAX_DEPTH {
+            return Err(EngineError::SubWorkflowDepthExceeded { max: subworkflow::MAX_DEPTH });
+        }
+        let sorted_ids = validate_dag(child)?;
+        quotas::check(&self.pool, child.id, child.quota.as_ref()).await?;
+        let meta = inheritance::inherit(
+            &self.pool,
+            parent_execution_id,
+            child.execution_meta(&input),
+            &self.inheritance,
+        )
+        .await?;
+        let exec_row = db::repository::executions::create_execution(&self.pool, child.id, &meta).await?;
+        info!("running sub-workflow '{}' as execution {}", child.id, exec_row.id);
+
+        let executor = Self { depth: self.depth + 1, ..self.clone() };
+        let start_node = sorted_ids.first().cloned().unwrap_or_default();
+        executor
+            .execute_from(child, &sorted_ids, exec_row.id, &start_node, FlowState::new(input))
+            .await
+    }
+
+    pub(crate) fn pool(&self) -> &DbPool {
+        &self.pool
+    }
+
+    /// Store the lines node `node_id` logged on `ctx`; losing them does not
+    /// fail the execution.
+    async fn save_logs(&self, execution_id: uuid::Uuid, node_id: &str, ctx: &ExecutionContext) {
+        let lines: Vec<NewLogLine> = ctx
+            .take_logs()
+            .into_iter()
+            .map(|entry| NewLogLine {
+                level: entry.level.as_str().to_owned(),
+                message: entry.message,
+                fields: entry.fields,
+                logged_at: entry.logged_at,
+            })
+            .collect();
+        if let Err(e) = db::repository::logs::insert_logs(&self.pool, execution_id, node_id, &lines).await {
+            warn!("cannot store the logs of node '{}': {}", node_id, e);
+        }
+    }
+
+    /// Warn if node `node_id` of `workflow`, which succeeded after `took`,
+    /// ran far slower than its baseline, and start the error workflow if
+    /// the workflow asks for it.
+    async fn check_slow_node(
+        &self,
+        workflow: &Workflow,
+        execution_id: uuid::Uuid,
+        node_id: &str,
+        node_type: &str,
+        took: Duration,
+    ) {
+        let Some(detector) = &self.slow_nodes else {
+            return;
+        };
+        let slow = match detector.check(&self.pool, workflow.id, node_id, took).await {
+            Ok(Some(slow)) => slow,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("cannot read the baseline of node '{}': {}", node_id, e);
+                return;
+            }
+        };
+        let message = slow.message(node_id);
+        warn!("execution {}: {}", execution_id, message);
+        metrics::slow_node(node_type);
+
+        if let (true, Some(error_workflow_id)) = (workflow.slow_node_alerts, workflow.error_workflow_id) {
+            let failure = Failure {
+                workflow_id: workflow.id,
+                workflow_name: workflow.name.clone(),
+                execution_id,
+                node_id: node_id.to_owned(),
+                message,
+                failed_at: Utc::now(),
+                kind: FailureKind::Slow,
+            };
+            if let Err(e) = error_workflow::launch(self, error_workflow_id, failure).await {
+                warn!("cannot start error workflow {}: {}", error_workflow_id, e);
+            }
+        }
+    }
+
+    /// Whether `flag` is on for `workflow`.
+    pub fn flag_enabled(&self, flag: &str, workflow: &Workflow) -> bool {
+        self.flags.is_enabled(flag, &FlagScope::workflow(workflow.id))
     }
 
     /// Run the workflow and return the final output.
     ///
     /// # Errors
-    /// Returns `EngineError` for validation failures, fatal node errors,
-    /// retry exhaustion, or database problems.
+    /// Returns `EngineError` for validation failures, a used-up quota,
+    /// fatal node errors, retry exhaustion, or database problems.
     #[instrument(skip(self, initial_input), fields(workflow_id = %workflow.id))]
     pub async fn run(
         &self,
@@ -108,9 +564,104 @@ impl WorkflowExecutor {
         // ------------------------------------------------------------------
         // Create the workflow_execution row.
         // ------------------------------------------------------------------
-        let exec_row = db::repository::executions::create_execution(&self.pool, workflow.id)
+        quotas::check(&self.pool, workflow.id, workflow.quota.as_ref()).await?;
+        let meta = workflow.execution_meta(&initial_input);
+        let exec_row = db::repository::executions::create_execution(&self.pool, workflow.id, &meta)
             .await?;
-        let execution_id = exec_row.id;
+
+        let start_node = sorted_ids.first().cloned().unwrap_or_default();
+        let state = FlowState::new(initial_input);
+        metrics::execution_started();
+        self.execute_from(workflow, &sorted_ids, exec_row.id, &start_node, state).await
+    }
+
+    /// Run an execution created elsewhere (e.g. queued by the API or a
+    /// trigger) from its first node.
+    ///
+    /// # Errors
+    /// Same as [`WorkflowExecutor::run`].
+    #[instrument(skip(self, initial_input), fields(workflow_id = %workflow.id))]
+    pub async fn run_queued(
+        &self,
+        workflow: &Workflow,
+        execution_id: uuid::Uuid,
+        initial_input: Value,
+    ) -> Result<ExecutionResult, EngineError> {
+        let sorted_ids = validate_dag(workflow)?;
+        let start_node = sorted_ids.first().cloned().unwrap_or_default();
+        let state = FlowState::new(initial_input);
+        metrics::execution_started();
+        self.execute_from(workflow, &sorted_ids, execution_id, &start_node, state).await
+    }
+
+    /// Continue an execution that a node deferred, starting at the
+    /// checkpointed node.
+    ///
+    /// # Errors
+    /// Same as [`WorkflowExecutor::run`].
+    #[instrument(skip(self, checkpoint), fields(workflow_id = %workflow.id))]
+    pub async fn resume(
+        &self,
+        workflow: &Workflow,
+        execution_id: uuid::Uuid,
+        checkpoint: Checkpoint,
+    ) -> Result<ExecutionResult, EngineError> {
+        let sorted_ids = validate_dag(workflow)?;
+        info!("resuming execution {} at node '{}'", execution_id, checkpoint.node_id);
+        let start_node = checkpoint.node_id.clone();
+        let state = FlowState::from_checkpoint(checkpoint);
+        self.execute_from(workflow, &sorted_ids, execution_id, &start_node, state).await
+    }
+
+    // -----------------------------------------------------------------------
+    // Internal: run the sorted nodes from a checkpoint onwards.
+    // -----------------------------------------------------------------------
+
+    async fn execute_from(
+        &self,
+        workflow: &Workflow,
+        sorted_ids: &[String],
+        execution_id: uuid::Uuid,
+        start_node: &str,
+        state: FlowState,
+    ) -> Result<ExecutionResult, EngineError> {
+        let Some(tracer) = &self.tracer else {
+            return self.run_nodes(workflow, sorted_ids, execution_id, start_node, state, None).await;
+        };
+        let parent = SpanContext::from_trigger_input(&state.input);
+        let span = tracer.start_execution(workflow.id, &workflow.name, execution_id, parent);
+        let result = self.run_nodes(workflow, sorted_ids, execution_id, start_node, state, Some(&span)).await;
+        match &result {
+            Ok(result) if result.deferred_until.is_some() => span.finish("waiting", None),
+            Ok(_) => span.finish("succeeded", None),
+            Err(e) => span.finish("failed", Some(e.to_string())),
+        }
+        result
+    }
+
+    async fn run_nodes(
+        &self,
+        workflow: &Workflow,
+        sorted_ids: &[String],
+        execution_id: uuid::Uuid,
+        start_node: &str,
+        mut state: FlowState,
+        span: Option<&ExecutionSpan>,
+    ) -> Result<ExecutionResult, EngineError> {
+        // ------------------------------------------------------------------
+        // Build the shared context and resolve the run's parameters before
+        // the execution counts as running, so a run that cannot start is
+        // failed rather than left running.
+        // ------------------------------------------------------------------
+        let setup = async {
+            let ctx = self.context(workflow, execution_id, &state.input).await?;
+            let params = self.params(workflow, execution_id).await?;
+            Ok::<_, EngineError>((ctx, params))
+        };
+        let (ctx, params) = match setup.await {
+            Ok(setup) => setup,
+            Err(e) => return Err(self.not_started(execution_id, e).await),
+        };
 
         db::repository::executions::update_execution_status(
             &self.pool, execution_id, "running", false,
@@ -126,24 +677,29 @@ impl WorkflowExecutor {
             .map(|n| (n.id.as_str(), n))
             .collect();
 
+        let record_hashes = self.flag_enabled(flags::DETERMINISM_REPORT, workflow);
+        let writer = NodeWriter::spawn(self.pool.clone(), execution_id, self.write_buffer);
+
         // ------------------------------------------------------------------
-        // Build the shared context (secrets not implemented yet — empty map).
+        // Edge lookups: parents of each node and each node's sorted position.
         // ------------------------------------------------------------------
-        let ctx = ExecutionContext {
-            workflow_id: workflow.id,
-            execution_id,
-            input: initial_input.clone(),
-            secrets: HashMap::new(),
-        };
+        let routing = Routing::new(workflow, sorted_ids);
+        let start = routing.position(start_node).unwrap_or(0);
 
         // ------------------------------------------------------------------
         // Execute nodes sequentially.
         // ------------------------------------------------------------------
-        let mut current_input = initial_input;
-
-        for node_id in &sorted_ids {
+        // `state.outputs` holds the outputs of nodes that succeeded without
+        // halting the flow and `state.branches` the branch each branching
+        // node picked; nodes before `start` ran before a deferral.
+        for node_id in &sorted_ids[start..] {
             let node_def = node_map[node_id.as_str()];
 
+            let Some(current_input) = routing.input(node_id, &state) else {
+                info!("node '{}' skipped: no active upstream node", node_id);
+                continue;
+            };
+
             let node_impl = self.registry.get(&node_def.node_type).ok_or_else(|| {
                 EngineError::NodeFatal {
                     node_id: node_id.clone(),
@@ -154,53 +710,181 @@ impl WorkflowExecutor {
                 }
             })?;
 
-            let node_output = self
-                .execute_with_retry(node_id, node_impl.as_ref(), current_input.clone(), &ctx)
-                .await;
+            // `{{ secrets.KEY }}` placeholders are filled in here, so nodes
+            // never see them and a missing secret fails like any node error;
+            // `{{ params.NAME }}` ones after them.
+            let (mut config, missing_secret) = match secrets::interpolate(&node_def.config, &ctx.secrets) {
+                Ok(config) => (config, None),
+                Err(key) => (node_def.config.clone(), Some(key)),
+            };
+            if !params.is_empty() {
+                config = params::interpolate(&config, &params);
+            }
+            let node_ctx = ctx.for_node(node_id.as_str(), config);
+            let started_at = Utc::now();
+            let timer = Instant::now();
+            let mut attempts = Vec::new();
+            let node_output = match missing_secret {
+                Some(key) => Err(EngineError::NodeFatal {
+                    node_id: node_id.clone(),
+                    message: format!("missing secret '{key}' for node '{node_id}'"),
+                }),
+                None => {
+                    let tags = Tags {
+                        workflow_id: Some(workflow.id),
+                        execution_id: Some(execution_id),
+                        node_id: Some(node_id.clone()),
+                    };
+                    let mut observe = |attempt: &Attempt| {
+                        if let Some(span) = span {
+                            span.attempt(node_id, &node_def.node_type, attempt);
+                        }
+                        attempts.push(NodeAttempt {
+                            attempt: attempt.number as i32,
+                            started_at: attempt.started_at,
+                            finished_at: attempt.finished_at,
+                            error: attempt.error.clone(),
+                        });
+                    };
+                    let run = self.runner.run_observed(node_id, node_impl, &current_input, &node_ctx, &mut observe);
+                    error_reporting::tagged(tags, run).await
+                }
+            };
+            let took = timer.elapsed();
+            metrics::node_finished(&node_def.node_type, node_output.is_ok(), took);
+            if node_output.is_ok() {
+                self.check_slow_node(workflow, execution_id, node_id, &node_def.node_type, took).await;
+            }
+            if !matches!(node_output, Err(EngineError::InjectedCrash { .. })) {
+                self.save_logs(execution_id, node_id, &node_ctx).await;
+            }
 
             match node_output {
                 Ok(output) => {
+                    let flow = node_ctx.take_flow();
+                    if let Flow::Defer(until) = flow {
+                        // The node runs again on resumption; its output is dropped.
+                        info!("node '{}' deferred the execution until {}", node_id, until);
+                        let last_output = Value::clone(&state.last_output);
+                        let checkpoint = state.into_checkpoint(node_id.clone());
+                        writer.finish(None).await?;
+                        let job = db::repository::jobs::enqueue_resume_job(
+                            &self.pool,
+                            execution_id,
+                            workflow.id,
+                            checkpoint.input.clone(),
+                            serde_json::to_value(&checkpoint).expect("checkpoints serialize"),
+                            until,
+                        )
+                        .await?;
+                        self.queue.push(&job).await?;
+                        db::repository::executions::update_execution_status(
+                            &self.pool, execution_id, "waiting", false,
+                        )
+                        .await?;
+
+                        return Ok(ExecutionResult {
+                            execution_id,
+                            output: last_output,
+                            deferred_until: Some(until),
+                        });
+                    }
+
                     // Persist success.
-                    let started_at = Utc::now(); // approximate — good enough for scaffold
-                    db::repository::executions::insert_node_execution(
-                        &self.pool,
-                        execution_id,
-                        node_id,
-                        current_input.clone(),
-                        Some(output.clone()),
-                        "succeeded",
-                        started_at,
-                    )
-                    .await?;
+                    let output = Arc::new(output);
+                    writer
+                        .record(NodeRecord {
+                            node_id: node_id.clone(),
+                            input: secrets::redact(&current_input, &ctx.secrets),
+                            output: Some(output.clone()),
+                            status: "succeeded",
+                            started_at,
+                            hash: record_hashes,
+                            error: None,
+                            attempts,
+                            flow: NodeFlow {
+                                branch: match &flow {
+                                    Flow::Branch(branch) => Some(branch.clone()),
+                                    _ => None,
+                                },
+                                halted: matches!(flow, Flow::Halt),
+                            },
+                        })
+                        .await;
 
                     info!("node '{}' succeeded", node_id);
-                    current_input = output;
+                    match flow {
+                        Flow::Continue => {
+                            state.outputs.insert(node_id.clone(), output.clone());
+                        }
+                        Flow::Branch(branch) => {
+                            info!("node '{}' took branch '{}'", node_id, branch);
+                            state.outputs.insert(node_id.clone(), output.clone());
+                            state.branches.insert(node_id.clone(), branch);
+                        }
+                        Flow::Halt | Flow::Defer(_) => {
+                            info!("node '{}' halted the flow", node_id);
+                        }
+                    }
+                    state.last_output = output;
+                }
+
+                // A simulated crash leaves everything as a dead worker would.
+                Err(crash @ EngineError::InjectedCrash { .. }) => {
+                    warn!("{}", crash);
+                    return Err(crash);
                 }
 
                 Err(engine_err) => {
                     // Persist failure.
-                    let started_at = Utc::now();
-                    let _ = db::repository::executions::insert_node_execution(
-                        &self.pool,
-                        execution_id,
-                        node_id,
-                        current_input.clone(),
-                        None,
-                        "failed",
-                        started_at,
-                    )
-                    .await;
+                    writer
+                        .record(NodeRecord {
+                            node_id: node_id.clone(),
+                            input: secrets::redact(&current_input, &ctx.secrets),
+                            output: None,
+                            status: "failed",
+                            started_at,
+                            hash: record_hashes,
+                            error: Some(engine_err.to_string()),
+                            flow: NodeFlow::default(),
+                            attempts,
+                        })
+                        .await;
 
                     error!("node '{}' failed: {}", node_id, engine_err);
 
-                    // Mark the whole execution as failed.
-                    let _ = db::repository::executions::update_execution_status(
-                        &self.pool,
-                        execution_id,
-                        "failed",
-                        true,
-                    )
-                    .await;
+                    // Mark the whole execution as failed, together with the
+                    // failed node; if that cannot be written, still mark it.
+                    if writer.finish(Some(Transition::FAILED)).await.is_err() {
+                        let _ = db::repository::executions::update_execution_status(
+                            &self.pool,
+                            execution_id,
+                            "failed",
+                            true,
+                        )
+                        .await;
+                    }
+                    metrics::execution_finished(false);
+                    if let Some(reporter) = &self.errors {
+                        if let Some(report) = ErrorReport::for_node_error(&engine_err, workflow.id, execution_id) {
+                            reporter.report(report);
+                        }
+                    }
+
+                    if let Some(error_workflow_id) = workflow.error_workflow_id {
+                        let failure = Failure {
+                            workflow_id: workflow.id,
+                            workflow_name: workflow.name.clone(),
+                            execution_id,
+                            node_id: node_id.clone(),
+                            message: engine_err.to_string(),
+                            failed_at: Utc::now(),
+                            kind: FailureKind::Failed,
+                        };
+                        if let Err(e) = error_workflow::launch(self, error_workflow_id, failure).await {
+                            warn!("cannot start error workflow {}: {}", error_workflow_id, e);
+                        }
+                    }
 
                     return Err(engine_err);
                 }
@@ -208,36 +892,175 @@ impl WorkflowExecutor {
         }
 
         // ------------------------------------------------------------------
-        // Mark execution as succeeded.
+        // Mark execution as succeeded with its last node records.
         // ------------------------------------------------------------------
-        db::repository::executions::update_execution_status(
-            &self.pool, execution_id, "succeeded", true,
-        )
-        .await?;
+        writer.finish(Some(Transition::SUCCEEDED)).await?;
+        metrics::execution_finished(true);
 
         info!("workflow '{}' execution {} succeeded", workflow.id, execution_id);
 
+        // Release the other reference so the last output is moved, not copied.
+        state.outputs.clear();
+        let output = Arc::unwrap_or_clone(state.last_output);
         Ok(ExecutionResult {
             execution_id,
-            output: current_input,
+            output,
+            deferred_until: None,
         })
     }
 
-    // -----------------------------------------------------------------------
-    // Internal: execute a single node with retry logic.
-    // -----------------------------------------------------------------------
+    /// The shared context of execution `execution_id`, with `workflow`'s
+    /// decrypted secrets and the shared credentials its nodes name.
+    async fn context(
+        &self,
+        workflow: &Workflow,
+        execution_id: uuid::Uuid,
+        input: &Value,
+    ) -> Result<ExecutionContext, EngineError> {
+        let mut ctx = ExecutionContext::new(workflow.id, execution_id, input.clone())
+            .with_state(Arc::new(PgWorkflowStateStore::new(self.pool.clone())))
+            .with_subworkflows(Arc::new(ExecutorSubWorkflows::new(self.clone())))
+            .with_clock(self.clock.clone());
+        if let Some(random) = &self.random {
+            ctx = ctx.with_random(random.clone());
+        }
+        if let Some(binary) = &self.binary {
+            ctx = ctx.with_binary(binary.clone());
+        }
+        if let Some(provider) = &self.secret_provider {
+            ctx.secrets = provider.load(workflow.id).await?;
+        }
+        if let Some(key) = &self.secrets {
+            if self.secret_provider.is_none() {
+                ctx.secrets = secrets::load(&self.pool, key, workflow.id, self.environment.as_deref()).await?;
+            }
+            ctx.credentials = secrets::load_credentials(&self.pool, key, workflow).await?;
+        }
+        Ok(ctx)
+    }
+
+    /// The parameters of execution `execution_id`: its overrides over
+    /// `workflow`'s defaults.
+    async fn params(
+        &self,
+        workflow: &Workflow,
+        execution_id: uuid::Uuid,
+    ) -> Result<serde_json::Map<String, Value>, EngineError> {
+        if workflow.params.is_empty() {
+            return Ok(serde_json::Map::new());
+        }
+        let overrides = db::repository::executions::execution_params(&self.pool, execution_id).await?;
+        params::resolve(&workflow.params, &overrides).map_err(EngineError::InvalidParams)
+    }
+
+    /// Fail execution `execution_id`, which could not start because of
+    /// `err`, and return `err`.  Infrastructure errors leave it as it is:
+    /// the worker runs the job again.
+    async fn not_started(&self, execution_id: uuid::Uuid, err: EngineError) -> EngineError {
+        if matches!(err, EngineError::Database(_) | EngineError::Queue(_)) {
+            return err;
+        }
+        error!("execution {} cannot start: {}", execution_id, err);
+        if let Err(e) =
+            db::repository::executions::update_execution_status(&self.pool, execution_id, "failed", true).await
+        {
+            warn!("cannot mark execution {} failed: {}", execution_id, e);
+        }
+        metrics::execution_finished(false);
+        err
+    }
+
+}
 
-    async fn execute_with_retry(
+// ---------------------------------------------------------------------------
+// NodeRunner
+// ---------------------------------------------------------------------------
+
+/// Runs one node with the executor's retry policy (and chaos injection, if
+/// enabled).  Holds no database handle, so simulations can drive it directly.
+///
+/// Nodes that report [`ExecutableNode::is_blocking`] run on the runner's
+/// [`BlockingPool`] rather than the async runtime.
+#[derive(Debug, Clone)]
+pub struct NodeRunner {
+    config: ExecutorConfig,
+    chaos: Option<Arc<Chaos>>,
+    blocking: BlockingPool,
+}
+
+impl NodeRunner {
+    pub fn new(config: ExecutorConfig) -> Self {
+        Self { config, chaos: None, blocking: BlockingPool::default() }
+    }
+
+    /// Run blocking nodes on `pool` (typically shared process-wide).
+    pub fn with_blocking_pool(mut self, pool: BlockingPool) -> Self {
+        self.blocking = pool;
+        self
+    }
+
+    /// Inject faults into node attempts (see [`crate::chaos`]).
+    pub fn with_chaos(mut self, config: ChaosConfig) -> Self {
+        warn!("chaos mode enabled: {:?}", config);
+        self.chaos = Some(Arc::new(Chaos::new(config)));
+        self
+    }
+
+    /// Execute `node`, retrying retryable errors with exponential back-off.
+    ///
+    /// # Errors
+    /// [`EngineError::NodeFatal`] on a fatal error,
+    /// [`EngineError::NodeRetryExhausted`] once retries are used up, and
+    /// [`EngineError::InjectedCrash`] when chaos simulates a crash.
+    pub async fn run(
         &self,
         node_id: &str,
-        node: &dyn ExecutableNode,
-        input: Value,
+        node: &Arc<dyn ExecutableNode>,
+        input: &Value,
+        ctx: &ExecutionContext,
+    ) -> Result<Value, EngineError> {
+        self.run_observed(node_id, node, input, ctx, &mut |_| {}).await
+    }
+
+    /// [`NodeRunner::run`], telling `observe` how each attempt went.
+    ///
+    /// # Errors
+    /// Same as [`NodeRunner::run`].
+    pub async fn run_observed(
+        &self,
+        node_id: &str,
+        node: &Arc<dyn ExecutableNode>,
+        input: &Value,
         ctx: &ExecutionContext,
+        observe: &mut (dyn FnMut(&Attempt) + Send),
     ) -> Result<Value, EngineError> {
         let mut attempts = 0u32;
 
         loop {
-            match node.execute(input.clone(), ctx).await {
+            let started_at = Utc::now();
+            // Discard any flow decision left behind by a failed attempt.
+            ctx.take_flow();
+            let injection = self.chaos.as_deref().map(Chaos::roll).unwrap_or_default();
+            if let Some(latency) = injection.latency {
+                tokio::time::sleep(latency).await;
+            }
+            let result = match injection.fault {
+                Some(Fault::Crash) => {
+                    return Err(EngineError::InjectedCrash { node_id: node_id.to_owned() });
+                }
+                Some(Fault::Failure) => Err(NodeError::Retryable("chaos: injected failure".into())),
+                None if node.is_blocking() => self.blocking.run(node.clone(), input.clone(), ctx).await,
+                None => node.execute(input.clone(), ctx).await,
+            };
+            let retrying = matches!(result, Err(NodeError::Retryable(_))) && attempts < self.config.max_retries;
+            observe(&Attempt {
+                number: attempts + 1,
+                started_at,
+                finished_at: Utc::now(),
+                error: result.as_ref().err().map(ToString::to_string),
+                retrying,
+            });
+            match result {
                 Ok(output) => return Ok(output),
 
                 Err(NodeError::Fatal(msg)) => {
@@ -256,8 +1079,7 @@ impl WorkflowExecutor {
                         });
                     }
 
-                    let delay = self.config.retry_base_delay
-                        * 2u32.pow(attempts.saturating_sub(1));
+                    let delay = self.config.retry_delay(attempts);
 
                     warn!(
                         "node '{}' retryable error (attempt {}/{}), retrying in {:?}: {}",
@@ -7,7 +7,6 @@
 //! `tests/it/` and are gated behind the `integration` feature flag.
 
 use std::collections::HashMap;
-use std::sync::Arc;
 use serde_json::{json, Value};
 
 // ---------------------------------------------------------------------------
@@ -25,8 +24,8 @@ use serde_json::{json, Value};
 //       repository functions so they can be replaced later.
 //       Tests that need a real Postgres instance are in `tests/integration/`.
 
-use engine::{Workflow, Trigger, models::{NodeDefinition, Edge}};
-use engine::dag::validate_dag;
+use crate::{Workflow, Trigger, models::{NodeDefinition, Edge}};
+use crate::dag::validate_dag;
 use nodes::mock::MockNode;
 use nodes::ExecutableNode;
 use nodes::traits::ExecutionContext;
@@ -40,15 +39,16 @@ fn linear_workflow(ids: &[&str]) -> Workflow {
             id: id.to_string(),
             node_type: "mock".into(),
             config: Value::Null,
+            worker_tags: Vec::new(),
         })
         .collect();
 
     let edges: Vec<Edge> = ids
         .windows(2)
-        .map(|w| Edge { from: w[0].into(), to: w[1].into() })
+        .map(|w| Edge::new(w[0], w[1]))
         .collect();
 
-    Workflow::new("test-linear", Trigger::Manual, nodes, edges)
+    Workflow::new("test-linear", Trigger::Manual { input_schema: None }, nodes, edges)
 }
 
 // ============================================================
@@ -66,7 +66,7 @@ fn linear_workflow_validates_and_sorts_correctly() {
 fn cycle_in_linear_workflow_is_detected() {
     let mut wf = linear_workflow(&["x", "y", "z"]);
     // Add a back-edge to create a cycle.
-    wf.edges.push(Edge { from: "z".into(), to: "x".into() });
+    wf.edges.push(Edge::new("z", "x"));
     assert!(validate_dag(&wf).is_err());
 }
 
@@ -74,9 +74,9 @@ fn cycle_in_linear_workflow_is_detected() {
 fn missing_node_reference_is_rejected() {
     let wf = Workflow::new(
         "bad",
-        Trigger::Manual,
-        vec![NodeDefinition { id: "a".into(), node_type: "mock".into(), config: Value::Null }],
-        vec![Edge { from: "a".into(), to: "b".into() }], // 'b' doesn't exist
+        Trigger::Manual { input_schema: None },
+        vec![NodeDefinition { id: "a".into(), node_type: "mock".into(), config: Value::Null, worker_tags: Vec::new() }],
+        vec![Edge::new("a", "b")], // 'b' doesn't exist
     );
     assert!(validate_dag(&wf).is_err());
 }
@@ -86,12 +86,7 @@ fn missing_node_reference_is_rejected() {
 // ============================================================
 
 fn make_ctx(wf: &Workflow) -> ExecutionContext {
-    ExecutionContext {
-        workflow_id: wf.id,
-        execution_id: uuid::Uuid::new_v4(),
-        input: json!({}),
-        secrets: HashMap::new(),
-    }
+    ExecutionContext::new(wf.id, uuid::Uuid::new_v4(), json!({}))
 }
 
 /// Execute a sequence of MockNodes manually (bypassing WorkflowExecutor + DB)
@@ -182,12 +177,7 @@ async fn fatal_node_error_stops_pipeline() {
 #[tokio::test]
 async fn retryable_node_error_is_returned_correctly() {
     let node = MockNode::failing_retryable("flaky", "transient failure");
-    let ctx = ExecutionContext {
-        workflow_id: uuid::Uuid::new_v4(),
-        execution_id: uuid::Uuid::new_v4(),
-        input: json!({}),
-        secrets: HashMap::new(),
-    };
+    let ctx = ExecutionContext::new(uuid::Uuid::new_v4(), uuid::Uuid::new_v4(), json!({}));
 
     let result = node.execute(json!({}), &ctx).await;
     assert!(matches!(result, Err(nodes::NodeError::Retryable(_))));
@@ -0,0 +1,225 @@
+//! Feature flags for rolling out engine and API behaviours gradually.
+//!
+//! A flag's value is resolved from the most specific source that sets it:
+//!
+//! 1. a stored override for the workflow,
+//! 2. a stored override for the workflow's organization,
+//! 3. a stored global override,
+//! 4. the default configured at startup (`--feature name[=off]`),
+//! 5. otherwise off.
+//!
+//! Overrides live in the `feature_flags` table and are cached in memory;
+//! [`FeatureFlags::run`] refreshes the cache periodically, so checks are
+//! cheap, synchronous, and never fail.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use tracing::warn;
+use uuid::Uuid;
+
+use db::DbPool;
+use db::models::FeatureFlagRow;
+use db::repository::feature_flags as flag_repo;
+
+use crate::EngineError;
+
+/// Read-only support access to any workflow or execution.
+pub const SUPPORT_ACCESS: &str = "support_access";
+
+/// Record node input/output hashes for [`crate::determinism::compare`].
+pub const DETERMINISM_REPORT: &str = "determinism_report";
+
+/// The read-only GraphQL endpoint, `POST /api/v1/graphql`.
+pub const GRAPHQL: &str = "graphql";
+
+/// What a flag is being checked for.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlagScope<'a> {
+    pub organization: Option<&'a str>,
+    pub workflow: Option<Uuid>,
+}
+
+impl<'a> FlagScope<'a> {
+    /// No organization or workflow: only global settings apply.
+    pub fn global() -> Self {
+        Self::default()
+    }
+
+    pub fn workflow(id: Uuid) -> Self {
+        Self { organization: None, workflow: Some(id) }
+    }
+
+    pub fn in_organization(mut self, organization: &'a str) -> Self {
+        self.organization = Some(organization);
+        self
+    }
+}
+
+/// Shared, cheaply clonable feature flag service.
+#[derive(Clone)]
+pub struct FeatureFlags {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    pool: Option<DbPool>,
+    defaults: HashMap<String, bool>,
+    overrides: RwLock<Vec<FeatureFlagRow>>,
+}
+
+impl FeatureFlags {
+    /// Flags backed by the `feature_flags` table, falling back to `defaults`.
+    /// Call [`refresh`](Self::refresh) (or spawn [`run`](Self::run)) to load
+    /// the stored overrides.
+    pub fn new(pool: DbPool, defaults: HashMap<String, bool>) -> Self {
+        Self::build(Some(pool), defaults)
+    }
+
+    /// Flags from configuration only, without stored overrides.
+    pub fn from_defaults(defaults: HashMap<String, bool>) -> Self {
+        Self::build(None, defaults)
+    }
+
+    fn build(pool: Option<DbPool>, defaults: HashMap<String, bool>) -> Self {
+        Self { inner: Arc::new(Inner { pool, defaults, overrides: RwLock::new(Vec::new()) }) }
+    }
+
+    /// Whether `flag` is on for `scope`.
+    pub fn is_enabled(&self, flag: &str, scope: &FlagScope<'_>) -> bool {
+        let overrides = self.inner.overrides.read().unwrap_or_else(|e| e.into_inner());
+        resolve(&self.inner.defaults, &overrides, flag, scope)
+    }
+
+    /// Startup defaults.
+    pub fn defaults(&self) -> &HashMap<String, bool> {
+        &self.inner.defaults
+    }
+
+    /// Currently cached overrides.
+    pub fn overrides(&self) -> Vec<FeatureFlagRow> {
+        self.inner.overrides.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Reload the stored overrides.  A no-op without a database.
+    pub async fn refresh(&self) -> Result<(), EngineError> {
+        let Some(pool) = &self.inner.pool else { return Ok(()) };
+        let rows = flag_repo::list_flags(pool).await?;
+        *self.inner.overrides.write().unwrap_or_else(|e| e.into_inner()) = rows;
+        Ok(())
+    }
+
+    /// Refresh every `interval` until the task is dropped; on failure the
+    /// previous overrides stay in effect.
+    pub async fn run(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.refresh().await {
+                warn!("feature flags: refresh failed: {}", e);
+            }
+        }
+    }
+}
+
+impl Default for FeatureFlags {
+    fn default() -> Self {
+        Self::from_defaults(HashMap::new())
+    }
+}
+
+impl std::fmt::Debug for FeatureFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FeatureFlags").field("defaults", &self.inner.defaults).finish()
+    }
+}
+
+/// Parse startup defaults such as `["parallel", "new_expressions=off", "!legacy"]`.
+///
+/// A bare name enables the flag; `name=on|off|true|false|1|0` and `!name`
+/// set it explicitly.  Returns the offending entry on a bad value.
+pub fn parse_defaults<S: AsRef<str>>(entries: &[S]) -> Result<HashMap<String, bool>, String> {
+    let mut defaults = HashMap::new();
+    for entry in entries {
+        let entry = entry.as_ref().trim();
+        if entry.is_empty() {
+            continue;
+        }
+        let (name, enabled) = match entry.split_once('=') {
+            Some((name, value)) => match value.trim().to_ascii_lowercase().as_str() {
+                "on" | "true" | "1" => (name, true),
+                "off" | "false" | "0" => (name, false),
+                _ => return Err(entry.to_owned()),
+            },
+            None => match entry.strip_prefix('!') {
+                Some(name) => (name, false),
+                None => (entry, true),
+            },
+        };
+        defaults.insert(name.trim().to_owned(), enabled);
+    }
+    Ok(defaults)
+}
+
+fn resolve(
+    defaults: &HashMap<String, bool>,
+    overrides: &[FeatureFlagRow],
+    flag: &str,
+    scope: &FlagScope<'_>,
+) -> bool {
+    let stored = |kind: &str, id: &str| {
+        overrides
+            .iter()
+            .find(|o| o.flag == flag && o.scope == kind && o.scope_id == id)
+            .map(|o| o.enabled)
+    };
+
+    scope
+        .workflow
+        .and_then(|id| stored("workflow", &id.to_string()))
+        .or_else(|| scope.organization.and_then(|org| stored("organization", org)))
+        .or_else(|| stored("global", ""))
+        .or_else(|| defaults.get(flag).copied())
+        .unwrap_or(false)
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(scope: &str, scope_id: &str, enabled: bool) -> FeatureFlagRow {
+        FeatureFlagRow {
+            flag: "parallel".into(),
+            scope: scope.into(),
+            scope_id: scope_id.into(),
+            enabled,
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn most_specific_setting_wins() {
+        let wf = Uuid::new_v4();
+        let defaults = parse_defaults(&["parallel"]).unwrap();
+        let overrides = vec![row("organization", "acme", false), row("workflow", &wf.to_string(), true)];
+
+        let flag = |scope: FlagScope<'_>| resolve(&defaults, &overrides, "parallel", &scope);
+        assert!(flag(FlagScope::global()));
+        assert!(!flag(FlagScope::global().in_organization("acme")));
+        assert!(flag(FlagScope::workflow(wf).in_organization("acme")));
+        assert!(!flag(FlagScope::workflow(Uuid::new_v4()).in_organization("acme")));
+        assert!(!resolve(&defaults, &overrides, "unknown", &FlagScope::global()));
+    }
+
+    #[test]
+    fn parses_startup_defaults() {
+        let defaults = parse_defaults(&["a", "b=off", "!c", "d = on", ""]).unwrap();
+        assert_eq!(defaults.len(), 4);
+        assert!(defaults["a"] && !defaults["b"] && !defaults["c"] && defaults["d"]);
+        assert_eq!(parse_defaults(&["a=maybe"]), Err("a=maybe".to_string()));
+    }
+}
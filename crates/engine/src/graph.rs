@@ -0,0 +1,155 @@
+//! Workflow graphs as text, for rendering with other tools.
+//!
+//! [`to_dot`] writes Graphviz DOT (`dot -Tpng`), [`to_mermaid`] a Mermaid
+//! flowchart (Markdown, GitHub, the Mermaid live editor).  Nodes show their
+//! id and type; branch edges are labelled with their branch.  Given the
+//! statuses of an execution's nodes, both colour each node by how it fared
+//! and grey out the ones it never reached.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::Workflow;
+
+/// A text format [`render`] can write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+/// `workflow` in `format`; see [`to_dot`] and [`to_mermaid`].
+pub fn render(workflow: &Workflow, format: GraphFormat, statuses: &HashMap<String, String>) -> String {
+    match format {
+        GraphFormat::Dot => to_dot(workflow, statuses),
+        GraphFormat::Mermaid => to_mermaid(workflow, statuses),
+    }
+}
+
+/// Fill and stroke colours for a node with `status`; `None` for a node an
+/// execution did not reach.
+fn colours(status: Option<&str>) -> (&'static str, &'static str) {
+    match status {
+        Some("succeeded") => ("#d4edda", "#28a745"),
+        Some("failed") => ("#f8d7da", "#dc3545"),
+        Some("running") | Some("waiting") | Some("pending") => ("#fff3cd", "#ffc107"),
+        Some(_) => ("#e2e3e5", "#6c757d"),
+        None => ("#ffffff", "#adb5bd"),
+    }
+}
+
+/// `workflow` as a Graphviz digraph, coloured by `statuses` (node id →
+/// status) unless it is empty.
+pub fn to_dot(workflow: &Workflow, statuses: &HashMap<String, String>) -> String {
+    let escape = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+    let quote = |text: &str| format!("\"{}\"", escape(text));
+    let mut dot = String::new();
+    let _ = writeln!(dot, "digraph {} {{", quote(&workflow.name));
+    let _ = writeln!(dot, "  rankdir=LR;");
+    let _ = writeln!(dot, "  node [shape=box, style=\"rounded,filled\", fillcolor=\"#ffffff\", fontname=\"Helvetica\"];");
+    for node in &workflow.nodes {
+        let label = format!("\"{}\\n{}\"", escape(&node.id), escape(&node.node_type));
+        if statuses.is_empty() {
+            let _ = writeln!(dot, "  {} [label={label}];", quote(&node.id));
+            continue;
+        }
+        let status = statuses.get(&node.id).map(String::as_str);
+        let (fill, stroke) = colours(status);
+        let dashed = if status.is_none() { ", style=\"rounded,filled,dashed\"" } else { "" };
+        let _ = writeln!(
+            dot,
+            "  {} [label={label}, fillcolor=\"{fill}\", color=\"{stroke}\"{dashed}];",
+            quote(&node.id)
+        );
+    }
+    for edge in &workflow.edges {
+        let label = edge.branch.as_deref().map(|b| format!(" [label={}]", quote(b))).unwrap_or_default();
+        let _ = writeln!(dot, "  {} -> {}{label};", quote(&edge.from), quote(&edge.to));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// `workflow` as a Mermaid flowchart, coloured by `statuses` (node id →
+/// status) unless it is empty.
+pub fn to_mermaid(workflow: &Workflow, statuses: &HashMap<String, String>) -> String {
+    // Mermaid ids are restricted, so nodes are numbered and labelled.
+    let ids: HashMap<&str, String> =
+        workflow.nodes.iter().enumerate().map(|(i, node)| (node.id.as_str(), format!("n{i}"))).collect();
+    let escape = |text: &str| text.replace('"', "#quot;");
+    let mut chart = String::from("flowchart LR\n");
+    for node in &workflow.nodes {
+        let _ = writeln!(chart, "  {}[\"{}<br/><i>{}</i>\"]", ids[node.id.as_str()], escape(&node.id), escape(&node.node_type));
+    }
+    for edge in &workflow.edges {
+        let (Some(from), Some(to)) = (ids.get(edge.from.as_str()), ids.get(edge.to.as_str())) else { continue };
+        match &edge.branch {
+            Some(branch) => {
+                let _ = writeln!(chart, "  {from} -->|\"{}\"| {to}", escape(branch));
+            }
+            None => {
+                let _ = writeln!(chart, "  {from} --> {to}");
+            }
+        }
+    }
+    if !statuses.is_empty() {
+        for node in &workflow.nodes {
+            let status = statuses.get(&node.id).map(String::as_str);
+            let (fill, stroke) = colours(status);
+            let dashed = if status.is_none() { ",stroke-dasharray:4 3" } else { "" };
+            let _ = writeln!(chart, "  style {} fill:{fill},stroke:{stroke}{dashed}", ids[node.id.as_str()]);
+        }
+    }
+    chart
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn workflow() -> Workflow {
+        serde_json::from_value(json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": "orders \"eu\"",
+            "trigger": { "type": "manual" },
+            "nodes": [
+                { "id": "check", "node_type": "if", "config": {} },
+                { "id": "ship", "node_type": "http_request", "config": {} },
+                { "id": "refund", "node_type": "http_request", "config": {} }
+            ],
+            "edges": [
+                { "from": "check", "to": "ship", "branch": "true" },
+                { "from": "check", "to": "refund", "branch": "false" }
+            ],
+            "created_at": "2024-01-01T00:00:00Z"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn dot_lists_nodes_and_branch_edges() {
+        let dot = to_dot(&workflow(), &HashMap::new());
+        assert!(dot.starts_with("digraph \"orders \\\"eu\\\"\" {"));
+        assert!(dot.contains("  \"ship\" [label=\"ship\\nhttp_request\"];"));
+        assert!(dot.contains("  \"check\" -> \"refund\" [label=\"false\"];"));
+        assert!(!dot.contains("fillcolor=\"#d4edda\""));
+    }
+
+    #[test]
+    fn statuses_colour_reached_nodes_and_dash_the_rest() {
+        let statuses = HashMap::from([("check".to_owned(), "succeeded".to_owned()), ("ship".to_owned(), "failed".to_owned())]);
+        let dot = to_dot(&workflow(), &statuses);
+        assert!(dot.contains("\"check\" [label=\"check\\nif\", fillcolor=\"#d4edda\""));
+        assert!(dot.contains("\"ship\" [label=\"ship\\nhttp_request\", fillcolor=\"#f8d7da\""));
+        assert!(dot.contains("\"refund\" [label=\"refund\\nhttp_request\", fillcolor=\"#ffffff\", color=\"#adb5bd\", style=\"rounded,filled,dashed\"]"));
+
+        let chart = to_mermaid(&workflow(), &statuses);
+        assert!(chart.contains("  n0 -->|\"true\"| n1\n"));
+        assert!(chart.contains("  style n1 fill:#f8d7da,stroke:#dc3545\n"));
+        assert!(chart.contains("  style n2 fill:#ffffff,stroke:#adb5bd,stroke-dasharray:4 3\n"));
+    }
+}
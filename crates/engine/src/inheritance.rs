@@ -0,0 +1,162 @@
+//! Priority inheritance for executions started by other executions.
+//!
+//! When a run starts another workflow — a sub-workflow, or a chained
+//! workflow triggered with its `parent_execution_id` — the child execution
+//! takes over the parent's priority, queue, and labels as the parent
+//! workflow's [`InheritancePolicy`] says (falling back to a process-wide
+//! default).  Without this, an urgent run's follow-up work would queue at
+//! default priority behind bulk work on its second hop.
+//!
+//! The parent is recorded on the child execution, and every job queued for
+//! the child (including resumptions) carries the child's priority and queue.
+
+use serde_json::Value;
+use uuid::Uuid;
+
+use db::DbPool;
+use db::models::{ExecutionMeta, JobRow, WorkflowExecutionRow};
+use db::repository::{executions as exec_repo, jobs as job_repo, workflows as wf_repo};
+use queue::JobQueue;
+
+use crate::{quotas, EngineError, InheritancePolicy, PriorityInheritance, QueueInheritance, Workflow};
+
+impl InheritancePolicy {
+    /// Combine a child's own execution metadata with its parent's.
+    pub fn apply(&self, parent: &WorkflowExecutionRow, mut child: ExecutionMeta) -> ExecutionMeta {
+        child.priority = match self.priority {
+            PriorityInheritance::Max => child.priority.max(parent.priority),
+            PriorityInheritance::Parent => parent.priority,
+            PriorityInheritance::Child => child.priority,
+        };
+        if self.queue == QueueInheritance::Parent {
+            child.queue = Some(parent.queue.clone());
+        }
+        if self.labels {
+            for label in &parent.labels {
+                if !child.labels.contains(label) {
+                    child.labels.push(label.clone());
+                }
+            }
+        }
+        child.parent_execution_id = Some(parent.id);
+        child
+    }
+}
+
+/// Apply the inheritance of execution `parent_execution_id` to a child's
+/// own execution metadata, using the parent workflow's policy (or
+/// `default_policy` when the parent workflow sets none).
+///
+/// # Errors
+/// [`EngineError::Database`] when the parent execution or its workflow
+/// cannot be loaded.
+pub async fn inherit(
+    pool: &DbPool,
+    parent_execution_id: Uuid,
+    child: ExecutionMeta,
+    default_policy: &InheritancePolicy,
+) -> Result<ExecutionMeta, EngineError> {
+    let parent = exec_repo::get_execution(pool, parent_execution_id).await?;
+    let parent_row = wf_repo::get_workflow(pool, parent.workflow_id).await?;
+    let policy = serde_json::from_value::<Workflow>(parent_row.definition)
+        .ok()
+        .and_then(|wf| wf.inheritance)
+        .unwrap_or_else(|| default_policy.clone());
+    Ok(policy.apply(&parent, child))
+}
+
+/// Create an execution of `child` started by execution `parent_execution_id`
+/// and queue its job, inheriting as [`inherit`] does.
+///
+/// # Errors
+/// [`EngineError::QuotaExceeded`] when the child's quota is used up,
+/// [`EngineError::Database`] when the parent cannot be loaded or the child
+/// cannot be queued, [`EngineError::Queue`] when it cannot be pushed.
+pub async fn start_child(
+    pool: &DbPool,
+    queue: &dyn JobQueue,
+    parent_execution_id: Uuid,
+    child: &Workflow,
+    input: Value,
+    default_policy: &InheritancePolicy,
+) -> Result<JobRow, EngineError> {
+    quotas::check(pool, child.id, child.quota.as_ref()).await?;
+    let meta = inherit(pool, parent_execution_id, child.execution_meta(&input), default_policy).await?;
+    let exec = exec_repo::create_execution(pool, child.id, &meta).await?;
+    let job = job_repo::enqueue_job(pool, exec.id, child.id, input).await?;
+    queue.push(&job).await?;
+    Ok(job)
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parent(priority: i32, queue: &str, labels: &[&str]) -> WorkflowExecutionRow {
+        WorkflowExecutionRow {
+            id: Uuid::new_v4(),
+            workflow_id: Uuid::new_v4(),
+            status: "running".into(),
+            started_at: chrono::Utc::now(),
+            finished_at: None,
+            business_key: None,
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            priority,
+            queue: queue.into(),
+            parent_execution_id: None,
+            required_tags: Vec::new(),
+            max_attempts: 3,
+            retry_of: None,
+            workflow_version: None,
+        }
+    }
+
+    fn child(priority: i32, queue: Option<&str>, labels: &[&str]) -> ExecutionMeta {
+        ExecutionMeta {
+            priority,
+            queue: queue.map(Into::into),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            ..ExecutionMeta::default()
+        }
+    }
+
+    #[test]
+    fn default_policy_keeps_urgent_work_urgent() {
+        let urgent = parent(10, "realtime", &["vip", "eu"]);
+        let meta = InheritancePolicy::default().apply(&urgent, child(0, Some("bulk"), &["sync", "vip"]));
+        assert_eq!(meta.priority, 10);
+        assert_eq!(meta.queue.as_deref(), Some("realtime"));
+        assert_eq!(meta.labels, vec!["sync", "vip", "eu"]);
+        assert_eq!(meta.parent_execution_id, Some(urgent.id));
+
+        // A child more urgent than its parent keeps its own priority.
+        let meta = InheritancePolicy::default().apply(&parent(1, "default", &[]), child(5, None, &[]));
+        assert_eq!(meta.priority, 5);
+    }
+
+    #[test]
+    fn policy_can_opt_out_of_each_part() {
+        let policy = InheritancePolicy {
+            priority: PriorityInheritance::Child,
+            queue: QueueInheritance::Child,
+            labels: false,
+        };
+        let meta = policy.apply(&parent(10, "realtime", &["vip"]), child(0, Some("bulk"), &["sync"]));
+        assert_eq!((meta.priority, meta.queue.as_deref()), (0, Some("bulk")));
+        assert_eq!(meta.labels, vec!["sync"]);
+
+        let policy = InheritancePolicy { priority: PriorityInheritance::Parent, ..InheritancePolicy::default() };
+        assert_eq!(policy.apply(&parent(-5, "default", &[]), child(3, None, &[])).priority, -5);
+    }
+
+    #[test]
+    fn policy_deserializes_with_defaults() {
+        let policy: InheritancePolicy = serde_json::from_value(serde_json::json!({ "priority": "parent" })).unwrap();
+        assert_eq!(policy.priority, PriorityInheritance::Parent);
+        assert_eq!(policy.queue, QueueInheritance::Parent);
+        assert!(policy.labels);
+    }
+}
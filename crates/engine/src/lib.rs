@@ -2,13 +2,63 @@
 
 pub mod models;
 pub mod error;
+pub mod error_reporting;
+pub mod error_workflow;
+#[cfg(feature = "amqp")]
+pub mod amqp;
+pub mod approval;
+pub mod automation;
+pub mod backoff;
+pub mod binary;
+pub mod blocking;
+pub mod bundle;
+pub mod chaos;
 pub mod dag;
+pub mod determinism;
 pub mod executor;
+pub mod flags;
+pub mod graph;
+pub mod inheritance;
+pub mod lineage;
+pub mod lint;
+pub mod local;
+pub mod metrics;
+pub mod otel;
+pub mod params;
+pub mod partitions;
+pub mod persistence;
+pub mod pg_notify;
+pub mod polling;
+pub mod privacy;
+pub mod quotas;
+pub mod readiness;
+pub mod retention;
+pub mod retry;
+pub mod scheduler;
+pub mod secret_providers;
+pub mod secrets;
+#[cfg(any(test, feature = "simulation"))]
+pub mod sim;
+pub mod slow_nodes;
+pub mod state;
+pub mod subworkflow;
+pub mod templates;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod timeline;
+pub mod triggers;
+pub mod worker;
 
-pub use models::{Workflow, Trigger, NodeDefinition, Edge};
+pub use models::{
+    Workflow, Trigger, PollSource, Throttle, Debounce, DebouncePayload, SyncResponse, RetentionPolicy, Quota,
+    RetryBackoff, InheritancePolicy, PriorityInheritance, QueueInheritance, NodeDefinition, Edge, Param, ParamType,
+};
 pub use error::EngineError;
+pub use automation::Automation;
 pub use dag::validate_dag;
 pub use executor::WorkflowExecutor;
+pub use flags::{FeatureFlags, FlagScope};
+pub use readiness::Readiness;
 
 #[cfg(test)]
 mod executor_tests;
@@ -0,0 +1,209 @@
+//! Field-level data lineage over a finished execution.
+//!
+//! Given the recorded input/output of every node in an execution and the
+//! workflow's node configs, [`trace`] reports which nodes touched one JSON
+//! path (e.g. `customer.email`):
+//!
+//! * **received** — the path was present in the node's input;
+//! * **read** — the node's config refers to it, through a template
+//!   placeholder (`{{ input.customer.email }}`) or as a path-valued setting
+//!   (`"item_field": "customer.email"`);
+//! * **wrote** — the node's output holds the path with a value it did not
+//!   receive;
+//! * **copied_to** — other output paths holding the traced value, i.e.
+//!   where the data went under a different name.
+//!
+//! The traced value is the first value seen at the path during the run.
+//! Configs come from the current workflow definition, so edits made after
+//! the execution ran are reflected in `read`.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use nodes::template;
+
+use crate::Workflow;
+
+/// The recorded input/output of one node run.
+#[derive(Debug, Clone, Copy)]
+pub struct NodeRecord<'a> {
+    pub node_id: &'a str,
+    pub input: &'a Value,
+    pub output: Option<&'a Value>,
+}
+
+/// How one node interacted with the traced path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NodeLineage {
+    pub node_id: String,
+    pub received: bool,
+    pub read: bool,
+    pub wrote: bool,
+    pub copied_to: Vec<String>,
+}
+
+/// Lineage of one JSON path across an execution.
+#[derive(Debug, Clone, Serialize)]
+pub struct LineageReport {
+    pub path: String,
+    /// First value seen at `path`, if any node had it.
+    pub value: Option<Value>,
+    /// Nodes that touched the path, in execution order.
+    pub nodes: Vec<NodeLineage>,
+}
+
+/// Report which nodes received, read, wrote, or copied `path`.
+pub fn trace(workflow: &Workflow, records: &[NodeRecord<'_>], path: &str) -> LineageReport {
+    let path = path.trim_matches('.');
+    let value = records
+        .iter()
+        .flat_map(|r| std::iter::once(r.input).chain(r.output))
+        .find_map(|v| template::lookup(v, path))
+        .cloned();
+    // Nulls and booleans are too common to say anything about data flow.
+    let traceable = value.as_ref().filter(|v| !matches!(v, Value::Null | Value::Bool(_)));
+
+    let nodes = records
+        .iter()
+        .map(|record| {
+            let received = template::lookup(record.input, path);
+            let written = record.output.and_then(|o| template::lookup(o, path));
+            let read = workflow
+                .nodes
+                .iter()
+                .find(|n| n.id == record.node_id)
+                .is_some_and(|n| config_reads(&n.config, path));
+
+            let mut copied_to = Vec::new();
+            if let (Some(target), Some(output)) = (traceable, record.output) {
+                find_value(output, target, &mut String::new(), &mut copied_to);
+                copied_to.retain(|p| p != path);
+            }
+
+            NodeLineage {
+                node_id: record.node_id.to_owned(),
+                received: received.is_some(),
+                read,
+                wrote: written.is_some() && written != received,
+                copied_to,
+            }
+        })
+        .filter(|n| n.received || n.read || n.wrote || !n.copied_to.is_empty())
+        .collect();
+
+    LineageReport { path: path.to_owned(), value, nodes }
+}
+
+/// Whether any string in `config` refers to `path` or a parent/child of it.
+fn config_reads(config: &Value, path: &str) -> bool {
+    match config {
+        Value::String(s) => {
+            let mut referenced = template::placeholder_paths(s);
+            if !template::has_placeholders(s) {
+                referenced.push(s);
+            }
+            referenced.into_iter().any(|r| {
+                let r = r.strip_prefix("input.").unwrap_or(r);
+                overlaps(r, path)
+            })
+        }
+        Value::Array(items) => items.iter().any(|v| config_reads(v, path)),
+        Value::Object(map) => map.values().any(|v| config_reads(v, path)),
+        _ => false,
+    }
+}
+
+/// `a` and `b` are equal, or one is a dotted prefix of the other.
+fn overlaps(a: &str, b: &str) -> bool {
+    let prefix_of = |short: &str, long: &str| {
+        long.strip_prefix(short).is_some_and(|rest| rest.starts_with('.'))
+    };
+    a == b || prefix_of(a, b) || prefix_of(b, a)
+}
+
+/// Collect the dotted paths inside `value` that hold exactly `target`.
+fn find_value(value: &Value, target: &Value, prefix: &mut String, found: &mut Vec<String>) {
+    if value == target {
+        found.push(prefix.clone());
+        return;
+    }
+    let mut descend = |key: &str, child: &Value| {
+        let len = prefix.len();
+        if !prefix.is_empty() {
+            prefix.push('.');
+        }
+        prefix.push_str(key);
+        find_value(child, target, prefix, found);
+        prefix.truncate(len);
+    };
+    match value {
+        Value::Object(map) => map.iter().for_each(|(k, v)| descend(k, v)),
+        Value::Array(items) => items.iter().enumerate().for_each(|(i, v)| descend(&i.to_string(), v)),
+        _ => {}
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NodeDefinition, Trigger};
+    use serde_json::json;
+
+    fn node(id: &str, config: Value) -> NodeDefinition {
+        NodeDefinition { id: id.into(), node_type: "mock".into(), config, worker_tags: Vec::new() }
+    }
+
+    #[test]
+    fn follows_a_field_through_reads_and_renames() {
+        let wf = Workflow::new(
+            "lineage",
+            Trigger::Manual { input_schema: None },
+            vec![
+                node("fetch", json!({})),
+                node("mail", json!({ "to": "{{ input.customer.email }}" })),
+                node("audit", json!({ "field": "note" })),
+            ],
+            vec![],
+        );
+        let order = json!({ "customer": { "email": "a@b.c" }, "note": "x" });
+        let mail_out = json!({ "recipients": ["a@b.c"], "sent": true });
+        let audit_in = json!({ "note": "x" });
+        let records = [
+            NodeRecord { node_id: "fetch", input: &json!({}), output: Some(&order) },
+            NodeRecord { node_id: "mail", input: &order, output: Some(&mail_out) },
+            NodeRecord { node_id: "audit", input: &audit_in, output: Some(&audit_in) },
+        ];
+
+        let report = trace(&wf, &records, "customer.email");
+        assert_eq!(report.value, Some(json!("a@b.c")));
+        assert_eq!(
+            report.nodes,
+            vec![
+                NodeLineage {
+                    node_id: "fetch".into(),
+                    received: false,
+                    read: false,
+                    wrote: true,
+                    copied_to: vec![],
+                },
+                NodeLineage {
+                    node_id: "mail".into(),
+                    received: true,
+                    read: true,
+                    wrote: false,
+                    copied_to: vec!["recipients.0".into()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parent_and_child_paths_overlap() {
+        assert!(overlaps("customer", "customer.email"));
+        assert!(overlaps("customer.email", "customer"));
+        assert!(!overlaps("customer.e", "customer.email"));
+    }
+}
@@ -0,0 +1,286 @@
+//! Checks on a workflow definition beyond the shape of its DAG.
+//!
+//! [`lint`] reports two kinds of [`Lint`]:
+//!
+//! * errors — the workflow cannot run as written: a node type the registry
+//!   does not know, a config its node type's schema rejects, a cron
+//!   expression that does not parse, a parameter default of the wrong
+//!   type, or a `{{ params.NAME }}` the workflow does not declare;
+//! * warnings — it runs, but not as its author probably meant: edges
+//!   labelled with a branch their node never picks, nodes that no
+//!   followable path reaches, and nodes connected to nothing.
+//!
+//! Branches are known from the node type's descriptor, or for `split_ab`
+//! from the branches its config names.  Configs are checked with the
+//! parameters' defaults filled in (see [`crate::params`]).
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use nodes::registry::NodeRegistry;
+
+use crate::params;
+use crate::scheduler::CronSchedule;
+use crate::Workflow;
+
+/// How much a [`Lint`] matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// One problem found by [`lint`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Lint {
+    pub severity: Severity,
+    /// The node concerned; `None` for the workflow as a whole.
+    pub node_id: Option<String>,
+    pub message: String,
+}
+
+/// Check `workflow` against the node types in `registry`.  Assumes the
+/// DAG itself is valid (see [`validate_dag`](crate::validate_dag)); errors
+/// come before warnings, each in node order.
+pub fn lint(workflow: &Workflow, registry: &NodeRegistry) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    let mut report = |severity, node_id: Option<&str>, message: String| {
+        lints.push(Lint { severity, node_id: node_id.map(str::to_owned), message })
+    };
+
+    if let Some(Err(e)) = CronSchedule::of(&workflow.trigger) {
+        report(Severity::Error, None, e.to_string());
+    }
+
+    // Defaults of the right type, filled into configs before they are checked.
+    let mut defaults = serde_json::Map::new();
+    for (name, param) in &workflow.params {
+        let Some(default) = &param.default else { continue };
+        if params::matches(param.kind, default) {
+            defaults.insert(name.clone(), default.clone());
+        } else {
+            report(Severity::Error, None, format!("default of parameter '{name}' is not of its type: {default}"));
+        }
+    }
+
+    // Branches each node may pick; `None` when it may pick any.
+    let mut branches: HashMap<&str, Option<HashSet<String>>> = HashMap::new();
+    for node in &workflow.nodes {
+        let Some(executable) = registry.get(&node.node_type) else {
+            report(Severity::Error, Some(&node.id), format!("unknown node type '{}'", node.node_type));
+            branches.insert(&node.id, None);
+            continue;
+        };
+        let mut undeclared = params::references(&node.config);
+        undeclared.retain(|name| !workflow.params.contains_key(name));
+        undeclared.dedup();
+        for name in undeclared {
+            report(Severity::Error, Some(&node.id), format!("undeclared parameter '{name}'"));
+        }
+        let config = params::interpolate(&node.config, &defaults);
+        let descriptor = executable.descriptor();
+        if !descriptor.config_schema.is_null() {
+            match jsonschema::validator_for(&descriptor.config_schema) {
+                Ok(validator) => {
+                    for error in validator.iter_errors(&config) {
+                        let path = error.instance_path.to_string();
+                        let at = if path.is_empty() { String::new() } else { format!(" at {path}") };
+                        report(Severity::Error, Some(&node.id), format!("invalid {} config{at}: {error}", node.node_type));
+                    }
+                }
+                Err(e) => report(
+                    Severity::Warning,
+                    Some(&node.id),
+                    format!("config schema of {} does not compile: {e}", node.node_type),
+                ),
+            }
+        }
+        branches.insert(&node.id, picked_branches(&node.node_type, &node.config, descriptor.branches));
+    }
+
+    for edge in &workflow.edges {
+        let (Some(label), Some(Some(known))) = (&edge.branch, branches.get(edge.from.as_str())) else { continue };
+        if known.contains(label) {
+            continue;
+        }
+        let message = if known.is_empty() {
+            format!("edge to '{}' is labelled '{label}', but '{}' never picks a branch", edge.to, edge.from)
+        } else {
+            let mut known: Vec<_> = known.iter().map(String::as_str).collect();
+            known.sort_unstable();
+            format!("edge to '{}' is labelled '{label}', but '{}' only picks {}", edge.to, edge.from, known.join(", "))
+        };
+        report(Severity::Warning, Some(&edge.from), message);
+    }
+
+    let reachable = reachable(workflow, &branches);
+    for node in &workflow.nodes {
+        if !reachable.contains(node.id.as_str()) {
+            report(Severity::Warning, Some(&node.id), "never reached: no followable edge leads to it".into());
+        }
+    }
+
+    if workflow.nodes.len() > 1 {
+        let connected: HashSet<&str> =
+            workflow.edges.iter().flat_map(|e| [e.from.as_str(), e.to.as_str()]).collect();
+        for node in &workflow.nodes {
+            if !connected.contains(node.id.as_str()) {
+                report(Severity::Warning, Some(&node.id), "not connected to any other node".into());
+            }
+        }
+    }
+
+    lints.sort_by_key(|lint| std::cmp::Reverse(lint.severity));
+    lints
+}
+
+/// Branches a node of `node_type` with `config` may pick, given those its
+/// descriptor lists; `None` when they cannot be known.
+fn picked_branches(node_type: &str, config: &Value, described: Vec<String>) -> Option<HashSet<String>> {
+    if node_type == "split_ab" {
+        let names = config.get("branches")?.as_array()?.iter().filter_map(|b| b.get("name")?.as_str());
+        return Some(names.map(str::to_owned).collect());
+    }
+    Some(described.into_iter().collect())
+}
+
+/// Nodes a run can get to: the roots, then every node with an incoming edge
+/// that is unlabelled or labelled with a branch its source may pick.
+fn reachable<'a>(workflow: &'a Workflow, branches: &HashMap<&str, Option<HashSet<String>>>) -> HashSet<&'a str> {
+    let targets: HashSet<&str> = workflow.edges.iter().map(|e| e.to.as_str()).collect();
+    let mut queue: VecDeque<&str> =
+        workflow.nodes.iter().map(|n| n.id.as_str()).filter(|id| !targets.contains(id)).collect();
+    let mut seen: HashSet<&str> = queue.iter().copied().collect();
+    while let Some(id) = queue.pop_front() {
+        for edge in workflow.edges.iter().filter(|e| e.from == id) {
+            let followable = match (&edge.branch, branches.get(id)) {
+                (Some(label), Some(Some(known))) => known.contains(label),
+                _ => true,
+            };
+            if followable && seen.insert(edge.to.as_str()) {
+                queue.push_back(edge.to.as_str());
+            }
+        }
+    }
+    seen
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nodes::registry::default_registry;
+    use serde_json::json;
+
+    fn workflow(trigger: Value, nodes: Value, edges: Value) -> Workflow {
+        serde_json::from_value(json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": "lint",
+            "trigger": trigger,
+            "nodes": nodes,
+            "edges": edges,
+            "created_at": "2024-01-01T00:00:00Z"
+        }))
+        .unwrap()
+    }
+
+    fn messages(lints: &[Lint], severity: Severity) -> Vec<String> {
+        lints
+            .iter()
+            .filter(|l| l.severity == severity)
+            .map(|l| format!("{}: {}", l.node_id.as_deref().unwrap_or("-"), l.message))
+            .collect()
+    }
+
+    #[test]
+    fn a_sound_workflow_has_no_lints() {
+        let workflow = workflow(
+            json!({ "type": "cron", "expression": "0 9 * * 1-5" }),
+            json!([
+                { "id": "check", "node_type": "validate_json", "config": { "schema": {} } },
+                { "id": "split", "node_type": "split_ab", "config": { "branches": [{ "name": "a", "weight": 1 }, { "name": "b", "weight": 1 }] } },
+                { "id": "a", "node_type": "validate_json", "config": { "schema": {} } }
+            ]),
+            json!([
+                { "from": "check", "to": "split", "branch": "valid" },
+                { "from": "split", "to": "a", "branch": "a" }
+            ]),
+        );
+        assert_eq!(lint(&workflow, &default_registry()), vec![]);
+    }
+
+    #[test]
+    fn unknown_types_bad_configs_and_bad_cron_are_errors() {
+        let workflow = workflow(
+            json!({ "type": "cron", "expression": "every day" }),
+            json!([
+                { "id": "check", "node_type": "validate_json", "config": {} },
+                { "id": "send", "node_type": "slack", "config": {} }
+            ]),
+            json!([{ "from": "check", "to": "send" }]),
+        );
+        let lints = lint(&workflow, &default_registry());
+        let errors = messages(&lints, Severity::Error);
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(errors[0].starts_with("-: ") && errors[0].contains("every day"));
+        assert!(errors[1].starts_with("check: invalid validate_json config"), "{}", errors[1]);
+        assert_eq!(errors[2], "send: unknown node type 'slack'");
+        assert!(messages(&lints, Severity::Warning).is_empty());
+    }
+
+    #[test]
+    fn unpicked_branches_leave_nodes_unreachable() {
+        let workflow = workflow(
+            json!({ "type": "manual" }),
+            json!([
+                { "id": "check", "node_type": "validate_json", "config": { "schema": {} } },
+                { "id": "ok", "node_type": "validate_json", "config": { "schema": {} } },
+                { "id": "next", "node_type": "validate_json", "config": { "schema": {} } },
+                { "id": "stray", "node_type": "validate_json", "config": { "schema": {} } }
+            ]),
+            json!([
+                { "from": "check", "to": "ok", "branch": "passed" },
+                { "from": "ok", "to": "next" }
+            ]),
+        );
+        let lints = lint(&workflow, &default_registry());
+        assert!(messages(&lints, Severity::Error).is_empty());
+        assert_eq!(
+            messages(&lints, Severity::Warning),
+            vec![
+                "check: edge to 'ok' is labelled 'passed', but 'check' only picks invalid, valid",
+                "ok: never reached: no followable edge leads to it",
+                "next: never reached: no followable edge leads to it",
+                "stray: not connected to any other node",
+            ]
+        );
+    }
+
+    #[test]
+    fn configs_are_checked_with_parameter_defaults() {
+        let mut workflow = workflow(
+            json!({ "type": "manual" }),
+            json!([
+                { "id": "top", "node_type": "sort_limit", "config": { "field": "amount", "limit": "{{ params.limit }}" } },
+                { "id": "check", "node_type": "validate_json", "config": { "schema": { "title": "{{ params.colour }}" } } }
+            ]),
+            json!([{ "from": "top", "to": "check" }]),
+        );
+        workflow.params = serde_json::from_value(json!({ "limit": { "type": "integer", "default": 10 } })).unwrap();
+        assert_eq!(messages(&lint(&workflow, &default_registry()), Severity::Error), vec!["check: undeclared parameter 'colour'"]);
+
+        workflow.params = serde_json::from_value(json!({
+            "limit": { "type": "integer", "default": "ten" },
+            "colour": { "type": "string" }
+        }))
+        .unwrap();
+        let errors = messages(&lint(&workflow, &default_registry()), Severity::Error);
+        assert_eq!(errors[0], "-: default of parameter 'limit' is not of its type: \"ten\"");
+        assert!(errors[1].starts_with("top: invalid sort_limit config at /limit"), "{errors:?}");
+    }
+}
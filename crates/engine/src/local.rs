@@ -0,0 +1,232 @@
+//! Local execution — run a workflow in-process, without Postgres.
+//!
+//! A [`LocalRunner`] routes inputs along edges, follows branches, and
+//! retries nodes exactly like [`WorkflowExecutor`](crate::WorkflowExecutor),
+//! but keeps everything in memory: workflow state lives in an
+//! [`InMemoryStateStore`] and nothing is recorded.  Nodes that need more
+//! than that (sub-workflows, secrets, binary data) fail, and a node
+//! deferring the run ends it there, so this is for iterating on a workflow
+//! rather than running it in production.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use nodes::state::InMemoryStateStore;
+use nodes::traits::{ExecutionContext, Flow, LogEntry};
+
+use crate::dag::validate_dag;
+use crate::executor::{ExecutorConfig, FlowState, NodeRegistry, NodeRunner, Routing};
+use crate::{params, secrets};
+use crate::{EngineError, Workflow};
+
+/// How a node fared in a local run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeStatus {
+    Succeeded,
+    Failed,
+    /// No upstream node passed it anything.
+    Skipped,
+}
+
+impl NodeStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// One node of a local run.
+#[derive(Debug, Clone)]
+pub struct NodeReport {
+    pub node_id: String,
+    pub node_type: String,
+    pub status: NodeStatus,
+    pub duration: Duration,
+    /// Branch the node picked, if it branched.
+    pub branch: Option<String>,
+    /// Whether the node halted the flow.
+    pub halted: bool,
+    pub error: Option<String>,
+    /// Lines the node logged.
+    pub logs: Vec<LogEntry>,
+}
+
+/// The outcome of a local run.
+#[derive(Debug, Clone)]
+pub struct LocalRun {
+    pub execution_id: Uuid,
+    /// Every node, in the order it was reached.
+    pub nodes: Vec<NodeReport>,
+    /// Output of the last node that ran.
+    pub output: Value,
+    /// Error of the node that failed the run.
+    pub error: Option<String>,
+    /// Set when a node deferred the run, which ended it there.
+    pub deferred_until: Option<DateTime<Utc>>,
+}
+
+impl LocalRun {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Runs workflows in-process with the nodes of a registry.
+pub struct LocalRunner {
+    registry: NodeRegistry,
+    runner: NodeRunner,
+}
+
+impl LocalRunner {
+    pub fn new(registry: NodeRegistry, config: ExecutorConfig) -> Self {
+        Self { registry, runner: NodeRunner::new(config) }
+    }
+
+    /// Run `workflow` on `input`, with its parameters' defaults, calling
+    /// `on_node` as each node finishes.
+    ///
+    /// # Errors
+    /// An invalid DAG, a node type missing from the registry, or a
+    /// parameter without a default; node
+    /// failures end the run and are reported in [`LocalRun::error`].
+    pub async fn run(
+        &self,
+        workflow: &Workflow,
+        input: Value,
+        mut on_node: impl FnMut(&NodeReport),
+    ) -> Result<LocalRun, EngineError> {
+        let sorted_ids = validate_dag(workflow)?;
+        for node in &workflow.nodes {
+            if !self.registry.contains_key(&node.node_type) {
+                return Err(EngineError::NodeFatal {
+                    node_id: node.id.clone(),
+                    message: format!("no implementation registered for node_type '{}'", node.node_type),
+                });
+            }
+        }
+
+        let params = params::resolve(&workflow.params, &Map::new()).map_err(EngineError::InvalidParams)?;
+        let execution_id = Uuid::new_v4();
+        let mut state = FlowState::new(input);
+        let ctx = ExecutionContext::new(workflow.id, execution_id, state.input.clone())
+            .with_state(Arc::new(InMemoryStateStore::default()));
+        let routing = Routing::new(workflow, &sorted_ids);
+        let mut run = LocalRun { execution_id, nodes: Vec::new(), output: Value::Null, error: None, deferred_until: None };
+
+        for node_def in sorted_ids.iter().filter_map(|id| workflow.nodes.iter().find(|n| &n.id == id)) {
+            let mut report = NodeReport {
+                node_id: node_def.id.clone(),
+                node_type: node_def.node_type.clone(),
+                status: NodeStatus::Skipped,
+                duration: Duration::ZERO,
+                branch: None,
+                halted: false,
+                error: None,
+                logs: Vec::new(),
+            };
+            let Some(current_input) = routing.input(&node_def.id, &state) else {
+                on_node(&report);
+                run.nodes.push(report);
+                continue;
+            };
+
+            let (config, missing_secret) = match secrets::interpolate(&node_def.config, &ctx.secrets) {
+                Ok(config) => (params::interpolate(&config, &params), None),
+                Err(key) => (node_def.config.clone(), Some(key)),
+            };
+            let node_ctx = ctx.for_node(node_def.id.as_str(), config);
+            let timer = Instant::now();
+            let output = match missing_secret {
+                Some(key) => Err(EngineError::NodeFatal {
+                    node_id: node_def.id.clone(),
+                    message: format!("missing secret '{key}' for node '{}'", node_def.id),
+                }),
+                None => self.runner.run(&node_def.id, &self.registry[&node_def.node_type], &current_input, &node_ctx).await,
+            };
+            report.duration = timer.elapsed();
+            report.logs = node_ctx.take_logs();
+
+            match output {
+                Ok(output) => {
+                    report.status = NodeStatus::Succeeded;
+                    let output = Arc::new(output);
+                    match node_ctx.take_flow() {
+                        Flow::Continue => {
+                            state.outputs.insert(node_def.id.clone(), output.clone());
+                        }
+                        Flow::Branch(branch) => {
+                            state.outputs.insert(node_def.id.clone(), output.clone());
+                            state.branches.insert(node_def.id.clone(), branch.clone());
+                            report.branch = Some(branch);
+                        }
+                        Flow::Halt => report.halted = true,
+                        Flow::Defer(until) => run.deferred_until = Some(until),
+                    }
+                    state.last_output = output;
+                }
+                Err(e) => {
+                    report.status = NodeStatus::Failed;
+                    report.error = Some(e.to_string());
+                    run.error = Some(e.to_string());
+                }
+            }
+            on_node(&report);
+            run.nodes.push(report);
+            if run.error.is_some() || run.deferred_until.is_some() {
+                break;
+            }
+        }
+
+        run.output = Value::clone(&state.last_output);
+        Ok(run)
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nodes::mock::MockNode;
+    use nodes::registry::RegistryBuilder;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn runs_nodes_in_order_without_a_database() {
+        let registry = RegistryBuilder::new()
+            .register("double", MockNode::returning("double", json!({ "n": 2 })))
+            .register("broken", MockNode::failing_fatal("broken", "no"))
+            .build();
+        let workflow: Workflow = serde_json::from_value(json!({
+            "id": Uuid::nil(),
+            "name": "local",
+            "trigger": { "type": "manual" },
+            "nodes": [
+                { "id": "b", "node_type": "double", "config": {} },
+                { "id": "a", "node_type": "double", "config": {} },
+                { "id": "c", "node_type": "broken", "config": {} }
+            ],
+            "edges": [{ "from": "a", "to": "b" }, { "from": "b", "to": "c" }],
+            "created_at": "2024-01-01T00:00:00Z"
+        }))
+        .unwrap();
+
+        let mut seen = Vec::new();
+        let run = LocalRunner::new(registry, ExecutorConfig::default())
+            .run(&workflow, json!({}), |report| seen.push((report.node_id.clone(), report.status)))
+            .await
+            .unwrap();
+        let statuses = [NodeStatus::Succeeded, NodeStatus::Succeeded, NodeStatus::Failed];
+        assert_eq!(seen, ["a", "b", "c"].map(String::from).into_iter().zip(statuses).collect::<Vec<_>>());
+        assert_eq!(run.output["n"], 2);
+        assert!(!run.succeeded() && run.error.unwrap().contains("no"));
+    }
+}
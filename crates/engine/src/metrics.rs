@@ -0,0 +1,55 @@
+//! Metrics the engine records: executions started and how they ended,
+//! node durations, slow node runs, and the jobs workers settle.
+//!
+//! They go through the `metrics` facade, so they are only kept once the
+//! process installs a recorder (`api::metrics` exports them to
+//! Prometheus); otherwise recording them costs next to nothing.
+
+use std::time::Duration;
+
+use ::metrics::{counter, describe_counter, describe_histogram, histogram, Unit};
+
+/// Executions that started running from their first node.
+pub const EXECUTIONS_STARTED: &str = "rusty_automation_executions_started_total";
+/// Executions that ran to the end.
+pub const EXECUTIONS_SUCCEEDED: &str = "rusty_automation_executions_succeeded_total";
+/// Executions that a node failed.
+pub const EXECUTIONS_FAILED: &str = "rusty_automation_executions_failed_total";
+/// Time nodes took, retries included, by `node_type` and `outcome`.
+pub const NODE_DURATION: &str = "rusty_automation_node_duration_seconds";
+/// Jobs workers settled, by `outcome` (`succeeded`, `deferred`, `failed`).
+pub const WORKER_JOBS: &str = "rusty_automation_worker_jobs_total";
+/// Node runs far slower than their baseline, by `node_type` (see
+/// [`crate::slow_nodes`]).
+pub const SLOW_NODES: &str = "rusty_automation_slow_nodes_total";
+
+/// Describe the engine's metrics to the installed recorder.
+pub fn describe() {
+    describe_counter!(EXECUTIONS_STARTED, "Executions that started running");
+    describe_counter!(EXECUTIONS_SUCCEEDED, "Executions that succeeded");
+    describe_counter!(EXECUTIONS_FAILED, "Executions that failed");
+    describe_histogram!(NODE_DURATION, Unit::Seconds, "Time nodes took to run, retries included");
+    describe_counter!(WORKER_JOBS, "Jobs settled by workers");
+    describe_counter!(SLOW_NODES, "Node runs far slower than their baseline");
+}
+
+pub(crate) fn execution_started() {
+    counter!(EXECUTIONS_STARTED).increment(1);
+}
+
+pub(crate) fn execution_finished(succeeded: bool) {
+    counter!(if succeeded { EXECUTIONS_SUCCEEDED } else { EXECUTIONS_FAILED }).increment(1);
+}
+
+pub(crate) fn node_finished(node_type: &str, succeeded: bool, took: Duration) {
+    let outcome = if succeeded { "succeeded" } else { "failed" };
+    histogram!(NODE_DURATION, "node_type" => node_type.to_owned(), "outcome" => outcome).record(took.as_secs_f64());
+}
+
+pub(crate) fn slow_node(node_type: &str) {
+    counter!(SLOW_NODES, "node_type" => node_type.to_owned()).increment(1);
+}
+
+pub(crate) fn job_settled(outcome: &'static str) {
+    counter!(WORKER_JOBS, "outcome" => outcome).increment(1);
+}
@@ -2,10 +2,16 @@
 //!
 //! These types are the source of truth for what a workflow looks like
 //! in memory.  They can be serialised to/from the JSONB `definition`
-//! column of the `workflows` table.
+//! column of the `workflows` table, and [`Workflow::json_schema`]
+//! describes that JSON for editors and linters outside this codebase.
+
+use std::collections::BTreeMap;
 
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
 use uuid::Uuid;
 
 // ---------------------------------------------------------------------------
@@ -13,29 +19,341 @@ use uuid::Uuid;
 // ---------------------------------------------------------------------------
 
 /// How a workflow is started.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Trigger {
     /// Triggered by an incoming HTTP request to `/webhook/{path}`.
     Webhook {
         /// URL path segment that identifies this workflow.
         path: String,
+        /// Reject requests beyond a per-minute execution budget.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        throttle: Option<Throttle>,
+        /// Collapse bursts of requests into a single delayed run.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        debounce: Option<Debounce>,
+        /// Answer requests with the execution's final output.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sync: Option<SyncResponse>,
     },
     /// Triggered manually via the REST API.
-    Manual,
-    /// Triggered on a cron schedule.
+    Manual {
+        /// JSON Schema the run's input must match; also describes the
+        /// input form for people starting the workflow by hand.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        input_schema: Option<Value>,
+    },
+    /// Triggered on a cron schedule (see [`crate::scheduler`]).
     Cron {
-        /// Standard cron expression (5 fields).
+        /// Cron expression: the standard 5 fields, or 6 with leading
+        /// seconds.
         expression: String,
+        /// IANA timezone the expression is evaluated in (default UTC).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timezone: Option<String>,
+    },
+    /// Triggered for each new item a polled source reports (see
+    /// [`crate::polling`]).
+    Poll {
+        /// Where the items come from.
+        source: PollSource,
+        /// Seconds between polls (default 60).
+        #[serde(default = "default_poll_interval_secs")]
+        interval_secs: u64,
+        /// Dotted path to the item array in the response; the response
+        /// itself when unset.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        items: Option<String>,
+        /// Dotted path, within an item, to the increasing id or timestamp
+        /// that tells new items from seen ones (default `id`).
+        #[serde(default = "default_poll_cursor")]
+        cursor: String,
+    },
+    /// Triggered by each message on a RabbitMQ queue (consumed by
+    /// `engine::amqp`, behind the `amqp` feature).
+    Amqp {
+        /// Environment variable holding the broker URL (default
+        /// `AMQP_URL`); the URL carries credentials, so it is not part of
+        /// the definition.
+        #[serde(default = "default_amqp_url_env")]
+        url_env: String,
+        /// Queue to consume from; it must already exist.
+        queue: String,
+        /// Unacknowledged messages the broker hands out at once (default 10).
+        #[serde(default = "default_amqp_prefetch")]
+        prefetch: u16,
+    },
+    /// Triggered by each `NOTIFY` on a Postgres channel (see
+    /// [`crate::pg_notify`]).
+    PgNotify {
+        /// Channel to `LISTEN` on (case-sensitive).
+        channel: String,
+        /// Environment variable holding the URL of the database to listen
+        /// on; the engine's own database when unset.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url_env: Option<String>,
+    },
+}
+
+impl Trigger {
+    /// Throttling configured on this trigger, if any.
+    pub fn throttle(&self) -> Option<&Throttle> {
+        match self {
+            Self::Webhook { throttle, .. } => throttle.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Debouncing configured on this trigger, if any.
+    pub fn debounce(&self) -> Option<&Debounce> {
+        match self {
+            Self::Webhook { debounce, .. } => debounce.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Schema that manually supplied input must match, if any.
+    pub fn input_schema(&self) -> Option<&Value> {
+        match self {
+            Self::Manual { input_schema } => input_schema.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Synchronous responses configured on this trigger, if any.
+    pub fn sync_response(&self) -> Option<&SyncResponse> {
+        match self {
+            Self::Webhook { sync, .. } => sync.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// What a [`Trigger::Poll`] calls on each poll.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PollSource {
+    /// `GET` an endpoint answering with JSON.
+    Http {
+        url: String,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+    },
+    /// Run a node and use its output.
+    Node {
+        node_type: String,
+        #[serde(default)]
+        config: Value,
     },
 }
 
+fn default_poll_interval_secs() -> u64 {
+    60
+}
+
+fn default_poll_cursor() -> String {
+    "id".into()
+}
+
+fn default_amqp_url_env() -> String {
+    "AMQP_URL".into()
+}
+
+fn default_amqp_prefetch() -> u16 {
+    10
+}
+
+/// Caps how many executions a trigger may start.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Throttle {
+    /// Maximum executions started in any rolling 60-second window.
+    pub max_per_minute: u32,
+}
+
+/// Makes a webhook request wait for the execution it starts.
+///
+/// The request is answered with the final node's output once the execution
+/// succeeds; if it has not finished within `timeout_secs` the caller gets
+/// the usual `202 Accepted` and the execution carries on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SyncResponse {
+    /// Default 30 seconds, at most 5 minutes.
+    #[serde(default = "default_sync_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_sync_timeout_secs() -> u64 {
+    30
+}
+
+impl SyncResponse {
+    /// How long a request may wait.
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.timeout_secs.min(300))
+    }
+}
+
+/// Collapses a burst of trigger events into one execution.
+///
+/// The first event schedules a run `window_secs` in the future; every
+/// further event before that run starts updates its payload and pushes it
+/// back by another `window_secs`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Debounce {
+    /// At most [`Debounce::MAX_WINDOW_SECS`].
+    pub window_secs: u64,
+    /// How a later event's payload combines with the pending one.
+    #[serde(default)]
+    pub payload: DebouncePayload,
+}
+
+impl Debounce {
+    /// The longest window: a day.
+    pub const MAX_WINDOW_SECS: u64 = 86_400;
+}
+
+/// Payload handling for debounced triggers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DebouncePayload {
+    /// The run receives the most recent event's payload.
+    #[default]
+    Latest,
+    /// Top-level keys of each event are merged into the pending payload;
+    /// for webhook requests, the keys of the request bodies.
+    Merge,
+}
+
+// ---------------------------------------------------------------------------
+// RetentionPolicy
+// ---------------------------------------------------------------------------
+
+/// How long, or how many, finished executions are kept before the pruner
+/// deletes them.
+///
+/// Unset limits keep executions forever.  A workflow's policy falls back to
+/// the process-wide default field by field.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RetentionPolicy {
+    /// Days to keep finished executions whose status has no limit below.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u32>,
+    /// Days to keep succeeded executions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub succeeded_days: Option<u32>,
+    /// Days to keep failed executions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_days: Option<u32>,
+    /// Most finished executions to keep, the most recently started; older
+    /// ones are pruned whatever their age.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_executions: Option<u32>,
+    /// Executions carrying any of these labels are never pruned.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keep_labels: Vec<String>,
+}
+
+// ---------------------------------------------------------------------------
+// RetryBackoff
+// ---------------------------------------------------------------------------
+
+/// How long a failed job waits before its next attempt: `base_secs`,
+/// doubled for every further attempt up to `max_secs`, then shortened by a
+/// random fraction of up to `jitter` so retries of jobs that failed
+/// together spread out.
+///
+/// A workflow's policy falls back to the worker's default field by field
+/// (see [`crate::backoff`]).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RetryBackoff {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_secs: Option<u64>,
+    /// Between 0 (exact delays) and 1 (anywhere from no wait up to the
+    /// delay).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter: Option<f64>,
+}
+
+// ---------------------------------------------------------------------------
+// Quota
+// ---------------------------------------------------------------------------
+
+/// Limits on how much a workflow, or all of a project's workflows, may run;
+/// new executions are refused once one is reached (see [`crate::quotas`]).
+///
+/// Hours are the last 60 minutes; days start at midnight UTC.  Unset limits
+/// do not apply.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Quota {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_executions_per_hour: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_executions_per_day: Option<u32>,
+    /// Seconds nodes may spend running per day, summed over all runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_node_runtime_secs_per_day: Option<u64>,
+}
+
+// ---------------------------------------------------------------------------
+// InheritancePolicy
+// ---------------------------------------------------------------------------
+
+/// What executions started by a run of this workflow (sub-workflows,
+/// chained workflows) take over from it, so urgent work stays urgent past
+/// the first hop.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct InheritancePolicy {
+    #[serde(default)]
+    pub priority: PriorityInheritance,
+    #[serde(default)]
+    pub queue: QueueInheritance,
+    /// Add the parent's labels to the child's own.
+    #[serde(default = "default_true")]
+    pub labels: bool,
+}
+
+impl Default for InheritancePolicy {
+    fn default() -> Self {
+        Self { priority: PriorityInheritance::default(), queue: QueueInheritance::default(), labels: true }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// How a child execution's priority is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriorityInheritance {
+    /// The higher of the parent's and the child workflow's own priority.
+    #[default]
+    Max,
+    /// Always the parent's priority.
+    Parent,
+    /// The child workflow's own priority; nothing is inherited.
+    Child,
+}
+
+/// Which queue a child execution's jobs go to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueInheritance {
+    /// The parent's queue.
+    #[default]
+    Parent,
+    /// The child workflow's own queue.
+    Child,
+}
+
 // ---------------------------------------------------------------------------
 // NodeDefinition
 // ---------------------------------------------------------------------------
 
 /// A single step in the workflow graph.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct NodeDefinition {
     /// Unique identifier within this workflow (referenced by edges).
     pub id: String,
@@ -43,6 +361,10 @@ pub struct NodeDefinition {
     pub node_type: String,
     /// Arbitrary configuration passed to the node at execution time.
     pub config: serde_json::Value,
+    /// Capabilities a worker needs to run this node (e.g. `gpu` for a
+    /// local model); the whole execution waits for such a worker.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub worker_tags: Vec<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -50,10 +372,49 @@ pub struct NodeDefinition {
 // ---------------------------------------------------------------------------
 
 /// Directed edge from one node to another.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Edge {
     pub from: String,
     pub to: String,
+    /// Only follow this edge when `from` picks this branch (see
+    /// `ExecutionContext::branch`).  Unlabelled edges are always followed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+}
+
+impl Edge {
+    /// An unlabelled edge.
+    pub fn new(from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self { from: from.into(), to: to.into(), branch: None }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Params
+// ---------------------------------------------------------------------------
+
+/// The JSON type a workflow parameter's values must have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Object,
+    Array,
+}
+
+/// A parameter a workflow declares.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Param {
+    #[serde(rename = "type")]
+    pub kind: ParamType,
+    /// Value when a run does not give one; runs must give one without it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -61,7 +422,7 @@ pub struct Edge {
 // ---------------------------------------------------------------------------
 
 /// A complete workflow definition.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Workflow {
     pub id: Uuid,
     pub name: String,
@@ -69,9 +430,75 @@ pub struct Workflow {
     pub nodes: Vec<NodeDefinition>,
     pub edges: Vec<Edge>,
     pub created_at: DateTime<Utc>,
+    /// Template (e.g. `{{ input.order_id }}`) rendered against
+    /// `{ "input": <trigger input> }` to tag each execution with the
+    /// business entity it dealt with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub business_key: Option<String>,
+    /// Label templates rendered like `business_key`; every non-empty result
+    /// is recorded on the execution (e.g. `"audit"`, `"tier-{{ input.tier }}"`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// Execution retention for this workflow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<RetentionPolicy>,
+    /// Limits on this workflow's executions, on top of its project's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota: Option<Quota>,
+    /// Jobs of higher-priority executions are picked up first.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub priority: i32,
+    /// Queue for this workflow's jobs; `default` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue: Option<String>,
+    /// Attempts each job of this workflow gets before it is
+    /// dead-lettered; 3 when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    /// Delays between attempts of this workflow's failed jobs; the
+    /// worker's default policy when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_backoff: Option<RetryBackoff>,
+    /// Capabilities a worker needs to run this workflow, on top of those
+    /// of its nodes (see [`Workflow::required_tags`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub worker_tags: Vec<String>,
+    /// What executions started by this workflow's runs inherit; the
+    /// executor's default policy when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inheritance: Option<InheritancePolicy>,
+    /// Workflow to run (starting with an `error_trigger` node) whenever an
+    /// execution of this one fails; see [`crate::error_workflow`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_workflow_id: Option<Uuid>,
+    /// Also start the error workflow when a node runs far slower than its
+    /// baseline; see [`crate::slow_nodes`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub slow_node_alerts: bool,
+    /// Named values node configs read as `{{ params.NAME }}`; runs can
+    /// override their defaults (see [`crate::params`]).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, Param>,
+}
+
+/// `$id` of [`Workflow::json_schema`].
+pub const SCHEMA_ID: &str = "https://github.com/satwikambashta/rusty-automation-tool/schemas/workflow.json";
+
+fn is_zero(n: &i32) -> bool {
+    *n == 0
 }
 
 impl Workflow {
+    /// JSON Schema (draft 2020-12) of workflow definitions, served at
+    /// `GET /api/v1/workflows/schema` and printed by `validate --schema`.
+    /// Node configs are left open: their schemas are per node type (see
+    /// `GET /api/v1/node-types`).
+    pub fn json_schema() -> Value {
+        let mut schema = schemars::schema_for!(Workflow).to_value();
+        schema["$id"] = Value::String(SCHEMA_ID.into());
+        schema
+    }
+
     /// Convenience constructor for testing.
     pub fn new(
         name: impl Into<String>,
@@ -86,6 +513,108 @@ impl Workflow {
             nodes,
             edges,
             created_at: Utc::now(),
+            business_key: None,
+            labels: Vec::new(),
+            retention: None,
+            quota: None,
+            priority: 0,
+            queue: None,
+            max_attempts: None,
+            retry_backoff: None,
+            worker_tags: Vec::new(),
+            inheritance: None,
+            error_workflow_id: None,
+            slow_node_alerts: false,
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// Capability tags a worker needs to run this workflow: its own
+    /// `worker_tags` and those of every node, sorted and deduplicated.
+    pub fn required_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .worker_tags
+            .iter()
+            .chain(self.nodes.iter().flat_map(|n| &n.worker_tags))
+            .cloned()
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// Render the business key and labels for a run started with `input`,
+    /// with the workflow's priority, queue, required tags, and attempt
+    /// limit.
+    ///
+    /// Templates that render empty or fail (e.g. the input lacks the field)
+    /// are left out — missing metadata never blocks a run.
+    pub fn execution_meta(&self, input: &Value) -> db::models::ExecutionMeta {
+        let root = serde_json::json!({ "input": input });
+        let render = |what: &str, template: &str| {
+            match nodes::template::render(template, &root) {
+                Ok(value) if !value.is_empty() => Some(value),
+                Ok(_) => None,
+                Err(e) => {
+                    warn!("workflow {}: cannot render {}: {}", self.id, what, e);
+                    None
+                }
+            }
+        };
+
+        let mut labels: Vec<String> = Vec::new();
+        for label in self.labels.iter().filter_map(|t| render("label", t)) {
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+        db::models::ExecutionMeta {
+            business_key: self.business_key.as_deref().and_then(|t| render("business key", t)),
+            labels,
+            priority: self.priority,
+            queue: self.queue.clone(),
+            parent_execution_id: None,
+            required_tags: self.required_tags(),
+            max_attempts: self.max_attempts.map(|n| i32::try_from(n.max(1)).unwrap_or(i32::MAX)),
+            retry_of: None,
+            params: serde_json::Map::new(),
+        }
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn the_json_schema_accepts_what_deserializes() {
+        let validator = jsonschema::validator_for(&Workflow::json_schema()).expect("a valid schema");
+        let definition = json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": "orders",
+            "trigger": { "type": "webhook", "path": "orders", "debounce": { "window_secs": 5, "payload": "merge" } },
+            "params": { "limit": { "type": "integer", "default": 10 } },
+            "nodes": [{ "id": "top", "node_type": "sort_limit", "config": { "limit": "{{ params.limit }}" } }],
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z",
+            "retry_backoff": { "base_secs": 5, "jitter": 0.5 }
+        });
+        assert!(serde_json::from_value::<Workflow>(definition.clone()).is_ok());
+        assert!(validator.is_valid(&definition));
+
+        for (field, value) in [
+            ("trigger", json!({ "type": "carrier_pigeon" })),
+            ("nodes", json!([{ "id": "top" }])),
+            ("priority", json!("high")),
+        ] {
+            let mut invalid = definition.clone();
+            invalid[field] = value;
+            assert!(serde_json::from_value::<Workflow>(invalid.clone()).is_err(), "{field}");
+            assert!(!validator.is_valid(&invalid), "{field}");
         }
     }
 }
@@ -0,0 +1,406 @@
+//! OpenTelemetry traces of executions, exported over OTLP/HTTP as JSON to
+//! a collector (or straight to Jaeger or Tempo, which accept it).
+//!
+//! Each run of an execution is one `execution` span, with a child span per
+//! node attempt annotated with the node's type, its status, and how many
+//! retries came before it.  An execution started by a webhook request
+//! carrying a W3C `traceparent` header continues that trace, so it shows
+//! up under the service that called it.  Otherwise the trace id is the
+//! execution id, so the runs of a deferred execution share one trace and
+//! it is found by the id the API shows.
+//!
+//! Spans are handed to a background task that sends them in batches;
+//! when the collector cannot keep up or is unreachable they are dropped,
+//! never holding up executions.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Spans waiting to be sent before new ones are dropped.
+const QUEUE_CAPACITY: usize = 4096;
+/// Spans sent per request.
+const MAX_BATCH: usize = 512;
+/// How often spans are sent when fewer than a batch are waiting.
+const EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+/// How long a request to the collector may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `SPAN_KIND_INTERNAL`: a node attempt.
+const KIND_INTERNAL: u8 = 1;
+/// `SPAN_KIND_CONSUMER`: an execution, run off the job queue.
+const KIND_CONSUMER: u8 = 5;
+
+/// Where a span sits in a trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpanContext {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+}
+
+impl SpanContext {
+    /// The context of a W3C `traceparent` header
+    /// (`00-<trace id>-<parent id>-<flags>`); `None` unless well-formed.
+    pub fn from_traceparent(header: &str) -> Option<Self> {
+        let mut parts = header.trim().split('-');
+        let (version, trace_id, span_id, flags) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        if version.len() != 2 || version == "ff" || flags.len() != 2 || (version == "00" && parts.next().is_some()) {
+            return None;
+        }
+        let context = Self { trace_id: unhex(trace_id)?, span_id: unhex(span_id)? };
+        (context.trace_id != [0; 16] && context.span_id != [0; 8]).then_some(context)
+    }
+
+    /// The context a webhook request passed in `input`, the execution's
+    /// trigger input, as its `traceparent` header.
+    pub fn from_trigger_input(input: &Value) -> Option<Self> {
+        input["headers"]["traceparent"].as_str().and_then(Self::from_traceparent)
+    }
+
+    /// The context as a `traceparent` header, sampled.
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-01", hex(&self.trace_id), hex(&self.span_id))
+    }
+}
+
+/// A finished span.
+#[derive(Debug, Clone)]
+pub struct Span {
+    pub context: SpanContext,
+    pub parent_span_id: Option<[u8; 8]>,
+    pub name: String,
+    kind: u8,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub attributes: Vec<(&'static str, Value)>,
+    /// Why the work failed; `None` when it did not.
+    pub error: Option<String>,
+}
+
+impl Span {
+    /// The span in OTLP's JSON encoding.
+    fn to_otlp(&self) -> Value {
+        let attributes: Vec<Value> = self
+            .attributes
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    Value::Bool(b) => json!({ "boolValue": b }),
+                    Value::Number(n) if n.is_i64() || n.is_u64() => json!({ "intValue": n.to_string() }),
+                    Value::Number(n) => json!({ "doubleValue": n.as_f64() }),
+                    Value::String(s) => json!({ "stringValue": s }),
+                    other => json!({ "stringValue": other.to_string() }),
+                };
+                json!({ "key": key, "value": value })
+            })
+            .collect();
+        let status = match &self.error {
+            None => json!({ "code": 1 }),
+            Some(message) => json!({ "code": 2, "message": message }),
+        };
+        let mut span = json!({
+            "traceId": hex(&self.context.trace_id),
+            "spanId": hex(&self.context.span_id),
+            "name": self.name,
+            "kind": self.kind,
+            "startTimeUnixNano": unix_nanos(self.start),
+            "endTimeUnixNano": unix_nanos(self.end),
+            "attributes": attributes,
+            "status": status,
+        });
+        if let Some(parent) = &self.parent_span_id {
+            span["parentSpanId"] = json!(hex(parent));
+        }
+        span
+    }
+}
+
+/// How one attempt at running a node went.
+#[derive(Debug, Clone)]
+pub struct Attempt {
+    /// 1 for the first attempt.
+    pub number: u32,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Why it failed; `None` when it succeeded.
+    pub error: Option<String>,
+    /// Whether the node is tried again after this failure.
+    pub retrying: bool,
+}
+
+/// The span of one run of an execution, while it runs.
+#[derive(Debug, Clone)]
+pub struct ExecutionSpan {
+    tracer: Tracer,
+    context: SpanContext,
+    parent_span_id: Option<[u8; 8]>,
+    start: DateTime<Utc>,
+    attributes: Vec<(&'static str, Value)>,
+}
+
+impl ExecutionSpan {
+    /// A node attempt of this run finished.
+    pub fn attempt(&self, node_id: &str, node_type: &str, attempt: &Attempt) {
+        let status = match (&attempt.error, attempt.retrying) {
+            (None, _) => "succeeded",
+            (Some(_), true) => "retrying",
+            (Some(_), false) => "failed",
+        };
+        self.tracer.record(Span {
+            context: SpanContext { trace_id: self.context.trace_id, span_id: span_id() },
+            parent_span_id: Some(self.context.span_id),
+            name: format!("node {node_id}"),
+            kind: KIND_INTERNAL,
+            start: attempt.started_at,
+            end: attempt.finished_at,
+            attributes: vec![
+                ("node.id", json!(node_id)),
+                ("node.type", json!(node_type)),
+                ("node.status", json!(status)),
+                ("node.attempt", json!(attempt.number)),
+                ("node.retry_count", json!(attempt.number - 1)),
+            ],
+            error: attempt.error.clone(),
+        });
+    }
+
+    /// The run ended with `status` (`succeeded`, `failed`, `waiting`), or
+    /// with `error`.
+    pub fn finish(self, status: &str, error: Option<String>) {
+        let mut attributes = self.attributes;
+        attributes.push(("execution.status", json!(status)));
+        self.tracer.record(Span {
+            context: self.context,
+            parent_span_id: self.parent_span_id,
+            name: "execution".into(),
+            kind: KIND_CONSUMER,
+            start: self.start,
+            end: Utc::now(),
+            attributes,
+            error,
+        });
+    }
+}
+
+enum Message {
+    Span(Box<Span>),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Sends spans to an OTLP collector.  Cheap to clone; clones share the
+/// background task sending them.
+#[derive(Debug, Clone)]
+pub struct Tracer {
+    sender: mpsc::Sender<Message>,
+}
+
+impl std::fmt::Debug for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Message::Span(span) => f.debug_tuple("Span").field(&span.name).finish(),
+            Message::Flush(_) => f.write_str("Flush"),
+        }
+    }
+}
+
+impl Tracer {
+    /// Send spans to the OTLP/HTTP collector at `endpoint`
+    /// (`http://collector:4318`), as service `service_name`.  Must be
+    /// called within a Tokio runtime.
+    pub fn otlp(endpoint: &str, service_name: &str) -> Self {
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        let url = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+        let resource = json!({
+            "attributes": [{ "key": "service.name", "value": { "stringValue": service_name } }],
+        });
+        tokio::spawn(export(url, resource, receiver));
+        Self { sender }
+    }
+
+    /// Start the span of a run of execution `execution_id` of workflow
+    /// `workflow_id`, continuing the trace of `parent` if any.
+    pub fn start_execution(
+        &self,
+        workflow_id: Uuid,
+        workflow_name: &str,
+        execution_id: Uuid,
+        parent: Option<SpanContext>,
+    ) -> ExecutionSpan {
+        let trace_id = parent.map_or(*execution_id.as_bytes(), |parent| parent.trace_id);
+        ExecutionSpan {
+            tracer: self.clone(),
+            context: SpanContext { trace_id, span_id: span_id() },
+            parent_span_id: parent.map(|parent| parent.span_id),
+            start: Utc::now(),
+            attributes: vec![
+                ("workflow.id", json!(workflow_id.to_string())),
+                ("workflow.name", json!(workflow_name)),
+                ("execution.id", json!(execution_id.to_string())),
+            ],
+        }
+    }
+
+    fn record(&self, span: Span) {
+        if self.sender.try_send(Message::Span(Box::new(span))).is_err() {
+            debug!("span dropped: the OTLP export queue is full");
+        }
+    }
+
+    /// Send the spans recorded so far; for shutting down.
+    pub async fn flush(&self) {
+        let (done, sent) = oneshot::channel();
+        if self.sender.send(Message::Flush(done)).await.is_ok() {
+            let _ = sent.await;
+        }
+    }
+}
+
+async fn export(url: String, resource: Value, mut receiver: mpsc::Receiver<Message>) {
+    let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().expect("a default HTTP client builds");
+    let send = |spans: Vec<Span>| {
+        let (client, url) = (client.clone(), url.clone());
+        let body = json!({
+            "resourceSpans": [{
+                "resource": resource,
+                "scopeSpans": [{
+                    "scope": { "name": "rusty-automation", "version": env!("CARGO_PKG_VERSION") },
+                    "spans": spans.iter().map(Span::to_otlp).collect::<Vec<_>>(),
+                }],
+            }],
+        });
+        async move {
+            match client.post(&url).json(&body).send().await {
+                Ok(response) if response.status().is_success() => {}
+                Ok(response) => warn!("OTLP collector answered {} for {} span(s)", response.status(), spans.len()),
+                Err(e) => warn!("cannot send {} span(s) to the OTLP collector: {}", spans.len(), e),
+            }
+        }
+    };
+
+    let mut batch = Vec::new();
+    let mut ticks = tokio::time::interval(EXPORT_INTERVAL);
+    loop {
+        tokio::select! {
+            message = receiver.recv() => match message {
+                Some(Message::Span(span)) => {
+                    batch.push(*span);
+                    if batch.len() >= MAX_BATCH {
+                        send(std::mem::take(&mut batch)).await;
+                    }
+                }
+                Some(Message::Flush(done)) => {
+                    if !batch.is_empty() {
+                        send(std::mem::take(&mut batch)).await;
+                    }
+                    let _ = done.send(());
+                }
+                None => {
+                    if !batch.is_empty() {
+                        send(batch).await;
+                    }
+                    return;
+                }
+            },
+            _ = ticks.tick() => {
+                if !batch.is_empty() {
+                    send(std::mem::take(&mut batch)).await;
+                }
+            }
+        }
+    }
+}
+
+/// A random, non-zero span id.
+fn span_id() -> [u8; 8] {
+    loop {
+        let id: [u8; 8] = rand::random();
+        if id != [0; 8] {
+            return id;
+        }
+    }
+}
+
+fn unix_nanos(at: DateTime<Utc>) -> String {
+    at.timestamp_nanos_opt().unwrap_or_default().max(0).to_string()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn unhex<const N: usize>(text: &str) -> Option<[u8; N]> {
+    if text.len() != N * 2 || !text.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&text[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traceparent_headers_are_parsed_strictly() {
+        let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let context = SpanContext::from_traceparent(header).unwrap();
+        assert_eq!(hex(&context.trace_id), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(hex(&context.span_id), "00f067aa0ba902b7");
+        assert_eq!(context.traceparent(), header);
+
+        for bad in [
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "00-4bf92f3577b34da6a3ce929d0e0e47-00f067aa0ba902b7-01",
+            "nonsense",
+        ] {
+            assert_eq!(SpanContext::from_traceparent(bad), None, "{bad}");
+        }
+
+        let input = json!({ "headers": { "traceparent": header } });
+        assert_eq!(SpanContext::from_trigger_input(&input), Some(context));
+        assert_eq!(SpanContext::from_trigger_input(&json!({ "order": 1 })), None);
+    }
+
+    #[test]
+    fn spans_are_encoded_as_otlp_json() {
+        let span = Span {
+            context: SpanContext { trace_id: [1; 16], span_id: [2; 8] },
+            parent_span_id: Some([3; 8]),
+            name: "node fetch".into(),
+            kind: KIND_INTERNAL,
+            start: DateTime::from_timestamp(1_700_000_000, 5).unwrap(),
+            end: DateTime::from_timestamp(1_700_000_001, 0).unwrap(),
+            attributes: vec![("node.type", json!("http")), ("node.attempt", json!(2)), ("cached", json!(false))],
+            error: Some("timed out".into()),
+        };
+        let otlp = span.to_otlp();
+        assert_eq!(otlp["traceId"], "01010101010101010101010101010101");
+        assert_eq!(otlp["spanId"], "0202020202020202");
+        assert_eq!(otlp["parentSpanId"], "0303030303030303");
+        assert_eq!(otlp["startTimeUnixNano"], "1700000000000000005");
+        assert_eq!(otlp["status"], json!({ "code": 2, "message": "timed out" }));
+        assert_eq!(
+            otlp["attributes"],
+            json!([
+                { "key": "node.type", "value": { "stringValue": "http" } },
+                { "key": "node.attempt", "value": { "intValue": "2" } },
+                { "key": "cached", "value": { "boolValue": false } },
+            ])
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! Workflow parameters — one workflow, many variations.
+//!
+//! A workflow declares named [`Param`]s with a type and usually a default,
+//! and node configs read them as `{{ params.NAME }}`:
+//!
+//! ```json
+//! "params": {
+//!   "region": { "type": "string", "default": "eu" },
+//!   "limit": { "type": "integer", "default": 10 }
+//! },
+//! "nodes": [{ "id": "top", "node_type": "sort_limit",
+//!             "config": { "field": "orders", "sort": [{ "field": "amount", "order": "desc" }],
+//!                         "limit": "{{ params.limit }}" } }]
+//! ```
+//!
+//! Runs override defaults through the `params` of `POST
+//! /workflows/:id/execute`, or `params.NAME` query parameters on webhook
+//! requests; the overrides are checked against the declared types when the
+//! run is queued and kept on the execution, so retries and replays run with
+//! them too.
+//!
+//! The executor substitutes parameters into a node's config before the
+//! node sees it, after secrets.  A string that is a single placeholder
+//! becomes the parameter's value with its JSON type (`"{{ params.limit }}"`
+//! is the number 10); placeholders inside longer strings are replaced by
+//! the value's text.  Other placeholders are left for the node to render.
+
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+use crate::{Param, ParamType};
+
+/// Placeholder prefix of parameters in node configs.
+const PLACEHOLDER_PREFIX: &str = "params.";
+
+/// Every declared parameter's value for a run: `overrides` where given,
+/// the defaults otherwise.
+///
+/// # Errors
+/// Overrides of undeclared parameters, values of the wrong type, and
+/// parameters with neither a default nor an override.
+pub fn resolve(declared: &BTreeMap<String, Param>, overrides: &Map<String, Value>) -> Result<Map<String, Value>, String> {
+    if let Some(name) = overrides.keys().find(|name| !declared.contains_key(*name)) {
+        return Err(format!("unknown parameter '{name}'"));
+    }
+    declared
+        .iter()
+        .map(|(name, param)| {
+            let value = overrides
+                .get(name)
+                .or(param.default.as_ref())
+                .ok_or_else(|| format!("parameter '{name}' has no default and was not given"))?;
+            if !matches(param.kind, value) {
+                return Err(format!("parameter '{name}' must be {}, not {value}", describe(param.kind)));
+            }
+            Ok((name.clone(), value.clone()))
+        })
+        .collect()
+}
+
+/// Overrides from text, e.g. query parameters: each `params.NAME` pair
+/// parsed by the type of parameter `NAME`; other pairs are ignored.
+/// Strings are taken as they are, everything else as JSON.
+///
+/// # Errors
+/// Undeclared parameters and text that does not parse.
+pub fn from_pairs<'a>(
+    declared: &BTreeMap<String, Param>,
+    pairs: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Result<Map<String, Value>, String> {
+    let mut overrides = Map::new();
+    for (key, text) in pairs {
+        let Some(name) = key.strip_prefix(PLACEHOLDER_PREFIX) else { continue };
+        let param = declared.get(name).ok_or_else(|| format!("unknown parameter '{name}'"))?;
+        let value = match param.kind {
+            ParamType::String => Value::String(text.to_owned()),
+            kind => serde_json::from_str(text)
+                .map_err(|_| format!("parameter '{name}' must be {}, not '{text}'", describe(kind)))?,
+        };
+        overrides.insert(name.to_owned(), value);
+    }
+    Ok(overrides)
+}
+
+/// `config` with every `{{ params.NAME }}` placeholder of a parameter in
+/// `params` replaced by its value.
+pub fn interpolate(config: &Value, params: &Map<String, Value>) -> Value {
+    match config {
+        Value::String(text) if text.contains("{{") => {
+            if let Some(value) = whole_placeholder(text).and_then(|name| params.get(name)) {
+                return value.clone();
+            }
+            Value::String(interpolate_str(text, params))
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| interpolate(item, params)).collect()),
+        Value::Object(fields) => {
+            Value::Object(fields.iter().map(|(name, field)| (name.clone(), interpolate(field, params))).collect())
+        }
+        other => other.clone(),
+    }
+}
+
+/// The parameter names `config`'s placeholders refer to, in order.
+pub fn references(config: &Value) -> Vec<String> {
+    match config {
+        Value::String(text) => nodes::template::placeholder_paths(text)
+            .into_iter()
+            .filter_map(|path| path.strip_prefix(PLACEHOLDER_PREFIX))
+            .map(str::to_owned)
+            .collect(),
+        Value::Array(items) => items.iter().flat_map(references).collect(),
+        Value::Object(fields) => fields.values().flat_map(references).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether `value` has JSON type `kind`.
+pub fn matches(kind: ParamType, value: &Value) -> bool {
+    match kind {
+        ParamType::String => value.is_string(),
+        ParamType::Number => value.is_number(),
+        ParamType::Integer => value.is_i64() || value.is_u64(),
+        ParamType::Boolean => value.is_boolean(),
+        ParamType::Object => value.is_object(),
+        ParamType::Array => value.is_array(),
+    }
+}
+
+fn describe(kind: ParamType) -> &'static str {
+    match kind {
+        ParamType::String => "a string",
+        ParamType::Number => "a number",
+        ParamType::Integer => "an integer",
+        ParamType::Boolean => "a boolean",
+        ParamType::Object => "an object",
+        ParamType::Array => "an array",
+    }
+}
+
+/// The parameter `text` consists of, if it is one placeholder and nothing
+/// else.
+fn whole_placeholder(text: &str) -> Option<&str> {
+    let inner = text.trim().strip_prefix("{{")?.strip_suffix("}}")?;
+    if inner.contains("{{") || inner.contains("}}") {
+        return None;
+    }
+    inner.trim().strip_prefix(PLACEHOLDER_PREFIX)
+}
+
+fn interpolate_str(text: &str, params: &Map<String, Value>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}").map(|end| start + end + 2) else { break };
+        let placeholder = &rest[start..end];
+        out.push_str(&rest[..start]);
+        let value = placeholder[2..placeholder.len() - 2]
+            .trim()
+            .strip_prefix(PLACEHOLDER_PREFIX)
+            .and_then(|name| params.get(name));
+        match value {
+            Some(Value::String(value)) => out.push_str(value),
+            Some(value) => out.push_str(&value.to_string()),
+            None => out.push_str(placeholder),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn declared() -> BTreeMap<String, Param> {
+        serde_json::from_value(json!({
+            "region": { "type": "string", "default": "eu" },
+            "limit": { "type": "integer", "default": 10 },
+            "customer": { "type": "string" }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn overrides_win_over_defaults_and_are_type_checked() {
+        let declared = declared();
+        let overrides = json!({ "customer": "acme", "limit": 3 });
+        let params = resolve(&declared, overrides.as_object().unwrap()).unwrap();
+        assert_eq!(Value::Object(params), json!({ "region": "eu", "limit": 3, "customer": "acme" }));
+
+        assert_eq!(resolve(&declared, &Map::new()).unwrap_err(), "parameter 'customer' has no default and was not given");
+        let wrong = json!({ "customer": "acme", "limit": "3" });
+        assert_eq!(resolve(&declared, wrong.as_object().unwrap()).unwrap_err(), "parameter 'limit' must be an integer, not \"3\"");
+        let unknown = json!({ "customer": "acme", "colour": "red" });
+        assert_eq!(resolve(&declared, unknown.as_object().unwrap()).unwrap_err(), "unknown parameter 'colour'");
+    }
+
+    #[test]
+    fn query_pairs_are_parsed_by_type() {
+        let declared = declared();
+        let pairs = [("params.limit", "5"), ("params.customer", "42"), ("source", "shop")];
+        assert_eq!(Value::Object(from_pairs(&declared, pairs).unwrap()), json!({ "limit": 5, "customer": "42" }));
+        assert!(from_pairs(&declared, [("params.limit", "many")]).is_err());
+        assert!(from_pairs(&declared, [("params.colour", "red")]).is_err());
+    }
+
+    #[test]
+    fn placeholders_keep_their_type_when_they_are_the_whole_string() {
+        let params = json!({ "limit": 3, "region": "us" });
+        let config = json!({
+            "limit": "{{ params.limit }}",
+            "url": "https://{{ params.region }}.example.com/top?n={{params.limit}}",
+            "other": ["{{ input.name }}", "{{ params.unknown }}"]
+        });
+        assert_eq!(
+            interpolate(&config, params.as_object().unwrap()),
+            json!({
+                "limit": 3,
+                "url": "https://us.example.com/top?n=3",
+                "other": ["{{ input.name }}", "{{ params.unknown }}"]
+            })
+        );
+        assert_eq!(references(&config), vec!["limit", "unknown", "region", "limit"]);
+    }
+}
@@ -0,0 +1,103 @@
+//! Partition maintenance — keep monthly `node_executions` partitions ahead
+//! of the clock and drop the ones retention has emptied.
+//!
+//! Rows landing past the last monthly partition go to the default one, so
+//! a maintainer that falls behind only costs partition pruning, never an
+//! insert.  Old partitions are dropped only once empty: how long node
+//! executions live is still up to [`crate::retention`].
+//!
+//! `workflow_executions` is not partitioned yet; that is a follow-up on
+//! the roadmap in the README.
+
+use std::time::Duration;
+
+use chrono::{Datelike, Months, NaiveDate, Utc};
+use tracing::{info, warn};
+
+use db::DbPool;
+use db::repository::partitions as partition_repo;
+
+use crate::EngineError;
+
+/// Outcome of one maintenance pass.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PartitionReport {
+    pub created: u64,
+    pub dropped: u64,
+}
+
+/// Creates upcoming monthly partitions and drops empty past ones.
+pub struct PartitionMaintainer {
+    pool: DbPool,
+    months_ahead: u32,
+}
+
+impl PartitionMaintainer {
+    /// Create a maintainer keeping the current and next month partitioned.
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool, months_ahead: 1 }
+    }
+
+    /// Number of months after the current one to create partitions for.
+    pub fn with_months_ahead(mut self, months_ahead: u32) -> Self {
+        self.months_ahead = months_ahead;
+        self
+    }
+
+    /// Run one maintenance pass.
+    pub async fn maintain_once(&self) -> Result<PartitionReport, EngineError> {
+        let this_month = first_of_month(Utc::now().date_naive());
+        let mut report = PartitionReport::default();
+
+        for month in months_from(this_month, self.months_ahead) {
+            if partition_repo::create_node_execution_partition(&self.pool, month).await? {
+                report.created += 1;
+            }
+        }
+        report.dropped = partition_repo::drop_empty_node_execution_partitions(&self.pool, this_month).await?;
+        Ok(report)
+    }
+
+    /// Maintain partitions every `interval` until the task is dropped.
+    pub async fn run(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match self.maintain_once().await {
+                Ok(PartitionReport { created: 0, dropped: 0 }) => {}
+                Ok(report) => info!(
+                    "partitions: created {} and dropped {} node execution partitions",
+                    report.created, report.dropped
+                ),
+                Err(e) => warn!("partitions: maintenance failed: {}", e),
+            }
+        }
+    }
+}
+
+fn first_of_month(day: NaiveDate) -> NaiveDate {
+    day.with_day(1).expect("every month has a first day")
+}
+
+/// `first` and the `ahead` months after it.
+fn months_from(first: NaiveDate, ahead: u32) -> Vec<NaiveDate> {
+    (0..=ahead).filter_map(|n| first.checked_add_months(Months::new(n))).collect()
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn months_roll_over_the_year() {
+        let november = NaiveDate::from_ymd_opt(2024, 11, 1).unwrap();
+        assert_eq!(
+            months_from(november, 2),
+            vec![november, NaiveDate::from_ymd_opt(2024, 12, 1).unwrap(), NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()]
+        );
+        assert_eq!(first_of_month(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()), NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+    }
+}
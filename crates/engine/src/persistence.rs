@@ -0,0 +1,286 @@
+//! Write-behind persistence of node executions.
+//!
+//! Recording a node's input and output used to sit on the critical path:
+//! the next node waited for the insert (and, with determinism reporting, for
+//! hashing both payloads).  [`NodeWriter`] moves that work to a background
+//! task fed through a bounded channel.  The bound gives back-pressure — a
+//! slow database eventually slows the execution down instead of buffering
+//! payloads without limit — and [`NodeWriter::finish`] waits until every
+//! queued record is written, so an execution is never marked finished (or
+//! waiting) before its node records are durable.
+//!
+//! The last record is written in one transaction with the execution's
+//! final status ([`Transition`]), so a crash never leaves a failed node
+//! under a `running` execution, nor a finished execution missing its last
+//! node.  Records are written in the order they were queued.
+//!
+//! Payloads are shared `Arc`s, so queueing one does not copy it, and they
+//! are never materialized as JSON text on the way to Postgres: the insert
+//! binds them by reference and serializes them straight into the
+//! statement's parameter buffer, hashing walks the value itself, and a
+//! payload compressed at rest is serialized straight into the compressor
+//! (see [`db::payloads`]).  Only sealing needs the text, to encrypt it.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::error;
+use uuid::Uuid;
+
+use db::models::NewNodeExecution;
+use db::{DbError, DbPool};
+
+use crate::determinism;
+
+/// Default number of records that may wait to be written.
+pub const DEFAULT_CAPACITY: usize = 32;
+
+/// One node run to persist.
+#[derive(Debug, Clone)]
+pub struct NodeRecord {
+    pub node_id: String,
+    pub input: Arc<Value>,
+    pub output: Option<Arc<Value>>,
+    pub status: &'static str,
+    pub started_at: DateTime<Utc>,
+    /// Compute and store input/output hashes (determinism reporting).
+    pub hash: bool,
+    /// Why the node failed, for `failed` records.
+    pub error: Option<String>,
+    /// Branch picked or halt, for `succeeded` records.
+    pub flow: db::models::NodeFlow,
+    /// Every attempt at the node, in order.
+    pub attempts: Vec<db::models::NodeAttempt>,
+}
+
+/// Status an execution moves to with its last node records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    pub status: &'static str,
+    /// Whether this ends the execution (sets `finished_at`).
+    pub finished: bool,
+}
+
+impl Transition {
+    pub const SUCCEEDED: Self = Self { status: "succeeded", finished: true };
+    pub const FAILED: Self = Self { status: "failed", finished: true };
+}
+
+type WriteFuture = Pin<Box<dyn Future<Output = Result<(), DbError>> + Send>>;
+
+#[derive(Debug)]
+enum Message {
+    Record(NodeRecord),
+    Finish(Option<Transition>),
+}
+
+/// Background writer for one execution's node records.
+#[derive(Debug)]
+pub struct NodeWriter {
+    tx: mpsc::Sender<Message>,
+    task: JoinHandle<Result<(), DbError>>,
+}
+
+impl NodeWriter {
+    /// Write records for `execution_id` to `node_executions`, with at most
+    /// `capacity` records queued.
+    pub fn spawn(pool: DbPool, execution_id: Uuid, capacity: usize) -> Self {
+        Self::spawn_with(capacity, move |records, transition| {
+            let pool = pool.clone();
+            Box::pin(async move {
+                let hashes: Vec<_> = records
+                    .iter()
+                    .map(|record| {
+                        record
+                            .hash
+                            .then(|| determinism::node_hashes(&record.input, record.output.as_deref()))
+                    })
+                    .collect();
+                let nodes: Vec<_> = records
+                    .iter()
+                    .zip(&hashes)
+                    .map(|(record, hashes)| NewNodeExecution {
+                        node_id: &record.node_id,
+                        input: &record.input,
+                        output: record.output.as_deref(),
+                        status: record.status,
+                        started_at: record.started_at,
+                        hashes: hashes.as_ref(),
+                        error: record.error.as_deref(),
+                        flow: &record.flow,
+                        attempts: &record.attempts,
+                    })
+                    .collect();
+                let status = transition.map(|t| (t.status, t.finished));
+                db::repository::executions::record_step(&pool, execution_id, &nodes, status)
+                    .await
+                    .map(|_| ())
+            })
+        })
+    }
+
+    /// Like [`NodeWriter::spawn`], with a custom `write` step.  `write` gets
+    /// records to store together with the transition to apply, if any, and
+    /// must write all of it or nothing.
+    pub fn spawn_with<F>(capacity: usize, write: F) -> Self
+    where
+        F: Fn(Vec<NodeRecord>, Option<Transition>) -> WriteFuture + Send + 'static,
+    {
+        let (tx, mut rx) = mpsc::channel::<Message>(capacity.max(1));
+        let task = tokio::spawn(async move {
+            // The latest record is held back until the next one arrives or
+            // the writer finishes, so it commits together with the final
+            // transition.  Keep writing after a failure so as much as
+            // possible is recorded; the first error is reported by `finish`.
+            let mut first_error = None;
+            let mut held: Option<NodeRecord> = None;
+            let mut transition = None;
+            while let Some(message) = rx.recv().await {
+                match message {
+                    Message::Record(record) => {
+                        if let Some(previous) = held.replace(record) {
+                            let node_id = previous.node_id.clone();
+                            if let Err(e) = write(vec![previous], None).await {
+                                error!("failed to persist node '{}': {}", node_id, e);
+                                first_error.get_or_insert(e);
+                            }
+                        }
+                    }
+                    Message::Finish(t) => transition = t,
+                }
+            }
+            if held.is_some() || transition.is_some() {
+                if let Err(e) = write(held.into_iter().collect(), transition).await {
+                    error!("failed to persist the last step: {}", e);
+                    first_error.get_or_insert(e);
+                }
+            }
+            first_error.map_or(Ok(()), Err)
+        });
+        Self { tx, task }
+    }
+
+    /// Queue `record`, waiting while the queue is full.
+    pub async fn record(&self, record: NodeRecord) {
+        // The writer task only stops once every sender is gone, so this
+        // cannot fail while `self` is alive.
+        let _ = self.tx.send(Message::Record(record)).await;
+    }
+
+    /// Wait until every queued record is written, the last ones in the same
+    /// transaction as `transition`.
+    ///
+    /// # Errors
+    /// The first error hit while writing; a failed last write leaves the
+    /// execution's status unchanged.
+    pub async fn finish(self, transition: Option<Transition>) -> Result<(), DbError> {
+        let _ = self.tx.send(Message::Finish(transition)).await;
+        drop(self.tx);
+        match self.task.await {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            // Cancelled: the runtime is shutting down.
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    fn record(node_id: &str) -> NodeRecord {
+        NodeRecord {
+            node_id: node_id.into(),
+            input: Arc::new(json!({})),
+            output: None,
+            status: "succeeded",
+            started_at: Utc::now(),
+            hash: false,
+            error: None,
+            flow: db::models::NodeFlow::default(),
+            attempts: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn finish_waits_for_every_record_in_order() {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let sink = written.clone();
+        let writer = NodeWriter::spawn_with(2, move |records, _| {
+            let sink = sink.clone();
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                sink.lock().unwrap().extend(records.into_iter().map(|r| r.node_id));
+                Ok(())
+            })
+        });
+
+        for id in ["a", "b", "c", "d", "e"] {
+            writer.record(record(id)).await;
+        }
+        writer.finish(None).await.unwrap();
+        assert_eq!(*written.lock().unwrap(), ["a", "b", "c", "d", "e"]);
+    }
+
+    #[tokio::test]
+    async fn reports_the_first_error_after_writing_the_rest() {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let sink = written.clone();
+        let writer = NodeWriter::spawn_with(4, move |records, _| {
+            let sink = sink.clone();
+            Box::pin(async move {
+                if records.iter().any(|r| r.node_id == "bad") {
+                    return Err(DbError::NotFound);
+                }
+                sink.lock().unwrap().extend(records.into_iter().map(|r| r.node_id));
+                Ok(())
+            })
+        });
+
+        for id in ["a", "bad", "c"] {
+            writer.record(record(id)).await;
+        }
+        assert!(matches!(writer.finish(None).await, Err(DbError::NotFound)));
+        assert_eq!(*written.lock().unwrap(), ["a", "c"]);
+    }
+
+    #[tokio::test]
+    async fn last_record_is_written_with_the_transition() {
+        let writes = Arc::new(Mutex::new(Vec::new()));
+        let sink = writes.clone();
+        let writer = NodeWriter::spawn_with(4, move |records, transition| {
+            let sink = sink.clone();
+            Box::pin(async move {
+                let ids: Vec<_> = records.into_iter().map(|r| r.node_id).collect();
+                sink.lock().unwrap().push((ids, transition));
+                Ok(())
+            })
+        });
+
+        for id in ["a", "b", "c"] {
+            writer.record(record(id)).await;
+        }
+        writer.finish(Some(Transition::FAILED)).await.unwrap();
+        assert_eq!(
+            *writes.lock().unwrap(),
+            [
+                (vec!["a".to_owned()], None),
+                (vec!["b".to_owned()], None),
+                (vec!["c".to_owned()], Some(Transition::FAILED)),
+            ]
+        );
+    }
+}
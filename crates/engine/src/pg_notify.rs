@@ -0,0 +1,236 @@
+//! Postgres notification trigger — starts workflows whose trigger is
+//! [`Trigger::PgNotify`] for each `NOTIFY` on their channel, so an existing
+//! application can kick off a workflow with a single statement:
+//!
+//! ```sql
+//! SELECT pg_notify('orders_created', json_build_object('id', NEW.id)::text);
+//! ```
+//!
+//! Each pass of [`NotifyTriggers::run`] routes every channel to the active
+//! workflows listening on it.  There is one listener per database — the
+//! engine's own (holding one connection of its pool) or the one named by a
+//! trigger's `url_env` — which subscribes to and drops channels as routes
+//! change, and reconnects with a growing back-off when its connection fails.
+//!
+//! `NOTIFY` is fire-and-forget: notifications sent while no listener is
+//! connected are lost, and every process running the listeners starts its
+//! own execution for each one, so serve these triggers from one process.
+//!
+//! The execution's input is `{ "channel": "...", "payload": ... }`, with the
+//! payload parsed as JSON when it is JSON and a string otherwise.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use db::listener::{Listener, Notification};
+use db::repository::workflows as wf_repo;
+use db::{DbError, DbPool};
+use queue::{JobQueue, PgJobQueue, SharedQueue};
+
+use crate::{triggers, EngineError, Trigger, Workflow};
+
+/// Longest pause between reconnection attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Workflows to start, by channel.
+type Routes = HashMap<String, Vec<(Uuid, Workflow)>>;
+
+/// A running listener and the routes it serves.
+struct Database {
+    routes: watch::Sender<Routes>,
+    task: JoinHandle<()>,
+}
+
+/// Keeps one listener running per database that notification-triggered
+/// workflows listen on.
+pub struct NotifyTriggers {
+    pool: DbPool,
+    jobs: SharedQueue,
+    /// Keyed by the trigger's `url_env`; `None` is the engine's database.
+    databases: HashMap<Option<String>, Database>,
+}
+
+impl NotifyTriggers {
+    pub fn new(pool: DbPool) -> Self {
+        Self { jobs: Arc::new(PgJobQueue::new(pool.clone())), pool, databases: HashMap::new() }
+    }
+
+    /// Hand admitted jobs to `jobs`.
+    pub fn with_queue(mut self, jobs: SharedQueue) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Update the routes of every listener, starting and stopping
+    /// listeners as needed; returns the number of notification-triggered
+    /// workflows.
+    pub async fn sync(&mut self) -> Result<usize, EngineError> {
+        let mut wanted: HashMap<Option<String>, Routes> = HashMap::new();
+        let mut count = 0;
+        for row in wf_repo::list_workflows(&self.pool).await? {
+            if !row.active {
+                continue;
+            }
+            let Ok(workflow) = serde_json::from_value::<Workflow>(row.definition) else {
+                continue;
+            };
+            let Trigger::PgNotify { channel, url_env } = &workflow.trigger else {
+                continue;
+            };
+            let routes = wanted.entry(url_env.clone()).or_default();
+            routes.entry(channel.clone()).or_default().push((row.id, workflow.clone()));
+            count += 1;
+        }
+
+        self.databases.retain(|key, database| {
+            let keep = wanted.contains_key(key) && !database.task.is_finished();
+            if !keep {
+                database.task.abort();
+            }
+            keep
+        });
+
+        for (key, routes) in wanted {
+            if let Some(database) = self.databases.get(&key) {
+                database.routes.send_replace(routes);
+                continue;
+            }
+            let url = match &key {
+                Some(url_env) => match std::env::var(url_env) {
+                    Ok(url) => Some(url),
+                    Err(_) => {
+                        warn!("pg_notify: not listening on {}: the variable is not set", url_env);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            let (sender, receiver) = watch::channel(routes);
+            let task = tokio::spawn(listen(self.pool.clone(), Arc::clone(&self.jobs), url, receiver));
+            self.databases.insert(key, Database { routes: sender, task });
+        }
+        Ok(count)
+    }
+
+    /// Sync every `interval` until the task is dropped.
+    pub async fn run(mut self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.sync().await {
+                warn!("pg_notify: sync failed: {}", e);
+            }
+        }
+    }
+}
+
+impl Drop for NotifyTriggers {
+    fn drop(&mut self) {
+        for database in self.databases.values() {
+            database.task.abort();
+        }
+    }
+}
+
+/// Listen on the database at `url` (the engine's own when `None`),
+/// reconnecting until the routes are dropped.
+async fn listen(pool: DbPool, jobs: SharedQueue, url: Option<String>, mut routes: watch::Receiver<Routes>) {
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        let listener = match &url {
+            Some(url) => Listener::connect(url).await,
+            None => Listener::connect_with(&pool).await,
+        };
+        match listener {
+            Ok(listener) => match serve(&pool, jobs.as_ref(), listener, &mut routes).await {
+                Ok(()) => return,
+                Err(e) => warn!("pg_notify: listener failed: {}; reconnecting in {:?}", e, backoff),
+            },
+            Err(e) => warn!("pg_notify: cannot connect: {}; retrying in {:?}", e, backoff),
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Follow route changes and admit notifications until the routes are
+/// dropped or the listener fails.
+async fn serve(
+    pool: &DbPool,
+    jobs: &dyn JobQueue,
+    mut listener: Listener,
+    routes: &mut watch::Receiver<Routes>,
+) -> Result<(), DbError> {
+    let mut channels: HashSet<String> = HashSet::new();
+    loop {
+        let wanted: HashSet<String> = routes.borrow_and_update().keys().cloned().collect();
+        for channel in wanted.difference(&channels) {
+            listener.listen(channel).await?;
+            info!("pg_notify: listening on '{}'", channel);
+        }
+        for channel in channels.difference(&wanted) {
+            listener.unlisten(channel).await?;
+        }
+        channels = wanted;
+
+        tokio::select! {
+            changed = routes.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+            }
+            notification = listener.recv() => {
+                let notification = notification?;
+                let targets = routes.borrow().get(&notification.channel).cloned().unwrap_or_default();
+                dispatch(pool, jobs, &targets, &notification).await;
+            }
+        }
+    }
+}
+
+/// Admit `notification` for every workflow listening on its channel.
+async fn dispatch(pool: &DbPool, jobs: &dyn JobQueue, targets: &[(Uuid, Workflow)], notification: &Notification) {
+    let input = notification_input(notification);
+    for (workflow_id, workflow) in targets {
+        if let Err(e) = triggers::admit(pool, jobs, *workflow_id, workflow, input.clone()).await {
+            warn!("pg_notify: workflow {} cannot admit a notification: {}", workflow_id, e);
+        }
+    }
+}
+
+/// The execution input for a notification.
+fn notification_input(notification: &Notification) -> Value {
+    let payload = serde_json::from_str(&notification.payload)
+        .unwrap_or_else(|_| Value::String(notification.payload.clone()));
+    json!({ "channel": notification.channel, "payload": payload })
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(payload: &str) -> Notification {
+        Notification { channel: "orders_created".into(), payload: payload.into() }
+    }
+
+    #[test]
+    fn payloads_are_json_when_they_parse() {
+        assert_eq!(
+            notification_input(&notification(r#"{"id": 7}"#)),
+            json!({ "channel": "orders_created", "payload": { "id": 7 } })
+        );
+        assert_eq!(notification_input(&notification("42"))["payload"], json!(42));
+        assert_eq!(notification_input(&notification("order 7"))["payload"], json!("order 7"));
+        assert_eq!(notification_input(&notification(""))["payload"], json!(""));
+    }
+}
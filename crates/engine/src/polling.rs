@@ -0,0 +1,301 @@
+//! Poll trigger — starts workflows whose trigger is [`Trigger::Poll`] for
+//! each new item their source reports.
+//!
+//! Each pass of [`Poller::run`] visits every active workflow with a poll
+//! trigger whose `interval_secs` has elapsed since its last poll, and:
+//!
+//! 1. **Fetches** the source — an HTTP `GET` answering with JSON, or a run
+//!    of a registered node — and takes the item array at `items`.
+//! 2. **Compares** each item's `cursor` (an increasing id or timestamp)
+//!    with the highest one seen so far, kept in the workflow's static data
+//!    under [`POLL_STATE_KEY`] together with the time of the last poll.
+//! 3. **Admits** every newer item, oldest first, through
+//!    [`triggers::admit`](crate::triggers::admit); the item is the
+//!    execution's input.
+//!
+//! The first poll of a workflow only records where the source stands, so
+//! enabling a trigger does not replay everything the source already holds.
+//! Cursors compare as numbers when both are numeric (or numeric strings),
+//! as instants when both are RFC 3339 timestamps, and as strings
+//! otherwise.  The state entry stays locked for the whole poll, so
+//! concurrent pollers never admit the same item twice; the cursor advances
+//! past each item as it is admitted, so a failure part-way only re-polls
+//! what was not admitted yet.
+
+use std::cmp::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use db::DbPool;
+use db::repository::{state as state_repo, workflows as wf_repo};
+use nodes::template::lookup;
+use nodes::traits::ExecutionContext;
+use queue::{PgJobQueue, SharedQueue};
+
+use crate::executor::{ExecutorConfig, NodeRegistry, NodeRunner};
+use crate::state::PgWorkflowStateStore;
+use crate::{triggers, EngineError, PollSource, Trigger, Workflow};
+
+/// Static-data key holding a poll trigger's cursor and last poll time.
+pub const POLL_STATE_KEY: &str = "trigger.poll";
+
+/// Node ID a polled node runs under.
+const SOURCE_NODE_ID: &str = "poll";
+
+/// Polls the sources of poll-triggered workflows and admits new items.
+pub struct Poller {
+    pool: DbPool,
+    jobs: SharedQueue,
+    registry: Arc<NodeRegistry>,
+    runner: NodeRunner,
+    http: reqwest::Client,
+}
+
+impl Poller {
+    /// A poller running node sources from `registry`.
+    pub fn new(pool: DbPool, registry: NodeRegistry) -> Self {
+        Self {
+            jobs: Arc::new(PgJobQueue::new(pool.clone())),
+            pool,
+            registry: Arc::new(registry),
+            runner: NodeRunner::new(ExecutorConfig::default()),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Hand admitted jobs to `jobs`.
+    pub fn with_queue(mut self, jobs: SharedQueue) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Poll every workflow that is due; returns how many executions were
+    /// admitted.
+    pub async fn poll_due(&self) -> Result<usize, EngineError> {
+        let mut admitted = 0;
+        for row in wf_repo::list_workflows(&self.pool).await? {
+            if !row.active {
+                continue;
+            }
+            let Ok(workflow) = serde_json::from_value::<Workflow>(row.definition) else {
+                continue;
+            };
+            if !matches!(workflow.trigger, Trigger::Poll { .. }) {
+                continue;
+            }
+            match self.poll(row.id, &workflow).await {
+                Ok(count) => admitted += count,
+                Err(e) => warn!("poller: workflow {}: {}", row.id, e),
+            }
+        }
+        Ok(admitted)
+    }
+
+    /// Poll the workflow stored under `workflow_id` if its interval has
+    /// elapsed; returns how many executions were admitted.
+    async fn poll(&self, workflow_id: Uuid, workflow: &Workflow) -> Result<usize, EngineError> {
+        let Trigger::Poll { source, interval_secs, items, cursor } = &workflow.trigger else {
+            return Ok(0);
+        };
+        let now = Utc::now();
+
+        let mut tx = self.pool.begin().await.map_err(db::DbError::from)?;
+        state_repo::ensure_state_row(&mut tx, workflow_id, POLL_STATE_KEY).await?;
+        let state = state_repo::lock_state(&mut tx, workflow_id, POLL_STATE_KEY).await?;
+        let polled_at = state
+            .as_ref()
+            .and_then(|s| s["polled_at"].as_str())
+            .and_then(|s| s.parse::<DateTime<Utc>>().ok());
+        let interval = chrono::Duration::seconds(i64::try_from(*interval_secs).unwrap_or(i64::MAX));
+        if polled_at.is_some_and(|at| at + interval > now) {
+            return Ok(0);
+        }
+        let mut last = state.as_ref().map(|s| s["cursor"].clone()).filter(|c| !c.is_null());
+
+        // Record the attempt even when the source fails, so a broken source
+        // is retried on its interval rather than on every pass.
+        let fresh = match self.fetch(workflow_id, source).await {
+            Ok(response) => new_items(&response, items.as_deref(), cursor, last.as_ref())
+                .map_err(|message| EngineError::PollFailed { workflow_id, message }),
+            Err(e) => Err(e),
+        };
+        let mut admitted = 0;
+        let outcome = match fresh {
+            Ok(fresh) if state.is_none() => {
+                info!("poller: workflow {} starts after {} existing items", workflow_id, fresh.len());
+                last = fresh.into_iter().last().map(|(cursor, _)| cursor);
+                Ok(0)
+            }
+            Ok(fresh) => {
+                let mut failure = None;
+                for (item_cursor, item) in fresh {
+                    if let Err(e) = triggers::admit(&self.pool, self.jobs.as_ref(), workflow_id, workflow, item).await {
+                        failure = Some(e);
+                        break;
+                    }
+                    last = Some(item_cursor);
+                    admitted += 1;
+                }
+                failure.map_or(Ok(admitted), Err)
+            }
+            Err(e) => Err(e),
+        };
+
+        let state = json!({ "cursor": last, "polled_at": now });
+        state_repo::write_state(&mut tx, workflow_id, POLL_STATE_KEY, state).await?;
+        tx.commit().await.map_err(db::DbError::from)?;
+        if admitted > 0 {
+            info!("poller: workflow {} admitted {} new items", workflow_id, admitted);
+        }
+        outcome
+    }
+
+    /// The source's current response.
+    async fn fetch(&self, workflow_id: Uuid, source: &PollSource) -> Result<Value, EngineError> {
+        let failed = |message: String| EngineError::PollFailed { workflow_id, message };
+        match source {
+            PollSource::Http { url, headers } => {
+                let mut request = self.http.get(url);
+                for (name, value) in headers {
+                    request = request.header(name, value);
+                }
+                let response = request
+                    .send()
+                    .await
+                    .and_then(reqwest::Response::error_for_status)
+                    .map_err(|e| failed(e.to_string()))?;
+                response.json().await.map_err(|e| failed(format!("response is not JSON: {e}")))
+            }
+            PollSource::Node { node_type, config } => {
+                let node = self
+                    .registry
+                    .get(node_type)
+                    .ok_or_else(|| failed(format!("no implementation registered for node_type '{node_type}'")))?;
+                let ctx = ExecutionContext::new(workflow_id, Uuid::nil(), Value::Null)
+                    .with_state(Arc::new(PgWorkflowStateStore::new(self.pool.clone())))
+                    .for_node(SOURCE_NODE_ID, config.clone());
+                self.runner.run(SOURCE_NODE_ID, node, &Value::Null, &ctx).await
+            }
+        }
+    }
+
+    /// Poll every `interval` until the task is dropped.  A workflow is
+    /// polled on the first pass after its own interval has elapsed.
+    pub async fn run(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.poll_due().await {
+                warn!("poller: pass failed: {}", e);
+            }
+        }
+    }
+}
+
+/// The items of `response` whose cursor is after `last`, as
+/// `(cursor, item)` pairs in cursor order.  Items without a cursor, or
+/// with one that does not compare with `last`, are skipped.
+///
+/// # Errors
+/// A message when `items` does not lead to an array.
+pub fn new_items(
+    response: &Value,
+    items: Option<&str>,
+    cursor: &str,
+    last: Option<&Value>,
+) -> Result<Vec<(Value, Value)>, String> {
+    let list = lookup(response, items.unwrap_or_default())
+        .and_then(Value::as_array)
+        .ok_or_else(|| format!("no item array at '{}'", items.unwrap_or_default()))?;
+
+    let mut fresh: Vec<(Value, Value)> = list
+        .iter()
+        .filter_map(|item| match lookup(item, cursor) {
+            Some(value) if !value.is_null() => Some((value.clone(), item.clone())),
+            _ => {
+                warn!("poller: skipping an item without '{}'", cursor);
+                None
+            }
+        })
+        .filter(|(value, _)| last.is_none_or(|last| compare_cursors(value, last) == Some(Ordering::Greater)))
+        .collect();
+    fresh.sort_by(|(a, _), (b, _)| compare_cursors(a, b).unwrap_or(Ordering::Equal));
+    Ok(fresh)
+}
+
+/// Order two cursor values; `None` when they are of different kinds.
+fn compare_cursors(a: &Value, b: &Value) -> Option<Ordering> {
+    fn number(value: &Value) -> Option<f64> {
+        match value {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+    fn instant(value: &Value) -> Option<DateTime<Utc>> {
+        value.as_str()?.parse().ok()
+    }
+
+    if let (Some(a), Some(b)) = (number(a), number(b)) {
+        return a.partial_cmp(&b);
+    }
+    if let (Some(a), Some(b)) = (instant(a), instant(b)) {
+        return Some(a.cmp(&b));
+    }
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cursors(fresh: &[(Value, Value)]) -> Vec<Value> {
+        fresh.iter().map(|(cursor, _)| cursor.clone()).collect()
+    }
+
+    #[test]
+    fn picks_items_after_the_last_cursor_in_order() {
+        let response = json!({ "data": { "orders": [
+            { "id": 12, "total": 5 },
+            { "id": 10, "total": 7 },
+            { "id": 11 },
+            { "total": 1 },
+        ] } });
+
+        let fresh = new_items(&response, Some("data.orders"), "id", Some(&json!(10))).unwrap();
+        assert_eq!(cursors(&fresh), [json!(11), json!(12)]);
+        assert_eq!(fresh[1].1, json!({ "id": 12, "total": 5 }));
+
+        let all = new_items(&response, Some("data.orders"), "id", None).unwrap();
+        assert_eq!(cursors(&all), [json!(10), json!(11), json!(12)]);
+
+        let err = new_items(&response, Some("data"), "id", None).unwrap_err();
+        assert!(err.contains("no item array at 'data'"));
+    }
+
+    #[test]
+    fn compares_numbers_timestamps_and_strings() {
+        let items = json!([
+            { "at": "2024-03-04T10:00:00+01:00" },
+            { "at": "2024-03-04T09:30:00Z" },
+        ]);
+        // 10:00+01:00 is 09:00Z: only the second item is newer.
+        let fresh = new_items(&items, None, "at", Some(&json!("2024-03-04T09:15:00Z"))).unwrap();
+        assert_eq!(cursors(&fresh), [json!("2024-03-04T09:30:00Z")]);
+
+        assert_eq!(compare_cursors(&json!("10"), &json!(9)), Some(Ordering::Greater));
+        assert_eq!(compare_cursors(&json!("b-2"), &json!("a-9")), Some(Ordering::Greater));
+        assert_eq!(compare_cursors(&json!("abc"), &json!(1)), None);
+    }
+}
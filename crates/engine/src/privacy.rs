@@ -0,0 +1,429 @@
+//! Privacy erasure — redact a data subject's values from run history.
+//!
+//! An erasure request names its subject in one of two ways:
+//!
+//! * **business key** — every execution tagged with the key is treated as
+//!   the subject's: all stored node inputs/outputs and job payloads are
+//!   redacted leaf by leaf (keeping their shape) and the key is cleared;
+//! * **path + value** — executions where some node input/output or job
+//!   payload holds `value` at `path` (e.g. `customer.email`) are matched,
+//!   and every occurrence of the value in them is redacted, including
+//!   copies under other field names and inside longer strings.
+//!
+//! In both modes, per-workflow state entries holding the value (or the
+//! business key) are redacted too.  Records under legal hold are left
+//! untouched and listed in the report.  Job payloads are the only other
+//! stored copy of run data, so there is no separate archive to rewrite.
+//! Compressed and encrypted payloads ([`db::payloads`]) are matched and
+//! redacted once decoded, and written back the same way.
+//!
+//! [`redact_secrets`] is the related read-side helper: it masks fields that
+//! look like credentials before data is shown to support staff.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::info;
+use uuid::Uuid;
+
+use db::DbPool;
+use db::repository::{executions as exec_repo, jobs as job_repo, privacy as privacy_repo};
+
+use crate::EngineError;
+
+/// Replacement written over erased values.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Which data an erasure request targets.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErasureCriteria {
+    BusinessKey(String),
+    Value { path: String, value: Value },
+}
+
+impl ErasureCriteria {
+    /// Criteria matching executions tagged with `key`; `None` if it is empty.
+    pub fn business_key(key: impl Into<String>) -> Option<Self> {
+        let key = key.into();
+        (!key.trim().is_empty()).then_some(Self::BusinessKey(key))
+    }
+
+    /// Criteria matching `value` at `path`; `None` unless the path is
+    /// non-empty and the value is a non-empty string or a number.
+    pub fn value(path: &str, value: Value) -> Option<Self> {
+        let path = path.trim_matches('.');
+        let searchable = match &value {
+            Value::String(s) => !s.is_empty(),
+            Value::Number(_) => true,
+            _ => false,
+        };
+        (!path.is_empty() && searchable).then(|| Self::Value { path: path.to_owned(), value })
+    }
+
+    /// Criteria kind recorded in the erasure log.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::BusinessKey(_) => "business_key",
+            Self::Value { .. } => "value",
+        }
+    }
+
+    /// The scalar searched for in workflow state.
+    fn needle(&self) -> Value {
+        match self {
+            Self::BusinessKey(key) => Value::String(key.clone()),
+            Self::Value { value, .. } => value.clone(),
+        }
+    }
+
+    /// Redact the subject's data in `value`; returns the number of values
+    /// redacted.
+    fn redact(&self, value: &mut Value) -> usize {
+        match self {
+            Self::BusinessKey(_) => redact_all(value),
+            Self::Value { value: needle, .. } => redact_matches(value, needle),
+        }
+    }
+}
+
+/// What was erased from one execution.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionErasure {
+    pub execution_id: Uuid,
+    pub workflow_id: Uuid,
+    /// Node execution records rewritten.
+    pub node_executions: usize,
+    /// Job payloads rewritten.
+    pub jobs: usize,
+    pub values_redacted: usize,
+}
+
+/// What was erased from one workflow state entry.
+#[derive(Debug, Clone, Serialize)]
+pub struct StateErasure {
+    pub workflow_id: Uuid,
+    pub key: String,
+    pub values_redacted: usize,
+}
+
+/// Outcome of an erasure request.  Contains record IDs and counts only,
+/// never the erased data.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErasureReport {
+    /// ID of the erasure log entry; `None` for a dry run.
+    pub erasure_id: Option<Uuid>,
+    pub dry_run: bool,
+    pub executions: Vec<ExecutionErasure>,
+    /// Matching executions left untouched because of a legal hold.
+    pub skipped_legal_hold: Vec<Uuid>,
+    pub state_entries: Vec<StateErasure>,
+    pub values_redacted: usize,
+}
+
+/// Erase the data matched by `criteria`, or with `dry_run` only report
+/// what would be erased.  Executed requests are logged under `actor`.
+pub async fn erase(
+    pool: &DbPool,
+    criteria: &ErasureCriteria,
+    actor: &str,
+    dry_run: bool,
+) -> Result<ErasureReport, EngineError> {
+    let candidates = match criteria {
+        ErasureCriteria::BusinessKey(key) => {
+            privacy_repo::find_candidates_by_business_key(pool, key).await?
+        }
+        ErasureCriteria::Value { path, value } => {
+            privacy_repo::find_candidates_containing(pool, containment(path, value.clone())).await?
+        }
+    };
+
+    // Candidates with compressed or encrypted payloads are only known to
+    // match once they are decoded.
+    let document = match criteria {
+        ErasureCriteria::BusinessKey(_) => None,
+        ErasureCriteria::Value { path, value } => Some(containment(path, value.clone())),
+    };
+
+    let mut report = ErasureReport {
+        erasure_id: None,
+        dry_run,
+        executions: Vec::new(),
+        skipped_legal_hold: Vec::new(),
+        state_entries: Vec::new(),
+        values_redacted: 0,
+    };
+
+    for candidate in candidates {
+        let stored_nodes = exec_repo::list_node_executions(pool, candidate.id).await?;
+        let stored_jobs = job_repo::list_jobs_for_execution(pool, candidate.id).await?;
+        if let Some(document) = &document {
+            let matched = stored_nodes
+                .iter()
+                .any(|node| contains(&node.input, document) || node.output.as_ref().is_some_and(|o| contains(o, document)))
+                || stored_jobs.iter().any(|job| contains(&job.payload, document));
+            if !matched {
+                continue;
+            }
+        }
+
+        if candidate.legal_hold {
+            report.skipped_legal_hold.push(candidate.id);
+            continue;
+        }
+
+        let mut values_redacted = 0;
+        let mut nodes = Vec::new();
+        for mut node in stored_nodes {
+            let mut n = criteria.redact(&mut node.input);
+            if let Some(output) = node.output.as_mut() {
+                n += criteria.redact(output);
+            }
+            if n > 0 {
+                values_redacted += n;
+                nodes.push((node.id, node.input, node.output));
+            }
+        }
+
+        let mut jobs = Vec::new();
+        for mut job in stored_jobs {
+            let n = criteria.redact(&mut job.payload);
+            if n > 0 {
+                values_redacted += n;
+                jobs.push((job.id, job.payload));
+            }
+        }
+
+        let clear_business_key = matches!(criteria, ErasureCriteria::BusinessKey(_));
+        if !dry_run && (clear_business_key || values_redacted > 0) {
+            privacy_repo::redact_execution(pool, candidate.id, &nodes, &jobs, clear_business_key)
+                .await?;
+        }
+
+        report.values_redacted += values_redacted;
+        report.executions.push(ExecutionErasure {
+            execution_id: candidate.id,
+            workflow_id: candidate.workflow_id,
+            node_executions: nodes.len(),
+            jobs: jobs.len(),
+            values_redacted,
+        });
+    }
+
+    let needle = criteria.needle();
+    for mut entry in privacy_repo::find_state_containing(pool, needle.clone()).await? {
+        let Some(value) = entry.value.as_mut() else { continue };
+        let n = redact_matches(value, &needle);
+        if n == 0 {
+            continue;
+        }
+        if !dry_run {
+            privacy_repo::redact_state(pool, entry.workflow_id, &entry.key, value.clone()).await?;
+        }
+        report.values_redacted += n;
+        report.state_entries.push(StateErasure {
+            workflow_id: entry.workflow_id,
+            key: entry.key,
+            values_redacted: n,
+        });
+    }
+
+    if !dry_run {
+        let logged = serde_json::to_value(&report).unwrap_or(Value::Null);
+        let row = privacy_repo::record_erasure(pool, actor, criteria.kind(), logged).await?;
+        report.erasure_id = Some(row.id);
+        info!(
+            "erasure {} by {}: {} values in {} executions and {} state entries ({} held)",
+            row.id,
+            actor,
+            report.values_redacted,
+            report.executions.len(),
+            report.state_entries.len(),
+            report.skipped_legal_hold.len()
+        );
+    }
+
+    Ok(report)
+}
+
+/// JSONB document matching `value` at dotted `path` via `@>`.  Numeric
+/// segments become arrays, which containment matches at any position.
+fn containment(path: &str, value: Value) -> Value {
+    path.rsplit('.').fold(value, |inner, segment| {
+        if segment.parse::<usize>().is_ok() {
+            json!([inner])
+        } else {
+            json!({ segment: inner })
+        }
+    })
+}
+
+/// Whether `value` contains `document` as JSONB `@>` decides: objects hold
+/// every key of the document, with values containing its values; arrays
+/// hold, for each element of the document's, one containing it.
+fn contains(value: &Value, document: &Value) -> bool {
+    match (value, document) {
+        (Value::Object(fields), Value::Object(wanted)) => {
+            wanted.iter().all(|(key, wanted)| fields.get(key).is_some_and(|field| contains(field, wanted)))
+        }
+        (Value::Array(items), Value::Array(wanted)) => {
+            wanted.iter().all(|wanted| items.iter().any(|item| contains(item, wanted)))
+        }
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (value, document) => value == document,
+    }
+}
+
+/// Key segments (split on `_` / `-`) that mark a field as secret.
+const SECRET_KEY_SEGMENTS: [&str; 8] =
+    ["password", "passwd", "secret", "token", "authorization", "cookie", "credentials", "privatekey"];
+
+/// Whether an object key names a secret (`password`, `access_token`,
+/// `x-api-key`, `clientSecret`, …).
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    let segments: Vec<&str> = key.split(['_', '-', '.']).filter(|s| !s.is_empty()).collect();
+    segments.iter().any(|s| {
+        SECRET_KEY_SEGMENTS.contains(s) || s.ends_with("secret") || s.ends_with("password")
+    }) || segments.windows(2).any(|w| w == ["api", "key"])
+        || segments.contains(&"apikey")
+}
+
+/// Redact everything stored under secret-looking keys (see
+/// [`is_secret_key`]), at any depth; returns the number of fields redacted.
+pub fn redact_secrets(value: &mut Value) -> usize {
+    match value {
+        Value::Array(items) => items.iter_mut().map(redact_secrets).sum(),
+        Value::Object(map) => map
+            .iter_mut()
+            .map(|(key, v)| {
+                if !is_secret_key(key) {
+                    redact_secrets(v)
+                } else if v.is_null() || v == REDACTED {
+                    0
+                } else {
+                    *v = Value::String(REDACTED.into());
+                    1
+                }
+            })
+            .sum(),
+        _ => 0,
+    }
+}
+
+/// Replace every non-null leaf of `value` with [`REDACTED`].
+fn redact_all(value: &mut Value) -> usize {
+    match value {
+        Value::Null => 0,
+        Value::String(s) if s == REDACTED => 0,
+        Value::Array(items) => items.iter_mut().map(redact_all).sum(),
+        Value::Object(map) => map.values_mut().map(redact_all).sum(),
+        leaf => {
+            *leaf = Value::String(REDACTED.into());
+            1
+        }
+    }
+}
+
+/// Replace values equal to `needle` with [`REDACTED`]; a string needle is
+/// also redacted inside longer strings.
+fn redact_matches(value: &mut Value, needle: &Value) -> usize {
+    if value == needle {
+        *value = Value::String(REDACTED.into());
+        return 1;
+    }
+    match value {
+        Value::String(s) => match needle.as_str() {
+            Some(n) if !n.is_empty() && s.contains(n) => {
+                let count = s.matches(n).count();
+                *s = s.replace(n, REDACTED);
+                count
+            }
+            _ => 0,
+        },
+        Value::Array(items) => items.iter_mut().map(|v| redact_matches(v, needle)).sum(),
+        Value::Object(map) => map.values_mut().map(|v| redact_matches(v, needle)).sum(),
+        _ => 0,
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_containment_documents() {
+        assert_eq!(containment("email", json!("a@b.c")), json!({ "email": "a@b.c" }));
+        assert_eq!(
+            containment("orders.0.customer.email", json!("a@b.c")),
+            json!({ "orders": [{ "customer": { "email": "a@b.c" } }] })
+        );
+    }
+
+    #[test]
+    fn containment_matches_like_jsonb() {
+        let payload = json!({ "orders": [{ "customer": { "email": "a@b.c", "id": 42 } }, { "total": 3 }] });
+        assert!(contains(&payload, &containment("orders.0.customer.email", json!("a@b.c"))));
+        assert!(contains(&payload, &containment("orders.1.total", json!(3.0))));
+        assert!(!contains(&payload, &containment("orders.0.customer.email", json!("x@y.z"))));
+        assert!(!contains(&payload, &containment("customer.email", json!("a@b.c"))));
+    }
+
+    #[test]
+    fn redacts_matches_wherever_they_were_copied() {
+        let mut value = json!({
+            "customer": { "email": "a@b.c", "id": 42 },
+            "recipients": ["a@b.c", "x@y.z"],
+            "body": "Reply to a@b.c or a@b.c",
+        });
+        assert_eq!(redact_matches(&mut value, &json!("a@b.c")), 4);
+        assert_eq!(
+            value,
+            json!({
+                "customer": { "email": REDACTED, "id": 42 },
+                "recipients": [REDACTED, "x@y.z"],
+                "body": "Reply to [REDACTED] or [REDACTED]",
+            })
+        );
+        assert_eq!(redact_matches(&mut value, &json!(42)), 1);
+        assert_eq!(redact_matches(&mut value, &json!("a@b.c")), 0);
+    }
+
+    #[test]
+    fn redact_all_keeps_shape_and_is_idempotent() {
+        let mut value = json!({ "name": "Ada", "age": 36, "tags": ["x"], "note": null });
+        assert_eq!(redact_all(&mut value), 3);
+        assert_eq!(
+            value,
+            json!({ "name": REDACTED, "age": REDACTED, "tags": [REDACTED], "note": null })
+        );
+        assert_eq!(redact_all(&mut value), 0);
+    }
+
+    #[test]
+    fn redacts_values_under_secret_keys() {
+        let mut value = json!({
+            "url": "https://example.com",
+            "headers": { "Authorization": "Bearer x", "X-Api-Key": "k" },
+            "auth": { "clientSecret": "s", "password": null },
+            "max_tokens": 200,
+            "api_key_secret": "OPENAI_KEY",
+        });
+        assert_eq!(redact_secrets(&mut value), 4);
+        assert_eq!(value["url"], "https://example.com");
+        assert_eq!(value["headers"]["X-Api-Key"], REDACTED);
+        assert_eq!(value["auth"]["clientSecret"], REDACTED);
+        assert_eq!(value["max_tokens"], 200);
+        assert_eq!(value["api_key_secret"], REDACTED);
+    }
+
+    #[test]
+    fn rejects_unsearchable_criteria() {
+        assert!(ErasureCriteria::business_key(" ").is_none());
+        assert!(ErasureCriteria::value("email", json!("")).is_none());
+        assert!(ErasureCriteria::value("flag", json!(true)).is_none());
+        assert!(ErasureCriteria::value(".", json!("a")).is_none());
+        assert!(ErasureCriteria::value("customer.email", json!("a@b.c")).is_some());
+    }
+}
@@ -0,0 +1,106 @@
+//! Execution quotas.
+//!
+//! A workflow's [`Quota`] (in its definition) and its project's (set by an
+//! admin through the API) cap the executions started per hour and per day
+//! and the node runtime used per day.  [`check`] runs before every new
+//! execution is created — by a trigger, by hand, on a schedule, as a
+//! sub-workflow, or as a retry or replay — and refuses it with
+//! [`EngineError::QuotaExceeded`] once a limit is reached, so one runaway
+//! workflow or project cannot take the whole instance.  Triggers report
+//! that as [`Admission::OverQuota`](crate::triggers::Admission); the API
+//! answers `429`.
+//!
+//! Usage is counted when it is checked rather than reserved, so starts
+//! racing each other can go a little over a limit.  Node runtime counts as
+//! node runs are recorded, so executions already running are never cut
+//! short by it: the next start is refused instead.
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use db::DbPool;
+use db::models::QuotaUsageRow;
+use db::repository::quotas as quota_repo;
+
+use crate::{EngineError, Quota};
+
+impl Quota {
+    /// Whether any limit is set.
+    pub fn is_set(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// The first limit `usage` has reached, described; `None` while there
+    /// is room for another execution.
+    pub fn exceeded(&self, usage: &QuotaUsageRow) -> Option<String> {
+        let reached = |limit: Option<u64>, used: i64| limit.is_some_and(|limit| u64::try_from(used).unwrap_or(0) >= limit);
+        if reached(self.max_executions_per_hour.map(u64::from), usage.executions_last_hour) {
+            return Some(format!("{} executions in the last hour", usage.executions_last_hour));
+        }
+        if reached(self.max_executions_per_day.map(u64::from), usage.executions_today) {
+            return Some(format!("{} executions today", usage.executions_today));
+        }
+        if reached(self.max_node_runtime_secs_per_day.map(|secs| secs.saturating_mul(1000)), usage.node_runtime_ms_today) {
+            return Some(format!("{}s of node runtime today", usage.node_runtime_ms_today / 1000));
+        }
+        None
+    }
+}
+
+/// Refuse another execution of workflow `workflow_id` once its own `quota`
+/// or its project's is used up.
+///
+/// # Errors
+/// [`EngineError::QuotaExceeded`] naming the limit reached, and database
+/// errors.
+pub async fn check(pool: &DbPool, workflow_id: Uuid, quota: Option<&Quota>) -> Result<(), EngineError> {
+    let now = Utc::now();
+    if let Some(quota) = quota.filter(|quota| quota.is_set()) {
+        let usage = quota_repo::workflow_usage(pool, workflow_id, now).await?;
+        if let Some(reason) = quota.exceeded(&usage) {
+            return Err(EngineError::QuotaExceeded(format!("workflow {workflow_id}: {reason}")));
+        }
+    }
+    let Some((project_id, project_quota)) = quota_repo::workflow_project_quota(pool, workflow_id).await? else {
+        return Ok(());
+    };
+    let Ok(project_quota) = serde_json::from_value::<Quota>(project_quota) else {
+        return Ok(());
+    };
+    if project_quota.is_set() {
+        let usage = quota_repo::project_usage(pool, project_id, now).await?;
+        if let Some(reason) = project_quota.exceeded(&usage) {
+            return Err(EngineError::QuotaExceeded(format!("project: {reason}")));
+        }
+    }
+    Ok(())
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(executions_last_hour: i64, executions_today: i64, node_runtime_ms_today: i64) -> QuotaUsageRow {
+        QuotaUsageRow { executions_last_hour, executions_today, node_runtime_ms_today }
+    }
+
+    #[test]
+    fn the_first_limit_reached_is_reported() {
+        let quota = Quota {
+            max_executions_per_hour: Some(10),
+            max_executions_per_day: Some(50),
+            max_node_runtime_secs_per_day: Some(60),
+        };
+        assert_eq!(quota.exceeded(&usage(9, 49, 59_999)), None);
+        assert_eq!(quota.exceeded(&usage(10, 10, 0)).as_deref(), Some("10 executions in the last hour"));
+        assert_eq!(quota.exceeded(&usage(3, 50, 0)).as_deref(), Some("50 executions today"));
+        assert_eq!(quota.exceeded(&usage(3, 3, 61_500)).as_deref(), Some("61s of node runtime today"));
+
+        // Unset limits never apply.
+        assert!(!Quota::default().is_set());
+        assert_eq!(Quota::default().exceeded(&usage(i64::MAX, i64::MAX, i64::MAX)), None);
+    }
+}
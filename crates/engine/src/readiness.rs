@@ -0,0 +1,231 @@
+//! Startup readiness tracking.
+//!
+//! Expensive initialization (loading feature flags, parsing and validating
+//! stored workflow definitions, building node registries) runs in the
+//! background at startup as named [`StartupTask`]s.  [`Readiness`] reports
+//! their progress, and the API's `/readyz` stays unready until every task
+//! has finished, so a load balancer keeps traffic away until the first
+//! request no longer pays the cold-start cost.
+//!
+//! A task that fails still counts as finished — the process can serve, just
+//! without what the task would have warmed — but its error is reported.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracing::{info, warn};
+
+use db::DbPool;
+use db::repository::workflows as wf_repo;
+
+use crate::{EngineError, Workflow, validate_dag};
+
+/// Shared view of the startup tasks.
+#[derive(Debug, Clone, Default)]
+pub struct Readiness {
+    tasks: Arc<Mutex<Vec<TaskState>>>,
+}
+
+#[derive(Debug)]
+struct TaskState {
+    name: String,
+    started: Instant,
+    done: u64,
+    total: Option<u64>,
+    elapsed: Option<Duration>,
+    error: Option<String>,
+}
+
+/// Status of one startup task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Running,
+    Done,
+    Failed,
+}
+
+/// Progress of one startup task.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskReport {
+    pub name: String,
+    pub status: TaskStatus,
+    pub done: u64,
+    pub total: Option<u64>,
+    pub elapsed_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Body of `/readyz`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub tasks: Vec<TaskReport>,
+}
+
+impl Readiness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a running startup task; readiness waits for it.
+    pub fn task(&self, name: impl Into<String>) -> StartupTask {
+        let name = name.into();
+        info!("startup: {} ...", name);
+        let mut tasks = self.lock();
+        tasks.push(TaskState {
+            name,
+            started: Instant::now(),
+            done: 0,
+            total: None,
+            elapsed: None,
+            error: None,
+        });
+        StartupTask { readiness: self.clone(), index: tasks.len() - 1, finished: false }
+    }
+
+    /// Whether every registered task has finished.
+    pub fn is_ready(&self) -> bool {
+        self.lock().iter().all(|t| t.elapsed.is_some())
+    }
+
+    pub fn report(&self) -> ReadinessReport {
+        let tasks: Vec<TaskReport> = self
+            .lock()
+            .iter()
+            .map(|t| TaskReport {
+                name: t.name.clone(),
+                status: match (&t.elapsed, &t.error) {
+                    (None, _) => TaskStatus::Running,
+                    (Some(_), None) => TaskStatus::Done,
+                    (Some(_), Some(_)) => TaskStatus::Failed,
+                },
+                done: t.done,
+                total: t.total,
+                elapsed_ms: t.elapsed.unwrap_or_else(|| t.started.elapsed()).as_millis() as u64,
+                error: t.error.clone(),
+            })
+            .collect();
+        ReadinessReport { ready: tasks.iter().all(|t| t.status != TaskStatus::Running), tasks }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<TaskState>> {
+        self.tasks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Handle to a running startup task.  Dropping it unfinished marks the
+/// task failed, so a panicking task cannot block readiness forever.
+#[derive(Debug)]
+pub struct StartupTask {
+    readiness: Readiness,
+    index: usize,
+    finished: bool,
+}
+
+impl StartupTask {
+    /// Record that `done` of `total` units of work are complete.
+    pub fn progress(&self, done: u64, total: u64) {
+        let mut tasks = self.readiness.lock();
+        let task = &mut tasks[self.index];
+        task.done = done;
+        task.total = Some(total);
+    }
+
+    pub fn finish(mut self) {
+        self.complete(None);
+    }
+
+    pub fn fail(mut self, error: impl std::fmt::Display) {
+        self.complete(Some(error.to_string()));
+    }
+
+    fn complete(&mut self, error: Option<String>) {
+        self.finished = true;
+        let mut tasks = self.readiness.lock();
+        let task = &mut tasks[self.index];
+        let elapsed = task.started.elapsed();
+        task.elapsed = Some(elapsed);
+        match &error {
+            None => info!("startup: {} done in {} ms", task.name, elapsed.as_millis()),
+            Some(e) => warn!("startup: {} failed after {} ms: {}", task.name, elapsed.as_millis(), e),
+        }
+        task.error = error;
+    }
+}
+
+impl Drop for StartupTask {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.complete(Some("aborted".into()));
+        }
+    }
+}
+
+/// Parse and validate every stored workflow definition, reporting progress
+/// on `task`; returns how many are valid.  Broken definitions are logged,
+/// not fatal.
+pub async fn preload_workflows(pool: &DbPool, task: StartupTask) -> Result<usize, EngineError> {
+    let rows = match wf_repo::list_workflows(pool).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            task.fail(&e);
+            return Err(e.into());
+        }
+    };
+
+    let total = rows.len() as u64;
+    let mut valid = 0;
+    for (i, row) in rows.into_iter().enumerate() {
+        match serde_json::from_value::<Workflow>(row.definition)
+            .map_err(|e| e.to_string())
+            .and_then(|wf| validate_dag(&wf).map_err(|e| e.to_string()))
+        {
+            Ok(_) => valid += 1,
+            Err(e) => warn!("startup: workflow {} ('{}') is invalid: {}", row.id, row.name, e),
+        }
+        task.progress(i as u64 + 1, total);
+    }
+    task.finish();
+    Ok(valid)
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ready_once_every_task_finishes() {
+        let readiness = Readiness::new();
+        assert!(readiness.is_ready());
+
+        let flags = readiness.task("feature flags");
+        let workflows = readiness.task("workflow definitions");
+        workflows.progress(3, 10);
+        assert!(!readiness.is_ready());
+
+        flags.fail("connection refused");
+        assert!(!readiness.is_ready());
+        workflows.finish();
+
+        let report = readiness.report();
+        assert!(report.ready);
+        assert_eq!(report.tasks[0].status, TaskStatus::Failed);
+        assert_eq!(report.tasks[0].error.as_deref(), Some("connection refused"));
+        assert_eq!((report.tasks[1].status, report.tasks[1].done), (TaskStatus::Done, 3));
+    }
+
+    #[test]
+    fn dropped_task_does_not_block_readiness() {
+        let readiness = Readiness::new();
+        drop(readiness.task("registry"));
+        let report = readiness.report();
+        assert!(report.ready);
+        assert_eq!(report.tasks[0].error.as_deref(), Some("aborted"));
+    }
+}
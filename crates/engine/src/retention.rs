@@ -0,0 +1,210 @@
+//! Execution retention — prune finished executions that outlived their
+//! workflow's [`RetentionPolicy`], or that are more than it keeps.
+//!
+//! Each workflow's policy falls back field by field to the pruner's default
+//! policy, so an operator can set e.g. "keep successes 7 days, failures 90
+//! days, and at most 10,000 executions" once and let individual workflows
+//! override or exempt labels.
+//! Only finished executions (`succeeded`/`failed`) are ever pruned, never
+//! ones under legal hold (directly or through their workflow), and deletes
+//! run in small batches so pruning never holds long locks.  With
+//! [`Pruner::with_binary_data`], each pass also deletes the binary data of
+//! executions that are gone.  With several instances up, only the one
+//! holding the `retention` advisory lock prunes.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use tracing::{info, warn};
+
+use db::DbPool;
+use db::lock::AdvisoryLock;
+use db::repository::{executions as exec_repo, workflows as wf_repo};
+
+use crate::binary::BinaryData;
+use crate::{EngineError, RetentionPolicy, Workflow};
+
+impl RetentionPolicy {
+    /// This policy with unset fields taken from `fallback`; exempt labels
+    /// are combined.
+    pub fn or(&self, fallback: &RetentionPolicy) -> RetentionPolicy {
+        let mut keep_labels = self.keep_labels.clone();
+        for label in &fallback.keep_labels {
+            if !keep_labels.contains(label) {
+                keep_labels.push(label.clone());
+            }
+        }
+        RetentionPolicy {
+            max_age_days: self.max_age_days.or(fallback.max_age_days),
+            succeeded_days: self.succeeded_days.or(fallback.succeeded_days),
+            failed_days: self.failed_days.or(fallback.failed_days),
+            max_executions: self.max_executions.or(fallback.max_executions),
+            keep_labels,
+        }
+    }
+
+    /// Age limit in days for each prunable execution status.
+    pub fn limits(&self) -> Vec<(&'static str, u32)> {
+        [
+            ("succeeded", self.succeeded_days.or(self.max_age_days)),
+            ("failed", self.failed_days.or(self.max_age_days)),
+        ]
+        .into_iter()
+        .filter_map(|(status, days)| Some((status, days?)))
+        .collect()
+    }
+}
+
+/// Advisory lock held by the one instance running the pruner.
+pub const RETENTION_LOCK: &str = "retention";
+
+/// Deletes expired executions according to per-workflow retention policies.
+pub struct Pruner {
+    pool: DbPool,
+    default_policy: RetentionPolicy,
+    batch_size: i64,
+    binary: Option<BinaryData>,
+}
+
+impl Pruner {
+    /// Create a pruner applying `default_policy` wherever a workflow's own
+    /// policy leaves a limit unset.
+    pub fn new(pool: DbPool, default_policy: RetentionPolicy) -> Self {
+        Self { pool, default_policy, batch_size: 1000, binary: None }
+    }
+
+    /// Maximum number of executions deleted per statement.
+    pub fn with_batch_size(mut self, batch_size: i64) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Delete the blobs of deleted executions from `binary` after pruning.
+    pub fn with_binary_data(mut self, binary: BinaryData) -> Self {
+        self.binary = Some(binary);
+        self
+    }
+
+    /// Run one pruning pass over every workflow; returns the number of
+    /// executions deleted.
+    pub async fn prune_once(&self) -> Result<u64, EngineError> {
+        let now = Utc::now();
+        let mut deleted = 0;
+
+        for row in wf_repo::list_workflows(&self.pool).await? {
+            let policy = serde_json::from_value::<Workflow>(row.definition)
+                .ok()
+                .and_then(|wf| wf.retention)
+                .unwrap_or_default()
+                .or(&self.default_policy);
+
+            for (status, days) in policy.limits() {
+                let before = now - chrono::Duration::days(i64::from(days));
+                loop {
+                    let n = exec_repo::delete_expired_executions(
+                        &self.pool,
+                        row.id,
+                        status,
+                        before,
+                        &policy.keep_labels,
+                        self.batch_size,
+                    )
+                    .await?;
+                    deleted += n;
+                    if n < self.batch_size as u64 {
+                        break;
+                    }
+                }
+            }
+            if let Some(keep) = policy.max_executions {
+                loop {
+                    let n = exec_repo::delete_excess_executions(
+                        &self.pool,
+                        row.id,
+                        i64::from(keep),
+                        &policy.keep_labels,
+                        self.batch_size,
+                    )
+                    .await?;
+                    deleted += n;
+                    if n < self.batch_size as u64 {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let Some(binary) = &self.binary {
+            loop {
+                let n = binary.sweep_once(self.batch_size).await?;
+                if n < self.batch_size as u64 {
+                    break;
+                }
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    /// Prune every `interval` until the task is dropped, skipping passes
+    /// while another instance holds [`RETENTION_LOCK`].
+    pub async fn run(self, interval: Duration) {
+        let mut lock = AdvisoryLock::new(self.pool.clone(), RETENTION_LOCK);
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match lock.hold().await {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    warn!("retention: cannot take the retention lock: {}", e);
+                    continue;
+                }
+            }
+            match self.prune_once().await {
+                Ok(0) => {}
+                Ok(n) => info!("retention: pruned {} executions", n),
+                Err(e) => warn!("retention: pruning failed: {}", e),
+            }
+        }
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_limits_fall_back_to_max_age() {
+        let policy = RetentionPolicy {
+            max_age_days: Some(30),
+            succeeded_days: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(policy.limits(), vec![("succeeded", 7), ("failed", 30)]);
+        assert!(RetentionPolicy::default().limits().is_empty());
+    }
+
+    #[test]
+    fn workflow_policy_overrides_default_field_by_field() {
+        let default = RetentionPolicy {
+            succeeded_days: Some(7),
+            failed_days: Some(90),
+            keep_labels: vec!["audit".into()],
+            ..Default::default()
+        };
+        let workflow = RetentionPolicy {
+            failed_days: Some(365),
+            max_executions: Some(500),
+            keep_labels: vec!["vip".into()],
+            ..Default::default()
+        };
+        let merged = workflow.or(&default);
+        assert_eq!(merged.limits(), vec![("succeeded", 7), ("failed", 365)]);
+        assert_eq!(merged.max_executions, Some(500));
+        assert_eq!(merged.keep_labels, vec!["vip", "audit"]);
+    }
+}
@@ -0,0 +1,372 @@
+//! Retries and replays of finished executions.
+//!
+//! A retry is a new execution of the same workflow with the original's
+//! metadata (business key, labels, priority, queue, tags, attempt limit),
+//! linked to it through `retry_of`.  It either starts over from the first
+//! node with the original trigger input, or picks up at the node that
+//! failed: the node records of the original rebuild the [`Checkpoint`] the
+//! executor would have held at that point — outputs, branches taken, nodes
+//! that halted — and the retry's job resumes from it, so nodes that already
+//! succeeded are not run again.
+//!
+//! A replay does the same for any finished execution, failed or not, from
+//! the start or from a node of choice: the nodes sorted before it keep
+//! their recorded outputs and the rest run again under the workflow's
+//! current definition — the quick way to check a fix against the run that
+//! went wrong.  [`plan_replay`] says what a replay would do without
+//! queueing it.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use db::DbPool;
+use db::models::{ExecutionMeta, JobRow, NodeExecutionRow, WorkflowExecutionRow};
+use db::repository::{executions as exec_repo, jobs as job_repo, workflows as wf_repo};
+use queue::JobQueue;
+
+use crate::executor::Checkpoint;
+use crate::{quotas, validate_dag, EngineError, Workflow};
+
+/// Queue a retry of failed execution `execution_id`, from its failed node
+/// when `from_failed_node` is set and from the start otherwise.  Returns
+/// the retry's job.
+///
+/// # Errors
+/// [`EngineError::NotRetryable`] when the execution has not failed, its
+/// trigger input was not recorded, or (from the failed node) no node
+/// failed or the failed node is gone from the workflow;
+/// [`EngineError::InvalidWorkflow`] when the definition does not parse;
+/// [`EngineError::QuotaExceeded`] when the workflow's quota is used up;
+/// database and queue errors.
+pub async fn retry(
+    pool: &DbPool,
+    queue: &dyn JobQueue,
+    execution_id: Uuid,
+    from_failed_node: bool,
+) -> Result<JobRow, EngineError> {
+    let exec = exec_repo::get_execution(pool, execution_id).await?;
+    if exec.status != "failed" {
+        return Err(not_retryable(execution_id, format!("it is {}, not failed", exec.status)));
+    }
+
+    let input = trigger_input(pool, execution_id).await?;
+    let workflow = current_workflow(pool, exec.workflow_id).await?;
+    quotas::check(pool, exec.workflow_id, workflow.quota.as_ref()).await?;
+
+    let checkpoint = if from_failed_node {
+        let nodes = exec_repo::list_node_executions(pool, execution_id).await?;
+        let checkpoint = checkpoint_at_failure(&workflow, input.clone(), &nodes)
+            .map_err(|reason| not_retryable(execution_id, reason))?;
+        Some(checkpoint)
+    } else {
+        None
+    };
+
+    let retry = exec_repo::create_execution(pool, exec.workflow_id, &retry_meta(pool, &exec).await?).await?;
+    let job = enqueue(pool, retry.id, exec.workflow_id, input, checkpoint).await?;
+    queue.push(&job).await?;
+    Ok(job)
+}
+
+/// What a replay of an execution does.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayPlan {
+    pub execution_id: Uuid,
+    pub workflow_id: Uuid,
+    /// The node the replay starts at; `None` from the start.
+    pub from_node: Option<String>,
+    /// Nodes whose recorded outputs are kept instead of running them again.
+    pub reused: Vec<String>,
+    /// Nodes run again, in order — those a branch or halt does not skip.
+    pub runs: Vec<String>,
+    /// The original trigger input.
+    pub input: Value,
+}
+
+/// What replaying finished execution `execution_id` from node `from_node`
+/// (or the start) would do, with the checkpoint the replay's job resumes
+/// from — `None` from the start.
+///
+/// # Errors
+/// [`EngineError::NotRetryable`] when the execution has not finished, its
+/// trigger input was not recorded, `from_node` is not in the workflow, or a
+/// node before it failed; [`EngineError::InvalidWorkflow`] when the
+/// definition does not parse or is not a valid DAG; database errors.
+pub async fn plan_replay(
+    pool: &DbPool,
+    execution_id: Uuid,
+    from_node: Option<&str>,
+) -> Result<(ReplayPlan, Option<Checkpoint>), EngineError> {
+    let exec = exec_repo::get_execution(pool, execution_id).await?;
+    if !matches!(exec.status.as_str(), "succeeded" | "failed") {
+        return Err(not_retryable(execution_id, format!("it is {}; wait for it to finish", exec.status)));
+    }
+    let input = trigger_input(pool, execution_id).await?;
+    let workflow = current_workflow(pool, exec.workflow_id).await?;
+    let mut sorted = validate_dag(&workflow).map_err(|e| EngineError::InvalidWorkflow {
+        workflow_id: exec.workflow_id,
+        message: e.to_string(),
+    })?;
+
+    let mut plan = ReplayPlan {
+        execution_id,
+        workflow_id: exec.workflow_id,
+        from_node: from_node.map(str::to_owned),
+        reused: Vec::new(),
+        runs: sorted.clone(),
+        input: input.clone(),
+    };
+    let Some(from_node) = from_node else {
+        return Ok((plan, None));
+    };
+    let nodes = exec_repo::list_node_executions(pool, execution_id).await?;
+    let checkpoint = checkpoint_before(&sorted, from_node, input, &nodes)
+        .map_err(|reason| not_retryable(execution_id, reason))?;
+    let start = sorted.iter().position(|id| id == from_node).expect("checked by checkpoint_before");
+    plan.runs = sorted.split_off(start);
+    plan.reused = sorted.into_iter().filter(|id| checkpoint.outputs.contains_key(id)).collect();
+    Ok((plan, Some(checkpoint)))
+}
+
+/// Queue a replay of finished execution `execution_id` from node
+/// `from_node`, or from the start.  Returns what it does and its job.
+///
+/// # Errors
+/// Same as [`plan_replay`], [`EngineError::QuotaExceeded`] when the
+/// workflow's quota is used up, and queue errors.
+pub async fn replay(
+    pool: &DbPool,
+    queue: &dyn JobQueue,
+    execution_id: Uuid,
+    from_node: Option<&str>,
+) -> Result<(ReplayPlan, JobRow), EngineError> {
+    let (plan, checkpoint) = plan_replay(pool, execution_id, from_node).await?;
+    let workflow = current_workflow(pool, plan.workflow_id).await?;
+    quotas::check(pool, plan.workflow_id, workflow.quota.as_ref()).await?;
+    let exec = exec_repo::get_execution(pool, execution_id).await?;
+    let replay = exec_repo::create_execution(pool, exec.workflow_id, &retry_meta(pool, &exec).await?).await?;
+    let job = enqueue(pool, replay.id, exec.workflow_id, plan.input.clone(), checkpoint).await?;
+    queue.push(&job).await?;
+    Ok((plan, job))
+}
+
+/// The trigger input execution `execution_id` was started with, from its
+/// first job.
+async fn trigger_input(pool: &DbPool, execution_id: Uuid) -> Result<Value, EngineError> {
+    let jobs = job_repo::list_jobs_for_execution(pool, execution_id).await?;
+    jobs.first()
+        .map(|job| job.payload.clone())
+        .ok_or_else(|| not_retryable(execution_id, "its trigger input was not recorded".into()))
+}
+
+/// Queue the job of retry or replay `execution_id`: from `checkpoint` when
+/// set, from the first node otherwise.
+async fn enqueue(
+    pool: &DbPool,
+    execution_id: Uuid,
+    workflow_id: Uuid,
+    input: Value,
+    checkpoint: Option<Checkpoint>,
+) -> Result<JobRow, EngineError> {
+    let job = match checkpoint {
+        Some(checkpoint) => {
+            let checkpoint = serde_json::to_value(&checkpoint).expect("checkpoints serialize");
+            job_repo::enqueue_resume_job(pool, execution_id, workflow_id, input, checkpoint, chrono::Utc::now()).await?
+        }
+        None => job_repo::enqueue_job(pool, execution_id, workflow_id, input).await?,
+    };
+    Ok(job)
+}
+
+/// The current definition of workflow `workflow_id`.
+async fn current_workflow(pool: &DbPool, workflow_id: Uuid) -> Result<Workflow, EngineError> {
+    let row = wf_repo::get_workflow(pool, workflow_id).await?;
+    serde_json::from_value(row.definition)
+        .map_err(|e| EngineError::InvalidWorkflow { workflow_id, message: e.to_string() })
+}
+
+/// The metadata of a retry of `original`, with the parameters it ran with.
+async fn retry_meta(pool: &DbPool, original: &WorkflowExecutionRow) -> Result<ExecutionMeta, EngineError> {
+    Ok(ExecutionMeta {
+        business_key: original.business_key.clone(),
+        labels: original.labels.clone(),
+        priority: original.priority,
+        queue: Some(original.queue.clone()),
+        parent_execution_id: original.parent_execution_id,
+        required_tags: original.required_tags.clone(),
+        max_attempts: Some(original.max_attempts),
+        retry_of: Some(original.id),
+        params: exec_repo::execution_params(pool, original.id).await?,
+    })
+}
+
+/// The checkpoint of a run of `workflow` with trigger `input` just before
+/// the last failed node in `nodes` (in the order they ran).
+fn checkpoint_at_failure(workflow: &Workflow, input: Value, nodes: &[NodeExecutionRow]) -> Result<Checkpoint, String> {
+    let failed = nodes
+        .iter()
+        .rposition(|n| n.status == "failed")
+        .ok_or_else(|| "none of its nodes failed".to_owned())?;
+    let node_id = nodes[failed].node_id.clone();
+    if !workflow.nodes.iter().any(|n| n.id == node_id) {
+        return Err(format!("node '{node_id}' is no longer in the workflow"));
+    }
+
+    let mut outputs = HashMap::new();
+    let mut branches = HashMap::new();
+    let mut last_output = input.clone();
+    for node in nodes[..failed].iter().filter(|n| n.status == "succeeded") {
+        let output = node.output.clone().unwrap_or(Value::Null);
+        if let Some(branch) = &node.branch {
+            branches.insert(node.node_id.clone(), branch.clone());
+        }
+        if !node.halted {
+            outputs.insert(node.node_id.clone(), output.clone());
+        }
+        last_output = output;
+    }
+
+    Ok(Checkpoint { node_id, input, outputs, branches, last_output })
+}
+
+/// The checkpoint of a run with trigger `input` just before node `node_id`
+/// of `sorted` (a workflow's nodes in topological order), from the records
+/// of the nodes sorted before it in `nodes`.
+fn checkpoint_before(
+    sorted: &[String],
+    node_id: &str,
+    input: Value,
+    nodes: &[NodeExecutionRow],
+) -> Result<Checkpoint, String> {
+    let start = sorted
+        .iter()
+        .position(|id| id == node_id)
+        .ok_or_else(|| format!("node '{node_id}' is not in the workflow"))?;
+    let before = &sorted[..start];
+
+    let mut outputs = HashMap::new();
+    let mut branches = HashMap::new();
+    let mut last_output = input.clone();
+    for node in nodes.iter().filter(|n| before.contains(&n.node_id)) {
+        if node.status != "succeeded" {
+            return Err(format!("node '{}' before it {}; replay from that node", node.node_id, node.status));
+        }
+        let output = node.output.clone().unwrap_or(Value::Null);
+        if let Some(branch) = &node.branch {
+            branches.insert(node.node_id.clone(), branch.clone());
+        }
+        if !node.halted {
+            outputs.insert(node.node_id.clone(), output.clone());
+        }
+        last_output = output;
+    }
+
+    Ok(Checkpoint { node_id: node_id.to_owned(), input, outputs, branches, last_output })
+}
+
+fn not_retryable(execution_id: Uuid, reason: String) -> EngineError {
+    EngineError::NotRetryable { execution_id, reason }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn workflow(nodes: &[&str]) -> Workflow {
+        serde_json::from_value(json!({
+            "id": Uuid::nil(),
+            "name": "orders",
+            "trigger": { "type": "manual" },
+            "nodes": nodes.iter().map(|id| json!({ "id": id, "node_type": "noop", "config": null })).collect::<Vec<_>>(),
+            "edges": [],
+            "created_at": "2024-01-01T00:00:00Z"
+        }))
+        .unwrap()
+    }
+
+    fn node(node_id: &str, status: &str, output: Value, branch: Option<&str>, halted: bool) -> NodeExecutionRow {
+        NodeExecutionRow {
+            id: Uuid::new_v4(),
+            execution_id: Uuid::nil(),
+            node_id: node_id.into(),
+            input: json!({}),
+            output: Some(output),
+            status: status.into(),
+            started_at: chrono::Utc::now(),
+            finished_at: None,
+            input_hash: None,
+            output_hash: None,
+            error: None,
+            branch: branch.map(Into::into),
+            halted,
+        }
+    }
+
+    #[test]
+    fn checkpoint_resumes_at_the_failed_node() {
+        let nodes = [
+            node("fetch", "succeeded", json!({ "n": 1 }), None, false),
+            node("route", "succeeded", json!({ "n": 2 }), Some("big"), false),
+            node("gate", "succeeded", json!({ "n": 3 }), None, true),
+            node("send", "failed", Value::Null, None, false),
+        ];
+        let checkpoint =
+            checkpoint_at_failure(&workflow(&["fetch", "route", "gate", "send"]), json!({ "in": 0 }), &nodes).unwrap();
+
+        assert_eq!(checkpoint.node_id, "send");
+        assert_eq!(checkpoint.input, json!({ "in": 0 }));
+        assert_eq!(checkpoint.branches, HashMap::from([("route".to_owned(), "big".to_owned())]));
+        let mut done: Vec<&str> = checkpoint.outputs.keys().map(String::as_str).collect();
+        done.sort_unstable();
+        assert_eq!(done, ["fetch", "route"]);
+        assert_eq!(checkpoint.last_output, json!({ "n": 3 }));
+    }
+
+    #[test]
+    fn checkpoint_needs_a_failed_node_still_in_the_workflow() {
+        let ok = [node("fetch", "succeeded", json!({}), None, false)];
+        assert!(checkpoint_at_failure(&workflow(&["fetch"]), json!({}), &ok).is_err());
+
+        let failed = [node("old", "failed", Value::Null, None, false)];
+        assert!(checkpoint_at_failure(&workflow(&["fetch"]), json!({}), &failed).is_err());
+    }
+
+    #[test]
+    fn replays_keep_the_outputs_of_nodes_sorted_before_the_start() {
+        let sorted: Vec<String> = ["fetch", "route", "gate", "send"].map(String::from).into();
+        let nodes = [
+            node("fetch", "succeeded", json!({ "n": 1 }), None, false),
+            node("route", "succeeded", json!({ "n": 2 }), Some("big"), false),
+            node("gate", "succeeded", json!({ "n": 3 }), None, true),
+            node("send", "succeeded", json!({ "n": 4 }), None, false),
+        ];
+
+        let checkpoint = checkpoint_before(&sorted, "gate", json!({ "in": 0 }), &nodes).unwrap();
+        assert_eq!(checkpoint.node_id, "gate");
+        let mut kept: Vec<&str> = checkpoint.outputs.keys().map(String::as_str).collect();
+        kept.sort_unstable();
+        assert_eq!(kept, ["fetch", "route"]);
+        assert_eq!(checkpoint.branches, HashMap::from([("route".to_owned(), "big".to_owned())]));
+        assert_eq!(checkpoint.last_output, json!({ "n": 2 }));
+
+        let first = checkpoint_before(&sorted, "fetch", json!({ "in": 0 }), &nodes).unwrap();
+        assert!(first.outputs.is_empty());
+        assert_eq!(first.last_output, json!({ "in": 0 }));
+    }
+
+    #[test]
+    fn replays_start_at_a_known_node_after_no_failure() {
+        let sorted: Vec<String> = ["fetch", "send"].map(String::from).into();
+        let failed = [node("fetch", "failed", Value::Null, None, false)];
+        assert!(checkpoint_before(&sorted, "fetch", json!({}), &failed).is_ok());
+        assert!(checkpoint_before(&sorted, "send", json!({}), &failed).unwrap_err().contains("'fetch'"));
+        assert!(checkpoint_before(&sorted, "gone", json!({}), &[]).unwrap_err().contains("not in the workflow"));
+    }
+}
@@ -0,0 +1,307 @@
+//! Cron scheduler — fires workflows whose trigger is [`Trigger::Cron`].
+//!
+//! Each pass of [`Scheduler::run`]:
+//!
+//! 1. **Sync** — every active workflow with a cron trigger gets a row in
+//!    `cron_schedules` holding its next fire time.  A new or changed
+//!    expression starts from the next occurrence after now; an unchanged
+//!    one keeps its persisted time, which is what carries schedules across
+//!    restarts.  Schedules of deleted, deactivated, or re-triggered
+//!    workflows are dropped.
+//! 2. **Enqueue** — every run due before the end of the look-ahead window
+//!    is claimed (see [`db::repository::schedules::advance_schedule`]) and
+//!    queued as a delayed job with `run_at` set to the fire time, so
+//!    workers start it on the minute rather than on the next poll.  A run
+//!    due while the workflow's quota is used up is skipped.
+//!
+//! Runs missed while no scheduler was up are collapsed into one run as
+//! soon as the scheduler is back; the schedule then continues from the
+//! next occurrence after now.  With several instances up, only the one
+//! holding the `scheduler` advisory lock runs passes; another takes over
+//! within a pass interval when it dies.  Expressions have the standard five fields
+//! (`min hour dom month dow`), or six with leading seconds, and are
+//! evaluated in the trigger's `timezone` (default UTC), so `0 9 * * *` in
+//! `Europe/Berlin` stays at 9:00 local time across DST changes.  The job's
+//! input is `{ "scheduled_at": "<RFC 3339>" }`.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use croner::Cron;
+use serde_json::json;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use db::DbPool;
+use db::lock::AdvisoryLock;
+use db::models::CronScheduleRow;
+use db::repository::{executions as exec_repo, jobs as job_repo, schedules, workflows as wf_repo};
+use queue::{PgJobQueue, SharedQueue};
+
+use crate::subworkflow::load_workflow;
+use crate::{quotas, EngineError, Trigger, Workflow};
+
+/// A parsed cron expression and the timezone it is evaluated in.
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    expression: String,
+    cron: Cron,
+    timezone: Tz,
+}
+
+impl CronSchedule {
+    /// Parse `expression` — 5 fields, or 6 with leading seconds — to be
+    /// evaluated in the IANA `timezone` (UTC when `None`).
+    ///
+    /// # Errors
+    /// [`EngineError::InvalidCron`] when the expression or the timezone
+    /// does not parse, or the expression never fires (e.g. `0 0 30 2 *`).
+    pub fn parse(expression: &str, timezone: Option<&str>) -> Result<Self, EngineError> {
+        let invalid = |message: String| EngineError::InvalidCron { expression: expression.to_owned(), message };
+        let timezone = match timezone {
+            Some(name) => name.parse::<Tz>().map_err(|_| invalid(format!("unknown timezone '{name}'")))?,
+            None => Tz::UTC,
+        };
+        let cron = Cron::new(expression).with_seconds_optional().parse().map_err(|e| invalid(e.to_string()))?;
+        let schedule = Self { expression: expression.to_owned(), cron, timezone };
+        schedule.next_after(Utc::now())?;
+        Ok(schedule)
+    }
+
+    /// The schedule of a cron trigger; `None` for other triggers.
+    pub fn of(trigger: &Trigger) -> Option<Result<Self, EngineError>> {
+        match trigger {
+            Trigger::Cron { expression, timezone } => Some(Self::parse(expression, timezone.as_deref())),
+            _ => None,
+        }
+    }
+
+    /// Next fire time strictly after `after`.
+    ///
+    /// # Errors
+    /// [`EngineError::InvalidCron`] when the expression has no occurrence
+    /// after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Result<DateTime<Utc>, EngineError> {
+        self.cron
+            .find_next_occurrence(&after.with_timezone(&self.timezone), false)
+            .map(|next| next.with_timezone(&Utc))
+            .map_err(|e| EngineError::InvalidCron {
+                expression: self.expression.clone(),
+                message: e.to_string(),
+            })
+    }
+
+    /// Up to `count` fire times after `after`.
+    pub fn upcoming(&self, after: DateTime<Utc>, count: usize) -> Vec<DateTime<Utc>> {
+        let mut times = Vec::with_capacity(count);
+        let mut last = after;
+        while times.len() < count {
+            let Ok(next) = self.next_after(last) else { break };
+            times.push(next);
+            last = next;
+        }
+        times
+    }
+
+    /// Name of the timezone the schedule is evaluated in.
+    pub fn timezone(&self) -> &str {
+        self.timezone.name()
+    }
+
+    /// When the schedule runs after the run due at `due`.  Missed runs are
+    /// skipped: the schedule resumes from `now` if `due` is already past.
+    fn following(&self, due: DateTime<Utc>, now: DateTime<Utc>) -> Result<DateTime<Utc>, EngineError> {
+        self.next_after(due.max(now))
+    }
+}
+
+/// Advisory lock held by the one instance running scheduler passes.
+pub const SCHEDULER_LOCK: &str = "scheduler";
+
+/// Keeps `cron_schedules` in sync with the stored workflows and enqueues
+/// their runs.
+pub struct Scheduler {
+    pool: DbPool,
+    jobs: SharedQueue,
+}
+
+impl Scheduler {
+    pub fn new(pool: DbPool) -> Self {
+        Self { jobs: Arc::new(PgJobQueue::new(pool.clone())), pool }
+    }
+
+    /// Hand queued runs to `jobs`.
+    pub fn with_queue(mut self, jobs: SharedQueue) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Create, reset, or drop schedules to match the stored workflows;
+    /// returns the number of cron-triggered workflows.
+    pub async fn sync(&self) -> Result<usize, EngineError> {
+        let now = Utc::now();
+        let mut scheduled: Vec<Uuid> = Vec::new();
+
+        for row in wf_repo::list_workflows(&self.pool).await? {
+            if !row.active {
+                continue;
+            }
+            let Ok(workflow) = serde_json::from_value::<Workflow>(row.definition) else {
+                continue;
+            };
+            let Trigger::Cron { expression, .. } = &workflow.trigger else {
+                continue;
+            };
+            let found = CronSchedule::of(&workflow.trigger)
+                .expect("a cron trigger")
+                .and_then(|schedule| Ok((schedule.next_after(now)?, schedule)));
+            let (next, schedule) = match found {
+                Ok(found) => found,
+                Err(e) => {
+                    warn!("scheduler: workflow {} is not scheduled: {}", row.id, e);
+                    continue;
+                }
+            };
+            if schedules::upsert_schedule(&self.pool, row.id, expression, schedule.timezone(), next).await? {
+                info!(
+                    "scheduler: workflow {} ('{}' in {}) next runs at {}",
+                    row.id,
+                    expression,
+                    schedule.timezone(),
+                    next
+                );
+            }
+            scheduled.push(row.id);
+        }
+
+        schedules::delete_schedules_except(&self.pool, &scheduled).await?;
+        Ok(scheduled.len())
+    }
+
+    /// Enqueue every run due at or before `until`; returns how many were
+    /// enqueued.
+    pub async fn enqueue_due(&self, until: DateTime<Utc>) -> Result<usize, EngineError> {
+        let mut enqueued = 0;
+        for schedule in schedules::list_due_schedules(&self.pool, until).await? {
+            match self.enqueue(&schedule).await {
+                Ok(true) => enqueued += 1,
+                Ok(false) => {}
+                Err(e) => warn!("scheduler: cannot enqueue workflow {}: {}", schedule.workflow_id, e),
+            }
+        }
+        Ok(enqueued)
+    }
+
+    /// Claim `schedule`'s due run and queue it; `false` when another
+    /// scheduler got there first, or the workflow's quota is used up and
+    /// the run is skipped.
+    async fn enqueue(&self, schedule: &CronScheduleRow) -> Result<bool, EngineError> {
+        let due = schedule.next_run_at;
+        let next = CronSchedule::parse(&schedule.expression, Some(&schedule.timezone))?.following(due, Utc::now())?;
+        let workflow = load_workflow(&self.pool, schedule.workflow_id).await?;
+
+        // Claim before enqueueing: a crash in between loses this one run
+        // rather than letting two schedulers both start it.
+        if !schedules::advance_schedule(&self.pool, schedule.workflow_id, due, next).await? {
+            return Ok(false);
+        }
+
+        match quotas::check(&self.pool, workflow.id, workflow.quota.as_ref()).await {
+            Err(EngineError::QuotaExceeded(reason)) => {
+                warn!("scheduler: skipping the run of workflow {} due {}: over quota ({})", workflow.id, due, reason);
+                return Ok(false);
+            }
+            checked => checked?,
+        }
+
+        let payload = json!({ "scheduled_at": due });
+        let exec = exec_repo::create_execution(&self.pool, workflow.id, &workflow.execution_meta(&payload)).await?;
+        let job = job_repo::enqueue_job_at(&self.pool, exec.id, workflow.id, payload, due).await?;
+        self.jobs.push(&job).await?;
+        info!("scheduler: queued execution {} of workflow {} for {}", exec.id, workflow.id, due);
+        Ok(true)
+    }
+
+    /// Sync and enqueue every `interval` until the task is dropped.  Runs
+    /// are enqueued up to two intervals ahead, so a slow pass never makes
+    /// one late.  Passes are skipped while another instance holds
+    /// [`SCHEDULER_LOCK`].
+    pub async fn run(self, interval: Duration) {
+        let lookahead = chrono::Duration::from_std(interval * 2).unwrap_or(chrono::Duration::minutes(1));
+        let mut lock = AdvisoryLock::new(self.pool.clone(), SCHEDULER_LOCK);
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match lock.hold().await {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    warn!("scheduler: cannot take the scheduler lock: {}", e);
+                    continue;
+                }
+            }
+            if let Err(e) = self.sync().await {
+                warn!("scheduler: sync failed: {}", e);
+                continue;
+            }
+            if let Err(e) = self.enqueue_due(Utc::now() + lookahead).await {
+                warn!("scheduler: enqueueing failed: {}", e);
+            }
+        }
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn utc(expression: &str) -> CronSchedule {
+        CronSchedule::parse(expression, None).unwrap()
+    }
+
+    #[test]
+    fn finds_the_next_occurrence_of_five_and_six_field_expressions() {
+        let now = at("2024-03-04T10:07:30Z");
+        assert_eq!(utc("*/15 * * * *").next_after(now).unwrap(), at("2024-03-04T10:15:00Z"));
+        assert_eq!(utc("0 9 * * 1").next_after(now).unwrap(), at("2024-03-11T09:00:00Z"));
+        assert_eq!(utc("*/20 * * * * *").next_after(now).unwrap(), at("2024-03-04T10:07:40Z"));
+        // Strictly after: a schedule never fires twice for the same minute.
+        assert_eq!(utc("15 10 * * *").next_after(at("2024-03-04T10:15:00Z")).unwrap(), at("2024-03-05T10:15:00Z"));
+
+        for bad in ["not a cron", "", "0 0 30 2 *"] {
+            assert!(matches!(CronSchedule::parse(bad, None), Err(EngineError::InvalidCron { .. })), "{bad}");
+        }
+        let err = CronSchedule::parse("0 9 * * *", Some("Mars/Olympus")).unwrap_err();
+        assert!(err.to_string().contains("unknown timezone"));
+    }
+
+    #[test]
+    fn evaluates_in_the_trigger_timezone_across_dst() {
+        let berlin = CronSchedule::parse("0 9 * * *", Some("Europe/Berlin")).unwrap();
+        // CET (+1) before the switch on 2024-03-31, CEST (+2) after.
+        assert_eq!(
+            berlin.upcoming(at("2024-03-30T00:00:00Z"), 2),
+            [at("2024-03-30T08:00:00Z"), at("2024-03-31T07:00:00Z")]
+        );
+        assert_eq!(berlin.timezone(), "Europe/Berlin");
+    }
+
+    #[test]
+    fn missed_runs_collapse_into_one() {
+        let hourly = utc("0 * * * *");
+        // On time: the following run is the next hour.
+        let due = at("2024-03-04T10:00:00Z");
+        assert_eq!(hourly.following(due, at("2024-03-04T09:59:40Z")).unwrap(), at("2024-03-04T11:00:00Z"));
+        // Down for a day: one catch-up run, then continue from now.
+        assert_eq!(hourly.following(due, at("2024-03-05T10:30:00Z")).unwrap(), at("2024-03-05T11:00:00Z"));
+    }
+}
@@ -0,0 +1,447 @@
+//! Where workflow secrets come from at execution time.
+//!
+//! By default they are the ones stored, encrypted, in the database through
+//! the API ([`DbSecrets`]).  A deployment keeping its secrets in a vault
+//! points the engine there instead, by URL (see [`from_url`]):
+//!
+//! * `db` — the database;
+//! * `vault://mount/prefix` — HashiCorp Vault's KV v2 engine at
+//!   `VAULT_ADDR`, read with `VAULT_TOKEN` (and in `VAULT_NAMESPACE` on
+//!   Vault Enterprise);
+//! * `aws-sm://prefix` — AWS Secrets Manager, region and credentials from
+//!   the usual `AWS_*` variables (`AWS_ENDPOINT_URL` for another endpoint).
+//!
+//! In a vault, a workflow's secrets are the keys of one entry at
+//! `prefix/<workflow id>`: a KV secret, or a Secrets Manager secret whose
+//! string is a JSON object.  A workflow without one has no secrets.  They
+//! are fetched when an execution starts or resumes and never stored.
+//! Secrets in the database may differ per environment
+//! ([`DbSecrets::with_environment`]); a vault's differ by prefix, each
+//! environment's workers reading their own.
+//! Shared credentials stay in the database whichever provider is used.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{StatusCode, Url};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use db::DbPool;
+
+use crate::secrets::{self, SecretsKey};
+use crate::EngineError;
+
+/// How long a request to a vault may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A source of workflow secrets.
+#[async_trait]
+pub trait SecretProvider: Send + Sync + fmt::Debug {
+    /// The secrets of workflow `workflow_id`, by key.
+    async fn load(&self, workflow_id: Uuid) -> Result<HashMap<String, String>, EngineError>;
+}
+
+/// The provider `url` names (see the module docs).  `key` opens secrets
+/// stored in the database; it is required for `db` only.
+pub fn from_url(url: &str, pool: DbPool, key: Option<SecretsKey>) -> Result<Arc<dyn SecretProvider>, EngineError> {
+    if url == "db" {
+        let key = key.ok_or_else(|| EngineError::SecretProvider("the db secrets provider needs a secrets key".into()))?;
+        Ok(Arc::new(DbSecrets::new(pool, key)))
+    } else if let Some(location) = url.strip_prefix("vault://") {
+        let (mount, prefix) = location.split_once('/').unwrap_or((location, ""));
+        if mount.is_empty() {
+            return Err(EngineError::SecretProvider(format!("'{url}' names no KV mount; use vault://mount/prefix")));
+        }
+        Ok(Arc::new(VaultSecrets::from_env(mount, prefix)?))
+    } else if let Some(prefix) = url.strip_prefix("aws-sm://") {
+        Ok(Arc::new(AwsSecrets::from_env(prefix)?))
+    } else {
+        Err(EngineError::SecretProvider(format!(
+            "unsupported secrets provider '{url}'; use db, vault://mount/prefix, or aws-sm://prefix"
+        )))
+    }
+}
+
+/// `prefix/<workflow id>`, or the id alone without a prefix.
+fn secret_path(prefix: &str, workflow_id: Uuid) -> String {
+    match prefix.trim_matches('/') {
+        "" => workflow_id.to_string(),
+        prefix => format!("{prefix}/{workflow_id}"),
+    }
+}
+
+/// The entries of JSON object `value` as secrets; values that are not
+/// strings keep their JSON text.
+fn secrets_of(value: &Value, path: &str) -> Result<HashMap<String, String>, EngineError> {
+    let Value::Object(entries) = value else {
+        return Err(EngineError::SecretProvider(format!("secret '{path}' is not a JSON object")));
+    };
+    Ok(entries
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            (key.clone(), value)
+        })
+        .collect())
+}
+
+fn env(name: &str) -> Result<String, EngineError> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| EngineError::SecretProvider(format!("{name} is not set")))
+}
+
+fn client() -> reqwest::Client {
+    reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().expect("a default HTTP client builds")
+}
+
+fn request_error(e: reqwest::Error) -> EngineError {
+    EngineError::SecretProvider(e.to_string())
+}
+
+// ============================================================
+// Database
+// ============================================================
+
+/// Secrets stored through the API, decrypted with the secrets key.
+#[derive(Clone)]
+pub struct DbSecrets {
+    pool: DbPool,
+    key: SecretsKey,
+    environment: Option<String>,
+}
+
+impl DbSecrets {
+    pub fn new(pool: DbPool, key: SecretsKey) -> Self {
+        Self { pool, key, environment: None }
+    }
+
+    /// Read the values for `environment`, falling back to each key's
+    /// default.
+    pub fn with_environment(mut self, environment: &str) -> Self {
+        self.environment = Some(environment.to_owned());
+        self
+    }
+}
+
+impl fmt::Debug for DbSecrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DbSecrets").field("key", &self.key.id()).field("environment", &self.environment).finish()
+    }
+}
+
+#[async_trait]
+impl SecretProvider for DbSecrets {
+    async fn load(&self, workflow_id: Uuid) -> Result<HashMap<String, String>, EngineError> {
+        Ok(secrets::load(&self.pool, &self.key, workflow_id, self.environment.as_deref()).await?)
+    }
+}
+
+// ============================================================
+// HashiCorp Vault
+// ============================================================
+
+/// Secrets in a HashiCorp Vault KV version 2 engine.
+#[derive(Clone)]
+pub struct VaultSecrets {
+    client: reqwest::Client,
+    addr: String,
+    token: String,
+    namespace: Option<String>,
+    mount: String,
+    prefix: String,
+}
+
+impl VaultSecrets {
+    /// Read the KV engine mounted at `mount`, under `prefix`, of the Vault
+    /// at `addr` with `token`.
+    pub fn new(addr: &str, token: &str, mount: &str, prefix: &str) -> Self {
+        Self {
+            client: client(),
+            addr: addr.trim_end_matches('/').to_owned(),
+            token: token.to_owned(),
+            namespace: None,
+            mount: mount.trim_matches('/').to_owned(),
+            prefix: prefix.to_owned(),
+        }
+    }
+
+    /// Address requests to Vault Enterprise namespace `namespace`.
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = Some(namespace.to_owned());
+        self
+    }
+
+    /// The Vault of `VAULT_ADDR`, `VAULT_TOKEN`, and `VAULT_NAMESPACE`.
+    pub fn from_env(mount: &str, prefix: &str) -> Result<Self, EngineError> {
+        let vault = Self::new(&env("VAULT_ADDR")?, &env("VAULT_TOKEN")?, mount, prefix);
+        Ok(match env("VAULT_NAMESPACE") {
+            Ok(namespace) => vault.with_namespace(&namespace),
+            Err(_) => vault,
+        })
+    }
+}
+
+impl fmt::Debug for VaultSecrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VaultSecrets")
+            .field("addr", &self.addr)
+            .field("namespace", &self.namespace)
+            .field("mount", &self.mount)
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl SecretProvider for VaultSecrets {
+    async fn load(&self, workflow_id: Uuid) -> Result<HashMap<String, String>, EngineError> {
+        let path = secret_path(&self.prefix, workflow_id);
+        let mut request = self
+            .client
+            .get(format!("{}/v1/{}/data/{path}", self.addr, self.mount))
+            .header("X-Vault-Token", &self.token);
+        if let Some(namespace) = &self.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+        let response = request.send().await.map_err(request_error)?;
+        match response.status() {
+            StatusCode::NOT_FOUND => return Ok(HashMap::new()),
+            status if !status.is_success() => {
+                return Err(EngineError::SecretProvider(format!("Vault answered {status} reading '{path}'")))
+            }
+            _ => {}
+        }
+        let body: Value = response.json().await.map_err(request_error)?;
+        secrets_of(&body["data"]["data"], &path)
+    }
+}
+
+// ============================================================
+// AWS Secrets Manager
+// ============================================================
+
+/// Secrets in AWS Secrets Manager, read with GetSecretValue.
+#[derive(Clone)]
+pub struct AwsSecrets {
+    client: reqwest::Client,
+    endpoint: Url,
+    region: String,
+    credentials: AwsCredentials,
+    prefix: String,
+}
+
+/// An access key, and its session token for temporary credentials.
+#[derive(Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl AwsSecrets {
+    /// Read secrets under `prefix` in `region` with `credentials`.
+    pub fn new(region: &str, credentials: AwsCredentials, prefix: &str) -> Self {
+        let endpoint = format!("https://secretsmanager.{region}.amazonaws.com/");
+        Self {
+            client: client(),
+            endpoint: Url::parse(&endpoint).expect("the regional endpoint is a URL"),
+            region: region.to_owned(),
+            credentials,
+            prefix: prefix.to_owned(),
+        }
+    }
+
+    /// Send requests to `endpoint` instead of the regional one (a VPC
+    /// endpoint, or a local stand-in).
+    pub fn with_endpoint(mut self, endpoint: &str) -> Result<Self, EngineError> {
+        self.endpoint = Url::parse(endpoint)
+            .map_err(|e| EngineError::SecretProvider(format!("invalid endpoint '{endpoint}': {e}")))?;
+        Ok(self)
+    }
+
+    /// Region from `AWS_REGION` (or `AWS_DEFAULT_REGION`), credentials
+    /// from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, and
+    /// `AWS_SESSION_TOKEN`, and the endpoint from `AWS_ENDPOINT_URL` if set.
+    pub fn from_env(prefix: &str) -> Result<Self, EngineError> {
+        let region = env("AWS_REGION").or_else(|_| env("AWS_DEFAULT_REGION"))?;
+        let credentials = AwsCredentials {
+            access_key_id: env("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: env("AWS_SECRET_ACCESS_KEY")?,
+            session_token: env("AWS_SESSION_TOKEN").ok(),
+        };
+        let aws = Self::new(&region, credentials, prefix);
+        match env("AWS_ENDPOINT_URL") {
+            Ok(endpoint) => aws.with_endpoint(&endpoint),
+            Err(_) => Ok(aws),
+        }
+    }
+
+    /// The signed headers of a Secrets Manager request with `body` at `at`
+    /// (AWS Signature Version 4), `Authorization` last.
+    fn signed_headers(&self, target: &str, body: &str, at: DateTime<Utc>) -> Vec<(&'static str, String)> {
+        const SERVICE: &str = "secretsmanager";
+        let host = match self.endpoint.port() {
+            Some(port) => format!("{}:{port}", self.endpoint.host_str().unwrap_or_default()),
+            None => self.endpoint.host_str().unwrap_or_default().to_owned(),
+        };
+        let amz_date = at.format("%Y%m%dT%H%M%SZ").to_string();
+        let mut headers = vec![
+            ("content-type", "application/x-amz-json-1.1".to_owned()),
+            ("host", host),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &self.credentials.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        headers.push(("x-amz-target", target.to_owned()));
+
+        let names: Vec<&str> = headers.iter().map(|(name, _)| *name).collect();
+        let names = names.join(";");
+        let canonical_headers: String = headers.iter().map(|(name, value)| format!("{name}:{}\n", value.trim())).collect();
+        let canonical_request = format!(
+            "POST\n{}\n\n{canonical_headers}\n{names}\n{}",
+            self.endpoint.path(),
+            hex(&Sha256::digest(body.as_bytes()))
+        );
+        let date = at.format("%Y%m%d").to_string();
+        let scope = format!("{date}/{}/{SERVICE}/aws4_request", self.region);
+        let string_to_sign =
+            format!("AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}", hex(&Sha256::digest(canonical_request.as_bytes())));
+        let key = signing_key(&self.credentials.secret_access_key, &date, &self.region, SERVICE);
+        let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+        headers.push((
+            "authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={names}, Signature={signature}",
+                self.credentials.access_key_id
+            ),
+        ));
+        headers
+    }
+}
+
+impl fmt::Debug for AwsSecrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AwsSecrets")
+            .field("endpoint", &self.endpoint.as_str())
+            .field("region", &self.region)
+            .field("access_key_id", &self.credentials.access_key_id)
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl SecretProvider for AwsSecrets {
+    async fn load(&self, workflow_id: Uuid) -> Result<HashMap<String, String>, EngineError> {
+        let path = secret_path(&self.prefix, workflow_id);
+        let body = json!({ "SecretId": path }).to_string();
+        let mut request = self.client.post(self.endpoint.clone());
+        for (name, value) in self.signed_headers("secretsmanager.GetSecretValue", &body, Utc::now()) {
+            // reqwest sets Host from the URL, to the same value.
+            if name != "host" {
+                request = request.header(name, value);
+            }
+        }
+        let response = request.body(body).send().await.map_err(request_error)?;
+        let status = response.status();
+        let answer: Value = response.json().await.map_err(request_error)?;
+        if !status.is_success() {
+            let kind = answer["__type"].as_str().unwrap_or_default();
+            if kind.ends_with("ResourceNotFoundException") {
+                return Ok(HashMap::new());
+            }
+            let message = answer["message"].as_str().or(answer["Message"].as_str()).unwrap_or_default();
+            return Err(EngineError::SecretProvider(format!(
+                "Secrets Manager answered {status} reading '{path}': {kind} {message}"
+            )));
+        }
+        let Some(text) = answer["SecretString"].as_str() else {
+            return Err(EngineError::SecretProvider(format!("secret '{path}' has no string value")));
+        };
+        let value: Value = serde_json::from_str(text)
+            .map_err(|_| EngineError::SecretProvider(format!("secret '{path}' is not a JSON object")))?;
+        secrets_of(&value, &path)
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// The SigV4 key for requests to `service` in `region` on `date`.
+fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac(format!("AWS4{secret_access_key}").as_bytes(), date.as_bytes());
+    let key = hmac(&key, region.as_bytes());
+    let key = hmac(&key, service.as_bytes());
+    hmac(&key, b"aws4_request")
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signing_keys_match_the_aws_example() {
+        // From AWS's "Examples of how to derive a signing key".
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+        assert_eq!(hex(&key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+    }
+
+    #[test]
+    fn requests_are_signed_over_the_target_and_body() {
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".into(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into(),
+            session_token: None,
+        };
+        let aws = AwsSecrets::new("eu-west-1", credentials, "rusty");
+        let at = "2024-05-01T12:00:00Z".parse().unwrap();
+        let headers = aws.signed_headers("secretsmanager.GetSecretValue", r#"{"SecretId":"rusty/x"}"#, at);
+        let header = |name: &str| headers.iter().find(|(n, _)| *n == name).map(|(_, v)| v.clone()).unwrap();
+        assert_eq!(header("host"), "secretsmanager.eu-west-1.amazonaws.com");
+        assert_eq!(header("x-amz-date"), "20240501T120000Z");
+        let authorization = header("authorization");
+        assert!(authorization.starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240501/eu-west-1/secretsmanager/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date;x-amz-target, Signature="
+        ));
+
+        let other = aws.signed_headers("secretsmanager.GetSecretValue", r#"{"SecretId":"rusty/y"}"#, at);
+        assert_ne!(other.last(), headers.last());
+    }
+
+    #[test]
+    fn secrets_are_the_entries_of_an_object() {
+        let secrets = secrets_of(&json!({ "TOKEN": "t0ken", "PORT": 5432 }), "p").unwrap();
+        assert_eq!(secrets["TOKEN"], "t0ken");
+        assert_eq!(secrets["PORT"], "5432");
+        assert!(secrets_of(&json!("flat"), "p").is_err());
+
+        let id = Uuid::nil();
+        assert_eq!(secret_path("/rusty/prod/", id), format!("rusty/prod/{id}"));
+        assert_eq!(secret_path("", id), id.to_string());
+    }
+}
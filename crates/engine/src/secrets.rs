@@ -0,0 +1,516 @@
+//! Workflow secrets and shared credentials, encrypted at rest.
+//!
+//! Values are sealed with AES-256-GCM under the deployment's
+//! [`SecretsKey`] before they are stored, and opened into the execution
+//! context's `secrets` and `credentials` when a workflow runs.  Each
+//! secret is bound to its owner — its workflow, or for a project secret
+//! its project — and key, and each credential to its id, so a stored
+//! value copied to another row does not open.
+//!
+//! The stored form is `v1:<key id>:<base64>`, the base64 holding a random
+//! 96-bit nonce followed by the ciphertext.  The key id names the key a
+//! value was sealed with (see [`SecretsKey::id`]), so a value sealed under
+//! another key is told apart from a corrupted one.  Values stored before
+//! the key id was recorded — the bare base64 — still open.
+//!
+//! Keys are rotated in three steps: deploy the new key as the current one
+//! with the old one among the previous keys ([`SecretsKey::with_previous`]),
+//! so both open while values are moved; run [`rotate`] to encrypt every
+//! value again under the new key; then drop the old one.
+//!
+//! Besides its own secrets, a workflow reads its project's, shared by
+//! every workflow of the project; its own win over the project's of the
+//! same key.
+//!
+//! A key may hold a value per environment (`dev`, `staging`, `prod`) next
+//! to its default; workers read the one for the environment they run in
+//! ([`crate::WorkflowExecutor::with_environment`]), so a workflow moves
+//! between environments unchanged.
+//!
+//! Node configs reach their workflow's secrets through `{{ secrets.KEY }}`
+//! placeholders, replaced by [`interpolate`] just before the node runs, and
+//! [`redact`] keeps the values out of the node inputs the engine persists.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use nodes::credentials::{self, Credential};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::Workflow;
+use db::repository::{credentials as credential_repo, secrets as secret_repo};
+use db::{DbError, DbPool};
+
+/// Length of the nonce that precedes each ciphertext.
+const NONCE_LEN: usize = 12;
+/// Version of the stored form, its first field.
+const FORMAT: &str = "v1";
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum SecretError {
+    #[error("the secrets key must be 32 bytes, base64-encoded")]
+    InvalidKey,
+    #[error("cannot decrypt the secret: wrong key or corrupted value")]
+    Undecryptable,
+    #[error("the secret was encrypted with key {0}, not this one")]
+    OtherKey(String),
+}
+
+/// The key secrets are encrypted with, and the keys they were encrypted
+/// with before, which still open them.
+#[derive(Clone)]
+pub struct SecretsKey {
+    cipher: Arc<Aes256Gcm>,
+    id: String,
+    previous: Vec<SecretsKey>,
+}
+
+impl fmt::Debug for SecretsKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let previous: Vec<&str> = self.previous.iter().map(|key| key.id.as_str()).collect();
+        write!(f, "SecretsKey({}, previous: {previous:?})", self.id)
+    }
+}
+
+impl SecretsKey {
+    /// A key from the base64 of its 32 bytes (e.g. `openssl rand -base64 32`).
+    pub fn parse(encoded: &str) -> Result<Self, SecretError> {
+        let bytes = STANDARD.decode(encoded.trim()).map_err(|_| SecretError::InvalidKey)?;
+        let cipher = Aes256Gcm::new_from_slice(&bytes).map_err(|_| SecretError::InvalidKey)?;
+        let digest = Sha256::new_with_prefix(b"rusty-automation secrets key id\0").chain_update(&bytes).finalize();
+        let id = digest[..4].iter().map(|byte| format!("{byte:02x}")).collect();
+        Ok(Self { cipher: Arc::new(cipher), id, previous: Vec::new() })
+    }
+
+    /// This key, also opening what the `previous` keys sealed.
+    pub fn with_previous(mut self, previous: Vec<SecretsKey>) -> Self {
+        self.previous = previous.into_iter().filter(|key| key.id != self.id).collect();
+        self
+    }
+
+    /// Whether `stored` is sealed with this key rather than a previous one.
+    pub fn is_current(&self, stored: &str) -> bool {
+        let mut fields = stored.splitn(3, ':');
+        fields.next() == Some(FORMAT) && fields.next() == Some(self.id.as_str()) && fields.next().is_some()
+    }
+
+    /// The key's id, recorded with each value it seals: 8 hex digits of a
+    /// hash of the key, which says which key a value needs without giving
+    /// the key away.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Seal `value` as secret `key` of workflow or project `owner`.
+    pub fn encrypt(&self, owner: Uuid, key: &str, value: &str) -> String {
+        self.seal(&associated_data(owner, key), value)
+    }
+
+    /// Open what [`SecretsKey::encrypt`] sealed as secret `key` of
+    /// workflow or project `owner`.
+    pub fn decrypt(&self, owner: Uuid, key: &str, stored: &str) -> Result<String, SecretError> {
+        self.open(&associated_data(owner, key), stored)
+    }
+
+    /// Seal `credential` as shared credential `id`.
+    pub fn encrypt_credential(&self, id: Uuid, credential: &Credential) -> String {
+        let json = serde_json::to_string(credential).expect("a credential serializes");
+        self.seal(&credential_associated_data(id), &json)
+    }
+
+    /// Open what [`SecretsKey::encrypt_credential`] sealed as shared
+    /// credential `id`.
+    pub fn decrypt_credential(&self, id: Uuid, stored: &str) -> Result<Credential, SecretError> {
+        let json = self.open(&credential_associated_data(id), stored)?;
+        serde_json::from_str(&json).map_err(|_| SecretError::Undecryptable)
+    }
+
+    fn seal(&self, aad: &[u8], value: &str) -> String {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, Payload { msg: value.as_bytes(), aad })
+            .expect("AES-GCM encrypts any value that fits in memory");
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        format!("{FORMAT}:{}:{}", self.id, STANDARD.encode(sealed))
+    }
+
+    fn open(&self, aad: &[u8], stored: &str) -> Result<String, SecretError> {
+        match stored.split_once(':') {
+            // Stored before key ids were recorded: any key may have sealed it.
+            None => std::iter::once(self)
+                .chain(&self.previous)
+                .find_map(|key| key.open_with(aad, stored).ok())
+                .ok_or(SecretError::Undecryptable),
+            Some((FORMAT, rest)) => {
+                let (id, encoded) = rest.split_once(':').ok_or(SecretError::Undecryptable)?;
+                let key = std::iter::once(self)
+                    .chain(&self.previous)
+                    .find(|key| key.id == id)
+                    .ok_or_else(|| SecretError::OtherKey(id.to_owned()))?;
+                key.open_with(aad, encoded)
+            }
+            Some(_) => Err(SecretError::Undecryptable),
+        }
+    }
+
+    /// Open the base64 `encoded` with this key alone.
+    fn open_with(&self, aad: &[u8], encoded: &str) -> Result<String, SecretError> {
+        let sealed = STANDARD.decode(encoded).map_err(|_| SecretError::Undecryptable)?;
+        if sealed.len() < NONCE_LEN {
+            return Err(SecretError::Undecryptable);
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+            .map_err(|_| SecretError::Undecryptable)?;
+        String::from_utf8(plaintext).map_err(|_| SecretError::Undecryptable)
+    }
+}
+
+/// Node inputs and outputs and job payloads are sealed in the same stored
+/// form as secrets, so a key's previous keys open them too, and bound to
+/// their execution as secrets are to their owner and key.  [`rotate`]
+/// leaves them be: payloads are many and short-lived, so a previous key
+/// is kept until retention has removed what it sealed.
+impl db::payloads::PayloadCipher for SecretsKey {
+    fn seal(&self, execution_id: Uuid, json: &str) -> String {
+        SecretsKey::seal(self, &payload_associated_data(execution_id), json)
+    }
+
+    fn open(&self, execution_id: Uuid, sealed: &str) -> Result<String, String> {
+        SecretsKey::open(self, &payload_associated_data(execution_id), sealed).map_err(|e| e.to_string())
+    }
+}
+
+fn associated_data(owner: Uuid, key: &str) -> Vec<u8> {
+    format!("{owner}/{key}").into_bytes()
+}
+
+fn credential_associated_data(id: Uuid) -> Vec<u8> {
+    format!("credential/{id}").into_bytes()
+}
+
+fn payload_associated_data(execution_id: Uuid) -> Vec<u8> {
+    format!("execution payload/{execution_id}").into_bytes()
+}
+
+/// Prefix of the placeholder paths [`interpolate`] replaces.
+const PLACEHOLDER_PREFIX: &str = "secrets.";
+/// What [`redact`] puts in place of a secret value.
+pub const REDACTED: &str = "[redacted]";
+/// Shorter secret values are not redacted: they would blank out unrelated
+/// text, and reveal little.
+const MIN_REDACTED_LEN: usize = 4;
+
+/// `config` with every `{{ secrets.KEY }}` placeholder in its strings
+/// replaced by the value of `KEY` in `secrets`.  Other placeholders are
+/// left for the node to render.
+///
+/// # Errors
+/// The first key `config` names that is not in `secrets`.
+pub fn interpolate(config: &Value, secrets: &HashMap<String, String>) -> Result<Value, String> {
+    Ok(match config {
+        Value::String(text) if text.contains("{{") => Value::String(interpolate_str(text, secrets)?),
+        Value::Array(items) => Value::Array(items.iter().map(|item| interpolate(item, secrets)).collect::<Result<_, _>>()?),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, field)| Ok((name.clone(), interpolate(field, secrets)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+fn interpolate_str(text: &str, secrets: &HashMap<String, String>) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}").map(|end| start + end + 2) else { break };
+        let placeholder = &rest[start..end];
+        out.push_str(&rest[..start]);
+        match placeholder[2..placeholder.len() - 2].trim().strip_prefix(PLACEHOLDER_PREFIX) {
+            Some(key) => out.push_str(secrets.get(key).ok_or_else(|| key.to_owned())?),
+            None => out.push_str(placeholder),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// `input` with every value in `secrets` (of at least four characters)
+/// replaced by [`REDACTED`] wherever it appears in a string, for
+/// persisting; `input` itself when none appears.
+pub fn redact(input: &Arc<Value>, secrets: &HashMap<String, String>) -> Arc<Value> {
+    let values: Vec<&str> =
+        secrets.values().map(String::as_str).filter(|value| value.chars().count() >= MIN_REDACTED_LEN).collect();
+    if values.is_empty() || !contains_any(input, &values) {
+        return input.clone();
+    }
+    Arc::new(redact_value(input, &values))
+}
+
+fn contains_any(value: &Value, secrets: &[&str]) -> bool {
+    match value {
+        Value::String(text) => secrets.iter().any(|secret| text.contains(secret)),
+        Value::Array(items) => items.iter().any(|item| contains_any(item, secrets)),
+        Value::Object(fields) => fields.values().any(|field| contains_any(field, secrets)),
+        _ => false,
+    }
+}
+
+fn redact_value(value: &Value, secrets: &[&str]) -> Value {
+    match value {
+        Value::String(text) => {
+            Value::String(secrets.iter().fold(text.clone(), |text, secret| text.replace(secret, REDACTED)))
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| redact_value(item, secrets)).collect()),
+        Value::Object(fields) => {
+            Value::Object(fields.iter().map(|(name, field)| (name.clone(), redact_value(field, secrets))).collect())
+        }
+        other => other.clone(),
+    }
+}
+
+/// What [`rotate`] did, or with `dry_run` would do.
+#[derive(Debug, Default, Serialize)]
+pub struct Rotation {
+    /// Values encrypted again under the current key.
+    pub rotated: usize,
+    /// Values already under it.
+    pub current: usize,
+    /// Values written by someone else while rotating; run it again.
+    pub changed: usize,
+    /// Values no key opens, e.g. `secret 'TOKEN' of workflow …`.
+    pub unreadable: Vec<String>,
+}
+
+/// Encrypt every stored secret and credential that is not under `key`'s
+/// current key again under it, opening them with its previous keys; with
+/// `dry_run` only count them.  Values no key opens are left as they are
+/// and listed.  Safe to run again, and alongside servers and workers.
+pub async fn rotate(pool: &DbPool, key: &SecretsKey, dry_run: bool) -> Result<Rotation, DbError> {
+    let mut rotation = Rotation::default();
+    for row in secret_repo::list_all_secrets(pool).await? {
+        if key.is_current(&row.encrypted_value) {
+            rotation.current += 1;
+            continue;
+        }
+        let Ok(value) = key.decrypt(row.owner_id(), &row.key, &row.encrypted_value) else {
+            let owner = match row.workflow_id {
+                Some(workflow_id) => format!("workflow {workflow_id}"),
+                None => format!("project {}", row.project_id),
+            };
+            rotation.unreadable.push(format!("secret '{}' of {owner}", row.key));
+            continue;
+        };
+        if dry_run {
+            rotation.rotated += 1;
+            continue;
+        }
+        let sealed = key.encrypt(row.owner_id(), &row.key, &value);
+        match secret_repo::reencrypt_secret(pool, row.id, &row.encrypted_value, &sealed).await? {
+            true => rotation.rotated += 1,
+            false => rotation.changed += 1,
+        }
+    }
+    for row in credential_repo::list_all_credentials(pool).await? {
+        if key.is_current(&row.encrypted_data) {
+            rotation.current += 1;
+            continue;
+        }
+        let Ok(credential) = key.decrypt_credential(row.id, &row.encrypted_data) else {
+            rotation.unreadable.push(format!("credential '{}' ({})", row.name, row.id));
+            continue;
+        };
+        if dry_run {
+            rotation.rotated += 1;
+            continue;
+        }
+        let sealed = key.encrypt_credential(row.id, &credential);
+        match credential_repo::reencrypt_credential(pool, row.id, &row.encrypted_data, &sealed).await? {
+            true => rotation.rotated += 1,
+            false => rotation.changed += 1,
+        }
+    }
+    Ok(rotation)
+}
+
+/// The decrypted secrets of workflow `workflow_id` in `environment`: for
+/// each key the workflow's own value, or else its project's, for that
+/// environment or else the default.  Secrets that do not
+/// open under `key` are left out (and logged), so only the nodes reading
+/// them fail.
+pub async fn load(
+    pool: &DbPool,
+    key: &SecretsKey,
+    workflow_id: Uuid,
+    environment: Option<&str>,
+) -> Result<HashMap<String, String>, DbError> {
+    let mut secrets = HashMap::new();
+    for row in secret_repo::secrets_for_environment(pool, workflow_id, environment).await? {
+        match key.decrypt(row.owner_id(), &row.key, &row.encrypted_value) {
+            Ok(value) => {
+                secrets.insert(row.key, value);
+            }
+            Err(e) => error!("secret '{}' of workflow {}: {}", row.key, workflow_id, e),
+        }
+    }
+    Ok(secrets)
+}
+
+/// The decrypted shared credentials `workflow`'s nodes name, by id.  Only
+/// credentials of the workflow's project are loaded; those that do not
+/// open under `key` are left out (and logged), so only the nodes using
+/// them fail.
+pub async fn load_credentials(
+    pool: &DbPool,
+    key: &SecretsKey,
+    workflow: &Workflow,
+) -> Result<HashMap<Uuid, Credential>, DbError> {
+    let mut ids: Vec<Uuid> =
+        workflow.nodes.iter().filter_map(|node| credentials::credential_id(&node.config)).collect();
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    ids.sort();
+    ids.dedup();
+    let mut loaded = HashMap::new();
+    for row in credential_repo::credentials_for_workflow(pool, workflow.id, &ids).await? {
+        match key.decrypt_credential(row.id, &row.encrypted_data) {
+            Ok(credential) => {
+                loaded.insert(row.id, credential);
+            }
+            Err(e) => error!("credential '{}' ({}): {}", row.name, row.id, e),
+        }
+    }
+    Ok(loaded)
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=";
+
+    #[test]
+    fn values_open_only_with_their_key_workflow_and_name() {
+        let key = SecretsKey::parse(KEY).unwrap();
+        let workflow = Uuid::new_v4();
+        let sealed = key.encrypt(workflow, "JIRA_TOKEN", "t0ken");
+        assert!(!sealed.contains("t0ken"));
+        assert_ne!(sealed, key.encrypt(workflow, "JIRA_TOKEN", "t0ken"));
+        assert_eq!(key.decrypt(workflow, "JIRA_TOKEN", &sealed).as_deref(), Ok("t0ken"));
+
+        assert_eq!(key.decrypt(workflow, "OTHER", &sealed), Err(SecretError::Undecryptable));
+        assert_eq!(key.decrypt(Uuid::new_v4(), "JIRA_TOKEN", &sealed), Err(SecretError::Undecryptable));
+        let other = SecretsKey::parse(&STANDARD.encode([7u8; 32])).unwrap();
+        assert_eq!(other.decrypt(workflow, "JIRA_TOKEN", &sealed), Err(SecretError::OtherKey(key.id().to_owned())));
+        assert_eq!(key.decrypt(workflow, "JIRA_TOKEN", "AAAA"), Err(SecretError::Undecryptable));
+
+        let id = Uuid::new_v4();
+        let credential = Credential::ApiToken { token: "t0ken".into() };
+        let sealed = key.encrypt_credential(id, &credential);
+        assert!(!sealed.contains("t0ken"));
+        assert_eq!(key.decrypt_credential(id, &sealed), Ok(credential));
+        assert_eq!(key.decrypt_credential(Uuid::new_v4(), &sealed), Err(SecretError::Undecryptable));
+
+        use db::payloads::PayloadCipher;
+        let execution = Uuid::new_v4();
+        let sealed = PayloadCipher::seal(&key, execution, r#"{"email":"a@b.c"}"#);
+        assert!(!sealed.contains("a@b.c"));
+        assert_eq!(PayloadCipher::open(&key, execution, &sealed).as_deref(), Ok(r#"{"email":"a@b.c"}"#));
+        assert!(PayloadCipher::open(&key, Uuid::new_v4(), &sealed).is_err());
+        assert!(key.decrypt(workflow, "JIRA_TOKEN", &sealed).is_err());
+
+        assert_eq!(SecretsKey::parse("c2hvcnQ=").unwrap_err(), SecretError::InvalidKey);
+        assert_eq!(SecretsKey::parse("not base64!").unwrap_err(), SecretError::InvalidKey);
+    }
+
+    #[test]
+    fn stored_values_name_their_key_and_unversioned_ones_still_open() {
+        let key = SecretsKey::parse(KEY).unwrap();
+        assert_eq!(key.id().len(), 8);
+        assert_eq!(key.id(), SecretsKey::parse(&format!(" {KEY}\n")).unwrap().id());
+        assert_ne!(key.id(), SecretsKey::parse(&STANDARD.encode([7u8; 32])).unwrap().id());
+
+        let workflow = Uuid::new_v4();
+        let sealed = key.encrypt(workflow, "TOKEN", "t0ken");
+        let prefix = format!("v1:{}:", key.id());
+        assert!(sealed.starts_with(&prefix), "{sealed}");
+
+        let unversioned = sealed.strip_prefix(&prefix).unwrap();
+        assert_eq!(key.decrypt(workflow, "TOKEN", unversioned).as_deref(), Ok("t0ken"));
+        let tampered = format!("v2:{}:{unversioned}", key.id());
+        assert_eq!(key.decrypt(workflow, "TOKEN", &tampered), Err(SecretError::Undecryptable));
+    }
+
+    #[test]
+    fn secret_placeholders_are_replaced_and_others_kept() {
+        let secrets = HashMap::from([("TOKEN".to_owned(), "t0ken".to_owned())]);
+        let config = serde_json::json!({
+            "url": "https://api.example.com?key={{secrets.TOKEN}}",
+            "headers": [{ "authorization": "Bearer {{ secrets.TOKEN }}" }],
+            "body": "{{ input.id }}",
+            "retries": 3
+        });
+        assert_eq!(
+            interpolate(&config, &secrets).unwrap(),
+            serde_json::json!({
+                "url": "https://api.example.com?key=t0ken",
+                "headers": [{ "authorization": "Bearer t0ken" }],
+                "body": "{{ input.id }}",
+                "retries": 3
+            })
+        );
+        assert_eq!(interpolate(&serde_json::json!("{{ secrets.OTHER }}"), &secrets).unwrap_err(), "OTHER");
+    }
+
+    #[test]
+    fn secret_values_are_redacted_from_inputs() {
+        let secrets = HashMap::from([("TOKEN".to_owned(), "t0ken".to_owned()), ("PIN".to_owned(), "42".to_owned())]);
+        let input = Arc::new(serde_json::json!({ "auth": "Bearer t0ken", "items": ["t0ken", 42], "pin": "42" }));
+        assert_eq!(
+            *redact(&input, &secrets),
+            serde_json::json!({ "auth": "Bearer [redacted]", "items": ["[redacted]", 42], "pin": "42" })
+        );
+
+        let clean = Arc::new(serde_json::json!({ "id": 7 }));
+        assert!(Arc::ptr_eq(&redact(&clean, &secrets), &clean));
+    }
+
+    #[test]
+    fn previous_keys_still_open_what_they_sealed() {
+        let old = SecretsKey::parse(&STANDARD.encode([1u8; 32])).unwrap();
+        let new = SecretsKey::parse(&STANDARD.encode([2u8; 32])).unwrap();
+        let workflow = Uuid::new_v4();
+        let sealed = old.encrypt(workflow, "TOKEN", "t0ken");
+        let unversioned = sealed.rsplit(':').next().unwrap();
+        assert_eq!(new.decrypt(workflow, "TOKEN", &sealed), Err(SecretError::OtherKey(old.id().to_owned())));
+
+        let ring = new.clone().with_previous(vec![old.clone(), new.clone()]);
+        assert_eq!(ring.decrypt(workflow, "TOKEN", &sealed).as_deref(), Ok("t0ken"));
+        assert_eq!(ring.decrypt(workflow, "TOKEN", unversioned).as_deref(), Ok("t0ken"));
+        assert!(!ring.is_current(&sealed));
+        let resealed = ring.encrypt(workflow, "TOKEN", "t0ken");
+        assert!(ring.is_current(&resealed) && !old.is_current(&resealed));
+        assert_eq!(new.decrypt(workflow, "TOKEN", &resealed).as_deref(), Ok("t0ken"));
+        assert!(!ring.is_current(unversioned));
+    }
+}
@@ -0,0 +1,316 @@
+//! Deterministic simulation harness.
+//!
+//! A [`Simulation`] runs a test body on a single-threaded tokio runtime
+//! whose clock starts paused: every `tokio::time::sleep` (retry back-off,
+//! chaos latency, …) completes instantly by jumping virtual time forward,
+//! in a reproducible order.  On top of that it provides:
+//!
+//! * [`SimClock`] — wall-clock time derived from the virtual clock, handed
+//!   to nodes through [`ExecutionContext::now`], so durable delays
+//!   (`time_gate`, `batch_collect` ages) can be driven forward with
+//!   [`SimContext::advance_to`] instead of waiting;
+//! * [`SeededRandom`] — node randomness (e.g. `split_ab`) from the
+//!   simulation seed;
+//! * [`SimContext::runner`] and [`SimContext::chaos`] — a database-free
+//!   [`NodeRunner`] with the engine's retry policy, and chaos injection
+//!   seeded from the same seed.
+//!
+//! The same seed always replays the same run.  Available in the engine's
+//! own tests and, for other crates, behind the `simulation` feature.
+//!
+//! ```ignore
+//! Simulation::new(7).run(|sim| async move {
+//!     let ctx = sim.execution_context(json!({}));
+//!     let output = sim.runner(ExecutorConfig::default()).run("a", &node, &json!({}), &ctx).await;
+//!     assert_eq!(sim.elapsed(), Duration::from_millis(700));
+//! });
+//! ```
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use serde_json::Value;
+use uuid::Uuid;
+
+use nodes::traits::{Clock, ExecutionContext, RandomSource};
+
+use crate::chaos::ChaosConfig;
+use crate::executor::{ExecutorConfig, NodeRunner};
+
+/// A reproducible run configuration: a seed and a virtual start time.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    seed: u64,
+    epoch: DateTime<Utc>,
+}
+
+impl Simulation {
+    /// A simulation starting at 2024-01-01T00:00:00Z.
+    pub fn new(seed: u64) -> Self {
+        let epoch = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).single().unwrap_or_default();
+        Self { seed, epoch }
+    }
+
+    /// Start the virtual wall clock at `epoch` instead.
+    pub fn starting_at(mut self, epoch: DateTime<Utc>) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    /// Run `body` on a fresh paused-clock runtime and return its result.
+    ///
+    /// # Panics
+    /// If the runtime cannot be built.
+    pub fn run<F, Fut>(&self, body: F) -> Fut::Output
+    where
+        F: FnOnce(SimContext) -> Fut,
+        Fut: Future,
+    {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .expect("failed to build simulation runtime");
+        runtime.block_on(async {
+            let sim = SimContext {
+                seed: self.seed,
+                clock: Arc::new(SimClock { epoch: self.epoch, start: tokio::time::Instant::now() }),
+                random: Arc::new(SeededRandom::new(self.seed)),
+            };
+            body(sim).await
+        })
+    }
+}
+
+/// Handle to the running simulation's clock and randomness.
+#[derive(Debug, Clone)]
+pub struct SimContext {
+    seed: u64,
+    clock: Arc<SimClock>,
+    random: Arc<SeededRandom>,
+}
+
+impl SimContext {
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn clock(&self) -> Arc<SimClock> {
+        self.clock.clone()
+    }
+
+    pub fn random(&self) -> Arc<SeededRandom> {
+        self.random.clone()
+    }
+
+    /// Current virtual wall-clock time.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// Virtual time elapsed since the simulation started.
+    pub fn elapsed(&self) -> Duration {
+        self.clock.start.elapsed()
+    }
+
+    /// Let `duration` of virtual time pass (instantly in real time).
+    pub async fn advance(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+
+    /// Let virtual time pass until `time`; a no-op if it is in the past.
+    pub async fn advance_to(&self, time: DateTime<Utc>) {
+        if let Ok(wait) = (time - self.now()).to_std() {
+            self.advance(wait).await;
+        }
+    }
+
+    /// An execution context wired to the simulation's clock and randomness.
+    pub fn execution_context(&self, input: Value) -> ExecutionContext {
+        ExecutionContext::new(Uuid::nil(), Uuid::nil(), input)
+            .with_clock(self.clock.clone())
+            .with_random(self.random.clone())
+    }
+
+    /// A node runner with the engine's retry policy.
+    pub fn runner(&self, config: ExecutorConfig) -> NodeRunner {
+        NodeRunner::new(config)
+    }
+
+    /// `config` with its seed derived from the simulation seed.
+    pub fn chaos(&self, config: ChaosConfig) -> ChaosConfig {
+        ChaosConfig { seed: Some(self.seed ^ 0x9e37_79b9_7f4a_7c15), ..config }
+    }
+}
+
+/// Wall-clock time that follows tokio's (paused) virtual clock.
+#[derive(Debug)]
+pub struct SimClock {
+    epoch: DateTime<Utc>,
+    start: tokio::time::Instant,
+}
+
+impl Clock for SimClock {
+    fn now(&self) -> DateTime<Utc> {
+        let elapsed = chrono::Duration::from_std(self.start.elapsed()).unwrap_or_default();
+        self.epoch + elapsed
+    }
+}
+
+/// Randomness from a fixed seed.
+#[derive(Debug)]
+pub struct SeededRandom(Mutex<StdRng>);
+
+impl SeededRandom {
+    pub fn new(seed: u64) -> Self {
+        Self(Mutex::new(StdRng::seed_from_u64(seed)))
+    }
+}
+
+impl RandomSource for SeededRandom {
+    fn next_u64(&self) -> u64 {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).next_u64()
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EngineError;
+    use async_trait::async_trait;
+    use nodes::traits::Flow;
+    use nodes::{ExecutableNode, NodeError};
+    use serde_json::json;
+
+    /// Fails with a retryable error `failures` times, recording when each
+    /// attempt happened.
+    #[derive(Debug, Default)]
+    struct Flaky {
+        failures: usize,
+        attempts: Mutex<Vec<DateTime<Utc>>>,
+    }
+
+    #[async_trait]
+    impl ExecutableNode for Flaky {
+        async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+            let mut attempts = self.attempts.lock().unwrap();
+            attempts.push(ctx.now());
+            if attempts.len() <= self.failures {
+                return Err(NodeError::Retryable("flaky".into()));
+            }
+            Ok(input)
+        }
+    }
+
+    #[test]
+    fn retry_backoff_runs_on_virtual_time() {
+        let started = std::time::Instant::now();
+        let sim = Simulation::new(1);
+        let (attempts, elapsed) = sim.run(|sim| async move {
+            let flaky = Arc::new(Flaky { failures: 3, ..Default::default() });
+            let node: Arc<dyn ExecutableNode> = flaky.clone();
+            let config = ExecutorConfig { max_retries: 3, retry_base_delay: Duration::from_secs(10) };
+            let ctx = sim.execution_context(json!({}));
+            sim.runner(config).run("flaky", &node, &json!({ "ok": true }), &ctx).await.unwrap();
+            let attempts = flaky.attempts.lock().unwrap().clone();
+            (attempts, sim.elapsed())
+        });
+
+        let offsets: Vec<i64> = attempts.iter().map(|t| (*t - attempts[0]).num_seconds()).collect();
+        assert_eq!(offsets, vec![0, 10, 30, 70]);
+        assert_eq!(elapsed, Duration::from_secs(70));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn seeded_chaos_replays_identically() {
+        let run = |seed| {
+            Simulation::new(seed).run(|sim| async move {
+                let chaos = sim.chaos(ChaosConfig {
+                    failure_rate: 0.4,
+                    latency_rate: 0.5,
+                    ..ChaosConfig::default()
+                });
+                let config = ExecutorConfig { max_retries: 5, retry_base_delay: Duration::from_millis(100) };
+                let runner = sim.runner(config).with_chaos(chaos);
+                let ctx = sim.execution_context(json!({}));
+                let node: Arc<dyn ExecutableNode> = Arc::new(Flaky::default());
+                let mut outcomes = Vec::new();
+                for i in 0..20 {
+                    let result = runner.run(&format!("n{i}"), &node, &json!(i), &ctx).await;
+                    outcomes.push((result.is_ok(), sim.elapsed()));
+                }
+                outcomes
+            })
+        };
+        assert_eq!(run(11), run(11));
+        assert_ne!(run(11), run(12));
+    }
+
+    #[test]
+    fn durable_delay_is_driven_by_the_virtual_clock() {
+        use nodes::builtin::time_gate::TimeGateNode;
+
+        // Saturday; the gate opens Monday 09:00 UTC.
+        let saturday = Utc.with_ymd_and_hms(2024, 3, 2, 12, 0, 0).unwrap();
+        Simulation::new(3).starting_at(saturday).run(|sim| async move {
+            let config = json!({ "days": ["mon", "tue", "wed", "thu", "fri"], "start": "09:00", "end": "17:00" });
+            let ctx = sim.execution_context(json!({})).for_node("gate", config);
+
+            let out = TimeGateNode.execute(json!({ "id": 1 }), &ctx).await.unwrap();
+            let Flow::Defer(until) = ctx.take_flow() else { panic!("gate should defer") };
+            assert_eq!(until, Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap());
+            assert_eq!(out["open"], false);
+
+            sim.advance_to(until).await;
+            let out = TimeGateNode.execute(json!({ "id": 1 }), &ctx).await.unwrap();
+            assert_eq!(ctx.take_flow(), Flow::Continue);
+            assert_eq!(out, json!({ "id": 1 }));
+            assert_eq!(sim.elapsed(), Duration::from_secs(45 * 3600));
+        });
+    }
+
+    #[test]
+    fn node_randomness_follows_the_seed() {
+        use nodes::builtin::split_ab::SplitAbNode;
+
+        let branches = |seed| {
+            Simulation::new(seed).run(|sim| async move {
+                let config = json!({ "branches": [{ "name": "a", "weight": 1 }, { "name": "b", "weight": 1 }] });
+                let ctx = sim.execution_context(json!({})).for_node("split", config);
+                let mut picked = String::new();
+                for _ in 0..32 {
+                    SplitAbNode.execute(json!({}), &ctx).await.unwrap();
+                    match ctx.take_flow() {
+                        Flow::Branch(b) => picked.push_str(&b),
+                        other => panic!("unexpected flow {other:?}"),
+                    }
+                }
+                picked
+            })
+        };
+        assert_eq!(branches(5), branches(5));
+        assert_ne!(branches(5), branches(6));
+    }
+
+    #[test]
+    fn crash_is_reported_without_running_the_node() {
+        Simulation::new(9).run(|sim| async move {
+            let chaos = sim.chaos(ChaosConfig { crash_rate: 1.0, ..ChaosConfig::default() });
+            let runner = sim.runner(ExecutorConfig::default()).with_chaos(chaos);
+            let flaky = Arc::new(Flaky::default());
+            let node: Arc<dyn ExecutableNode> = flaky.clone();
+            let ctx = sim.execution_context(json!({}));
+            let err = runner.run("n", &node, &json!({}), &ctx).await.unwrap_err();
+            assert!(matches!(err, EngineError::InjectedCrash { .. }));
+            assert!(flaky.attempts.lock().unwrap().is_empty());
+        });
+    }
+}
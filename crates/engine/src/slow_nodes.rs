@@ -0,0 +1,155 @@
+//! Slow-node detection.
+//!
+//! A node's baseline is the 95th percentile of how long its successful
+//! runs in the same workflow took over a rolling window (7 days by
+//! default).  Once the baseline rests on enough runs, a successful run
+//! taking more than `factor` times it is slow: the executor logs a warning,
+//! counts it in `rusty_automation_slow_nodes_total`, and, for workflows
+//! with `slow_node_alerts`, starts the error workflow with a `slow` failure.
+//! A downstream API that is degrading shows up this way well before the
+//! workflows calling it start timing out.
+//!
+//! Baselines are read from `node_executions` and cached per worker for a
+//! few minutes, so a hot node costs one query per refresh, not per run.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use db::{DbError, DbPool};
+
+/// When a node counts as slow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlowNodeConfig {
+    /// A run is slow once it takes more than this many times the baseline.
+    pub factor: f64,
+    /// Runs a baseline needs before nodes are judged against it.
+    pub min_samples: i64,
+    /// How far back the runs of a baseline go.
+    pub window: Duration,
+    /// How long a baseline is reused before it is read again.
+    pub refresh: Duration,
+}
+
+impl Default for SlowNodeConfig {
+    fn default() -> Self {
+        Self {
+            factor: 2.0,
+            min_samples: 20,
+            window: Duration::from_secs(7 * 24 * 3600),
+            refresh: Duration::from_secs(300),
+        }
+    }
+}
+
+/// The historical duration of a node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Baseline {
+    pub p95_ms: f64,
+    pub samples: i64,
+}
+
+/// A run that took longer than its baseline allows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlowNode {
+    pub took_ms: i64,
+    pub baseline: Baseline,
+}
+
+impl SlowNode {
+    /// What happened to node `node_id`, for logs and error workflows.
+    pub fn message(&self, node_id: &str) -> String {
+        format!(
+            "node '{node_id}' took {}ms, {:.1}x its p95 of {:.0}ms over {} runs",
+            self.took_ms,
+            self.took_ms as f64 / self.baseline.p95_ms,
+            self.baseline.p95_ms,
+            self.baseline.samples
+        )
+    }
+}
+
+#[derive(Debug)]
+struct Cached {
+    read_at: Instant,
+    baseline: Option<Baseline>,
+}
+
+/// Judges node runs against their baselines.
+#[derive(Debug)]
+pub struct SlowNodeDetector {
+    config: SlowNodeConfig,
+    baselines: Mutex<HashMap<(Uuid, String), Cached>>,
+}
+
+impl SlowNodeDetector {
+    pub fn new(config: SlowNodeConfig) -> Self {
+        Self { config, baselines: Mutex::new(HashMap::new()) }
+    }
+
+    /// Whether a successful run of node `node_id` of workflow `workflow_id`
+    /// taking `took` was slow.
+    ///
+    /// # Errors
+    /// Database errors reading the baseline.
+    pub async fn check(
+        &self,
+        pool: &DbPool,
+        workflow_id: Uuid,
+        node_id: &str,
+        took: Duration,
+    ) -> Result<Option<SlowNode>, DbError> {
+        let key = (workflow_id, node_id.to_owned());
+        let cached = self
+            .baselines
+            .lock()
+            .unwrap()
+            .get(&key)
+            .filter(|cached| cached.read_at.elapsed() < self.config.refresh)
+            .map(|cached| cached.baseline);
+        let baseline = match cached {
+            Some(baseline) => baseline,
+            None => {
+                let since = chrono::Utc::now()
+                    - chrono::Duration::from_std(self.config.window).unwrap_or(chrono::Duration::MAX);
+                let row = db::repository::executions::node_duration_p95(pool, workflow_id, node_id, since).await?;
+                let baseline = row.p95_ms.map(|p95_ms| Baseline { p95_ms, samples: row.samples });
+                self.baselines.lock().unwrap().insert(key, Cached { read_at: Instant::now(), baseline });
+                baseline
+            }
+        };
+        Ok(self.judge(baseline, took))
+    }
+
+    /// Whether `took` is slow against `baseline`.
+    fn judge(&self, baseline: Option<Baseline>, took: Duration) -> Option<SlowNode> {
+        let baseline = baseline.filter(|b| b.samples >= self.config.min_samples && b.p95_ms > 0.0)?;
+        let took_ms = took.as_millis() as i64;
+        (took_ms as f64 > baseline.p95_ms * self.config.factor).then_some(SlowNode { took_ms, baseline })
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_are_slow_past_the_factor_once_the_baseline_has_enough_samples() {
+        let detector = SlowNodeDetector::new(SlowNodeConfig { factor: 2.0, min_samples: 20, ..Default::default() });
+        let baseline = Baseline { p95_ms: 400.0, samples: 50 };
+
+        assert_eq!(detector.judge(Some(baseline), Duration::from_millis(800)), None);
+        let slow = detector.judge(Some(baseline), Duration::from_millis(1_000)).expect("slow");
+        assert_eq!(slow.took_ms, 1_000);
+        assert_eq!(slow.message("fetch"), "node 'fetch' took 1000ms, 2.5x its p95 of 400ms over 50 runs");
+
+        let young = Baseline { samples: 19, ..baseline };
+        assert_eq!(detector.judge(Some(young), Duration::from_secs(60)), None);
+        assert_eq!(detector.judge(None, Duration::from_secs(60)), None);
+    }
+}
@@ -0,0 +1,70 @@
+//! Postgres-backed [`WorkflowStateStore`] handed to nodes at execution time.
+
+use async_trait::async_trait;
+use serde_json::Value;
+use uuid::Uuid;
+
+use db::DbPool;
+use db::repository::state as state_repo;
+use nodes::NodeError;
+use nodes::state::{StateUpdateFn, WorkflowStateStore};
+
+/// Stores workflow state in the `workflow_state` table.
+///
+/// Updates lock the row with `SELECT … FOR UPDATE`, so concurrent executions
+/// of the same workflow see each other's writes in order.
+#[derive(Debug, Clone)]
+pub struct PgWorkflowStateStore {
+    pool: DbPool,
+}
+
+impl PgWorkflowStateStore {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Database hiccups are transient from a node's point of view.
+fn db_error(err: db::DbError) -> NodeError {
+    NodeError::Retryable(format!("workflow state store: {err}"))
+}
+
+#[async_trait]
+impl WorkflowStateStore for PgWorkflowStateStore {
+    async fn get(&self, workflow_id: Uuid, key: &str) -> Result<Option<Value>, NodeError> {
+        let row = state_repo::get_state(&self.pool, workflow_id, key)
+            .await
+            .map_err(db_error)?;
+        Ok(row.and_then(|r| r.value))
+    }
+
+    async fn update(
+        &self,
+        workflow_id: Uuid,
+        key: &str,
+        update: StateUpdateFn,
+    ) -> Result<Value, NodeError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| db_error(e.into()))?;
+
+        state_repo::ensure_state_row(&mut tx, workflow_id, key)
+            .await
+            .map_err(db_error)?;
+        let current = state_repo::lock_state(&mut tx, workflow_id, key)
+            .await
+            .map_err(db_error)?;
+
+        let (new_value, result) = update(current);
+        match new_value {
+            Some(value) => state_repo::write_state(&mut tx, workflow_id, key, value).await,
+            None => state_repo::delete_state(&mut tx, workflow_id, key).await,
+        }
+        .map_err(db_error)?;
+
+        tx.commit().await.map_err(|e| db_error(e.into()))?;
+        Ok(result)
+    }
+}
@@ -0,0 +1,101 @@
+//! Sub-workflows run by nodes such as `execute_workflow`.
+//!
+//! Every execution hands its nodes an [`ExecutorSubWorkflows`], which loads
+//! the child workflow and either runs it inline with a clone of the
+//! executor ([`WorkflowExecutor::run_child`]) or queues it
+//! ([`WorkflowExecutor::start_child`]).  Either way the child execution
+//! records its parent and inherits its priority, queue, and labels.
+//!
+//! Inline runs nest at most [`MAX_DEPTH`] levels, so a workflow that
+//! (indirectly) calls itself fails instead of recursing forever.
+
+use async_trait::async_trait;
+use serde_json::Value;
+use uuid::Uuid;
+
+use db::DbPool;
+use db::repository::workflows as wf_repo;
+use nodes::NodeError;
+use nodes::subworkflow::SubWorkflowRunner;
+
+use crate::{EngineError, Workflow, WorkflowExecutor};
+
+/// Deepest nesting of inline sub-workflow runs.
+pub const MAX_DEPTH: u32 = 8;
+
+/// Load and parse stored workflow `workflow_id`.
+///
+/// # Errors
+/// [`EngineError::InvalidWorkflow`] when the definition does not parse,
+/// and database errors (`NotFound` for an unknown id).
+pub async fn load_workflow(pool: &DbPool, workflow_id: Uuid) -> Result<Workflow, EngineError> {
+    let row = wf_repo::get_workflow(pool, workflow_id).await?;
+    let mut workflow: Workflow = serde_json::from_value(row.definition)
+        .map_err(|e| EngineError::InvalidWorkflow { workflow_id, message: e.to_string() })?;
+    workflow.id = row.id;
+    Ok(workflow)
+}
+
+/// [`SubWorkflowRunner`] backed by a [`WorkflowExecutor`].
+#[derive(Clone)]
+pub struct ExecutorSubWorkflows {
+    executor: WorkflowExecutor,
+}
+
+impl ExecutorSubWorkflows {
+    pub fn new(executor: WorkflowExecutor) -> Self {
+        Self { executor }
+    }
+}
+
+impl std::fmt::Debug for ExecutorSubWorkflows {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExecutorSubWorkflows").finish_non_exhaustive()
+    }
+}
+
+/// Database hiccups are transient; everything else about a child is final
+/// (retrying a failed child would run it again from scratch).
+fn node_error(workflow_id: Uuid, err: EngineError) -> NodeError {
+    match err {
+        EngineError::Database(db::DbError::Sqlx(e)) => {
+            NodeError::Retryable(format!("sub-workflow {workflow_id}: database error: {e}"))
+        }
+        EngineError::Queue(e) => NodeError::Retryable(format!("sub-workflow {workflow_id}: {e}")),
+        other => NodeError::Fatal(format!("sub-workflow {workflow_id}: {other}")),
+    }
+}
+
+#[async_trait]
+impl SubWorkflowRunner for ExecutorSubWorkflows {
+    async fn run(&self, parent_execution_id: Uuid, workflow_id: Uuid, input: Value) -> Result<Value, NodeError> {
+        let child = load_workflow(self.executor.pool(), workflow_id)
+            .await
+            .map_err(|e| node_error(workflow_id, e))?;
+        let result = self
+            .executor
+            .run_child(parent_execution_id, &child, input)
+            .await
+            .map_err(|e| node_error(workflow_id, e))?;
+        match result.deferred_until {
+            None => Ok(result.output),
+            Some(until) => Err(NodeError::Fatal(format!(
+                "sub-workflow {workflow_id} paused until {until} (execution {}); \
+                 run it with mode \"async\" instead",
+                result.execution_id
+            ))),
+        }
+    }
+
+    async fn start(&self, parent_execution_id: Uuid, workflow_id: Uuid, input: Value) -> Result<Uuid, NodeError> {
+        let child = load_workflow(self.executor.pool(), workflow_id)
+            .await
+            .map_err(|e| node_error(workflow_id, e))?;
+        let job = self
+            .executor
+            .start_child(parent_execution_id, &child, input)
+            .await
+            .map_err(|e| node_error(workflow_id, e))?;
+        Ok(job.execution_id)
+    }
+}
@@ -0,0 +1,205 @@
+//! Starter workflows, so new workflows don't begin from a blank file.
+//!
+//! [`scaffold`] writes a complete definition: a trigger, nodes with
+//! placeholder configs that match their config schemas, and the edges
+//! between them.  Placeholders to replace are URLs, project keys, and the
+//! names of secrets to create; `{{ input.… }}` templates read the fields
+//! the first node validates.
+
+use std::str::FromStr;
+
+use chrono::Utc;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// A starter workflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Template {
+    /// One node that accepts any input.
+    Blank,
+    /// Validate an incoming request and open a Jira issue from it.
+    WebhookToJira,
+    /// Summarise a text with an LLM.
+    LlmSummary,
+    /// Parse a CSV and keep its top rows by amount.
+    CsvTop,
+}
+
+impl Template {
+    pub const ALL: [Template; 4] = [Self::Blank, Self::WebhookToJira, Self::LlmSummary, Self::CsvTop];
+    /// The names of [`ALL`](Self::ALL), as [`FromStr`] takes them.
+    pub const NAMES: [&'static str; 4] = ["blank", "webhook-to-jira", "llm-summary", "csv-top"];
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES[Self::ALL.iter().position(|t| *t == self).expect("every template is listed")]
+    }
+
+    /// The trigger the template is written for.
+    pub fn default_trigger(self) -> StarterTrigger {
+        match self {
+            Self::WebhookToJira => StarterTrigger::Webhook,
+            Self::Blank | Self::LlmSummary | Self::CsvTop => StarterTrigger::Manual,
+        }
+    }
+
+    /// Nodes and edges of the template.
+    fn graph(self) -> (Value, Value) {
+        match self {
+            Self::Blank => (
+                json!([{ "id": "start", "node_type": "validate_json", "config": { "schema": {} } }]),
+                json!([]),
+            ),
+            Self::WebhookToJira => (
+                json!([
+                    {
+                        "id": "check",
+                        "node_type": "validate_json",
+                        "config": {
+                            "schema": {
+                                "type": "object",
+                                "required": ["title", "description"],
+                                "properties": { "title": { "type": "string" }, "description": { "type": "string" } }
+                            }
+                        }
+                    },
+                    {
+                        "id": "create_issue",
+                        "node_type": "jira",
+                        "config": {
+                            "base_url": "https://your-site.atlassian.net",
+                            "email": "you@example.com",
+                            "api_token_secret": "jira_api_token",
+                            "operation": "create_issue",
+                            "project": "OPS",
+                            "issue_type": "Task",
+                            "summary": "{{ input.title }}",
+                            "description": "{{ input.description }}"
+                        }
+                    }
+                ]),
+                json!([{ "from": "check", "to": "create_issue", "branch": "valid" }]),
+            ),
+            Self::LlmSummary => (
+                json!([
+                    {
+                        "id": "check",
+                        "node_type": "validate_json",
+                        "config": {
+                            "schema": { "type": "object", "required": ["text"], "properties": { "text": { "type": "string" } } }
+                        }
+                    },
+                    {
+                        "id": "summarize",
+                        "node_type": "llm",
+                        "config": {
+                            "api_key_secret": "llm_api_key",
+                            "system": "You summarise documents in three sentences.",
+                            "prompt": "{{ input.text }}",
+                            "max_tokens": 300
+                        }
+                    }
+                ]),
+                json!([{ "from": "check", "to": "summarize", "branch": "valid" }]),
+            ),
+            Self::CsvTop => (
+                json!([
+                    { "id": "parse", "node_type": "csv", "config": { "mode": "parse", "field": "data", "infer_types": true } },
+                    {
+                        "id": "top",
+                        "node_type": "sort_limit",
+                        "config": { "field": "records", "sort": [{ "field": "amount", "order": "desc" }], "limit": 10 }
+                    }
+                ]),
+                json!([{ "from": "parse", "to": "top" }]),
+            ),
+        }
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::NAMES
+            .iter()
+            .position(|name| *name == s)
+            .map(|i| Self::ALL[i])
+            .ok_or_else(|| format!("unknown template '{s}' (expected one of {})", Self::NAMES.join(", ")))
+    }
+}
+
+/// The kinds of trigger a starter workflow can have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StarterTrigger {
+    Manual,
+    /// At a path derived from the workflow's name.
+    Webhook,
+    /// Weekdays at 09:00 UTC.
+    Cron,
+}
+
+/// A new workflow called `name` from `template`, with `trigger` or the
+/// template's own.
+pub fn scaffold(name: &str, template: Template, trigger: Option<StarterTrigger>) -> Value {
+    let trigger = match trigger.unwrap_or(template.default_trigger()) {
+        StarterTrigger::Manual => json!({ "type": "manual" }),
+        StarterTrigger::Webhook => json!({ "type": "webhook", "path": slug(name) }),
+        StarterTrigger::Cron => json!({ "type": "cron", "expression": "0 9 * * 1-5" }),
+    };
+    let (nodes, edges) = template.graph();
+    json!({
+        "id": Uuid::new_v4(),
+        "name": name,
+        "trigger": trigger,
+        "nodes": nodes,
+        "edges": edges,
+        "created_at": Utc::now(),
+    })
+}
+
+/// `name` as a URL path segment: `Order Sync!` → `order-sync`.
+fn slug(name: &str) -> String {
+    let slug = name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() { "workflow".to_owned() } else { slug }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{validate_dag, Workflow};
+    use nodes::registry::{catalog, default_registry};
+
+    #[test]
+    fn every_template_is_a_valid_workflow_with_valid_configs() {
+        let catalog = catalog(&default_registry());
+        for template in Template::ALL {
+            for trigger in [None, Some(StarterTrigger::Manual), Some(StarterTrigger::Webhook), Some(StarterTrigger::Cron)] {
+                let definition = scaffold("Order Sync!", template, trigger);
+                let workflow: Workflow = serde_json::from_value(definition).unwrap();
+                validate_dag(&workflow).unwrap();
+
+                for node in &workflow.nodes {
+                    let entry = catalog.iter().find(|e| e.node_type == node.node_type).expect("registered node type");
+                    let validator = jsonschema::validator_for(&entry.descriptor.config_schema).unwrap();
+                    assert!(validator.is_valid(&node.config), "{} config of {}", node.id, template.name());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn templates_parse_by_name_and_webhooks_get_a_slug() {
+        assert_eq!("webhook-to-jira".parse::<Template>(), Ok(Template::WebhookToJira));
+        assert!("http-to-slack".parse::<Template>().unwrap_err().contains("csv-top"));
+        let definition = scaffold("Order Sync!", Template::WebhookToJira, None);
+        assert_eq!(definition["trigger"], json!({ "type": "webhook", "path": "order-sync" }));
+    }
+}
@@ -0,0 +1,209 @@
+//! Test utilities: `proptest` strategies that generate random workflows.
+//!
+//! [`valid_workflow`] produces acyclic workflows of a configurable
+//! [`WorkflowShape`]; [`invalid_workflow`] produces workflows with exactly
+//! one known [`Defect`] (a cycle, a duplicate node ID, or an edge to a
+//! missing node), so properties can assert the precise error.  Node
+//! declaration order is shuffled independently of the graph, which makes the
+//! generators good at catching order-dependent bugs in validation, sorting,
+//! and scheduling.
+//!
+//! Available in the engine's own tests and, for other crates, behind the
+//! `testing` feature:
+//!
+//! ```ignore
+//! proptest! {
+//!     #[test]
+//!     fn sorts_every_dag(workflow in valid_workflow(WorkflowShape::default())) {
+//!         prop_assert!(validate_dag(&workflow).is_ok());
+//!     }
+//! }
+//! ```
+
+use std::collections::BTreeSet;
+
+use proptest::prelude::*;
+
+use crate::models::{Edge, NodeDefinition, Trigger, Workflow};
+
+/// Size and connectivity of generated workflows.
+#[derive(Debug, Clone)]
+pub struct WorkflowShape {
+    /// Node count range (inclusive); `min_nodes` is raised to what a defect
+    /// needs.
+    pub min_nodes: usize,
+    pub max_nodes: usize,
+    /// Maximum outgoing edges per node.
+    pub max_fan_out: usize,
+    /// `node_type` of each node is picked from these.
+    pub node_types: Vec<String>,
+}
+
+impl Default for WorkflowShape {
+    fn default() -> Self {
+        Self { min_nodes: 1, max_nodes: 12, max_fan_out: 3, node_types: vec!["mock".into()] }
+    }
+}
+
+/// What makes a generated workflow invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Defect {
+    Cycle,
+    DuplicateNodeId,
+    UnknownNodeReference,
+}
+
+/// Acyclic workflows with unique node IDs and valid edges.
+pub fn valid_workflow(shape: WorkflowShape) -> impl Strategy<Value = Workflow> {
+    let min = shape.min_nodes.max(1);
+    let max = shape.max_nodes.max(min);
+    (min..=max).prop_flat_map(move |n| dag(n, shape.clone()))
+}
+
+/// Workflows with exactly one defect, paired with that defect.
+pub fn invalid_workflow(shape: WorkflowShape) -> impl Strategy<Value = (Workflow, Defect)> {
+    let min = shape.min_nodes.max(2);
+    let max = shape.max_nodes.max(min);
+    let base = (min..=max).prop_flat_map(move |n| dag(n, shape.clone()));
+
+    prop_oneof![
+        (base.clone(), any::<prop::sample::Index>(), any::<prop::sample::Index>())
+            .prop_map(|(workflow, a, b)| (with_cycle(workflow, a, b), Defect::Cycle)),
+        (base.clone(), any::<prop::sample::Index>(), any::<prop::sample::Index>()).prop_map(
+            |(mut workflow, a, b)| {
+                let n = workflow.nodes.len();
+                let (src, dst) = (a.index(n), (a.index(n) + 1 + b.index(n - 1)) % n);
+                workflow.nodes[dst].id = workflow.nodes[src].id.clone();
+                (workflow, Defect::DuplicateNodeId)
+            }
+        ),
+        (base, any::<prop::sample::Index>(), any::<bool>()).prop_map(|(mut workflow, a, outgoing)| {
+            let known = workflow.nodes[a.index(workflow.nodes.len())].id.clone();
+            let edge = if outgoing {
+                Edge::new(known, "missing")
+            } else {
+                Edge::new("missing", known)
+            };
+            workflow.edges.push(edge);
+            (workflow, Defect::UnknownNodeReference)
+        }),
+    ]
+}
+
+/// A DAG over `n` nodes: edges only point from lower to higher generation
+/// index, then nodes and edges are shuffled.
+fn dag(n: usize, shape: WorkflowShape) -> impl Strategy<Value = Workflow> {
+    let types = shape.node_types.clone();
+    let fan_out = shape.max_fan_out;
+    let per_node = prop::collection::vec(prop::collection::btree_set(0..n.max(1), 0..=fan_out), n);
+    let node_types = prop::collection::vec(prop::sample::select(types), n);
+
+    (per_node, node_types)
+        .prop_flat_map(move |(targets, types)| {
+            let nodes: Vec<NodeDefinition> = types
+                .into_iter()
+                .enumerate()
+                .map(|(i, node_type)| NodeDefinition {
+                    id: format!("n{i}"),
+                    node_type,
+                    config: serde_json::Value::Null,
+                    worker_tags: Vec::new(),
+                })
+                .collect();
+            let edges: Vec<Edge> = targets
+                .iter()
+                .enumerate()
+                .flat_map(|(from, to)| {
+                    to.iter()
+                        .filter(move |&&to| to > from)
+                        .map(move |to| Edge::new(format!("n{from}"), format!("n{to}")))
+                })
+                .collect();
+            (Just(nodes).prop_shuffle(), Just(edges).prop_shuffle())
+        })
+        .prop_map(|(nodes, edges)| Workflow::new("generated", Trigger::Manual { input_schema: None }, nodes, edges))
+}
+
+/// Close a cycle through two nodes picked by `a` and `b` (the same node
+/// twice makes a self-loop).
+fn with_cycle(mut workflow: Workflow, a: prop::sample::Index, b: prop::sample::Index) -> Workflow {
+    let n = workflow.nodes.len();
+    let (x, y) = (&workflow.nodes[a.index(n)].id, &workflow.nodes[b.index(n)].id);
+    let existing: BTreeSet<(&str, &str)> =
+        workflow.edges.iter().map(|e| (e.from.as_str(), e.to.as_str())).collect();
+    let mut extra = Vec::new();
+    if !existing.contains(&(x.as_str(), y.as_str())) {
+        extra.push(Edge::new(x.clone(), y.clone()));
+    }
+    if x != y && !existing.contains(&(y.as_str(), x.as_str())) {
+        extra.push(Edge::new(y.clone(), x.clone()));
+    }
+    workflow.edges.extend(extra);
+    workflow
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{EngineError, validate_dag};
+
+    proptest! {
+        #[test]
+        fn valid_workflows_sort_topologically(workflow in valid_workflow(WorkflowShape::default())) {
+            let sorted = validate_dag(&workflow).unwrap();
+            prop_assert_eq!(sorted.len(), workflow.nodes.len());
+
+            let position: HashMap<&str, usize> =
+                sorted.iter().enumerate().map(|(i, id)| (id.as_str(), i)).collect();
+            for edge in &workflow.edges {
+                prop_assert!(position[edge.from.as_str()] < position[edge.to.as_str()]);
+            }
+        }
+
+        #[test]
+        fn sorting_is_deterministic(workflow in valid_workflow(WorkflowShape::default())) {
+            let round_tripped: Workflow =
+                serde_json::from_value(serde_json::to_value(&workflow).unwrap()).unwrap();
+            prop_assert_eq!(validate_dag(&workflow).unwrap(), validate_dag(&round_tripped).unwrap());
+        }
+
+        #[test]
+        fn defects_are_reported(
+            (workflow, defect) in invalid_workflow(WorkflowShape { max_fan_out: 2, ..WorkflowShape::default() })
+        ) {
+            let reported = match validate_dag(&workflow) {
+                Err(EngineError::CycleDetected) => Defect::Cycle,
+                Err(EngineError::DuplicateNodeId(_)) => Defect::DuplicateNodeId,
+                Err(EngineError::UnknownNodeReference { node_id, .. }) if node_id == "missing" => {
+                    Defect::UnknownNodeReference
+                }
+                other => return Err(TestCaseError::fail(format!("unexpected result {other:?}"))),
+            };
+            prop_assert_eq!(reported, defect);
+        }
+    }
+
+    #[test]
+    fn shape_bounds_are_respected() {
+        use proptest::strategy::ValueTree;
+        use proptest::test_runner::TestRunner;
+
+        let shape = WorkflowShape { min_nodes: 3, max_nodes: 5, max_fan_out: 1, ..WorkflowShape::default() };
+        let strategy = valid_workflow(shape);
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..64 {
+            let workflow = strategy.new_tree(&mut runner).unwrap().current();
+            assert!((3..=5).contains(&workflow.nodes.len()));
+            let mut fan_out: HashMap<&str, usize> = HashMap::new();
+            for edge in &workflow.edges {
+                *fan_out.entry(edge.from.as_str()).or_default() += 1;
+            }
+            assert!(fan_out.values().all(|&n| n <= 1));
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Execution timelines — where the time of a run went.
+//!
+//! [`build`] turns the recorded timings of an execution into a waterfall:
+//! the time it sat queued before a worker picked it up, then one bar per
+//! node run with a sub-bar per attempt.  Every bar carries its offset from
+//! when the execution was queued, so a client can draw it without doing
+//! date arithmetic.  Time a node spent on failed attempts and the backoff
+//! between them is its `retry_ms`, kept apart from its last attempt.
+//!
+//! Queue time runs from the execution's creation, so the delay of a
+//! delayed or debounced trigger counts as queueing.  Nodes recorded before
+//! attempts were kept show a single attempt spanning the whole node.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use db::models::{ExecutionTimingRow, NodeAttempt, NodeTimingRow};
+
+/// The waterfall of one execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Timeline {
+    pub execution_id: uuid::Uuid,
+    pub status: String,
+    pub queued_at: DateTime<Utc>,
+    pub running_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// From queued to picked up; `None` while still queued.
+    pub queue_ms: Option<i64>,
+    /// From picked up to finished; `None` until finished.
+    pub run_ms: Option<i64>,
+    /// Time all nodes spent on failed attempts and backoff.
+    pub retry_ms: i64,
+    /// The node run that took longest, if any ran.
+    pub slowest_node: Option<String>,
+    pub nodes: Vec<NodeBar>,
+}
+
+/// One node run on the timeline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NodeBar {
+    pub node_id: String,
+    pub status: String,
+    /// From the execution being queued to the node starting.
+    pub offset_ms: i64,
+    pub duration_ms: Option<i64>,
+    /// From the node starting to its last attempt starting.
+    pub retry_ms: i64,
+    pub attempts: Vec<AttemptBar>,
+}
+
+/// One attempt at a node on the timeline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AttemptBar {
+    pub attempt: i32,
+    pub offset_ms: i64,
+    pub duration_ms: i64,
+    pub error: Option<String>,
+}
+
+/// Lay out `execution` and its `nodes` (in the order they ran) as a
+/// waterfall.
+pub fn build(execution: &ExecutionTimingRow, nodes: &[NodeTimingRow]) -> Timeline {
+    let origin = execution.queued_at;
+    let ms = |from: DateTime<Utc>, to: DateTime<Utc>| (to - from).num_milliseconds();
+
+    let nodes: Vec<NodeBar> = nodes
+        .iter()
+        .map(|node| {
+            let mut attempts: Vec<NodeAttempt> =
+                serde_json::from_value(node.attempts.clone()).unwrap_or_default();
+            if attempts.is_empty() {
+                if let Some(finished_at) = node.finished_at {
+                    attempts.push(NodeAttempt {
+                        attempt: 1,
+                        started_at: node.started_at,
+                        finished_at,
+                        error: None,
+                    });
+                }
+            }
+            NodeBar {
+                node_id: node.node_id.clone(),
+                status: node.status.clone(),
+                offset_ms: ms(origin, node.started_at),
+                duration_ms: node.finished_at.map(|f| ms(node.started_at, f)),
+                retry_ms: attempts.last().map_or(0, |last| ms(node.started_at, last.started_at).max(0)),
+                attempts: attempts
+                    .into_iter()
+                    .map(|a| AttemptBar {
+                        attempt: a.attempt,
+                        offset_ms: ms(origin, a.started_at),
+                        duration_ms: ms(a.started_at, a.finished_at),
+                        error: a.error,
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    Timeline {
+        execution_id: execution.id,
+        status: execution.status.clone(),
+        queued_at: execution.queued_at,
+        running_at: execution.running_at,
+        finished_at: execution.finished_at,
+        queue_ms: execution.running_at.map(|r| ms(origin, r)),
+        run_ms: execution.running_at.zip(execution.finished_at).map(|(r, f)| ms(r, f)),
+        retry_ms: nodes.iter().map(|n| n.retry_ms).sum(),
+        slowest_node: nodes
+            .iter()
+            .filter_map(|n| Some((n.duration_ms?, &n.node_id)))
+            .max_by_key(|(duration, _)| *duration)
+            .map(|(_, id)| id.clone()),
+        nodes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use serde_json::json;
+
+    #[test]
+    fn splits_queueing_retries_and_node_time() {
+        let t0 = Utc::now();
+        let at = |ms: i64| t0 + Duration::milliseconds(ms);
+        let execution = ExecutionTimingRow {
+            id: uuid::Uuid::new_v4(),
+            status: "succeeded".into(),
+            queued_at: t0,
+            running_at: Some(at(500)),
+            finished_at: Some(at(2_000)),
+        };
+        let flaky = vec![
+            NodeAttempt { attempt: 1, started_at: at(600), finished_at: at(700), error: Some("timeout".into()) },
+            NodeAttempt { attempt: 2, started_at: at(900), finished_at: at(1_900), error: None },
+        ];
+        let nodes = vec![
+            NodeTimingRow {
+                node_id: "fetch".into(),
+                status: "succeeded".into(),
+                started_at: at(510),
+                finished_at: Some(at(590)),
+                attempts: json!([]),
+            },
+            NodeTimingRow {
+                node_id: "send".into(),
+                status: "succeeded".into(),
+                started_at: at(600),
+                finished_at: Some(at(1_900)),
+                attempts: json!(flaky),
+            },
+        ];
+
+        let timeline = build(&execution, &nodes);
+        assert_eq!((timeline.queue_ms, timeline.run_ms), (Some(500), Some(1_500)));
+        assert_eq!(timeline.retry_ms, 300);
+        assert_eq!(timeline.slowest_node.as_deref(), Some("send"));
+
+        let fetch = &timeline.nodes[0];
+        assert_eq!((fetch.offset_ms, fetch.duration_ms, fetch.retry_ms), (510, Some(80), 0));
+        assert_eq!(fetch.attempts.len(), 1);
+
+        let send = &timeline.nodes[1];
+        assert_eq!(send.retry_ms, 300);
+        assert_eq!(
+            send.attempts,
+            vec![
+                AttemptBar { attempt: 1, offset_ms: 600, duration_ms: 100, error: Some("timeout".into()) },
+                AttemptBar { attempt: 2, offset_ms: 900, duration_ms: 1_000, error: None },
+            ]
+        );
+    }
+}
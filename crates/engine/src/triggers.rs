@@ -0,0 +1,253 @@
+//! Admission of trigger events into the job queue.
+//!
+//! Triggers can carry throttling and debouncing options (see
+//! [`Trigger::throttle`] and [`Trigger::debounce`]).  [`admit`] applies them
+//! before anything is enqueued, so a chatty upstream cannot flood the queue:
+//!
+//! 1. **Quota** — if the workflow's or its project's quota is used up
+//!    (see [`crate::quotas`]), the event is rejected.
+//! 2. **Throttle** — if the workflow already started `max_per_minute`
+//!    executions in the last 60 seconds, the event is rejected.
+//! 3. **Debounce** — the event is folded into the workflow's pending
+//!    debounced job (if any) and that job's start is pushed back;
+//!    otherwise a new job is scheduled `window_secs` from now.
+//! 4. Otherwise a job is enqueued immediately.
+//!
+//! The throttle is counted in the transaction that creates the execution
+//! (or folds the event), under a per-workflow lock, so a burst of
+//! concurrent events cannot all see room for the last execution.  A
+//! rejected event is never folded into a pending debounced run.
+//!
+//! Callers that answer with the result (synchronous webhooks, see
+//! [`SyncResponse`](crate::SyncResponse)) then [`wait_for`] the execution.
+//!
+//! Input supplied by hand is first checked against the manual trigger's
+//! schema with [`check_input`].
+
+use std::time::Duration as StdDuration;
+
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::info;
+use uuid::Uuid;
+
+use db::DbPool;
+use db::models::{JobRow, StartLimit};
+use db::repository::{executions as exec_repo, jobs as job_repo};
+use queue::JobQueue;
+
+use crate::{quotas, DebouncePayload, EngineError, Trigger, Workflow};
+
+/// What happened to a trigger event.
+#[derive(Debug)]
+pub enum Admission {
+    /// A new job was enqueued.
+    Enqueued(JobRow),
+    /// The event was folded into a debounced job (new or already pending).
+    Debounced(JobRow),
+    /// The trigger's execution budget is used up; nothing was enqueued.
+    Throttled,
+    /// The workflow's or project's quota is used up, for the reason given;
+    /// nothing was enqueued.
+    OverQuota(String),
+}
+
+/// Apply the workflow trigger's throttle/debounce options and enqueue
+/// `payload` for the workflow stored under `workflow_id`, handing the job to
+/// `queue`.
+pub async fn admit(
+    pool: &DbPool,
+    queue: &dyn JobQueue,
+    workflow_id: Uuid,
+    workflow: &Workflow,
+    payload: Value,
+) -> Result<Admission, EngineError> {
+    admit_with_params(pool, queue, workflow_id, workflow, payload, Map::new()).await
+}
+
+/// [`admit`], running with `params` over the workflow's parameter defaults
+/// (already checked, see [`crate::params`]).  An event folded into a
+/// pending debounced job runs with that job's parameters.
+pub async fn admit_with_params(
+    pool: &DbPool,
+    queue: &dyn JobQueue,
+    workflow_id: Uuid,
+    workflow: &Workflow,
+    payload: Value,
+    params: Map<String, Value>,
+) -> Result<Admission, EngineError> {
+    let now = Utc::now();
+    let trigger = &workflow.trigger;
+    let mut meta = workflow.execution_meta(&payload);
+    meta.params = params;
+
+    match quotas::check(pool, workflow_id, workflow.quota.as_ref()).await {
+        Err(EngineError::QuotaExceeded(reason)) => {
+            info!("workflow {} over quota: {}", workflow_id, reason);
+            return Ok(Admission::OverQuota(reason));
+        }
+        checked => checked?,
+    }
+
+    let limit = trigger
+        .throttle()
+        .map(|throttle| StartLimit { max: i64::from(throttle.max_per_minute), since: now - Duration::minutes(1) });
+    let throttled = || {
+        info!("workflow {} throttled: {} executions started in the last minute", workflow_id, limit.map_or(0, |l| l.max));
+        Ok(Admission::Throttled)
+    };
+
+    if let Some(debounce) = trigger.debounce() {
+        // Workflows saved before the window was bounded may still hold any
+        // length.
+        let run_at = i64::try_from(debounce.window_secs)
+            .ok()
+            .and_then(Duration::try_seconds)
+            .and_then(|window| now.checked_add_signed(window))
+            .ok_or_else(|| {
+                EngineError::InvalidDefinition(format!("debounce window_secs {} is out of range", debounce.window_secs))
+            })?;
+        let Some((job, created)) = job_repo::enqueue_debounced_job(
+            pool,
+            workflow_id,
+            &debounce_key(trigger),
+            payload,
+            run_at,
+            debounce.payload == DebouncePayload::Merge,
+            &meta,
+            limit,
+        )
+        .await?
+        else {
+            return throttled();
+        };
+        if !created {
+            info!("workflow {} event folded into pending job {}", workflow_id, job.id);
+        }
+        queue.push(&job).await?;
+        return Ok(Admission::Debounced(job));
+    }
+
+    let exec = match limit {
+        Some(limit) => match exec_repo::create_execution_within(pool, workflow_id, &meta, limit).await? {
+            Some(exec) => exec,
+            None => return throttled(),
+        },
+        None => exec_repo::create_execution(pool, workflow_id, &meta).await?,
+    };
+    let job = job_repo::enqueue_job(pool, exec.id, workflow_id, payload).await?;
+    queue.push(&job).await?;
+    Ok(Admission::Enqueued(job))
+}
+
+/// One way manually supplied input fails the trigger's schema.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InputViolation {
+    /// JSON pointer to the offending value (empty for the input itself).
+    pub path: String,
+    pub message: String,
+}
+
+/// Check `input` against the trigger's input schema (see
+/// [`Trigger::input_schema`]); no violations when it matches or the
+/// trigger has no schema.
+///
+/// # Errors
+/// [`EngineError::InvalidInputSchema`] when the schema does not compile.
+pub fn check_input(trigger: &Trigger, input: &Value) -> Result<Vec<InputViolation>, EngineError> {
+    let Some(schema) = trigger.input_schema() else {
+        return Ok(Vec::new());
+    };
+    let validator = jsonschema::validator_for(schema)
+        .map_err(|e| EngineError::InvalidInputSchema { message: e.to_string() })?;
+    Ok(validator
+        .iter_errors(input)
+        .map(|e| InputViolation { path: e.instance_path.to_string(), message: e.to_string() })
+        .collect())
+}
+
+/// How far an execution got while a caller waited on it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// Finished; the output of the last node that ran.
+    Succeeded(Value),
+    /// Failed at `node_id` (unknown if no node record was written).
+    Failed { node_id: Option<String> },
+    /// Still pending, running, or waiting when the wait timed out.
+    Unfinished,
+}
+
+/// How often [`wait_for`] checks on the execution.
+const POLL_INTERVAL: StdDuration = StdDuration::from_millis(100);
+
+/// Wait up to `timeout` for a queued execution to finish.
+///
+/// The execution is run by a worker; this only polls its status.  An
+/// execution that succeeds without running any node yields its `input`.
+pub async fn wait_for(
+    pool: &DbPool,
+    execution_id: Uuid,
+    input: &Value,
+    timeout: StdDuration,
+) -> Result<Outcome, EngineError> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let exec = exec_repo::get_execution(pool, execution_id).await?;
+        match exec.status.as_str() {
+            "succeeded" | "failed" => {
+                let nodes = exec_repo::list_node_executions(pool, execution_id).await?;
+                return Ok(if exec.status == "succeeded" {
+                    let last = nodes.into_iter().rev().find(|n| n.status == "succeeded");
+                    Outcome::Succeeded(last.and_then(|n| n.output).unwrap_or_else(|| input.clone()))
+                } else {
+                    let failed = nodes.into_iter().rev().find(|n| n.status == "failed");
+                    Outcome::Failed { node_id: failed.map(|n| n.node_id) }
+                });
+            }
+            _ if tokio::time::Instant::now() >= deadline => return Ok(Outcome::Unfinished),
+            _ => tokio::time::sleep(POLL_INTERVAL).await,
+        }
+    }
+}
+
+/// Key identifying a trigger's pending debounced job within its workflow.
+fn debounce_key(trigger: &Trigger) -> String {
+    match trigger {
+        Trigger::Webhook { path, .. } => format!("webhook:{path}"),
+        Trigger::Manual { .. } => "manual".into(),
+        Trigger::Cron { expression, .. } => format!("cron:{expression}"),
+        Trigger::Poll { .. } => "poll".into(),
+        Trigger::Amqp { queue, .. } => format!("amqp:{queue}"),
+        Trigger::PgNotify { channel, .. } => format!("pg_notify:{channel}"),
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn manual_input_is_checked_against_the_schema() {
+        let trigger = Trigger::Manual {
+            input_schema: Some(json!({
+                "type": "object",
+                "required": ["email"],
+                "properties": { "email": { "type": "string" }, "seats": { "type": "integer", "minimum": 1 } }
+            })),
+        };
+        assert!(check_input(&trigger, &json!({ "email": "ada@example.com", "seats": 3 })).unwrap().is_empty());
+
+        let violations = check_input(&trigger, &json!({ "seats": 0 })).unwrap();
+        let paths: Vec<&str> = violations.iter().map(|v| v.path.as_str()).collect();
+        assert_eq!(violations.len(), 2);
+        assert!(paths.contains(&"") && paths.contains(&"/seats"), "{violations:?}");
+
+        // No schema, nothing to check.
+        assert!(check_input(&Trigger::Manual { input_schema: None }, &json!(42)).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,478 @@
+//! Queue worker — runs the executions queued in `job_queue`.
+//!
+//! Each step of [`Worker::run`] claims the next due job from its
+//! [`JobQueue`](queue::JobQueue) backend (Postgres unless
+//! [`Worker::with_queue`] says otherwise), loads its workflow, and runs the
+//! execution — from the first node, or from the checkpoint of a deferred
+//! execution — then settles the job:
+//!
+//! - **completed** when the execution finished, successfully or not (a
+//!   failed node is final; re-running the job would repeat side effects);
+//! - **failed** on database errors, so the job is retried — after a
+//!   jittered, growing delay (see [`crate::backoff`]) — until it runs out
+//!   of attempts and is dead-lettered.
+//!
+//! A claimed job is leased for [`Worker::with_lease`], and the worker
+//! renews the lease with a heartbeat while the execution runs.  Every
+//! running worker also reclaims the jobs whose lease expired — their
+//! worker crashed or lost its database — returning them to `pending`, or
+//! dead-lettering them once out of attempts.
+//!
+//! An idle worker sleeps until a job is announced on
+//! [`JOBS_CHANNEL`](db::repository::jobs::JOBS_CHANNEL), the next delayed
+//! job is due, or its poll interval — a fallback for lost notifications —
+//! has passed, whichever comes first.  Without a listener (its connection
+//! failed) it polls on the interval until it reconnects.
+//!
+//! Jobs can require capability tags (a GPU, a host inside the VPN; see
+//! `worker_tags` on workflows and nodes); a worker only claims those whose
+//! tags it declared with [`Worker::with_tags`].
+//!
+//! A worker runs up to [`Worker::with_concurrency`] jobs at a time, each
+//! in a task of its own.  Workers claim jobs with `SKIP LOCKED`, so any
+//! number of them can share a database.
+//!
+//! [`Worker::run_until`] shuts down gracefully: it stops claiming jobs,
+//! gives the running ones [`Worker::with_grace_period`] to finish (or
+//! defer, which checkpoints them), then stops the rest and puts their jobs
+//! back in the queue without counting the attempt.  An interrupted
+//! execution starts over from its first node (or last checkpoint) on the
+//! next worker.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::Semaphore;
+use tokio::task::{self, JoinSet};
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use db::listener::Listener;
+use db::models::JobRow;
+use db::repository::jobs::{self as job_repo, JOBS_CHANNEL};
+use db::{DbError, DbPool};
+use queue::{PgJobQueue, QueueError, SharedQueue};
+
+use crate::error_reporting::{self, Tags};
+use crate::executor::{Checkpoint, ExecutionResult};
+use crate::metrics;
+use crate::subworkflow::load_workflow;
+use crate::{EngineError, RetryBackoff, WorkflowExecutor};
+
+/// Longest pause after a failed poll.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Shortest idle wait, so a due job held by another worker's claim does
+/// not make this one spin.
+const MIN_WAIT: Duration = Duration::from_millis(50);
+
+/// How long a claimed job stays leased without a heartbeat by default.
+pub const DEFAULT_LEASE: Duration = Duration::from_secs(60);
+
+/// How long running jobs may take to finish at shutdown by default.
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+/// Claims queued jobs and runs their executions.
+#[derive(Clone)]
+pub struct Worker {
+    pool: DbPool,
+    queue: SharedQueue,
+    executor: WorkflowExecutor,
+    queues: Vec<String>,
+    tags: Vec<String>,
+    backoff: RetryBackoff,
+    concurrency: usize,
+    grace_period: Duration,
+    lease: Duration,
+}
+
+impl Worker {
+    /// A worker running one job at a time, of every queue, with
+    /// `executor`.
+    pub fn new(pool: DbPool, executor: WorkflowExecutor) -> Self {
+        Self {
+            queue: Arc::new(PgJobQueue::new(pool.clone())),
+            pool,
+            executor,
+            queues: Vec::new(),
+            tags: Vec::new(),
+            backoff: RetryBackoff::standard(),
+            concurrency: 1,
+            grace_period: DEFAULT_GRACE_PERIOD,
+            lease: DEFAULT_LEASE,
+        }
+    }
+
+    /// Claim and settle jobs through `queue` instead of the `job_queue`
+    /// table itself.
+    pub fn with_queue(mut self, queue: SharedQueue) -> Self {
+        self.queue = queue;
+        self
+    }
+
+    /// Only take jobs on one of `queues` (every queue when empty).
+    pub fn with_queues(mut self, queues: Vec<String>) -> Self {
+        self.queues = queues;
+        self
+    }
+
+    /// Declare the worker's capability `tags` (e.g. `gpu`); it only takes
+    /// jobs whose required tags are all among them.  Untagged workers only
+    /// take jobs that require none.
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Delay retries of failed jobs by `backoff` (filled in with the
+    /// built-in defaults), unless their workflow says otherwise.
+    pub fn with_backoff(mut self, backoff: RetryBackoff) -> Self {
+        self.backoff = backoff.or(&RetryBackoff::standard());
+        self
+    }
+
+    /// Run up to `concurrency` jobs at the same time (at least one).  Each
+    /// running job holds a database connection, and more while it writes
+    /// node records, so size the pool for it.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// How long running jobs may take to finish once shutdown begins.
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Lease claimed jobs for `lease`, renewed every third of it while they
+    /// run.  A job whose worker stops renewing is reclaimed once its lease
+    /// expires, so keep it well above any pause a worker may go through.
+    pub fn with_lease(mut self, lease: Duration) -> Self {
+        self.lease = lease.max(Duration::from_secs(1));
+        self
+    }
+
+    /// Claim, run, and settle the next due job; `None` when no job is due.
+    ///
+    /// # Errors
+    /// Queue errors while claiming or settling the job.  Errors of the
+    /// execution itself are returned inside the `Some`.
+    pub async fn run_next(&self) -> Result<Option<Result<ExecutionResult, EngineError>>, EngineError> {
+        match self.claim().await? {
+            Some(job) => self.process(job).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Claim the next due job, leased for [`Worker::with_lease`].
+    async fn claim(&self) -> Result<Option<JobRow>, QueueError> {
+        self.queue.claim(&self.queues, &self.tags, self.lease_end()).await
+    }
+
+    fn lease_end(&self) -> DateTime<Utc> {
+        Utc::now() + chrono::Duration::from_std(self.lease).unwrap_or(chrono::Duration::MAX)
+    }
+
+    /// Run the execution of a claimed `job`, renewing its lease, and
+    /// settle the job.  Everything logged meanwhile is in a `job` span
+    /// carrying the job, execution, and workflow ids.
+    #[instrument(name = "job", skip_all, fields(job_id = %job.id, execution_id = %job.execution_id, workflow_id = %job.workflow_id))]
+    async fn process(&self, job: JobRow) -> Result<Result<ExecutionResult, EngineError>, EngineError> {
+        let heartbeat = async {
+            let mut ticker = tokio::time::interval(self.lease / 3);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                match self.queue.heartbeat(job.id, self.lease_end()).await {
+                    Ok(true) => {}
+                    Ok(false) => warn!("worker: job {} was reclaimed while it ran", job.id),
+                    Err(e) => warn!("worker: heartbeat of job {} failed: {}", job.id, e),
+                }
+            }
+        };
+        // A panic in the execution is reported with the job's workflow and
+        // execution.
+        let tags = Tags { workflow_id: Some(job.workflow_id), execution_id: Some(job.execution_id), node_id: None };
+        let result = tokio::select! {
+            result = error_reporting::tagged(tags, self.execute(&job)) => result,
+            never = heartbeat => never,
+        };
+        match &result {
+            Ok(done) if done.deferred_until.is_some() => {
+                info!("worker: execution {} deferred", job.execution_id);
+                metrics::job_settled("deferred");
+            }
+            Ok(_) => {
+                info!("worker: execution {} succeeded", job.execution_id);
+                metrics::job_settled("succeeded");
+            }
+            Err(e) => {
+                warn!("worker: execution {} failed: {}", job.execution_id, e);
+                metrics::job_settled("failed");
+            }
+        }
+        if retryable(&result) {
+            let retry_at = self.retry_at(&job).await;
+            self.queue.fail(&job, retry_at).await?;
+        } else {
+            self.queue.complete(&job).await?;
+        }
+        Ok(result)
+    }
+
+    /// When the failed `job` may be attempted again, by its workflow's
+    /// back-off policy (or the worker's, when the workflow cannot be
+    /// loaded).
+    async fn retry_at(&self, job: &JobRow) -> DateTime<Utc> {
+        let policy = match load_workflow(&self.pool, job.workflow_id).await {
+            Ok(workflow) => workflow.retry_backoff.map_or_else(|| self.backoff.clone(), |own| own.or(&self.backoff)),
+            Err(_) => self.backoff.clone(),
+        };
+        let attempt = u32::try_from(job.attempts).unwrap_or(1);
+        let delay = policy.delay(attempt, rand::random::<f64>());
+        Utc::now() + chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::MAX)
+    }
+
+    /// Run the execution of `job`.
+    async fn execute(&self, job: &JobRow) -> Result<ExecutionResult, EngineError> {
+        let workflow = load_workflow(&self.pool, job.workflow_id).await?;
+        match Checkpoint::from_job(job)? {
+            Some(checkpoint) => self.executor.resume(&workflow, job.execution_id, checkpoint).await,
+            None => self.executor.run_queued(&workflow, job.execution_id, job.payload.clone()).await,
+        }
+    }
+
+    /// Run jobs until the task is dropped; see [`Worker::run_until`].
+    pub async fn run(self, idle: Duration) {
+        self.run_until(idle, std::future::pending()).await;
+    }
+
+    /// Run jobs until `shutdown` completes, then shut down gracefully.
+    /// While the queues are empty the worker waits for an announced job,
+    /// polling at least every `idle`; it backs off while the database is
+    /// unreachable.
+    ///
+    /// A job is only claimed once one of the `concurrency` slots is free,
+    /// so the worker never holds jobs it is not running yet.
+    pub async fn run_until(self, idle: Duration, shutdown: impl Future<Output = ()>) {
+        tokio::pin!(shutdown);
+        let slots = Arc::new(Semaphore::new(self.concurrency));
+        let mut running = Running::default();
+        let reaper = tokio::spawn(reclaim_expired(Arc::clone(&self.queue), self.lease));
+        let mut listener = self.listen().await;
+        let mut backoff = idle;
+        loop {
+            running.reap();
+            let slot = tokio::select! {
+                _ = &mut shutdown => break,
+                slot = Arc::clone(&slots).acquire_owned() => slot.expect("the semaphore is never closed"),
+            };
+            match self.claim().await {
+                Ok(Some(job)) => {
+                    backoff = idle;
+                    let worker = self.clone();
+                    running.spawn(job.id, async move {
+                        let job_id = job.id;
+                        if let Err(e) = worker.process(job).await {
+                            warn!("worker: cannot settle job {}: {}", job_id, e);
+                        }
+                        drop(slot);
+                    });
+                    continue;
+                }
+                Ok(None) => {
+                    backoff = idle;
+                    drop(slot);
+                    let wait = self.idle_wait(idle).await;
+                    if listener.is_none() {
+                        listener = self.listen().await;
+                    }
+                    tokio::select! {
+                        _ = &mut shutdown => break,
+                        _ = wait_for_job(&mut listener, &self.queues, wait) => {}
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    warn!("worker: cannot take jobs: {}; retrying in {:?}", e, backoff);
+                    backoff = (backoff * 2).clamp(idle, MAX_BACKOFF);
+                }
+            }
+            drop(slot);
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = tokio::time::sleep(backoff) => {}
+            }
+        }
+
+        drop(listener);
+        reaper.abort();
+        info!(
+            "worker: shutting down; waiting up to {:?} for {} running jobs",
+            self.grace_period,
+            running.tasks.len()
+        );
+        for job_id in running.stop(self.grace_period).await {
+            match self.queue.release(job_id).await {
+                Ok(()) => info!("worker: job {} put back in the queue", job_id),
+                Err(e) => warn!("worker: cannot put job {} back in the queue: {}", job_id, e),
+            }
+        }
+    }
+}
+
+impl Worker {
+    /// A listener for announced jobs; `None` (and a warning) when it cannot
+    /// connect.
+    async fn listen(&self) -> Option<Listener> {
+        let connected = async {
+            let mut listener = Listener::connect_with(&self.pool).await?;
+            listener.listen(JOBS_CHANNEL).await?;
+            Ok::<_, DbError>(listener)
+        };
+        match connected.await {
+            Ok(listener) => Some(listener),
+            Err(e) => {
+                warn!("worker: cannot listen for jobs: {}; polling instead", e);
+                None
+            }
+        }
+    }
+
+    /// How long an idle worker waits: until the next delayed job is due,
+    /// and at most `idle`.
+    async fn idle_wait(&self, idle: Duration) -> Duration {
+        match job_repo::next_due_at(&self.pool, &self.queues, &self.tags).await {
+            Ok(Some(at)) => (at - Utc::now()).to_std().unwrap_or_default().clamp(MIN_WAIT, idle.max(MIN_WAIT)),
+            _ => idle,
+        }
+    }
+}
+
+/// Reclaim jobs with an expired lease every `lease`.
+async fn reclaim_expired(queue: SharedQueue, lease: Duration) {
+    let mut ticker = tokio::time::interval(lease);
+    loop {
+        ticker.tick().await;
+        match queue.reclaim_expired().await {
+            Ok(jobs) => {
+                for job in jobs {
+                    warn!("worker: job {} lost its worker and is now {}", job.id, job.status);
+                }
+            }
+            Err(e) => warn!("worker: cannot reclaim expired jobs: {}", e),
+        }
+    }
+}
+
+/// Wait up to `timeout` for a job to be announced on one of `queues`
+/// (any queue when empty).  A failed listener is dropped, to be
+/// reconnected before the next wait.
+async fn wait_for_job(listener: &mut Option<Listener>, queues: &[String], timeout: Duration) {
+    let Some(active) = listener.as_mut() else {
+        tokio::time::sleep(timeout).await;
+        return;
+    };
+    let announced = async {
+        loop {
+            let notification = active.recv().await?;
+            if queues.is_empty() || queues.contains(&notification.payload) {
+                return Ok::<(), DbError>(());
+            }
+        }
+    };
+    let outcome = tokio::time::timeout(timeout, announced).await;
+    if let Ok(Err(e)) = outcome {
+        warn!("worker: job listener failed: {}; polling until it reconnects", e);
+        *listener = None;
+    }
+}
+
+/// The tasks running claimed jobs, and their job ids.
+#[derive(Default)]
+struct Running {
+    tasks: JoinSet<()>,
+    jobs: HashMap<task::Id, Uuid>,
+}
+
+impl Running {
+    fn spawn(&mut self, job_id: Uuid, task: impl Future<Output = ()> + Send + 'static) {
+        let handle = self.tasks.spawn(task);
+        self.jobs.insert(handle.id(), job_id);
+    }
+
+    /// Forget the tasks that are done.
+    fn reap(&mut self) {
+        while let Some(done) = self.tasks.try_join_next_with_id() {
+            self.finished(done);
+        }
+    }
+
+    fn finished(&mut self, done: Result<(task::Id, ()), task::JoinError>) {
+        match done {
+            Ok((id, ())) => {
+                self.jobs.remove(&id);
+            }
+            // Stopped at shutdown: the job goes back to the queue.
+            Err(e) if e.is_cancelled() => {}
+            Err(e) => {
+                if let Some(job_id) = self.jobs.remove(&e.id()) {
+                    warn!("worker: job {} panicked and stays claimed", job_id);
+                }
+            }
+        }
+    }
+
+    /// Wait up to `grace_period` for the tasks to finish, then stop the
+    /// rest; returns the jobs of the stopped tasks.
+    async fn stop(mut self, grace_period: Duration) -> Vec<Uuid> {
+        let deadline = tokio::time::sleep(grace_period);
+        tokio::pin!(deadline);
+        loop {
+            tokio::select! {
+                done = self.tasks.join_next_with_id() => match done {
+                    Some(done) => self.finished(done),
+                    None => return Vec::new(),
+                },
+                _ = &mut deadline => break,
+            }
+        }
+        // A task may finish before it sees the abort; only the cancelled
+        // ones keep their job.
+        self.tasks.abort_all();
+        while let Some(done) = self.tasks.join_next_with_id().await {
+            self.finished(done);
+        }
+        self.jobs.into_values().collect()
+    }
+}
+
+/// Whether the job of `result` should be attempted again: only
+/// infrastructure errors are.
+fn retryable(result: &Result<ExecutionResult, EngineError>) -> bool {
+    matches!(result, Err(EngineError::Database(_) | EngineError::Queue(_)))
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_infrastructure_errors_are_retried() {
+        assert!(retryable(&Err(EngineError::Database(db::DbError::NotFound))));
+        let finished = ExecutionResult {
+            execution_id: uuid::Uuid::nil(),
+            output: serde_json::Value::Null,
+            deferred_until: None,
+        };
+        assert!(!retryable(&Ok(finished)));
+        assert!(!retryable(&Err(EngineError::InvalidInputSchema { message: "bad".into() })));
+    }
+}
@@ -0,0 +1,117 @@
+//! Golden-file compatibility tests for the workflow definition format.
+//!
+//! `tests/golden/<version>/` holds definitions as users stored them when
+//! that version of the format was current (JSON, or YAML for definitions
+//! kept in config repositories).  Every fixture must still:
+//!
+//! 1. deserialize into a [`Workflow`],
+//! 2. keep every field it sets when serialized again — a renamed or removed
+//!    field would otherwise be dropped without an error,
+//! 3. validate, with the topological order recorded in
+//!    `tests/golden/expected.json`.
+//!
+//! Fixtures are never edited once committed.  When the format changes, add
+//! fixtures under a new version directory instead.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use engine::{validate_dag, Workflow};
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+/// Every fixture, as its path relative to the golden directory.
+fn fixtures() -> Vec<String> {
+    let root = golden_dir();
+    let mut found = Vec::new();
+    for version in std::fs::read_dir(&root).unwrap() {
+        let version = version.unwrap().path();
+        if !version.is_dir() {
+            continue;
+        }
+        for file in std::fs::read_dir(&version).unwrap() {
+            let file = file.unwrap().path();
+            found.push(file.strip_prefix(&root).unwrap().to_string_lossy().replace('\\', "/"));
+        }
+    }
+    found.sort();
+    found
+}
+
+fn expected_orders() -> BTreeMap<String, Vec<String>> {
+    let text = std::fs::read_to_string(golden_dir().join("expected.json")).unwrap();
+    serde_json::from_str(&text).unwrap()
+}
+
+/// The fixture as plain JSON, whatever its file format.
+fn load(fixture: &str) -> Value {
+    let text = std::fs::read_to_string(golden_dir().join(fixture)).unwrap();
+    match Path::new(fixture).extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::from_str(&text).unwrap(),
+        Some("yaml" | "yml") => serde_yaml::from_str(&text).unwrap(),
+        other => panic!("{fixture}: unsupported fixture format {other:?}"),
+    }
+}
+
+/// Paths of values in `original` that `reserialized` lost or changed.
+fn lost_fields(original: &Value, reserialized: &Value, path: &str, lost: &mut Vec<String>) {
+    match (original, reserialized) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, value) in a {
+                let path = format!("{path}.{key}");
+                match b.get(key) {
+                    Some(other) => lost_fields(value, other, &path, lost),
+                    None => lost.push(path),
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                lost_fields(x, y, &format!("{path}[{i}]"), lost);
+            }
+        }
+        (a, b) if a == b => {}
+        _ => lost.push(path.to_owned()),
+    }
+}
+
+#[test]
+fn every_fixture_has_an_expected_order() {
+    let expected: Vec<String> = expected_orders().into_keys().collect();
+    assert_eq!(fixtures(), expected, "fixtures and expected.json are out of sync");
+}
+
+#[test]
+fn fixtures_still_deserialize_round_trip_and_validate() {
+    let expected = expected_orders();
+    let mut failures = Vec::new();
+
+    for fixture in fixtures() {
+        let raw = load(&fixture);
+        let workflow: Workflow = match serde_json::from_value(raw.clone()) {
+            Ok(w) => w,
+            Err(e) => {
+                failures.push(format!("{fixture}: no longer deserializes: {e}"));
+                continue;
+            }
+        };
+
+        let mut lost = Vec::new();
+        lost_fields(&raw, &serde_json::to_value(&workflow).unwrap(), "", &mut lost);
+        if !lost.is_empty() {
+            failures.push(format!("{fixture}: fields lost on round trip: {lost:?}"));
+        }
+
+        match validate_dag(&workflow) {
+            Ok(order) if Some(&order) == expected.get(&fixture) => {}
+            Ok(order) => failures.push(format!("{fixture}: order changed to {order:?}")),
+            Err(e) => failures.push(format!("{fixture}: no longer validates: {e}")),
+        }
+    }
+
+    assert!(failures.is_empty(), "golden fixtures broke:\n{}", failures.join("\n"));
+}
@@ -0,0 +1,56 @@
+//! `nodes-macros` — the `#[register_node]` attribute.
+//!
+//! Use it through the re-export `nodes::register_node`; the expansion refers
+//! to items in the `nodes` crate by absolute path.
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, DeriveInput, LitStr};
+
+/// Register a node type in the default registry under `node_type`.
+///
+/// ```ignore
+/// use nodes::register_node;
+///
+/// #[register_node("slack")]
+/// #[derive(Debug, Default)]
+/// pub struct SlackNode;
+/// ```
+///
+/// The annotated type must implement `ExecutableNode` and `Default`; the
+/// registry builds one instance with `Default::default()`.  Generic types
+/// cannot be registered.
+#[proc_macro_attribute]
+pub fn register_node(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let node_type = parse_macro_input!(attr as LitStr);
+    let input = parse_macro_input!(item as DeriveInput);
+
+    if node_type.value().is_empty() {
+        return syn::Error::new(node_type.span(), "node type must not be empty")
+            .to_compile_error()
+            .into();
+    }
+    if !input.generics.params.is_empty() {
+        return syn::Error::new_spanned(&input.generics, "generic node types cannot be registered")
+            .to_compile_error()
+            .into();
+    }
+
+    let ident = &input.ident;
+    let factory = format_ident!("__register_node_{}", ident);
+    quote! {
+        #input
+
+        const _: () = {
+            #[allow(non_snake_case)]
+            fn #factory() -> ::std::sync::Arc<dyn ::nodes::ExecutableNode> {
+                ::std::sync::Arc::new(<#ident as ::core::default::Default>::default())
+            }
+
+            ::nodes::inventory::submit! {
+                ::nodes::registry::NodeRegistration::new(#node_type, #factory)
+            }
+        };
+    }
+    .into()
+}
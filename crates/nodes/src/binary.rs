@@ -0,0 +1,165 @@
+//! Binary data (files, images, audio) passed between nodes by reference.
+//!
+//! Node payloads are JSON, so file contents used to travel base64-encoded
+//! through every input and output.  A node holding bytes instead stores
+//! them in the [`BinaryStore`] handed to it in
+//! [`ExecutionContext::binary`](crate::traits::ExecutionContext), and
+//! passes on the small [`BinaryRef`] it gets back:
+//!
+//! ```json
+//! { "$binary": "3f0c…", "mime_type": "application/pdf", "file_name": "invoice.pdf", "size": 48213 }
+//! ```
+//!
+//! Downstream nodes load the bytes with [`BinaryStore::get`].  The engine's
+//! store keeps blobs on disk or in S3 and deletes them with the execution
+//! that stored them; [`InMemoryBinaryStore`] is available for tests and
+//! local runs.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::NodeError;
+
+/// Key marking a JSON object as a [`BinaryRef`].
+pub const BINARY_KEY: &str = "$binary";
+
+/// A stored blob, as it appears in node payloads.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BinaryRef {
+    #[serde(rename = "$binary")]
+    pub id: Uuid,
+    pub mime_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    /// Length in bytes.
+    pub size: u64,
+}
+
+impl BinaryRef {
+    /// The reference in `value`, if it is one.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        value.get(BINARY_KEY)?;
+        serde_json::from_value(value.clone()).ok()
+    }
+
+    /// The reference as a payload value.
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).expect("a binary reference serializes")
+    }
+}
+
+/// MIME type for a file named `file_name`, by extension;
+/// `application/octet-stream` when unknown.
+pub fn guess_mime_type(file_name: &str) -> &'static str {
+    let extension = file_name.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("pdf") => "application/pdf",
+        Some("json") => "application/json",
+        Some("xml") => "application/xml",
+        Some("zip") => "application/zip",
+        Some("gz") => "application/gzip",
+        Some("csv") => "text/csv",
+        Some("txt" | "log") => "text/plain",
+        Some("html" | "htm") => "text/html",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        Some("mp3") => "audio/mpeg",
+        Some("wav") => "audio/wav",
+        Some("ogg") => "audio/ogg",
+        Some("mp4") => "video/mp4",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Blob storage for the binary data of executions.
+#[async_trait]
+pub trait BinaryStore: Send + Sync + std::fmt::Debug {
+    /// Store `data` on behalf of execution `execution_id`; the blob lives
+    /// as long as the execution.
+    async fn put(
+        &self,
+        execution_id: Uuid,
+        data: Vec<u8>,
+        mime_type: &str,
+        file_name: Option<&str>,
+    ) -> Result<BinaryRef, NodeError>;
+
+    /// Load the bytes of `reference` on behalf of execution
+    /// `execution_id`; stores that know who owns a blob refuse blobs of
+    /// other projects.
+    async fn get(&self, execution_id: Uuid, reference: &BinaryRef) -> Result<Vec<u8>, NodeError>;
+}
+
+/// Process-local binary store backed by a mutex-guarded map.
+#[derive(Debug, Default)]
+pub struct InMemoryBinaryStore {
+    blobs: Mutex<HashMap<Uuid, Vec<u8>>>,
+}
+
+#[async_trait]
+impl BinaryStore for InMemoryBinaryStore {
+    async fn put(
+        &self,
+        _execution_id: Uuid,
+        data: Vec<u8>,
+        mime_type: &str,
+        file_name: Option<&str>,
+    ) -> Result<BinaryRef, NodeError> {
+        let reference = BinaryRef {
+            id: Uuid::new_v4(),
+            mime_type: mime_type.to_owned(),
+            file_name: file_name.map(str::to_owned),
+            size: data.len() as u64,
+        };
+        self.blobs.lock().unwrap().insert(reference.id, data);
+        Ok(reference)
+    }
+
+    async fn get(&self, _execution_id: Uuid, reference: &BinaryRef) -> Result<Vec<u8>, NodeError> {
+        self.blobs
+            .lock()
+            .unwrap()
+            .get(&reference.id)
+            .cloned()
+            .ok_or_else(|| NodeError::Fatal(format!("binary data {} does not exist", reference.id)))
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn references_round_trip_through_payloads() {
+        let store = InMemoryBinaryStore::default();
+        let reference = store.put(Uuid::new_v4(), b"%PDF".to_vec(), "application/pdf", Some("a.pdf")).await.unwrap();
+
+        let value = reference.to_value();
+        assert_eq!(value["$binary"], json!(reference.id));
+        assert_eq!(value["size"], 4);
+        assert_eq!(BinaryRef::from_value(&value), Some(reference.clone()));
+        assert_eq!(store.get(Uuid::new_v4(), &reference).await.unwrap(), b"%PDF");
+
+        assert_eq!(BinaryRef::from_value(&json!({ "mime_type": "text/plain", "size": 1 })), None);
+        assert_eq!(BinaryRef::from_value(&json!("text")), None);
+    }
+
+    #[test]
+    fn mime_types_are_guessed_from_the_extension() {
+        assert_eq!(guess_mime_type("/inbound/Report.PDF"), "application/pdf");
+        assert_eq!(guess_mime_type("orders.csv"), "text/csv");
+        assert_eq!(guess_mime_type("README"), "application/octet-stream");
+    }
+}
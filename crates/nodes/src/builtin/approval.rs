@@ -0,0 +1,340 @@
+//! `approval` node — pause the execution until a person approves or rejects.
+//!
+//! ```json
+//! {
+//!   "message": "Refund of {{ input.amount }} for order {{ input.order_id }}",
+//!   "timeout_secs": 172800,
+//!   "on_timeout": "reject"
+//! }
+//! ```
+//!
+//! The first run creates a pending approval with a random token, stored in
+//! workflow state under [`state_key`], and defers the execution until the
+//! approval times out.  The API exposes the pending approval (with
+//! ready-made approve/reject links carrying the token, for editors) and
+//! records the decision, which releases the deferred execution right away:
+//!
+//! * `GET  /api/v1/executions/:id/approvals/:node_id`
+//! * `POST /api/v1/executions/:id/approvals/:node_id` with
+//!   `{ "token": "...", "decision": "approve" | "reject", "comment": "..." }`
+//! * `GET  /api/v1/executions/:id/approvals/:node_id/approve?token=...` (and
+//!   `/reject`) for links sent by email or chat: a page whose button posts
+//!   the decision
+//!
+//! When the execution resumes, the node routes the flow down edges labelled
+//! `approved` or `rejected` and outputs
+//! `{ "decision": "approved", "actor": "...", "comment": "...", "decided_at": "...", "timed_out": false, "input": ... }`.
+//!
+//! Without a decision before `timeout_secs` (default 7 days, at most a
+//! year) the node takes the `rejected` branch with `timed_out: true`, or
+//! fails with `on_timeout: "fail"`.
+
+use async_trait::async_trait;
+use base64::Engine as _;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::builtin::parse_config;
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, template, traits::ExecutionContext};
+
+/// What happens when nobody decides in time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OnTimeout {
+    /// Take the `rejected` branch.
+    #[default]
+    Reject,
+    /// Fail the execution.
+    Fail,
+}
+
+/// Configuration for the `approval` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ApprovalConfig {
+    /// Template rendered against `{ "input": ... }`, shown to approvers.
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default)]
+    pub on_timeout: OnTimeout,
+}
+
+fn default_timeout_secs() -> u64 {
+    7 * 24 * 3600
+}
+
+/// Longer timeouts are capped to a year.
+const MAX_TIMEOUT_SECS: u64 = 365 * 24 * 3600;
+
+/// Lifecycle of an approval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// A pending or decided approval.  Kept in workflow state in the form of
+/// [`ApprovalRecord::to_state`]; serialized any other way it leaves out
+/// the token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalRecord {
+    /// Secret that a decision must present.
+    #[serde(skip_serializing)]
+    pub token: String,
+    pub status: ApprovalStatus,
+    #[serde(default)]
+    pub message: Option<String>,
+    pub requested_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    #[serde(default)]
+    pub actor: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+impl ApprovalRecord {
+    /// The record as kept in workflow state: serialized with its token.
+    pub fn to_state(&self) -> Value {
+        let mut value = serde_json::to_value(self).expect("approval records serialize");
+        value["token"] = Value::String(self.token.clone());
+        value
+    }
+}
+
+/// Workflow state key of the approval requested by `node_id` in an execution.
+pub fn state_key(execution_id: Uuid, node_id: &str) -> String {
+    format!("approval:{execution_id}:{node_id}")
+}
+
+/// What the node does on this run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    /// Still pending: suspend until this time.
+    Wait(DateTime<Utc>),
+    /// Decided (or timed out): the finished record.
+    Done { record: ApprovalRecord, timed_out: bool },
+}
+
+/// The `approval` node.
+#[register_node("approval")]
+#[derive(Debug, Default)]
+pub struct ApprovalNode;
+
+#[async_trait]
+impl ExecutableNode for ApprovalNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: ApprovalConfig = parse_config("approval", ctx)?;
+        let message = match &config.message {
+            Some(tpl) => Some(template::render(tpl, &json!({ "input": input })).map_err(|e| {
+                NodeError::Fatal(format!("approval: invalid message template: {e}"))
+            })?),
+            None => None,
+        };
+
+        let now = ctx.now();
+        let fresh = ApprovalRecord {
+            token: new_token(),
+            status: ApprovalStatus::Pending,
+            message,
+            requested_at: now,
+            expires_at: now + Duration::seconds(config.timeout_secs.min(MAX_TIMEOUT_SECS) as i64),
+            actor: None,
+            comment: None,
+            decided_at: None,
+        };
+
+        let key = state_key(ctx.execution_id, &ctx.node_id);
+        let result = ctx
+            .require_state()?
+            .update(
+                ctx.workflow_id,
+                &key,
+                Box::new(move |current| {
+                    let current = current.and_then(|v| serde_json::from_value(v).ok());
+                    let (keep, step) = step(current, fresh, now);
+                    let result = match &step {
+                        Step::Wait(until) => json!({ "wait_until": until }),
+                        Step::Done { record, timed_out } => {
+                            json!({ "record": record.to_state(), "timed_out": timed_out })
+                        }
+                    };
+                    (keep.map(|r| r.to_state()), result)
+                }),
+            )
+            .await?;
+
+        if let Some(until) = result.get("wait_until") {
+            let until = serde_json::from_value(until.clone())
+                .map_err(|e| NodeError::Fatal(format!("approval: bad state: {e}")))?;
+            ctx.defer_until(until);
+            return Ok(json!({ "status": "pending", "expires_at": until }));
+        }
+
+        let record: ApprovalRecord = serde_json::from_value(result["record"].clone())
+            .map_err(|e| NodeError::Fatal(format!("approval: bad state: {e}")))?;
+        let timed_out = result["timed_out"] == Value::Bool(true);
+        if timed_out && config.on_timeout == OnTimeout::Fail {
+            return Err(NodeError::Fatal(format!(
+                "approval for node '{}' timed out at {}",
+                ctx.node_id, record.expires_at
+            )));
+        }
+
+        let decision = match record.status {
+            ApprovalStatus::Approved => "approved",
+            _ => "rejected",
+        };
+        ctx.branch(decision);
+        Ok(json!({
+            "decision": decision,
+            "actor": record.actor,
+            "comment": record.comment,
+            "decided_at": record.decided_at,
+            "timed_out": timed_out,
+            "input": input,
+        }))
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("Approval", "Pause the execution until a person approves or rejects.")
+            .category(NodeCategory::Flow)
+            .config::<ApprovalConfig>()
+            .output("{ decision, actor, comment, decided_at, timed_out, input }")
+            .branches(["approved", "rejected"])
+    }
+}
+
+/// Decide the next step from the stored record.
+///
+/// Returns the record to keep in state (`None` once it has been consumed)
+/// and the step.  `fresh` is stored when no approval exists yet.
+fn step(
+    current: Option<ApprovalRecord>,
+    fresh: ApprovalRecord,
+    now: DateTime<Utc>,
+) -> (Option<ApprovalRecord>, Step) {
+    match current {
+        None => {
+            let until = fresh.expires_at;
+            (Some(fresh), Step::Wait(until))
+        }
+        Some(record) if record.status != ApprovalStatus::Pending => {
+            (None, Step::Done { record, timed_out: false })
+        }
+        Some(record) if now < record.expires_at => {
+            let until = record.expires_at;
+            (Some(record), Step::Wait(until))
+        }
+        Some(mut record) => {
+            record.status = ApprovalStatus::Rejected;
+            record.decided_at = Some(now);
+            (None, Step::Done { record, timed_out: true })
+        }
+    }
+}
+
+/// 192 random bits from the OS, URL-safe.
+fn new_token() -> String {
+    let mut bytes = [0u8; 24];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::state::{InMemoryStateStore, WorkflowStateStore};
+    use crate::traits::Flow;
+
+    fn record(status: ApprovalStatus, expires_at: DateTime<Utc>) -> ApprovalRecord {
+        ApprovalRecord {
+            token: "t".into(),
+            status,
+            message: None,
+            requested_at: expires_at - Duration::hours(1),
+            expires_at,
+            actor: None,
+            comment: None,
+            decided_at: None,
+        }
+    }
+
+    #[test]
+    fn pending_approvals_wait_until_decided_or_expired() {
+        let now = Utc::now();
+        let expires = now + Duration::hours(1);
+
+        let pending = record(ApprovalStatus::Pending, expires);
+        let fresh = || record(ApprovalStatus::Pending, now + Duration::days(7));
+
+        // First run stores the fresh approval; later runs keep waiting on it.
+        let (kept, next) = step(None, pending.clone(), now);
+        assert_eq!((kept.is_some(), next), (true, Step::Wait(expires)));
+        let (kept, next) = step(Some(pending.clone()), fresh(), now);
+        assert_eq!((kept, next), (Some(pending.clone()), Step::Wait(expires)));
+
+        let approved = record(ApprovalStatus::Approved, expires);
+        let (kept, next) = step(Some(approved.clone()), fresh(), now);
+        assert_eq!((kept, next), (None, Step::Done { record: approved, timed_out: false }));
+
+        let later = expires + Duration::seconds(1);
+        let (kept, next) = step(Some(pending), fresh(), later);
+        assert!(kept.is_none());
+        let Step::Done { record, timed_out } = next else { panic!("expected a timeout") };
+        assert_eq!((record.status, timed_out), (ApprovalStatus::Rejected, true));
+    }
+
+    #[test]
+    fn only_the_stored_form_carries_the_token() {
+        let pending = record(ApprovalStatus::Pending, Utc::now());
+        assert_eq!(serde_json::to_value(&pending).unwrap().get("token"), None);
+        let stored = pending.to_state();
+        assert_eq!(stored["token"], "t");
+        assert_eq!(serde_json::from_value::<ApprovalRecord>(stored).unwrap(), pending);
+    }
+
+    #[tokio::test]
+    async fn defers_then_follows_the_decision() {
+        let store = Arc::new(InMemoryStateStore::default());
+        let ctx = ExecutionContext::new(Uuid::new_v4(), Uuid::new_v4(), json!({}))
+            .with_state(store.clone())
+            .for_node("approve_refund", json!({ "message": "Refund {{ input.amount }}" }));
+        let input = json!({ "amount": 40 });
+
+        ApprovalNode.execute(input.clone(), &ctx).await.unwrap();
+        assert!(matches!(ctx.take_flow(), Flow::Defer(_)));
+
+        let key = state_key(ctx.execution_id, "approve_refund");
+        let mut stored: ApprovalRecord =
+            serde_json::from_value(store.get(ctx.workflow_id, &key).await.unwrap().unwrap()).unwrap();
+        assert_eq!(stored.message.as_deref(), Some("Refund 40"));
+        assert_eq!(stored.token.len(), 32);
+
+        // What the API does when someone approves.
+        stored.status = ApprovalStatus::Approved;
+        stored.actor = Some("dana".into());
+        let value = stored.to_state();
+        store.update(ctx.workflow_id, &key, Box::new(move |_| (Some(value), Value::Null))).await.unwrap();
+
+        let out = ApprovalNode.execute(input.clone(), &ctx).await.unwrap();
+        assert_eq!(ctx.take_flow(), Flow::Branch("approved".into()));
+        assert_eq!((out["decision"].as_str(), out["actor"].as_str()), (Some("approved"), Some("dana")));
+        assert_eq!(out["input"], input);
+        assert_eq!(store.get(ctx.workflow_id, &key).await.unwrap(), None);
+    }
+}
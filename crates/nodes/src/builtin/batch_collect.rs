@@ -0,0 +1,435 @@
+//! `batch_collect` node — accumulate items across executions and release
+//! them downstream in batches.
+//!
+//! Each execution appends one item (the whole input, or `input[item_field]`)
+//! to a buffer kept in workflow state.  When the buffer reaches `max_items`,
+//! or its oldest item is older than `max_age_secs`, the whole batch is
+//! emitted; otherwise the node halts the flow so downstream nodes (e.g.
+//! "send digest email") do not run.
+//!
+//! ```json
+//! { "max_items": 200, "max_age_secs": 3600 }
+//! ```
+//!
+//! Output on release:
+//! `{ "released": true, "reason": "size" | "age" | "redelivery", "count": 200, "items": [...] }`
+//!
+//! The age threshold is evaluated when an item arrives, so a quiet workflow
+//! releases its last partial batch with the next execution.
+//!
+//! # Delivery
+//!
+//! By default (`"delivery": "at_most_once"`) the buffer is cleared as the
+//! batch is released, before the downstream nodes run: if one of them
+//! fails, the batch is lost.  With `"delivery": "at_least_once"` the batch
+//! stays buffered until a second `batch_collect` node with the same `key`
+//! and `"acknowledge": true`, placed after the downstream nodes, runs in the
+//! execution that released it.  New items keep being buffered meanwhile; a
+//! batch still unacknowledged `redeliver_after_secs` after its release is
+//! released again, with them, by the next item — so a batch can be
+//! delivered twice, never lost.  Retrying the failed execution also
+//! delivers and acknowledges it.
+//!
+//! ```json
+//! { "key": "digest", "max_items": 200, "delivery": "at_least_once" }
+//! { "key": "digest", "acknowledge": true }
+//! ```
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use uuid::Uuid;
+
+use crate::builtin::parse_config;
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, template, traits::ExecutionContext};
+
+/// Configuration for the `batch_collect` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct BatchCollectConfig {
+    /// State key of the buffer; defaults to the node ID.  Use the same key
+    /// in several workflows' nodes only if they share a workflow.
+    /// Required to acknowledge.
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Release once this many items are buffered.
+    #[serde(default)]
+    pub max_items: Option<usize>,
+    /// Release once the oldest buffered item is this old.
+    #[serde(default)]
+    pub max_age_secs: Option<u64>,
+    /// Dotted input path of the item to buffer; the whole input if unset.
+    #[serde(default)]
+    pub item_field: Option<String>,
+    /// Whether a released batch is cleared at once or kept until it is
+    /// acknowledged.
+    #[serde(default)]
+    pub delivery: Delivery,
+    /// Acknowledge the batch this execution released, instead of
+    /// collecting an item.
+    #[serde(default)]
+    pub acknowledge: bool,
+    /// With `at_least_once` delivery, how long a released batch may go
+    /// unacknowledged before the next item releases it again.
+    #[serde(default = "default_redeliver_after_secs")]
+    pub redeliver_after_secs: u64,
+}
+
+fn default_redeliver_after_secs() -> u64 {
+    300
+}
+
+/// What happens to a released batch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Delivery {
+    /// Cleared as it is released; lost if a downstream node fails.
+    #[default]
+    AtMostOnce,
+    /// Kept until acknowledged, and released again if it is not.
+    AtLeastOnce,
+}
+
+/// The `batch_collect` node.
+#[register_node("batch_collect")]
+#[derive(Debug, Default)]
+pub struct BatchCollectNode;
+
+#[async_trait]
+impl ExecutableNode for BatchCollectNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: BatchCollectConfig = parse_config("batch_collect", ctx)?;
+        if config.acknowledge {
+            let key = config.key.as_deref().ok_or_else(|| {
+                NodeError::Fatal("batch_collect: `acknowledge` needs the `key` of the collecting node".into())
+            })?;
+            let execution_id = ctx.execution_id;
+            let now = ctx.now();
+            return ctx
+                .require_state()?
+                .update(
+                    ctx.workflow_id,
+                    &format!("batch_collect:{key}"),
+                    Box::new(move |state| acknowledge(state, execution_id, now)),
+                )
+                .await;
+        }
+        if config.max_items.is_none() && config.max_age_secs.is_none() {
+            return Err(NodeError::Fatal(
+                "batch_collect needs `max_items` and/or `max_age_secs`".into(),
+            ));
+        }
+
+        let item = match &config.item_field {
+            Some(path) => template::lookup(&input, path).cloned().ok_or_else(|| {
+                NodeError::Fatal(format!("batch_collect: input has no field '{path}'"))
+            })?,
+            None => input,
+        };
+
+        let (execution_id, now) = (ctx.execution_id, ctx.now());
+        let ages = [("max_age_secs", config.max_age_secs), ("redeliver_after_secs", Some(config.redeliver_after_secs))];
+        for (field, secs) in ages {
+            if secs.is_some_and(|secs| cutoff(now, secs).is_none()) {
+                return Err(NodeError::Fatal(format!("batch_collect: `{field}` is out of range")));
+            }
+        }
+
+        let key = format!("batch_collect:{}", config.key.as_deref().unwrap_or(&ctx.node_id));
+        let output = ctx
+            .require_state()?
+            .update(
+                ctx.workflow_id,
+                &key,
+                Box::new(move |state| collect(state, item, &config, execution_id, now)),
+            )
+            .await?;
+
+        if output["released"] != Value::Bool(true) {
+            ctx.halt();
+        }
+        Ok(output)
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("Batch collect", "Accumulate items across executions and release them in batches.")
+            .category(NodeCategory::Flow)
+            .config::<BatchCollectConfig>()
+            .output(
+                "{ released, reason, count, items }; halts until a batch is released; \
+                 { acknowledged, count } when acknowledging",
+            )
+    }
+}
+
+/// A batch released with `at_least_once` delivery and not yet
+/// acknowledged: the first `count` buffered items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, Deserialize)]
+struct Release {
+    execution_id: Uuid,
+    count: usize,
+    at: DateTime<Utc>,
+}
+
+/// The time `secs` seconds before `now`, if it can be represented.
+fn cutoff(now: DateTime<Utc>, secs: u64) -> Option<DateTime<Utc>> {
+    let age = i64::try_from(secs).ok().and_then(Duration::try_seconds)?;
+    now.checked_sub_signed(age)
+}
+
+fn pending_release(state: Option<&Value>) -> Option<Release> {
+    state.and_then(|s| s.get("release")).and_then(|r| serde_json::from_value(r.clone()).ok())
+}
+
+/// Append `item` to the buffer in `state` and decide whether to release.
+///
+/// Returns the new state (`None` once released with `at_most_once`
+/// delivery) and the node output.
+fn collect(
+    state: Option<Value>,
+    item: Value,
+    config: &BatchCollectConfig,
+    execution_id: Uuid,
+    now: DateTime<Utc>,
+) -> (Option<Value>, Value) {
+    let mut items = state
+        .as_ref()
+        .and_then(|s| s.get("items"))
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let first_added_at = state
+        .as_ref()
+        .and_then(|s| s.get("first_added_at"))
+        .and_then(Value::as_str)
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or(now);
+
+    let release = pending_release(state.as_ref());
+
+    items.push(item);
+
+    // `execute` refuses ages without a cutoff.
+    let older_than = |since: DateTime<Utc>, secs: u64| cutoff(now, secs).is_some_and(|cutoff| since <= cutoff);
+    let reason = match release {
+        // A batch is out for delivery: wait for its acknowledgement.
+        Some(release) if older_than(release.at, config.redeliver_after_secs) => Some("redelivery"),
+        Some(_) => None,
+        None if config.max_items.is_some_and(|max| items.len() >= max) => Some("size"),
+        None if config.max_age_secs.is_some_and(|max| older_than(first_added_at, max)) => Some("age"),
+        None => None,
+    };
+
+    let Some(reason) = reason else {
+        let pending = items.len();
+        let mut new_state = json!({
+            "items": items,
+            "first_added_at": first_added_at.to_rfc3339(),
+        });
+        if let Some(release) = release {
+            new_state["release"] = json!(release);
+        }
+        return (Some(new_state), json!({ "released": false, "pending": pending }));
+    };
+
+    let new_state = (config.delivery == Delivery::AtLeastOnce).then(|| {
+        json!({
+            "items": items,
+            "first_added_at": first_added_at.to_rfc3339(),
+            "release": Release { execution_id, count: items.len(), at: now },
+        })
+    });
+    let output = json!({
+        "released": true,
+        "reason": reason,
+        "count": items.len(),
+        "items": items,
+    });
+    (new_state, output)
+}
+
+/// Drop the batch execution `execution_id` released from the buffer in
+/// `state`; nothing changes unless it released the pending batch.
+fn acknowledge(state: Option<Value>, execution_id: Uuid, now: DateTime<Utc>) -> (Option<Value>, Value) {
+    let release = pending_release(state.as_ref()).filter(|release| release.execution_id == execution_id);
+    let Some(release) = release else {
+        return (state, json!({ "acknowledged": false, "count": 0 }));
+    };
+
+    let mut items =
+        state.as_ref().and_then(|s| s.get("items")).and_then(Value::as_array).cloned().unwrap_or_default();
+    items.drain(..release.count.min(items.len()));
+    // What is left arrived after the release.
+    let new_state = (!items.is_empty()).then(|| {
+        json!({
+            "items": items,
+            "first_added_at": release.at.min(now).to_rfc3339(),
+        })
+    });
+    (new_state, json!({ "acknowledged": true, "count": release.count }))
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::InMemoryStateStore;
+    use crate::traits::Flow;
+    use std::sync::Arc;
+
+    fn config(max_items: Option<usize>, max_age_secs: Option<u64>) -> BatchCollectConfig {
+        BatchCollectConfig {
+            key: None,
+            max_items,
+            max_age_secs,
+            item_field: None,
+            delivery: Delivery::AtMostOnce,
+            acknowledge: false,
+            redeliver_after_secs: default_redeliver_after_secs(),
+        }
+    }
+
+    #[test]
+    fn releases_when_size_threshold_is_reached() {
+        let cfg = config(Some(2), None);
+        let now = Utc::now();
+
+        let (state, out) = collect(None, json!(1), &cfg, Uuid::new_v4(), now);
+        assert_eq!(out, json!({ "released": false, "pending": 1 }));
+
+        let (state, out) = collect(state, json!(2), &cfg, Uuid::new_v4(), now);
+        assert!(state.is_none());
+        assert_eq!(out["reason"], "size");
+        assert_eq!(out["items"], json!([1, 2]));
+    }
+
+    #[test]
+    fn releases_when_oldest_item_is_too_old() {
+        let cfg = config(None, Some(60));
+        let start = Utc::now();
+
+        let (state, _) = collect(None, json!("a"), &cfg, Uuid::new_v4(), start);
+        let (state, out) = collect(state, json!("b"), &cfg, Uuid::new_v4(), start + Duration::seconds(30));
+        assert_eq!(out["released"], false);
+
+        let (state, out) = collect(state, json!("c"), &cfg, Uuid::new_v4(), start + Duration::seconds(61));
+        assert!(state.is_none());
+        assert_eq!(out["reason"], "age");
+        assert_eq!(out["count"], 3);
+    }
+
+    #[tokio::test]
+    async fn node_halts_until_batch_is_released() {
+        let store = Arc::new(InMemoryStateStore::default());
+        let ctx = ExecutionContext::new(uuid::Uuid::new_v4(), uuid::Uuid::new_v4(), json!({}))
+            .with_state(store)
+            .for_node("digest", json!({ "max_items": 2, "item_field": "msg" }));
+        let node = BatchCollectNode;
+
+        node.execute(json!({ "msg": "one" }), &ctx).await.unwrap();
+        assert_eq!(ctx.take_flow(), Flow::Halt);
+
+        let out = node.execute(json!({ "msg": "two" }), &ctx).await.unwrap();
+        assert_eq!(ctx.take_flow(), Flow::Continue);
+        assert_eq!(out["items"], json!(["one", "two"]));
+    }
+
+    #[test]
+    fn at_least_once_batches_stay_until_their_execution_acknowledges_them() {
+        let cfg = BatchCollectConfig { delivery: Delivery::AtLeastOnce, ..config(Some(2), None) };
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let start = Utc::now();
+
+        let (state, _) = collect(None, json!(1), &cfg, Uuid::new_v4(), start);
+        let (state, out) = collect(state, json!(2), &cfg, first, start);
+        assert_eq!((&out["reason"], &out["items"]), (&json!("size"), &json!([1, 2])));
+        assert!(state.is_some(), "the batch is kept until acknowledged");
+
+        // Items arriving meanwhile wait, whatever the size.
+        let (state, out) = collect(state, json!(3), &cfg, second, start + Duration::seconds(1));
+        let (state, _) = collect(state, json!(4), &cfg, second, start + Duration::seconds(1));
+        assert_eq!(out, json!({ "released": false, "pending": 3 }));
+
+        // Only the releasing execution acknowledges, and only its items.
+        let (state, out) = acknowledge(state, second, start + Duration::seconds(2));
+        assert_eq!(out["acknowledged"], false);
+        let (state, out) = acknowledge(state, first, start + Duration::seconds(2));
+        assert_eq!(out, json!({ "acknowledged": true, "count": 2 }));
+        assert_eq!(state.as_ref().unwrap()["items"], json!([3, 4]));
+        assert!(state.as_ref().unwrap().get("release").is_none());
+
+        let (state, out) = collect(state, json!(5), &cfg, second, start + Duration::seconds(3));
+        assert_eq!(out["items"], json!([3, 4, 5]));
+        let (state, _) = acknowledge(state, second, start + Duration::seconds(3));
+        assert!(state.is_none());
+    }
+
+    #[test]
+    fn unacknowledged_batches_are_released_again() {
+        let cfg =
+            BatchCollectConfig { delivery: Delivery::AtLeastOnce, redeliver_after_secs: 60, ..config(Some(1), None) };
+        let (failed, next) = (Uuid::new_v4(), Uuid::new_v4());
+        let start = Utc::now();
+
+        let (state, _) = collect(None, json!("a"), &cfg, failed, start);
+        let (state, out) = collect(state, json!("b"), &cfg, next, start + Duration::seconds(59));
+        assert_eq!(out["released"], false);
+        let (state, out) = collect(state, json!("c"), &cfg, next, start + Duration::seconds(60));
+        assert_eq!((&out["reason"], &out["items"]), (&json!("redelivery"), &json!(["a", "b", "c"])));
+
+        // The failed execution's late acknowledgement no longer counts.
+        let (state, out) = acknowledge(state, failed, start + Duration::seconds(61));
+        assert_eq!(out["acknowledged"], false);
+        let (state, _) = acknowledge(state, next, start + Duration::seconds(61));
+        assert!(state.is_none());
+    }
+
+    #[tokio::test]
+    async fn a_failed_send_keeps_the_batch_for_the_next_release() {
+        let store = Arc::new(InMemoryStateStore::default());
+        let workflow_id = uuid::Uuid::new_v4();
+        let collect =
+            json!({ "key": "digest", "max_items": 1, "delivery": "at_least_once", "redeliver_after_secs": 0 });
+        let run = |execution_id: Uuid, config: Value| {
+            let ctx = ExecutionContext::new(workflow_id, execution_id, json!({}));
+            ctx.with_state(store.clone()).for_node("node", config)
+        };
+        let node = BatchCollectNode;
+
+        // The first execution releases the batch, then fails to send it.
+        let out = node.execute(json!("one"), &run(Uuid::new_v4(), collect.clone())).await.unwrap();
+        assert_eq!(out["items"], json!(["one"]));
+
+        let second = run(Uuid::new_v4(), collect);
+        let out = node.execute(json!("two"), &second).await.unwrap();
+        assert_eq!((&out["reason"], &out["items"]), (&json!("redelivery"), &json!(["one", "two"])));
+        let ack = second.for_node("ack", json!({ "key": "digest", "acknowledge": true }));
+        let out = node.execute(json!({}), &ack).await.unwrap();
+        assert_eq!(out, json!({ "acknowledged": true, "count": 2 }));
+        assert_eq!(ack.take_flow(), Flow::Continue);
+
+        let keyless = node.execute(json!({}), &ack.for_node("ack", json!({ "acknowledge": true }))).await;
+        assert!(matches!(keyless, Err(NodeError::Fatal(message)) if message.contains("`key`")));
+    }
+
+    #[tokio::test]
+    async fn ages_out_of_range_are_refused() {
+        let ctx = ExecutionContext::new(Uuid::new_v4(), Uuid::new_v4(), json!({}))
+            .with_state(Arc::new(InMemoryStateStore::default()));
+        for config in [
+            json!({ "max_age_secs": u64::MAX }),
+            json!({ "max_age_secs": 9_000_000_000_000u64 }),
+            json!({ "max_items": 1, "delivery": "at_least_once", "redeliver_after_secs": u64::MAX }),
+        ] {
+            let result = BatchCollectNode.execute(json!(1), &ctx.for_node("batch", config)).await;
+            assert!(matches!(result, Err(NodeError::Fatal(message)) if message.contains("out of range")));
+        }
+        let ok = BatchCollectNode.execute(json!(1), &ctx.for_node("batch", json!({ "max_age_secs": 86_400 }))).await;
+        assert_eq!(ok.unwrap(), json!({ "released": false, "pending": 1 }));
+    }
+}
@@ -0,0 +1,426 @@
+//! `classify` node — sentiment, language detection, or custom-label
+//! classification behind pluggable providers.
+//!
+//! Every provider's answer is normalised into the same output shape so
+//! downstream routing does not care which backend produced it:
+//!
+//! ```json
+//! {
+//!   "task": "sentiment",
+//!   "label": "negative",
+//!   "score": 0.91,
+//!   "scores": { "negative": 0.91, "neutral": 0.07, "positive": 0.02 },
+//!   "provider": "openai"
+//! }
+//! ```
+//!
+//! Built-in providers are `openai` (any OpenAI-compatible chat completions
+//! endpoint, prompted to answer in JSON) and `huggingface` (a text
+//! classification inference endpoint).  Embedders can register their own
+//! implementation of [`ClassificationProvider`] with
+//! [`ClassifyNode::with_provider`] and select it with
+//! `{ "provider": { "type": "custom", "name": "..." } }`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::builtin::{check_response, parse_config, require_secret, transport_error};
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, traits::ExecutionContext};
+
+const SENTIMENT_LABELS: [&str; 3] = ["positive", "neutral", "negative"];
+
+/// The kind of classification to perform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ClassifyTask {
+    /// `positive` / `neutral` / `negative`.
+    Sentiment,
+    /// ISO-639-1 language code.
+    Language,
+    /// One of the caller-supplied `labels`.
+    Labels,
+}
+
+impl ClassifyTask {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Sentiment => "sentiment",
+            Self::Language => "language",
+            Self::Labels => "labels",
+        }
+    }
+}
+
+/// Provider selection and credentials.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProviderConfig {
+    Openai {
+        #[serde(default = "default_openai_base_url")]
+        base_url: String,
+        api_key_secret: String,
+        #[serde(default = "default_openai_model")]
+        model: String,
+    },
+    Huggingface {
+        /// Full inference URL of the model endpoint.
+        url: String,
+        api_key_secret: String,
+    },
+    Custom {
+        name: String,
+    },
+}
+
+fn default_openai_base_url() -> String {
+    "https://api.openai.com/v1".into()
+}
+
+fn default_openai_model() -> String {
+    "gpt-4o-mini".into()
+}
+
+/// Configuration for the `classify` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ClassifyConfig {
+    pub task: ClassifyTask,
+    /// Candidate labels for [`ClassifyTask::Labels`].
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Input field holding the text to classify.
+    #[serde(default = "default_text_field")]
+    pub text_field: String,
+    pub provider: ProviderConfig,
+}
+
+fn default_text_field() -> String {
+    "text".into()
+}
+
+/// What a provider is asked to classify.
+#[derive(Debug, Clone)]
+pub struct ClassificationRequest<'a> {
+    pub task: ClassifyTask,
+    pub text: &'a str,
+    /// Candidate labels (fixed for sentiment, empty for language detection).
+    pub labels: &'a [String],
+}
+
+/// Normalised provider answer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classification {
+    pub label: String,
+    pub score: f64,
+    pub scores: BTreeMap<String, f64>,
+}
+
+impl Classification {
+    /// Build a classification from raw per-label scores: labels are
+    /// lower-cased and the highest score wins.
+    pub fn from_scores(scores: impl IntoIterator<Item = (String, f64)>) -> Option<Self> {
+        let scores: BTreeMap<String, f64> = scores
+            .into_iter()
+            .map(|(label, score)| (label.trim().to_lowercase(), score))
+            .collect();
+
+        let (label, score) = scores
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(l, s)| (l.clone(), *s))?;
+
+        Some(Self { label, score, scores })
+    }
+}
+
+/// A classification backend.
+#[async_trait]
+pub trait ClassificationProvider: Send + Sync {
+    async fn classify(
+        &self,
+        request: &ClassificationRequest<'_>,
+        ctx: &ExecutionContext,
+    ) -> Result<Classification, NodeError>;
+}
+
+/// The `classify` node.
+#[register_node("classify")]
+#[derive(Default)]
+pub struct ClassifyNode {
+    client: reqwest::Client,
+    custom: HashMap<String, Arc<dyn ClassificationProvider>>,
+}
+
+impl ClassifyNode {
+    /// Register a custom provider selectable by `name`.
+    pub fn with_provider(
+        mut self,
+        name: impl Into<String>,
+        provider: Arc<dyn ClassificationProvider>,
+    ) -> Self {
+        self.custom.insert(name.into(), provider);
+        self
+    }
+}
+
+#[async_trait]
+impl ExecutableNode for ClassifyNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: ClassifyConfig = parse_config("classify", ctx)?;
+
+        let text = input
+            .get(&config.text_field)
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                NodeError::Fatal(format!(
+                    "classify expects text in input field '{}'",
+                    config.text_field
+                ))
+            })?;
+
+        let labels: Vec<String> = match config.task {
+            ClassifyTask::Sentiment => SENTIMENT_LABELS.iter().map(|s| s.to_string()).collect(),
+            ClassifyTask::Language => Vec::new(),
+            ClassifyTask::Labels if config.labels.is_empty() => {
+                return Err(NodeError::Fatal(
+                    "classify task 'labels' requires a non-empty `labels` list".into(),
+                ));
+            }
+            ClassifyTask::Labels => config.labels.clone(),
+        };
+
+        let request = ClassificationRequest { task: config.task, text, labels: &labels };
+
+        let (provider_name, result) = match &config.provider {
+            ProviderConfig::Openai { base_url, api_key_secret, model } => {
+                let provider = OpenAiProvider {
+                    client: self.client.clone(),
+                    base_url: base_url.clone(),
+                    api_key: require_secret(ctx, api_key_secret)?.to_owned(),
+                    model: model.clone(),
+                };
+                ("openai", provider.classify(&request, ctx).await?)
+            }
+            ProviderConfig::Huggingface { url, api_key_secret } => {
+                let provider = HuggingFaceProvider {
+                    client: self.client.clone(),
+                    url: url.clone(),
+                    api_key: require_secret(ctx, api_key_secret)?.to_owned(),
+                };
+                ("huggingface", provider.classify(&request, ctx).await?)
+            }
+            ProviderConfig::Custom { name } => {
+                let provider = self.custom.get(name).ok_or_else(|| {
+                    NodeError::Fatal(format!("no classification provider registered as '{name}'"))
+                })?;
+                (name.as_str(), provider.classify(&request, ctx).await?)
+            }
+        };
+
+        if !labels.is_empty() && !labels.iter().any(|l| l.to_lowercase() == result.label) {
+            return Err(NodeError::Fatal(format!(
+                "provider returned label '{}' which is not one of {labels:?}",
+                result.label
+            )));
+        }
+
+        Ok(json!({
+            "task": config.task.as_str(),
+            "label": result.label,
+            "score": result.score,
+            "scores": result.scores,
+            "provider": provider_name,
+        }))
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("Classify", "Sentiment, language, or custom-label classification.")
+            .category(NodeCategory::Ai)
+            .config::<ClassifyConfig>()
+            .optional_credential("provider.api_key_secret", "API key of the openai or huggingface provider")
+            .output("{ task, label, score, scores, provider }")
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Built-in providers
+// ---------------------------------------------------------------------------
+
+/// Classification via an OpenAI-compatible chat completions endpoint.
+pub struct OpenAiProvider {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    model: String,
+}
+
+#[async_trait]
+impl ClassificationProvider for OpenAiProvider {
+    async fn classify(
+        &self,
+        request: &ClassificationRequest<'_>,
+        _ctx: &ExecutionContext,
+    ) -> Result<Classification, NodeError> {
+        let instruction = match request.task {
+            ClassifyTask::Language => {
+                "Detect the language of the user's text. Use ISO-639-1 codes as labels.".to_string()
+            }
+            _ => format!(
+                "Classify the user's text into exactly one of these labels: {}.",
+                request.labels.join(", ")
+            ),
+        };
+        let system = format!(
+            "{instruction} Respond only with a JSON object of the form \
+             {{\"scores\": {{\"<label>\": <probability between 0 and 1>, ...}}}}."
+        );
+
+        let response = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url.trim_end_matches('/')))
+            .bearer_auth(&self.api_key)
+            .json(&json!({
+                "model": self.model,
+                "temperature": 0,
+                "response_format": { "type": "json_object" },
+                "messages": [
+                    { "role": "system", "content": system },
+                    { "role": "user", "content": request.text },
+                ],
+            }))
+            .send()
+            .await
+            .map_err(|e| transport_error("classification API", e))?;
+        let body: Value = check_response("classification API", response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| NodeError::Fatal(format!("invalid classification response: {e}")))?;
+
+        let content = body
+            .pointer("/choices/0/message/content")
+            .and_then(Value::as_str)
+            .ok_or_else(|| NodeError::Fatal("classification response has no content".into()))?;
+        let parsed: Value = serde_json::from_str(content).map_err(|e| {
+            // The model ignored the JSON instruction; a retry may well succeed.
+            NodeError::Retryable(format!("model did not return JSON: {e}"))
+        })?;
+
+        let scores = parsed
+            .get("scores")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .filter_map(|(label, score)| score.as_f64().map(|s| (label.clone(), s)));
+
+        Classification::from_scores(scores)
+            .ok_or_else(|| NodeError::Retryable("model returned no label scores".into()))
+    }
+}
+
+/// Classification via a Hugging Face text-classification inference endpoint.
+pub struct HuggingFaceProvider {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+}
+
+#[async_trait]
+impl ClassificationProvider for HuggingFaceProvider {
+    async fn classify(
+        &self,
+        request: &ClassificationRequest<'_>,
+        _ctx: &ExecutionContext,
+    ) -> Result<Classification, NodeError> {
+        let mut payload = json!({ "inputs": request.text });
+        if request.task == ClassifyTask::Labels {
+            // Zero-shot models take the candidate labels as parameters.
+            payload["parameters"] = json!({ "candidate_labels": request.labels });
+        }
+
+        let response = self
+            .client
+            .post(&self.url)
+            .bearer_auth(&self.api_key)
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| transport_error("inference API", e))?;
+        let body: Value = check_response("inference API", response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| NodeError::Fatal(format!("invalid inference response: {e}")))?;
+
+        Classification::from_scores(hugging_face_scores(&body))
+            .ok_or_else(|| NodeError::Fatal(format!("unrecognised inference response: {body}")))
+    }
+}
+
+/// Accepts both response shapes: `[[{label, score}, …]]` (text
+/// classification) and `{labels: […], scores: […]}` (zero-shot).
+fn hugging_face_scores(body: &Value) -> Vec<(String, f64)> {
+    if let (Some(labels), Some(scores)) = (
+        body.get("labels").and_then(Value::as_array),
+        body.get("scores").and_then(Value::as_array),
+    ) {
+        return labels
+            .iter()
+            .zip(scores)
+            .filter_map(|(l, s)| Some((l.as_str()?.to_owned(), s.as_f64()?)))
+            .collect();
+    }
+
+    let items = match body.get(0) {
+        Some(Value::Array(inner)) => inner.as_slice(),
+        _ => body.as_array().map(Vec::as_slice).unwrap_or_default(),
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            Some((item.get("label")?.as_str()?.to_owned(), item.get("score")?.as_f64()?))
+        })
+        .collect()
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highest_score_wins_and_labels_are_normalised() {
+        let c = Classification::from_scores([
+            ("Positive".to_string(), 0.2),
+            (" NEGATIVE ".to_string(), 0.7),
+            ("neutral".to_string(), 0.1),
+        ])
+        .unwrap();
+        assert_eq!(c.label, "negative");
+        assert_eq!(c.score, 0.7);
+        assert_eq!(c.scores.len(), 3);
+    }
+
+    #[test]
+    fn empty_scores_yield_none() {
+        assert!(Classification::from_scores(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn parses_both_hugging_face_shapes() {
+        let nested = json!([[{ "label": "POSITIVE", "score": 0.9 }, { "label": "NEGATIVE", "score": 0.1 }]]);
+        assert_eq!(hugging_face_scores(&nested).len(), 2);
+
+        let zero_shot = json!({ "labels": ["billing", "bug"], "scores": [0.8, 0.2] });
+        assert_eq!(
+            hugging_face_scores(&zero_shot),
+            vec![("billing".to_string(), 0.8), ("bug".to_string(), 0.2)]
+        );
+    }
+}
@@ -0,0 +1,334 @@
+//! `crypto` node — hashing, HMAC signing/verification, encoding, and
+//! random tokens.
+//!
+//! The operation is selected with `operation`; inputs are templates
+//! rendered against `{ "input": ... }`:
+//!
+//! ```json
+//! {
+//!   "operation": "verify_hmac",
+//!   "algorithm": "sha256",
+//!   "value": "{{ input.raw_body }}",
+//!   "secret": "GITHUB_WEBHOOK_SECRET",
+//!   "signature": "{{ input.headers.x-hub-signature-256 }}",
+//!   "signature_prefix": "sha256=",
+//!   "fail_on_mismatch": true
+//! }
+//! ```
+//!
+//! | operation     | output                         |
+//! |---------------|--------------------------------|
+//! | `hash`        | `{ "hash": "..." }`            |
+//! | `hmac`        | `{ "signature": "..." }`       |
+//! | `verify_hmac` | `{ "valid": true }`            |
+//! | `encode`      | `{ "encoded": "..." }`         |
+//! | `decode`      | `{ "decoded": "..." }`         |
+//! | `random`      | `{ "token": "..." }`           |
+//!
+//! Digests and tokens are hex-encoded unless `encoding` says otherwise.
+//! HMAC keys are read from the workflow's secrets (`secret` names the
+//! secret), and signatures are compared in constant time.
+
+use async_trait::async_trait;
+use base64::Engine as _;
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use rand::RngCore;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::builtin::{parse_config, require_secret};
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, template, traits::ExecutionContext};
+
+/// Digest algorithm for `hash`, `hmac`, and `verify_hmac`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+    #[default]
+    Sha256,
+    Sha512,
+    Md5,
+}
+
+/// Text encoding of binary values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    #[default]
+    Hex,
+    Base64,
+    /// URL-safe base64 without padding.
+    Base64Url,
+}
+
+impl Encoding {
+    fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            Self::Hex => hex::encode(bytes),
+            Self::Base64 => base64::engine::general_purpose::STANDARD.encode(bytes),
+            Self::Base64Url => base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes),
+        }
+    }
+
+    fn decode(self, text: &str) -> Result<Vec<u8>, NodeError> {
+        let decoded = match self {
+            Self::Hex => hex::decode(text).map_err(|e| e.to_string()),
+            Self::Base64 => base64::engine::general_purpose::STANDARD
+                .decode(text)
+                .map_err(|e| e.to_string()),
+            Self::Base64Url => base64::engine::general_purpose::URL_SAFE_NO_PAD
+                .decode(text.trim_end_matches('='))
+                .map_err(|e| e.to_string()),
+        };
+        decoded.map_err(|e| NodeError::Fatal(format!("crypto: cannot decode {self:?} value: {e}")))
+    }
+}
+
+/// Configuration for the `crypto` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum CryptoConfig {
+    Hash {
+        #[serde(default)]
+        algorithm: Algorithm,
+        value: String,
+        #[serde(default)]
+        encoding: Encoding,
+    },
+    Hmac {
+        #[serde(default)]
+        algorithm: Algorithm,
+        value: String,
+        /// Name of the secret holding the key.
+        secret: String,
+        #[serde(default)]
+        encoding: Encoding,
+    },
+    VerifyHmac {
+        #[serde(default)]
+        algorithm: Algorithm,
+        value: String,
+        secret: String,
+        /// The signature to check, in `encoding`.
+        signature: String,
+        /// Stripped from `signature` first (e.g. `sha256=`).
+        #[serde(default)]
+        signature_prefix: Option<String>,
+        #[serde(default)]
+        encoding: Encoding,
+        /// Fail the node instead of returning `{ "valid": false }`.
+        #[serde(default)]
+        fail_on_mismatch: bool,
+    },
+    Encode {
+        value: String,
+        #[serde(default)]
+        encoding: Encoding,
+    },
+    Decode {
+        value: String,
+        #[serde(default)]
+        encoding: Encoding,
+    },
+    Random {
+        /// Number of random bytes.
+        #[serde(default = "default_length")]
+        length: usize,
+        #[serde(default)]
+        encoding: Encoding,
+    },
+}
+
+fn default_length() -> usize {
+    32
+}
+
+/// Upper bound on `random.length`.
+const MAX_RANDOM_BYTES: usize = 1024;
+
+/// The `crypto` node.
+#[register_node("crypto")]
+#[derive(Debug, Default)]
+pub struct CryptoNode;
+
+#[async_trait]
+impl ExecutableNode for CryptoNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: CryptoConfig = parse_config("crypto", ctx)?;
+        let root = json!({ "input": input });
+        let render = |tpl: &str| {
+            template::render(tpl, &root).map_err(|e| NodeError::Fatal(format!("crypto: {e}")))
+        };
+
+        match config {
+            CryptoConfig::Hash { algorithm, value, encoding } => {
+                let digest = hash(algorithm, render(&value)?.as_bytes());
+                Ok(json!({ "hash": encoding.encode(&digest) }))
+            }
+            CryptoConfig::Hmac { algorithm, value, secret, encoding } => {
+                let key = require_secret(ctx, &secret)?;
+                let mac = hmac(algorithm, key.as_bytes(), render(&value)?.as_bytes())?;
+                Ok(json!({ "signature": encoding.encode(&mac) }))
+            }
+            CryptoConfig::VerifyHmac {
+                algorithm,
+                value,
+                secret,
+                signature,
+                signature_prefix,
+                encoding,
+                fail_on_mismatch,
+            } => {
+                let key = require_secret(ctx, &secret)?;
+                let signature = render(&signature)?;
+                let signature = signature.trim();
+                let signature = signature_prefix
+                    .as_deref()
+                    .and_then(|p| signature.strip_prefix(p))
+                    .unwrap_or(signature);
+
+                let valid = match encoding.decode(signature) {
+                    Ok(expected) => {
+                        verify_hmac(algorithm, key.as_bytes(), render(&value)?.as_bytes(), &expected)?
+                    }
+                    // A malformed signature is simply not a valid one.
+                    Err(_) => false,
+                };
+                if !valid && fail_on_mismatch {
+                    return Err(NodeError::Fatal("crypto: HMAC signature mismatch".into()));
+                }
+                Ok(json!({ "valid": valid }))
+            }
+            CryptoConfig::Encode { value, encoding } => {
+                Ok(json!({ "encoded": encoding.encode(render(&value)?.as_bytes()) }))
+            }
+            CryptoConfig::Decode { value, encoding } => {
+                let bytes = encoding.decode(render(&value)?.trim())?;
+                let decoded = String::from_utf8(bytes)
+                    .map_err(|_| NodeError::Fatal("crypto: decoded value is not UTF-8".into()))?;
+                Ok(json!({ "decoded": decoded }))
+            }
+            CryptoConfig::Random { length, encoding } => {
+                if length == 0 || length > MAX_RANDOM_BYTES {
+                    return Err(NodeError::Fatal(format!(
+                        "crypto: random length must be between 1 and {MAX_RANDOM_BYTES}"
+                    )));
+                }
+                let mut bytes = vec![0u8; length];
+                rand::thread_rng().fill_bytes(&mut bytes);
+                Ok(json!({ "token": encoding.encode(&bytes) }))
+            }
+        }
+    }
+
+    fn is_blocking(&self) -> bool {
+        true
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("Crypto", "Hash, sign and verify HMACs, encode, decode, and generate random tokens.")
+            .category(NodeCategory::Data)
+            .config::<CryptoConfig>()
+            .optional_credential("secret", "HMAC key, for the hmac and verify_hmac operations")
+            .output("{ hash } | { signature } | { valid } | { encoded } | { decoded } | { token }")
+    }
+}
+
+fn hash(algorithm: Algorithm, data: &[u8]) -> Vec<u8> {
+    match algorithm {
+        Algorithm::Sha256 => Sha256::digest(data).to_vec(),
+        Algorithm::Sha512 => Sha512::digest(data).to_vec(),
+        Algorithm::Md5 => Md5::digest(data).to_vec(),
+    }
+}
+
+fn hmac(algorithm: Algorithm, key: &[u8], data: &[u8]) -> Result<Vec<u8>, NodeError> {
+    Ok(match algorithm {
+        Algorithm::Sha256 => mac::<Hmac<Sha256>>(key, data)?.finalize().into_bytes().to_vec(),
+        Algorithm::Sha512 => mac::<Hmac<Sha512>>(key, data)?.finalize().into_bytes().to_vec(),
+        Algorithm::Md5 => mac::<Hmac<Md5>>(key, data)?.finalize().into_bytes().to_vec(),
+    })
+}
+
+/// Constant-time comparison of `expected` against the HMAC of `data`.
+fn verify_hmac(
+    algorithm: Algorithm,
+    key: &[u8],
+    data: &[u8],
+    expected: &[u8],
+) -> Result<bool, NodeError> {
+    Ok(match algorithm {
+        Algorithm::Sha256 => mac::<Hmac<Sha256>>(key, data)?.verify_slice(expected).is_ok(),
+        Algorithm::Sha512 => mac::<Hmac<Sha512>>(key, data)?.verify_slice(expected).is_ok(),
+        Algorithm::Md5 => mac::<Hmac<Md5>>(key, data)?.verify_slice(expected).is_ok(),
+    })
+}
+
+fn mac<M: Mac + hmac::digest::KeyInit>(key: &[u8], data: &[u8]) -> Result<M, NodeError> {
+    let mut mac = <M as Mac>::new_from_slice(key)
+        .map_err(|e| NodeError::Fatal(format!("crypto: invalid HMAC key: {e}")))?;
+    mac.update(data);
+    Ok(mac)
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(config: Value) -> ExecutionContext {
+        let mut ctx = ExecutionContext::new(uuid::Uuid::new_v4(), uuid::Uuid::new_v4(), json!({}));
+        ctx.secrets.insert("KEY".into(), "key".into());
+        ctx.for_node("crypto", config)
+    }
+
+    #[test]
+    fn hashes_with_known_vectors() {
+        assert_eq!(
+            hex::encode(hash(Algorithm::Sha256, b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(hex::encode(hash(Algorithm::Md5, b"")), "d41d8cd98f00b204e9800998ecf8427e");
+    }
+
+    #[tokio::test]
+    async fn signs_and_verifies_hmac() {
+        let input = json!({ "body": "The quick brown fox jumps over the lazy dog" });
+        let signed = CryptoNode
+            .execute(input.clone(), &ctx(json!({ "operation": "hmac", "value": "{{ input.body }}", "secret": "KEY" })))
+            .await
+            .unwrap();
+        assert_eq!(
+            signed["signature"],
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+
+        let verify = |signature: &str| {
+            ctx(json!({
+                "operation": "verify_hmac",
+                "value": "{{ input.body }}",
+                "secret": "KEY",
+                "signature": signature,
+                "signature_prefix": "sha256=",
+            }))
+        };
+        let good = format!("sha256={}", signed["signature"].as_str().unwrap());
+        let out = CryptoNode.execute(input.clone(), &verify(&good)).await.unwrap();
+        assert_eq!(out["valid"], true);
+        let out = CryptoNode.execute(input, &verify("sha256=00ff")).await.unwrap();
+        assert_eq!(out["valid"], false);
+    }
+
+    #[test]
+    fn encodings_round_trip() {
+        for encoding in [Encoding::Hex, Encoding::Base64, Encoding::Base64Url] {
+            let text = encoding.encode(b"hello?>");
+            assert_eq!(encoding.decode(&text).unwrap(), b"hello?>");
+        }
+        assert_eq!(Encoding::Base64Url.encode(b"hello?>"), "aGVsbG8_Pg");
+    }
+}
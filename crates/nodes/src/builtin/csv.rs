@@ -0,0 +1,326 @@
+//! `csv` node — convert CSV to a JSON array of records and back.
+//!
+//! `parse` mode reads CSV text (or base64 bytes) from `input[field]`:
+//!
+//! ```json
+//! { "mode": "parse", "delimiter": ";", "infer_types": true, "offset": 0, "limit": 5000 }
+//! ```
+//!
+//! Output: `{ "headers": [...], "records": [{...}, ...], "count": 2, "has_more": false }`.
+//! With `has_headers: false` every record is an array of strings.
+//!
+//! Rows are decoded one at a time from the input buffer, and `offset`/`limit`
+//! let a workflow walk a very large file in pages without ever holding all
+//! of it as JSON.
+//!
+//! `generate` mode turns `input[field]` (an array of objects or arrays) back
+//! into CSV text: `{ "csv": "...", "count": 2 }`.  Columns come from
+//! `columns` or, if unset, from the keys of the records in first-seen order.
+
+use async_trait::async_trait;
+use base64::Engine as _;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::builtin::parse_config;
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, traits::ExecutionContext};
+
+/// Conversion direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvMode {
+    Parse,
+    Generate,
+}
+
+/// How CSV content is carried in the JSON payload.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvEncoding {
+    #[default]
+    Text,
+    Base64,
+}
+
+/// Configuration for the `csv` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct CsvConfig {
+    pub mode: CsvMode,
+    /// Input field holding the CSV (parse) or the records (generate).
+    #[serde(default = "default_field")]
+    pub field: String,
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    /// Whether the first row holds column names.
+    #[serde(default = "default_true")]
+    pub has_headers: bool,
+    /// Explicit column names (overrides the header row / record keys).
+    #[serde(default)]
+    pub columns: Option<Vec<String>>,
+    /// Parse: convert numeric/boolean-looking cells to JSON numbers/booleans.
+    #[serde(default)]
+    pub infer_types: bool,
+    /// Parse: number of data rows to skip.
+    #[serde(default)]
+    pub offset: usize,
+    /// Parse: maximum number of records to emit.
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub encoding: CsvEncoding,
+}
+
+fn default_field() -> String {
+    "data".into()
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// The `csv` node.
+#[register_node("csv")]
+#[derive(Debug, Default)]
+pub struct CsvNode;
+
+#[async_trait]
+impl ExecutableNode for CsvNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: CsvConfig = parse_config("csv", ctx)?;
+        if !config.delimiter.is_ascii() {
+            return Err(NodeError::Fatal("csv delimiter must be a single ASCII character".into()));
+        }
+
+        let field = input.get(&config.field).ok_or_else(|| {
+            NodeError::Fatal(format!("csv: input has no field '{}'", config.field))
+        })?;
+
+        match config.mode {
+            CsvMode::Parse => {
+                let text = field.as_str().ok_or_else(|| {
+                    NodeError::Fatal(format!("csv: input field '{}' must be a string", config.field))
+                })?;
+                let bytes = match config.encoding {
+                    CsvEncoding::Text => text.as_bytes().to_vec(),
+                    CsvEncoding::Base64 => base64::engine::general_purpose::STANDARD
+                        .decode(text)
+                        .map_err(|e| NodeError::Fatal(format!("csv: invalid base64: {e}")))?,
+                };
+                parse(&bytes, &config)
+            }
+            CsvMode::Generate => {
+                let records = field.as_array().ok_or_else(|| {
+                    NodeError::Fatal(format!("csv: input field '{}' must be an array", config.field))
+                })?;
+                generate(records, &config)
+            }
+        }
+    }
+
+    fn is_blocking(&self) -> bool {
+        true
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("CSV", "Convert CSV to a JSON array of records and back.")
+            .category(NodeCategory::Data)
+            .config::<CsvConfig>()
+            .input("CSV text or base64 bytes (parse), or an array of records (generate), in input[field]")
+            .output("{ headers, records, count, has_more } | { csv, count }")
+    }
+}
+
+fn parse(bytes: &[u8], config: &CsvConfig) -> Result<Value, NodeError> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(config.delimiter as u8)
+        .has_headers(config.has_headers)
+        .flexible(true)
+        .from_reader(bytes);
+
+    let headers: Option<Vec<String>> = match (&config.columns, config.has_headers) {
+        (Some(columns), _) => Some(columns.clone()),
+        (None, true) => Some(
+            reader
+                .headers()
+                .map_err(csv_error)?
+                .iter()
+                .map(str::to_owned)
+                .collect(),
+        ),
+        (None, false) => None,
+    };
+
+    let limit = config.limit.unwrap_or(usize::MAX);
+    let mut records = Vec::new();
+    let mut has_more = false;
+
+    for row in reader.records().skip(config.offset) {
+        let row = row.map_err(csv_error)?;
+        if records.len() == limit {
+            has_more = true;
+            break;
+        }
+
+        let cell = |value: &str| {
+            if config.infer_types { infer(value) } else { Value::String(value.to_owned()) }
+        };
+        let record = match &headers {
+            Some(headers) => Value::Object(
+                headers
+                    .iter()
+                    .zip(row.iter())
+                    .map(|(h, v)| (h.clone(), cell(v)))
+                    .collect::<Map<_, _>>(),
+            ),
+            None => Value::Array(row.iter().map(cell).collect()),
+        };
+        records.push(record);
+    }
+
+    Ok(json!({
+        "headers": headers,
+        "count": records.len(),
+        "has_more": has_more,
+        "records": records,
+    }))
+}
+
+fn generate(records: &[Value], config: &CsvConfig) -> Result<Value, NodeError> {
+    let columns: Vec<String> = match &config.columns {
+        Some(columns) => columns.clone(),
+        None => {
+            let mut columns: Vec<String> = Vec::new();
+            for key in records.iter().filter_map(Value::as_object).flat_map(Map::keys) {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+            columns
+        }
+    };
+
+    let mut writer = ::csv::WriterBuilder::new()
+        .delimiter(config.delimiter as u8)
+        .flexible(true)
+        .from_writer(Vec::new());
+
+    if config.has_headers && !columns.is_empty() {
+        writer.write_record(&columns).map_err(csv_error)?;
+    }
+
+    for record in records {
+        let row: Vec<String> = match record {
+            Value::Object(map) => columns
+                .iter()
+                .map(|c| map.get(c).map(cell_text).unwrap_or_default())
+                .collect(),
+            Value::Array(items) => items.iter().map(cell_text).collect(),
+            other => {
+                return Err(NodeError::Fatal(format!(
+                    "csv: records must be objects or arrays, got {other}"
+                )));
+            }
+        };
+        writer.write_record(&row).map_err(csv_error)?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| NodeError::Fatal(format!("csv: cannot flush output: {e}")))?;
+    let text = String::from_utf8(bytes)
+        .map_err(|e| NodeError::Fatal(format!("csv: output is not UTF-8: {e}")))?;
+
+    let csv = match config.encoding {
+        CsvEncoding::Text => text,
+        CsvEncoding::Base64 => base64::engine::general_purpose::STANDARD.encode(text),
+    };
+    Ok(json!({ "csv": csv, "count": records.len() }))
+}
+
+/// Text representation of a JSON value in a CSV cell.
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Best-effort conversion of a cell to a JSON scalar.
+fn infer(cell: &str) -> Value {
+    match cell {
+        "" => Value::Null,
+        "true" | "TRUE" | "True" => Value::Bool(true),
+        "false" | "FALSE" | "False" => Value::Bool(false),
+        _ => {
+            if let Ok(i) = cell.parse::<i64>() {
+                json!(i)
+            } else if let Some(f) = cell.parse::<f64>().ok().filter(|f| f.is_finite()) {
+                json!(f)
+            } else {
+                Value::String(cell.to_owned())
+            }
+        }
+    }
+}
+
+fn csv_error(err: ::csv::Error) -> NodeError {
+    NodeError::Fatal(format!("csv: {err}"))
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(mode: &str) -> CsvConfig {
+        serde_json::from_value(json!({ "mode": mode })).unwrap()
+    }
+
+    #[test]
+    fn parses_with_headers_and_type_inference() {
+        let mut cfg = config("parse");
+        cfg.infer_types = true;
+        let out = parse(b"id,name,active\n1,Ada,true\n2,\"Lovelace, A\",false\n", &cfg).unwrap();
+        assert_eq!(out["count"], 2);
+        assert_eq!(out["records"][1], json!({ "id": 2, "name": "Lovelace, A", "active": false }));
+    }
+
+    #[test]
+    fn pages_through_rows_with_offset_and_limit() {
+        let mut cfg = config("parse");
+        cfg.offset = 1;
+        cfg.limit = Some(1);
+        let out = parse(b"n\na\nb\nc\n", &cfg).unwrap();
+        assert_eq!(out["records"], json!([{ "n": "b" }]));
+        assert_eq!(out["has_more"], true);
+    }
+
+    #[test]
+    fn generates_csv_with_union_of_keys() {
+        let mut cfg = config("generate");
+        cfg.delimiter = ';';
+        let records = vec![json!({ "a": 1, "b": "x" }), json!({ "b": "y", "c": null })];
+        let out = generate(&records, &cfg).unwrap();
+        assert_eq!(out["csv"], "a;b;c\n1;x;\n;y;\n");
+    }
+
+    #[test]
+    fn round_trips_without_headers() {
+        let mut cfg = config("generate");
+        cfg.has_headers = false;
+        let out = generate(&[json!(["1", "2"]), json!(["3", "4"])], &cfg).unwrap();
+
+        let mut parse_cfg = config("parse");
+        parse_cfg.has_headers = false;
+        let parsed = parse(out["csv"].as_str().unwrap().as_bytes(), &parse_cfg).unwrap();
+        assert_eq!(parsed["records"], json!([["1", "2"], ["3", "4"]]));
+    }
+}
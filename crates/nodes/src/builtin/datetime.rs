@@ -0,0 +1,314 @@
+//! `datetime` node — parse, format, convert, and do arithmetic on dates.
+//!
+//! The operation is selected with `operation`; `value`, `start`, and `end`
+//! are templates rendered against `{ "input": ... }`:
+//!
+//! ```json
+//! { "operation": "add", "value": "{{ input.due }}", "days": 3, "timezone": "America/New_York" }
+//! ```
+//!
+//! | operation | output                                                 |
+//! |-----------|--------------------------------------------------------|
+//! | `now`     | `{ "datetime": "<RFC 3339>", "unix": 1700000000 }`     |
+//! | `parse`   | same as `now`                                          |
+//! | `convert` | same as `now`, expressed in `timezone`                 |
+//! | `add`     | same as `now`                                          |
+//! | `format`  | `{ "formatted": "Mon, 04 Mar 2024" }`                  |
+//! | `diff`    | `{ "diff": 3, "unit": "days", "seconds": 259200 }`     |
+//!
+//! Without an explicit `input_format`, values are accepted as RFC 3339,
+//! RFC 2822, Unix seconds, or `YYYY-MM-DD[ HH:MM:SS]`.  Values without an
+//! offset are interpreted in `timezone` (default UTC).  Formats use
+//! strftime syntax (`%Y-%m-%d %H:%M`).  Day and month arithmetic follows
+//! the calendar in `timezone`, so "+1 day" across a DST change keeps the
+//! wall-clock time.
+
+use async_trait::async_trait;
+use chrono::{
+    DateTime, Duration, FixedOffset, Months, NaiveDate, NaiveDateTime, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::builtin::parse_config;
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, template, traits::ExecutionContext};
+
+/// Unit of a `diff` result.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffUnit {
+    #[default]
+    Seconds,
+    Minutes,
+    Hours,
+    Days,
+    Weeks,
+}
+
+/// Configuration for the `datetime` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum DateTimeConfig {
+    Now {
+        #[serde(default)]
+        timezone: Option<String>,
+    },
+    Parse {
+        value: String,
+        #[serde(default)]
+        input_format: Option<String>,
+        #[serde(default)]
+        timezone: Option<String>,
+    },
+    Format {
+        value: String,
+        /// strftime output format.
+        format: String,
+        #[serde(default)]
+        input_format: Option<String>,
+        #[serde(default)]
+        timezone: Option<String>,
+    },
+    Convert {
+        value: String,
+        /// Target time zone.
+        timezone: String,
+        #[serde(default)]
+        input_format: Option<String>,
+    },
+    Add {
+        value: String,
+        #[serde(default)]
+        months: i32,
+        #[serde(default)]
+        days: i64,
+        #[serde(default)]
+        hours: i64,
+        #[serde(default)]
+        minutes: i64,
+        #[serde(default)]
+        seconds: i64,
+        #[serde(default)]
+        input_format: Option<String>,
+        #[serde(default)]
+        timezone: Option<String>,
+    },
+    Diff {
+        start: String,
+        end: String,
+        #[serde(default)]
+        unit: DiffUnit,
+        #[serde(default)]
+        input_format: Option<String>,
+        #[serde(default)]
+        timezone: Option<String>,
+    },
+}
+
+/// The `datetime` node.
+#[register_node("datetime")]
+#[derive(Debug, Default)]
+pub struct DateTimeNode;
+
+#[async_trait]
+impl ExecutableNode for DateTimeNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: DateTimeConfig = parse_config("datetime", ctx)?;
+        let root = json!({ "input": input });
+        let render = |tpl: &str| {
+            template::render(tpl, &root).map_err(|e| NodeError::Fatal(format!("datetime: {e}")))
+        };
+
+        match config {
+            DateTimeConfig::Now { timezone } => {
+                let tz = timezone_or_utc(timezone.as_deref())?;
+                Ok(datetime_output(&ctx.now().with_timezone(&tz)))
+            }
+            DateTimeConfig::Parse { value, input_format, timezone } => {
+                let tz = timezone_or_utc(timezone.as_deref())?;
+                let dt = parse(&render(&value)?, input_format.as_deref(), tz)?;
+                Ok(datetime_output(&dt))
+            }
+            DateTimeConfig::Format { value, format, input_format, timezone } => {
+                let tz = timezone_or_utc(timezone.as_deref())?;
+                let dt = parse(&render(&value)?, input_format.as_deref(), tz)?;
+                Ok(json!({ "formatted": format_with(&dt, &format)? }))
+            }
+            DateTimeConfig::Convert { value, timezone, input_format } => {
+                let tz = timezone_or_utc(Some(&timezone))?;
+                let dt = parse(&render(&value)?, input_format.as_deref(), tz)?;
+                Ok(datetime_output(&dt))
+            }
+            DateTimeConfig::Add { value, months, days, hours, minutes, seconds, input_format, timezone } => {
+                let tz = timezone_or_utc(timezone.as_deref())?;
+                let dt = parse(&render(&value)?, input_format.as_deref(), tz)?;
+                let shifted = add(dt, months, days, hours * 3600 + minutes * 60 + seconds)?;
+                Ok(datetime_output(&shifted))
+            }
+            DateTimeConfig::Diff { start, end, unit, input_format, timezone } => {
+                let tz = timezone_or_utc(timezone.as_deref())?;
+                let start = parse(&render(&start)?, input_format.as_deref(), tz)?;
+                let end = parse(&render(&end)?, input_format.as_deref(), tz)?;
+                let seconds = (end - start).num_seconds();
+                Ok(json!({ "diff": in_unit(seconds, unit), "unit": unit_name(unit), "seconds": seconds }))
+            }
+        }
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("Date & time", "Parse, format, convert, and do arithmetic on dates.")
+            .category(NodeCategory::Data)
+            .config::<DateTimeConfig>()
+            .output("{ datetime, unix } | { formatted } | { diff, unit, seconds }")
+    }
+}
+
+fn timezone_or_utc(name: Option<&str>) -> Result<Tz, NodeError> {
+    match name {
+        None => Ok(Tz::UTC),
+        Some(name) => name
+            .parse()
+            .map_err(|_| NodeError::Fatal(format!("datetime: unknown time zone '{name}'"))),
+    }
+}
+
+fn datetime_output(dt: &DateTime<Tz>) -> Value {
+    json!({ "datetime": dt.to_rfc3339(), "unix": dt.timestamp() })
+}
+
+/// Parse `text` into a zoned datetime, interpreting offset-less values in `tz`.
+fn parse(text: &str, format: Option<&str>, tz: Tz) -> Result<DateTime<Tz>, NodeError> {
+    let text = text.trim();
+    let invalid = || NodeError::Fatal(format!("datetime: cannot parse '{text}'"));
+
+    let with_offset = |dt: DateTime<FixedOffset>| dt.with_timezone(&tz);
+    let local = |naive: NaiveDateTime| tz.from_local_datetime(&naive).earliest();
+
+    let parsed = match format {
+        Some(format) => DateTime::parse_from_str(text, format)
+            .map(with_offset)
+            .ok()
+            .or_else(|| NaiveDateTime::parse_from_str(text, format).ok().and_then(local))
+            .or_else(|| {
+                NaiveDate::parse_from_str(text, format)
+                    .ok()
+                    .and_then(|d| local(d.and_time(chrono::NaiveTime::MIN)))
+            }),
+        None => DateTime::parse_from_rfc3339(text)
+            .or_else(|_| DateTime::parse_from_rfc2822(text))
+            .map(with_offset)
+            .ok()
+            .or_else(|| {
+                text.parse::<i64>()
+                    .ok()
+                    .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+                    .map(|dt| dt.with_timezone(&tz))
+            })
+            .or_else(|| {
+                ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+                    .iter()
+                    .find_map(|f| NaiveDateTime::parse_from_str(text, f).ok())
+                    .and_then(local)
+            })
+            .or_else(|| {
+                NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                    .ok()
+                    .and_then(|d| local(d.and_time(chrono::NaiveTime::MIN)))
+            }),
+    };
+    parsed.ok_or_else(invalid)
+}
+
+/// Shift `dt` by calendar months and days (in its own zone) plus `seconds`.
+fn add(dt: DateTime<Tz>, months: i32, days: i64, seconds: i64) -> Result<DateTime<Tz>, NodeError> {
+    let overflow = || NodeError::Fatal("datetime: result out of range".into());
+
+    let mut naive = dt.naive_local();
+    naive = if months >= 0 {
+        naive.checked_add_months(Months::new(months.unsigned_abs()))
+    } else {
+        naive.checked_sub_months(Months::new(months.unsigned_abs()))
+    }
+    .ok_or_else(overflow)?;
+    naive = naive.checked_add_signed(Duration::days(days)).ok_or_else(overflow)?;
+
+    let tz = dt.timezone();
+    let local = tz
+        .from_local_datetime(&naive)
+        .earliest()
+        // Landed in a DST gap: step past it.
+        .or_else(|| tz.from_local_datetime(&(naive + Duration::hours(1))).earliest())
+        .ok_or_else(overflow)?;
+    local.checked_add_signed(Duration::seconds(seconds)).ok_or_else(overflow)
+}
+
+fn format_with(dt: &DateTime<Tz>, format: &str) -> Result<String, NodeError> {
+    use std::fmt::Write as _;
+    let mut out = String::new();
+    write!(out, "{}", dt.format(format))
+        .map_err(|_| NodeError::Fatal(format!("datetime: invalid format '{format}'")))?;
+    Ok(out)
+}
+
+fn in_unit(seconds: i64, unit: DiffUnit) -> i64 {
+    match unit {
+        DiffUnit::Seconds => seconds,
+        DiffUnit::Minutes => seconds / 60,
+        DiffUnit::Hours => seconds / 3600,
+        DiffUnit::Days => seconds / 86_400,
+        DiffUnit::Weeks => seconds / 604_800,
+    }
+}
+
+fn unit_name(unit: DiffUnit) -> &'static str {
+    match unit {
+        DiffUnit::Seconds => "seconds",
+        DiffUnit::Minutes => "minutes",
+        DiffUnit::Hours => "hours",
+        DiffUnit::Days => "days",
+        DiffUnit::Weeks => "weeks",
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ny() -> Tz {
+        chrono_tz::America::New_York
+    }
+
+    #[test]
+    fn parses_common_shapes() {
+        let utc = parse("2024-03-04T12:00:00Z", None, Tz::UTC).unwrap();
+        assert_eq!(parse("1709553600", None, Tz::UTC).unwrap(), utc);
+        assert_eq!(parse("Mon, 04 Mar 2024 12:00:00 +0000", None, Tz::UTC).unwrap(), utc);
+        // Offset-less values are local to the configured zone.
+        assert_eq!(parse("2024-03-04 07:00:00", None, ny()).unwrap(), utc);
+        assert_eq!(parse("04/03/2024 12:00", Some("%d/%m/%Y %H:%M"), Tz::UTC).unwrap(), utc);
+        assert!(parse("not a date", None, Tz::UTC).is_err());
+    }
+
+    #[test]
+    fn adding_days_keeps_wall_clock_time_across_dst() {
+        // US DST started on 2024-03-10.
+        let before = parse("2024-03-09 09:00:00", None, ny()).unwrap();
+        let after = add(before, 0, 1, 0).unwrap();
+        assert_eq!(after.to_rfc3339(), "2024-03-10T09:00:00-04:00");
+        assert_eq!(add(before, -1, 0, 90).unwrap().to_rfc3339(), "2024-02-09T09:01:30-05:00");
+    }
+
+    #[test]
+    fn diffs_and_formats() {
+        let start = parse("2024-03-01", None, Tz::UTC).unwrap();
+        let end = parse("2024-03-04T06:00:00Z", None, Tz::UTC).unwrap();
+        assert_eq!(in_unit((end - start).num_seconds(), DiffUnit::Days), 3);
+        assert_eq!(format_with(&start, "%a, %d %b %Y").unwrap(), "Fri, 01 Mar 2024");
+        assert!(format_with(&start, "%Q").is_err());
+    }
+}
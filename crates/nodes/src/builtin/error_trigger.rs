@@ -0,0 +1,149 @@
+//! `error_trigger` node — start of an error workflow.
+//!
+//! A workflow names its error workflow with `"error_workflow_id"`.  When an
+//! execution of it fails, the engine queues a run of the error workflow
+//! whose input is a [`Failure`]:
+//!
+//! ```json
+//! {
+//!   "workflow_id": "...",
+//!   "workflow_name": "Sync orders",
+//!   "execution_id": "...",
+//!   "node_id": "push_to_erp",
+//!   "message": "node 'push_to_erp' failed fatally: HTTP 500",
+//!   "failed_at": "2024-01-01T12:00:00Z"
+//! }
+//! ```
+//!
+//! Workflows with `"slow_node_alerts": true` also start it, with `"kind":
+//! "slow"`, when a node runs far slower than it usually does; the
+//! execution carries on.
+//!
+//! Put an `error_trigger` node at the root of the error workflow: it checks
+//! that the input is such a failure and outputs it, so the nodes after it
+//! (a Slack message, a Jira ticket, ...) can refer to `{{ input.message }}`
+//! and friends.  With `"node_ids": [...]` in its config the node halts the
+//! flow for failures of any other node.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::builtin::parse_config;
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, traits::ExecutionContext};
+
+/// What the engine hands an error workflow about the failed execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Failure {
+    pub workflow_id: Uuid,
+    pub workflow_name: String,
+    pub execution_id: Uuid,
+    /// The node that failed.
+    pub node_id: String,
+    pub message: String,
+    pub failed_at: DateTime<Utc>,
+    /// Left out for failed executions.
+    #[serde(default, skip_serializing_if = "FailureKind::is_failed")]
+    pub kind: FailureKind,
+}
+
+/// What went wrong with the execution.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// The node failed and the execution with it.
+    #[default]
+    Failed,
+    /// The node succeeded, far slower than its baseline.
+    Slow,
+}
+
+impl FailureKind {
+    fn is_failed(&self) -> bool {
+        *self == Self::Failed
+    }
+}
+
+/// Configuration for the `error_trigger` node.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct ErrorTriggerConfig {
+    /// Only continue for failures of these nodes; every failure when empty.
+    #[serde(default)]
+    pub node_ids: Vec<String>,
+}
+
+/// The `error_trigger` node.
+#[register_node("error_trigger")]
+#[derive(Debug, Default)]
+pub struct ErrorTriggerNode;
+
+#[async_trait]
+impl ExecutableNode for ErrorTriggerNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: ErrorTriggerConfig = if ctx.config.is_null() {
+            ErrorTriggerConfig::default()
+        } else {
+            parse_config("error_trigger", ctx)?
+        };
+        let failure: Failure = serde_json::from_value(input).map_err(|e| {
+            NodeError::Fatal(format!(
+                "error_trigger: input is not a workflow failure ({e}); \
+                 error workflows are started by the engine when a workflow naming them fails"
+            ))
+        })?;
+
+        if !config.node_ids.is_empty() && !config.node_ids.contains(&failure.node_id) {
+            ctx.halt();
+        }
+        serde_json::to_value(failure).map_err(|e| NodeError::Fatal(format!("error_trigger: {e}")))
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("Error trigger", "Start of an error workflow, run when another workflow fails.")
+            .category(NodeCategory::Trigger)
+            .config::<ErrorTriggerConfig>()
+            .input("{ workflow_id, workflow_name, execution_id, node_id, message, failed_at, kind? }")
+            .output("the failure, unchanged")
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::traits::Flow;
+
+    fn failure(node_id: &str) -> Value {
+        json!({
+            "workflow_id": Uuid::nil(),
+            "workflow_name": "sync",
+            "execution_id": Uuid::nil(),
+            "node_id": node_id,
+            "message": "boom",
+            "failed_at": "2024-01-01T00:00:00Z"
+        })
+    }
+
+    #[tokio::test]
+    async fn passes_failures_through_filtered_by_node() {
+        let base = ExecutionContext::new(Uuid::new_v4(), Uuid::new_v4(), json!({}));
+
+        let ctx = base.for_node("on_error", Value::Null);
+        assert_eq!(ErrorTriggerNode.execute(failure("push"), &ctx).await.unwrap(), failure("push"));
+        assert_eq!(ctx.take_flow(), Flow::Continue);
+
+        let ctx = base.for_node("on_error", json!({ "node_ids": ["fetch"] }));
+        ErrorTriggerNode.execute(failure("push"), &ctx).await.unwrap();
+        assert_eq!(ctx.take_flow(), Flow::Halt);
+
+        let err = ErrorTriggerNode.execute(json!({ "order": 1 }), &ctx).await.unwrap_err();
+        assert!(matches!(err, NodeError::Fatal(_)));
+    }
+}
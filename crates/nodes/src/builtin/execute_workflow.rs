@@ -0,0 +1,176 @@
+//! `execute_workflow` node — run another workflow as a sub-workflow.
+//!
+//! ```json
+//! {
+//!   "workflow_id": "7b0e2d4c-6f1a-4d8e-9a53-2f0c8e1b9d47",
+//!   "input": { "order": "{{ input.order }}", "note": "from {{ input.source }}" },
+//!   "mode": "sync"
+//! }
+//! ```
+//!
+//! `input` maps this node's input onto the child's trigger input: every
+//! string in it is a template rendered against `{ "input": ... }`, and a
+//! string that is a single placeholder (`"{{ input.order }}"`) keeps the
+//! JSON type of the value it refers to.  Without `input`, the node's input
+//! is passed on unchanged.
+//!
+//! In `sync` mode (the default) the child runs to completion and the node
+//! outputs the child's final output.  A child that fails, or pauses (e.g.
+//! on an approval), fails the node.  In `async` mode the child is queued
+//! and the node outputs `{ "execution_id": "...", "workflow_id": "..." }`
+//! right away.
+//!
+//! Child executions inherit the parent's priority, queue, and labels.
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::builtin::parse_config;
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, template, traits::ExecutionContext};
+
+/// Whether the node waits for the child.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMode {
+    /// Wait for the child and output its result.
+    #[default]
+    Sync,
+    /// Queue the child and carry on (fire-and-forget).
+    Async,
+}
+
+/// Configuration for the `execute_workflow` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ExecuteWorkflowConfig {
+    pub workflow_id: Uuid,
+    /// Mapping from this node's input to the child's input.
+    #[serde(default)]
+    pub input: Option<Value>,
+    #[serde(default)]
+    pub mode: ExecuteMode,
+}
+
+/// The `execute_workflow` node.
+#[register_node("execute_workflow")]
+#[derive(Debug, Default)]
+pub struct ExecuteWorkflowNode;
+
+#[async_trait]
+impl ExecutableNode for ExecuteWorkflowNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: ExecuteWorkflowConfig = parse_config("execute_workflow", ctx)?;
+        let runner = ctx.require_subworkflows()?;
+        let child_input = match &config.input {
+            Some(mapping) => map_input(mapping, &json!({ "input": input }))?,
+            None => input,
+        };
+
+        match config.mode {
+            ExecuteMode::Sync => runner.run(ctx.execution_id, config.workflow_id, child_input).await,
+            ExecuteMode::Async => {
+                let execution_id = runner.start(ctx.execution_id, config.workflow_id, child_input).await?;
+                Ok(json!({ "execution_id": execution_id, "workflow_id": config.workflow_id }))
+            }
+        }
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("Execute workflow", "Run another workflow as a sub-workflow.")
+            .category(NodeCategory::Flow)
+            .config::<ExecuteWorkflowConfig>()
+            .output("the child's final output (sync) | { execution_id, workflow_id } (async)")
+    }
+}
+
+/// Render every string in `mapping` against `root`; a lone placeholder is
+/// replaced by the value it refers to.
+fn map_input(mapping: &Value, root: &Value) -> Result<Value, NodeError> {
+    Ok(match mapping {
+        Value::String(s) => match lone_placeholder(s) {
+            Some(path) => template::lookup(root, path).cloned().ok_or_else(|| {
+                NodeError::Fatal(format!("execute_workflow: unknown template path '{path}'"))
+            })?,
+            None => Value::String(
+                template::render(s, root).map_err(|e| NodeError::Fatal(format!("execute_workflow: {e}")))?,
+            ),
+        },
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|v| map_input(v, root)).collect::<Result<_, _>>()?)
+        }
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| Ok((k.clone(), map_input(v, root)?)))
+                .collect::<Result<_, NodeError>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// The path of `s` when it is exactly one `{{ path }}` placeholder.
+fn lone_placeholder(s: &str) -> Option<&str> {
+    let inner = s.trim().strip_prefix("{{")?.strip_suffix("}}")?;
+    (!inner.contains("{{") && !inner.contains("}}")).then(|| inner.trim())
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use crate::subworkflow::SubWorkflowRunner;
+
+    /// Echoes the child input back as its output and records every call.
+    #[derive(Debug, Default)]
+    struct Recorder {
+        calls: Mutex<Vec<(&'static str, Uuid, Value)>>,
+    }
+
+    #[async_trait]
+    impl SubWorkflowRunner for Recorder {
+        async fn run(&self, _parent: Uuid, workflow_id: Uuid, input: Value) -> Result<Value, NodeError> {
+            self.calls.lock().unwrap().push(("run", workflow_id, input.clone()));
+            Ok(json!({ "echo": input }))
+        }
+
+        async fn start(&self, _parent: Uuid, workflow_id: Uuid, input: Value) -> Result<Uuid, NodeError> {
+            self.calls.lock().unwrap().push(("start", workflow_id, input));
+            Ok(Uuid::nil())
+        }
+    }
+
+    #[test]
+    fn maps_input_keeping_types_of_lone_placeholders() {
+        let root = json!({ "input": { "order": { "id": 7 }, "source": "shop" } });
+        let mapping = json!({ "order": "{{ input.order }}", "note": "from {{ input.source }}", "n": 1 });
+        assert_eq!(
+            map_input(&mapping, &root).unwrap(),
+            json!({ "order": { "id": 7 }, "note": "from shop", "n": 1 })
+        );
+        assert!(map_input(&json!("{{ input.nope }}"), &root).is_err());
+    }
+
+    #[tokio::test]
+    async fn runs_children_sync_or_fire_and_forget() {
+        let recorder = Arc::new(Recorder::default());
+        let child = Uuid::new_v4();
+        let base = ExecutionContext::new(Uuid::new_v4(), Uuid::new_v4(), json!({}))
+            .with_subworkflows(recorder.clone());
+
+        let ctx = base.for_node("sub", json!({ "workflow_id": child, "input": { "x": "{{ input.a }}" } }));
+        let out = ExecuteWorkflowNode.execute(json!({ "a": [1, 2] }), &ctx).await.unwrap();
+        assert_eq!(out, json!({ "echo": { "x": [1, 2] } }));
+
+        let ctx = base.for_node("sub", json!({ "workflow_id": child, "mode": "async" }));
+        let out = ExecuteWorkflowNode.execute(json!({ "a": 1 }), &ctx).await.unwrap();
+        assert_eq!(out["execution_id"], json!(Uuid::nil()));
+
+        let calls = recorder.calls.lock().unwrap();
+        assert_eq!(calls[1], ("start", child, json!({ "a": 1 })));
+    }
+}
@@ -0,0 +1,214 @@
+//! `html_extract` node — pull structured data out of an HTML document with
+//! CSS selectors.
+//!
+//! ```json
+//! {
+//!   "field": "body",
+//!   "items": "div.product",
+//!   "extract": [
+//!     { "key": "name",  "selector": "h2" },
+//!     { "key": "url",   "selector": "a.details", "attribute": "href" },
+//!     { "key": "tags",  "selector": "li.tag", "multiple": true }
+//!   ]
+//! }
+//! ```
+//!
+//! Without `items` the rules run against the whole document and the output
+//! is one object (`{ "name": ..., "url": ..., "tags": [...] }`).  With
+//! `items`, the rules run inside every element matching that selector and
+//! the output is `{ "items": [{...}, ...], "count": n }`.
+//!
+//! Each rule yields the element's whitespace-normalised text by default, an
+//! attribute with `attribute`, or markup with `value: "html"` /
+//! `"inner_html"`.  A rule that matches nothing yields `null` (or `[]` with
+//! `multiple: true`).
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use scraper::{ElementRef, Html, Selector};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::builtin::parse_config;
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, traits::ExecutionContext};
+
+/// What to read from a matched element.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractValue {
+    #[default]
+    Text,
+    /// The element's outer HTML.
+    Html,
+    InnerHtml,
+}
+
+/// One field to extract.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ExtractRule {
+    /// Output key.
+    pub key: String,
+    pub selector: String,
+    /// Read this attribute instead of the element's content.
+    #[serde(default)]
+    pub attribute: Option<String>,
+    #[serde(default)]
+    pub value: ExtractValue,
+    /// Collect every match into an array instead of taking the first.
+    #[serde(default)]
+    pub multiple: bool,
+}
+
+/// Configuration for the `html_extract` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct HtmlExtractConfig {
+    /// Input field holding the HTML document.
+    #[serde(default = "default_field")]
+    pub field: String,
+    /// Selector of repeated containers to extract one record from each.
+    #[serde(default)]
+    pub items: Option<String>,
+    pub extract: Vec<ExtractRule>,
+}
+
+fn default_field() -> String {
+    "html".into()
+}
+
+/// The `html_extract` node.
+#[register_node("html_extract")]
+#[derive(Debug, Default)]
+pub struct HtmlExtractNode;
+
+#[async_trait]
+impl ExecutableNode for HtmlExtractNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: HtmlExtractConfig = parse_config("html_extract", ctx)?;
+        let html = input
+            .get(&config.field)
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                NodeError::Fatal(format!("html_extract: input field '{}' must be a string", config.field))
+            })?;
+        extract(html, &config)
+    }
+
+    fn is_blocking(&self) -> bool {
+        true
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("HTML extract", "Pull structured data out of an HTML document with CSS selectors.")
+            .category(NodeCategory::Data)
+            .config::<HtmlExtractConfig>()
+            .input("HTML text in input[field]")
+            .output("{ <key>: ... } | { items, count }")
+    }
+}
+
+fn extract(html: &str, config: &HtmlExtractConfig) -> Result<Value, NodeError> {
+    let rules = config
+        .extract
+        .iter()
+        .map(|rule| Ok((rule, selector(&rule.selector)?)))
+        .collect::<Result<Vec<_>, NodeError>>()?;
+
+    let document = Html::parse_document(html);
+
+    match &config.items {
+        Some(items) => {
+            let items = selector(items)?;
+            let records: Vec<Value> = document
+                .select(&items)
+                .map(|item| record(item, &rules))
+                .collect();
+            Ok(json!({ "count": records.len(), "items": records }))
+        }
+        None => Ok(record(document.root_element(), &rules)),
+    }
+}
+
+/// Apply every rule within `scope`.
+fn record(scope: ElementRef<'_>, rules: &[(&ExtractRule, Selector)]) -> Value {
+    let fields: Map<String, Value> = rules
+        .iter()
+        .map(|(rule, selector)| {
+            let mut matches = scope.select(selector).map(|el| read(el, rule));
+            let value = if rule.multiple {
+                Value::Array(matches.collect())
+            } else {
+                matches.next().unwrap_or(Value::Null)
+            };
+            (rule.key.clone(), value)
+        })
+        .collect();
+    Value::Object(fields)
+}
+
+fn read(element: ElementRef<'_>, rule: &ExtractRule) -> Value {
+    if let Some(attribute) = &rule.attribute {
+        return element
+            .value()
+            .attr(attribute)
+            .map_or(Value::Null, |v| Value::String(v.to_owned()));
+    }
+    Value::String(match rule.value {
+        ExtractValue::Text => element.text().flat_map(str::split_whitespace).collect::<Vec<_>>().join(" "),
+        ExtractValue::Html => element.html(),
+        ExtractValue::InnerHtml => element.inner_html(),
+    })
+}
+
+fn selector(css: &str) -> Result<Selector, NodeError> {
+    Selector::parse(css)
+        .map_err(|e| NodeError::Fatal(format!("html_extract: invalid selector '{css}': {e}")))
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"
+        <html><body>
+          <h1>  Spring   sale </h1>
+          <div class="product"><h2>Kettle</h2><a class="details" href="/p/1">more</a>
+            <ul><li class="tag">kitchen</li><li class="tag">steel</li></ul></div>
+          <div class="product"><h2>Toaster</h2></div>
+        </body></html>"#;
+
+    fn config(value: Value) -> HtmlExtractConfig {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn extracts_from_the_whole_document() {
+        let cfg = config(json!({ "extract": [
+            { "key": "title", "selector": "h1" },
+            { "key": "names", "selector": ".product h2", "multiple": true },
+        ]}));
+        let out = extract(PAGE, &cfg).unwrap();
+        assert_eq!(out, json!({ "title": "Spring sale", "names": ["Kettle", "Toaster"] }));
+    }
+
+    #[test]
+    fn extracts_one_record_per_item() {
+        let cfg = config(json!({ "items": "div.product", "extract": [
+            { "key": "name", "selector": "h2" },
+            { "key": "url", "selector": "a.details", "attribute": "href" },
+            { "key": "tags", "selector": "li.tag", "multiple": true },
+        ]}));
+        let out = extract(PAGE, &cfg).unwrap();
+        assert_eq!(out["count"], 2);
+        assert_eq!(out["items"][0], json!({ "name": "Kettle", "url": "/p/1", "tags": ["kitchen", "steel"] }));
+        assert_eq!(out["items"][1], json!({ "name": "Toaster", "url": null, "tags": [] }));
+    }
+
+    #[test]
+    fn rejects_invalid_selectors() {
+        let cfg = config(json!({ "extract": [{ "key": "x", "selector": "div[" }] }));
+        assert!(matches!(extract(PAGE, &cfg), Err(NodeError::Fatal(_))));
+    }
+}
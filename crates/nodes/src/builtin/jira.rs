@@ -0,0 +1,409 @@
+//! `jira` node — create, update, and transition issues and add comments
+//! through the Jira REST API (v2, which accepts plain-text descriptions and
+//! comments on both Jira Cloud and Data Center).
+//!
+//! ```json
+//! {
+//!   "base_url": "https://acme.atlassian.net",
+//!   "email": "automation@acme.com",
+//!   "api_token_secret": "JIRA_TOKEN",
+//!   "operation": "create_issue",
+//!   "project": "OPS",
+//!   "issue_type": "Bug",
+//!   "summary": "Disk full on {{ input.host }}",
+//!   "description": "Usage at {{ input.usage }}%",
+//!   "fields": { "labels": ["alert"], "priority": { "name": "High" } }
+//! }
+//! ```
+//!
+//! Operations and their settings:
+//!
+//! * `create_issue` — `project`, `issue_type`, `summary`, optional
+//!   `description` and extra `fields`;
+//! * `update_issue` — `issue` and the `fields` to set;
+//! * `transition_issue` — `issue` and `transition`, a transition name
+//!   (case-insensitive) or ID, plus an optional `comment`;
+//! * `add_comment` — `issue` and `body`.
+//!
+//! Every string setting, including strings nested in `fields`, is a template
+//! rendered against `{ "input": ... }`.  With `email` the token is sent as
+//! Jira Cloud basic auth; without it, as a Data Center personal access
+//! token.  Instead of `api_token_secret`, `credential_id` can name a shared
+//! credential: an `api_token` or `oauth2` one stands in for the token, and
+//! an `http_basic` one is sent as is.  Rate limiting (429) and 5xx
+//! responses are retryable.
+//!
+//! Output: `{ "operation": "create_issue", "key": "OPS-42", "id": "10042",
+//! "url": "https://acme.atlassian.net/browse/OPS-42" }`; the other
+//! operations report the issue `key` plus the applied `transition` or the
+//! new `comment_id`.
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::builtin::{check_response, parse_config, require_credential, require_secret, transport_error};
+use crate::credentials::Credential;
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, template, traits::ExecutionContext};
+
+const SERVICE: &str = "Jira API";
+
+/// Configuration for the `jira` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct JiraConfig {
+    /// Site URL, e.g. `https://acme.atlassian.net`.
+    pub base_url: String,
+    /// Account email for Jira Cloud basic auth; omit for a Data Center
+    /// personal access token.
+    #[serde(default)]
+    pub email: Option<String>,
+    /// Secret holding the API token.
+    #[serde(default)]
+    pub api_token_secret: Option<String>,
+    /// Shared credential to authenticate with instead of
+    /// `api_token_secret`.
+    #[serde(default)]
+    pub credential_id: Option<Uuid>,
+    #[serde(flatten)]
+    pub operation: JiraOperation,
+}
+
+/// What to do.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum JiraOperation {
+    CreateIssue {
+        project: String,
+        issue_type: String,
+        summary: String,
+        #[serde(default)]
+        description: Option<String>,
+        #[serde(default)]
+        fields: Map<String, Value>,
+    },
+    UpdateIssue {
+        issue: String,
+        fields: Map<String, Value>,
+    },
+    TransitionIssue {
+        issue: String,
+        /// Transition name or ID.
+        transition: String,
+        #[serde(default)]
+        comment: Option<String>,
+    },
+    AddComment {
+        issue: String,
+        body: String,
+    },
+}
+
+/// The `jira` node.
+#[register_node("jira")]
+#[derive(Debug, Default)]
+pub struct JiraNode {
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl ExecutableNode for JiraNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: JiraConfig = parse_config("jira", ctx)?;
+        let auth = auth(&config, ctx)?;
+        let api = Api { client: &self.client, config: &config, auth };
+        let root = json!({ "input": input });
+        let render = |tpl: &str| render_str(tpl, &root);
+
+        match &config.operation {
+            JiraOperation::CreateIssue { .. } => {
+                let body = create_body(&config.operation, &root)?;
+                let created = api.send(reqwest::Method::POST, "issue", Some(&body)).await?;
+                let key = created.get("key").and_then(Value::as_str).unwrap_or_default();
+                Ok(json!({
+                    "operation": "create_issue",
+                    "key": key,
+                    "id": created.get("id").cloned().unwrap_or(Value::Null),
+                    "url": format!("{}/browse/{key}", config.base_url.trim_end_matches('/')),
+                }))
+            }
+            JiraOperation::UpdateIssue { issue, fields } => {
+                let issue = render(issue)?;
+                let body = json!({ "fields": render_value(&Value::Object(fields.clone()), &root)? });
+                api.send(reqwest::Method::PUT, &format!("issue/{issue}"), Some(&body)).await?;
+                Ok(json!({ "operation": "update_issue", "key": issue }))
+            }
+            JiraOperation::TransitionIssue { issue, transition, comment } => {
+                let issue = render(issue)?;
+                let wanted = render(transition)?;
+                let path = format!("issue/{issue}/transitions");
+                let available = api.send(reqwest::Method::GET, &path, None).await?;
+                let (id, name) = find_transition(&available, &wanted)?;
+
+                let mut body = json!({ "transition": { "id": id } });
+                if let Some(comment) = comment {
+                    body["update"] = json!({ "comment": [{ "add": { "body": render(comment)? } }] });
+                }
+                api.send(reqwest::Method::POST, &path, Some(&body)).await?;
+                Ok(json!({
+                    "operation": "transition_issue",
+                    "key": issue,
+                    "transition": { "id": id, "name": name },
+                }))
+            }
+            JiraOperation::AddComment { issue, body } => {
+                let issue = render(issue)?;
+                let body = json!({ "body": render(body)? });
+                let comment =
+                    api.send(reqwest::Method::POST, &format!("issue/{issue}/comment"), Some(&body)).await?;
+                Ok(json!({
+                    "operation": "add_comment",
+                    "key": issue,
+                    "comment_id": comment.get("id").cloned().unwrap_or(Value::Null),
+                }))
+            }
+        }
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("Jira", "Create, update, and transition Jira issues and add comments.")
+            .category(NodeCategory::Integration)
+            .config::<JiraConfig>()
+            .optional_credential(
+                "api_token_secret",
+                "Jira Cloud API token or Data Center personal access token, unless `credential_id` is set",
+            )
+            .output("{ operation, key, id, url }")
+    }
+}
+
+/// How requests authenticate.
+#[derive(Debug, PartialEq, Eq)]
+enum Auth<'a> {
+    Basic { username: &'a str, password: &'a str },
+    Bearer(&'a str),
+}
+
+/// The authentication `config` asks for, from its credential or secret.
+fn auth<'a>(config: &'a JiraConfig, ctx: &'a ExecutionContext) -> Result<Auth<'a>, NodeError> {
+    let token = match (config.credential_id, &config.api_token_secret) {
+        (Some(id), _) => match require_credential(ctx, id)? {
+            Credential::HttpBasic { username, password } => return Ok(Auth::Basic { username, password }),
+            credential => credential.bearer_token().ok_or_else(|| {
+                NodeError::Fatal(format!("jira cannot authenticate with a {} credential", credential.kind()))
+            })?,
+        },
+        (None, Some(secret)) => require_secret(ctx, secret)?,
+        (None, None) => {
+            return Err(NodeError::Fatal("jira needs `api_token_secret` or `credential_id`".into()));
+        }
+    };
+    Ok(match &config.email {
+        Some(email) => Auth::Basic { username: email, password: token },
+        None => Auth::Bearer(token),
+    })
+}
+
+/// Authenticated access to one Jira site.
+struct Api<'a> {
+    client: &'a reqwest::Client,
+    config: &'a JiraConfig,
+    auth: Auth<'a>,
+}
+
+impl Api<'_> {
+    /// Call `/rest/api/2/{path}` and return the JSON body (`null` when the
+    /// response has none, as for updates and transitions).
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<Value, NodeError> {
+        let url = format!("{}/rest/api/2/{path}", self.config.base_url.trim_end_matches('/'));
+        let mut request = self.client.request(method, url).header("Accept", "application/json");
+        request = match self.auth {
+            Auth::Basic { username, password } => request.basic_auth(username, Some(password)),
+            Auth::Bearer(token) => request.bearer_auth(token),
+        };
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response = request.send().await.map_err(|e| transport_error(SERVICE, e))?;
+        let text = check_response(SERVICE, response)
+            .await?
+            .text()
+            .await
+            .map_err(|e| transport_error(SERVICE, e))?;
+        if text.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        serde_json::from_str(&text)
+            .map_err(|e| NodeError::Fatal(format!("invalid {SERVICE} response: {e}")))
+    }
+}
+
+fn render_str(tpl: &str, root: &Value) -> Result<String, NodeError> {
+    template::render(tpl, root).map_err(|e| NodeError::Fatal(format!("jira: {e}")))
+}
+
+/// Render every string nested in `value` as a template.
+fn render_value(value: &Value, root: &Value) -> Result<Value, NodeError> {
+    Ok(match value {
+        Value::String(s) => Value::String(render_str(s, root)?),
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|v| render_value(v, root)).collect::<Result<_, _>>()?)
+        }
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| Ok((k.clone(), render_value(v, root)?)))
+                .collect::<Result<_, NodeError>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// The `POST /issue` payload; explicit settings win over `fields`.
+fn create_body(operation: &JiraOperation, root: &Value) -> Result<Value, NodeError> {
+    let JiraOperation::CreateIssue { project, issue_type, summary, description, fields } = operation
+    else {
+        unreachable!("create_body is only called for create_issue");
+    };
+
+    let Value::Object(mut body) = render_value(&Value::Object(fields.clone()), root)? else {
+        unreachable!("rendering an object yields an object");
+    };
+    body.insert("project".into(), json!({ "key": render_str(project, root)? }));
+    body.insert("issuetype".into(), json!({ "name": render_str(issue_type, root)? }));
+    body.insert("summary".into(), json!(render_str(summary, root)?));
+    if let Some(description) = description {
+        body.insert("description".into(), json!(render_str(description, root)?));
+    }
+    Ok(json!({ "fields": body }))
+}
+
+/// Pick a transition from a `GET /issue/{key}/transitions` response by ID
+/// or case-insensitive name, returning `(id, name)`.
+fn find_transition(available: &Value, wanted: &str) -> Result<(String, String), NodeError> {
+    let transitions = available
+        .get("transitions")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let field = |t: &Value, key: &str| t.get(key).and_then(Value::as_str).unwrap_or_default().to_owned();
+
+    transitions
+        .iter()
+        .find(|t| field(t, "id") == wanted)
+        .or_else(|| transitions.iter().find(|t| field(t, "name").eq_ignore_ascii_case(wanted)))
+        .map(|t| (field(t, "id"), field(t, "name")))
+        .ok_or_else(|| {
+            let names: Vec<String> = transitions.iter().map(|t| field(t, "name")).collect();
+            NodeError::Fatal(format!(
+                "jira: transition '{wanted}' is not available; available: [{}]",
+                names.join(", ")
+            ))
+        })
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(value: Value) -> JiraConfig {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn builds_create_payload_from_templates() {
+        let cfg = config(json!({
+            "base_url": "https://acme.atlassian.net",
+            "email": "bot@acme.com",
+            "api_token_secret": "JIRA",
+            "operation": "create_issue",
+            "project": "OPS",
+            "issue_type": "Bug",
+            "summary": "Disk full on {{ input.host }}",
+            "fields": { "labels": ["{{ input.env }}"], "priority": { "name": "High" }, "summary": "ignored" },
+        }));
+        let root = json!({ "input": { "host": "db1", "env": "prod" } });
+        let body = create_body(&cfg.operation, &root).unwrap();
+
+        assert_eq!(body["fields"]["project"], json!({ "key": "OPS" }));
+        assert_eq!(body["fields"]["issuetype"], json!({ "name": "Bug" }));
+        assert_eq!(body["fields"]["summary"], "Disk full on db1");
+        assert_eq!(body["fields"]["labels"], json!(["prod"]));
+        assert_eq!(body["fields"]["priority"], json!({ "name": "High" }));
+        assert!(body["fields"].get("description").is_none());
+    }
+
+    #[test]
+    fn finds_transition_by_id_or_name() {
+        let available = json!({ "transitions": [
+            { "id": "11", "name": "In Progress" },
+            { "id": "31", "name": "Done" },
+        ]});
+        assert_eq!(find_transition(&available, "done").unwrap(), ("31".into(), "Done".into()));
+        assert_eq!(find_transition(&available, "11").unwrap(), ("11".into(), "In Progress".into()));
+
+        match find_transition(&available, "Closed") {
+            Err(NodeError::Fatal(msg)) => assert!(msg.contains("In Progress, Done"), "{msg}"),
+            other => panic!("expected fatal error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_each_operation() {
+        let op = |extra: Value| {
+            let mut value = json!({ "base_url": "https://jira.local", "api_token_secret": "PAT" });
+            value.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            config(value).operation
+        };
+        assert!(matches!(
+            op(json!({ "operation": "update_issue", "issue": "OPS-1", "fields": {} })),
+            JiraOperation::UpdateIssue { .. }
+        ));
+        assert!(matches!(
+            op(json!({ "operation": "transition_issue", "issue": "OPS-1", "transition": "Done" })),
+            JiraOperation::TransitionIssue { comment: None, .. }
+        ));
+        assert!(matches!(
+            op(json!({ "operation": "add_comment", "issue": "OPS-1", "body": "hi" })),
+            JiraOperation::AddComment { .. }
+        ));
+    }
+
+    #[test]
+    fn authenticates_with_a_secret_or_a_shared_credential() {
+        let id = Uuid::new_v4();
+        let mut ctx = ExecutionContext::new(Uuid::nil(), Uuid::nil(), Value::Null);
+        ctx.secrets.insert("PAT".into(), "pat".into());
+        ctx.credentials.insert(id, Credential::HttpBasic { username: "bot".into(), password: "pw".into() });
+        let base = json!({ "base_url": "https://jira.local", "operation": "add_comment", "issue": "OPS-1", "body": "hi" });
+        let with = |extra: Value| {
+            let mut value = base.clone();
+            value.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            config(value)
+        };
+
+        let cfg = with(json!({ "api_token_secret": "PAT" }));
+        assert_eq!(auth(&cfg, &ctx).unwrap(), Auth::Bearer("pat"));
+        let cfg = with(json!({ "credential_id": id, "email": "ignored@acme.com" }));
+        assert_eq!(auth(&cfg, &ctx).unwrap(), Auth::Basic { username: "bot", password: "pw" });
+
+        ctx.credentials.insert(id, Credential::ApiToken { token: "tok".into() });
+        let cfg = with(json!({ "credential_id": id, "email": "bot@acme.com" }));
+        assert_eq!(auth(&cfg, &ctx).unwrap(), Auth::Basic { username: "bot@acme.com", password: "tok" });
+
+        ctx.credentials.insert(id, Credential::SshKey { private_key: "k".into(), passphrase: None });
+        assert!(matches!(auth(&cfg, &ctx), Err(NodeError::Fatal(_))));
+        let cfg = with(json!({ "credential_id": Uuid::new_v4() }));
+        assert!(matches!(auth(&cfg, &ctx), Err(NodeError::Fatal(msg)) if msg.contains("missing credential")));
+        assert!(matches!(auth(&with(json!({})), &ctx), Err(NodeError::Fatal(_))));
+    }
+}
@@ -0,0 +1,392 @@
+//! `llm` node — chat completion against any OpenAI-compatible endpoint.
+//!
+//! `base_url` defaults to OpenAI but can point at a local server (Ollama,
+//! llama.cpp, vLLM, LM Studio) or a gateway that speaks the same
+//! `/chat/completions` protocol.  `api_key_secret` is optional for servers
+//! that do not check keys.  `system`, `prompt`, and every entry of
+//! `messages` are templates rendered against `{ "input": ... }`:
+//!
+//! ```json
+//! {
+//!   "base_url": "http://localhost:11434/v1",
+//!   "model": "llama3.1",
+//!   "system": "You summarise support tickets in one sentence.",
+//!   "prompt": "{{ input.ticket.body }}",
+//!   "max_tokens": 200,
+//!   "stream": true
+//! }
+//! ```
+//!
+//! Output:
+//!
+//! ```json
+//! {
+//!   "content": "...",
+//!   "finish_reason": "stop",
+//!   "truncated": false,
+//!   "model": "llama3.1",
+//!   "usage": { "prompt_tokens": 52, "completion_tokens": 18, "total_tokens": 70 }
+//! }
+//! ```
+//!
+//! With `stream: true` the reply is read as server-sent events and the
+//! deltas are joined, which keeps slow local models from hitting idle
+//! timeouts on proxies; the node output is the same either way, and a
+//! server that ignores `stream` and answers with plain JSON is handled
+//! too.  A reply cut off by `max_tokens` has `truncated: true`, or fails
+//! the node when `fail_on_truncation` is set.
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::builtin::{check_response, parse_config, require_secret, transport_error};
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, template, traits::ExecutionContext};
+
+/// One templated chat message.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct MessageTemplate {
+    /// `system`, `user`, or `assistant`.
+    pub role: String,
+    pub content: String,
+}
+
+/// Configuration for the `llm` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct LlmConfig {
+    #[serde(default = "default_base_url")]
+    pub base_url: String,
+    /// Secret holding the bearer token; omit for unauthenticated servers.
+    #[serde(default)]
+    pub api_key_secret: Option<String>,
+    #[serde(default = "default_model")]
+    pub model: String,
+    /// System message, sent first.
+    #[serde(default)]
+    pub system: Option<String>,
+    /// Conversation so far, sent after `system`.
+    #[serde(default)]
+    pub messages: Vec<MessageTemplate>,
+    /// Final user message, sent last.
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// Upper bound on generated tokens.
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    /// Ask for a server-sent event stream instead of a single response.
+    #[serde(default)]
+    pub stream: bool,
+    /// Fail instead of returning a reply cut off by `max_tokens`.
+    #[serde(default)]
+    pub fail_on_truncation: bool,
+}
+
+fn default_base_url() -> String {
+    "https://api.openai.com/v1".into()
+}
+
+fn default_model() -> String {
+    "gpt-4o-mini".into()
+}
+
+/// The `llm` node.
+#[register_node("llm")]
+#[derive(Debug, Default)]
+pub struct LlmNode {
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl ExecutableNode for LlmNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: LlmConfig = parse_config("llm", ctx)?;
+        let messages = build_messages(&config, &json!({ "input": input }))?;
+        if messages.is_empty() {
+            return Err(NodeError::Fatal(
+                "llm requires `prompt`, `system`, or `messages`".into(),
+            ));
+        }
+
+        let mut body = json!({
+            "model": config.model,
+            "messages": messages,
+            "stream": config.stream,
+        });
+        if let Some(max_tokens) = config.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        if let Some(temperature) = config.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if config.stream {
+            body["stream_options"] = json!({ "include_usage": true });
+        }
+
+        let mut request = self
+            .client
+            .post(format!("{}/chat/completions", config.base_url.trim_end_matches('/')))
+            .json(&body);
+        if let Some(secret) = &config.api_key_secret {
+            request = request.bearer_auth(require_secret(ctx, secret)?);
+        }
+        let mut response = check_response(
+            "chat completions API",
+            request.send().await.map_err(|e| transport_error("chat completions API", e))?,
+        )
+        .await?;
+
+        let is_stream = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+
+        let completion = if is_stream {
+            let mut stream = StreamAccumulator::default();
+            while let Some(chunk) = response
+                .chunk()
+                .await
+                .map_err(|e| transport_error("chat completions API", e))?
+            {
+                if stream.feed(&chunk)? {
+                    break;
+                }
+            }
+            stream.finish()?
+        } else {
+            let body: Value = response
+                .json()
+                .await
+                .map_err(|e| NodeError::Fatal(format!("invalid chat completions response: {e}")))?;
+            Completion::from_response(&body)?
+        };
+
+        if completion.truncated() && config.fail_on_truncation {
+            return Err(NodeError::Fatal(format!(
+                "llm reply was truncated at max_tokens ({})",
+                config.max_tokens.map_or("provider default".into(), |n| n.to_string())
+            )));
+        }
+        Ok(completion.into_output(&config.model))
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("LLM", "Chat completion against any OpenAI-compatible endpoint.")
+            .category(NodeCategory::Ai)
+            .config::<LlmConfig>()
+            .optional_credential("api_key_secret", "API key, for servers that check one")
+            .output("{ content, finish_reason, truncated, model, usage }")
+    }
+}
+
+/// Render the configured system message, conversation, and prompt.
+fn build_messages(config: &LlmConfig, root: &Value) -> Result<Vec<Value>, NodeError> {
+    let render = |tpl: &str| {
+        template::render(tpl, root).map_err(|e| NodeError::Fatal(format!("llm: {e}")))
+    };
+
+    let mut messages = Vec::new();
+    if let Some(system) = &config.system {
+        messages.push(json!({ "role": "system", "content": render(system)? }));
+    }
+    for message in &config.messages {
+        messages.push(json!({ "role": message.role, "content": render(&message.content)? }));
+    }
+    if let Some(prompt) = &config.prompt {
+        messages.push(json!({ "role": "user", "content": render(prompt)? }));
+    }
+    Ok(messages)
+}
+
+// ---------------------------------------------------------------------------
+// Response handling
+// ---------------------------------------------------------------------------
+
+/// A finished chat completion, however it was delivered.
+#[derive(Debug, Default, PartialEq)]
+struct Completion {
+    content: String,
+    finish_reason: Option<String>,
+    model: Option<String>,
+    usage: Option<Value>,
+}
+
+impl Completion {
+    /// Read a non-streamed `/chat/completions` response.
+    fn from_response(body: &Value) -> Result<Self, NodeError> {
+        let choice = body
+            .pointer("/choices/0")
+            .ok_or_else(|| NodeError::Fatal("chat completions response has no choices".into()))?;
+        Ok(Self {
+            content: choice
+                .pointer("/message/content")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_owned(),
+            finish_reason: choice.get("finish_reason").and_then(Value::as_str).map(str::to_owned),
+            model: body.get("model").and_then(Value::as_str).map(str::to_owned),
+            usage: body.get("usage").filter(|u| !u.is_null()).cloned(),
+        })
+    }
+
+    fn truncated(&self) -> bool {
+        self.finish_reason.as_deref() == Some("length")
+    }
+
+    fn into_output(self, requested_model: &str) -> Value {
+        let truncated = self.truncated();
+        json!({
+            "content": self.content,
+            "finish_reason": self.finish_reason,
+            "truncated": truncated,
+            "model": self.model.as_deref().unwrap_or(requested_model),
+            "usage": self.usage.unwrap_or_else(|| Value::Object(Map::new())),
+        })
+    }
+}
+
+/// Incrementally parses a server-sent event stream of completion chunks.
+///
+/// Network chunks can split lines anywhere, so bytes are buffered until a
+/// full line is available.
+#[derive(Debug, Default)]
+struct StreamAccumulator {
+    buffer: Vec<u8>,
+    completion: Completion,
+    saw_chunk: bool,
+    done: bool,
+}
+
+impl StreamAccumulator {
+    /// Consume raw bytes; returns `true` once the `[DONE]` marker arrived.
+    fn feed(&mut self, bytes: &[u8]) -> Result<bool, NodeError> {
+        self.buffer.extend_from_slice(bytes);
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            self.line(&String::from_utf8_lossy(&line))?;
+            if self.done {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn line(&mut self, line: &str) -> Result<(), NodeError> {
+        let Some(data) = line.trim_end().strip_prefix("data:") else {
+            // Comments, `event:`/`id:` fields, and blank separators.
+            return Ok(());
+        };
+        let data = data.trim_start();
+        if data == "[DONE]" {
+            self.done = true;
+            return Ok(());
+        }
+
+        let chunk: Value = serde_json::from_str(data)
+            .map_err(|e| NodeError::Fatal(format!("invalid chat completions stream chunk: {e}")))?;
+        if let Some(error) = chunk.get("error") {
+            return Err(NodeError::Retryable(format!("chat completions stream error: {error}")));
+        }
+        self.saw_chunk = true;
+
+        let completion = &mut self.completion;
+        if let Some(model) = chunk.get("model").and_then(Value::as_str) {
+            completion.model.get_or_insert_with(|| model.to_owned());
+        }
+        if let Some(usage) = chunk.get("usage").filter(|u| !u.is_null()) {
+            completion.usage = Some(usage.clone());
+        }
+        if let Some(choice) = chunk.pointer("/choices/0") {
+            if let Some(delta) = choice.pointer("/delta/content").and_then(Value::as_str) {
+                completion.content.push_str(delta);
+            }
+            if let Some(reason) = choice.get("finish_reason").and_then(Value::as_str) {
+                completion.finish_reason = Some(reason.to_owned());
+            }
+        }
+        Ok(())
+    }
+
+    /// The joined completion; fails if the stream ended before any chunk.
+    fn finish(mut self) -> Result<Completion, NodeError> {
+        if !self.buffer.is_empty() {
+            let rest = String::from_utf8_lossy(&std::mem::take(&mut self.buffer)).into_owned();
+            self.line(&rest)?;
+        }
+        if !self.saw_chunk {
+            return Err(NodeError::Retryable("chat completions stream ended without data".into()));
+        }
+        Ok(self.completion)
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_messages_in_order() {
+        let config: LlmConfig = serde_json::from_value(json!({
+            "system": "Be brief.",
+            "messages": [{ "role": "assistant", "content": "Hi {{ input.name }}" }],
+            "prompt": "Summarise: {{ input.text }}",
+        }))
+        .unwrap();
+        let messages =
+            build_messages(&config, &json!({ "input": { "name": "Ada", "text": "long" } })).unwrap();
+        assert_eq!(
+            messages,
+            vec![
+                json!({ "role": "system", "content": "Be brief." }),
+                json!({ "role": "assistant", "content": "Hi Ada" }),
+                json!({ "role": "user", "content": "Summarise: long" }),
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_stream_deltas_across_split_chunks() {
+        let events = concat!(
+            ": keep-alive\n\n",
+            "data: {\"model\":\"m\",\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"lo\"},\"finish_reason\":\"length\"}]}\n\n",
+            "data: {\"choices\":[],\"usage\":{\"total_tokens\":7}}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let mut stream = StreamAccumulator::default();
+        let mut done = false;
+        for piece in events.as_bytes().chunks(7) {
+            done = stream.feed(piece).unwrap();
+            if done {
+                break;
+            }
+        }
+        assert!(done);
+
+        let completion = stream.finish().unwrap();
+        assert!(completion.truncated());
+        let output = completion.into_output("requested");
+        assert_eq!(output["content"], "Hello");
+        assert_eq!(output["model"], "m");
+        assert_eq!(output["usage"]["total_tokens"], 7);
+    }
+
+    #[test]
+    fn reads_plain_json_responses() {
+        let body = json!({
+            "choices": [{ "message": { "role": "assistant", "content": "ok" }, "finish_reason": "stop" }],
+        });
+        let output = Completion::from_response(&body).unwrap().into_output("gpt");
+        assert_eq!(output["content"], "ok");
+        assert_eq!(output["truncated"], false);
+        assert_eq!(output["model"], "gpt");
+        assert!(StreamAccumulator::default().finish().is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! Built-in node implementations.
+//!
+//! Each node lives in its own module and is gated behind a cargo feature
+//! so embedders only compile the integrations they actually use.
+
+#[cfg(feature = "amqp-publish")]
+pub mod amqp_publish;
+#[cfg(feature = "approval")]
+pub mod approval;
+#[cfg(feature = "batch-collect")]
+pub mod batch_collect;
+#[cfg(feature = "classify")]
+pub mod classify;
+#[cfg(feature = "crypto")]
+pub mod crypto;
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(feature = "datetime")]
+pub mod datetime;
+#[cfg(feature = "error-trigger")]
+pub mod error_trigger;
+#[cfg(feature = "execute-workflow")]
+pub mod execute_workflow;
+#[cfg(feature = "html-extract")]
+pub mod html_extract;
+#[cfg(feature = "jira")]
+pub mod jira;
+#[cfg(feature = "llm")]
+pub mod llm;
+#[cfg(feature = "sftp")]
+pub mod sftp;
+#[cfg(feature = "shell")]
+pub mod shell;
+#[cfg(feature = "sort-limit")]
+pub mod sort_limit;
+#[cfg(feature = "speech")]
+pub mod speech;
+#[cfg(feature = "split-ab")]
+pub mod split_ab;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+#[cfg(feature = "time-gate")]
+pub mod time_gate;
+#[cfg(feature = "validate-json")]
+pub mod validate_json;
+#[cfg(feature = "xml")]
+pub mod xml;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+use crate::credentials::Credential;
+use crate::{NodeError, traits::ExecutionContext};
+
+/// What a command-running node does when the command exits non-zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NonZeroExit {
+    /// Fail the execution immediately.
+    #[default]
+    Fatal,
+    /// Treat the failure as transient and let the engine retry.
+    Retryable,
+}
+
+/// Deserialize the current node's `config` into a typed struct.
+///
+/// A malformed config can never succeed on retry, so it is reported as
+/// [`NodeError::Fatal`].
+pub(crate) fn parse_config<T: DeserializeOwned>(
+    node_type: &str,
+    ctx: &ExecutionContext,
+) -> Result<T, NodeError> {
+    serde_json::from_value(ctx.config.clone()).map_err(|e| {
+        NodeError::Fatal(format!("invalid {node_type} config for node '{}': {e}", ctx.node_id))
+    })
+}
+
+/// Look up a secret by key, failing fatally when it has not been configured.
+pub(crate) fn require_secret<'a>(
+    ctx: &'a ExecutionContext,
+    key: &str,
+) -> Result<&'a str, NodeError> {
+    ctx.secrets.get(key).map(String::as_str).ok_or_else(|| {
+        NodeError::Fatal(format!("missing secret '{key}' for node '{}'", ctx.node_id))
+    })
+}
+
+/// Look up the shared credential `id`, failing fatally when it does not
+/// exist or belongs to another project.
+pub(crate) fn require_credential(
+    ctx: &ExecutionContext,
+    id: uuid::Uuid,
+) -> Result<&Credential, NodeError> {
+    ctx.credentials.get(&id).ok_or_else(|| {
+        NodeError::Fatal(format!("missing credential {id} for node '{}'", ctx.node_id))
+    })
+}
+
+/// Turn a non-success HTTP response into the matching [`NodeError`].
+///
+/// Rate limiting (429) and server errors (5xx) are transient and therefore
+/// [`NodeError::Retryable`]; every other non-2xx status is
+/// [`NodeError::Fatal`].  The response body is included in the message to
+/// make provider errors debuggable.
+#[cfg(feature = "http-client")]
+pub(crate) async fn check_response(
+    service: &str,
+    response: reqwest::Response,
+) -> Result<reqwest::Response, NodeError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    let message = format!("{service} returned {status}: {body}");
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        Err(NodeError::Retryable(message))
+    } else {
+        Err(NodeError::Fatal(message))
+    }
+}
+
+/// Map a transport-level `reqwest` error (DNS, connect, timeout) to a
+/// [`NodeError::Retryable`].
+#[cfg(feature = "http-client")]
+pub(crate) fn transport_error(service: &str, err: reqwest::Error) -> NodeError {
+    NodeError::Retryable(format!("{service} request failed: {err}"))
+}
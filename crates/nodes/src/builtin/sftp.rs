@@ -0,0 +1,372 @@
+//! `sftp` node — upload, download, list, or delete files on an SFTP server.
+//!
+//! Example config:
+//!
+//! ```json
+//! {
+//!   "host": "sftp.partner.example",
+//!   "username": "etl",
+//!   "host_key_fingerprint": "SHA256:ZPo9vKkR3Cw9wXHnZ5sLgYb6Xw3jG2qN8dT1uVfE4aA",
+//!   "private_key_secret": "PARTNER_SFTP_KEY",
+//!   "operation": "upload",
+//!   "remote_path": "/inbound/orders.csv"
+//! }
+//! ```
+//!
+//! Connection, host key, and authentication settings are shared with `ssh_exec`
+//! (see [`SshConnection`]).
+//!
+//! For `upload`, the file body is taken from `input[content_field]`
+//! (default `"content"`).  `encoding` controls whether file bodies are
+//! exchanged as UTF-8 text, base64, or `binary` references to the
+//! execution's [binary data](crate::binary).
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use base64::Engine as _;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use ssh2::FileStat;
+
+use crate::binary::{guess_mime_type, BinaryRef};
+use crate::builtin::parse_config;
+use crate::builtin::ssh::{classify, Credentials, SshConnection};
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, traits::ExecutionContext};
+
+/// Operation performed against `remote_path`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SftpOperation {
+    Upload,
+    Download,
+    List,
+    Delete,
+}
+
+/// How file bodies are represented in the node's JSON input/output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentEncoding {
+    #[default]
+    Utf8,
+    Base64,
+    /// A [`BinaryRef`] to the execution's binary data.
+    Binary,
+}
+
+/// Configuration for the `sftp` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SftpConfig {
+    #[serde(flatten)]
+    pub connection: SshConnection,
+    pub operation: SftpOperation,
+    pub remote_path: String,
+    #[serde(default)]
+    pub encoding: ContentEncoding,
+    /// Input field holding the file body for uploads.
+    #[serde(default = "default_content_field")]
+    pub content_field: String,
+}
+
+fn default_content_field() -> String {
+    "content".into()
+}
+
+/// The `sftp` node.
+#[register_node("sftp")]
+#[derive(Debug, Default)]
+pub struct SftpNode;
+
+#[async_trait]
+impl ExecutableNode for SftpNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: SftpConfig = parse_config("sftp", ctx)?;
+        let credentials = config.connection.credentials(ctx)?;
+
+        // Fail before connecting when binary data cannot be stored.
+        if config.encoding == ContentEncoding::Binary {
+            ctx.require_binary()?;
+        }
+        let upload_body = if config.operation == SftpOperation::Upload {
+            Some(upload_body(&config, &input, ctx).await?)
+        } else {
+            None
+        };
+
+        let remote_path = config.remote_path.clone();
+        let (mut output, download) =
+            tokio::task::spawn_blocking(move || run_blocking(&config, &credentials, upload_body))
+                .await
+                .map_err(|e| NodeError::Fatal(format!("sftp task panicked: {e}")))??;
+
+        // Binary downloads are stored here, off the blocking thread.
+        if let Some(body) = download {
+            let file_name = Path::new(&remote_path).file_name().map(|name| name.to_string_lossy());
+            let reference = ctx
+                .store_binary(body, guess_mime_type(&remote_path), file_name.as_deref())
+                .await?;
+            output["content"] = reference.to_value();
+        }
+        Ok(output)
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("SFTP", "Upload, download, list, or delete files on an SFTP server.")
+            .category(NodeCategory::Integration)
+            .config::<SftpConfig>()
+            .optional_credential("password_secret", "Account password")
+            .optional_credential("private_key_secret", "PEM private key")
+            .optional_credential("passphrase_secret", "Passphrase of the private key")
+            .output("{ operation, remote_path, ... }: bytes, content, entries, or deleted")
+    }
+}
+
+/// Extract and decode the upload body from the node input.
+async fn upload_body(config: &SftpConfig, input: &Value, ctx: &ExecutionContext) -> Result<Vec<u8>, NodeError> {
+    let field = input.get(&config.content_field);
+    let missing = |expected: &str| {
+        NodeError::Fatal(format!(
+            "sftp upload expects {expected} in input field '{}'",
+            config.content_field
+        ))
+    };
+
+    match config.encoding {
+        ContentEncoding::Utf8 => {
+            let raw = field.and_then(Value::as_str).ok_or_else(|| missing("a string"))?;
+            Ok(raw.as_bytes().to_vec())
+        }
+        ContentEncoding::Base64 => {
+            let raw = field.and_then(Value::as_str).ok_or_else(|| missing("a string"))?;
+            base64::engine::general_purpose::STANDARD
+                .decode(raw)
+                .map_err(|e| NodeError::Fatal(format!("sftp upload body is not valid base64: {e}")))
+        }
+        ContentEncoding::Binary => {
+            let reference = field.and_then(BinaryRef::from_value).ok_or_else(|| missing("a binary reference"))?;
+            ctx.require_binary()?.get(ctx.execution_id, &reference).await
+        }
+    }
+}
+
+/// Run the operation; returns the output and, for `binary` downloads, the
+/// file body still to be stored.
+fn run_blocking(
+    config: &SftpConfig,
+    credentials: &Credentials,
+    upload_body: Option<Vec<u8>>,
+) -> Result<(Value, Option<Vec<u8>>), NodeError> {
+    let sftp = config
+        .connection
+        .open_session(credentials)?
+        .sftp()
+        .map_err(classify)?;
+    let path = Path::new(&config.remote_path);
+
+    match config.operation {
+        SftpOperation::Upload => {
+            let body = upload_body.unwrap_or_default();
+            let mut file = sftp.create(path).map_err(classify)?;
+            file.write_all(&body)
+                .map_err(|e| NodeError::Retryable(format!("sftp write failed: {e}")))?;
+            Ok((upload_output(config, body.len()), None))
+        }
+        SftpOperation::Download => {
+            let mut file = sftp.open(path).map_err(classify)?;
+            let mut body = Vec::new();
+            file.read_to_end(&mut body)
+                .map_err(|e| NodeError::Retryable(format!("sftp read failed: {e}")))?;
+            download_output(config, body)
+        }
+        SftpOperation::List => {
+            let entries = sftp.readdir(path).map_err(classify)?;
+            Ok((list_output(config, entries), None))
+        }
+        SftpOperation::Delete => {
+            sftp.unlink(path).map_err(classify)?;
+            let output = json!({
+                "operation": "delete",
+                "remote_path": config.remote_path,
+                "deleted": true,
+            });
+            Ok((output, None))
+        }
+    }
+}
+
+fn upload_output(config: &SftpConfig, bytes: usize) -> Value {
+    json!({
+        "operation": "upload",
+        "remote_path": config.remote_path,
+        "bytes": bytes,
+    })
+}
+
+/// The output for downloaded `body` and, for `binary` downloads, the body
+/// still to be stored.
+fn download_output(config: &SftpConfig, body: Vec<u8>) -> Result<(Value, Option<Vec<u8>>), NodeError> {
+    let content = match config.encoding {
+        ContentEncoding::Utf8 => Value::String(String::from_utf8(body.clone()).map_err(|_| {
+            NodeError::Fatal(format!(
+                "'{}' is not valid UTF-8; use \"encoding\": \"base64\" or \"binary\"",
+                config.remote_path
+            ))
+        })?),
+        ContentEncoding::Base64 => Value::String(base64::engine::general_purpose::STANDARD.encode(&body)),
+        ContentEncoding::Binary => Value::Null,
+    };
+
+    let output = json!({
+        "operation": "download",
+        "remote_path": config.remote_path,
+        "bytes": body.len(),
+        "content": content,
+    });
+    let download = (config.encoding == ContentEncoding::Binary).then_some(body);
+    Ok((output, download))
+}
+
+fn list_output(config: &SftpConfig, entries: Vec<(PathBuf, FileStat)>) -> Value {
+    let entries: Vec<Value> = entries
+        .into_iter()
+        .map(|(entry_path, stat)| {
+            json!({
+                "name": entry_path.file_name().map(|n| n.to_string_lossy().into_owned()),
+                "path": entry_path.to_string_lossy(),
+                "size": stat.size,
+                "is_dir": stat.is_dir(),
+                "modified": stat.mtime,
+            })
+        })
+        .collect();
+
+    json!({
+        "operation": "list",
+        "remote_path": config.remote_path,
+        "entries": entries,
+    })
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn config(extra: Value) -> SftpConfig {
+        let mut config = json!({
+            "host": "sftp.partner.example",
+            "username": "etl",
+            "operation": "upload",
+            "remote_path": "/inbound/orders.csv"
+        });
+        config.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(config).unwrap()
+    }
+
+    fn ctx() -> ExecutionContext {
+        ExecutionContext::new(Uuid::new_v4(), Uuid::new_v4(), json!({}))
+    }
+
+    #[test]
+    fn configs_default_to_utf8_bodies_in_the_content_field() {
+        let parsed = config(json!({}));
+        assert_eq!((parsed.operation, parsed.encoding), (SftpOperation::Upload, ContentEncoding::Utf8));
+        assert_eq!(parsed.content_field, "content");
+        assert_eq!((parsed.connection.port, parsed.connection.timeout_secs), (22, 30));
+
+        let parsed = config(json!({ "operation": "list", "encoding": "base64", "content_field": "csv", "port": 2222 }));
+        assert_eq!((parsed.operation, parsed.encoding), (SftpOperation::List, ContentEncoding::Base64));
+        assert_eq!((parsed.content_field.as_str(), parsed.connection.port), ("csv", 2222));
+    }
+
+    #[tokio::test]
+    async fn unknown_encodings_and_operations_are_refused_before_connecting() {
+        for bad in [json!({ "encoding": "hex" }), json!({ "operation": "rename" })] {
+            let mut raw = json!({ "host": "h", "username": "u", "operation": "upload", "remote_path": "/x" });
+            raw.as_object_mut().unwrap().extend(bad.as_object().unwrap().clone());
+            let result = SftpNode.execute(json!({ "content": "x" }), &ctx().for_node("put", raw)).await;
+            assert!(matches!(result, Err(NodeError::Fatal(message)) if message.contains("invalid sftp config")));
+        }
+    }
+
+    #[tokio::test]
+    async fn upload_bodies_are_decoded_from_text_or_base64() {
+        let input = json!({ "content": "id,total\n7,40\n", "b64": "aWQsdG90YWwK" });
+        let body = upload_body(&config(json!({})), &input, &ctx()).await.unwrap();
+        assert_eq!(body, b"id,total\n7,40\n");
+        let b64 = config(json!({ "encoding": "base64", "content_field": "b64" }));
+        assert_eq!(upload_body(&b64, &input, &ctx()).await.unwrap(), b"id,total\n");
+
+        let not_base64 = upload_body(&b64, &json!({ "b64": "not base64!" }), &ctx()).await;
+        assert!(matches!(not_base64, Err(NodeError::Fatal(message)) if message.contains("not valid base64")));
+        let missing = upload_body(&config(json!({})), &json!({ "content": 7 }), &ctx()).await;
+        assert!(matches!(missing, Err(NodeError::Fatal(message)) if message.contains("'content'")));
+    }
+
+    #[test]
+    fn uploads_report_the_bytes_written() {
+        assert_eq!(
+            upload_output(&config(json!({})), 12),
+            json!({ "operation": "upload", "remote_path": "/inbound/orders.csv", "bytes": 12 })
+        );
+    }
+
+    #[test]
+    fn downloads_carry_the_body_in_the_configured_encoding() {
+        let text = config(json!({ "operation": "download" }));
+        let (output, stored) = download_output(&text, b"7,40\n".to_vec()).unwrap();
+        assert_eq!(
+            output,
+            json!({ "operation": "download", "remote_path": "/inbound/orders.csv", "bytes": 5, "content": "7,40\n" })
+        );
+        assert_eq!(stored, None);
+        assert!(matches!(download_output(&text, vec![0xff]), Err(NodeError::Fatal(m)) if m.contains("UTF-8")));
+
+        let b64 = config(json!({ "operation": "download", "encoding": "base64" }));
+        let (output, _) = download_output(&b64, vec![0xff, 0x00]).unwrap();
+        assert_eq!((&output["content"], &output["bytes"]), (&json!("/wA="), &json!(2)));
+
+        // Binary bodies are handed back to be stored as binary data.
+        let binary = config(json!({ "operation": "download", "encoding": "binary" }));
+        let (output, stored) = download_output(&binary, vec![0xff]).unwrap();
+        assert_eq!((&output["content"], stored), (&Value::Null, Some(vec![0xff])));
+    }
+
+    #[test]
+    fn listings_describe_each_entry() {
+        let modified = 1_700_000_000;
+        let stat = |size, perm| FileStat {
+            size: Some(size),
+            uid: None,
+            gid: None,
+            perm: Some(perm),
+            atime: None,
+            mtime: Some(modified),
+        };
+        let entries = vec![
+            (PathBuf::from("/inbound/orders.csv"), stat(120, 0o100644)),
+            (PathBuf::from("/inbound/archive"), stat(4096, 0o040755)),
+        ];
+        let output = list_output(&config(json!({ "operation": "list", "remote_path": "/inbound" })), entries);
+        assert_eq!((&output["operation"], &output["remote_path"]), (&json!("list"), &json!("/inbound")));
+        assert_eq!(
+            output["entries"],
+            json!([
+                {
+                    "name": "orders.csv",
+                    "path": "/inbound/orders.csv",
+                    "size": 120,
+                    "is_dir": false,
+                    "modified": modified
+                },
+                { "name": "archive", "path": "/inbound/archive", "size": 4096, "is_dir": true, "modified": modified }
+            ])
+        );
+    }
+}
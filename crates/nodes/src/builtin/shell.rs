@@ -0,0 +1,298 @@
+//! `shell` node — run a local program and capture its output.
+//!
+//! Running arbitrary commands on the worker host is dangerous, so the node
+//! is **disabled by default**: the operator must construct it with
+//! [`ShellSettings`] where `enabled = true` (`worker --shell-enabled`).
+//! Those server-side settings also define the sandbox every invocation
+//! runs in — a fixed working directory, an allowlist of environment
+//! variables passed through from the worker, an optional allowlist of
+//! programs, and a timeout ceiling.  The timeout covers feeding stdin too.
+//!
+//! Workflow config:
+//!
+//! ```json
+//! {
+//!   "program": "convert",
+//!   "args": ["{{ input.file }}", "-resize", "50%", "out.png"],
+//!   "timeout_secs": 20
+//! }
+//! ```
+//!
+//! Arguments are rendered with [`crate::template`] against `{ "input": … }`.
+//! The program is executed directly (no shell), so template values can
+//! never inject extra commands.
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::builtin::{parse_config, NonZeroExit};
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, template, traits::ExecutionContext};
+
+/// Server-side sandbox settings for the `shell` node.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ShellSettings {
+    /// Master switch; when `false` every invocation fails fatally.
+    pub enabled: bool,
+    /// Directory commands run in (defaults to the OS temp dir).
+    pub working_dir: Option<PathBuf>,
+    /// Names of worker environment variables passed to the child process.
+    /// Everything else is cleared.
+    pub env_allowlist: Vec<String>,
+    /// If set, only these program names/paths may be executed.
+    pub allowed_programs: Option<Vec<String>>,
+    /// Upper bound for any per-node `timeout_secs`.
+    pub max_timeout_secs: u64,
+}
+
+impl Default for ShellSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            working_dir: None,
+            env_allowlist: vec!["PATH".into()],
+            allowed_programs: None,
+            max_timeout_secs: 60,
+        }
+    }
+}
+
+/// Configuration for the `shell` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ShellConfig {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Per-node timeout, capped at [`ShellSettings::max_timeout_secs`].
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Input field whose string value is written to the child's stdin.
+    #[serde(default)]
+    pub stdin_field: Option<String>,
+    #[serde(default)]
+    pub on_nonzero_exit: NonZeroExit,
+}
+
+/// The `shell` node.
+///
+/// Output: `{ "stdout": "...", "stderr": "...", "exit_code": 0 }`.
+#[register_node("shell")]
+#[derive(Debug, Default)]
+pub struct ShellNode {
+    settings: ShellSettings,
+}
+
+impl ShellNode {
+    pub fn new(settings: ShellSettings) -> Self {
+        Self { settings }
+    }
+}
+
+#[async_trait]
+impl ExecutableNode for ShellNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        if !self.settings.enabled {
+            return Err(NodeError::Fatal(
+                "the shell node is disabled on this server".into(),
+            ));
+        }
+
+        let config: ShellConfig = parse_config("shell", ctx)?;
+
+        if let Some(allowed) = &self.settings.allowed_programs {
+            if !allowed.contains(&config.program) {
+                return Err(NodeError::Fatal(format!(
+                    "program '{}' is not in the shell allowlist",
+                    config.program
+                )));
+            }
+        }
+
+        let data = json!({ "input": input });
+        let args = config
+            .args
+            .iter()
+            .map(|arg| template::render(arg, &data))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| NodeError::Fatal(format!("cannot render shell args: {e}")))?;
+
+        let stdin_body = config
+            .stdin_field
+            .as_deref()
+            .map(|field| {
+                input.get(field).and_then(Value::as_str).ok_or_else(|| {
+                    NodeError::Fatal(format!("shell expects a string in input field '{field}'"))
+                })
+            })
+            .transpose()?;
+
+        let working_dir = self.settings.working_dir.clone().unwrap_or_else(std::env::temp_dir);
+        let mut command = Command::new(&config.program);
+        command
+            .args(&args)
+            .current_dir(working_dir)
+            .env_clear()
+            .envs(
+                self.settings
+                    .env_allowlist
+                    .iter()
+                    .filter_map(|key| std::env::var(key).ok().map(|v| (key.clone(), v))),
+            )
+            .stdin(if stdin_body.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let mut child = command.spawn().map_err(|e| {
+            NodeError::Fatal(format!("cannot start '{}': {e}", config.program))
+        })?;
+
+        // Feed stdin while collecting the output, so neither side blocks
+        // on a full pipe; closing stdin tells the child it has it all.
+        let stdin = child.stdin.take();
+        let feed = async move {
+            if let (Some(body), Some(mut stdin)) = (stdin_body, stdin) {
+                match stdin.write_all(body.as_bytes()).await {
+                    // The child exited without reading all of it.
+                    Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {}
+                    result => result.map_err(|e| NodeError::Retryable(format!("cannot write to stdin: {e}")))?,
+                }
+            }
+            Ok::<_, NodeError>(())
+        };
+        let run = async {
+            let (fed, output) = tokio::join!(feed, child.wait_with_output());
+            fed?;
+            output.map_err(|e| NodeError::Retryable(format!("failed waiting for '{}': {e}", config.program)))
+        };
+
+        let timeout_secs = config
+            .timeout_secs
+            .unwrap_or(self.settings.max_timeout_secs)
+            .min(self.settings.max_timeout_secs);
+        // On timeout the future (and with it the child, via kill_on_drop) is dropped.
+        let output = tokio::time::timeout(Duration::from_secs(timeout_secs), run)
+            .await
+            .map_err(|_| {
+                NodeError::Retryable(format!(
+                    "'{}' timed out after {timeout_secs}s",
+                    config.program
+                ))
+            })??;
+
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        // `None` means the process was killed by a signal.
+        let exit_code = output.status.code().unwrap_or(-1);
+
+        if exit_code != 0 {
+            let message = format!(
+                "'{}' exited with status {exit_code}: {}",
+                config.program,
+                stderr.trim()
+            );
+            return Err(match config.on_nonzero_exit {
+                NonZeroExit::Fatal => NodeError::Fatal(message),
+                NonZeroExit::Retryable => NodeError::Retryable(message),
+            });
+        }
+
+        Ok(json!({
+            "stdout": stdout,
+            "stderr": stderr,
+            "exit_code": exit_code,
+        }))
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("Shell", "Run a local program on the worker and capture its output.")
+            .category(NodeCategory::System)
+            .config::<ShellConfig>()
+            .output("{ stdout, stderr, exit_code }")
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled() -> ShellSettings {
+        ShellSettings { enabled: true, ..ShellSettings::default() }
+    }
+
+    async fn run(settings: ShellSettings, input: Value, config: Value) -> Result<Value, NodeError> {
+        let ctx = ExecutionContext::new(uuid::Uuid::new_v4(), uuid::Uuid::new_v4(), json!({})).for_node("run", config);
+        ShellNode::new(settings).execute(input, &ctx).await
+    }
+
+    #[tokio::test]
+    async fn the_node_is_disabled_by_default() {
+        let result = run(ShellSettings::default(), json!({}), json!({ "program": "true" })).await;
+        assert!(matches!(result, Err(NodeError::Fatal(message)) if message.contains("disabled")));
+        assert!(run(enabled(), json!({}), json!({ "program": "true" })).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn only_allowed_programs_run() {
+        let settings = ShellSettings { allowed_programs: Some(vec!["echo".into()]), ..enabled() };
+        let refused = run(settings.clone(), json!({}), json!({ "program": "sh", "args": ["-c", "true"] })).await;
+        assert!(matches!(refused, Err(NodeError::Fatal(message)) if message.contains("allowlist")));
+
+        let output = run(settings, json!({ "name": "a; rm -rf /" }), json!({ "program": "echo", "args": ["{{ input.name }}"] }))
+            .await
+            .unwrap();
+        assert_eq!(output["stdout"], "a; rm -rf /\n");
+    }
+
+    #[tokio::test]
+    async fn only_allowlisted_variables_reach_the_child() {
+        // PATH is allowlisted by default; CARGO is set for every test run.
+        assert!(std::env::var("CARGO").is_ok());
+        let output = run(enabled(), json!({}), json!({ "program": "env" })).await.unwrap();
+        let names: Vec<&str> =
+            output["stdout"].as_str().unwrap().lines().filter_map(|line| line.split_once('=')).map(|(name, _)| name).collect();
+        assert_eq!(names, ["PATH"]);
+
+        let settings = ShellSettings { env_allowlist: vec!["PATH".into(), "CARGO".into()], ..enabled() };
+        let output = run(settings, json!({}), json!({ "program": "env" })).await.unwrap();
+        assert!(output["stdout"].as_str().unwrap().lines().any(|line| line.starts_with("CARGO=")));
+    }
+
+    #[tokio::test]
+    async fn stdin_is_fed_while_the_output_is_read() {
+        // More than a pipe buffer each way: writing all of stdin before
+        // reading stdout would block forever.
+        let body = "x".repeat(1 << 20);
+        let output = run(enabled(), json!({ "body": body }), json!({ "program": "cat", "stdin_field": "body", "timeout_secs": 10 }))
+            .await
+            .unwrap();
+        assert_eq!(output["stdout"].as_str().unwrap().len(), body.len());
+    }
+
+    #[tokio::test]
+    async fn slow_programs_time_out_within_the_ceiling() {
+        let settings = ShellSettings { max_timeout_secs: 1, ..enabled() };
+        let started = std::time::Instant::now();
+        // The node asks for longer than the ceiling allows.
+        let result = run(settings, json!({}), json!({ "program": "sleep", "args": ["5"], "timeout_secs": 30 })).await;
+        assert!(matches!(result, Err(NodeError::Retryable(message)) if message.contains("timed out after 1s")));
+        assert!(started.elapsed() < Duration::from_secs(4));
+
+        // A child that never reads its stdin cannot hold the node past the timeout either.
+        let settings = ShellSettings { max_timeout_secs: 1, ..enabled() };
+        let body = "x".repeat(1 << 20);
+        let result = run(settings, json!({ "body": body }), json!({ "program": "sleep", "args": ["5"], "stdin_field": "body" })).await;
+        assert!(matches!(result, Err(NodeError::Retryable(message)) if message.contains("timed out")));
+    }
+}
@@ -0,0 +1,236 @@
+//! `sort_limit` node — order an array by one or more fields and keep the
+//! first N items.
+//!
+//! ```json
+//! {
+//!   "field": "rows",
+//!   "sort": [
+//!     { "field": "revenue", "order": "desc", "type": "numeric" },
+//!     { "field": "customer.name" }
+//!   ],
+//!   "limit": 10
+//! }
+//! ```
+//!
+//! The array is read from `input[field]` (a dotted path), or is the input
+//! itself when `field` is unset.  Keys are compared in order, each either
+//! ascending (the default) or descending; the sort is stable, so items that
+//! tie on every key keep their input order.
+//!
+//! A key's `type` decides how values are compared:
+//!
+//! * `auto` (default): numbers numerically, strings lexicographically, and
+//!   numbers before strings when an array mixes them.
+//! * `numeric`: numbers and numeric strings (`"12.5"`) numerically.
+//! * `string`: every scalar by its text.
+//!
+//! Items where a key is missing, `null`, or not comparable under its type
+//! always sort last, whatever the order.
+//!
+//! Output: `{ "items": [...], "count": 10, "total": 250 }`, where `total` is
+//! the array length before truncation.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::builtin::parse_config;
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, template, traits::ExecutionContext};
+
+/// Sort direction of one key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// How the values of one key are compared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortType {
+    #[default]
+    Auto,
+    Numeric,
+    String,
+}
+
+/// One sort key.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SortKey {
+    /// Dotted path inside each item; an empty path sorts by the item itself.
+    pub field: String,
+    #[serde(default)]
+    pub order: SortOrder,
+    #[serde(default, rename = "type")]
+    pub kind: SortType,
+}
+
+/// Configuration for the `sort_limit` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SortLimitConfig {
+    /// Dotted input path of the array; the whole input if unset.
+    #[serde(default)]
+    pub field: Option<String>,
+    /// Keys to sort by, most significant first.  No keys keeps input order.
+    #[serde(default)]
+    pub sort: Vec<SortKey>,
+    /// Keep at most this many items after sorting.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// The `sort_limit` node.
+#[register_node("sort_limit")]
+#[derive(Debug, Default)]
+pub struct SortLimitNode;
+
+#[async_trait]
+impl ExecutableNode for SortLimitNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: SortLimitConfig = parse_config("sort_limit", ctx)?;
+        let items = match &config.field {
+            Some(path) => template::lookup(&input, path).ok_or_else(|| {
+                NodeError::Fatal(format!("sort_limit: input has no field '{path}'"))
+            })?,
+            None => &input,
+        };
+        let items = items
+            .as_array()
+            .ok_or_else(|| NodeError::Fatal("sort_limit: expected an array to sort".into()))?;
+
+        let total = items.len();
+        let items = sort_limit(items.clone(), &config.sort, config.limit);
+        Ok(json!({ "count": items.len(), "total": total, "items": items }))
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("Sort & limit", "Order an array by one or more fields and keep the first N items.")
+            .category(NodeCategory::Data)
+            .config::<SortLimitConfig>()
+            .input("an array, in input[field] or as the input itself")
+            .output("{ items, count, total }")
+    }
+}
+
+/// Stable-sort `items` by `keys`, then truncate to `limit`.
+fn sort_limit(mut items: Vec<Value>, keys: &[SortKey], limit: Option<usize>) -> Vec<Value> {
+    if !keys.is_empty() {
+        items.sort_by(|a, b| {
+            keys.iter()
+                .map(|key| compare_key(a, b, key))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+    if let Some(limit) = limit {
+        items.truncate(limit);
+    }
+    items
+}
+
+/// A key's value in comparable form.
+#[derive(Debug, PartialEq, PartialOrd)]
+enum SortValue<'a> {
+    Number(f64),
+    Text(Cow<'a, str>),
+}
+
+fn sort_value<'a>(item: &'a Value, key: &SortKey) -> Option<SortValue<'a>> {
+    let value = template::lookup(item, &key.field)?;
+    match (key.kind, value) {
+        (_, Value::Null) => None,
+        (SortType::Auto | SortType::Numeric, Value::Number(n)) => n.as_f64().map(SortValue::Number),
+        (SortType::Auto, Value::String(s)) => Some(SortValue::Text(s.as_str().into())),
+        (SortType::Numeric, Value::String(s)) => s.trim().parse().ok().map(SortValue::Number),
+        (SortType::String, Value::String(s)) => Some(SortValue::Text(s.as_str().into())),
+        (SortType::String, Value::Number(_) | Value::Bool(_)) => Some(SortValue::Text(value.to_string().into())),
+        _ => None,
+    }
+}
+
+/// Compare two items on one key; missing values sort last in either order.
+fn compare_key(a: &Value, b: &Value, key: &SortKey) -> Ordering {
+    match (sort_value(a, key), sort_value(b, key)) {
+        (Some(a), Some(b)) => {
+            let ordering = a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+            match key.order {
+                SortOrder::Asc => ordering,
+                SortOrder::Desc => ordering.reverse(),
+            }
+        }
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(value: Value) -> Vec<SortKey> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn names(items: &[Value]) -> Vec<&str> {
+        items.iter().map(|i| i["name"].as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn sorts_by_several_keys_and_truncates() {
+        let rows = vec![
+            json!({ "name": "a", "region": "eu", "revenue": 10 }),
+            json!({ "name": "b", "region": "us", "revenue": 30 }),
+            json!({ "name": "c", "region": "eu", "revenue": 30 }),
+            json!({ "name": "d", "region": "us", "revenue": 5.5 }),
+        ];
+        let sort = keys(json!([{ "field": "revenue", "order": "desc" }, { "field": "region" }]));
+        assert_eq!(names(&sort_limit(rows.clone(), &sort, None)), ["c", "b", "a", "d"]);
+        assert_eq!(names(&sort_limit(rows.clone(), &sort, Some(2))), ["c", "b"]);
+        // No keys keeps the input order.
+        assert_eq!(names(&sort_limit(rows, &[], Some(3))), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn type_controls_comparison_and_missing_values_sort_last() {
+        let rows = vec![
+            json!({ "name": "a", "v": "10" }),
+            json!({ "name": "b", "v": "9" }),
+            json!({ "name": "c" }),
+            json!({ "name": "d", "v": 2 }),
+            json!({ "name": "e", "v": null }),
+        ];
+        let numeric = keys(json!([{ "field": "v", "type": "numeric", "order": "desc" }]));
+        assert_eq!(names(&sort_limit(rows.clone(), &numeric, None)), ["a", "b", "d", "c", "e"]);
+
+        let string = keys(json!([{ "field": "v", "type": "string" }]));
+        assert_eq!(names(&sort_limit(rows.clone(), &string, None)), ["a", "d", "b", "c", "e"]);
+
+        // Auto: numbers before strings, strings compared as text.
+        let auto = keys(json!([{ "field": "v" }]));
+        assert_eq!(names(&sort_limit(rows, &auto, None)), ["d", "a", "b", "c", "e"]);
+    }
+
+    #[tokio::test]
+    async fn reads_the_array_from_a_nested_field() {
+        let ctx = ExecutionContext::new(uuid::Uuid::nil(), uuid::Uuid::nil(), json!({})).for_node(
+            "top",
+            json!({ "field": "report.rows", "sort": [{ "field": "n", "order": "desc" }], "limit": 1 }),
+        );
+        let input = json!({ "report": { "rows": [{ "n": 1 }, { "n": 3 }, { "n": 2 }] } });
+        let out = SortLimitNode.execute(input, &ctx).await.unwrap();
+        assert_eq!(out, json!({ "items": [{ "n": 3 }], "count": 1, "total": 3 }));
+
+        let err = SortLimitNode.execute(json!({ "report": {} }), &ctx).await.unwrap_err();
+        assert!(matches!(err, NodeError::Fatal(_)));
+    }
+}
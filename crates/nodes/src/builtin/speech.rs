@@ -0,0 +1,423 @@
+//! `transcribe` (speech-to-text) and `tts` (text-to-speech) nodes.
+//!
+//! Both talk to an OpenAI-compatible audio API (`/audio/transcriptions` and
+//! `/audio/speech`); point `base_url` at another provider or a self-hosted
+//! gateway that speaks the same protocol.  The API key is read from the
+//! workflow secret named by `api_key_secret`.
+//!
+//! Audio is exchanged inside the JSON payload: `transcribe` reads it from
+//! `input[audio_field]`, as base64 or a reference to the execution's
+//! [binary data](crate::binary); `tts` writes it to `output.audio`, as
+//! base64 or, with `"binary": true`, as a reference.
+
+use async_trait::async_trait;
+use base64::Engine as _;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::binary::{guess_mime_type, BinaryRef};
+use crate::builtin::{check_response, parse_config, require_secret, transport_error};
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, traits::ExecutionContext};
+
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+fn default_base_url() -> String {
+    DEFAULT_BASE_URL.into()
+}
+
+// ---------------------------------------------------------------------------
+// transcribe
+// ---------------------------------------------------------------------------
+
+/// Configuration for the `transcribe` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct TranscribeConfig {
+    #[serde(default = "default_base_url")]
+    pub base_url: String,
+    pub api_key_secret: String,
+    #[serde(default = "default_transcribe_model")]
+    pub model: String,
+    /// Input field holding the audio: base64, or a binary reference.
+    #[serde(default = "default_audio_field")]
+    pub audio_field: String,
+    /// File name sent to the provider; its extension tells it the format.
+    /// A binary reference's own file name takes precedence.
+    #[serde(default = "default_file_name")]
+    pub file_name: String,
+    /// Optional ISO-639-1 language hint.
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Optional prompt to guide vocabulary/spelling.
+    #[serde(default)]
+    pub prompt: Option<String>,
+}
+
+fn default_transcribe_model() -> String {
+    "whisper-1".into()
+}
+
+fn default_audio_field() -> String {
+    "audio".into()
+}
+
+fn default_file_name() -> String {
+    "audio.mp3".into()
+}
+
+/// Speech-to-text node.
+///
+/// Output: `{ "text": "...", "language": "en" | null, "duration": 12.3 | null }`.
+#[register_node("transcribe")]
+#[derive(Debug, Default)]
+pub struct TranscribeNode {
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl ExecutableNode for TranscribeNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: TranscribeConfig = parse_config("transcribe", ctx)?;
+        let api_key = require_secret(ctx, &config.api_key_secret)?;
+
+        let field = input.get(&config.audio_field);
+        let (audio, file_name) = match field.and_then(BinaryRef::from_value) {
+            Some(reference) => {
+                let audio = ctx.require_binary()?.get(ctx.execution_id, &reference).await?;
+                (audio, reference.file_name.unwrap_or_else(|| config.file_name.clone()))
+            }
+            None => {
+                let encoded = field.and_then(Value::as_str).ok_or_else(|| {
+                    NodeError::Fatal(format!(
+                        "transcribe expects base64 audio or a binary reference in input field '{}'",
+                        config.audio_field
+                    ))
+                })?;
+                let audio = base64::engine::general_purpose::STANDARD
+                    .decode(encoded)
+                    .map_err(|e| NodeError::Fatal(format!("audio is not valid base64: {e}")))?;
+                (audio, config.file_name.clone())
+            }
+        };
+
+        let mut form = reqwest::multipart::Form::new()
+            .part(
+                "file",
+                reqwest::multipart::Part::bytes(audio).file_name(file_name),
+            )
+            .text("model", config.model.clone())
+            .text("response_format", "verbose_json");
+        if let Some(language) = &config.language {
+            form = form.text("language", language.clone());
+        }
+        if let Some(prompt) = &config.prompt {
+            form = form.text("prompt", prompt.clone());
+        }
+
+        let response = self
+            .client
+            .post(format!("{}/audio/transcriptions", config.base_url.trim_end_matches('/')))
+            .bearer_auth(api_key)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| transport_error("transcription API", e))?;
+        let body: Value = check_response("transcription API", response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| NodeError::Fatal(format!("invalid transcription response: {e}")))?;
+
+        Ok(json!({
+            "text": body.get("text").cloned().unwrap_or(Value::Null),
+            "language": body.get("language").cloned().unwrap_or(Value::Null),
+            "duration": body.get("duration").cloned().unwrap_or(Value::Null),
+        }))
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("Transcribe", "Speech to text through an OpenAI-compatible audio API.")
+            .category(NodeCategory::Ai)
+            .config::<TranscribeConfig>()
+            .credential("api_key_secret", "API key of the audio API")
+            .input("base64 audio or a binary reference in input[audio_field]")
+            .output("{ text, language, duration }")
+    }
+}
+
+// ---------------------------------------------------------------------------
+// tts
+// ---------------------------------------------------------------------------
+
+/// Configuration for the `tts` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct TtsConfig {
+    #[serde(default = "default_base_url")]
+    pub base_url: String,
+    pub api_key_secret: String,
+    #[serde(default = "default_tts_model")]
+    pub model: String,
+    #[serde(default = "default_voice")]
+    pub voice: String,
+    /// Audio container requested from the provider (`mp3`, `opus`, `wav`, …).
+    #[serde(default = "default_format")]
+    pub format: String,
+    /// Input field holding the text to speak.
+    #[serde(default = "default_text_field")]
+    pub text_field: String,
+    /// Store the audio as binary data and output a reference to it
+    /// instead of base64.
+    #[serde(default)]
+    pub binary: bool,
+}
+
+fn default_tts_model() -> String {
+    "tts-1".into()
+}
+
+fn default_voice() -> String {
+    "alloy".into()
+}
+
+fn default_format() -> String {
+    "mp3".into()
+}
+
+fn default_text_field() -> String {
+    "text".into()
+}
+
+/// Text-to-speech node.
+///
+/// Output: `{ "audio": "<base64>" | { "$binary": … }, "format": "mp3", "bytes": 12345 }`.
+#[register_node("tts")]
+#[derive(Debug, Default)]
+pub struct TtsNode {
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl ExecutableNode for TtsNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: TtsConfig = parse_config("tts", ctx)?;
+        let api_key = require_secret(ctx, &config.api_key_secret)?;
+
+        if config.binary {
+            ctx.require_binary()?;
+        }
+        let text = input
+            .get(&config.text_field)
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                NodeError::Fatal(format!("tts expects text in input field '{}'", config.text_field))
+            })?;
+
+        let response = self
+            .client
+            .post(format!("{}/audio/speech", config.base_url.trim_end_matches('/')))
+            .bearer_auth(api_key)
+            .json(&json!({
+                "model": config.model,
+                "voice": config.voice,
+                "input": text,
+                "response_format": config.format,
+            }))
+            .send()
+            .await
+            .map_err(|e| transport_error("speech API", e))?;
+        let audio = check_response("speech API", response)
+            .await?
+            .bytes()
+            .await
+            .map_err(|e| transport_error("speech API", e))?;
+
+        let bytes = audio.len();
+        let audio = if config.binary {
+            let file_name = format!("speech.{}", config.format);
+            ctx.store_binary(audio.to_vec(), guess_mime_type(&file_name), Some(&file_name))
+                .await?
+                .to_value()
+        } else {
+            Value::String(base64::engine::general_purpose::STANDARD.encode(&audio))
+        };
+
+        Ok(json!({
+            "audio": audio,
+            "format": config.format,
+            "bytes": bytes,
+        }))
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("Text to speech", "Text to speech through an OpenAI-compatible audio API.")
+            .category(NodeCategory::Ai)
+            .config::<TtsConfig>()
+            .credential("api_key_secret", "API key of the audio API")
+            .output("{ audio, format, bytes }, with base64 audio or a binary reference")
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    use super::*;
+    use crate::binary::{BinaryStore, InMemoryBinaryStore};
+
+    /// Answer one request on a local port with `status` and `body`; the
+    /// handle resolves to the raw request, head and body.
+    async fn answer_once(status: u16, content_type: &'static str, body: Vec<u8>) -> (String, JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut chunk = [0; 8192];
+            while !complete(&request) {
+                let n = stream.read(&mut chunk).await.unwrap();
+                assert!(n > 0, "the client hung up mid-request");
+                request.extend_from_slice(&chunk[..n]);
+            }
+            let head = format!(
+                "HTTP/1.1 {status} Answer\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(head.as_bytes()).await.unwrap();
+            stream.write_all(&body).await.unwrap();
+            request
+        });
+        (base_url, server)
+    }
+
+    /// Whether `request` holds a whole request with a `Content-Length` body.
+    fn complete(request: &[u8]) -> bool {
+        let Some(head_end) = request.windows(4).position(|w| w == b"\r\n\r\n") else { return false };
+        let head = String::from_utf8_lossy(&request[..head_end]).to_ascii_lowercase();
+        let length = head.lines().find_map(|line| line.strip_prefix("content-length:"));
+        let length = length.map_or(0, |n| n.trim().parse().unwrap());
+        request.len() >= head_end + 4 + length
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|window| window == needle)
+    }
+
+    fn context(config: Value) -> ExecutionContext {
+        let mut ctx =
+            ExecutionContext::new(uuid::Uuid::new_v4(), uuid::Uuid::new_v4(), json!({})).for_node("speech", config);
+        ctx.secrets.insert("audio_key".into(), "sk-test".into());
+        ctx
+    }
+
+    /// The multipart field `name` of `request` carries `value`.
+    fn has_field(request: &[u8], name: &str, value: &str) -> bool {
+        contains(request, format!("name=\"{name}\"\r\n\r\n{value}\r\n").as_bytes())
+    }
+
+    #[tokio::test]
+    async fn transcribe_uploads_base64_audio_and_keeps_text_language_and_duration() {
+        let answer = json!({ "text": "hello there", "language": "english", "duration": 1.5, "segments": [{ "id": 0 }] });
+        let (base_url, server) = answer_once(200, "application/json", answer.to_string().into_bytes()).await;
+        let config =
+            json!({ "base_url": format!("{base_url}/"), "api_key_secret": "audio_key", "language": "en", "prompt": "Ada" });
+        let input = json!({ "audio": base64::engine::general_purpose::STANDARD.encode(b"ID3 not really mp3") });
+
+        let output = TranscribeNode::default().execute(input, &context(config)).await.unwrap();
+        assert_eq!(output, json!({ "text": "hello there", "language": "english", "duration": 1.5 }));
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with(b"POST /audio/transcriptions HTTP/1.1\r\n"));
+        assert!(contains(&request.to_ascii_lowercase(), b"authorization: bearer sk-test\r\n"));
+        assert!(contains(&request, b"filename=\"audio.mp3\"") && contains(&request, b"ID3 not really mp3"));
+        let fields = [("model", "whisper-1"), ("response_format", "verbose_json"), ("language", "en"), ("prompt", "Ada")];
+        for (name, value) in fields {
+            assert!(has_field(&request, name, value), "{name}: {}", String::from_utf8_lossy(&request));
+        }
+    }
+
+    #[tokio::test]
+    async fn transcribe_reads_binary_references_under_their_own_file_name() {
+        let (base_url, server) = answer_once(200, "application/json", br#"{ "text": "hi" }"#.to_vec()).await;
+        let store = Arc::new(InMemoryBinaryStore::default());
+        let config = json!({ "base_url": base_url, "api_key_secret": "audio_key", "audio_field": "recording" });
+        let ctx = context(config).with_binary(store.clone());
+        let recording = store.put(ctx.execution_id, b"RIFF wave".to_vec(), "audio/wav", Some("call.wav")).await.unwrap();
+
+        let output = TranscribeNode::default().execute(json!({ "recording": recording.to_value() }), &ctx).await.unwrap();
+        assert_eq!(output, json!({ "text": "hi", "language": null, "duration": null }));
+        let request = server.await.unwrap();
+        assert!(contains(&request, b"filename=\"call.wav\"") && contains(&request, b"RIFF wave"));
+        assert!(!has_field(&request, "language", "en") && !contains(&request, b"name=\"prompt\""));
+    }
+
+    #[tokio::test]
+    async fn transcribe_needs_audio_and_a_store_for_references() {
+        let config = json!({ "base_url": "http://127.0.0.1:9", "api_key_secret": "audio_key" });
+        let missing = TranscribeNode::default().execute(json!({}), &context(config.clone())).await;
+        assert!(matches!(missing, Err(NodeError::Fatal(message)) if message.contains("input field 'audio'")));
+        let garbled = TranscribeNode::default().execute(json!({ "audio": "not base64!" }), &context(config.clone())).await;
+        assert!(matches!(garbled, Err(NodeError::Fatal(message)) if message.contains("base64")));
+
+        let reference = BinaryRef { id: uuid::Uuid::new_v4(), mime_type: "audio/wav".into(), file_name: None, size: 1 };
+        let unstored = TranscribeNode::default().execute(json!({ "audio": reference.to_value() }), &context(config)).await;
+        assert!(matches!(unstored, Err(NodeError::Fatal(_))), "{unstored:?}");
+    }
+
+    #[tokio::test]
+    async fn tts_sends_the_text_and_returns_base64_audio() {
+        let (base_url, server) = answer_once(200, "audio/ogg", b"OggS voice".to_vec()).await;
+        let config = json!({ "base_url": base_url, "api_key_secret": "audio_key", "voice": "nova", "format": "opus" });
+
+        let output = TtsNode::default().execute(json!({ "text": "Hi Ada" }), &context(config)).await.unwrap();
+        let audio = base64::engine::general_purpose::STANDARD.encode(b"OggS voice");
+        assert_eq!(output, json!({ "audio": audio, "format": "opus", "bytes": 10 }));
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with(b"POST /audio/speech HTTP/1.1\r\n"));
+        assert!(contains(&request.to_ascii_lowercase(), b"authorization: bearer sk-test\r\n"));
+        let head_end = request.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let body: Value = serde_json::from_slice(&request[head_end + 4..]).unwrap();
+        assert_eq!(body, json!({ "model": "tts-1", "voice": "nova", "input": "Hi Ada", "response_format": "opus" }));
+    }
+
+    #[tokio::test]
+    async fn tts_stores_audio_as_binary_data_when_asked() {
+        let (base_url, server) = answer_once(200, "audio/mpeg", b"ID3 speech".to_vec()).await;
+        let store = Arc::new(InMemoryBinaryStore::default());
+        let config = json!({ "base_url": base_url, "api_key_secret": "audio_key", "binary": true });
+        let ctx = context(config.clone()).with_binary(store.clone());
+
+        let output = TtsNode::default().execute(json!({ "text": "Hi" }), &ctx).await.unwrap();
+        assert_eq!((&output["format"], &output["bytes"]), (&json!("mp3"), &json!(10)));
+        let reference = BinaryRef::from_value(&output["audio"]).expect("a binary reference");
+        assert_eq!((reference.file_name.as_deref(), reference.mime_type.as_str()), (Some("speech.mp3"), "audio/mpeg"));
+        assert_eq!(store.get(ctx.execution_id, &reference).await.unwrap(), b"ID3 speech");
+        server.await.unwrap();
+
+        // Without a store nothing is sent.
+        let refused = TtsNode::default().execute(json!({ "text": "Hi" }), &context(config)).await;
+        assert!(matches!(refused, Err(NodeError::Fatal(_))), "{refused:?}");
+    }
+
+    #[tokio::test]
+    async fn provider_errors_are_fatal_unless_worth_retrying() {
+        for (status, retryable) in [(400, false), (429, true), (503, true)] {
+            let (base_url, server) = answer_once(status, "application/json", br#"{ "error": "nope" }"#.to_vec()).await;
+            let config = json!({ "base_url": base_url, "api_key_secret": "audio_key" });
+            let result = TtsNode::default().execute(json!({ "text": "Hi" }), &context(config)).await;
+            match result {
+                Err(NodeError::Retryable(message)) if retryable => assert!(message.contains("nope"), "{message}"),
+                Err(NodeError::Fatal(message)) if !retryable => assert!(message.contains("nope"), "{message}"),
+                other => panic!("{status}: {other:?}"),
+            }
+            server.await.unwrap();
+        }
+    }
+}
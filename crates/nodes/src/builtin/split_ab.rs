@@ -0,0 +1,194 @@
+//! `split_ab` node — send each execution down one of several weighted
+//! branches.
+//!
+//! ```json
+//! {
+//!   "branches": [
+//!     { "name": "control", "weight": 80 },
+//!     { "name": "new_copy", "weight": 20 }
+//!   ],
+//!   "sticky_key": "{{ input.user_id }}",
+//!   "branch_field": "variant"
+//! }
+//! ```
+//!
+//! Label the node's outgoing edges with the branch names (`"branch":
+//! "control"`); only the chosen branch's edges are followed.
+//!
+//! Without `sticky_key` the branch is picked at random.  With it, the
+//! rendered key is hashed (together with `salt`, which defaults to the node
+//! ID) so the same key always lands in the same branch — the same user
+//! sees the same variant on every run.  Changing the weights only moves the
+//! keys near the affected boundaries.
+//!
+//! The input passes through unchanged; with `branch_field` the chosen
+//! branch name is also written into that field of an object input.
+
+use async_trait::async_trait;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::builtin::parse_config;
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, template, traits::ExecutionContext};
+
+/// One outgoing branch and its share of the traffic.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct Branch {
+    pub name: String,
+    pub weight: f64,
+}
+
+/// Configuration for the `split_ab` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SplitAbConfig {
+    pub branches: Vec<Branch>,
+    /// Template rendered against `{ "input": ... }` to key sticky assignment.
+    #[serde(default)]
+    pub sticky_key: Option<String>,
+    /// Mixed into the sticky hash; defaults to the node ID.
+    #[serde(default)]
+    pub salt: Option<String>,
+    /// Input field to record the chosen branch in.
+    #[serde(default)]
+    pub branch_field: Option<String>,
+}
+
+/// The `split_ab` node.
+#[register_node("split_ab")]
+#[derive(Debug, Default)]
+pub struct SplitAbNode;
+
+#[async_trait]
+impl ExecutableNode for SplitAbNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: SplitAbConfig = parse_config("split_ab", ctx)?;
+        if config.branches.is_empty() {
+            return Err(NodeError::Fatal("split_ab: `branches` must not be empty".into()));
+        }
+        if config.branches.iter().any(|b| !(b.weight.is_finite() && b.weight >= 0.0)) {
+            return Err(NodeError::Fatal("split_ab: weights must be non-negative numbers".into()));
+        }
+        let total: f64 = config.branches.iter().map(|b| b.weight).sum();
+        if total <= 0.0 {
+            return Err(NodeError::Fatal("split_ab: at least one weight must be positive".into()));
+        }
+
+        let point = match &config.sticky_key {
+            Some(key) => {
+                let key = template::render(key, &json!({ "input": &input }))
+                    .map_err(|e| NodeError::Fatal(format!("split_ab: sticky_key: {e}")))?;
+                let salt = config.salt.as_deref().unwrap_or(&ctx.node_id);
+                sticky_point(salt, &key)
+            }
+            None => match &ctx.random {
+                Some(random) => random.next_f64(),
+                None => rand::thread_rng().gen::<f64>(),
+            },
+        };
+
+        let branch = choose(&config.branches, point * total).to_owned();
+        ctx.branch(branch.clone());
+
+        let mut output = input;
+        if let (Some(field), Some(object)) = (&config.branch_field, output.as_object_mut()) {
+            object.insert(field.clone(), Value::String(branch));
+        }
+        Ok(output)
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("A/B split", "Send each execution down one of several weighted branches.")
+            .category(NodeCategory::Flow)
+            .config::<SplitAbConfig>()
+            .output("the input, unchanged; the branch is named by the config")
+    }
+}
+
+/// Pick the branch whose cumulative weight range contains `point`
+/// (`0 <= point < total weight`).
+fn choose(branches: &[Branch], point: f64) -> &str {
+    let mut upper = 0.0;
+    for branch in branches {
+        upper += branch.weight;
+        if point < upper {
+            return &branch.name;
+        }
+    }
+    // Only reachable through rounding at the very top of the range.
+    branches
+        .iter()
+        .rev()
+        .find(|b| b.weight > 0.0)
+        .map_or(&branches[0].name, |b| &b.name)
+}
+
+/// Map `salt` + `key` to a stable point in `[0, 1)`.
+///
+/// 64-bit FNV-1a followed by the MurmurHash3 finaliser, which spreads the
+/// high bits of near-identical keys (`user-1`, `user-2`, …).
+fn sticky_point(salt: &str, key: &str) -> f64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in salt.bytes().chain([0]).chain(key.bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^= hash >> 33;
+    // Top 53 bits give a uniformly distributed f64 mantissa.
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::Flow;
+
+    fn branches() -> Vec<Branch> {
+        vec![
+            Branch { name: "a".into(), weight: 1.0 },
+            Branch { name: "off".into(), weight: 0.0 },
+            Branch { name: "b".into(), weight: 3.0 },
+        ]
+    }
+
+    #[test]
+    fn chooses_by_cumulative_weight() {
+        let b = branches();
+        assert_eq!(choose(&b, 0.0), "a");
+        assert_eq!(choose(&b, 0.99), "a");
+        assert_eq!(choose(&b, 1.0), "b");
+        assert_eq!(choose(&b, 4.0), "b");
+    }
+
+    #[test]
+    fn sticky_points_are_stable_and_spread() {
+        assert_eq!(sticky_point("exp", "user-1"), sticky_point("exp", "user-1"));
+        assert_ne!(sticky_point("exp", "user-1"), sticky_point("other", "user-1"));
+
+        let in_lower_half = (0..1000)
+            .filter(|i| sticky_point("exp", &format!("user-{i}")) < 0.5)
+            .count();
+        assert!((400..600).contains(&in_lower_half), "{in_lower_half}");
+    }
+
+    #[tokio::test]
+    async fn records_the_branch_and_signals_it_to_the_engine() {
+        let ctx = ExecutionContext::new(uuid::Uuid::new_v4(), uuid::Uuid::new_v4(), json!({}))
+            .for_node("split", json!({
+                "branches": [{ "name": "a", "weight": 0 }, { "name": "b", "weight": 1 }],
+                "sticky_key": "{{ input.user }}",
+                "branch_field": "variant",
+            }));
+        let out = SplitAbNode.execute(json!({ "user": "u1" }), &ctx).await.unwrap();
+        assert_eq!(out, json!({ "user": "u1", "variant": "b" }));
+        assert_eq!(ctx.take_flow(), Flow::Branch("b".into()));
+    }
+}
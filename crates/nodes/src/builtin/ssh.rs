@@ -0,0 +1,497 @@
+//! SSH plumbing shared by the `ssh_exec` and `sftp` nodes, plus the
+//! `ssh_exec` node itself.
+//!
+//! `ssh_exec` runs a single command on a remote host and returns its
+//! stdout, stderr, and exit code:
+//!
+//! ```json
+//! {
+//!   "host": "build-box.internal",
+//!   "username": "deploy",
+//!   "host_key_fingerprint": "SHA256:uNiVztksCsDhcc0u9e8BujQXVUpKZIDTMczCvj3tD2s",
+//!   "private_key_secret": "DEPLOY_KEY",
+//!   "command": "systemctl restart api",
+//!   "timeout_secs": 60,
+//!   "on_nonzero_exit": "retryable"
+//! }
+//! ```
+//!
+//! Authentication uses either `password_secret` or `private_key_secret`
+//! (optionally with `passphrase_secret`); the values are read from the
+//! workflow's secrets, never from the config itself.  Alternatively
+//! `credential_id` names a shared `ssh_key` credential, or an `http_basic`
+//! one whose password is used.
+//!
+//! The server's host key must match `host_key_fingerprint` — the SHA-256
+//! fingerprint as printed by `ssh-keygen -lf` — before any credentials are
+//! sent.  Connections to hosts without a configured fingerprint are
+//! refused; the error names the fingerprint the host presented.
+
+use std::io::{self, Read};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use base64::Engine as _;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use ssh2::{ErrorCode, HashType, Session};
+use uuid::Uuid;
+
+use crate::builtin::{parse_config, require_credential, require_secret, NonZeroExit};
+use crate::credentials::Credential;
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, traits::ExecutionContext};
+
+/// libssh2 error code for rejected credentials.
+const LIBSSH2_ERROR_AUTHENTICATION_FAILED: i32 = -18;
+
+/// SFTP status codes that will not go away on retry
+/// (no such file, permission denied, no such path, already exists, write protect).
+const PERMANENT_SFTP_CODES: [i32; 5] = [2, 3, 10, 11, 12];
+
+/// How long to wait before polling a quiet channel again.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// ---------------------------------------------------------------------------
+// Shared connection handling
+// ---------------------------------------------------------------------------
+
+/// Connection settings shared by every SSH-based node (flattened into the
+/// node's own config).
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SshConnection {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    pub username: String,
+    /// Expected SHA-256 fingerprint of the server's host key
+    /// (`SHA256:<base64>`, as printed by `ssh-keygen -lf`).
+    #[serde(default)]
+    pub host_key_fingerprint: Option<String>,
+    /// Secret key holding the account password.
+    #[serde(default)]
+    pub password_secret: Option<String>,
+    /// Secret key holding a PEM/OpenSSH private key.
+    #[serde(default)]
+    pub private_key_secret: Option<String>,
+    /// Secret key holding the private key passphrase, if any.
+    #[serde(default)]
+    pub passphrase_secret: Option<String>,
+    /// Shared credential to authenticate with instead of the secrets.
+    #[serde(default)]
+    pub credential_id: Option<Uuid>,
+    /// Connect and per-operation timeout.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_port() -> u16 {
+    22
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+/// Credentials resolved from secrets before the blocking work starts.
+pub(crate) enum Credentials {
+    Password(String),
+    PrivateKey { key: String, passphrase: Option<String> },
+}
+
+impl SshConnection {
+    /// Resolve the configured credential or secret keys into credentials.
+    pub(crate) fn credentials(&self, ctx: &ExecutionContext) -> Result<Credentials, NodeError> {
+        if let Some(id) = self.credential_id {
+            return match require_credential(ctx, id)? {
+                Credential::SshKey { private_key, passphrase } => {
+                    Ok(Credentials::PrivateKey { key: private_key.clone(), passphrase: passphrase.clone() })
+                }
+                Credential::HttpBasic { password, .. } => Ok(Credentials::Password(password.clone())),
+                other => Err(NodeError::Fatal(format!(
+                    "ssh connection cannot authenticate with a {} credential",
+                    other.kind()
+                ))),
+            };
+        }
+        match (&self.password_secret, &self.private_key_secret) {
+            (_, Some(key_secret)) => Ok(Credentials::PrivateKey {
+                key: require_secret(ctx, key_secret)?.to_owned(),
+                passphrase: self
+                    .passphrase_secret
+                    .as_deref()
+                    .map(|s| require_secret(ctx, s).map(str::to_owned))
+                    .transpose()?,
+            }),
+            (Some(password_secret), None) => Ok(Credentials::Password(
+                require_secret(ctx, password_secret)?.to_owned(),
+            )),
+            (None, None) => Err(NodeError::Fatal(
+                "ssh connection requires `password_secret`, `private_key_secret`, or `credential_id`".into(),
+            )),
+        }
+    }
+
+    /// Open a TCP connection, perform the SSH handshake, verify the host
+    /// key, and authenticate.
+    ///
+    /// Blocking — call from `spawn_blocking`.
+    pub(crate) fn open_session(&self, credentials: &Credentials) -> Result<Session, NodeError> {
+        let timeout = Duration::from_secs(self.timeout_secs);
+
+        let addr = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map_err(|e| NodeError::Retryable(format!("cannot resolve {}: {e}", self.host)))?
+            .next()
+            .ok_or_else(|| NodeError::Fatal(format!("no address found for {}", self.host)))?;
+
+        let tcp = TcpStream::connect_timeout(&addr, timeout)
+            .map_err(|e| NodeError::Retryable(format!("cannot connect to {addr}: {e}")))?;
+
+        let mut session = Session::new()
+            .map_err(|e| NodeError::Fatal(format!("cannot create ssh session: {e}")))?;
+        session.set_timeout(timeout.as_millis().try_into().unwrap_or(u32::MAX));
+        session.set_tcp_stream(tcp);
+        session.handshake().map_err(classify)?;
+        verify_host_key(
+            &self.host,
+            self.host_key_fingerprint.as_deref(),
+            session.host_key_hash(HashType::Sha256),
+        )?;
+
+        match credentials {
+            Credentials::Password(password) => {
+                session.userauth_password(&self.username, password).map_err(classify)?
+            }
+            Credentials::PrivateKey { key, passphrase } => session
+                .userauth_pubkey_memory(&self.username, None, key, passphrase.as_deref())
+                .map_err(classify)?,
+        }
+
+        Ok(session)
+    }
+}
+
+/// Format a raw SHA-256 host key hash the way OpenSSH prints it.
+fn fingerprint(hash: &[u8]) -> String {
+    format!("SHA256:{}", base64::engine::general_purpose::STANDARD_NO_PAD.encode(hash))
+}
+
+/// Check the host key the server presented against the configured
+/// fingerprint.  Unknown hosts are refused, as are mismatches.
+fn verify_host_key(host: &str, expected: Option<&str>, presented: Option<&[u8]>) -> Result<(), NodeError> {
+    let presented = presented
+        .map(fingerprint)
+        .ok_or_else(|| NodeError::Fatal(format!("{host} did not present a host key")))?;
+    let Some(expected) = expected else {
+        return Err(NodeError::Fatal(format!(
+            "refusing unknown host {host}: set `host_key_fingerprint` to {presented} if that is its key"
+        )));
+    };
+    let expected = expected.trim().trim_end_matches('=');
+    let expected = expected.strip_prefix("SHA256:").unwrap_or(expected);
+    if presented.strip_prefix("SHA256:") != Some(expected) {
+        return Err(NodeError::Fatal(format!(
+            "host key mismatch for {host}: expected SHA256:{expected}, got {presented}"
+        )));
+    }
+    Ok(())
+}
+
+/// Map libssh2/SFTP errors onto the engine's retry semantics.
+pub(crate) fn classify(err: ssh2::Error) -> NodeError {
+    match err.code() {
+        ErrorCode::Session(LIBSSH2_ERROR_AUTHENTICATION_FAILED) => {
+            NodeError::Fatal(format!("ssh authentication failed: {err}"))
+        }
+        ErrorCode::SFTP(code) if PERMANENT_SFTP_CODES.contains(&code) => {
+            NodeError::Fatal(format!("sftp error: {err}"))
+        }
+        _ => NodeError::Retryable(format!("ssh error: {err}")),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ssh_exec
+// ---------------------------------------------------------------------------
+
+/// Configuration for the `ssh_exec` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SshExecConfig {
+    #[serde(flatten)]
+    pub connection: SshConnection,
+    pub command: String,
+    #[serde(default)]
+    pub on_nonzero_exit: NonZeroExit,
+}
+
+/// Runs a command over SSH.
+///
+/// Output: `{ "stdout": "...", "stderr": "...", "exit_code": 0 }`.
+#[register_node("ssh_exec")]
+#[derive(Debug, Default)]
+pub struct SshExecNode;
+
+#[async_trait]
+impl ExecutableNode for SshExecNode {
+    async fn execute(&self, _input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: SshExecConfig = parse_config("ssh_exec", ctx)?;
+        let credentials = config.connection.credentials(ctx)?;
+        let timeout = Duration::from_secs(config.connection.timeout_secs);
+        let deadline = Instant::now() + timeout;
+
+        let task = tokio::task::spawn_blocking({
+            let config = config.clone();
+            move || run_command(&config, &credentials, deadline)
+        });
+
+        let (stdout, stderr, exit_code) = tokio::time::timeout(timeout, task)
+            .await
+            .map_err(|_| {
+                NodeError::Retryable(format!(
+                    "ssh command timed out after {}s",
+                    config.connection.timeout_secs
+                ))
+            })?
+            .map_err(|e| NodeError::Fatal(format!("ssh task panicked: {e}")))??;
+
+        if exit_code != 0 {
+            let message = format!(
+                "remote command exited with status {exit_code}: {}",
+                stderr.trim()
+            );
+            return Err(match config.on_nonzero_exit {
+                NonZeroExit::Fatal => NodeError::Fatal(message),
+                NonZeroExit::Retryable => NodeError::Retryable(message),
+            });
+        }
+
+        Ok(json!({
+            "stdout": stdout,
+            "stderr": stderr,
+            "exit_code": exit_code,
+        }))
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("SSH command", "Run a command on a remote host over SSH.")
+            .category(NodeCategory::System)
+            .config::<SshExecConfig>()
+            .optional_credential("password_secret", "Account password")
+            .optional_credential("private_key_secret", "PEM private key")
+            .optional_credential("passphrase_secret", "Passphrase of the private key")
+            .output("{ stdout, stderr, exit_code }")
+    }
+}
+
+/// Run the command, giving up on it at `deadline`.
+fn run_command(
+    config: &SshExecConfig,
+    credentials: &Credentials,
+    deadline: Instant,
+) -> Result<(String, String, i32), NodeError> {
+    let session = config.connection.open_session(credentials)?;
+    let mut channel = session.channel_session().map_err(classify)?;
+    channel.exec(&config.command).map_err(classify)?;
+
+    // Read both streams as data arrives: a command that fills the stderr
+    // window while we wait for stdout EOF would otherwise never finish.
+    session.set_blocking(false);
+    let read = read_interleaved(&mut channel.stream(0), &mut channel.stderr(), || channel.eof(), deadline);
+    let (stdout, stderr) = match read {
+        Ok(output) => output,
+        Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+            // Nobody waits for the command any more: end it rather than
+            // keep this thread and the session around.
+            let _ = channel.close();
+            let _ = session.disconnect(None, "command timed out", None);
+            return Err(NodeError::Retryable(format!(
+                "ssh command timed out after {}s",
+                config.connection.timeout_secs
+            )));
+        }
+        Err(e) => return Err(NodeError::Retryable(format!("failed reading command output: {e}"))),
+    };
+    session.set_blocking(true);
+
+    channel.wait_close().map_err(classify)?;
+    let exit_code = channel.exit_status().map_err(classify)?;
+
+    Ok((
+        String::from_utf8_lossy(&stdout).into_owned(),
+        String::from_utf8_lossy(&stderr).into_owned(),
+        exit_code,
+    ))
+}
+
+/// Drain two non-blocking streams until `eof` reports the remote side is
+/// done and neither has anything left.  Fails with
+/// [`io::ErrorKind::TimedOut`] once `deadline` passes.
+fn read_interleaved(
+    stdout: &mut impl Read,
+    stderr: &mut impl Read,
+    eof: impl Fn() -> bool,
+    deadline: Instant,
+) -> io::Result<(Vec<u8>, Vec<u8>)> {
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let mut buf = [0u8; 8192];
+    loop {
+        let mut progressed = false;
+        for (stream, sink) in [(&mut *stdout as &mut dyn Read, &mut out), (stderr, &mut err)] {
+            match stream.read(&mut buf) {
+                Ok(0) => {}
+                Ok(n) => {
+                    sink.extend_from_slice(&buf[..n]);
+                    progressed = true;
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if !progressed {
+            if eof() {
+                return Ok((out, err));
+            }
+            if Instant::now() >= deadline {
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    const HASH: [u8; 32] = [7; 32];
+
+    #[test]
+    fn host_keys_matching_the_fingerprint_are_accepted() {
+        let printed = fingerprint(&HASH);
+        assert!(printed.starts_with("SHA256:") && !printed.ends_with('='));
+        assert!(verify_host_key("box", Some(&printed), Some(&HASH)).is_ok());
+        // With or without the prefix and base64 padding.
+        let bare = printed.trim_start_matches("SHA256:");
+        assert!(verify_host_key("box", Some(bare), Some(&HASH)).is_ok());
+        assert!(verify_host_key("box", Some(&format!(" {printed}= ")), Some(&HASH)).is_ok());
+    }
+
+    #[test]
+    fn unknown_and_mismatched_host_keys_are_refused() {
+        let Err(NodeError::Fatal(message)) = verify_host_key("box", None, Some(&HASH)) else {
+            panic!("unknown hosts are refused");
+        };
+        assert!(message.contains(&fingerprint(&HASH)), "{message}");
+
+        let other = fingerprint(&[8; 32]);
+        let Err(NodeError::Fatal(message)) = verify_host_key("box", Some(&other), Some(&HASH)) else {
+            panic!("mismatched keys are refused");
+        };
+        assert!(message.contains("mismatch"), "{message}");
+
+        assert!(matches!(verify_host_key("box", Some(&other), None), Err(NodeError::Fatal(_))));
+    }
+
+    #[test]
+    fn the_fingerprint_is_part_of_the_connection_config() {
+        let config: SshExecConfig = serde_json::from_value(json!({
+            "host": "box",
+            "username": "deploy",
+            "host_key_fingerprint": "SHA256:abc",
+            "command": "true"
+        }))
+        .unwrap();
+        assert_eq!(config.connection.host_key_fingerprint.as_deref(), Some("SHA256:abc"));
+    }
+
+    fn later() -> Instant {
+        Instant::now() + Duration::from_secs(60)
+    }
+
+    /// A non-blocking stream fed in chunks; reports `WouldBlock` until
+    /// `ready` allows it to produce the next chunk.
+    struct Chunks {
+        chunks: Vec<&'static [u8]>,
+        ready: Box<dyn Fn() -> bool>,
+    }
+
+    impl Read for Chunks {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.chunks.is_empty() {
+                return Ok(0);
+            }
+            if !(self.ready)() {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            let chunk = self.chunks.remove(0);
+            buf[..chunk.len()].copy_from_slice(chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    #[test]
+    fn stderr_is_drained_while_stdout_is_still_open() {
+        // The remote only writes stdout once all of stderr has been read, as
+        // a command blocked on a full stderr window would.
+        let stderr_left = Rc::new(Cell::new(3));
+        let mut stderr = Chunks {
+            chunks: vec![b"warn 1\n", b"warn 2\n", b"warn 3\n"],
+            ready: Box::new({
+                let left = stderr_left.clone();
+                move || {
+                    left.set(left.get() - 1);
+                    true
+                }
+            }),
+        };
+        let mut stdout = Chunks {
+            chunks: vec![b"done\n"],
+            ready: Box::new({
+                let left = stderr_left.clone();
+                move || left.get() == 0
+            }),
+        };
+
+        let (out, err) = read_interleaved(&mut stdout, &mut stderr, || true, later()).unwrap();
+        assert_eq!(out, b"done\n");
+        assert_eq!(err, b"warn 1\nwarn 2\nwarn 3\n");
+    }
+
+    #[test]
+    fn reading_waits_for_eof_and_keeps_invalid_utf8() {
+        let polls = Cell::new(0);
+        let mut stdout = Chunks { chunks: vec![b"caf\xe9"], ready: Box::new(|| true) };
+        let (out, err) = read_interleaved(
+            &mut stdout,
+            &mut io::empty(),
+            || {
+                polls.set(polls.get() + 1);
+                polls.get() > 2
+            },
+            later(),
+        )
+        .unwrap();
+        assert_eq!(polls.get(), 3);
+        assert!(err.is_empty());
+        assert_eq!(String::from_utf8_lossy(&out), "caf\u{fffd}");
+    }
+
+    #[test]
+    fn a_stalled_command_times_out_at_the_deadline() {
+        let mut stalled = Chunks { chunks: vec![b"never"], ready: Box::new(|| false) };
+        let started = Instant::now();
+        let deadline = started + Duration::from_millis(50);
+        let err = read_interleaved(&mut stalled, &mut io::empty(), || false, deadline).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert!(started.elapsed() < Duration::from_secs(5), "gave up soon after the deadline");
+    }
+}
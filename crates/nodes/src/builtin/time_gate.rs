@@ -0,0 +1,189 @@
+//! `time_gate` node — only let the execution continue inside a time window.
+//!
+//! ```json
+//! {
+//!   "timezone": "Europe/Berlin",
+//!   "days": ["mon", "tue", "wed", "thu", "fri"],
+//!   "start": "09:00",
+//!   "end": "18:00",
+//!   "outside": "defer"
+//! }
+//! ```
+//!
+//! Inside the window the input passes through unchanged.  Outside it the
+//! node either defers the execution until the window next opens (`defer`,
+//! the default — the engine suspends and resumes it durably) or halts the
+//! flow (`skip`), emitting `{ "open": false, "next_open": "<RFC 3339>" }`.
+//!
+//! A window whose `end` is not after its `start` spans midnight and belongs
+//! to the day it starts on; `start` and `end` both default to midnight, so
+//! omitting them opens the gate for whole days.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::builtin::parse_config;
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, traits::ExecutionContext};
+
+/// What to do when an execution arrives outside the window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OutsideWindow {
+    /// Suspend the execution until the window opens.
+    #[default]
+    Defer,
+    /// Stop here; downstream nodes do not run.
+    Skip,
+}
+
+/// Configuration for the `time_gate` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct TimeGateConfig {
+    /// IANA time zone the window is expressed in.
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// Days on which the window opens (`"mon"`, `"tuesday"`, …).
+    #[serde(default = "all_days")]
+    pub days: Vec<Weekday>,
+    #[serde(default)]
+    pub start: Option<NaiveTime>,
+    #[serde(default)]
+    pub end: Option<NaiveTime>,
+    #[serde(default)]
+    pub outside: OutsideWindow,
+}
+
+fn default_timezone() -> String {
+    "UTC".into()
+}
+
+fn all_days() -> Vec<Weekday> {
+    vec![
+        Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu,
+        Weekday::Fri, Weekday::Sat, Weekday::Sun,
+    ]
+}
+
+/// The `time_gate` node.
+#[register_node("time_gate")]
+#[derive(Debug, Default)]
+pub struct TimeGateNode;
+
+#[async_trait]
+impl ExecutableNode for TimeGateNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: TimeGateConfig = parse_config("time_gate", ctx)?;
+        let tz: Tz = config.timezone.parse().map_err(|_| {
+            NodeError::Fatal(format!("time_gate: unknown time zone '{}'", config.timezone))
+        })?;
+        if config.days.is_empty() {
+            return Err(NodeError::Fatal("time_gate: `days` must not be empty".into()));
+        }
+
+        let now = ctx.now();
+        if is_open(&config, now.with_timezone(&tz)) {
+            return Ok(input);
+        }
+
+        let next = next_open(&config, tz, now);
+        match config.outside {
+            OutsideWindow::Defer => ctx.defer_until(next),
+            OutsideWindow::Skip => ctx.halt(),
+        }
+        Ok(json!({ "open": false, "next_open": next.to_rfc3339() }))
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("Time gate", "Only let the execution continue inside a time window.")
+            .category(NodeCategory::Flow)
+            .config::<TimeGateConfig>()
+            .output("the input, unchanged | { open: false, next_open }")
+    }
+}
+
+fn window(config: &TimeGateConfig) -> (NaiveTime, NaiveTime) {
+    (
+        config.start.unwrap_or(NaiveTime::MIN),
+        config.end.unwrap_or(NaiveTime::MIN),
+    )
+}
+
+/// Whether `local` falls inside the configured window.
+fn is_open(config: &TimeGateConfig, local: DateTime<Tz>) -> bool {
+    let (start, end) = window(config);
+    let time = local.time();
+    let today = config.days.contains(&local.weekday());
+
+    if start < end {
+        today && start <= time && time < end
+    } else {
+        // Overnight window: the tail end belongs to the previous day.
+        let yesterday = config.days.contains(&local.weekday().pred());
+        (today && time >= start) || (yesterday && time < end)
+    }
+}
+
+/// The next time after `now` at which the window opens.
+fn next_open(config: &TimeGateConfig, tz: Tz, now: DateTime<Utc>) -> DateTime<Utc> {
+    let (start, _) = window(config);
+    let today = now.with_timezone(&tz).date_naive();
+
+    (0..=7)
+        .map(|offset| today + Duration::days(offset))
+        .filter(|date| config.days.contains(&date.weekday()))
+        .filter_map(|date| {
+            let naive = date.and_time(start);
+            // A start time inside a DST gap opens an hour later.
+            tz.from_local_datetime(&naive)
+                .earliest()
+                .or_else(|| tz.from_local_datetime(&(naive + Duration::hours(1))).earliest())
+        })
+        .map(|t| t.with_timezone(&Utc))
+        .find(|t| *t > now)
+        .unwrap_or(now + Duration::days(7))
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(value: Value) -> TimeGateConfig {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn berlin(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Tz> {
+        chrono_tz::Europe::Berlin.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn office_hours_window() {
+        let cfg = config(json!({ "days": ["mon", "tue", "wed", "thu", "fri"], "start": "09:00", "end": "17:30" }));
+        // 2024-03-04 is a Monday.
+        assert!(is_open(&cfg, berlin(2024, 3, 4, 9, 0)));
+        assert!(!is_open(&cfg, berlin(2024, 3, 4, 17, 30)));
+        assert!(!is_open(&cfg, berlin(2024, 3, 9, 12, 0)));
+    }
+
+    #[test]
+    fn overnight_window_belongs_to_its_start_day() {
+        let cfg = config(json!({ "days": ["fri"], "start": "22:00", "end": "06:00" }));
+        assert!(is_open(&cfg, berlin(2024, 3, 8, 23, 0)));
+        assert!(is_open(&cfg, berlin(2024, 3, 9, 5, 59)));
+        assert!(!is_open(&cfg, berlin(2024, 3, 8, 5, 0)));
+    }
+
+    #[test]
+    fn next_open_skips_to_the_next_allowed_day() {
+        let cfg = config(json!({ "timezone": "Europe/Berlin", "days": ["mon"], "start": "09:00", "end": "17:00" }));
+        let saturday = berlin(2024, 3, 9, 12, 0).with_timezone(&Utc);
+        let next = next_open(&cfg, chrono_tz::Europe::Berlin, saturday);
+        assert_eq!(next, berlin(2024, 3, 11, 9, 0).with_timezone(&Utc));
+    }
+}
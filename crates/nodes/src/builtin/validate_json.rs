@@ -0,0 +1,234 @@
+//! `validate_json` node — assert that data matches a JSON Schema.
+//!
+//! ```json
+//! {
+//!   "schema": {
+//!     "type": "object",
+//!     "required": ["email", "items"],
+//!     "properties": {
+//!       "email": { "type": "string", "minLength": 3 },
+//!       "items": { "type": "array", "minItems": 1 }
+//!     }
+//!   },
+//!   "field": "order",
+//!   "on_invalid": "branch"
+//! }
+//! ```
+//!
+//! The data is `input[field]` (a dotted path), or the whole input when
+//! `field` is unset.  Valid data passes the input through unchanged.
+//!
+//! With `on_invalid: "fail"` (the default) a violation fails the node with
+//! [`NodeError::Fatal`], listing every violation with its JSON Pointer path,
+//! e.g. `/items/0/qty: -1 is less than the minimum of 0`.
+//!
+//! With `on_invalid: "branch"` the node always succeeds and routes the flow
+//! instead: down edges labelled `valid` with the input, or down edges
+//! labelled `invalid` with
+//! `{ "valid": false, "errors": [{ "path": "/items/0/qty", "schema_path": "/properties/...", "message": "..." }], "input": ... }`.
+//! Each violation is also logged as a warning.
+//!
+//! At most `max_errors` (default 20) violations are reported.
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::builtin::parse_config;
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, template, traits::{ExecutionContext, LogLevel}};
+
+/// What the node does with data that does not match the schema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OnInvalid {
+    /// Fail the execution.
+    #[default]
+    Fail,
+    /// Route to the `invalid` branch with the violations.
+    Branch,
+}
+
+/// Configuration for the `validate_json` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ValidateJsonConfig {
+    pub schema: Value,
+    /// Dotted input path of the data to validate; the whole input if unset.
+    #[serde(default)]
+    pub field: Option<String>,
+    #[serde(default)]
+    pub on_invalid: OnInvalid,
+    #[serde(default = "default_max_errors")]
+    pub max_errors: usize,
+}
+
+fn default_max_errors() -> usize {
+    20
+}
+
+/// One schema violation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Violation {
+    path: String,
+    schema_path: String,
+    message: String,
+}
+
+/// The `validate_json` node.
+#[register_node("validate_json")]
+#[derive(Debug, Default)]
+pub struct ValidateJsonNode;
+
+#[async_trait]
+impl ExecutableNode for ValidateJsonNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: ValidateJsonConfig = parse_config("validate_json", ctx)?;
+        let data = match &config.field {
+            Some(path) => template::lookup(&input, path).ok_or_else(|| {
+                NodeError::Fatal(format!("validate_json: input has no field '{path}'"))
+            })?,
+            None => &input,
+        };
+
+        let violations = validate(&config.schema, data, config.max_errors)?;
+        if violations.is_empty() {
+            if config.on_invalid == OnInvalid::Branch {
+                ctx.branch("valid");
+            }
+            return Ok(input);
+        }
+
+        match config.on_invalid {
+            OnInvalid::Fail => {
+                let details: Vec<String> = violations
+                    .iter()
+                    .map(|v| format!("{}: {}", display_path(&v.path), v.message))
+                    .collect();
+                Err(NodeError::Fatal(format!(
+                    "validate_json: input does not match the schema: {}",
+                    details.join("; ")
+                )))
+            }
+            OnInvalid::Branch => {
+                ctx.branch("invalid");
+                for v in &violations {
+                    ctx.log(
+                        LogLevel::Warn,
+                        format!("{}: {}", display_path(&v.path), v.message),
+                        json!({ "path": v.path, "schema_path": v.schema_path }),
+                    );
+                }
+                let errors: Vec<Value> = violations
+                    .into_iter()
+                    .map(|v| json!({ "path": v.path, "schema_path": v.schema_path, "message": v.message }))
+                    .collect();
+                Ok(json!({ "valid": false, "errors": errors, "input": input }))
+            }
+        }
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("Validate JSON", "Assert that data matches a JSON Schema.")
+            .category(NodeCategory::Data)
+            .config::<ValidateJsonConfig>()
+            .output("the input, unchanged | { valid: false, errors, input }")
+            .branches(["valid", "invalid"])
+    }
+}
+
+/// Validate `data` against `schema`, returning up to `max_errors` violations.
+fn validate(schema: &Value, data: &Value, max_errors: usize) -> Result<Vec<Violation>, NodeError> {
+    let validator = jsonschema::validator_for(schema)
+        .map_err(|e| NodeError::Fatal(format!("validate_json: invalid schema: {e}")))?;
+    Ok(validator
+        .iter_errors(data)
+        .take(max_errors)
+        .map(|e| Violation {
+            path: e.instance_path.to_string(),
+            schema_path: e.schema_path.to_string(),
+            message: e.to_string(),
+        })
+        .collect())
+}
+
+/// The document root has an empty JSON Pointer; show it as `/`.
+fn display_path(path: &str) -> &str {
+    if path.is_empty() { "/" } else { path }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::Flow;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["email", "items"],
+            "properties": {
+                "email": { "type": "string" },
+                "items": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "qty": { "type": "integer", "minimum": 1 } }
+                    }
+                }
+            }
+        })
+    }
+
+    fn ctx(config: Value) -> ExecutionContext {
+        ExecutionContext::new(uuid::Uuid::nil(), uuid::Uuid::nil(), json!({})).for_node("check", config)
+    }
+
+    #[test]
+    fn reports_every_violation_with_its_path() {
+        let data = json!({ "items": [{ "qty": 2 }, { "qty": 0 }] });
+        let mut violations = validate(&schema(), &data, 20).unwrap();
+        violations.sort_by(|a, b| a.path.cmp(&b.path));
+        let paths: Vec<&str> = violations.iter().map(|v| v.path.as_str()).collect();
+        assert_eq!(paths, ["", "/items/1/qty"]);
+        assert!(violations[0].message.contains("email"));
+        assert_eq!(violations[1].schema_path, "/properties/items/items/properties/qty/minimum");
+
+        assert_eq!(validate(&schema(), &data, 1).unwrap().len(), 1);
+        assert!(validate(&json!({ "type": 12 }), &data, 20).is_err());
+    }
+
+    #[tokio::test]
+    async fn fails_fatally_by_default() {
+        let ctx = ctx(json!({ "schema": schema(), "field": "order" }));
+        let valid = json!({ "order": { "email": "a@b.c", "items": [] } });
+        assert_eq!(ValidateJsonNode.execute(valid.clone(), &ctx).await.unwrap(), valid);
+
+        let invalid = json!({ "order": { "email": 7, "items": [] } });
+        match ValidateJsonNode.execute(invalid, &ctx).await {
+            Err(NodeError::Fatal(msg)) => assert!(msg.contains("/email: 7 is not of type \"string\""), "{msg}"),
+            other => panic!("expected a fatal error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn routes_to_branches_when_configured() {
+        let ctx = ctx(json!({ "schema": schema(), "on_invalid": "branch" }));
+
+        ValidateJsonNode.execute(json!({ "email": "a@b.c", "items": [] }), &ctx).await.unwrap();
+        assert_eq!(ctx.take_flow(), Flow::Branch("valid".into()));
+
+        let out = ValidateJsonNode.execute(json!({ "email": "a@b.c" }), &ctx).await.unwrap();
+        assert_eq!(ctx.take_flow(), Flow::Branch("invalid".into()));
+        assert_eq!(out["valid"], false);
+        assert_eq!(out["errors"][0]["path"], "");
+        assert_eq!(out["input"], json!({ "email": "a@b.c" }));
+        let logs = ctx.take_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].level, LogLevel::Warn);
+        assert!(logs[0].message.contains("items"), "{}", logs[0].message);
+        assert_eq!(logs[0].fields["path"], "");
+        assert!(ctx.take_logs().is_empty());
+    }
+}
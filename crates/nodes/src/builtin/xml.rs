@@ -0,0 +1,385 @@
+//! `xml` node — convert XML documents to JSON and back.
+//!
+//! `parse` mode reads XML text from `input[field]` and emits the document
+//! as JSON, keyed by the root element's name:
+//!
+//! ```json
+//! { "mode": "parse", "attribute_prefix": "@", "always_array": ["item"] }
+//! ```
+//!
+//! `<feed lang="en"><item id="1">A</item><item id="2">B</item></feed>` becomes
+//! `{ "feed": { "@lang": "en", "item": [{ "@id": "1", "#text": "A" }, { "@id": "2", "#text": "B" }] } }`.
+//!
+//! * Attributes become keys prefixed with `attribute_prefix` (or are dropped
+//!   with `attributes: false`).
+//! * Text of an element without attributes or children becomes a plain
+//!   string; otherwise it is stored under `text_key`.
+//! * Repeated child elements become arrays; names listed in `always_array`
+//!   are arrays even when they occur once, so downstream nodes can iterate
+//!   them reliably.
+//!
+//! `serialize` mode is the inverse: `input[field]` (an object) is written as
+//! XML using the same conventions, and the node outputs `{ "xml": "..." }`.
+//! An object with a single key names the root element; anything else is
+//! wrapped in a `root` element.
+
+use async_trait::async_trait;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::builtin::parse_config;
+use crate::{ExecutableNode, NodeCategory, NodeDescriptor, NodeError, register_node, traits::ExecutionContext};
+
+/// Conversion direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum XmlMode {
+    Parse,
+    Serialize,
+}
+
+/// Configuration for the `xml` node.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct XmlConfig {
+    pub mode: XmlMode,
+    /// Input field holding the XML text (parse) or the object (serialize).
+    #[serde(default = "default_field")]
+    pub field: String,
+    /// Parse: keep attributes (as prefixed keys).
+    #[serde(default = "default_true")]
+    pub attributes: bool,
+    #[serde(default = "default_attribute_prefix")]
+    pub attribute_prefix: String,
+    /// Key holding an element's text when it also has attributes/children.
+    #[serde(default = "default_text_key")]
+    pub text_key: String,
+    /// Parse: element names that are always emitted as arrays.
+    #[serde(default)]
+    pub always_array: Vec<String>,
+    /// Serialize: root element name when the object has several keys.
+    #[serde(default = "default_root")]
+    pub root: String,
+    /// Serialize: emit an `<?xml ...?>` declaration.
+    #[serde(default = "default_true")]
+    pub declaration: bool,
+    /// Serialize: indent nested elements.
+    #[serde(default)]
+    pub pretty: bool,
+}
+
+fn default_field() -> String {
+    "data".into()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_attribute_prefix() -> String {
+    "@".into()
+}
+
+fn default_text_key() -> String {
+    "#text".into()
+}
+
+fn default_root() -> String {
+    "root".into()
+}
+
+/// The `xml` node.
+#[register_node("xml")]
+#[derive(Debug, Default)]
+pub struct XmlNode;
+
+#[async_trait]
+impl ExecutableNode for XmlNode {
+    async fn execute(&self, input: Value, ctx: &ExecutionContext) -> Result<Value, NodeError> {
+        let config: XmlConfig = parse_config("xml", ctx)?;
+        let field = input.get(&config.field).ok_or_else(|| {
+            NodeError::Fatal(format!("xml: input has no field '{}'", config.field))
+        })?;
+
+        match config.mode {
+            XmlMode::Parse => {
+                let text = field.as_str().ok_or_else(|| {
+                    NodeError::Fatal(format!("xml: input field '{}' must be a string", config.field))
+                })?;
+                parse(text, &config)
+            }
+            XmlMode::Serialize => {
+                let xml = serialize(field, &config)?;
+                Ok(json!({ "xml": xml }))
+            }
+        }
+    }
+
+    fn is_blocking(&self) -> bool {
+        true
+    }
+
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::new("XML", "Convert XML documents to JSON and back.")
+            .category(NodeCategory::Data)
+            .config::<XmlConfig>()
+            .input("XML text (parse) or an object (serialize) in input[field]")
+            .output("{ <root>: ... } | { xml }")
+    }
+}
+
+// ---------------------------------------------------------------------------
+// XML → JSON
+// ---------------------------------------------------------------------------
+
+/// An element whose end tag has not been seen yet.
+struct OpenElement {
+    name: String,
+    fields: Map<String, Value>,
+    text: String,
+}
+
+impl OpenElement {
+    fn start(tag: &BytesStart<'_>, config: &XmlConfig) -> Result<Self, NodeError> {
+        let name = String::from_utf8_lossy(tag.name().as_ref()).into_owned();
+        let mut fields = Map::new();
+        if config.attributes {
+            for attr in tag.attributes() {
+                let attr = attr.map_err(|e| xml_error(e.into()))?;
+                let key = String::from_utf8_lossy(attr.key.as_ref());
+                let value = attr.unescape_value().map_err(xml_error)?;
+                fields.insert(
+                    format!("{}{key}", config.attribute_prefix),
+                    Value::String(value.into_owned()),
+                );
+            }
+        }
+        Ok(Self { name, fields, text: String::new() })
+    }
+
+    fn finish(self, config: &XmlConfig) -> (String, Value) {
+        let text = self.text.trim();
+        let value = match (self.fields.is_empty(), text.is_empty()) {
+            (true, true) => Value::Null,
+            (true, false) => Value::String(text.to_owned()),
+            (false, _) => {
+                let mut fields = self.fields;
+                if !text.is_empty() {
+                    fields.insert(config.text_key.clone(), Value::String(text.to_owned()));
+                }
+                Value::Object(fields)
+            }
+        };
+        (self.name, value)
+    }
+}
+
+/// Attach a finished child to its parent's fields.
+fn attach(fields: &mut Map<String, Value>, name: String, value: Value, config: &XmlConfig) {
+    match fields.get_mut(&name) {
+        Some(Value::Array(items)) => items.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None if config.always_array.contains(&name) => {
+            fields.insert(name, Value::Array(vec![value]));
+        }
+        None => {
+            fields.insert(name, value);
+        }
+    }
+}
+
+fn parse(text: &str, config: &XmlConfig) -> Result<Value, NodeError> {
+    let mut reader = Reader::from_str(text);
+    let mut stack: Vec<OpenElement> = Vec::new();
+    let mut document = Map::new();
+
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(tag) => stack.push(OpenElement::start(&tag, config)?),
+            Event::Empty(tag) => {
+                let (name, value) = OpenElement::start(&tag, config)?.finish(config);
+                match stack.last_mut() {
+                    Some(parent) => attach(&mut parent.fields, name, value, config),
+                    None => attach(&mut document, name, value, config),
+                }
+            }
+            Event::End(_) => {
+                let element = stack
+                    .pop()
+                    .ok_or_else(|| NodeError::Fatal("xml: unbalanced end tag".into()))?;
+                let (name, value) = element.finish(config);
+                match stack.last_mut() {
+                    Some(parent) => attach(&mut parent.fields, name, value, config),
+                    None => attach(&mut document, name, value, config),
+                }
+            }
+            Event::Text(t) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&t.unescape().map_err(xml_error)?);
+                }
+            }
+            Event::CData(c) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&String::from_utf8_lossy(&c));
+                }
+            }
+            Event::Eof => break,
+            // Declarations, comments, processing instructions, doctypes.
+            _ => {}
+        }
+    }
+
+    if !stack.is_empty() {
+        return Err(NodeError::Fatal("xml: document ended inside an element".into()));
+    }
+    Ok(Value::Object(document))
+}
+
+// ---------------------------------------------------------------------------
+// JSON → XML
+// ---------------------------------------------------------------------------
+
+fn serialize(value: &Value, config: &XmlConfig) -> Result<String, NodeError> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| NodeError::Fatal("xml: serialize expects an object".into()))?;
+
+    let mut writer = if config.pretty {
+        Writer::new_with_indent(Vec::new(), b' ', 2)
+    } else {
+        Writer::new(Vec::new())
+    };
+    if config.declaration {
+        writer
+            .write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))
+            .map_err(write_error)?;
+    }
+
+    match object.iter().next() {
+        Some((name, root)) if object.len() == 1 => write_element(&mut writer, name, root, config)?,
+        _ => write_element(&mut writer, &config.root, value, config)?,
+    }
+
+    String::from_utf8(writer.into_inner())
+        .map_err(|e| NodeError::Fatal(format!("xml: output is not UTF-8: {e}")))
+}
+
+fn write_element(
+    writer: &mut Writer<Vec<u8>>,
+    name: &str,
+    value: &Value,
+    config: &XmlConfig,
+) -> Result<(), NodeError> {
+    if let Value::Array(items) = value {
+        for item in items {
+            write_element(writer, name, item, config)?;
+        }
+        return Ok(());
+    }
+
+    let mut start = BytesStart::new(name);
+    let mut text = None;
+    let mut children = Vec::new();
+
+    match value {
+        Value::Object(fields) => {
+            for (key, child) in fields {
+                if let Some(attr) = key.strip_prefix(config.attribute_prefix.as_str()) {
+                    start.push_attribute((attr, scalar_text(child).as_str()));
+                } else if *key == config.text_key {
+                    text = Some(scalar_text(child));
+                } else {
+                    children.push((key, child));
+                }
+            }
+        }
+        Value::Null => {}
+        scalar => text = Some(scalar_text(scalar)),
+    }
+
+    if text.is_none() && children.is_empty() {
+        return writer.write_event(Event::Empty(start)).map_err(write_error);
+    }
+
+    writer.write_event(Event::Start(start)).map_err(write_error)?;
+    if let Some(text) = text {
+        writer.write_event(Event::Text(BytesText::new(&text))).map_err(write_error)?;
+    }
+    for (key, child) in children {
+        write_element(writer, key, child, config)?;
+    }
+    writer.write_event(Event::End(BytesEnd::new(name))).map_err(write_error)
+}
+
+/// Text of a JSON value used as an attribute value or element text.
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn xml_error(err: quick_xml::Error) -> NodeError {
+    NodeError::Fatal(format!("xml: {err}"))
+}
+
+fn write_error(err: impl std::fmt::Display) -> NodeError {
+    NodeError::Fatal(format!("xml: cannot write output: {err}"))
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(mode: &str) -> XmlConfig {
+        serde_json::from_value(json!({ "mode": mode })).unwrap()
+    }
+
+    #[test]
+    fn parses_attributes_text_and_repeated_children() {
+        let out = parse(
+            r#"<?xml version="1.0"?><feed lang="en"><title>News &amp; more</title><item id="1">A</item><item id="2">B</item></feed>"#,
+            &config("parse"),
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            json!({ "feed": {
+                "@lang": "en",
+                "title": "News & more",
+                "item": [{ "@id": "1", "#text": "A" }, { "@id": "2", "#text": "B" }],
+            }})
+        );
+    }
+
+    #[test]
+    fn always_array_wraps_single_elements() {
+        let mut cfg = config("parse");
+        cfg.always_array = vec!["item".into()];
+        cfg.attributes = false;
+        let out = parse(r#"<list><item id="1">only</item><empty/></list>"#, &cfg).unwrap();
+        assert_eq!(out, json!({ "list": { "item": ["only"], "empty": null } }));
+    }
+
+    #[test]
+    fn serializes_back_to_xml() {
+        let mut cfg = config("serialize");
+        cfg.declaration = false;
+        let doc = json!({ "order": { "@id": "7", "line": [{ "sku": "a<b" }, { "sku": "c" }], "note": null } });
+        let xml = serialize(&doc, &cfg).unwrap();
+        assert_eq!(
+            xml,
+            r#"<order id="7"><line><sku>a&lt;b</sku></line><line><sku>c</sku></line><note/></order>"#
+        );
+        assert_eq!(parse(&xml, &config("parse")).unwrap()["order"]["line"][0]["sku"], "a<b");
+    }
+}
@@ -0,0 +1,115 @@
+//! Shared credentials: login details kept once per project and used by any
+//! of its workflows, where secrets belong to a single workflow.
+//!
+//! A node names a credential by id in its config:
+//!
+//! ```json
+//! { "base_url": "https://acme.atlassian.net", "credential_id": "8b0c…", "operation": "add_comment", … }
+//! ```
+//!
+//! Before a workflow runs, the engine decrypts the credentials its nodes
+//! name into [`ExecutionContext::credentials`](crate::traits::ExecutionContext);
+//! credentials of other projects are never loaded, so naming one fails like
+//! naming one that does not exist.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Config field naming the credential a node uses.
+pub const CREDENTIAL_ID_FIELD: &str = "credential_id";
+
+/// A credential's fields, by type.
+///
+/// Serialized as `{ "type": "api_token", "data": { "token": "…" } }`.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum Credential {
+    HttpBasic {
+        username: String,
+        password: String,
+    },
+    #[serde(rename = "oauth2")]
+    OAuth2 {
+        access_token: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        refresh_token: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token_url: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client_secret: Option<String>,
+    },
+    ApiToken {
+        token: String,
+    },
+    SshKey {
+        private_key: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        passphrase: Option<String>,
+    },
+}
+
+impl Credential {
+    /// The credential's type, as named in `type`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::HttpBasic { .. } => "http_basic",
+            Self::OAuth2 { .. } => "oauth2",
+            Self::ApiToken { .. } => "api_token",
+            Self::SshKey { .. } => "ssh_key",
+        }
+    }
+
+    /// The token to send as `Authorization: Bearer`, for the types that
+    /// have one.
+    pub fn bearer_token(&self) -> Option<&str> {
+        match self {
+            Self::OAuth2 { access_token, .. } => Some(access_token),
+            Self::ApiToken { token } => Some(token),
+            Self::HttpBasic { .. } | Self::SshKey { .. } => None,
+        }
+    }
+}
+
+/// Only the type: the fields are secret.
+impl fmt::Debug for Credential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Credential({})", self.kind())
+    }
+}
+
+/// The credential a node's `config` names, if it names one.
+pub fn credential_id(config: &Value) -> Option<Uuid> {
+    config.get(CREDENTIAL_ID_FIELD)?.as_str()?.parse().ok()
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn credentials_are_tagged_by_type_and_debug_without_fields() {
+        let credential: Credential =
+            serde_json::from_value(json!({ "type": "oauth2", "data": { "access_token": "at" } })).unwrap();
+        assert_eq!(credential.kind(), "oauth2");
+        assert_eq!(credential.bearer_token(), Some("at"));
+        assert_eq!(format!("{credential:?}"), "Credential(oauth2)");
+
+        let basic = Credential::HttpBasic { username: "u".into(), password: "p".into() };
+        let value = serde_json::to_value(&basic).unwrap();
+        assert_eq!(value, json!({ "type": "http_basic", "data": { "username": "u", "password": "p" } }));
+        assert_eq!(basic.bearer_token(), None);
+
+        let missing = json!({ "type": "ssh_key", "data": { "passphrase": "p" } });
+        assert!(serde_json::from_value::<Credential>(missing).is_err());
+        assert!(serde_json::from_value::<Credential>(json!({ "type": "kerberos", "data": {} })).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+//! Descriptive metadata about node types.
+//!
+//! Every [`ExecutableNode`](crate::ExecutableNode) can describe itself with
+//! a [`NodeDescriptor`]: a display name and description, a category for
+//! grouping in a palette, the JSON Schema of its `config`, the config fields
+//! that name workflow secrets, and hints about what it expects and
+//! produces.  [`crate::registry::catalog`] collects the descriptors of a
+//! whole registry into one serializable list for UIs and config validation.
+//!
+//! ```ignore
+//! fn descriptor(&self) -> NodeDescriptor {
+//!     NodeDescriptor::new("Jira", "Create, update, and transition Jira issues.")
+//!         .category(NodeCategory::Integration)
+//!         .config::<JiraConfig>()
+//!         .credential("api_token_secret", "Jira API token or personal access token")
+//!         .output("{ operation, key, id, url }")
+//! }
+//! ```
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Palette group of a node type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeCategory {
+    /// Starts a flow (e.g. `error_trigger`).
+    Trigger,
+    /// Routes, pauses, or composes executions.
+    Flow,
+    /// Parses, converts, or reshapes data.
+    Data,
+    /// Talks to an external system.
+    Integration,
+    /// Calls a machine-learning model.
+    Ai,
+    /// Runs commands locally or on remote hosts.
+    System,
+    #[default]
+    Other,
+}
+
+/// A config field that names a workflow secret.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CredentialRequirement {
+    /// Config field holding the secret's name (e.g. `api_key_secret`).
+    pub config_field: String,
+    /// What the secret must contain.
+    pub description: String,
+    /// Whether the node also works without it.
+    #[serde(default)]
+    pub optional: bool,
+}
+
+/// Metadata describing a node type.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeDescriptor {
+    pub display_name: String,
+    pub description: String,
+    pub category: NodeCategory,
+    /// JSON Schema of the node's `config`; `null` when not described.
+    pub config_schema: Value,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub credentials: Vec<CredentialRequirement>,
+    /// What the node expects as input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+    /// The shape of the node's output.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Branch labels the node may route to (see `ExecutionContext::branch`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches: Vec<String>,
+}
+
+impl NodeDescriptor {
+    pub fn new(display_name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self { display_name: display_name.into(), description: description.into(), ..Self::default() }
+    }
+
+    pub fn category(mut self, category: NodeCategory) -> Self {
+        self.category = category;
+        self
+    }
+
+    /// Describe the config with the JSON Schema of `T`, the type the node
+    /// deserializes its `config` into.
+    pub fn config<T: JsonSchema>(mut self) -> Self {
+        self.config_schema = schemars::schema_for!(T).to_value();
+        self
+    }
+
+    /// A required secret, named by `config_field`.
+    pub fn credential(mut self, config_field: &str, description: &str) -> Self {
+        self.credentials.push(CredentialRequirement {
+            config_field: config_field.into(),
+            description: description.into(),
+            optional: false,
+        });
+        self
+    }
+
+    /// A secret the node can do without.
+    pub fn optional_credential(mut self, config_field: &str, description: &str) -> Self {
+        self.credentials.push(CredentialRequirement {
+            config_field: config_field.into(),
+            description: description.into(),
+            optional: true,
+        });
+        self
+    }
+
+    pub fn input(mut self, hint: impl Into<String>) -> Self {
+        self.input = Some(hint.into());
+        self
+    }
+
+    pub fn output(mut self, hint: impl Into<String>) -> Self {
+        self.output = Some(hint.into());
+        self
+    }
+
+    pub fn branches<I, S>(mut self, branches: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.branches = branches.into_iter().map(Into::into).collect();
+        self
+    }
+}
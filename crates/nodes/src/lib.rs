@@ -2,10 +2,31 @@
 //!
 //! Every node — built-in and plugin alike — must implement [`ExecutableNode`].
 //! The engine crate dispatches execution through this trait object.
+//!
+//! Node types register themselves with `#[register_node("type")]`;
+//! [`default_registry`] collects every one linked into the binary, and
+//! [`RegistryBuilder`] adds to or overrides that set.
+
+// Lets `#[register_node]` expansions refer to `::nodes` inside this crate too.
+extern crate self as nodes;
 
 pub mod error;
 pub mod traits;
 pub mod mock;
+pub mod builtin;
+pub mod template;
+pub mod state;
+pub mod binary;
+pub mod credentials;
+pub mod subworkflow;
+pub mod registry;
+pub mod descriptor;
 
 pub use error::NodeError;
 pub use traits::ExecutableNode;
+pub use descriptor::{NodeCategory, NodeDescriptor};
+pub use registry::{NodeRegistry, RegistryBuilder, default_registry};
+pub use nodes_macros::register_node;
+
+#[doc(hidden)]
+pub use inventory;
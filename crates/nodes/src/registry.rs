@@ -0,0 +1,224 @@
+//! Compile-time node registration.
+//!
+//! Node types annotated with [`register_node`](crate::register_node) submit a
+//! [`NodeRegistration`] at link time — built-ins as well as nodes defined in
+//! downstream crates, as long as the crate is linked into the binary.
+//! [`default_registry`] collects them, so an embedder gets every compiled-in
+//! node without wiring each one by hand:
+//!
+//! ```ignore
+//! use nodes::register_node;
+//!
+//! #[register_node("slack")]
+//! #[derive(Debug, Default)]
+//! pub struct SlackNode;
+//!
+//! let registry = nodes::default_registry();
+//! assert!(registry.contains_key("slack"));
+//! ```
+//!
+//! [`RegistryBuilder`] starts from the same set (or from nothing) and adds,
+//! replaces, or drops entries — e.g. to enable the `shell` node with the
+//! operator's settings:
+//!
+//! ```ignore
+//! let registry = RegistryBuilder::with_defaults()
+//!     .register("shell", ShellNode::new(settings))
+//!     .remove("ssh_exec")
+//!     .build();
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{ExecutableNode, NodeDescriptor};
+
+/// Maps `node_type` strings to boxed `ExecutableNode` implementations.
+pub type NodeRegistry = HashMap<String, Arc<dyn ExecutableNode>>;
+
+/// One node type submitted by `#[register_node]`.
+#[derive(Debug, Clone, Copy)]
+pub struct NodeRegistration {
+    pub node_type: &'static str,
+    factory: fn() -> Arc<dyn ExecutableNode>,
+}
+
+impl NodeRegistration {
+    pub const fn new(node_type: &'static str, factory: fn() -> Arc<dyn ExecutableNode>) -> Self {
+        Self { node_type, factory }
+    }
+
+    /// Build a fresh instance of the node.
+    pub fn build(&self) -> Arc<dyn ExecutableNode> {
+        (self.factory)()
+    }
+}
+
+inventory::collect!(NodeRegistration);
+
+/// Every registration linked into the binary, sorted by node type.
+pub fn registrations() -> Vec<&'static NodeRegistration> {
+    let mut all: Vec<_> = inventory::iter::<NodeRegistration>.into_iter().collect();
+    all.sort_by_key(|r| r.node_type);
+    all
+}
+
+/// A registry holding one instance of every registered node type.
+///
+/// Node types must be unique; when two registrations claim the same type a
+/// warning is logged and the first one (in sort order) is kept.
+pub fn default_registry() -> NodeRegistry {
+    RegistryBuilder::with_defaults().build()
+}
+
+/// Assembles a [`NodeRegistry`] entry by entry.
+#[derive(Default)]
+pub struct RegistryBuilder {
+    nodes: NodeRegistry,
+}
+
+impl RegistryBuilder {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every registered node type, as in [`default_registry`].
+    pub fn with_defaults() -> Self {
+        let mut nodes = NodeRegistry::new();
+        for registration in registrations() {
+            if nodes.contains_key(registration.node_type) {
+                warn!("node type '{}' is registered more than once; keeping the first", registration.node_type);
+                continue;
+            }
+            nodes.insert(registration.node_type.to_string(), registration.build());
+        }
+        Self { nodes }
+    }
+
+    /// Add `node` as `node_type`, replacing any existing entry.
+    pub fn register(self, node_type: impl Into<String>, node: impl ExecutableNode + 'static) -> Self {
+        self.register_arc(node_type, Arc::new(node))
+    }
+
+    /// Like [`RegistryBuilder::register`], for a node that is already shared.
+    pub fn register_arc(mut self, node_type: impl Into<String>, node: Arc<dyn ExecutableNode>) -> Self {
+        self.nodes.insert(node_type.into(), node);
+        self
+    }
+
+    /// Drop `node_type`, if present.
+    pub fn remove(mut self, node_type: &str) -> Self {
+        self.nodes.remove(node_type);
+        self
+    }
+
+    pub fn contains(&self, node_type: &str) -> bool {
+        self.nodes.contains_key(node_type)
+    }
+
+    pub fn build(self) -> NodeRegistry {
+        self.nodes
+    }
+}
+
+impl std::fmt::Debug for RegistryBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut types: Vec<_> = self.nodes.keys().collect();
+        types.sort();
+        f.debug_struct("RegistryBuilder").field("node_types", &types).finish()
+    }
+}
+
+/// One node type in the catalog returned by [`catalog`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    pub node_type: String,
+    #[serde(flatten)]
+    pub descriptor: NodeDescriptor,
+}
+
+/// The descriptors of every node type in `registry`, sorted by type.
+///
+/// Node types that do not describe themselves are listed under their type
+/// name, so the catalog always covers the whole registry.
+pub fn catalog(registry: &NodeRegistry) -> Vec<CatalogEntry> {
+    let mut entries: Vec<_> = registry
+        .iter()
+        .map(|(node_type, node)| {
+            let mut descriptor = node.descriptor();
+            if descriptor.display_name.is_empty() {
+                descriptor.display_name = node_type.clone();
+            }
+            CatalogEntry { node_type: node_type.clone(), descriptor }
+        })
+        .collect();
+    entries.sort_by(|a, b| a.node_type.cmp(&b.node_type));
+    entries
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_nodes_self_register() {
+        let registry = default_registry();
+        #[cfg(feature = "shell")]
+        assert!(registry.contains_key("shell"));
+        #[cfg(feature = "speech")]
+        assert!(registry.contains_key("transcribe") && registry.contains_key("tts"));
+
+        let types: Vec<_> = registrations().iter().map(|r| r.node_type).collect();
+        let mut sorted = types.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(types, sorted, "registrations are sorted and unique");
+        assert_eq!(registry.len(), types.len());
+    }
+
+    #[test]
+    fn builder_adds_overrides_and_removes_entries() {
+        use crate::mock::MockNode;
+        use serde_json::Value;
+
+        let registry = RegistryBuilder::new()
+            .register("a", MockNode::returning("a", Value::Null))
+            .register("b", MockNode::returning("b", Value::Null))
+            .remove("b")
+            .build();
+        assert_eq!(registry.keys().collect::<Vec<_>>(), ["a"]);
+
+        let custom: Arc<dyn ExecutableNode> = Arc::new(MockNode::returning("shell", Value::Null));
+        let registry = RegistryBuilder::with_defaults().register_arc("shell", custom.clone()).build();
+        assert_eq!(registry.len(), default_registry().len() + usize::from(!cfg!(feature = "shell")));
+        assert!(Arc::ptr_eq(&registry["shell"], &custom));
+    }
+
+    #[test]
+    fn catalog_describes_every_node_type() {
+        let registry = default_registry();
+        let catalog = catalog(&registry);
+        assert_eq!(catalog.len(), registry.len());
+        for entry in &catalog {
+            assert!(!entry.descriptor.display_name.is_empty(), "{} has no name", entry.node_type);
+            assert!(entry.descriptor.config_schema.is_object(), "{} has no config schema", entry.node_type);
+        }
+
+        let json = serde_json::to_value(&catalog).unwrap();
+        let back: Vec<CatalogEntry> = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(back, catalog);
+
+        #[cfg(feature = "validate-json")]
+        {
+            let entry = json.as_array().unwrap().iter().find(|e| e["node_type"] == "validate_json").unwrap();
+            assert_eq!(entry["branches"], serde_json::json!(["valid", "invalid"]));
+        }
+    }
+}
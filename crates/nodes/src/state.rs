@@ -0,0 +1,69 @@
+//! Persistent per-workflow state ("static data") shared across executions.
+//!
+//! Nodes such as `batch_collect` need to remember things between runs of
+//! the same workflow.  They do so through the [`WorkflowStateStore`] handed
+//! to them in [`ExecutionContext::state`](crate::traits::ExecutionContext).
+//! The engine provides a Postgres-backed store; [`InMemoryStateStore`] is
+//! available for tests and local runs.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::NodeError;
+
+/// A read-modify-write step applied atomically by [`WorkflowStateStore::update`].
+///
+/// Receives the current value (`None` if the key is unset) and returns the
+/// value to store (`None` deletes the key) plus a result handed back to the
+/// caller.
+pub type StateUpdateFn = Box<dyn FnOnce(Option<Value>) -> (Option<Value>, Value) + Send>;
+
+/// Key/value storage scoped to a workflow.
+#[async_trait]
+pub trait WorkflowStateStore: Send + Sync + std::fmt::Debug {
+    /// Read the current value for `key`.
+    async fn get(&self, workflow_id: Uuid, key: &str) -> Result<Option<Value>, NodeError>;
+
+    /// Atomically apply `update` to `key`; concurrent executions updating the
+    /// same key are serialised.  Returns the result produced by `update`.
+    async fn update(
+        &self,
+        workflow_id: Uuid,
+        key: &str,
+        update: StateUpdateFn,
+    ) -> Result<Value, NodeError>;
+}
+
+/// Process-local state store backed by a mutex-guarded map.
+#[derive(Debug, Default)]
+pub struct InMemoryStateStore {
+    entries: Mutex<HashMap<(Uuid, String), Value>>,
+}
+
+#[async_trait]
+impl WorkflowStateStore for InMemoryStateStore {
+    async fn get(&self, workflow_id: Uuid, key: &str) -> Result<Option<Value>, NodeError> {
+        let entries = self.entries.lock().unwrap();
+        Ok(entries.get(&(workflow_id, key.to_owned())).cloned())
+    }
+
+    async fn update(
+        &self,
+        workflow_id: Uuid,
+        key: &str,
+        update: StateUpdateFn,
+    ) -> Result<Value, NodeError> {
+        let mut entries = self.entries.lock().unwrap();
+        let map_key = (workflow_id, key.to_owned());
+        let (new_value, result) = update(entries.get(&map_key).cloned());
+        match new_value {
+            Some(v) => entries.insert(map_key, v),
+            None => entries.remove(&map_key),
+        };
+        Ok(result)
+    }
+}
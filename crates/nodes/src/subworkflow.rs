@@ -0,0 +1,25 @@
+//! Running other workflows from inside a node.
+//!
+//! Nodes such as `execute_workflow` start sub-workflows through the
+//! [`SubWorkflowRunner`] handed to them in
+//! [`ExecutionContext::subworkflows`](crate::traits::ExecutionContext).
+//! The engine provides the implementation, so child executions inherit the
+//! parent's priority, queue, and labels like any other child run.
+
+use async_trait::async_trait;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::NodeError;
+
+/// Starts executions of other workflows on behalf of a running execution.
+#[async_trait]
+pub trait SubWorkflowRunner: Send + Sync + std::fmt::Debug {
+    /// Run `workflow_id` with `input` to completion and return its final
+    /// output (the output of the last node that ran).
+    async fn run(&self, parent_execution_id: Uuid, workflow_id: Uuid, input: Value) -> Result<Value, NodeError>;
+
+    /// Queue a run of `workflow_id` with `input` without waiting for it.
+    /// Returns the child execution's id.
+    async fn start(&self, parent_execution_id: Uuid, workflow_id: Uuid, input: Value) -> Result<Uuid, NodeError>;
+}
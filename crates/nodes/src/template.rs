@@ -0,0 +1,136 @@
+//! Minimal `{{ path }}` string templating over JSON values.
+//!
+//! Placeholders are dotted paths resolved against a JSON root object, e.g.
+//! `{{ input.customer.email }}` or `{{ input.items.0.sku }}`.  String values
+//! are substituted verbatim; every other JSON value is substituted as its
+//! compact JSON text.  Unknown paths are an error rather than an empty
+//! string, so typos surface immediately.
+
+use serde_json::Value;
+use thiserror::Error;
+
+/// Errors produced while rendering a template.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A `{{` without a matching `}}`.
+    #[error("unterminated placeholder starting at byte {0}")]
+    Unterminated(usize),
+
+    /// The placeholder's path does not exist in the data.
+    #[error("unknown template path '{0}'")]
+    UnknownPath(String),
+}
+
+/// Resolve a dotted path (`a.b.0.c`) inside `root`.
+///
+/// Numeric segments index into arrays; an empty path returns `root`.
+pub fn lookup<'a>(root: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(root, |current, segment| match current {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        })
+}
+
+/// Render every `{{ path }}` placeholder in `template` using `root`.
+pub fn render(template: &str, root: &Value) -> Result<String, TemplateError> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    let mut offset = 0;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after_open = &rest[start + 2..];
+        let end = after_open
+            .find("}}")
+            .ok_or(TemplateError::Unterminated(offset + start))?;
+
+        let path = after_open[..end].trim();
+        let value = lookup(root, path).ok_or_else(|| TemplateError::UnknownPath(path.to_owned()))?;
+        match value {
+            Value::String(s) => out.push_str(s),
+            other => out.push_str(&other.to_string()),
+        }
+
+        let consumed = start + 2 + end + 2;
+        offset += consumed;
+        rest = &rest[consumed..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// The paths referenced by `template`'s placeholders, in order.
+///
+/// An unterminated placeholder ends the scan.
+pub fn placeholder_paths(template: &str) -> Vec<&str> {
+    let mut paths = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let after_open = &rest[start + 2..];
+        let Some(end) = after_open.find("}}") else { break };
+        paths.push(after_open[..end].trim());
+        rest = &after_open[end + 2..];
+    }
+    paths
+}
+
+/// Whether `template` contains at least one placeholder.
+pub fn has_placeholders(template: &str) -> bool {
+    template.contains("{{")
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn renders_nested_paths_and_array_indices() {
+        let data = json!({ "input": { "user": { "name": "Ada" }, "ids": [7, 8] } });
+        assert_eq!(
+            render("hi {{ input.user.name }} #{{input.ids.1}}", &data).unwrap(),
+            "hi Ada #8"
+        );
+    }
+
+    #[test]
+    fn non_string_values_render_as_json() {
+        let data = json!({ "input": { "tags": ["a", "b"], "n": 3 } });
+        assert_eq!(render("{{ input.tags }}/{{ input.n }}", &data).unwrap(), r#"["a","b"]/3"#);
+    }
+
+    #[test]
+    fn unknown_path_is_an_error() {
+        let data = json!({ "input": {} });
+        assert_eq!(
+            render("{{ input.missing }}", &data),
+            Err(TemplateError::UnknownPath("input.missing".into()))
+        );
+    }
+
+    #[test]
+    fn unterminated_placeholder_is_an_error() {
+        assert_eq!(render("ok {{ input", &json!({})), Err(TemplateError::Unterminated(3)));
+    }
+
+    #[test]
+    fn lists_placeholder_paths() {
+        assert_eq!(
+            placeholder_paths("{{ input.a }}-{{input.b.0}} {{ broken"),
+            vec!["input.a", "input.b.0"]
+        );
+    }
+
+    #[test]
+    fn text_without_placeholders_is_unchanged() {
+        assert_eq!(render("plain text", &json!({})).unwrap(), "plain text");
+        assert!(!has_placeholders("plain text"));
+    }
+}
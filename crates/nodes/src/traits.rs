@@ -1,9 +1,107 @@
 //! The `ExecutableNode` trait — the contract every node must fulfil.
 
+use std::sync::{Arc, Mutex};
+
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::NodeError;
+use crate::{NodeDescriptor, NodeError};
+use crate::binary::{BinaryRef, BinaryStore};
+use crate::credentials::Credential;
+use crate::state::WorkflowStateStore;
+use crate::subworkflow::SubWorkflowRunner;
+
+/// Flow-control decision a node can make in addition to returning output.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Flow {
+    /// Pass the output on to every downstream node (the default).
+    #[default]
+    Continue,
+    /// Succeed, but do not run any downstream node fed only by this one.
+    Halt,
+    /// Succeed, and only follow outgoing edges that are unlabelled or
+    /// labelled with this branch.
+    Branch(String),
+    /// Pause the execution and run this node again at the given time.
+    /// The node's output is discarded.
+    Defer(DateTime<Utc>),
+}
+
+/// Severity of a line a node logs, least severe first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 4] = [Self::Debug, Self::Info, Self::Warn, Self::Error];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        }
+    }
+}
+
+impl std::str::FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|level| level.as_str() == s)
+            .ok_or_else(|| format!("unknown log level '{s}' (expected debug, info, warn, or error)"))
+    }
+}
+
+/// A line a node logged during its call (see [`ExecutionContext::log`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub level: LogLevel,
+    pub message: String,
+    /// Structured context for the line, usually an object (`{}` if none).
+    pub fields: Value,
+    pub logged_at: DateTime<Utc>,
+}
+
+/// Source of the current time for nodes.
+///
+/// Nodes read time through [`ExecutionContext::now`] instead of
+/// `Utc::now()` so that simulations can drive them with a virtual clock.
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The real wall clock.
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Source of non-cryptographic randomness (sampling, traffic splitting)
+/// that simulations can seed.  Security-sensitive randomness such as
+/// tokens always comes from the OS.
+pub trait RandomSource: Send + Sync + std::fmt::Debug {
+    fn next_u64(&self) -> u64;
+
+    /// Uniform float in `[0, 1)`.
+    fn next_f64(&self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
 
 /// Shared context passed to every node during execution.
 ///
@@ -15,10 +113,180 @@ pub struct ExecutionContext {
     pub workflow_id: uuid::Uuid,
     /// ID of the current execution run.
     pub execution_id: uuid::Uuid,
-    /// Initial input supplied when the execution was triggered.
-    pub input: Value,
+    /// Initial input supplied when the execution was triggered; shared, so
+    /// deriving per-node contexts does not copy it.
+    pub input: Arc<Value>,
     /// Decrypted secrets scoped to this workflow.
     pub secrets: std::collections::HashMap<String, String>,
+    /// Decrypted shared credentials the workflow's nodes name, by id.
+    pub credentials: std::collections::HashMap<uuid::Uuid, Credential>,
+    /// ID of the node currently being executed (empty outside a node call).
+    pub node_id: String,
+    /// The `config` object of the node currently being executed.
+    pub config: Value,
+    /// Persistent per-workflow key/value state, if the runtime provides one.
+    pub state: Option<Arc<dyn WorkflowStateStore>>,
+    /// Storage for files and other binary data, if the runtime provides it.
+    pub binary: Option<Arc<dyn BinaryStore>>,
+    /// Runs other workflows as children of this execution, if the runtime
+    /// provides it.
+    pub subworkflows: Option<Arc<dyn SubWorkflowRunner>>,
+    /// Time source; the system clock unless a simulation replaces it.
+    pub clock: Arc<dyn Clock>,
+    /// Seeded randomness for simulations; nodes fall back to their own RNG.
+    pub random: Option<Arc<dyn RandomSource>>,
+    /// Flow-control decision for the current node call (see [`Flow`]).
+    flow: Arc<Mutex<Flow>>,
+    /// Lines logged during the current node call.
+    logs: Arc<Mutex<Vec<LogEntry>>>,
+}
+
+impl ExecutionContext {
+    /// Create an execution-wide context with no secrets and no current node.
+    pub fn new(workflow_id: uuid::Uuid, execution_id: uuid::Uuid, input: impl Into<Arc<Value>>) -> Self {
+        Self {
+            workflow_id,
+            execution_id,
+            input: input.into(),
+            secrets: std::collections::HashMap::new(),
+            credentials: std::collections::HashMap::new(),
+            node_id: String::new(),
+            config: Value::Null,
+            state: None,
+            binary: None,
+            subworkflows: None,
+            clock: Arc::new(SystemClock),
+            random: None,
+            flow: Arc::default(),
+            logs: Arc::default(),
+        }
+    }
+
+    /// Attach a workflow state store.
+    pub fn with_state(mut self, store: Arc<dyn WorkflowStateStore>) -> Self {
+        self.state = Some(store);
+        self
+    }
+
+    /// Attach a binary data store.
+    pub fn with_binary(mut self, store: Arc<dyn BinaryStore>) -> Self {
+        self.binary = Some(store);
+        self
+    }
+
+    /// Attach a sub-workflow runner.
+    pub fn with_subworkflows(mut self, runner: Arc<dyn SubWorkflowRunner>) -> Self {
+        self.subworkflows = Some(runner);
+        self
+    }
+
+    /// Replace the time source.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Use a seeded random source.
+    pub fn with_random(mut self, random: Arc<dyn RandomSource>) -> Self {
+        self.random = Some(random);
+        self
+    }
+
+    /// The current time according to the context's clock.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// Derive the context for a single node call, carrying that node's config.
+    pub fn for_node(&self, node_id: impl Into<String>, config: Value) -> Self {
+        Self {
+            node_id: node_id.into(),
+            config,
+            flow: Arc::default(),
+            logs: Arc::default(),
+            ..self.clone()
+        }
+    }
+
+    /// Stop the flow after this node: its output is recorded, but downstream
+    /// nodes that depend only on it are skipped.
+    pub fn halt(&self) {
+        *self.flow.lock().unwrap() = Flow::Halt;
+    }
+
+    /// Route the flow down `branch`: of this node's outgoing edges, only the
+    /// unlabelled ones and those labelled `branch` are followed.
+    pub fn branch(&self, branch: impl Into<String>) {
+        *self.flow.lock().unwrap() = Flow::Branch(branch.into());
+    }
+
+    /// Suspend the execution and re-run this node at `until`.  The engine
+    /// checkpoints the execution and schedules a delayed job to resume it.
+    pub fn defer_until(&self, until: DateTime<Utc>) {
+        *self.flow.lock().unwrap() = Flow::Defer(until);
+    }
+
+    /// Take the flow-control decision recorded during the current node call,
+    /// resetting it to [`Flow::Continue`].  Used by the engine.
+    pub fn take_flow(&self) -> Flow {
+        std::mem::take(&mut *self.flow.lock().unwrap())
+    }
+
+    /// Log `message` for the execution's logs (`GET
+    /// /executions/:id/logs`), attributed to the current node.  `fields`
+    /// carries structured context for the line, e.g. `json!({ "status": 502
+    /// })`; pass `json!({})` for none.
+    pub fn log(&self, level: LogLevel, message: impl Into<String>, fields: Value) {
+        let entry = LogEntry { level, message: message.into(), fields, logged_at: self.now() };
+        self.logs.lock().unwrap().push(entry);
+    }
+
+    /// Take the lines logged during the current node call, in order.  Used
+    /// by the engine.
+    pub fn take_logs(&self) -> Vec<LogEntry> {
+        std::mem::take(&mut *self.logs.lock().unwrap())
+    }
+
+    /// The workflow state store, or a fatal error if the runtime has none.
+    pub fn require_state(&self) -> Result<&Arc<dyn WorkflowStateStore>, NodeError> {
+        self.state.as_ref().ok_or_else(|| {
+            NodeError::Fatal(format!(
+                "node '{}' needs workflow state, but no state store is configured",
+                self.node_id
+            ))
+        })
+    }
+
+    /// The binary data store, or a fatal error if the runtime has none.
+    pub fn require_binary(&self) -> Result<&Arc<dyn BinaryStore>, NodeError> {
+        self.binary.as_ref().ok_or_else(|| {
+            NodeError::Fatal(format!(
+                "node '{}' handles binary data, but no binary data store is configured",
+                self.node_id
+            ))
+        })
+    }
+
+    /// Store `data` for this execution; returns its reference for the
+    /// node's output.
+    pub async fn store_binary(
+        &self,
+        data: Vec<u8>,
+        mime_type: &str,
+        file_name: Option<&str>,
+    ) -> Result<BinaryRef, NodeError> {
+        self.require_binary()?.put(self.execution_id, data, mime_type, file_name).await
+    }
+
+    /// The sub-workflow runner, or a fatal error if the runtime has none.
+    pub fn require_subworkflows(&self) -> Result<&Arc<dyn SubWorkflowRunner>, NodeError> {
+        self.subworkflows.as_ref().ok_or_else(|| {
+            NodeError::Fatal(format!(
+                "node '{}' runs sub-workflows, but no sub-workflow runner is configured",
+                self.node_id
+            ))
+        })
+    }
 }
 
 /// The core node trait.
@@ -28,9 +296,26 @@ pub struct ExecutionContext {
 pub trait ExecutableNode: Send + Sync {
     /// Execute the node, receive the *previous* node's JSON output as `input`,
     /// and return this node's JSON output.
+    ///
+    /// The node's own configuration is available as `ctx.config`.
     async fn execute(
         &self,
         input: Value,
         ctx: &ExecutionContext,
     ) -> Result<Value, NodeError>;
+
+    /// Whether the node does CPU-heavy work without yielding (parsing,
+    /// hashing, rendering).  The engine runs such nodes on its bounded
+    /// blocking pool instead of the async runtime, so they cannot starve
+    /// latency-sensitive tasks such as webhook handlers.
+    fn is_blocking(&self) -> bool {
+        false
+    }
+
+    /// Metadata about the node type: display name, config schema, required
+    /// secrets, and so on.  Listed in the catalog built by
+    /// [`registry::catalog`](crate::registry::catalog).
+    fn descriptor(&self) -> NodeDescriptor {
+        NodeDescriptor::default()
+    }
 }
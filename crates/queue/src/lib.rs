@@ -1,4 +1,82 @@
-//! `queue` crate — queue worker runtime (not yet implemented).
+//! `queue` crate — how workers find and claim jobs.
 //!
-//! Phase 1: workers poll the `job_queue` Postgres table.
-//! Phase 2: swap in a Redis-backed queue with configurable concurrency.
+//! Every job is a row of the `job_queue` Postgres table, created by the
+//! repository functions in `db::repository::jobs`: the row holds the
+//! payload, status, attempts, and lease, and stays the system of record.
+//! A [`JobQueue`] backend decides how due jobs reach workers:
+//!
+//! - [`PgJobQueue`] — workers claim rows straight from the table with
+//!   `SELECT … FOR UPDATE SKIP LOCKED`.
+//! - `RedisJobQueue` (feature `redis`) — due jobs are indexed in Redis
+//!   sorted sets, so claiming one is a Redis pop and a primary-key update
+//!   instead of a table scan under row locks.
+//!
+//! Whoever creates or moves a job (a new row, a debounced job pushed back,
+//! a delayed job released early) hands it to [`JobQueue::push`].
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use db::models::JobRow;
+use db::DbError;
+
+pub mod postgres;
+#[cfg(feature = "redis")]
+pub mod redis;
+
+pub use postgres::PgJobQueue;
+#[cfg(feature = "redis")]
+pub use self::redis::RedisJobQueue;
+
+/// A queue backend shared by the producers and workers of a process.
+pub type SharedQueue = Arc<dyn JobQueue>;
+
+/// Errors from a queue backend.
+#[derive(Debug, thiserror::Error)]
+pub enum QueueError {
+    #[error("database error: {0}")]
+    Database(#[from] DbError),
+
+    #[cfg(feature = "redis")]
+    #[error("redis error: {0}")]
+    Redis(#[from] ::redis::RedisError),
+}
+
+/// Dispatch of the jobs stored in `job_queue`.
+#[async_trait]
+pub trait JobQueue: Send + Sync {
+    /// Offer `job` — just created, or pending again with a new `run_at` —
+    /// to workers from its `run_at` on.
+    async fn push(&self, job: &JobRow) -> Result<(), QueueError>;
+
+    /// Claim the next due job on one of `queues` (every queue when empty)
+    /// whose required tags are all among the worker's `tags`, leased until
+    /// `locked_until`; `None` when no such job is due.
+    async fn claim(
+        &self,
+        queues: &[String],
+        tags: &[String],
+        locked_until: DateTime<Utc>,
+    ) -> Result<Option<JobRow>, QueueError>;
+
+    /// Extend the lease on a claimed job; `false` when it is no longer
+    /// processing.
+    async fn heartbeat(&self, job_id: Uuid, locked_until: DateTime<Utc>) -> Result<bool, QueueError>;
+
+    /// Mark a claimed job completed.
+    async fn complete(&self, job: &JobRow) -> Result<(), QueueError>;
+
+    /// Mark a claimed job failed: pending for another attempt from
+    /// `retry_at` on, or dead-lettered once out of attempts.
+    async fn fail(&self, job: &JobRow, retry_at: DateTime<Utc>) -> Result<(), QueueError>;
+
+    /// Put a claimed job back without counting the attempt.
+    async fn release(&self, job_id: Uuid) -> Result<(), QueueError>;
+
+    /// Return the jobs whose lease expired to pending (or dead-letter
+    /// them), and offer them again; returns the reclaimed jobs.
+    async fn reclaim_expired(&self) -> Result<Vec<JobRow>, QueueError>;
+}
@@ -0,0 +1,63 @@
+//! The `job_queue` table as its own queue.
+//!
+//! Rows are claimed with `SELECT … FOR UPDATE SKIP LOCKED`, so nothing
+//! needs pushing: a job is visible to workers as soon as its row is
+//! committed and due.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use db::models::JobRow;
+use db::repository::jobs as job_repo;
+use db::DbPool;
+
+use crate::{JobQueue, QueueError};
+
+/// Workers claim jobs straight from `job_queue`.
+#[derive(Clone)]
+pub struct PgJobQueue {
+    pool: DbPool,
+}
+
+impl PgJobQueue {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JobQueue for PgJobQueue {
+    async fn push(&self, _job: &JobRow) -> Result<(), QueueError> {
+        Ok(())
+    }
+
+    async fn claim(
+        &self,
+        queues: &[String],
+        tags: &[String],
+        locked_until: DateTime<Utc>,
+    ) -> Result<Option<JobRow>, QueueError> {
+        Ok(job_repo::claim_next_job(&self.pool, queues, tags, locked_until).await?)
+    }
+
+    async fn heartbeat(&self, job_id: Uuid, locked_until: DateTime<Utc>) -> Result<bool, QueueError> {
+        Ok(job_repo::heartbeat_job(&self.pool, job_id, locked_until).await?)
+    }
+
+    async fn complete(&self, job: &JobRow) -> Result<(), QueueError> {
+        Ok(job_repo::complete_job(&self.pool, job.id).await?)
+    }
+
+    async fn fail(&self, job: &JobRow, retry_at: DateTime<Utc>) -> Result<(), QueueError> {
+        Ok(job_repo::fail_job(&self.pool, job.id, job.max_attempts, retry_at).await?)
+    }
+
+    async fn release(&self, job_id: Uuid) -> Result<(), QueueError> {
+        Ok(job_repo::release_job(&self.pool, job_id).await?)
+    }
+
+    async fn reclaim_expired(&self) -> Result<Vec<JobRow>, QueueError> {
+        Ok(job_repo::reclaim_expired_jobs(&self.pool).await?)
+    }
+}
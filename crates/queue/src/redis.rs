@@ -0,0 +1,278 @@
+//! Redis-indexed dispatch.
+//!
+//! Jobs are split into lanes — one per queue and set of required tags,
+//! named `<queue>` or `<queue>[<tag>,…]` — and each lane has two sorted
+//! sets of job ids (as `<priority>:<id>`):
+//!
+//! - `<prefix>:delayed:<lane>`, scored by `run_at`, for jobs not yet due;
+//! - `<prefix>:ready:<lane>`, scored by priority and then `run_at`, for
+//!   due jobs.
+//!
+//! `<prefix>:lanes` lists the lanes.  A claim runs one script that moves
+//! the due jobs of the lanes the worker may serve (requested queue, tags
+//! it has) to their ready sets and pops the best ready job; the worker then takes
+//! the `job_queue` row by primary key (see
+//! [`claim_job`](db::repository::jobs::claim_job)).  An id whose row is no
+//! longer pending — settled, erased, or moved later — is skipped (and
+//! offered again when it is still pending).
+//!
+//! Postgres stays the system of record, so Redis only ever holds a copy of
+//! the pending set: [`RedisJobQueue::resync`] rebuilds it from the table,
+//! after a Redis restart or a worker that died between popping an id and
+//! claiming its row.  [`JobQueue::reclaim_expired`] resyncs on every call.
+//! All keys of one prefix must live on one Redis node.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, Script};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use uuid::Uuid;
+
+use db::models::JobRow;
+use db::repository::jobs as job_repo;
+use db::DbPool;
+
+use crate::{JobQueue, QueueError};
+
+/// Key prefix used unless configured otherwise.
+pub const DEFAULT_PREFIX: &str = "rusty:jobs";
+
+/// Move a job (KEYS[1] = delayed set, KEYS[2] = ready set of its lane) to
+/// the delayed set at ARGV[2] (`run_at` in ms).
+const PUSH: &str = r"
+redis.call('ZREM', KEYS[2], ARGV[1])
+redis.call('ZADD', KEYS[1], ARGV[2], ARGV[1])
+";
+
+/// KEYS holds delayed/ready pairs; ARGV[1] is now in ms.  Promote the due
+/// jobs of every pair, then pop and return the best ready job: highest
+/// priority, then earliest `run_at`.
+const CLAIM: &str = r"
+local now = tonumber(ARGV[1])
+local best, best_key, best_score
+for i = 1, #KEYS, 2 do
+  local due = redis.call('ZRANGEBYSCORE', KEYS[i], '-inf', now, 'WITHSCORES', 'LIMIT', 0, 100)
+  for j = 1, #due, 2 do
+    local priority = tonumber(string.match(due[j], '^(-?%d+):'))
+    redis.call('ZADD', KEYS[i + 1], -priority * 1e13 + tonumber(due[j + 1]), due[j])
+    redis.call('ZREM', KEYS[i], due[j])
+  end
+  local head = redis.call('ZRANGE', KEYS[i + 1], 0, 0, 'WITHSCORES')
+  if head[1] and (best_score == nil or tonumber(head[2]) < best_score) then
+    best, best_key, best_score = head[1], KEYS[i + 1], tonumber(head[2])
+  end
+end
+if best then
+  redis.call('ZREM', best_key, best)
+  return best
+end
+return false
+";
+
+/// Workers pop due job ids from Redis and claim their rows by id.
+#[derive(Clone)]
+pub struct RedisJobQueue {
+    pool: DbPool,
+    redis: ConnectionManager,
+    prefix: String,
+}
+
+impl RedisJobQueue {
+    /// Connect to the Redis server at `url`.
+    pub async fn connect(pool: DbPool, url: &str) -> Result<Self, QueueError> {
+        let client = redis::Client::open(url)?;
+        let redis = ConnectionManager::new(client).await?;
+        Ok(Self { pool, redis, prefix: DEFAULT_PREFIX.to_owned() })
+    }
+
+    /// Use keys under `prefix` instead of [`DEFAULT_PREFIX`], e.g. to share
+    /// a Redis server between deployments.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Offer every pending job of `job_queue` again; returns how many.
+    pub async fn resync(&self) -> Result<usize, QueueError> {
+        let jobs = job_repo::list_pending_jobs(&self.pool).await?;
+        for job in &jobs {
+            self.push(job).await?;
+        }
+        Ok(jobs.len())
+    }
+
+    fn lanes_key(&self) -> String {
+        format!("{}:lanes", self.prefix)
+    }
+
+    fn delayed_key(&self, lane: &Lane) -> String {
+        format!("{}:delayed:{}", self.prefix, lane.name())
+    }
+
+    fn ready_key(&self, lane: &Lane) -> String {
+        format!("{}:ready:{}", self.prefix, lane.name())
+    }
+
+    /// Offer the current state of job `job_id`, if it is still pending.
+    async fn repush(&self, job_id: Uuid) -> Result<(), QueueError> {
+        match job_repo::get_job(&self.pool, job_id).await? {
+            Some(job) if job.status == "pending" => self.push(&job).await,
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The queue and required tags shared by the jobs of one lane.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Lane {
+    queue: String,
+    tags: Vec<String>,
+}
+
+impl Lane {
+    fn of(job: &JobRow) -> Self {
+        let mut tags = job.required_tags.clone();
+        tags.sort();
+        tags.dedup();
+        Self { queue: job.queue.clone(), tags }
+    }
+
+    /// The part of the lane's keys after `delayed:`/`ready:`.
+    fn name(&self) -> String {
+        if self.tags.is_empty() {
+            self.queue.clone()
+        } else {
+            format!("{}[{}]", self.queue, self.tags.join(","))
+        }
+    }
+
+    /// Whether a worker taking `queues` (all when empty) with `tags` may
+    /// claim this lane's jobs.
+    fn serves(&self, queues: &[String], tags: &[String]) -> bool {
+        (queues.is_empty() || queues.contains(&self.queue)) && self.tags.iter().all(|t| tags.contains(t))
+    }
+}
+
+/// The set member of a job.
+fn member(job: &JobRow) -> String {
+    format!("{}:{}", job.priority, job.id)
+}
+
+/// The job id of a set member.
+fn member_id(member: &str) -> Option<Uuid> {
+    member.split_once(':')?.1.parse().ok()
+}
+
+#[async_trait]
+impl JobQueue for RedisJobQueue {
+    async fn push(&self, job: &JobRow) -> Result<(), QueueError> {
+        let mut redis = self.redis.clone();
+        let lane = Lane::of(job);
+        let listed = serde_json::to_string(&lane).expect("lanes serialize");
+        let _: () = redis.sadd(self.lanes_key(), listed).await?;
+        let _: () = Script::new(PUSH)
+            .key(self.delayed_key(&lane))
+            .key(self.ready_key(&lane))
+            .arg(member(job))
+            .arg(job.run_at.timestamp_millis())
+            .invoke_async(&mut redis)
+            .await?;
+        Ok(())
+    }
+
+    async fn claim(
+        &self,
+        queues: &[String],
+        tags: &[String],
+        locked_until: DateTime<Utc>,
+    ) -> Result<Option<JobRow>, QueueError> {
+        let mut redis = self.redis.clone();
+        let listed: Vec<String> = redis.smembers(self.lanes_key()).await?;
+        let lanes: Vec<Lane> = listed
+            .iter()
+            .filter_map(|lane| serde_json::from_str::<Lane>(lane).ok())
+            .filter(|lane| lane.serves(queues, tags))
+            .collect();
+        if lanes.is_empty() {
+            return Ok(None);
+        }
+        let script = Script::new(CLAIM);
+        loop {
+            let mut claim = script.prepare_invoke();
+            for lane in &lanes {
+                claim.key(self.delayed_key(lane)).key(self.ready_key(lane));
+            }
+            let popped: Option<String> = claim.arg(Utc::now().timestamp_millis()).invoke_async(&mut redis).await?;
+            let Some(job_id) = popped.as_deref().and_then(member_id) else {
+                return Ok(None);
+            };
+            if let Some(job) = job_repo::claim_job(&self.pool, job_id, locked_until).await? {
+                return Ok(Some(job));
+            }
+            self.repush(job_id).await?;
+        }
+    }
+
+    async fn heartbeat(&self, job_id: Uuid, locked_until: DateTime<Utc>) -> Result<bool, QueueError> {
+        Ok(job_repo::heartbeat_job(&self.pool, job_id, locked_until).await?)
+    }
+
+    async fn complete(&self, job: &JobRow) -> Result<(), QueueError> {
+        Ok(job_repo::complete_job(&self.pool, job.id).await?)
+    }
+
+    async fn fail(&self, job: &JobRow, retry_at: DateTime<Utc>) -> Result<(), QueueError> {
+        job_repo::fail_job(&self.pool, job.id, job.max_attempts, retry_at).await?;
+        self.repush(job.id).await
+    }
+
+    async fn release(&self, job_id: Uuid) -> Result<(), QueueError> {
+        job_repo::release_job(&self.pool, job_id).await?;
+        self.repush(job_id).await
+    }
+
+    async fn reclaim_expired(&self) -> Result<Vec<JobRow>, QueueError> {
+        let reclaimed = job_repo::reclaim_expired_jobs(&self.pool).await?;
+        let offered = self.resync().await?;
+        debug!("redis queue: {} pending jobs offered", offered);
+        Ok(reclaimed)
+    }
+}
+
+// ============================================================
+// Unit tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn members_carry_the_priority_and_id() {
+        let id = Uuid::new_v4();
+        assert_eq!(member_id(&format!("-5:{id}")), Some(id));
+        assert_eq!(member_id(&format!("12:{id}")), Some(id));
+        assert_eq!(member_id("12"), None);
+        assert_eq!(member_id("12:nope"), None);
+    }
+
+    #[test]
+    fn lanes_split_jobs_by_queue_and_tags() {
+        let lane = |queue: &str, tags: &[&str]| Lane {
+            queue: queue.into(),
+            tags: tags.iter().map(|t| (*t).to_owned()).collect(),
+        };
+        let strings = |items: &[&str]| items.iter().map(|t| (*t).to_owned()).collect::<Vec<_>>();
+
+        assert_eq!(lane("default", &[]).name(), "default");
+        assert_eq!(lane("llm", &["gpu", "vpn"]).name(), "llm[gpu,vpn]");
+
+        let gpu = lane("llm", &["gpu"]);
+        assert!(gpu.serves(&[], &strings(&["gpu", "vpn"])));
+        assert!(gpu.serves(&strings(&["llm"]), &strings(&["gpu"])));
+        assert!(!gpu.serves(&[], &[]));
+        assert!(!gpu.serves(&strings(&["default"]), &strings(&["gpu"])));
+        assert!(lane("default", &[]).serves(&[], &strings(&["gpu"])));
+    }
+}
@@ -0,0 +1,10 @@
+-- Migration: 002 — Persistent per-workflow key/value state
+-- Used by nodes that remember data across executions (e.g. batch_collect).
+
+CREATE TABLE IF NOT EXISTS workflow_state (
+    workflow_id UUID        NOT NULL REFERENCES workflows(id) ON DELETE CASCADE,
+    key         TEXT        NOT NULL,
+    value       JSONB,
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (workflow_id, key)
+);
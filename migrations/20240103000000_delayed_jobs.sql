@@ -0,0 +1,16 @@
+-- Migration: 003 — Delayed and debounced jobs
+-- `run_at` hides a job from workers until the given time; `debounce_key`
+-- marks the single pending job a burst of trigger events collapses into.
+
+ALTER TABLE job_queue ADD COLUMN IF NOT EXISTS run_at       TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE job_queue ADD COLUMN IF NOT EXISTS debounce_key TEXT;
+
+DROP INDEX IF EXISTS idx_job_queue_status;
+CREATE INDEX IF NOT EXISTS idx_job_queue_status_run_at ON job_queue (status, run_at ASC);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_job_queue_pending_debounce
+    ON job_queue (workflow_id, debounce_key)
+    WHERE status = 'pending' AND debounce_key IS NOT NULL;
+
+-- Trigger throttling counts a workflow's recent executions.
+CREATE INDEX IF NOT EXISTS idx_wexec_workflow_started ON workflow_executions (workflow_id, started_at DESC);
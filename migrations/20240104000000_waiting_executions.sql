@@ -0,0 +1,7 @@
+-- Migration: 004 — `waiting` execution status
+-- An execution is `waiting` while a node has deferred it (e.g. `time_gate`)
+-- and a delayed job is scheduled to resume it.
+
+ALTER TABLE workflow_executions DROP CONSTRAINT IF EXISTS workflow_executions_status_check;
+ALTER TABLE workflow_executions ADD CONSTRAINT workflow_executions_status_check
+    CHECK (status IN ('pending', 'running', 'waiting', 'succeeded', 'failed'));
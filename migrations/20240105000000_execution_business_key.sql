@@ -0,0 +1,9 @@
+-- Migration: 005 — Business key on executions
+-- An identifier of the business entity a run dealt with (order, customer…),
+-- rendered from the workflow's `business_key` template when the run starts.
+
+ALTER TABLE workflow_executions ADD COLUMN IF NOT EXISTS business_key TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_wexec_business_key
+    ON workflow_executions (business_key, started_at DESC)
+    WHERE business_key IS NOT NULL;
@@ -0,0 +1,11 @@
+-- Migration: 006 — Execution labels and retention lookups
+-- Labels are rendered from the workflow's `labels` templates when a run
+-- starts; retention policies can exempt labelled executions from pruning.
+
+ALTER TABLE workflow_executions ADD COLUMN IF NOT EXISTS labels TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS idx_wexec_labels ON workflow_executions USING GIN (labels);
+
+-- Pruning scans finished executions per workflow and status by age.
+CREATE INDEX IF NOT EXISTS idx_wexec_retention
+    ON workflow_executions (workflow_id, status, finished_at);
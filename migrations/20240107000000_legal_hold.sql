@@ -0,0 +1,25 @@
+-- Migration: 007 — Legal hold
+-- Held workflows and executions are skipped by retention pruning and
+-- refuse deletion until the hold is lifted.  Every hold change is recorded
+-- in `legal_hold_events`, which deliberately has no foreign keys so the
+-- audit trail outlives the records it describes.
+
+ALTER TABLE workflows           ADD COLUMN IF NOT EXISTS legal_hold BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE workflow_executions ADD COLUMN IF NOT EXISTS legal_hold BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX IF NOT EXISTS idx_wexec_legal_hold
+    ON workflow_executions (workflow_id)
+    WHERE legal_hold;
+
+CREATE TABLE IF NOT EXISTS legal_hold_events (
+    id          UUID        PRIMARY KEY DEFAULT gen_random_uuid(),
+    target_type TEXT        NOT NULL CHECK (target_type IN ('workflow', 'execution')),
+    target_id   UUID        NOT NULL,
+    held        BOOLEAN     NOT NULL,
+    reason      TEXT,
+    actor       TEXT        NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_legal_hold_events_target
+    ON legal_hold_events (target_type, target_id, created_at DESC);
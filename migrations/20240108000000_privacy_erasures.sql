@@ -0,0 +1,13 @@
+-- Migration: 008 — Privacy erasure log
+-- One row per executed erasure request.  Only the kind of criteria and the
+-- report (record IDs and counts) are stored, never the erased values.
+
+CREATE TABLE IF NOT EXISTS privacy_erasures (
+    id            UUID        PRIMARY KEY DEFAULT gen_random_uuid(),
+    actor         TEXT        NOT NULL,
+    criteria_type TEXT        NOT NULL CHECK (criteria_type IN ('business_key', 'value')),
+    report        JSONB       NOT NULL,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_privacy_erasures_created_at ON privacy_erasures (created_at DESC);
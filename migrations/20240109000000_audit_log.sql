@@ -0,0 +1,16 @@
+-- Migration: 009 — Audit log
+-- Append-only record of privileged actions (support access, admin changes).
+-- No foreign keys: entries must outlive the records they mention.
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id          UUID        PRIMARY KEY DEFAULT gen_random_uuid(),
+    actor       TEXT        NOT NULL,
+    action      TEXT        NOT NULL,
+    target_type TEXT        NOT NULL,
+    target_id   UUID,
+    details     JSONB       NOT NULL DEFAULT '{}',
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log (created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_target     ON audit_log (target_type, target_id);
@@ -0,0 +1,13 @@
+-- Migration: 010 — Feature flags
+-- Overrides of the feature flag defaults configured at startup.  A flag can
+-- be set globally (`scope_id` empty), per organization, or per workflow;
+-- the most specific matching row wins.
+
+CREATE TABLE IF NOT EXISTS feature_flags (
+    flag       TEXT        NOT NULL,
+    scope      TEXT        NOT NULL CHECK (scope IN ('global', 'organization', 'workflow')),
+    scope_id   TEXT        NOT NULL DEFAULT '',
+    enabled    BOOLEAN     NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (flag, scope, scope_id)
+);
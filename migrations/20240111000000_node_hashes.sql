@@ -0,0 +1,8 @@
+-- Migration: 011 — Node input/output hashes
+-- SHA-256 digests of each node run's canonical input and output, recorded
+-- for workflows with the `determinism_report` flag so repeated runs can be
+-- compared for divergence without diffing full payloads.
+
+ALTER TABLE node_executions
+    ADD COLUMN IF NOT EXISTS input_hash  TEXT,
+    ADD COLUMN IF NOT EXISTS output_hash TEXT;
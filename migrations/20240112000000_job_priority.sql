@@ -0,0 +1,20 @@
+-- Migration: 012 — Job priority, queues, and parent executions
+-- Executions carry a priority and a queue that every job queued for them
+-- copies; workers take the highest-priority due job first.  Executions
+-- started by another execution (sub-workflows, chained workflows) record
+-- their parent, from which they may inherit priority, queue, and labels.
+
+ALTER TABLE workflow_executions
+    ADD COLUMN IF NOT EXISTS priority            INT  NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS queue               TEXT NOT NULL DEFAULT 'default',
+    ADD COLUMN IF NOT EXISTS parent_execution_id UUID REFERENCES workflow_executions(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_wexec_parent ON workflow_executions (parent_execution_id)
+    WHERE parent_execution_id IS NOT NULL;
+
+ALTER TABLE job_queue
+    ADD COLUMN IF NOT EXISTS priority INT  NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS queue    TEXT NOT NULL DEFAULT 'default';
+
+DROP INDEX IF EXISTS idx_job_queue_status_run_at;
+CREATE INDEX IF NOT EXISTS idx_job_queue_dispatch ON job_queue (status, queue, priority DESC, run_at ASC);
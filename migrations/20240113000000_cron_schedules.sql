@@ -0,0 +1,14 @@
+-- Migration: 013 — Cron schedules
+-- One row per workflow with a cron trigger.  The scheduler keeps the next
+-- fire time here, so a restart neither skips nor repeats a run; a changed
+-- expression resets it.
+
+CREATE TABLE IF NOT EXISTS cron_schedules (
+    workflow_id UUID        PRIMARY KEY REFERENCES workflows(id) ON DELETE CASCADE,
+    expression  TEXT        NOT NULL,
+    next_run_at TIMESTAMPTZ NOT NULL,
+    last_run_at TIMESTAMPTZ,
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_cron_schedules_next_run_at ON cron_schedules (next_run_at);
@@ -0,0 +1,5 @@
+-- Migration: 014 — Cron schedule timezones
+-- Cron triggers may name the IANA timezone their expression is evaluated
+-- in; a changed timezone resets the schedule like a changed expression.
+
+ALTER TABLE cron_schedules ADD COLUMN IF NOT EXISTS timezone TEXT NOT NULL DEFAULT 'UTC';
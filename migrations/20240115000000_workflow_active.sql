@@ -0,0 +1,6 @@
+-- Migration: 015 — Workflow active flag
+-- Inactive workflows are not started by their triggers (webhooks, cron,
+-- polling, queues, notifications) but can still be executed manually.
+-- Existing workflows stay active.
+
+ALTER TABLE workflows ADD COLUMN IF NOT EXISTS active BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,12 @@
+-- Migration: 016 — Webhook captures
+-- A workflow author can arm a workflow's webhook to capture the next
+-- request instead of executing it.  The captured input is kept as the
+-- workflow's pinned sample until it is replaced or cleared.
+
+CREATE TABLE IF NOT EXISTS webhook_captures (
+    workflow_id UUID        PRIMARY KEY REFERENCES workflows(id) ON DELETE CASCADE,
+    -- Set while armed: the next request before this time is captured.
+    armed_until TIMESTAMPTZ,
+    payload     JSONB,
+    captured_at TIMESTAMPTZ
+);
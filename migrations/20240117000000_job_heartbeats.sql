@@ -0,0 +1,11 @@
+-- Migration: 017 — Job heartbeats
+-- A worker holds a lease on each job it runs and keeps extending it while
+-- the execution is in progress.  A `processing` job whose lease expired
+-- belongs to a worker that died; it is returned to `pending` (or
+-- dead-lettered once out of attempts).
+
+ALTER TABLE job_queue ADD COLUMN IF NOT EXISTS locked_until TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_job_queue_locked_until
+    ON job_queue (locked_until)
+    WHERE status = 'processing';
@@ -0,0 +1,11 @@
+-- Migration: 018 — Worker capability tags
+-- Executions carry the capability tags a worker needs to run them (e.g.
+-- `gpu`, `vpn`), gathered from the workflow and its nodes; every job
+-- queued for them copies the tags, and a worker only claims jobs whose
+-- tags it all has.
+
+ALTER TABLE workflow_executions
+    ADD COLUMN IF NOT EXISTS required_tags TEXT[] NOT NULL DEFAULT '{}';
+
+ALTER TABLE job_queue
+    ADD COLUMN IF NOT EXISTS required_tags TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,7 @@
+-- Migration: 019 — Per-execution attempt limits
+-- Executions carry the number of attempts their jobs get before they are
+-- dead-lettered (set by the workflow or the execute call); every job
+-- queued for them copies it.
+
+ALTER TABLE workflow_executions
+    ADD COLUMN IF NOT EXISTS max_attempts INT NOT NULL DEFAULT 3;
@@ -0,0 +1,6 @@
+-- Migration: 020 — Node error messages
+-- A failed node records why it failed, so a run can be debugged from the
+-- API without digging through worker logs.
+
+ALTER TABLE node_executions
+    ADD COLUMN IF NOT EXISTS error TEXT;
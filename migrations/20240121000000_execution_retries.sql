@@ -0,0 +1,15 @@
+-- Migration: 021 — Execution retries
+-- A failed execution can be retried through the API; the retry is a new
+-- execution that records which execution it retried.  Retrying from the
+-- failed node rebuilds the flow state from the node records, so they also
+-- keep the branch a node picked and whether it halted the flow.
+
+ALTER TABLE workflow_executions
+    ADD COLUMN IF NOT EXISTS retry_of UUID REFERENCES workflow_executions(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_wexec_retry_of ON workflow_executions (retry_of)
+    WHERE retry_of IS NOT NULL;
+
+ALTER TABLE node_executions
+    ADD COLUMN IF NOT EXISTS branch TEXT,
+    ADD COLUMN IF NOT EXISTS halted BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,8 @@
+-- Migration: 022 — Workflow tags
+-- Free-form tags (owner, project, …) for organising workflows; listings
+-- filter on them.
+
+ALTER TABLE workflows
+    ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS idx_workflows_tags ON workflows USING GIN (tags);
@@ -0,0 +1,24 @@
+-- Migration: 023 — Workflow versions
+-- Every stored definition of a workflow is kept as a numbered version with
+-- its author, so changes can be reviewed and rolled back; executions
+-- record the version they ran against.
+
+ALTER TABLE workflows
+    ADD COLUMN IF NOT EXISTS version INT NOT NULL DEFAULT 1;
+
+CREATE TABLE IF NOT EXISTS workflow_versions (
+    workflow_id UUID        NOT NULL REFERENCES workflows(id) ON DELETE CASCADE,
+    version     INT         NOT NULL,
+    name        TEXT        NOT NULL,
+    definition  JSONB       NOT NULL,
+    author      TEXT,
+    created_at  TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (workflow_id, version)
+);
+
+INSERT INTO workflow_versions (workflow_id, version, name, definition, author, created_at)
+SELECT id, version, name, definition, NULL, created_at FROM workflows
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE workflow_executions
+    ADD COLUMN IF NOT EXISTS workflow_version INT;
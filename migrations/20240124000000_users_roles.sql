@@ -0,0 +1,25 @@
+-- Migration: 024 — Users and roles
+-- Who may do what through the API.  Users are keyed by the subject they
+-- authenticate as (an API key's name or a token's user claim) and hold
+-- one role: viewers read, editors also change and run workflows, admins
+-- also manage users and the admin endpoints.
+
+CREATE TABLE IF NOT EXISTS roles (
+    name        TEXT PRIMARY KEY,
+    description TEXT NOT NULL
+);
+
+INSERT INTO roles (name, description) VALUES
+    ('viewer', 'List and read workflows and executions'),
+    ('editor', 'Also create, update, and execute workflows'),
+    ('admin',  'Also manage users, secrets, and the admin endpoints')
+ON CONFLICT (name) DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS users (
+    subject    TEXT PRIMARY KEY,
+    role       TEXT NOT NULL REFERENCES roles (name),
+    name       TEXT,
+    email      TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,34 @@
+-- Migration: 025 — Projects
+-- A project (team, organization) owns workflows and, through them, their
+-- executions, jobs, and secrets.  API requests act within one project and
+-- API keys can be confined to one, so a deployment can serve several
+-- teams.  Existing rows go to the `default` project.
+
+CREATE TABLE IF NOT EXISTS projects (
+    id         UUID        PRIMARY KEY DEFAULT gen_random_uuid(),
+    name       TEXT        NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO projects (id, name)
+VALUES ('00000000-0000-0000-0000-000000000000', 'default')
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE workflows
+    ADD COLUMN IF NOT EXISTS project_id UUID NOT NULL
+        DEFAULT '00000000-0000-0000-0000-000000000000' REFERENCES projects (id);
+
+-- Copied from the workflow when rows are inserted.
+ALTER TABLE workflow_executions
+    ADD COLUMN IF NOT EXISTS project_id UUID NOT NULL
+        DEFAULT '00000000-0000-0000-0000-000000000000' REFERENCES projects (id);
+ALTER TABLE job_queue
+    ADD COLUMN IF NOT EXISTS project_id UUID NOT NULL
+        DEFAULT '00000000-0000-0000-0000-000000000000' REFERENCES projects (id);
+ALTER TABLE secrets
+    ADD COLUMN IF NOT EXISTS project_id UUID NOT NULL
+        DEFAULT '00000000-0000-0000-0000-000000000000' REFERENCES projects (id);
+
+CREATE INDEX IF NOT EXISTS idx_workflows_project ON workflows (project_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_workflow_executions_project ON workflow_executions (project_id, started_at DESC);
+CREATE INDEX IF NOT EXISTS idx_job_queue_project ON job_queue (project_id, status);
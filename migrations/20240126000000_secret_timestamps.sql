@@ -0,0 +1,7 @@
+-- Migration: 026 — Secret timestamps
+-- Secrets are managed through the API now; their values are never shown,
+-- so listings say when each was set instead.
+
+ALTER TABLE secrets
+    ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
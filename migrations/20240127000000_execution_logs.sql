@@ -0,0 +1,15 @@
+-- Migration: 027 — Execution logs
+-- Lines nodes log while they run, kept with the execution so runs can be
+-- debugged from the API instead of the worker's stdout.  `id` orders the
+-- lines of an execution as they were written.
+
+CREATE TABLE IF NOT EXISTS execution_logs (
+    id           BIGSERIAL   PRIMARY KEY,
+    execution_id UUID        NOT NULL REFERENCES workflow_executions(id) ON DELETE CASCADE,
+    node_id      TEXT        NOT NULL,
+    level        TEXT        NOT NULL CHECK (level IN ('debug', 'info', 'warn', 'error')),
+    message      TEXT        NOT NULL,
+    logged_at    TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_execution_logs_execution ON execution_logs (execution_id, id);
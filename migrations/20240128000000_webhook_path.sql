@@ -0,0 +1,29 @@
+-- Migration: 028 — Indexed webhook paths
+-- Webhook requests looked their workflow up by reading every definition.
+-- The path of a webhook trigger is now a generated column, so the lookup
+-- is one index probe, and no two active workflows can answer the same
+-- path.  Inactive workflows may share one (a clone of a webhook workflow
+-- starts inactive); they only take captures.
+
+ALTER TABLE workflows
+    ADD COLUMN IF NOT EXISTS webhook_path TEXT GENERATED ALWAYS AS (
+        CASE WHEN definition->'trigger'->>'type' = 'webhook' THEN definition->'trigger'->>'path' END
+    ) STORED;
+
+-- Requests went to the newest of several active workflows with a path;
+-- the older ones never ran from it, so they are deactivated.
+UPDATE workflows w
+SET active = FALSE
+WHERE w.active
+  AND w.webhook_path IS NOT NULL
+  AND EXISTS (
+      SELECT 1 FROM workflows newer
+      WHERE newer.active
+        AND newer.webhook_path = w.webhook_path
+        AND (newer.created_at, newer.id) > (w.created_at, w.id)
+  );
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_workflows_webhook_path
+    ON workflows (webhook_path) WHERE active AND webhook_path IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_workflows_webhook_path_all
+    ON workflows (webhook_path) WHERE webhook_path IS NOT NULL;
@@ -0,0 +1,80 @@
+-- Migration: 029 — Monthly node execution partitions
+-- node_executions gets a row for every node of every run, so it is by far
+-- the largest table.  It is now partitioned by month of `started_at`, so
+-- indexes stay small and a month that retention has emptied is dropped
+-- in one statement instead of being vacuumed.  `node_executions_default`
+-- takes rows outside every monthly partition, so inserts never fail when
+-- maintenance falls behind.
+--
+-- workflow_executions stays one table: jobs, node executions, logs, and
+-- retries reference it by id, and a foreign key into a partitioned table
+-- has to include the partition key.
+--
+-- Existing rows are copied into their months, which takes a while on a
+-- large table.
+
+ALTER TABLE node_executions RENAME TO node_executions_unpartitioned;
+ALTER INDEX idx_nexec_execution_id RENAME TO idx_nexec_unpartitioned_execution_id;
+
+CREATE TABLE node_executions (
+    LIKE node_executions_unpartitioned INCLUDING DEFAULTS INCLUDING CONSTRAINTS,
+    PRIMARY KEY (id, started_at),
+    FOREIGN KEY (execution_id) REFERENCES workflow_executions(id) ON DELETE CASCADE
+) PARTITION BY RANGE (started_at);
+
+CREATE INDEX IF NOT EXISTS idx_nexec_execution_id ON node_executions (execution_id);
+
+CREATE TABLE IF NOT EXISTS node_executions_default PARTITION OF node_executions DEFAULT;
+
+-- Create the partition for the month `month` falls in, unless it exists;
+-- returns whether it was created.
+CREATE OR REPLACE FUNCTION node_execution_partition_create(month DATE) RETURNS BOOLEAN AS $$
+DECLARE
+    first_day DATE := date_trunc('month', month)::date;
+    name TEXT := 'node_executions_p' || to_char(first_day, 'YYYYMM');
+BEGIN
+    IF to_regclass(name) IS NOT NULL THEN
+        RETURN FALSE;
+    END IF;
+    EXECUTE format(
+        'CREATE TABLE %I PARTITION OF node_executions FOR VALUES FROM (%L) TO (%L)',
+        name, first_day::timestamptz, (first_day + INTERVAL '1 month')::timestamptz
+    );
+    RETURN TRUE;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Drop the monthly partitions that end on or before `before` and hold no
+-- rows; returns how many were dropped.
+CREATE OR REPLACE FUNCTION node_execution_partitions_drop_empty(before DATE) RETURNS INTEGER AS $$
+DECLARE
+    partition TEXT;
+    empty BOOLEAN;
+    dropped INTEGER := 0;
+BEGIN
+    FOR partition IN
+        SELECT c.relname
+        FROM pg_inherits i JOIN pg_class c ON c.oid = i.inhrelid
+        WHERE i.inhparent = 'node_executions'::regclass
+          AND c.relname ~ '^node_executions_p[0-9]{6}$'
+          AND to_date(substring(c.relname from '[0-9]{6}$'), 'YYYYMM') + INTERVAL '1 month' <= before
+    LOOP
+        EXECUTE format('SELECT NOT EXISTS (SELECT 1 FROM %I)', partition) INTO empty;
+        IF empty THEN
+            EXECUTE format('DROP TABLE %I', partition);
+            dropped := dropped + 1;
+        END IF;
+    END LOOP;
+    RETURN dropped;
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT node_execution_partition_create(month::date)
+FROM generate_series(
+    date_trunc('month', LEAST((SELECT MIN(started_at) FROM node_executions_unpartitioned), NOW())),
+    date_trunc('month', NOW()) + INTERVAL '1 month',
+    INTERVAL '1 month'
+) AS months (month);
+
+INSERT INTO node_executions SELECT * FROM node_executions_unpartitioned;
+DROP TABLE node_executions_unpartitioned;
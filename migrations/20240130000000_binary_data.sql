@@ -0,0 +1,21 @@
+-- Migration: 030 — Binary data
+-- Files produced and consumed by nodes are kept in a blob store (a
+-- directory or an S3 bucket) and passed between nodes by reference.  This
+-- table records each blob and the execution it belongs to.  Deleting the
+-- execution (retention, erasure, or its workflow going away) only clears
+-- `execution_id`; the pruner then deletes the blob and the row, since the
+-- database cannot reach the blob store itself.
+
+CREATE TABLE IF NOT EXISTS binary_data (
+    id           UUID        PRIMARY KEY,
+    execution_id UUID        REFERENCES workflow_executions(id) ON DELETE SET NULL,
+    workflow_id  UUID        NOT NULL,
+    storage_key  TEXT        NOT NULL,
+    mime_type    TEXT        NOT NULL,
+    file_name    TEXT,
+    size         BIGINT      NOT NULL,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_binary_data_execution_id ON binary_data (execution_id);
+CREATE INDEX IF NOT EXISTS idx_binary_data_orphaned ON binary_data (created_at) WHERE execution_id IS NULL;
@@ -0,0 +1,16 @@
+-- Migration: 031 — Shared credentials
+-- Login details (HTTP basic, OAuth2 tokens, API tokens, SSH keys) kept
+-- once per project and used by any of its workflows, where secrets belong
+-- to a single workflow.  Nodes name a credential by id; its fields are
+-- stored encrypted as one JSON document, and only its type is readable.
+
+CREATE TABLE IF NOT EXISTS credentials (
+    id              UUID        PRIMARY KEY,
+    project_id      UUID        NOT NULL REFERENCES projects (id),
+    name            TEXT        NOT NULL,
+    credential_type TEXT        NOT NULL,
+    encrypted_data  TEXT        NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (project_id, name)
+);
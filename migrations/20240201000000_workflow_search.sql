@@ -0,0 +1,20 @@
+-- Migration: 032 — Workflow search
+-- Full-text search over workflow names and every string in their
+-- definitions (node types, URLs, descriptions).  `.`, `/`, and `:` are
+-- split on first, so `https://billing-api.internal/charge` is found by
+-- `billing-api`; the `simple` configuration keeps identifiers unstemmed.
+-- Names weigh more than definitions when results are ranked.
+
+ALTER TABLE workflows
+    ADD COLUMN IF NOT EXISTS search_vector TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', translate(name, './:', '   ')), 'A')
+        || setweight(
+            to_tsvector(
+                'simple',
+                translate(jsonb_path_query_array(definition, 'strict $.** ? (@.type() == "string")')::text, './:', '   ')
+            ),
+            'B'
+        )
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_workflows_search ON workflows USING GIN (search_vector);
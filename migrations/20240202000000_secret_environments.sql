@@ -0,0 +1,11 @@
+-- Migration: 033 — Secret environments
+-- A secret key may hold a value per environment (dev, staging, prod) next
+-- to its default, so one workflow definition runs unchanged everywhere.
+-- Workers read the value for their environment and fall back to the
+-- default; '' is the default.
+
+ALTER TABLE secrets ADD COLUMN IF NOT EXISTS environment TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE secrets DROP CONSTRAINT IF EXISTS secrets_workflow_id_key_key;
+ALTER TABLE secrets
+    ADD CONSTRAINT secrets_workflow_id_key_environment_key UNIQUE (workflow_id, key, environment);
@@ -0,0 +1,9 @@
+-- Migration: 034 — Project secrets
+-- Secrets without a workflow belong to their project, and every workflow
+-- in it can read them — one Slack token for dozens of workflows.  A
+-- workflow's own secret of the same key wins over the project's.
+
+ALTER TABLE secrets ALTER COLUMN workflow_id DROP NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_secrets_project_key
+    ON secrets (project_id, key, environment) WHERE workflow_id IS NULL;
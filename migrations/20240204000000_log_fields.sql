@@ -0,0 +1,5 @@
+-- Migration: 035 — Structured log fields
+-- Nodes attach structured context to the lines they log (a status code, a
+-- record id, a JSON path) so lines can be read without parsing messages.
+
+ALTER TABLE execution_logs ADD COLUMN IF NOT EXISTS fields JSONB NOT NULL DEFAULT '{}';
@@ -0,0 +1,9 @@
+-- Migration: 036 — Execution timings
+-- Enough timing to draw an execution as a waterfall: `running_at` is when
+-- a worker first picked the execution up, so the time before it was spent
+-- queued, and each node execution keeps the start, end, and error of every
+-- attempt, so time lost to retries shows up next to the node's own.
+
+ALTER TABLE workflow_executions ADD COLUMN IF NOT EXISTS running_at TIMESTAMPTZ;
+
+ALTER TABLE node_executions ADD COLUMN IF NOT EXISTS attempts JSONB NOT NULL DEFAULT '[]';
@@ -0,0 +1,34 @@
+-- Migration: 037 — Node daily stats
+-- A rollup of node runs per workflow, node, and UTC day, kept up to date
+-- as node executions are recorded, so per-node durations and failure
+-- counts over weeks are read from a few rows per node instead of scanning
+-- node_executions.  Rows outlive the node executions they summarise;
+-- retention does not touch them.
+
+CREATE TABLE IF NOT EXISTS node_daily_stats (
+    workflow_id UUID        NOT NULL REFERENCES workflows (id) ON DELETE CASCADE,
+    node_id     TEXT        NOT NULL,
+    day         DATE        NOT NULL,
+    runs        BIGINT      NOT NULL DEFAULT 0,
+    failures    BIGINT      NOT NULL DEFAULT 0,
+    -- Attempts beyond the first, over all runs.
+    retries     BIGINT      NOT NULL DEFAULT 0,
+    total_ms    BIGINT      NOT NULL DEFAULT 0,
+    max_ms      BIGINT      NOT NULL DEFAULT 0,
+    PRIMARY KEY (workflow_id, node_id, day)
+);
+
+INSERT INTO node_daily_stats (workflow_id, node_id, day, runs, failures, retries, total_ms, max_ms)
+SELECT e.workflow_id,
+       n.node_id,
+       (n.started_at AT TIME ZONE 'UTC')::date,
+       COUNT(*),
+       COUNT(*) FILTER (WHERE n.status = 'failed'),
+       SUM(GREATEST(jsonb_array_length(n.attempts) - 1, 0)),
+       SUM(GREATEST(EXTRACT(EPOCH FROM n.finished_at - n.started_at) * 1000, 0))::bigint,
+       MAX(GREATEST(EXTRACT(EPOCH FROM n.finished_at - n.started_at) * 1000, 0))::bigint
+FROM node_executions n
+JOIN workflow_executions e ON e.id = n.execution_id
+WHERE n.finished_at IS NOT NULL
+GROUP BY 1, 2, 3
+ON CONFLICT (workflow_id, node_id, day) DO NOTHING;
@@ -0,0 +1,5 @@
+-- Migration: 038 — Execution params
+-- The workflow parameters an execution was started with, where they
+-- differ from the workflow's defaults; retries and replays reuse them.
+
+ALTER TABLE workflow_executions ADD COLUMN IF NOT EXISTS params JSONB NOT NULL DEFAULT '{}';
@@ -0,0 +1,6 @@
+-- Migration: 039 — Quotas
+-- Limits on how many executions a project's workflows start per hour and
+-- per day and how much node runtime they use per day.  Workflows carry
+-- their own limits in their definitions; NULL means the project has none.
+
+ALTER TABLE projects ADD COLUMN IF NOT EXISTS quota JSONB;
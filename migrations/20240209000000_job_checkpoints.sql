@@ -0,0 +1,6 @@
+-- Migration: 040 — Job checkpoints
+-- Jobs that resume a suspended, retried or replayed execution carry its
+-- saved progress here rather than in the payload, which holds the trigger
+-- input as the caller sent it.  NULL for jobs that start from the first node.
+
+ALTER TABLE job_queue ADD COLUMN IF NOT EXISTS checkpoint JSONB;